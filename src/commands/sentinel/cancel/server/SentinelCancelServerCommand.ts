@@ -26,7 +26,7 @@ export class SentinelCancelServerCommand extends CommandBase<SentinelCancelParam
     try {
       // Direct cancel: single handle provided
       if (cancelParams.handle) {
-        const result = await this.cancelOne(rustClient, cancelParams.handle);
+        const result = await this.cancelOne(rustClient, cancelParams.handle, cancelParams.timeoutMs);
         return transformPayload(params, {
           success: result.cancelled,
           cancelled: [result],
@@ -53,7 +53,7 @@ export class SentinelCancelServerCommand extends CommandBase<SentinelCancelParam
       let totalCancelled = 0;
 
       for (const handle of handles) {
-        const result = await this.cancelOne(rustClient, handle.id, handle);
+        const result = await this.cancelOne(rustClient, handle.id, cancelParams.timeoutMs, handle);
         results.push(result);
         if (result.cancelled) totalCancelled++;
       }
@@ -96,15 +96,17 @@ export class SentinelCancelServerCommand extends CommandBase<SentinelCancelParam
   private async cancelOne(
     rustClient: RustCoreIPCClient,
     handleId: string,
+    timeoutMs?: number,
     handle?: SentinelHandle,
   ): Promise<CancelledSentinel> {
     try {
-      const result = await rustClient.sentinelCancel(handleId);
+      const result = await rustClient.sentinelCancel(handleId, timeoutMs);
       return {
         handle: handleId,
         type: handle?.sentinelType ?? 'unknown',
         previousStatus: handle?.status ?? 'unknown',
         cancelled: result.status === 'cancelled',
+        ...(result.forced !== undefined && { forced: result.forced }),
       };
     } catch (error: unknown) {
       const message = error instanceof Error ? error.message : String(error);
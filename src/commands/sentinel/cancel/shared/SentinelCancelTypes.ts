@@ -24,6 +24,9 @@ export interface SentinelCancelParams extends CommandParams {
 
   /** Filter by status (default: 'running') */
   status?: 'running' | 'completed' | 'failed' | 'cancelled';
+
+  /** Wait this long for each sentinel to stop; past it, the sentinel is aborted (forced) */
+  timeoutMs?: number;
 }
 
 export interface CancelledSentinel {
@@ -31,6 +34,8 @@ export interface CancelledSentinel {
   type: string;
   previousStatus: string;
   cancelled: boolean;
+  /** True when the sentinel ignored cancellation and was aborted at the deadline */
+  forced?: boolean;
   error?: string;
 }
 
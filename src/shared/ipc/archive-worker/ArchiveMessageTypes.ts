@@ -17,6 +17,30 @@ export type ArchiveRequest =
       dest_handle: string;
       max_rows: number;
       batch_size: number;
+      /** gzip each row's JSON payload in the archive */
+      compress?: boolean;
+    }
+  | {
+      command: 'restore';
+      task_id: string;
+      collection: string;
+      archive_handle: string;
+      primary_handle: string;
+      batch_size: number;
+      /** data/list filter selecting rows to restore (e.g. a createdAt range) */
+      filter?: Record<string, unknown>;
+    }
+  | {
+      command: 'read';
+      collection: string;
+      archive_handle: string;
+      filter?: Record<string, unknown>;
+      limit: number;
+    }
+  | {
+      /** Completion stats (or error) of a finished archive/restore task */
+      command: 'result';
+      task_id: string;
     }
   | {
       command: 'ping';
@@ -34,12 +58,20 @@ export type ArchiveResponse =
   | {
       status: 'complete';
       task_id: string;
-      rows_archived: number;
-      duration_ms: number;
+      rows_found: number;
+      original_bytes: number;
+      stored_bytes: number;
+      /** stored_bytes / original_bytes (1.0 when uncompressed) */
+      compression_ratio: number;
+    }
+  | {
+      status: 'rows';
+      /** Archived rows, decompressed */
+      items: Record<string, unknown>[];
     }
   | {
       status: 'error';
-      task_id: string;
+      task_id?: string;
       error: string;
     }
   | {
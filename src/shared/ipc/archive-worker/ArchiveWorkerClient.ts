@@ -21,6 +21,19 @@ export interface ArchiveTaskParams {
   destHandle: string;
   maxRows: number;
   batchSize: number;
+  compress?: boolean;
+}
+
+/**
+ * Restore (un-archive) task parameters
+ */
+export interface RestoreTaskParams {
+  taskId: string;
+  collection: string;
+  archiveHandle: string;
+  primaryHandle: string;
+  batchSize: number;
+  filter?: Record<string, unknown>;
 }
 
 /**
@@ -50,13 +63,72 @@ export class ArchiveWorkerClient extends WorkerClient<ArchiveRequest, ArchiveRes
       source_handle: params.sourceHandle,
       dest_handle: params.destHandle,
       max_rows: params.maxRows,
-      batch_size: params.batchSize
+      batch_size: params.batchSize,
+      compress: params.compress
     };
 
     const response = await this.send('archive', request);
     return response.payload as ArchiveResponse;
   }
 
+  /**
+   * Queue a restore task: move rows from the archive back to primary
+   *
+   * @param params - Restore task parameters (optional filter, e.g. a date range)
+   * @returns Promise resolving to queued response with position
+   */
+  async queueRestore(params: RestoreTaskParams): Promise<ArchiveResponse> {
+    const request: ArchiveRequest = {
+      command: 'restore',
+      task_id: params.taskId,
+      collection: params.collection,
+      archive_handle: params.archiveHandle,
+      primary_handle: params.primaryHandle,
+      batch_size: params.batchSize,
+      filter: params.filter
+    };
+
+    const response = await this.send('restore', request);
+    return response.payload as ArchiveResponse;
+  }
+
+  /**
+   * Read archived rows without moving them (compressed rows are decompressed)
+   */
+  async readArchive(
+    collection: string,
+    archiveHandle: string,
+    limit: number,
+    filter?: Record<string, unknown>
+  ): Promise<ArchiveResponse> {
+    const request: ArchiveRequest = {
+      command: 'read',
+      collection,
+      archive_handle: archiveHandle,
+      filter,
+      limit
+    };
+
+    const response = await this.send('read', request);
+    return response.payload as ArchiveResponse;
+  }
+
+  /**
+   * Get the outcome of a finished task
+   *
+   * @returns 'complete' with row count and compression stats, or 'error'
+   * if the task failed or hasn't finished
+   */
+  async getResult(taskId: string): Promise<ArchiveResponse> {
+    const request: ArchiveRequest = {
+      command: 'result',
+      task_id: taskId
+    };
+
+    const response = await this.send('result', request);
+    return response.payload as ArchiveResponse;
+  }
+
   /**
    * Ping the worker to check health
    */
@@ -25,6 +25,9 @@
 
 export type LogLevel = 'debug' | 'info' | 'warn' | 'error';
 
+/** 'text' (default) or 'jsonl': one JSON object per line, in a sibling .jsonl file */
+export type LogFormat = 'text' | 'jsonl';
+
 /**
  * Payload for write-log requests.
  */
@@ -34,6 +37,7 @@ export interface WriteLogPayload {
   component: string;       // e.g., 'PersonaUser', 'DataDaemonServer'
   message: string;
   args?: unknown[];        // Additional arguments to log
+  format?: LogFormat;      // Defaults to LOGGER_FORMAT on the Rust side (text if unset)
 }
 
 /**
@@ -55,7 +59,7 @@ export interface WriteLogBatchPayload {
  * Result of log/write-batch command.
  */
 export interface WriteLogBatchResult {
-  entriesQueued: number;
+  entriesQueued: number;   // Entries over the connection's rate limit or a full queue are dropped
 }
 
 /**
@@ -88,4 +92,5 @@ export interface PingResult {
   connectionsTotal: number;
   requestsProcessed: number;
   activeCategories: number;
+  droppedMessages?: number; // Lines refused by connection rate limits or a full queue
 }
@@ -17,8 +17,9 @@ interface GrpcPingResponse { message: string; timestamp: string }
 interface GrpcSuccessResponse { success: boolean; error?: string }
 interface GrpcLoadResponse extends GrpcSuccessResponse { load_time_ms: string }
 interface GrpcGenerateProgress { tokens_generated: number; tokens_total: number }
-interface GrpcGenerateComplete { text: string; tokens: number; duration_ms: number }
-interface GrpcGenerateResponse { progress?: GrpcGenerateProgress; complete?: GrpcGenerateComplete }
+interface GrpcGenerateComplete { text: string; tokens: number; duration_ms: number; truncated?: boolean }
+interface GrpcGenerateToken { text: string; tokens_generated: number }
+interface GrpcGenerateResponse { progress?: GrpcGenerateProgress; complete?: GrpcGenerateComplete; token?: GrpcGenerateToken }
 interface GrpcModelEntry { model_id: string; loaded: boolean; memory_bytes: string; dtype: string }
 interface GrpcAdapterEntry { adapter_id: string; path: string; scale: number; active: boolean }
 interface GrpcAdapterMetadata { base_model: string; rank: number; alpha: number; target_modules: string[]; peft_type: string }
@@ -37,6 +38,7 @@ interface GrpcStatusResponse {
 interface InferenceGrpcService {
   ping(req: Record<string, never>, cb: (err: Error | null, res: GrpcPingResponse) => void): void;
   generate(req: Record<string, unknown>, opts: { deadline: Date }): grpc.ClientReadableStream<GrpcGenerateResponse>;
+  generateChat(req: Record<string, unknown>, opts: { deadline: Date }): grpc.ClientReadableStream<GrpcGenerateResponse>;
   loadModel(req: Record<string, unknown>, opts: { deadline: Date }, cb: (err: Error | null, res: GrpcLoadResponse) => void): void;
   unloadModel(req: Record<string, never>, cb: (err: Error | null, res: GrpcSuccessResponse) => void): void;
   listModels(req: Record<string, never>, cb: (err: Error | null, res: { models: GrpcModelEntry[] }) => void): void;
@@ -72,6 +74,7 @@ export interface GenerateResult {
   text: string;
   tokens: number;
   durationMs: number;
+  truncated: boolean;  // Stopped by maxGenerationMs; text is what was generated until then
 }
 
 export interface GenerateProgress {
@@ -79,6 +82,24 @@ export interface GenerateProgress {
   tokensTotal: number;
 }
 
+export interface GenerateOptions {
+  maxTokens?: number;
+  temperature?: number;
+  timeoutMs?: number;
+  onProgress?: (progress: GenerateProgress) => void;
+  onToken?: (text: string, tokensGenerated: number) => void; // Streamed text before completion
+  signal?: AbortSignal;
+  personaId?: string;   // For per-persona logging in Rust
+  personaName?: string; // Human-readable name for logs
+  jsonSchema?: object;  // Constrain output to JSON matching this schema ({} = any JSON)
+  maxGenerationMs?: number; // Stop generating after this long and return the partial text
+}
+
+export interface ChatMessage {
+  role: string; // 'system' | 'user' | 'assistant' | ...
+  content: string;
+}
+
 export interface ModelInfo {
   modelId: string;
   loaded: boolean;
@@ -184,35 +205,71 @@ export class InferenceGrpcClient {
   async generate(
     modelId: string,
     prompt: string,
-    options?: {
-      maxTokens?: number;
-      temperature?: number;
-      timeoutMs?: number;
-      onProgress?: (progress: GenerateProgress) => void;
-      signal?: AbortSignal;
-      personaId?: string;   // For per-persona logging in Rust
-      personaName?: string; // Human-readable name for logs
-    }
+    options?: GenerateOptions
   ): Promise<GenerateResult> {
-    return new Promise((resolve, reject) => {
-      const personaLabel = options?.personaName || 'unknown';
-      console.log(`[InferenceGrpcClient] [${personaLabel}] Sending to Rust WorkerPool (prompt: ${prompt.length} chars)`);
-      const deadline = new Date(Date.now() + (options?.timeoutMs ?? 300000)); // 5 min
-      const maxTokens = options?.maxTokens ?? 100;
-      const temperature = options?.temperature ?? 0.7;
+    const personaLabel = options?.personaName || 'unknown';
+    console.log(`[InferenceGrpcClient] [${personaLabel}] Sending to Rust WorkerPool (prompt: ${prompt.length} chars)`);
+    const deadline = new Date(Date.now() + (options?.timeoutMs ?? 300000)); // 5 min
+
+    const call = this.client.generate(
+      {
+        model_id: modelId,
+        prompt,
+        max_tokens: options?.maxTokens ?? 100,
+        temperature: options?.temperature ?? 0.7,
+        persona_id: options?.personaId || '',
+        persona_name: options?.personaName || '',
+        json_schema: options?.jsonSchema ? JSON.stringify(options.jsonSchema) : '',
+        timeout_ms: options?.maxGenerationMs,
+      },
+      { deadline }
+    );
+    return this.collectGeneration(call, options);
+  }
 
-      const call = this.client.generate(
-        {
-          model_id: modelId,
-          prompt,
-          max_tokens: maxTokens,
-          temperature,
-          persona_id: options?.personaId || '',
-          persona_name: options?.personaName || '',
-        },
-        { deadline }
-      );
+  /**
+   * Generate a reply to chat messages
+   *
+   * The Rust worker formats the messages with the model's own chat template
+   * (tokenizer_config.json), so callers never hand-build model-specific markup.
+   *
+   * @param modelId - Model to use
+   * @param messages - Conversation so far, oldest first
+   * @param options - Generation options
+   */
+  async generateChat(
+    modelId: string,
+    messages: ChatMessage[],
+    options?: GenerateOptions
+  ): Promise<GenerateResult> {
+    const personaLabel = options?.personaName || 'unknown';
+    console.log(`[InferenceGrpcClient] [${personaLabel}] Sending chat to Rust WorkerPool (${messages.length} messages)`);
+    const deadline = new Date(Date.now() + (options?.timeoutMs ?? 300000)); // 5 min
+
+    const call = this.client.generateChat(
+      {
+        model_id: modelId,
+        messages,
+        max_tokens: options?.maxTokens ?? 100,
+        temperature: options?.temperature ?? 0.7,
+        persona_id: options?.personaId || '',
+        persona_name: options?.personaName || '',
+        json_schema: options?.jsonSchema ? JSON.stringify(options.jsonSchema) : '',
+        timeout_ms: options?.maxGenerationMs,
+      },
+      { deadline }
+    );
+    return this.collectGeneration(call, options);
+  }
 
+  /**
+   * Stream Token/Progress updates to the callbacks and resolve on Complete
+   */
+  private collectGeneration(
+    call: grpc.ClientReadableStream<GrpcGenerateResponse>,
+    options?: GenerateOptions
+  ): Promise<GenerateResult> {
+    return new Promise((resolve, reject) => {
       // Handle abort signal
       if (options?.signal) {
         options.signal.addEventListener('abort', () => {
@@ -222,7 +279,9 @@ export class InferenceGrpcClient {
       }
 
       call.on('data', (response: GrpcGenerateResponse) => {
-        if (response.progress) {
+        if (response.token) {
+          options?.onToken?.(response.token.text, response.token.tokens_generated);
+        } else if (response.progress) {
           options?.onProgress?.({
             tokensGenerated: response.progress.tokens_generated,
             tokensTotal: response.progress.tokens_total,
@@ -233,6 +292,7 @@ export class InferenceGrpcClient {
             text: response.complete.text,
             tokens: response.complete.tokens,
             durationMs: response.complete.duration_ms,
+            truncated: response.complete.truncated ?? false,
           });
         }
       });
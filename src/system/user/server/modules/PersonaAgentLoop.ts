@@ -6,6 +6,8 @@
  *
  * The model decides when to stop (finishReason !== 'tool_use').
  * Safety cap prevents infinite loops for less capable models.
+ * Between tool calls the loop checks for preemption and, when a more urgent
+ * item is waiting, stops with the response it has so far.
  */
 
 import type { UUID } from '../../../core/types/CrossPlatformUUID';
@@ -42,6 +44,8 @@ export interface AgentLoopContext {
   promptAssembler: PersonaPromptAssembler;
   mediaConfig: PersonaMediaConfig;
   log: (message: string, ...args: unknown[]) => void;
+  /** Preemption checkpoint, polled between tool calls */
+  shouldYield?: () => Promise<boolean>;
 }
 
 export interface AgentLoopResult {
@@ -104,6 +108,12 @@ export async function runAgentLoop(
       break;
     }
 
+    // Safe checkpoint: yield to a more urgent item instead of running more tools
+    if (toolIterations > 0 && ctx.shouldYield && await ctx.shouldYield().catch(() => false)) {
+      ctx.log(`⏸️ ${ctx.personaName}: [AGENT-LOOP] Preempted after ${toolIterations} iteration(s), stopping`);
+      break;
+    }
+
     toolIterations++;
     ctx.log(`🔧 ${ctx.personaName}: [AGENT-LOOP] Iteration ${toolIterations}/${SAFETY_MAX}`);
 
@@ -667,6 +667,9 @@ export class PersonaResponseGenerator {
             promptAssembler: this.promptAssembler,
             mediaConfig: this.mediaConfig,
             log: this.log.bind(this),
+            shouldYield: this.rustCognitionBridge
+              ? () => this.rustCognitionBridge!.inboxShouldYield()
+              : undefined,
           },
           messages,
           request,
@@ -426,6 +426,30 @@ export class RustCognitionBridge {
     }
   }
 
+  /**
+   * Checkpoint poll: true when a more urgent item arrived while the current
+   * one was in flight, so it should stop at the next safe point.
+   * THROWS on failure
+   */
+  async inboxShouldYield(): Promise<boolean> {
+    this.assertReady('inboxShouldYield');
+    const start = performance.now();
+
+    try {
+      const shouldYield = await this.client.inboxShouldYield(this.personaId);
+      const elapsed = performance.now() - start;
+      if (shouldYield) {
+        this.logger.info(`In-flight item preempted (${elapsed.toFixed(2)}ms)`);
+      }
+      return shouldYield;
+    } catch (error) {
+      const elapsed = performance.now() - start;
+      this.logger.error(`inboxShouldYield FAILED after ${elapsed.toFixed(2)}ms`);
+      this.logger.error(`Error: ${error}`);
+      throw error;
+    }
+  }
+
   // ========================================================================
   // Memory Subsystem (Hippocampus in Rust — corpus-based, no SQL)
   // Corpus loaded at startup, recall/consciousness bypass TS event loop
@@ -1,16 +1,26 @@
 /// Archive Worker - PRODUCTION IMPLEMENTATION
 ///
 /// FLOW:
-/// 1. TypeScript → Rust: Queue archive task
+/// 1. TypeScript → Rust: Queue archive (or restore) task
 /// 2. Rust: Direct SQL to archive rows (copy-verify-delete)
 /// 3. Rust → TypeScript: Emit progress events
 /// 4. Rust → TypeScript: Return completion status
 ///
+/// Restore is the same flow pointed the other way: archive → primary.
+///
+/// With `compress`, each archived row is stored as a gzip'd JSON payload
+/// (base64, since rows travel as JSON through data/create). Restore and
+/// `read` decompress transparently.
+///
 /// Uses CommandClient to call TypeScript Commands.execute() for coordinated database access.
+use base64::Engine;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::collections::VecDeque;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::sync::{mpsc, Arc, Mutex};
 use std::{fs, thread};
@@ -29,7 +39,34 @@ enum Request {
         source_handle: String,
         dest_handle: String,
         batch_size: usize,
+        /// Store each row as a gzip-compressed payload
+        #[serde(default)]
+        compress: bool,
+    },
+    /// Un-archive: move rows from `archive_handle` back to `primary_handle`.
+    /// `filter` is a data/list filter, e.g. a createdAt range.
+    #[serde(rename = "restore")]
+    Restore {
+        task_id: String,
+        collection: String,
+        archive_handle: String,
+        primary_handle: String,
+        batch_size: usize,
+        #[serde(default)]
+        filter: Option<serde_json::Value>,
+    },
+    /// Read archived rows (decompressed) without moving them.
+    #[serde(rename = "read")]
+    Read {
+        collection: String,
+        archive_handle: String,
+        #[serde(default)]
+        filter: Option<serde_json::Value>,
+        limit: usize,
     },
+    /// Outcome of a finished task: its completion stats or its error.
+    #[serde(rename = "result")]
+    Result { task_id: String },
     #[serde(rename = "ping")]
     Ping,
 }
@@ -43,18 +80,101 @@ enum Response {
         queue_position: usize,
     },
     #[serde(rename = "complete")]
-    Complete { task_id: String, rows_found: usize },
+    Complete {
+        task_id: String,
+        rows_found: usize,
+        /// Row JSON size before compression
+        original_bytes: usize,
+        /// Bytes actually written to the destination payload
+        stored_bytes: usize,
+        /// stored / original (1.0 when uncompressed)
+        compression_ratio: f64,
+    },
+    #[serde(rename = "rows")]
+    Rows { items: Vec<serde_json::Value> },
+    #[serde(rename = "error")]
+    Error {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task_id: Option<String>,
+        error: String,
+    },
     #[serde(rename = "pong")]
     Pong { uptime_seconds: u64 },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TaskKind {
+    Archive,
+    Restore,
+}
+
 #[derive(Debug, Clone)]
 struct Task {
     task_id: String,
+    kind: TaskKind,
     collection: String,
+    /// Rows move from source to dest (primary → archive, or back on restore)
     source_handle: String,
     dest_handle: String,
     batch_size: usize,
+    filter: Option<serde_json::Value>,
+    compress: bool,
+}
+
+/// Byte accounting for one task, reported in the completion response.
+#[derive(Debug, Default, Clone)]
+struct TransferStats {
+    rows: usize,
+    original_bytes: usize,
+    stored_bytes: usize,
+}
+
+impl TransferStats {
+    fn compression_ratio(&self) -> f64 {
+        if self.original_bytes == 0 {
+            1.0
+        } else {
+            self.stored_bytes as f64 / self.original_bytes as f64
+        }
+    }
+}
+
+/// Finished tasks kept for `result` queries; the oldest are dropped first.
+const MAX_FINISHED_TASKS: usize = 256;
+
+/// Outcomes of recently finished tasks, oldest first.
+#[derive(Default)]
+struct FinishedTasks {
+    outcomes: VecDeque<(String, Result<TransferStats, String>)>,
+}
+
+impl FinishedTasks {
+    fn record(&mut self, task_id: String, outcome: Result<TransferStats, String>) {
+        if self.outcomes.len() >= MAX_FINISHED_TASKS {
+            self.outcomes.pop_front();
+        }
+        self.outcomes.push_back((task_id, outcome));
+    }
+
+    fn response(&self, task_id: &str) -> Response {
+        match self.outcomes.iter().rev().find(|(id, _)| id == task_id) {
+            Some((_, Ok(stats))) => Response::Complete {
+                task_id: task_id.to_string(),
+                rows_found: stats.rows,
+                original_bytes: stats.original_bytes,
+                stored_bytes: stats.stored_bytes,
+                compression_ratio: stats.compression_ratio(),
+            },
+            Some((_, Err(error))) => Response::Error {
+                task_id: Some(task_id.to_string()),
+                error: error.clone(),
+            },
+            None => Response::Error {
+                task_id: Some(task_id.to_string()),
+                error: format!("Task {task_id} is unknown or still running"),
+            },
+        }
+    }
 }
 
 // ============================================================================
@@ -163,9 +283,11 @@ fn main() -> std::io::Result<()> {
     // Shared state
     let queue: Arc<Mutex<VecDeque<Task>>> = Arc::new(Mutex::new(VecDeque::new()));
     let (task_tx, task_rx) = mpsc::channel::<Task>();
+    let finished: Arc<Mutex<FinishedTasks>> = Arc::new(Mutex::new(FinishedTasks::default()));
 
     // Spawn worker thread with command client access
     let worker_queue = queue.clone();
+    let worker_finished = finished.clone();
     let worker_command_client = command_client.clone();
     thread::spawn(move || {
         println!("🔥 Worker thread started");
@@ -174,21 +296,35 @@ fn main() -> std::io::Result<()> {
             println!("📦 Processing task: {} ({})", task.task_id, task.collection);
 
             // Archive rows using Commands.execute() via CommandRouterServer
-            match archive_rows(&worker_command_client, &task) {
-                Ok(archived) => {
+            let result = match task.kind {
+                TaskKind::Archive => archive_rows(&worker_command_client, &task),
+                TaskKind::Restore => restore_rows(&worker_command_client, &task),
+            };
+            match &result {
+                Ok(stats) => {
+                    let verb = match task.kind {
+                        TaskKind::Archive => "Archived",
+                        TaskKind::Restore => "Restored",
+                    };
                     println!(
-                        "✅ Task {} complete: Archived {} rows from {}",
-                        task.task_id, archived, task.collection
+                        "✅ Task {} complete: {} {} rows from {}",
+                        task.task_id, verb, stats.rows, task.collection
                     );
-
-                    // Remove from queue
-                    let mut q = worker_queue.lock().unwrap();
-                    q.retain(|t| t.task_id != task.task_id);
                 }
                 Err(e) => {
                     println!("❌ Task {} failed: {}", task.task_id, e);
                 }
             }
+
+            // Stats are returned to callers through the `result` request
+            worker_queue
+                .lock()
+                .unwrap()
+                .retain(|t| t.task_id != task.task_id);
+            worker_finished
+                .lock()
+                .unwrap()
+                .record(task.task_id.clone(), result);
         }
     });
 
@@ -202,9 +338,17 @@ fn main() -> std::io::Result<()> {
             Ok(stream) => {
                 let queue_clone = queue.clone();
                 let task_tx_clone = task_tx.clone();
+                let client_clone = command_client.clone();
+                let finished_clone = finished.clone();
 
                 thread::spawn(move || {
-                    if let Err(e) = handle_connection(stream, queue_clone, task_tx_clone) {
+                    if let Err(e) = handle_connection(
+                        stream,
+                        queue_clone,
+                        task_tx_clone,
+                        &client_clone,
+                        &finished_clone,
+                    ) {
                         eprintln!("Connection error: {e}");
                     }
                 });
@@ -224,6 +368,8 @@ fn handle_connection(
     stream: UnixStream,
     queue: Arc<Mutex<VecDeque<Task>>>,
     task_tx: mpsc::Sender<Task>,
+    command_client: &CommandClient,
+    finished: &Mutex<FinishedTasks>,
 ) -> std::io::Result<()> {
     let mut reader = BufReader::new(&stream);
     let mut writer = stream.try_clone()?;
@@ -250,29 +396,53 @@ fn handle_connection(
                 source_handle,
                 dest_handle,
                 batch_size,
+                compress,
             } => {
                 let task = Task {
                     task_id: task_id.clone(),
+                    kind: TaskKind::Archive,
                     collection,
                     source_handle,
                     dest_handle,
                     batch_size,
+                    filter: None,
+                    compress,
                 };
-
-                // Queue task
-                let mut q = queue.lock().unwrap();
-                q.push_back(task.clone());
-                let position = q.len();
-                drop(q);
-
-                // Send to worker thread
-                task_tx.send(task).ok();
-
-                Response::Queued {
-                    task_id,
-                    queue_position: position,
-                }
+                queue_task(&queue, &task_tx, task)
+            }
+            Request::Restore {
+                task_id,
+                collection,
+                archive_handle,
+                primary_handle,
+                batch_size,
+                filter,
+            } => {
+                let task = Task {
+                    task_id: task_id.clone(),
+                    kind: TaskKind::Restore,
+                    collection,
+                    source_handle: archive_handle,
+                    dest_handle: primary_handle,
+                    batch_size,
+                    filter,
+                    compress: false,
+                };
+                queue_task(&queue, &task_tx, task)
             }
+            Request::Read {
+                collection,
+                archive_handle,
+                filter,
+                limit,
+            } => match read_rows(command_client, &collection, &archive_handle, filter, limit) {
+                Ok(items) => Response::Rows { items },
+                Err(error) => Response::Error {
+                    task_id: None,
+                    error,
+                },
+            },
+            Request::Result { task_id } => finished.lock().unwrap().response(&task_id),
             Request::Ping => {
                 Response::Pong {
                     uptime_seconds: 0, // TODO: Track actual uptime
@@ -288,24 +458,153 @@ fn handle_connection(
     Ok(())
 }
 
+fn queue_task(queue: &Mutex<VecDeque<Task>>, task_tx: &mpsc::Sender<Task>, task: Task) -> Response {
+    let task_id = task.task_id.clone();
+
+    // Queue task
+    let mut q = queue.lock().unwrap();
+    q.push_back(task.clone());
+    let position = q.len();
+    drop(q);
+
+    // Send to worker thread
+    task_tx.send(task).ok();
+
+    Response::Queued {
+        task_id,
+        queue_position: position,
+    }
+}
+
+// ============================================================================
+// Compression
+// ============================================================================
+
+/// Marker field on compressed archive rows; its value names the encoding.
+const ENCODING_FIELD: &str = "archiveEncoding";
+const PAYLOAD_FIELD: &str = "archivePayload";
+const GZIP_BASE64: &str = "gzip+base64";
+
+/// Fields kept uncompressed so the archive can still be listed, ordered,
+/// and filtered (e.g. restore by date range).
+const PLAIN_FIELDS: &[&str] = &["id", "createdAt", "created_at"];
+
+/// Replace a row with its gzip'd JSON. Returns (row, original_bytes, stored_bytes).
+fn compress_row(row: &serde_json::Value) -> Result<(serde_json::Value, usize, usize), String> {
+    let raw = serde_json::to_vec(row).map_err(|e| e.to_string())?;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&raw).map_err(|e| e.to_string())?;
+    let gz = encoder.finish().map_err(|e| e.to_string())?;
+    let payload = base64::engine::general_purpose::STANDARD.encode(gz);
+
+    let mut compressed = serde_json::Map::new();
+    for field in PLAIN_FIELDS {
+        if let Some(v) = row.get(*field) {
+            compressed.insert((*field).to_string(), v.clone());
+        }
+    }
+    let stored_bytes = payload.len();
+    compressed.insert(ENCODING_FIELD.to_string(), json!(GZIP_BASE64));
+    compressed.insert(PAYLOAD_FIELD.to_string(), json!(payload));
+
+    Ok((
+        serde_json::Value::Object(compressed),
+        raw.len(),
+        stored_bytes,
+    ))
+}
+
+/// Inverse of `compress_row`. Rows without the marker pass through unchanged.
+fn decompress_row(row: &serde_json::Value) -> Result<serde_json::Value, String> {
+    match row.get(ENCODING_FIELD).and_then(|v| v.as_str()) {
+        None => Ok(row.clone()),
+        Some(GZIP_BASE64) => {
+            let payload = row
+                .get(PAYLOAD_FIELD)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| format!("Missing {PAYLOAD_FIELD} in compressed row"))?;
+            let gz = base64::engine::general_purpose::STANDARD
+                .decode(payload)
+                .map_err(|e| format!("Invalid payload encoding: {e}"))?;
+            let mut raw = Vec::new();
+            GzDecoder::new(gz.as_slice())
+                .read_to_end(&mut raw)
+                .map_err(|e| format!("Failed to decompress row: {e}"))?;
+            serde_json::from_slice(&raw).map_err(|e| format!("Invalid row JSON: {e}"))
+        }
+        Some(other) => Err(format!("Unknown archive encoding: {other}")),
+    }
+}
+
+/// List archived rows, decompressing any compressed ones.
+fn read_rows(
+    command_client: &CommandClient,
+    collection: &str,
+    archive_handle: &str,
+    filter: Option<serde_json::Value>,
+    limit: usize,
+) -> Result<Vec<serde_json::Value>, String> {
+    let mut params = json!({
+        "collection": collection,
+        "dbHandle": archive_handle,
+        "limit": limit,
+        "orderBy": [{"field": "created_at", "direction": "asc"}]
+    });
+    if let Some(filter) = filter {
+        params["filter"] = filter;
+    }
+    let result = command_client.execute("data/list", params)?;
+
+    result
+        .get("items")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| "Missing items in response".to_string())?
+        .iter()
+        .map(decompress_row)
+        .collect()
+}
+
 // ============================================================================
 // Archive Logic (Copy-Verify-Delete Pattern)
 // ============================================================================
 
-fn archive_rows(command_client: &CommandClient, task: &Task) -> Result<usize, String> {
-    let mut total_archived = 0;
+fn archive_rows(command_client: &CommandClient, task: &Task) -> Result<TransferStats, String> {
+    // Archive caps each task at one batch
+    move_rows(command_client, task, Some(task.batch_size))
+}
+
+/// Restore every row matching the task filter, batch by batch.
+///
+/// Same copy-verify-delete discipline as archiving: a row is only deleted
+/// from the archive once it reads back from primary. A crash mid-restore
+/// leaves at most a duplicate (re-running skips the failed insert, verifies,
+/// and deletes), never a lost row.
+fn restore_rows(command_client: &CommandClient, task: &Task) -> Result<TransferStats, String> {
+    move_rows(command_client, task, None)
+}
+
+/// Copy-verify-delete rows from `task.source_handle` to `task.dest_handle`
+/// until the source (filtered) is empty or `max_rows` have moved.
+fn move_rows(
+    command_client: &CommandClient,
+    task: &Task,
+    max_rows: Option<usize>,
+) -> Result<TransferStats, String> {
+    let mut stats = TransferStats::default();
 
     loop {
         // Get batch of rows from source via Commands.execute()
-        let list_result = command_client.execute(
-            "data/list",
-            json!({
-                "collection": task.collection,
-                "dbHandle": task.source_handle,
-                "limit": task.batch_size,
-                "orderBy": [{"field": "created_at", "direction": "asc"}]
-            }),
-        )?;
+        let mut list_params = json!({
+            "collection": task.collection,
+            "dbHandle": task.source_handle,
+            "limit": task.batch_size,
+            "orderBy": [{"field": "created_at", "direction": "asc"}]
+        });
+        if let Some(filter) = &task.filter {
+            list_params["filter"] = filter.clone();
+        }
+        let list_result = command_client.execute("data/list", list_params)?;
 
         let items = list_result
             .get("items")
@@ -326,13 +625,28 @@ fn archive_rows(command_client: &CommandClient, task: &Task) -> Result<usize, St
                 .and_then(|v| v.as_str())
                 .ok_or_else(|| "Missing id field".to_string())?;
 
+            // Compress on the way into the archive; restore always
+            // decompresses (a no-op for rows archived uncompressed)
+            let data = if task.compress {
+                let (compressed, original_bytes, stored_bytes) = compress_row(row)?;
+                stats.original_bytes += original_bytes;
+                stats.stored_bytes += stored_bytes;
+                compressed
+            } else {
+                let data = decompress_row(row)?;
+                let bytes = serde_json::to_vec(&data).map_or(0, |v| v.len());
+                stats.original_bytes += bytes;
+                stats.stored_bytes += bytes;
+                data
+            };
+
             // 1. Copy to archive via Commands.execute()
             command_client.execute(
                 "data/create",
                 json!({
                     "collection": task.collection,
                     "dbHandle": task.dest_handle,
-                    "data": row,
+                    "data": data,
                     "suppressEvents": true
                 }),
             )?;
@@ -357,8 +671,8 @@ fn archive_rows(command_client: &CommandClient, task: &Task) -> Result<usize, St
                 return Err(format!("Failed to verify row {id} in archive"));
             }
 
-            // 3. Delete from primary via Commands.execute()
-            command_client.execute(
+            // 3. Delete from source via Commands.execute()
+            let delete_result = command_client.execute(
                 "data/delete",
                 json!({
                     "collection": task.collection,
@@ -368,16 +682,94 @@ fn archive_rows(command_client: &CommandClient, task: &Task) -> Result<usize, St
                 }),
             )?;
 
-            total_archived += 1;
+            // A row that won't delete would be re-listed forever
+            if delete_result.get("success").and_then(|v| v.as_bool()) == Some(false) {
+                return Err(format!("Failed to delete row {id} from source"));
+            }
+
+            stats.rows += 1;
         }
 
-        println!("  ✅ Archived {batch_size} rows (total: {total_archived})");
+        println!("  ✅ Moved {batch_size} rows (total: {})", stats.rows);
 
-        // Check if we've archived enough (cap at batch size for now)
-        if total_archived >= task.batch_size {
+        // Check if we've moved enough
+        if max_rows.is_some_and(|max| stats.rows >= max) {
             break;
         }
     }
 
-    Ok(total_archived)
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compress_row_round_trip() {
+        let row = json!({
+            "id": "row-1",
+            "createdAt": "2026-01-01T00:00:00Z",
+            "content": "hello ".repeat(200),
+            "nested": {"tags": ["a", "b"], "score": 0.5}
+        });
+
+        let (compressed, original_bytes, stored_bytes) = compress_row(&row).unwrap();
+        assert_eq!(compressed["id"], "row-1");
+        assert_eq!(compressed["createdAt"], "2026-01-01T00:00:00Z");
+        assert_eq!(compressed[ENCODING_FIELD], GZIP_BASE64);
+        assert!(compressed.get("content").is_none());
+        assert_eq!(original_bytes, serde_json::to_vec(&row).unwrap().len());
+        assert!(stored_bytes < original_bytes);
+
+        assert_eq!(decompress_row(&compressed).unwrap(), row);
+        // Uncompressed rows pass through
+        assert_eq!(decompress_row(&row).unwrap(), row);
+    }
+
+    #[test]
+    fn test_decompress_row_rejects_bad_payloads() {
+        let unknown = json!({"id": "x", ENCODING_FIELD: "zstd", PAYLOAD_FIELD: ""});
+        assert!(decompress_row(&unknown).is_err());
+        let missing = json!({"id": "x", ENCODING_FIELD: GZIP_BASE64});
+        assert!(decompress_row(&missing).is_err());
+        let garbage = json!({"id": "x", ENCODING_FIELD: GZIP_BASE64, PAYLOAD_FIELD: "not gzip"});
+        assert!(decompress_row(&garbage).is_err());
+    }
+
+    #[test]
+    fn test_finished_task_results() {
+        let mut finished = FinishedTasks::default();
+        finished.record(
+            "t1".to_string(),
+            Ok(TransferStats {
+                rows: 3,
+                original_bytes: 400,
+                stored_bytes: 100,
+            }),
+        );
+        finished.record("t2".to_string(), Err("boom".to_string()));
+
+        match finished.response("t1") {
+            Response::Complete {
+                rows_found,
+                compression_ratio,
+                ..
+            } => {
+                assert_eq!(rows_found, 3);
+                assert_eq!(compression_ratio, 0.25);
+            }
+            other => panic!("expected complete, got {other:?}"),
+        }
+        assert!(
+            matches!(finished.response("t2"), Response::Error { error, .. } if error == "boom")
+        );
+        assert!(matches!(finished.response("t3"), Response::Error { .. }));
+
+        for i in 0..MAX_FINISHED_TASKS {
+            finished.record(format!("n{i}"), Err(String::new()));
+        }
+        assert_eq!(finished.outcomes.len(), MAX_FINISHED_TASKS);
+        assert!(finished.outcomes.iter().all(|(id, _)| id != "t1"));
+    }
 }
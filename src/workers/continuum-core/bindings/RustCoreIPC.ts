@@ -21,7 +21,7 @@ export type { VoiceParticipant, UtteranceEvent, VoiceSynthesizeResult } from './
 export type { DiscoveredModel, ProviderConfig, ModelsDiscoverResult } from './modules/models';
 export type { AIGenerateParams, AIGenerateResult } from './modules/ai';
 export type { EmbeddingResult, SimilarityResult, TopKResult, TopKResponse, ClusterResult } from './modules/embedding';
-export type { SearchExecuteResult, SearchVectorResult } from './modules/search';
+export type { SearchExecuteResult, SearchVectorResult, SearchHybridResult } from './modules/search';
 export type { ChannelEnqueueResult, ChannelDequeueResult, ChannelServiceCycleResult, ChannelServiceCycleFullResult } from './modules/channel';
 export type { ModuleInfo, ModuleMetrics, SlowCommand } from './modules/runtime';
 export type { GpuStatsResponse, SubsystemInfo } from './modules/gpu';
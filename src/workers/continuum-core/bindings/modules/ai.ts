@@ -51,12 +51,23 @@ export interface AIGenerateResult {
 	}>;
 }
 
+/** One frame of `ai/generate/stream`: a token, or the final summary when `done` */
+export interface AIGenerateStreamChunk {
+	done: boolean;
+	token?: string;
+	generatedTokens?: number;
+	finishReason?: string;
+	model?: string;
+	provider?: string;
+}
+
 // ============================================================================
 // Mixin
 // ============================================================================
 
 export interface AIMixin {
 	aiGenerate(params: AIGenerateParams): Promise<AIGenerateResult>;
+	aiGenerateStream(params: AIGenerateParams): AsyncGenerator<AIGenerateStreamChunk>;
 }
 
 export function AIMixin<T extends new (...args: any[]) => RustCoreIPCClientBase>(Base: T) {
@@ -97,5 +108,26 @@ export function AIMixin<T extends new (...args: any[]) => RustCoreIPCClientBase>
 				toolCalls: result.toolCalls,
 			};
 		}
+
+		/**
+		 * Generate text token by token. Yields each token as it is produced and
+		 * finishes with a `done` chunk carrying the usage summary.
+		 */
+		async *aiGenerateStream(params: AIGenerateParams): AsyncGenerator<AIGenerateStreamChunk> {
+			for await (const response of this.requestStream({ command: 'ai/generate/stream', ...params })) {
+				const result = response.result;
+				if (!response.success || result?.error) {
+					throw new Error(response.error || result?.error || 'AI generation failed');
+				}
+				yield {
+					done: result.done === true,
+					token: result.token,
+					generatedTokens: result.generated_tokens,
+					finishReason: result.finishReason,
+					model: result.model,
+					provider: result.provider,
+				};
+			}
+		}
 	};
 }
@@ -42,6 +42,15 @@ export interface IPCResponse {
 	binaryData?: Buffer;
 }
 
+/** A request awaiting its response frame(s) */
+export interface PendingRequest {
+	resolve: (result: IPCResponse) => void;
+	reject: (err: Error) => void;
+	timer: ReturnType<typeof setTimeout>;
+	/** Streaming requests stay registered until a chunk with `done: true` or an error */
+	stream?: boolean;
+}
+
 /**
  * Base IPC Client - Core connection and request logic only.
  * Domain-specific methods are added via mixins.
@@ -53,7 +62,7 @@ export class RustCoreIPCClientBase extends EventEmitter {
 	// Internal members (public for mixin compatibility, but treat as private)
 	public _socket: net.Socket | null = null;
 	public _buffer: Buffer = Buffer.alloc(0);
-	public _pendingRequests: Map<number, { resolve: (result: IPCResponse) => void; reject: (err: Error) => void; timer: ReturnType<typeof setTimeout> }> = new Map();
+	public _pendingRequests: Map<number, PendingRequest> = new Map();
 	public _nextRequestId = 1;
 	public _connected = false;
 	public _socketPath: string;
@@ -163,7 +172,10 @@ export class RustCoreIPCClientBase extends EventEmitter {
 			const pending = this._pendingRequests.get(response.requestId);
 			if (pending) {
 				clearTimeout(pending.timer);
-				this._pendingRequests.delete(response.requestId);
+				const streaming = pending.stream && response.success && response.result?.done !== true;
+				if (!streaming) {
+					this._pendingRequests.delete(response.requestId);
+				}
 				pending.resolve({ response, binaryData });
 			}
 		}
@@ -229,6 +241,82 @@ export class RustCoreIPCClientBase extends EventEmitter {
 		});
 	}
 
+	/**
+	 * Send a streaming request and yield each response frame as it arrives.
+	 *
+	 * Streaming commands (`ai/generate/stream`, `embedding/generate/stream`) answer
+	 * with one frame per chunk under the same requestId, ending with a chunk whose
+	 * result has `done: true`. An error response also ends the stream. The timeout
+	 * applies between chunks, not to the whole stream. Breaking out of the loop
+	 * drops any chunks still in flight.
+	 */
+	async *requestStream(command: Record<string, unknown>, timeoutMs?: number): AsyncGenerator<IPCJsonResponse> {
+		await this._ensureConnected();
+
+		const requestId = this._nextRequestId++;
+		const requestWithId = { ...command, requestId };
+		const timeout = timeoutMs ?? RustCoreIPCClientBase.REQUEST_TIMEOUT_MS;
+
+		const chunks: IPCJsonResponse[] = [];
+		const state: { finished: boolean; failure?: Error; wake?: () => void } = { finished: false };
+		const notify = () => {
+			state.wake?.();
+			state.wake = undefined;
+		};
+		const armTimer = () =>
+			setTimeout(() => {
+				this._pendingRequests.delete(requestId);
+				state.failure = new Error(`IPC timeout: ${command.command} sent no chunk within ${timeout}ms`);
+				notify();
+			}, timeout);
+
+		const pending: PendingRequest = {
+			resolve: ({ response }) => {
+				chunks.push(response);
+				if (this._pendingRequests.has(requestId)) {
+					pending.timer = armTimer();
+				} else {
+					state.finished = true;
+				}
+				notify();
+			},
+			reject: (err) => {
+				state.failure = err;
+				notify();
+			},
+			timer: armTimer(),
+			stream: true,
+		};
+		this._pendingRequests.set(requestId, pending);
+
+		this._socket!.write(JSON.stringify(requestWithId) + '\n', (err) => {
+			if (err) {
+				clearTimeout(pending.timer);
+				this._pendingRequests.delete(requestId);
+				state.failure = err;
+				notify();
+			}
+		});
+
+		try {
+			while (true) {
+				const chunk = chunks.shift();
+				if (chunk) {
+					yield chunk;
+				} else if (state.finished) {
+					return;
+				} else if (state.failure) {
+					throw state.failure;
+				} else {
+					await new Promise<void>((wake) => (state.wake = wake));
+				}
+			}
+		} finally {
+			clearTimeout(pending.timer);
+			this._pendingRequests.delete(requestId);
+		}
+	}
+
 	/**
 	 * Send a request and wait for JSON response (ignores binary payload).
 	 */
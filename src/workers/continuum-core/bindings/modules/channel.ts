@@ -8,6 +8,8 @@ import type {
 	ChannelEnqueueRequest,
 	ActivityDomain,
 	CognitionDecision,
+	Mood,
+	MoodProsodyTable,
 } from '../../../../shared/generated';
 
 // ============================================================================
@@ -17,6 +19,8 @@ import type {
 export interface ChannelEnqueueResult {
 	routed_to: ActivityDomain;
 	status: ChannelRegistryStatus;
+	/** The item outranks the in-flight one, which should yield at its next checkpoint */
+	preempted: boolean;
 }
 
 export interface ChannelDequeueResult {
@@ -36,6 +40,13 @@ export interface ChannelServiceCycleFullResult extends ChannelServiceCycleResult
 	decision: CognitionDecision | null;
 }
 
+export interface ChannelMoodProsodyResult {
+	table: MoodProsodyTable;
+	mood: Mood;
+	speed: number;
+	pitch_semitones: number;
+}
+
 // ============================================================================
 // Mixin
 // ============================================================================
@@ -47,6 +58,7 @@ export interface ChannelMixin {
 	channelServiceCycle(personaId: string): Promise<ChannelServiceCycleResult>;
 	channelServiceCycleFull(personaId: string): Promise<ChannelServiceCycleFullResult>;
 	channelClear(personaId: string): Promise<void>;
+	channelMoodProsody(personaId: string, table?: MoodProsodyTable): Promise<ChannelMoodProsodyResult>;
 }
 
 // eslint-disable-next-line @typescript-eslint/no-explicit-any -- mixin constructor constraint requires any[]
@@ -171,5 +183,23 @@ export function ChannelMixin<T extends new (...args: any[]) => RustCoreIPCClient
 				throw new Error(response.error || 'Failed to clear channels');
 			}
 		}
+
+		/**
+		 * Get (or set, when `table` is given) the persona's mood → voice prosody
+		 * table. Also returns the prosody for the current mood.
+		 */
+		async channelMoodProsody(personaId: string, table?: MoodProsodyTable): Promise<ChannelMoodProsodyResult> {
+			const response = await this.request({
+				command: 'channel/mood-prosody',
+				persona_id: personaId,
+				...(table !== undefined && { table }),
+			});
+
+			if (!response.success) {
+				throw new Error(response.error || 'Failed to get mood prosody');
+			}
+
+			return response.result as ChannelMoodProsodyResult;
+		}
 	};
 }
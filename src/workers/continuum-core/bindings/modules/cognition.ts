@@ -27,6 +27,7 @@ import type {
 	DomainClassification,
 	CoverageReport,
 	QualityScore,
+	InboxNext,
 } from '../../../../shared/generated';
 
 // ============================================================================
@@ -45,7 +46,11 @@ export interface CognitionMixin {
 		timestamp: number
 	): Promise<PriorityScore>;
 	cognitionFastPathDecision(personaId: string, message: InboxMessageRequest): Promise<CognitionDecision>;
-	cognitionEnqueueMessage(personaId: string, message: InboxMessageRequest): Promise<void>;
+	cognitionEnqueueMessage(personaId: string, message: InboxMessageRequest): Promise<{ preempted: boolean }>;
+	inboxNext(personaId: string): Promise<InboxNext | null>;
+	inboxShouldYield(personaId: string): Promise<boolean>;
+	inboxSuspend(personaId: string, checkpoint: unknown): Promise<void>;
+	inboxComplete(personaId: string): Promise<void>;
 	cognitionGetState(personaId: string): Promise<PersonaState & { service_cadence_ms: number }>;
 	cognitionTextSimilarity(text1: string, text2: string): Promise<TextSimilarityResult>;
 	cognitionCheckSemanticLoop(responseText: string, history: ConversationMessage[], maxHistory?: number): Promise<SemanticLoopResult>;
@@ -164,12 +169,13 @@ export function CognitionMixin<T extends new (...args: any[]) => RustCoreIPCClie
 		}
 
 		/**
-		 * Enqueue message to persona's priority inbox
+		 * Enqueue message to persona's priority inbox.
+		 * `preempted` = the in-flight message was asked to yield for this one.
 		 */
 		async cognitionEnqueueMessage(
 			personaId: string,
 			message: InboxMessageRequest
-		): Promise<void> {
+		): Promise<{ preempted: boolean }> {
 			const response = await this.request({
 				command: 'cognition/enqueue-message',
 				persona_id: personaId,
@@ -179,6 +185,71 @@ export function CognitionMixin<T extends new (...args: any[]) => RustCoreIPCClie
 			if (!response.success) {
 				throw new Error(response.error || 'Failed to enqueue message');
 			}
+
+			return { preempted: response.result.preempted };
+		}
+
+		/**
+		 * Start the next inbox message. Resumed (previously preempted) messages
+		 * carry the checkpoint they were suspended with.
+		 */
+		async inboxNext(personaId: string): Promise<InboxNext | null> {
+			const response = await this.request({
+				command: 'inbox/next',
+				persona_id: personaId,
+			});
+
+			if (!response.success) {
+				throw new Error(response.error || 'Failed to get next inbox message');
+			}
+
+			return (response.result.next as InboxNext) ?? null;
+		}
+
+		/**
+		 * Checkpoint poll: true when the in-flight message has been preempted
+		 * and should be suspended with inboxSuspend().
+		 */
+		async inboxShouldYield(personaId: string): Promise<boolean> {
+			const response = await this.request({
+				command: 'inbox/should-yield',
+				persona_id: personaId,
+			});
+
+			if (!response.success) {
+				throw new Error(response.error || 'Failed to check preemption');
+			}
+
+			return response.result.should_yield;
+		}
+
+		/**
+		 * Suspend the in-flight message with its progress so far
+		 */
+		async inboxSuspend(personaId: string, checkpoint: unknown): Promise<void> {
+			const response = await this.request({
+				command: 'inbox/suspend',
+				persona_id: personaId,
+				checkpoint,
+			});
+
+			if (!response.success) {
+				throw new Error(response.error || 'Failed to suspend message');
+			}
+		}
+
+		/**
+		 * Finish the in-flight message
+		 */
+		async inboxComplete(personaId: string): Promise<void> {
+			const response = await this.request({
+				command: 'inbox/complete',
+				persona_id: personaId,
+			});
+
+			if (!response.success) {
+				throw new Error(response.error || 'Failed to complete message');
+			}
 		}
 
 		/**
@@ -37,6 +37,29 @@ export interface DatasetListResult {
 	root: string;
 }
 
+export type DatasetExportFormat = 'chatml' | 'sharegpt' | 'parquet';
+
+/**
+ * Re-split pooled conversations into train/val with a seeded shuffle.
+ * The same dataset, ratios, and seed always yield the same partition.
+ */
+export interface DatasetExportSplit {
+	train: number;
+	val: number;
+	seed?: number;
+	/** Record field whose values are split in the same proportions */
+	stratifyBy?: string;
+}
+
+export interface DatasetExportResult {
+	name: string;
+	format: DatasetExportFormat;
+	files: { split: string; path: string; rows: number }[];
+	totalRows: number;
+	/** Records left out of the export (1-based line in the split's JSONL) */
+	skipped: { split: string; line: number; reason: string }[];
+}
+
 interface RustDatasetManifest {
 	name: string;
 	version: string;
@@ -101,6 +124,14 @@ export interface DatasetMixin {
 	datasetList(outputDir?: string): Promise<DatasetListResult>;
 
 	datasetInfo(name: string, outputDir?: string): Promise<DatasetManifest>;
+
+	datasetExport(params: {
+		name: string;
+		format: DatasetExportFormat;
+		outputDir?: string;
+		exportDir?: string;
+		split?: DatasetExportSplit;
+	}): Promise<DatasetExportResult>;
 }
 
 export function DatasetMixin<T extends new (...args: any[]) => RustCoreIPCClientBase>(Base: T) {
@@ -166,5 +197,30 @@ export function DatasetMixin<T extends new (...args: any[]) => RustCoreIPCClient
 			if (!response.success) throw new Error(response.error || 'dataset/info failed');
 			return mapManifest(response.result as RustDatasetManifest);
 		}
+
+		/**
+		 * Export a dataset's splits as ChatML, ShareGPT, or Parquet.
+		 * Malformed conversations are skipped and listed in the result.
+		 * Pass `split` to write a reproducible train/val split instead of the
+		 * dataset's own train/eval files.
+		 */
+		async datasetExport(params: {
+			name: string;
+			format: DatasetExportFormat;
+			outputDir?: string;
+			exportDir?: string;
+			split?: DatasetExportSplit;
+		}): Promise<DatasetExportResult> {
+			const response = await this.request({ command: 'dataset/export', ...params });
+			if (!response.success) throw new Error(response.error || 'dataset/export failed');
+			const r = response.result as Omit<DatasetExportResult, 'totalRows'> & { total_rows: number };
+			return {
+				name: r.name,
+				format: r.format,
+				files: r.files,
+				totalRows: r.total_rows,
+				skipped: r.skipped,
+			};
+		}
 	};
 }
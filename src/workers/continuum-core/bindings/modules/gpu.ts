@@ -6,7 +6,7 @@
  */
 
 import type { RustCoreIPCClientBase } from './base';
-import type { GpuStats as RustGpuStats, SubsystemStats as RustSubsystemStats, AllocationsByPriority as RustAllocationsByPriority, EvictableEntry as RustEvictableEntry, EvictionRegistrySnapshot as RustEvictionRegistrySnapshot } from '../../../../shared/generated/gpu';
+import type { GpuStats as RustGpuStats, SubsystemStats as RustSubsystemStats, AllocationsByPriority as RustAllocationsByPriority, EvictableEntry as RustEvictableEntry, EvictionRegistrySnapshot as RustEvictionRegistrySnapshot, GpuReservationInfo as RustGpuReservationInfo } from '../../../../shared/generated/gpu';
 
 // ============================================================================
 // Types (camelCase for TypeScript consumers)
@@ -14,7 +14,18 @@ import type { GpuStats as RustGpuStats, SubsystemStats as RustSubsystemStats, Al
 
 export interface SubsystemInfo {
 	budgetMb: number;
+	/** Includes reserved memory */
 	usedMb: number;
+	/** Reserved but not yet committed */
+	reservedMb: number;
+}
+
+export interface GpuReservationInfo {
+	id: string;
+	subsystem: string;
+	priority: string;
+	mb: number;
+	expiresInMs: number;
 }
 
 export interface AllocationsByPriorityInfo {
@@ -28,6 +39,11 @@ export interface GpuStatsResponse {
 	gpuName: string;
 	totalVramMb: number;
 	totalUsedMb: number;
+	totalReservedMb: number;
+	/** Largest single allocation that would fit right now */
+	largestFreeBlockMb: number;
+	/** 0.0 = free memory is contiguous, approaching 1.0 = shattered into small holes */
+	fragmentation: number;
 	pressure: number;
 	reserveMb: number;
 	rendering: SubsystemInfo;
@@ -37,6 +53,7 @@ export interface GpuStatsResponse {
 	highThreshold: number;
 	criticalThreshold: number;
 	allocationsByPriority: AllocationsByPriorityInfo;
+	reservations: GpuReservationInfo[];
 }
 
 export interface EvictableEntryInfo {
@@ -67,10 +84,23 @@ export interface GpuMixin {
 	gpuEvictionCandidates(): Promise<EvictableEntryInfo[]>;
 	gpuRegisterConsumer(id: string, label: string, bytes: number, priority?: string): Promise<{ registered: boolean; pressure: number }>;
 	gpuUnregisterConsumer(id: string, bytes: number): Promise<{ unregistered: boolean; pressure: number }>;
+	gpuReserve(id: string, sizeMb: number, ttlMs?: number, subsystem?: string, priority?: string): Promise<{ reserved: boolean; bytes: number; pressure: number }>;
+	gpuCommitReservation(id: string, label?: string): Promise<{ committed: boolean; bytes: number; pressure: number }>;
+	gpuCancelReservation(id: string): Promise<{ cancelled: boolean; pressure: number }>;
 }
 
 function mapSubsystem(s: RustSubsystemStats): SubsystemInfo {
-	return { budgetMb: Number(s.budget_mb), usedMb: Number(s.used_mb) };
+	return { budgetMb: Number(s.budget_mb), usedMb: Number(s.used_mb), reservedMb: Number(s.reserved_mb) };
+}
+
+function mapReservation(r: RustGpuReservationInfo): GpuReservationInfo {
+	return {
+		id: r.id,
+		subsystem: r.subsystem,
+		priority: r.priority,
+		mb: Number(r.mb),
+		expiresInMs: Number(r.expires_in_ms),
+	};
 }
 
 function mapEvictableEntry(e: RustEvictableEntry): EvictableEntryInfo {
@@ -101,6 +131,9 @@ export function GpuMixin<T extends new (...args: any[]) => RustCoreIPCClientBase
 				gpuName: r.gpu_name,
 				totalVramMb: Number(r.total_vram_mb),
 				totalUsedMb: Number(r.total_used_mb),
+				totalReservedMb: Number(r.total_reserved_mb),
+				largestFreeBlockMb: Number(r.largest_free_block_mb),
+				fragmentation: Number(r.fragmentation),
 				pressure: Number(r.pressure),
 				reserveMb: Number(r.reserve_mb),
 				rendering: mapSubsystem(r.rendering),
@@ -115,6 +148,7 @@ export function GpuMixin<T extends new (...args: any[]) => RustCoreIPCClientBase
 					background: Number(abp.background),
 					batch: Number(abp.batch),
 				},
+				reservations: r.reservations.map(mapReservation),
 			};
 		}
 
@@ -140,6 +174,9 @@ export function GpuMixin<T extends new (...args: any[]) => RustCoreIPCClientBase
 				gpuName: r.gpu_name,
 				totalVramMb: Number(r.total_vram_mb),
 				totalUsedMb: Number(r.total_used_mb),
+				totalReservedMb: Number(r.total_reserved_mb),
+				largestFreeBlockMb: Number(r.largest_free_block_mb),
+				fragmentation: Number(r.fragmentation),
 				pressure: Number(r.pressure),
 				reserveMb: Number(r.reserve_mb),
 				rendering: mapSubsystem(r.rendering),
@@ -154,6 +191,7 @@ export function GpuMixin<T extends new (...args: any[]) => RustCoreIPCClientBase
 					background: Number(abp.background),
 					batch: Number(abp.batch),
 				},
+				reservations: r.reservations.map(mapReservation),
 			};
 		}
 
@@ -199,5 +237,38 @@ export function GpuMixin<T extends new (...args: any[]) => RustCoreIPCClientBase
 			if (!response.success) throw new Error(response.error || 'Failed to unregister GPU consumer');
 			return { unregistered: true, pressure: Number((response.result as any).pressure) };
 		}
+
+		/**
+		 * Reserve VRAM before loading a model. The reservation counts against pressure
+		 * until committed or cancelled, and expires after ttlMs if neither happens
+		 * (e.g., the load crashed). Rejected under pressure like any allocation.
+		 */
+		async gpuReserve(id: string, sizeMb: number, ttlMs?: number, subsystem = 'inference', priority = 'interactive'): Promise<{ reserved: boolean; bytes: number; pressure: number }> {
+			const response = await this.request({ command: 'gpu/reserve', id, sizeMb, ttlMs, subsystem, priority });
+			if (!response.success) throw new Error(response.error || 'Failed to reserve GPU memory');
+			const r = response.result as any;
+			return { reserved: true, bytes: Number(r.bytes), pressure: Number(r.pressure) };
+		}
+
+		/**
+		 * Commit a reservation after a successful load. The memory is then tracked as a
+		 * consumer and released with gpuUnregisterConsumer(id, 0).
+		 */
+		async gpuCommitReservation(id: string, label?: string): Promise<{ committed: boolean; bytes: number; pressure: number }> {
+			const response = await this.request({ command: 'gpu/commit-reservation', id, label });
+			if (!response.success) throw new Error(response.error || 'Failed to commit GPU reservation');
+			const r = response.result as any;
+			return { committed: true, bytes: Number(r.bytes), pressure: Number(r.pressure) };
+		}
+
+		/**
+		 * Release a reservation whose load failed.
+		 */
+		async gpuCancelReservation(id: string): Promise<{ cancelled: boolean; pressure: number }> {
+			const response = await this.request({ command: 'gpu/cancel-reservation', id });
+			if (!response.success) throw new Error(response.error || 'Failed to cancel GPU reservation');
+			const r = response.result as any;
+			return { cancelled: Boolean(r.cancelled), pressure: Number(r.pressure) };
+		}
 	};
 }
@@ -3,7 +3,12 @@
  */
 
 import type { RustCoreIPCClientBase } from './base';
-import type { RagSourceRequest, RagComposeResult } from '../../../../shared/generated/rag';
+import type {
+	RagSourceRequest,
+	RagComposeResult,
+	ChunkStrategy,
+	RagChunkResult,
+} from '../../../../shared/generated/rag';
 
 // ============================================================================
 // Mixin
@@ -17,6 +22,7 @@ export interface RagMixin {
 		queryText?: string,
 		totalBudget?: number
 	): Promise<RagComposeResult>;
+	ragChunk(text: string, strategy?: ChunkStrategy): Promise<RagChunkResult>;
 }
 
 export function RagMixin<T extends new (...args: any[]) => RustCoreIPCClientBase>(Base: T) {
@@ -53,5 +59,25 @@ export function RagMixin<T extends new (...args: any[]) => RustCoreIPCClientBase
 
 			return response.result as RagComposeResult;
 		}
+
+		/**
+		 * Split a document into chunks for indexing.
+		 *
+		 * @param text - Document text
+		 * @param strategy - Chunking strategy (default: paragraph)
+		 */
+		async ragChunk(text: string, strategy?: ChunkStrategy): Promise<RagChunkResult> {
+			const response = await this.request({
+				command: 'rag/chunk',
+				text,
+				strategy: strategy ?? null,
+			});
+
+			if (!response.success) {
+				throw new Error(response.error || 'Failed to chunk document');
+			}
+
+			return response.result as RagChunkResult;
+		}
 	};
 }
@@ -19,6 +19,14 @@ export interface SearchVectorResult {
 	rankedIndices: number[];
 }
 
+export interface SearchHybridResult {
+	/** Reciprocal-rank-fusion scores, parallel to corpus */
+	scores: number[];
+	rankedIndices: number[];
+	bm25Scores: number[];
+	cosineScores: number[];
+}
+
 // ============================================================================
 // Mixin
 // ============================================================================
@@ -26,7 +34,8 @@ export interface SearchVectorResult {
 export interface SearchMixin {
 	searchList(): Promise<string[]>;
 	searchExecute(query: string, corpus: string[], algorithm?: string, params?: Record<string, unknown>): Promise<SearchExecuteResult>;
-	searchVector(queryVector: number[], corpusVectors: number[][], normalize?: boolean, threshold?: number): Promise<SearchVectorResult>;
+	searchVector(queryVector: number[], corpusVectors: number[][], normalize?: boolean, threshold?: number, topK?: number): Promise<SearchVectorResult>;
+	searchHybrid(query: string, corpus: string[], queryVector: number[], corpusVectors: number[][], k?: number, params?: Record<string, unknown>): Promise<SearchHybridResult>;
 	searchParams(algorithm: string): Promise<{ params: string[]; values: Record<string, unknown> }>;
 }
 
@@ -66,13 +75,15 @@ export function SearchMixin<T extends new (...args: any[]) => RustCoreIPCClientB
 		}
 
 		/**
-		 * Vector similarity search using cosine similarity
+		 * Vector similarity search using cosine similarity.
+		 * With topK, only the best K come back and scores align with rankedIndices.
 		 */
 		async searchVector(
 			queryVector: number[],
 			corpusVectors: number[][],
 			normalize: boolean = true,
-			threshold: number = 0.0
+			threshold: number = 0.0,
+			topK?: number
 		): Promise<SearchVectorResult> {
 			const response = await this.request({
 				command: 'search/vector',
@@ -80,6 +91,7 @@ export function SearchMixin<T extends new (...args: any[]) => RustCoreIPCClientB
 				corpusVectors,
 				normalize,
 				threshold,
+				topK: topK ?? null,
 			});
 			if (!response.success) throw new Error(response.error || 'Vector search failed');
 			return {
@@ -88,6 +100,35 @@ export function SearchMixin<T extends new (...args: any[]) => RustCoreIPCClientB
 			};
 		}
 
+		/**
+		 * Hybrid search: BM25 over corpus + cosine over corpusVectors, fused with RRF
+		 */
+		async searchHybrid(
+			query: string,
+			corpus: string[],
+			queryVector: number[],
+			corpusVectors: number[][],
+			k: number = 60,
+			params?: Record<string, unknown>
+		): Promise<SearchHybridResult> {
+			const response = await this.request({
+				command: 'search/hybrid',
+				query,
+				corpus,
+				queryVector,
+				corpusVectors,
+				k,
+				params: params ?? null,
+			});
+			if (!response.success) throw new Error(response.error || 'Hybrid search failed');
+			return {
+				scores: response.result?.scores || [],
+				rankedIndices: response.result?.rankedIndices || [],
+				bm25Scores: response.result?.bm25Scores || [],
+				cosineScores: response.result?.cosineScores || [],
+			};
+		}
+
 		/**
 		 * Get algorithm parameters and current values
 		 */
@@ -180,7 +180,7 @@ export interface SentinelMixin {
 	sentinelExecute(params: SentinelRunParams): Promise<{ success: boolean; exitCode: number; output: string; handle: string }>;
 	sentinelStatus(handle: string): Promise<SentinelStatusResult>;
 	sentinelList(): Promise<SentinelListResult>;
-	sentinelCancel(handle: string): Promise<{ handle: string; status: string }>;
+	sentinelCancel(handle: string, timeoutMs?: number): Promise<{ handle: string; status: string; forced?: boolean }>;
 	sentinelLogsList(handle: string): Promise<SentinelLogsListResult>;
 	sentinelLogsRead(handle: string, stream?: string, offset?: number, limit?: number): Promise<SentinelLogsReadResult>;
 	sentinelLogsTail(handle: string, stream?: string, lines?: number): Promise<SentinelLogsTailResult>;
@@ -321,17 +321,18 @@ export function SentinelMixin<T extends new (...args: any[]) => RustCoreIPCClien
 		/**
 		 * Cancel a running sentinel
 		 */
-		async sentinelCancel(handle: string): Promise<{ handle: string; status: string }> {
+		async sentinelCancel(handle: string, timeoutMs?: number): Promise<{ handle: string; status: string; forced?: boolean }> {
 			const response = await this.request({
 				command: 'sentinel/cancel',
 				handle,
+				...(timeoutMs !== undefined && { timeoutMs }),
 			});
 
 			if (!response.success) {
 				throw new Error(response.error || 'sentinel/cancel failed');
 			}
 
-			return response.result as { handle: string; status: string };
+			return response.result as { handle: string; status: string; forced?: boolean };
 		}
 
 		/**
@@ -115,6 +115,10 @@ pub enum ApiStyle {
     Local,
 }
 
+/// Receives incremental text from `generate_text_stream`.
+/// Return `false` to stop generation (e.g. the consumer disconnected).
+pub type TextCallback = Box<dyn FnMut(&str) -> bool + Send>;
+
 /// The universal AI provider adapter trait
 ///
 /// All AI providers implement this trait. The AIProviderModule calls
@@ -151,6 +155,39 @@ pub trait AIProviderAdapter: Send + Sync {
         request: TextGenerationRequest,
     ) -> Result<TextGenerationResponse, String>;
 
+    /// Generate text, delivering output through `on_text` as it is produced.
+    /// Returning `false` from the callback cancels generation.
+    ///
+    /// Default: adapters without incremental output run `generate_text` and
+    /// deliver the full response as a single chunk.
+    async fn generate_text_stream(
+        &self,
+        request: TextGenerationRequest,
+        mut on_text: TextCallback,
+    ) -> Result<TextGenerationResponse, String> {
+        let response = self.generate_text(request).await?;
+        if !response.text.is_empty() {
+            on_text(&response.text);
+        }
+        Ok(response)
+    }
+
+    /// Generate text for several independent requests, one result per
+    /// request in the same order. An error fails only its own entry.
+    ///
+    /// Default: requests run one after another through `generate_text`.
+    /// Adapters that can pad prompts into a single batch override this.
+    async fn generate_text_batch(
+        &self,
+        requests: Vec<TextGenerationRequest>,
+    ) -> Vec<Result<TextGenerationResponse, String>> {
+        let mut results = Vec::with_capacity(requests.len());
+        for request in requests {
+            results.push(self.generate_text(request).await);
+        }
+        results
+    }
+
     // ─── Embeddings (optional) ──────────────────────────────────────────────
 
     /// Create embeddings (optional - not all providers support this)
@@ -161,6 +198,38 @@ pub trait AIProviderAdapter: Send + Sync {
         Err(format!("{} does not support embeddings", self.name()))
     }
 
+    // ─── Tokenization ───────────────────────────────────────────────────────
+
+    /// Tokenize `text` with `model`'s tokenizer without generating.
+    /// Only local providers hold a tokenizer; `model = None` means whatever
+    /// the adapter has loaded.
+    fn tokenize(
+        &self,
+        _model: Option<&str>,
+        _text: &str,
+        _add_special_tokens: bool,
+    ) -> Result<Vec<u32>, String> {
+        Err(format!("{} does not expose a tokenizer", self.name()))
+    }
+
+    /// Inverse of `tokenize`.
+    fn detokenize(
+        &self,
+        _model: Option<&str>,
+        _tokens: &[u32],
+        _skip_special_tokens: bool,
+    ) -> Result<String, String> {
+        Err(format!("{} does not expose a tokenizer", self.name()))
+    }
+
+    // ─── Resource Management ────────────────────────────────────────────────
+
+    /// Free local resources (model weights, VRAM) unused for `idle_timeout`.
+    /// Returns true if anything was released. Remote providers hold nothing.
+    fn release_if_idle(&self, _idle_timeout: std::time::Duration) -> bool {
+        false
+    }
+
     // ─── Health & Metadata ──────────────────────────────────────────────────
 
     /// Check provider health
@@ -428,6 +428,7 @@ impl AIProviderAdapter for AnthropicAdapter {
                 Some(tool_calls)
             },
             routing: None,
+            logprobs: None,
             error: None,
         })
     }
@@ -28,7 +28,7 @@ pub mod types;
 // Re-export commonly used types
 pub use adapter::{
     AIProviderAdapter, AdapterCapabilities, AdapterConfig, AdapterRegistry, ApiStyle,
-    LoRAAdapterInfo, LoRACapabilities,
+    LoRAAdapterInfo, LoRACapabilities, TextCallback,
 };
 pub use anthropic_adapter::AnthropicAdapter;
 pub use openai_adapter::OpenAICompatibleAdapter;
@@ -36,7 +36,7 @@ pub use types::{
     ActiveAdapterRequest, ChatMessage, ContentPart, EmbeddingInput, EmbeddingRequest,
     EmbeddingResponse, FinishReason, HealthState, HealthStatus, MessageContent, ModelCapability,
     ModelInfo, NativeToolSpec, RoutingInfo, TextGenerationRequest, TextGenerationResponse,
-    ToolCall, ToolChoice, ToolInputSchema, ToolResult, UsageMetrics,
+    TokenLogprob, ToolCall, ToolChoice, ToolInputSchema, ToolResult, TopLogprob, UsageMetrics,
 };
 
 // Re-export CandleAdapter from inference module
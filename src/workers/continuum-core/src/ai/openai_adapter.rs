@@ -768,6 +768,7 @@ impl AIProviderAdapter for OpenAICompatibleAdapter {
             },
             tool_calls,
             routing: None,
+            logprobs: None,
             error: None,
         })
     }
@@ -230,6 +230,34 @@ pub struct TextGenerationRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[ts(optional)]
     pub stop_sequences: Option<Vec<String>>,
+    /// Local inference only: penalty for recently generated tokens (1.0 = off)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub repeat_penalty: Option<f32>,
+    /// Local inference only: how many trailing tokens the penalty covers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub repeat_last_n: Option<u32>,
+    /// Local inference only: conversation whose KV cache may be kept warm
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub handle_id: Option<String>,
+    /// Local inference only: clear the KV cache first (default true). Set
+    /// false with `handle_id` to reuse the cache when the prompt extends the
+    /// previous turn.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub reset_context: Option<bool>,
+    /// Local inference only: return the top-N token log probabilities for
+    /// every generated position (off by default — allocates per token)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub logprobs: Option<u32>,
+    /// Local inference only: sampling seed. A fixed seed makes the same
+    /// request reproduce the same output (random per request when unset)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional, type = "number")]
+    pub seed: Option<u64>,
 
     // Tool calling (native JSON format)
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -291,11 +319,38 @@ pub struct TextGenerationResponse {
     #[ts(optional)]
     pub routing: Option<RoutingInfo>,
 
+    /// Per-token log probabilities (when requested and supported)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub logprobs: Option<Vec<TokenLogprob>>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     #[ts(optional)]
     pub error: Option<String>,
 }
 
+/// Log probability of one generated token, with the most likely alternatives
+/// at that position
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/ai/TokenLogprob.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct TokenLogprob {
+    pub token: String,
+    pub token_id: u32,
+    pub logprob: f32,
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// One candidate token in a `TokenLogprob`'s top-N list
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/ai/TopLogprob.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct TopLogprob {
+    pub token: String,
+    pub token_id: u32,
+    pub logprob: f32,
+}
+
 /// Finish reason for generation
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../../shared/generated/ai/FinishReason.ts")]
@@ -560,6 +615,8 @@ mod tests {
         TextGenerationRequest::export(&cfg).expect("export TextGenerationRequest");
         TextGenerationResponse::export(&cfg).expect("export TextGenerationResponse");
         FinishReason::export(&cfg).expect("export FinishReason");
+        TokenLogprob::export(&cfg).expect("export TokenLogprob");
+        TopLogprob::export(&cfg).expect("export TopLogprob");
         UsageMetrics::export(&cfg).expect("export UsageMetrics");
         RoutingInfo::export(&cfg).expect("export RoutingInfo");
         HealthStatus::export(&cfg).expect("export HealthStatus");
@@ -0,0 +1,593 @@
+use crate::clog_warn;
+use crate::live::handle::Handle;
+use parking_lot::{Mutex, RwLock};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+/// Per-handle publish/subscribe with optional late-subscriber replay
+///
+/// Pattern: one broadcast channel per Handle and subscriber capacity,
+/// created on subscribe. Each subscriber picks its own live buffer size (`capacity`,
+/// defaulting to the bus's), so one slow consumer doesn't force a large
+/// buffer on everyone; falling further behind drops events and the
+/// subscriber receives `SubscriptionEvent::Lagged(n)` in their place.
+/// With `replay_depth > 0` the last N events for each handle are
+/// kept, and a new subscriber receives them before any live event — so
+/// subscribing just after an operation starts doesn't miss its first events.
+///
+/// `subscribe_all` taps every handle at once through a separate channel, so a
+/// slow observer lags on its own without stalling per-handle delivery.
+/// `subscribe_tree` does the same for one handle and its `child()` handles.
+///
+/// Sinks added with `add_sink` see every event before any subscriber does,
+/// e.g. a `FileEventSink` journal to reconstruct handles after a crash.
+pub struct EventBus<T: Clone + Send + 'static> {
+    channels: Mutex<HashMap<Handle, HandleChannel<T>>>,
+    all: broadcast::Sender<(Handle, T)>,
+    trees: Mutex<HashMap<Handle, broadcast::Sender<(Handle, T)>>>,
+    sinks: RwLock<Vec<Box<dyn EventSink<T>>>>,
+    capacity: usize,
+    replay_depth: usize,
+}
+
+/// Receives every event published on an `EventBus`, in publish order.
+/// Called on the publishing thread, so it should be quick and must not
+/// publish on the same bus.
+pub trait EventSink<T>: Send + Sync {
+    /// `timestamp_ms` is wall-clock milliseconds since the Unix epoch
+    fn record(&self, handle: Handle, timestamp_ms: u64, event: &T);
+}
+
+/// Lets the caller keep a handle on a sink it adds, e.g. to flush it
+impl<T, S: EventSink<T>> EventSink<T> for std::sync::Arc<S> {
+    fn record(&self, handle: Handle, timestamp_ms: u64, event: &T) {
+        (**self).record(handle, timestamp_ms, event);
+    }
+}
+
+/// One journaled event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry<T> {
+    pub handle: Handle,
+    pub timestamp_ms: u64,
+    pub event: T,
+}
+
+/// Journal lines queued for the writer thread; past this, events are
+/// dropped (and counted) rather than blocking publishers on the disk
+const JOURNAL_CHANNEL_CAPACITY: usize = 4096;
+
+enum JournalMessage {
+    Line(String),
+    Flush(mpsc::SyncSender<()>),
+}
+
+/// Append-only JSONL journal: one `JournalEntry` per line. `record` only
+/// queues the line; a background thread owns the file and hands each line
+/// to the OS as it arrives, so it survives the process crashing without
+/// publishers waiting on disk I/O.
+pub struct FileEventSink {
+    path: PathBuf,
+    sender: Option<mpsc::SyncSender<JournalMessage>>,
+    writer: Option<JoinHandle<()>>,
+    dropped: AtomicU64,
+}
+
+impl FileEventSink {
+    /// Open `path` for appending, creating it if needed
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open event journal {}: {e}", path.display()))?;
+
+        let (sender, receiver) = mpsc::sync_channel(JOURNAL_CHANNEL_CAPACITY);
+        let writer_path = path.clone();
+        let writer = std::thread::Builder::new()
+            .name("event-journal".into())
+            .spawn(move || {
+                let mut file = LineWriter::new(file);
+                while let Ok(message) = receiver.recv() {
+                    match message {
+                        JournalMessage::Line(line) => {
+                            if let Err(e) = writeln!(file, "{line}") {
+                                clog_warn!(
+                                    "Event journal {}: write failed: {}",
+                                    writer_path.display(),
+                                    e
+                                );
+                            }
+                        }
+                        JournalMessage::Flush(done) => {
+                            let _ = file.flush();
+                            let _ = done.send(());
+                        }
+                    }
+                }
+                // Channel closed — sink dropped
+            })
+            .map_err(|e| format!("Failed to spawn event journal thread: {e}"))?;
+
+        Ok(Self {
+            path,
+            sender: Some(sender),
+            writer: Some(writer),
+            dropped: AtomicU64::new(0),
+        })
+    }
+
+    /// Block until every event recorded so far has been written
+    pub fn flush(&self) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        let (done_tx, done_rx) = mpsc::sync_channel(1);
+        if sender.send(JournalMessage::Flush(done_tx)).is_ok() {
+            let _ = done_rx.recv();
+        }
+    }
+
+    /// Events dropped because the writer thread fell behind
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Read a journal back, oldest first. A torn last line (the process
+    /// died mid-write) is skipped; any other bad line is an error.
+    pub fn read<T: DeserializeOwned>(
+        path: impl AsRef<Path>,
+    ) -> Result<Vec<JournalEntry<T>>, String> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|e| format!("Failed to open event journal {}: {e}", path.display()))?;
+        let lines: Vec<String> = BufReader::new(file)
+            .lines()
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to read event journal {}: {e}", path.display()))?;
+
+        let mut entries = Vec::with_capacity(lines.len());
+        for (i, line) in lines.iter().enumerate() {
+            match serde_json::from_str(line) {
+                Ok(entry) => entries.push(entry),
+                Err(_) if i + 1 == lines.len() => break,
+                Err(e) => return Err(format!("{}:{}: {e}", path.display(), i + 1)),
+            }
+        }
+        Ok(entries)
+    }
+}
+
+impl<T: Serialize> EventSink<T> for FileEventSink {
+    fn record(&self, handle: Handle, timestamp_ms: u64, event: &T) {
+        let entry = JournalEntry {
+            handle,
+            timestamp_ms,
+            event,
+        };
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                clog_warn!("Event journal {}: {}", self.path.display(), e);
+                return;
+            }
+        };
+        let Some(sender) = &self.sender else {
+            return;
+        };
+        if let Err(mpsc::TrySendError::Full(_)) = sender.try_send(JournalMessage::Line(line)) {
+            // Warn on the first drop only; `dropped()` has the count
+            if self.dropped.fetch_add(1, Ordering::Relaxed) == 0 {
+                clog_warn!(
+                    "Event journal {}: writer behind, dropping events",
+                    self.path.display()
+                );
+            }
+        }
+    }
+}
+
+impl Drop for FileEventSink {
+    fn drop(&mut self) {
+        // Closing the channel lets the writer drain what's queued and exit
+        drop(self.sender.take());
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+struct HandleChannel<T> {
+    /// One channel per subscriber capacity in use
+    senders: HashMap<usize, broadcast::Sender<T>>,
+    replay: VecDeque<T>,
+}
+
+impl<T: Clone + Send + 'static> EventBus<T> {
+    /// Create a bus without replay (subscribers see only later events)
+    pub fn new(capacity: usize) -> Self {
+        Self::new_with_replay(capacity, 0)
+    }
+
+    /// Create a bus that replays the last `replay_depth` events per handle
+    /// to each new subscriber. `capacity` is the default live buffer per
+    /// subscriber.
+    pub fn new_with_replay(capacity: usize, replay_depth: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (all, _) = broadcast::channel(capacity);
+        Self {
+            channels: Mutex::new(HashMap::new()),
+            all,
+            trees: Mutex::new(HashMap::new()),
+            sinks: RwLock::new(Vec::new()),
+            capacity,
+            replay_depth,
+        }
+    }
+
+    /// Send every later event to `sink` as well (no-op until one is added)
+    pub fn add_sink(&self, sink: Box<dyn EventSink<T>>) {
+        self.sinks.write().push(sink);
+    }
+
+    /// Publish an event for a handle (non-blocking; slow subscribers lag)
+    pub fn publish(&self, handle: Handle, event: T) {
+        let mut channels = self.channels.lock();
+        // Under the channel lock, so sinks record events in delivery order
+        let sinks = self.sinks.read();
+        if !sinks.is_empty() {
+            let timestamp_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64);
+            for sink in sinks.iter() {
+                sink.record(handle, timestamp_ms, &event);
+            }
+        }
+        drop(sinks);
+
+        let channel = channels.entry(handle).or_insert_with(HandleChannel::new);
+
+        if self.replay_depth > 0 {
+            if channel.replay.len() == self.replay_depth {
+                channel.replay.pop_front();
+            }
+            channel.replay.push_back(event.clone());
+        }
+        // Skip the clone when nobody is watching everything
+        if self.all.receiver_count() > 0 {
+            let _ = self.all.send((handle, event.clone()));
+        }
+        self.publish_to_trees(handle, &event);
+        // A send only fails once a capacity's last subscriber is gone; no
+        // receivers at all is fine — replay still holds the event
+        channel
+            .senders
+            .retain(|_, sender| sender.send(event.clone()).is_ok());
+    }
+
+    /// Subscribe to one handle's events, starting with its replay buffer.
+    /// `capacity` is how many live events this subscriber may fall behind
+    /// before dropping some (None: the bus's capacity).
+    ///
+    /// Snapshot and subscribe happen under the same lock as publish, so
+    /// every event is seen exactly once: either replayed or live.
+    pub fn subscribe_handle(
+        &self,
+        handle: Handle,
+        capacity: Option<usize>,
+    ) -> HandleSubscription<T> {
+        let capacity = capacity.unwrap_or(self.capacity).max(1);
+        let mut channels = self.channels.lock();
+        let channel = channels.entry(handle).or_insert_with(HandleChannel::new);
+        let receiver = channel
+            .senders
+            .entry(capacity)
+            .or_insert_with(|| broadcast::channel(capacity).0)
+            .subscribe();
+
+        HandleSubscription {
+            replay: channel.replay.clone(),
+            receiver,
+            lagged: 0,
+        }
+    }
+
+    /// Subscribe to every event on every handle, tagged with its handle.
+    /// Live only (no replay); a subscriber that falls more than `capacity`
+    /// behind gets `Lagged` instead of blocking publishers.
+    pub fn subscribe_all(&self) -> broadcast::Receiver<(Handle, T)> {
+        self.all.subscribe()
+    }
+
+    /// Subscribe to a handle and every descendant created via
+    /// `Handle::child()`, each event tagged with the handle that emitted it.
+    /// Live only (no replay), lagging like `subscribe_all`.
+    pub fn subscribe_tree(&self, root: Handle) -> broadcast::Receiver<(Handle, T)> {
+        self.trees
+            .lock()
+            .entry(root)
+            .or_insert_with(|| broadcast::channel(self.capacity).0)
+            .subscribe()
+    }
+
+    fn publish_to_trees(&self, handle: Handle, event: &T) {
+        let mut trees = self.trees.lock();
+        if trees.is_empty() {
+            return;
+        }
+        for node in std::iter::once(handle).chain(handle.ancestors()) {
+            if let Some(sender) = trees.get(&node) {
+                if sender.send((handle, event.clone())).is_err() {
+                    // Last tree subscriber went away
+                    trees.remove(&node);
+                }
+            }
+        }
+    }
+
+    /// Drop a handle's channel and replay buffer (call when the operation
+    /// is finished). Existing subscribers see the channel close.
+    pub fn remove_handle(&self, handle: &Handle) {
+        self.channels.lock().remove(handle);
+        self.trees.lock().remove(handle);
+    }
+
+    /// Number of handles with a live channel
+    pub fn handle_count(&self) -> usize {
+        self.channels.lock().len()
+    }
+}
+
+impl<T> HandleChannel<T> {
+    fn new() -> Self {
+        Self {
+            senders: HashMap::new(),
+            replay: VecDeque::new(),
+        }
+    }
+}
+
+/// What a `HandleSubscription` delivers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionEvent<T> {
+    Event(T),
+    /// This many events were dropped because the subscriber fell more than
+    /// its capacity behind; delivery resumes with the oldest one still held
+    Lagged(u64),
+}
+
+/// Receiver for one handle: drains replayed events, then live ones
+pub struct HandleSubscription<T: Clone> {
+    replay: VecDeque<T>,
+    receiver: broadcast::Receiver<T>,
+    lagged: u64,
+}
+
+impl<T: Clone> HandleSubscription<T> {
+    /// Next event, or None once the handle is removed
+    pub async fn recv(&mut self) -> Option<SubscriptionEvent<T>> {
+        if let Some(event) = self.replay.pop_front() {
+            return Some(SubscriptionEvent::Event(event));
+        }
+        match self.receiver.recv().await {
+            Ok(event) => Some(SubscriptionEvent::Event(event)),
+            Err(broadcast::error::RecvError::Lagged(n)) => Some(self.lagged_by(n)),
+            Err(broadcast::error::RecvError::Closed) => None,
+        }
+    }
+
+    /// Non-blocking variant of `recv`: errors with `Empty` when nothing is
+    /// waiting, `Closed` once the handle is removed
+    pub fn try_recv(&mut self) -> Result<SubscriptionEvent<T>, broadcast::error::TryRecvError> {
+        if let Some(event) = self.replay.pop_front() {
+            return Ok(SubscriptionEvent::Event(event));
+        }
+        match self.receiver.try_recv() {
+            Ok(event) => Ok(SubscriptionEvent::Event(event)),
+            Err(broadcast::error::TryRecvError::Lagged(n)) => Ok(self.lagged_by(n)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Events dropped for this subscriber so far
+    pub fn lagged(&self) -> u64 {
+        self.lagged
+    }
+
+    fn lagged_by(&mut self, n: u64) -> SubscriptionEvent<T> {
+        self.lagged += n;
+        SubscriptionEvent::Lagged(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_late_subscriber_gets_replay_then_live() {
+        let bus = EventBus::new_with_replay(16, 3);
+        let handle = Handle::new();
+        let other = Handle::new();
+
+        for i in 0..5 {
+            bus.publish(handle, i);
+        }
+        bus.publish(other, 100);
+
+        let mut sub = bus.subscribe_handle(handle, None);
+        bus.publish(handle, 5);
+
+        // Last 3 replayed, then live, no duplicates and nothing from `other`
+        let mut received = Vec::new();
+        while let Ok(SubscriptionEvent::Event(event)) = sub.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(received, vec![2, 3, 4, 5]);
+
+        bus.remove_handle(&handle);
+        assert_eq!(sub.recv().await, None);
+        assert_eq!(bus.handle_count(), 1);
+    }
+
+    #[test]
+    fn test_no_replay_by_default() {
+        let bus = EventBus::new(16);
+        let handle = Handle::new();
+        bus.publish(handle, "started");
+
+        let mut sub = bus.subscribe_handle(handle, None);
+        assert!(sub.try_recv().is_err());
+        bus.publish(handle, "progress");
+        assert_eq!(
+            sub.try_recv().unwrap(),
+            SubscriptionEvent::Event("progress")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_per_subscriber_capacity_and_lag() {
+        let bus = EventBus::new(2);
+        let handle = Handle::new();
+        let mut ui = bus.subscribe_handle(handle, None);
+        let mut recorder = bus.subscribe_handle(handle, Some(16));
+
+        for i in 0..7 {
+            bus.publish(handle, i);
+        }
+
+        // The small buffer kept the newest 2 and says how many it missed
+        assert_eq!(ui.recv().await, Some(SubscriptionEvent::Lagged(5)));
+        assert_eq!(ui.recv().await, Some(SubscriptionEvent::Event(5)));
+        assert_eq!(ui.recv().await, Some(SubscriptionEvent::Event(6)));
+        assert_eq!(ui.lagged(), 5);
+
+        // The large one saw everything
+        for i in 0..7 {
+            assert_eq!(recorder.try_recv().unwrap(), SubscriptionEvent::Event(i));
+        }
+        assert_eq!(recorder.lagged(), 0);
+
+        // A capacity's channel goes away with its last subscriber
+        drop(recorder);
+        bus.publish(handle, 7);
+        assert_eq!(bus.channels.lock()[&handle].senders.len(), 1);
+    }
+
+    #[test]
+    fn test_subscribe_all_tags_handles_without_stalling() {
+        let bus = EventBus::new(2);
+        let (a, b) = (Handle::new(), Handle::new());
+
+        let mut all = bus.subscribe_all();
+        let mut sub_a = bus.subscribe_handle(a, None);
+        bus.publish(a, 1);
+        bus.publish(b, 2);
+        assert_eq!(all.try_recv().unwrap(), (a, 1));
+        assert_eq!(all.try_recv().unwrap(), (b, 2));
+        assert_eq!(sub_a.try_recv().unwrap(), SubscriptionEvent::Event(1));
+
+        // An all-subscriber that stops reading lags; per-handle keeps flowing
+        for i in 10..15 {
+            bus.publish(a, i);
+            assert_eq!(sub_a.try_recv().unwrap(), SubscriptionEvent::Event(i));
+        }
+        assert!(matches!(
+            all.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(_))
+        ));
+    }
+
+    #[test]
+    fn test_sinks_journal_every_event() {
+        struct Collect(Arc<Mutex<Vec<(Handle, &'static str)>>>);
+        impl EventSink<&'static str> for Collect {
+            fn record(&self, handle: Handle, _timestamp_ms: u64, event: &&'static str) {
+                self.0.lock().push((handle, *event));
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("events.jsonl");
+        let bus = EventBus::new(16);
+        let (a, b) = (Handle::new(), Handle::new());
+        bus.publish(a, "before");
+
+        let collected = Arc::new(Mutex::new(Vec::new()));
+        bus.add_sink(Box::new(Collect(collected.clone())));
+        let journal_sink = Arc::new(FileEventSink::open(&path).unwrap());
+        bus.add_sink(Box::new(journal_sink.clone()));
+        // Recorded with nobody subscribed
+        bus.publish(a, "connected");
+        bus.publish(b, "connected");
+        bus.publish(a, "dropped");
+        assert_eq!(
+            *collected.lock(),
+            vec![(a, "connected"), (b, "connected"), (a, "dropped")]
+        );
+        journal_sink.flush();
+        assert_eq!(journal_sink.dropped(), 0);
+
+        // A crash mid-write leaves a torn last line
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(b"{\"handle\":")
+            .unwrap();
+        let journal: Vec<JournalEntry<String>> = FileEventSink::read(&path).unwrap();
+        let for_a: Vec<&str> = journal
+            .iter()
+            .filter(|e| e.handle == a)
+            .map(|e| e.event.as_str())
+            .collect();
+        assert_eq!(journal.len(), 3);
+        assert_eq!(for_a, vec!["connected", "dropped"]);
+        assert!(journal
+            .windows(2)
+            .all(|w| w[0].timestamp_ms <= w[1].timestamp_ms));
+    }
+
+    #[test]
+    fn test_subscribe_tree_receives_descendants() {
+        let bus = EventBus::new(16);
+        let turn = Handle::new();
+        let (stt, tts) = (turn.child(), turn.child());
+        let chunk = tts.child();
+        let unrelated = Handle::new();
+
+        let mut tree = bus.subscribe_tree(turn);
+        let mut tts_tree = bus.subscribe_tree(tts);
+        bus.publish(turn, "start");
+        bus.publish(stt, "transcript");
+        bus.publish(chunk, "audio");
+        bus.publish(unrelated, "noise");
+
+        let mut received = Vec::new();
+        while let Ok(event) = tree.try_recv() {
+            received.push(event);
+        }
+        assert_eq!(
+            received,
+            vec![(turn, "start"), (stt, "transcript"), (chunk, "audio")]
+        );
+        assert_eq!(tts_tree.try_recv().unwrap(), (chunk, "audio"));
+        assert!(tts_tree.try_recv().is_err());
+
+        for h in [stt, tts, chunk] {
+            h.release();
+        }
+    }
+}
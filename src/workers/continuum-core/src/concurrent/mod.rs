@@ -3,9 +3,14 @@
 //! OOP-style traits for common operations:
 //! - PriorityQueue<T>: Generic priority-based message queue
 //! - MessageProcessor<T>: Process messages concurrently
-//! - EventBus<T>: Publish-subscribe pattern
+//! - EventBus<T>: Per-handle publish-subscribe with replay and journal sinks
+//! - ProgressTracker: Fraction-complete and smoothed ETA for long operations
+pub mod event_bus;
 pub mod message_processor;
 pub mod priority_queue;
+pub mod progress;
 
+pub use event_bus::*;
 pub use message_processor::*;
 pub use priority_queue::*;
+pub use progress::*;
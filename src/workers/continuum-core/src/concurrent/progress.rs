@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// Smoothing factor for the per-step rate (weight of the newest sample)
+const RATE_SMOOTHING: f64 = 0.3;
+
+/// Progress of a long-running operation (image/video generation, backfills)
+///
+/// `fraction` is 0.0–1.0, or None when the total amount of work is unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/runtime/ProgressUpdate.ts"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressUpdate {
+    #[ts(type = "number")]
+    pub step: u64,
+    #[ts(type = "number | null")]
+    pub total_steps: Option<u64>,
+    pub fraction: Option<f32>,
+    #[ts(type = "number")]
+    pub elapsed_ms: u64,
+    #[ts(type = "number | null")]
+    pub eta_ms: Option<u64>,
+}
+
+/// Turns step completions into ProgressUpdates with a smoothed ETA
+///
+/// Pattern: exponentially-weighted time-per-step, so one slow step (model
+/// warmup, a GC pause) nudges the ETA instead of making it jump.
+pub struct ProgressTracker {
+    total_steps: Option<u64>,
+    step: u64,
+    started: Instant,
+    last_step_at: Instant,
+    ms_per_step: Option<f64>,
+}
+
+impl ProgressTracker {
+    /// Start tracking. `total_steps = None` for indeterminate work.
+    pub fn new(total_steps: Option<u64>) -> Self {
+        Self::started_at(total_steps, Instant::now())
+    }
+
+    fn started_at(total_steps: Option<u64>, now: Instant) -> Self {
+        Self {
+            total_steps,
+            step: 0,
+            started: now,
+            last_step_at: now,
+            ms_per_step: None,
+        }
+    }
+
+    /// Record `steps` more completed steps and return the new snapshot
+    pub fn advance(&mut self, steps: u64) -> ProgressUpdate {
+        self.advance_at(steps, Instant::now())
+    }
+
+    fn advance_at(&mut self, steps: u64, now: Instant) -> ProgressUpdate {
+        if steps > 0 {
+            let sample =
+                now.duration_since(self.last_step_at).as_secs_f64() * 1000.0 / steps as f64;
+            self.ms_per_step = Some(match self.ms_per_step {
+                Some(rate) => RATE_SMOOTHING * sample + (1.0 - RATE_SMOOTHING) * rate,
+                None => sample,
+            });
+            self.step += steps;
+            self.last_step_at = now;
+        }
+        self.snapshot_at(now)
+    }
+
+    /// Current progress without recording a step
+    pub fn snapshot(&self) -> ProgressUpdate {
+        self.snapshot_at(Instant::now())
+    }
+
+    fn snapshot_at(&self, now: Instant) -> ProgressUpdate {
+        let fraction = self.total_steps.map(|total| {
+            if total == 0 {
+                1.0
+            } else {
+                (self.step as f32 / total as f32).min(1.0)
+            }
+        });
+        let eta_ms = match (self.total_steps, self.ms_per_step) {
+            (Some(total), Some(rate)) => {
+                let remaining = total.saturating_sub(self.step) as f64 * rate;
+                Some(remaining.round() as u64)
+            }
+            _ => None,
+        };
+
+        ProgressUpdate {
+            step: self.step,
+            total_steps: self.total_steps,
+            fraction,
+            elapsed_ms: duration_ms(now.duration_since(self.started)),
+            eta_ms,
+        }
+    }
+}
+
+fn duration_ms(d: Duration) -> u64 {
+    d.as_millis().min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fraction_and_smoothed_eta() {
+        let t0 = Instant::now();
+        let mut tracker = ProgressTracker::started_at(Some(10), t0);
+
+        let first = tracker.advance_at(1, t0 + Duration::from_millis(100));
+        assert_eq!(first.fraction, Some(0.1));
+        assert_eq!(first.eta_ms, Some(900));
+
+        // One slow step moves the ETA by the smoothing weight, not 10x
+        let second = tracker.advance_at(1, t0 + Duration::from_millis(1100));
+        assert_eq!(second.fraction, Some(0.2));
+        assert_eq!(second.eta_ms, Some(8 * 370));
+        assert_eq!(second.elapsed_ms, 1100);
+
+        let done = tracker.advance_at(8, t0 + Duration::from_millis(2000));
+        assert_eq!(done.fraction, Some(1.0));
+        assert_eq!(done.eta_ms, Some(0));
+    }
+
+    #[test]
+    fn test_indeterminate_progress() {
+        let mut tracker = ProgressTracker::new(None);
+        let update = tracker.advance(3);
+        assert_eq!(update.step, 3);
+        assert_eq!(update.fraction, None);
+        assert_eq!(update.eta_ms, None);
+    }
+}
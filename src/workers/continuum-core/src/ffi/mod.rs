@@ -7,16 +7,24 @@
 //! - Rust core owns all data (VoiceOrchestrator, PersonaInbox)
 //! - FFI returns opaque pointers that Node.js/Swift holds
 //! - Caller must free pointers via continuum_free()
+//! - Async work (generation) runs on a runtime owned by this module;
+//!   callbacks are invoked on the caller's thread
 //!
 //! Performance:
 //! - All FFI calls are timed and logged
 //! - Timing thresholds: >10ms = warn, >1ms = info, <1ms = debug
+use crate::gpu::memory_manager::GpuMemoryManager;
 use crate::live::{UtteranceEvent, VoiceOrchestrator, VoiceParticipant};
 use crate::logging::{init_logger, logger, TimingGuard};
+use crate::modules::ai_provider::AIProviderModule;
 use crate::persona::PersonaInbox;
-use std::ffi::{CStr, CString};
+use crate::runtime::{
+    CommandResult, MessageBus, ModuleContext, ModuleRegistry, ServiceModule, SharedCompute,
+};
+use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
 use std::ptr;
+use std::sync::{Arc, OnceLock};
 use uuid::Uuid;
 
 // ============================================================================
@@ -348,6 +356,190 @@ pub unsafe extern "C" fn continuum_inbox_free(ptr: *mut PersonaInbox) {
     }
 }
 
+// ============================================================================
+// Streaming Generation FFI
+// ============================================================================
+
+/// Per-token callback for continuum_generate_stream().
+///
+/// @param token Null-terminated UTF-8 text (valid only during the call)
+/// @param user_data The pointer passed to continuum_generate_stream()
+/// @return 0 to continue, non-zero to stop generation
+pub type ContinuumTokenCallback =
+    Option<unsafe extern "C" fn(token: *const c_char, user_data: *mut c_void) -> i32>;
+
+/// Runtime for async work behind FFI calls (created on first use)
+static FFI_RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+/// AI provider module for FFI generation: the server's registered module
+/// when one runs in this process, otherwise created on first use
+static FFI_AI_PROVIDER: tokio::sync::OnceCell<Arc<AIProviderModule>> =
+    tokio::sync::OnceCell::const_new();
+
+/// Serve FFI generation from the runtime's registered module (called from
+/// ipc/mod.rs during startup), so models it loads share one adapter
+/// registry and one VRAM budget
+pub fn set_ai_provider(module: Arc<AIProviderModule>) {
+    if FFI_AI_PROVIDER.set(module).is_err() {
+        logger().warn(
+            "ffi",
+            "generate",
+            "FFI generation already started with its own AI provider",
+        );
+    }
+}
+
+fn ffi_runtime() -> Result<&'static tokio::runtime::Runtime, String> {
+    if let Some(runtime) = FFI_RUNTIME.get() {
+        return Ok(runtime);
+    }
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("continuum-ffi")
+        .build()
+        .map_err(|e| format!("Failed to start FFI runtime: {e}"))?;
+    Ok(FFI_RUNTIME.get_or_init(|| runtime))
+}
+
+async fn ffi_ai_provider() -> Result<&'static AIProviderModule, String> {
+    FFI_AI_PROVIDER
+        .get_or_try_init(|| async {
+            // No server in this process: nothing else loads models here, so
+            // a budget of its own is the whole GPU
+            let module = Arc::new(AIProviderModule::with_gpu_manager(Arc::new(
+                GpuMemoryManager::detect(),
+            )));
+            let ctx = ModuleContext::new(
+                Arc::new(ModuleRegistry::new()),
+                Arc::new(MessageBus::new()),
+                Arc::new(SharedCompute::new()),
+                tokio::runtime::Handle::current(),
+            );
+            module.initialize(&ctx).await?;
+            Ok(module)
+        })
+        .await
+        .map(Arc::as_ref)
+}
+
+/// Generate text, calling `on_token` with each piece as it is produced
+///
+/// Generation runs on a Rust worker thread and hands text to the calling
+/// thread over a channel, so `on_token` runs on the caller's thread with no
+/// Rust locks held — it may call back into this library. Returning non-zero
+/// from `on_token` stops generation.
+///
+/// @param request_json Same JSON as the `ai/generate/stream` command
+///        (`prompt` or `messages`, plus optional `model`, `provider`, `maxTokens`, ...)
+/// @param on_token Callback invoked per generated piece of text
+/// @param user_data Passed through to on_token untouched (may be null)
+/// @param out_response_json If not null, receives the final JSON chunk
+///        (`generated_tokens`, `finishReason`, `model`, or `error`);
+///        caller must free with continuum_free_string()
+/// @return 0 when generation completes, 1 if stopped by on_token, -1 on error
+/// # Safety
+/// Caller must ensure request_json is a null-terminated C string, on_token
+/// is safe to call with user_data, and out_response_json is null or valid
+/// for writes. Must not be called from inside an async runtime.
+#[no_mangle]
+pub unsafe extern "C" fn continuum_generate_stream(
+    request_json: *const c_char,
+    on_token: ContinuumTokenCallback,
+    user_data: *mut c_void,
+    out_response_json: *mut *mut c_char,
+) -> i32 {
+    let _timer = TimingGuard::new("ffi", "generate_stream").with_threshold(10);
+
+    if !out_response_json.is_null() {
+        unsafe { *out_response_json = ptr::null_mut() };
+    }
+    let Some(on_token) = on_token else {
+        logger().error("ffi", "generate", "generate_stream: null on_token");
+        return -1;
+    };
+    if request_json.is_null() {
+        logger().error("ffi", "generate", "generate_stream: null request_json");
+        return -1;
+    }
+
+    let request_str = unsafe {
+        match CStr::from_ptr(request_json).to_str() {
+            Ok(s) => s,
+            Err(e) => {
+                logger().error("ffi", "generate", &format!("Invalid request UTF-8: {e}"));
+                return -1;
+            }
+        }
+    };
+    let params: serde_json::Value = match serde_json::from_str(request_str) {
+        Ok(p) => p,
+        Err(e) => {
+            logger().error("ffi", "generate", &format!("Invalid request JSON: {e}"));
+            return -1;
+        }
+    };
+
+    // Start generation; it runs on the FFI runtime and streams chunks back
+    let started = ffi_runtime().and_then(|runtime| {
+        runtime.block_on(async {
+            ffi_ai_provider()
+                .await?
+                .handle_command("ai/generate/stream", params)
+                .await
+        })
+    });
+    let mut chunks = match started {
+        Ok(CommandResult::Stream(rx)) => rx,
+        Ok(_) => {
+            logger().error("ffi", "generate", "ai/generate/stream did not stream");
+            return -1;
+        }
+        Err(e) => {
+            logger().error(
+                "ffi",
+                "generate",
+                &format!("Generation failed to start: {e}"),
+            );
+            return -1;
+        }
+    };
+
+    // Deliver tokens on this thread; dropping `chunks` cancels generation
+    while let Some(chunk) = chunks.blocking_recv() {
+        if chunk["done"].as_bool() == Some(true) {
+            let failed = chunk.get("error").is_some();
+            if failed {
+                logger().error(
+                    "ffi",
+                    "generate",
+                    &format!("Generation failed: {}", chunk["error"]),
+                );
+            }
+            if !out_response_json.is_null() {
+                if let Ok(c_string) = CString::new(chunk.to_string()) {
+                    unsafe { *out_response_json = c_string.into_raw() };
+                }
+            }
+            return if failed { -1 } else { 0 };
+        }
+
+        let Some(token) = chunk["token"].as_str() else {
+            continue;
+        };
+        // Interior NULs would truncate the C string; they carry no text
+        let Ok(c_token) = CString::new(token.replace('\0', "")) else {
+            continue;
+        };
+        if unsafe { on_token(c_token.as_ptr(), user_data) } != 0 {
+            logger().info("ffi", "generate", "Generation stopped by caller");
+            return 1;
+        }
+    }
+
+    logger().error("ffi", "generate", "Generation ended without a final chunk");
+    -1
+}
+
 // ============================================================================
 // Memory Management
 // ============================================================================
@@ -410,9 +602,9 @@ pub unsafe extern "C" fn continuum_get_stats(category: *const c_char) -> *mut c_
     c_string.into_raw()
 }
 
-/// Free a string returned from continuum_get_stats()
+/// Free a string returned from continuum_get_stats() or continuum_generate_stream()
 /// # Safety
-/// Caller must ensure ptr was returned from continuum_get_stats().
+/// Caller must ensure ptr was returned from one of those functions.
 #[no_mangle]
 pub unsafe extern "C" fn continuum_free_string(ptr: *mut c_char) {
     if !ptr.is_null() {
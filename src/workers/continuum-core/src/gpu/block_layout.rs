@@ -0,0 +1,129 @@
+//! BlockLayout — placement model of the usable VRAM range.
+//!
+//! Byte counters say whether there is enough free memory in total, but a
+//! model's weights need large contiguous buffers. After enough load/unload
+//! churn the free space can be split into holes that are each too small,
+//! even though they add up to plenty.
+//!
+//! Neither Metal nor CUDA exposes its allocator's free list, so the manager
+//! keeps its own first-fit model of where each tracked allocation sits in
+//! the usable range. It only sees allocations made through guards; memory
+//! accounted with `account_external` is counted but not placed.
+
+use std::collections::BTreeMap;
+
+/// First-fit block map over `[0, size)`.
+#[derive(Debug)]
+pub struct BlockLayout {
+    size: u64,
+    /// Free blocks: offset → length. Adjacent blocks are always coalesced.
+    free: BTreeMap<u64, u64>,
+}
+
+impl BlockLayout {
+    pub fn new(size: u64) -> Self {
+        let mut free = BTreeMap::new();
+        if size > 0 {
+            free.insert(0, size);
+        }
+        Self { size, free }
+    }
+
+    /// Place a block of `len` bytes at the lowest offset it fits.
+    /// Returns None if no single free block is large enough.
+    pub fn place(&mut self, len: u64) -> Option<u64> {
+        if len == 0 {
+            return Some(0);
+        }
+        let (&offset, &block) = self.free.iter().find(|(_, &block)| block >= len)?;
+        self.free.remove(&offset);
+        if block > len {
+            self.free.insert(offset + len, block - len);
+        }
+        Some(offset)
+    }
+
+    /// Return a block placed by `place`, merging it with free neighbours.
+    pub fn free(&mut self, offset: u64, len: u64) {
+        if len == 0 {
+            return;
+        }
+        let mut start = offset;
+        let mut end = offset + len;
+
+        if let Some((&prev, &prev_len)) = self.free.range(..offset).next_back() {
+            if prev + prev_len == offset {
+                self.free.remove(&prev);
+                start = prev;
+            }
+        }
+        if let Some(next_len) = self.free.remove(&end) {
+            end += next_len;
+        }
+        self.free.insert(start, end - start);
+    }
+
+    /// Size of the largest free block.
+    pub fn largest_free(&self) -> u64 {
+        self.free.values().copied().max().unwrap_or(0)
+    }
+
+    /// Total free bytes across all blocks.
+    pub fn total_free(&self) -> u64 {
+        self.free.values().sum()
+    }
+
+    /// Number of separate free blocks.
+    pub fn free_blocks(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Share of free memory unusable by a request the size of all of it:
+/// `1 - largest_free / total_free`. 0.0 = one contiguous block (or nothing
+/// free), approaching 1.0 = free space shattered into small holes.
+pub fn fragmentation_ratio(largest_free: u64, total_free: u64) -> f32 {
+    if total_free == 0 {
+        return 0.0;
+    }
+    1.0 - (largest_free.min(total_free) as f64 / total_free as f64) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_fit_and_coalescing() {
+        let mut layout = BlockLayout::new(100);
+        let a = layout.place(30).unwrap();
+        let b = layout.place(30).unwrap();
+        let c = layout.place(30).unwrap();
+        assert_eq!((a, b, c), (0, 30, 60));
+        assert_eq!(layout.largest_free(), 10);
+
+        // Hole in the middle: 40 free in total, but split 30 + 10
+        layout.free(b, 30);
+        assert_eq!(layout.total_free(), 40);
+        assert_eq!(layout.largest_free(), 30);
+        assert_eq!(layout.free_blocks(), 2);
+        assert!(layout.place(35).is_none());
+
+        // Freeing the neighbours merges everything back into one block
+        layout.free(a, 30);
+        layout.free(c, 30);
+        assert_eq!(layout.free_blocks(), 1);
+        assert_eq!(layout.largest_free(), 100);
+    }
+
+    #[test]
+    fn test_fragmentation_ratio() {
+        assert_eq!(fragmentation_ratio(0, 0), 0.0);
+        assert_eq!(fragmentation_ratio(100, 100), 0.0);
+        assert!((fragmentation_ratio(25, 100) - 0.75).abs() < 1e-6);
+    }
+}
@@ -2,8 +2,8 @@
 //!
 //! The GpuGovernor (Layer 3, TypeScript) queries this registry to understand what's
 //! loaded, how much VRAM it uses, when it was last used, and what priority it has.
-//! This enables informed eviction recommendations without the registry itself
-//! performing any eviction — that's future work requiring unload callbacks.
+//! The registry itself never evicts: `GpuMemoryManager::allocate` walks its
+//! candidates under pressure and hands them to the owners' `on_evict` hooks.
 //!
 //! ## Eviction Score
 //!
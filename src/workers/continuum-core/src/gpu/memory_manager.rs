@@ -11,14 +11,29 @@
 //!   60-80%  Warning  — log warnings, genome evicts non-critical
 //!   80-95%  High     — refuse new model loads, aggressive eviction
 //!   95%+    Critical — refuse all allocations, force evictions
+//!
+//! Reservations: a loader can `reserve()` memory before it knows the load
+//! will succeed. Reserved bytes count against pressure like an allocation,
+//! then either `commit()` into a guard or are released when the TTL runs
+//! out (a load that crashed or hung never holds VRAM forever).
+//!
+//! Fragmentation: allocations are also placed in a first-fit `BlockLayout`
+//! of the usable range, reported in `stats()`. The layout only approximates
+//! the real allocator, so by default an allocation no single block fits is
+//! still accepted (tracked unplaced). With `set_deny_fragmented(true)` it is
+//! denied as fragmented instead (and goes through eviction like any other
+//! rejection).
 
 use serde::Serialize;
-use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, Weak};
+use std::time::{Duration, Instant};
 use tokio::sync::watch;
 use ts_rs::TS;
 
-use super::eviction_registry::EvictionRegistry;
+use super::block_layout::{fragmentation_ratio, BlockLayout};
+use super::eviction_registry::{EvictableEntry, EvictionRegistry};
 use crate::{log_error, log_info};
 
 // =============================================================================
@@ -113,6 +128,17 @@ impl GpuPriority {
             Self::Batch => "batch",
         }
     }
+
+    /// Parse priority from string name (as sent by TypeScript IPC).
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "realtime" => Some(Self::Realtime),
+            "interactive" => Some(Self::Interactive),
+            "background" => Some(Self::Background),
+            "batch" => Some(Self::Batch),
+            _ => None,
+        }
+    }
 }
 
 // =============================================================================
@@ -190,6 +216,38 @@ pub const PRESSURE_CRITICAL: f32 = 0.95;
 /// Number of priority levels (Realtime, Interactive, Background, Batch).
 const PRIORITY_LEVELS: usize = 4;
 
+/// Unload hook registered with `on_evict`. Returns true if it unloaded the
+/// victim (dropping its allocation guard), false if it doesn't own it or
+/// can't unload it right now.
+pub type EvictCallback = Arc<dyn Fn(&EvictableEntry) -> bool + Send + Sync>;
+
+/// Registration from `GpuMemoryManager::evict_hook`. Unregisters the hook
+/// when dropped, releasing whatever the hook captured.
+pub struct EvictHook {
+    mgr: Weak<GpuMemoryManager>,
+    callback: EvictCallback,
+}
+
+impl Drop for EvictHook {
+    fn drop(&mut self) {
+        if let Some(mgr) = self.mgr.upgrade() {
+            mgr.remove_evict_callback(&self.callback);
+        }
+    }
+}
+
+/// Bookkeeping for an uncommitted reservation. The bytes are already
+/// allocated in the subsystem budget; `token` tells a reservation handle
+/// apart from a later one reusing the same id.
+struct ReservationState {
+    subsystem: GpuSubsystem,
+    bytes: u64,
+    priority: GpuPriority,
+    offset: Option<u64>,
+    expires_at: Instant,
+    token: u64,
+}
+
 pub struct GpuMemoryManager {
     total_vram_bytes: u64,
     gpu_name: String,
@@ -201,6 +259,15 @@ pub struct GpuMemoryManager {
     allocation_counts: [AtomicU32; PRIORITY_LEVELS],
     /// Registry of GPU consumers for eviction visibility.
     pub eviction_registry: EvictionRegistry,
+    /// Unload hooks tried, in registration order, for each eviction victim.
+    evict_callbacks: RwLock<Vec<EvictCallback>>,
+    /// Uncommitted reservations by id.
+    reservations: Mutex<HashMap<String, ReservationState>>,
+    next_reservation_token: AtomicU64,
+    /// Where each guarded allocation sits in the usable range.
+    layout: Mutex<BlockLayout>,
+    /// Deny allocations the layout can't place (off by default).
+    deny_fragmented: AtomicBool,
 }
 
 impl std::fmt::Debug for GpuMemoryManager {
@@ -268,6 +335,11 @@ impl GpuMemoryManager {
                 AtomicU32::new(0),
             ],
             eviction_registry: EvictionRegistry::new(),
+            evict_callbacks: RwLock::new(Vec::new()),
+            reservations: Mutex::new(HashMap::new()),
+            next_reservation_token: AtomicU64::new(0),
+            layout: Mutex::new(BlockLayout::new(usable)),
+            deny_fragmented: AtomicBool::new(false),
         }
     }
 
@@ -281,11 +353,59 @@ impl GpuMemoryManager {
     /// - Background: rejected at WARNING (60%) — LoRA rebuild spikes
     /// - Batch: rejected at 50% — training, yields the bus first
     ///
+    /// When the gate rejects, eviction candidates at this priority or lower
+    /// are offered to the `on_evict` hooks (highest eviction score first),
+    /// retrying after each one that gets unloaded. Fails once no candidate
+    /// is left to offer.
+    pub fn allocate(
+        self: &Arc<Self>,
+        subsystem: GpuSubsystem,
+        bytes: u64,
+        priority: GpuPriority,
+    ) -> Result<GpuAllocationGuard, GpuError> {
+        self.expire_reservations();
+
+        let mut offered: HashSet<String> = HashSet::new();
+        let mut evicted = 0;
+        loop {
+            let err = match self.try_allocate(subsystem, bytes, priority) {
+                Ok(guard) => return Ok(guard),
+                Err(err) => err,
+            };
+
+            let victim = self
+                .eviction_registry
+                .candidates()
+                .into_iter()
+                .find(|entry| entry.priority >= priority && !offered.contains(&entry.id));
+            match victim {
+                Some(victim) if self.has_evict_callbacks() => {
+                    offered.insert(victim.id.clone());
+                    if self.evict(&victim) {
+                        evicted += 1;
+                    }
+                }
+                _ => {
+                    log_error!(
+                        "gpu",
+                        "manager",
+                        "PRESSURE GATE: Rejecting — {} ({} evicted)",
+                        err,
+                        evicted
+                    );
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Single allocation attempt against the priority gate.
+    ///
     /// Concurrency: Uses optimistic-allocate-then-rollback to avoid TOCTOU races.
     /// Two threads racing to allocate cannot both succeed if either would push
     /// pressure past their gate — the post-allocation check catches the overcommit
     /// and rolls back the losing thread's allocation atomically.
-    pub fn allocate(
+    fn try_allocate(
         self: &Arc<Self>,
         subsystem: GpuSubsystem,
         bytes: u64,
@@ -306,18 +426,6 @@ impl GpuMemoryManager {
         if new_pressure >= gate {
             // Rollback the optimistic allocation
             self.subsystems[subsystem.index()].release(bytes);
-
-            log_error!(
-                "gpu",
-                "manager",
-                "PRESSURE GATE: Rejecting {:.0}MB {} allocation for {} \
-                 (pressure={:.0}% >= {:.0}% gate)",
-                mb,
-                priority.name(),
-                subsystem.name(),
-                new_pressure * 100.0,
-                gate * 100.0
-            );
             return Err(GpuError::PressureGate {
                 subsystem: subsystem.name(),
                 priority,
@@ -327,6 +435,27 @@ impl GpuMemoryManager {
             });
         }
 
+        // Place the block. Free memory that only exists as scattered holes
+        // can't hold it; unless denial is opted into (and always with too
+        // little free memory overall, possible only through manual
+        // accounting) it's tracked unplaced.
+        let offset = self.lock_layout().place(bytes);
+        if offset.is_none() && self.deny_fragmented.load(Ordering::Relaxed) {
+            let usable = self.total_vram_bytes.saturating_sub(self.reserve_bytes);
+            let total_used: u64 = self.subsystems.iter().map(|s| s.used()).sum();
+            let free_before = usable.saturating_sub(total_used.saturating_sub(bytes));
+            if free_before >= bytes {
+                self.subsystems[subsystem.index()].release(bytes);
+                return Err(GpuError::Fragmented {
+                    subsystem: subsystem.name(),
+                    requested_mb: mb,
+                    free_mb: free_before as f64 / (1024.0 * 1024.0),
+                    largest_free_block_mb: self.largest_free_block_bytes() as f64
+                        / (1024.0 * 1024.0),
+                });
+            }
+        }
+
         // Allocation accepted — increment priority counter
         self.allocation_counts[priority.index()].fetch_add(1, Ordering::Relaxed);
 
@@ -360,10 +489,224 @@ impl GpuMemoryManager {
             subsystem,
             bytes,
             priority,
+            offset,
             released: false,
         })
     }
 
+    // ── Eviction ────────────────────────────────────────────────────────
+
+    /// Register an unload hook for eviction under allocation pressure.
+    ///
+    /// Owners of GPU memory (inference, TTS, ...) register one hook each and
+    /// decline victims they don't own. A hook that returns true must have
+    /// released the victim's allocation; the manager then drops its registry
+    /// entry. Hooks run on the allocating thread, so they must not block on
+    /// locks that thread may hold — try-lock and decline instead.
+    pub fn on_evict(&self, callback: impl Fn(&EvictableEntry) -> bool + Send + Sync + 'static) {
+        if let Ok(mut callbacks) = self.evict_callbacks.write() {
+            callbacks.push(Arc::new(callback));
+        }
+    }
+
+    /// `on_evict`, but the hook only lives as long as the returned guard.
+    /// Owners that can go away (or re-register) hold it instead of leaking
+    /// a hook that keeps their state alive.
+    pub fn evict_hook(
+        self: &Arc<Self>,
+        callback: impl Fn(&EvictableEntry) -> bool + Send + Sync + 'static,
+    ) -> EvictHook {
+        let callback: EvictCallback = Arc::new(callback);
+        if let Ok(mut callbacks) = self.evict_callbacks.write() {
+            callbacks.push(Arc::clone(&callback));
+        }
+        EvictHook {
+            mgr: Arc::downgrade(self),
+            callback,
+        }
+    }
+
+    fn remove_evict_callback(&self, callback: &EvictCallback) {
+        if let Ok(mut callbacks) = self.evict_callbacks.write() {
+            callbacks.retain(|registered| !Arc::ptr_eq(registered, callback));
+        }
+    }
+
+    fn has_evict_callbacks(&self) -> bool {
+        self.evict_callbacks
+            .read()
+            .map(|callbacks| !callbacks.is_empty())
+            .unwrap_or(false)
+    }
+
+    /// Offer a victim to each hook until one unloads it.
+    fn evict(&self, victim: &EvictableEntry) -> bool {
+        // Clone out so hooks run without holding the lock
+        let callbacks = match self.evict_callbacks.read() {
+            Ok(callbacks) => callbacks.clone(),
+            Err(_) => return false,
+        };
+        if !callbacks.iter().any(|callback| callback(victim)) {
+            return false;
+        }
+        self.eviction_registry.unregister(&victim.id);
+        log_info!(
+            "gpu",
+            "manager",
+            "GPU: Evicted {} ({:.0}MB {}) under pressure (pressure={:.0}%)",
+            victim.id,
+            victim.bytes as f64 / (1024.0 * 1024.0),
+            victim.priority.name(),
+            self.pressure() * 100.0
+        );
+        true
+    }
+
+    // ── Reservations ────────────────────────────────────────────────────
+
+    /// Reserve VRAM ahead of a load that may fail.
+    ///
+    /// Goes through the same priority gate (and eviction) as `allocate()`,
+    /// and the bytes count against pressure from now on. The returned
+    /// handle either `commit()`s into a normal allocation guard once the
+    /// load succeeds, or gives the memory back when dropped. If neither
+    /// happens within `ttl`, the reservation expires and the memory is
+    /// released anyway. Reserving an id that is already reserved replaces
+    /// the old reservation.
+    pub fn reserve(
+        self: &Arc<Self>,
+        id: &str,
+        subsystem: GpuSubsystem,
+        bytes: u64,
+        priority: GpuPriority,
+        ttl: Duration,
+    ) -> Result<GpuReservation, GpuError> {
+        if let Some(previous) = self.take_reservation(id, None) {
+            self.release_reservation(&previous);
+        }
+
+        // Allocate, then keep the bytes accounted after the guard goes away
+        let mut guard = self.allocate(subsystem, bytes, priority)?;
+        guard.released = true;
+
+        let token = self.next_reservation_token.fetch_add(1, Ordering::Relaxed);
+        self.lock_reservations().insert(
+            id.to_string(),
+            ReservationState {
+                subsystem,
+                bytes,
+                priority,
+                offset: guard.offset,
+                expires_at: Instant::now() + ttl,
+                token,
+            },
+        );
+        log_info!(
+            "gpu",
+            "manager",
+            "GPU: Reserved {:.0}MB for {} as '{}' (ttl={}ms)",
+            bytes as f64 / (1024.0 * 1024.0),
+            subsystem.name(),
+            id,
+            ttl.as_millis()
+        );
+
+        Ok(GpuReservation {
+            manager: Arc::clone(self),
+            id: id.to_string(),
+            bytes,
+            token,
+            done: false,
+        })
+    }
+
+    /// Release every reservation whose TTL has passed. Returns how many
+    /// expired. Runs on every `allocate()` and `stats()`.
+    pub fn expire_reservations(&self) -> usize {
+        let now = Instant::now();
+        let expired: Vec<(String, ReservationState)> = {
+            let mut reservations = self.lock_reservations();
+            let ids: Vec<String> = reservations
+                .iter()
+                .filter(|(_, r)| r.expires_at <= now)
+                .map(|(id, _)| id.clone())
+                .collect();
+            ids.into_iter()
+                .filter_map(|id| reservations.remove(&id).map(|r| (id, r)))
+                .collect()
+        };
+        for (id, reservation) in &expired {
+            log_info!(
+                "gpu",
+                "manager",
+                "GPU: Reservation '{}' expired uncommitted",
+                id
+            );
+            self.release_reservation(reservation);
+        }
+        expired.len()
+    }
+
+    /// Number of uncommitted, unexpired reservations.
+    pub fn reservation_count(&self) -> usize {
+        self.lock_reservations().len()
+    }
+
+    /// Remove a reservation, only if it still belongs to `token` (when given).
+    fn take_reservation(&self, id: &str, token: Option<u64>) -> Option<ReservationState> {
+        let mut reservations = self.lock_reservations();
+        match reservations.get(id) {
+            Some(r) if token.map_or(true, |t| t == r.token) => reservations.remove(id),
+            _ => None,
+        }
+    }
+
+    fn release_reservation(&self, reservation: &ReservationState) {
+        self.allocation_counts[reservation.priority.index()].fetch_sub(1, Ordering::Relaxed);
+        self.free_block(reservation.offset, reservation.bytes);
+        self.release(reservation.subsystem, reservation.bytes);
+    }
+
+    // ── Block layout ────────────────────────────────────────────────────
+
+    /// Free bytes by accounting: usable VRAM minus everything allocated,
+    /// reserved, or accounted externally.
+    fn free_bytes(&self) -> u64 {
+        let usable = self.total_vram_bytes.saturating_sub(self.reserve_bytes);
+        let total_used: u64 = self.subsystems.iter().map(|s| s.used()).sum();
+        usable.saturating_sub(total_used)
+    }
+
+    /// Deny allocations that fit in free memory overall but in no single
+    /// free block of the layout, instead of tracking them unplaced.
+    pub fn set_deny_fragmented(&self, deny: bool) {
+        self.deny_fragmented.store(deny, Ordering::Relaxed);
+    }
+
+    /// Largest single allocation that could be placed right now.
+    pub fn largest_free_block_bytes(&self) -> u64 {
+        self.lock_layout().largest_free().min(self.free_bytes())
+    }
+
+    fn free_block(&self, offset: Option<u64>, bytes: u64) {
+        if let Some(offset) = offset {
+            self.lock_layout().free(offset, bytes);
+        }
+    }
+
+    fn lock_layout(&self) -> MutexGuard<'_, BlockLayout> {
+        self.layout
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_reservations(&self) -> MutexGuard<'_, HashMap<String, ReservationState>> {
+        // State is plain data; a panic mid-update can't leave it inconsistent
+        self.reservations
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
     /// Account for external memory usage (e.g., training subprocess).
     /// Unlike `allocate()`, this doesn't check pressure gates or return a guard.
     /// The caller MUST call `release()` when the external process finishes.
@@ -481,31 +824,66 @@ impl GpuMemoryManager {
                 AtomicU32::new(0),
             ],
             eviction_registry: EvictionRegistry::new(),
+            evict_callbacks: RwLock::new(Vec::new()),
+            reservations: Mutex::new(HashMap::new()),
+            next_reservation_token: AtomicU64::new(0),
+            layout: Mutex::new(BlockLayout::new(
+                total_vram_bytes.saturating_sub(reserve_bytes),
+            )),
+            deny_fragmented: AtomicBool::new(false),
         }
     }
 
     /// Full stats snapshot for IPC.
+    ///
+    /// `used_mb` includes reserved memory; `reserved_mb` is the part of it
+    /// that is reserved but not yet committed.
     pub fn stats(&self) -> GpuStats {
+        self.expire_reservations();
+
         let mb = |b: u64| b as f32 / (1024.0 * 1024.0);
         let total_used: u64 = self.subsystems.iter().map(|s| s.used()).sum();
+        let free = self.free_bytes();
+        let largest_free_block = self.largest_free_block_bytes();
+
+        let now = Instant::now();
+        let mut reserved_bytes = [0u64; 3];
+        let mut reservations: Vec<GpuReservationInfo> = self
+            .lock_reservations()
+            .iter()
+            .map(|(id, r)| {
+                reserved_bytes[r.subsystem.index()] += r.bytes;
+                GpuReservationInfo {
+                    id: id.clone(),
+                    subsystem: r.subsystem.name().to_string(),
+                    priority: r.priority,
+                    mb: mb(r.bytes),
+                    expires_in_ms: r.expires_at.saturating_duration_since(now).as_millis() as u64,
+                }
+            })
+            .collect();
+        reservations.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let subsystem_stats = |subsystem: GpuSubsystem| {
+            let i = subsystem.index();
+            SubsystemStats {
+                budget_mb: mb(self.subsystems[i].budget()),
+                used_mb: mb(self.subsystems[i].used()),
+                reserved_mb: mb(reserved_bytes[i]),
+            }
+        };
 
         GpuStats {
             gpu_name: self.gpu_name.clone(),
             total_vram_mb: mb(self.total_vram_bytes),
             total_used_mb: mb(total_used),
+            total_reserved_mb: mb(reserved_bytes.iter().sum()),
+            largest_free_block_mb: mb(largest_free_block),
+            fragmentation: fragmentation_ratio(largest_free_block, free),
             pressure: self.pressure(),
-            rendering: SubsystemStats {
-                budget_mb: mb(self.subsystems[0].budget()),
-                used_mb: mb(self.subsystems[0].used()),
-            },
-            inference: SubsystemStats {
-                budget_mb: mb(self.subsystems[1].budget()),
-                used_mb: mb(self.subsystems[1].used()),
-            },
-            tts: SubsystemStats {
-                budget_mb: mb(self.subsystems[2].budget()),
-                used_mb: mb(self.subsystems[2].used()),
-            },
+            rendering: subsystem_stats(GpuSubsystem::Rendering),
+            inference: subsystem_stats(GpuSubsystem::Inference),
+            tts: subsystem_stats(GpuSubsystem::Tts),
             reserve_mb: mb(self.reserve_bytes),
             warning_threshold: PRESSURE_WARNING,
             high_threshold: PRESSURE_HIGH,
@@ -519,6 +897,7 @@ impl GpuMemoryManager {
                     .load(Ordering::Relaxed),
                 batch: self.allocation_counts[GpuPriority::Batch.index()].load(Ordering::Relaxed),
             },
+            reservations,
         }
     }
 
@@ -540,6 +919,8 @@ pub struct GpuAllocationGuard {
     subsystem: GpuSubsystem,
     bytes: u64,
     priority: GpuPriority,
+    /// Position in the block layout (None if tracked unplaced)
+    offset: Option<u64>,
     released: bool,
 }
 
@@ -549,6 +930,7 @@ impl std::fmt::Debug for GpuAllocationGuard {
             .field("subsystem", &self.subsystem)
             .field("priority", &self.priority)
             .field("bytes", &self.bytes)
+            .field("offset", &self.offset)
             .field("released", &self.released)
             .finish()
     }
@@ -580,6 +962,7 @@ impl GpuAllocationGuard {
     /// Internal release: decrement allocation counter + release bytes.
     fn do_release(&mut self) {
         self.manager.allocation_counts[self.priority.index()].fetch_sub(1, Ordering::Relaxed);
+        self.manager.free_block(self.offset, self.bytes);
         self.manager.release(self.subsystem, self.bytes);
         self.released = true;
     }
@@ -593,6 +976,95 @@ impl Drop for GpuAllocationGuard {
     }
 }
 
+// =============================================================================
+// RESERVATION HANDLE
+// =============================================================================
+
+/// Handle for memory reserved with `GpuMemoryManager::reserve()`.
+/// Dropping it uncommitted cancels the reservation.
+pub struct GpuReservation {
+    manager: Arc<GpuMemoryManager>,
+    id: String,
+    bytes: u64,
+    token: u64,
+    done: bool,
+}
+
+impl std::fmt::Debug for GpuReservation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuReservation")
+            .field("id", &self.id)
+            .field("bytes", &self.bytes)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl GpuReservation {
+    /// Reservation id.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Bytes reserved.
+    pub fn bytes(&self) -> u64 {
+        self.bytes
+    }
+
+    /// True once the TTL has run out and the manager released the memory
+    /// (see `GpuMemoryManager::expire_reservations`).
+    pub fn is_expired(&self) -> bool {
+        self.manager.expire_reservations();
+        !self
+            .manager
+            .lock_reservations()
+            .get(&self.id)
+            .is_some_and(|r| r.token == self.token)
+    }
+
+    /// Turn the reservation into a real allocation. Fails if it already
+    /// expired (the memory was released and must be allocated again).
+    pub fn commit(mut self) -> Result<GpuAllocationGuard, GpuError> {
+        self.done = true;
+        let reservation = self
+            .manager
+            .take_reservation(&self.id, Some(self.token))
+            .ok_or_else(|| GpuError::ReservationExpired {
+                id: self.id.clone(),
+            })?;
+        log_info!(
+            "gpu",
+            "manager",
+            "GPU: Committed reservation '{}' ({:.0}MB {})",
+            self.id,
+            reservation.bytes as f64 / (1024.0 * 1024.0),
+            reservation.subsystem.name()
+        );
+        Ok(GpuAllocationGuard {
+            manager: Arc::clone(&self.manager),
+            subsystem: reservation.subsystem,
+            bytes: reservation.bytes,
+            priority: reservation.priority,
+            offset: reservation.offset,
+            released: false,
+        })
+    }
+
+    /// Give the memory back now (same as dropping the handle).
+    pub fn cancel(self) {}
+}
+
+impl Drop for GpuReservation {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        if let Some(reservation) = self.manager.take_reservation(&self.id, Some(self.token)) {
+            self.manager.release_reservation(&reservation);
+        }
+    }
+}
+
 // =============================================================================
 // ERROR TYPE
 // =============================================================================
@@ -608,6 +1080,16 @@ pub enum GpuError {
         pressure: f32,
         gate: f32,
     },
+    /// The reservation's TTL ran out before it was committed.
+    ReservationExpired { id: String },
+    /// Enough memory is free in total, but no single free block is large
+    /// enough to hold the allocation. Only with `set_deny_fragmented(true)`.
+    Fragmented {
+        subsystem: &'static str,
+        requested_mb: f64,
+        free_mb: f64,
+        largest_free_block_mb: f64,
+    },
 }
 
 impl std::fmt::Display for GpuError {
@@ -631,6 +1113,22 @@ impl std::fmt::Display for GpuError {
                     subsystem
                 )
             }
+            Self::ReservationExpired { id } => {
+                write!(f, "GPU reservation '{}' expired before commit", id)
+            }
+            Self::Fragmented {
+                subsystem,
+                requested_mb,
+                free_mb,
+                largest_free_block_mb,
+            } => {
+                write!(
+                    f,
+                    "GPU memory fragmented: cannot allocate {:.0}MB for {} \
+                     ({:.0}MB free, largest free block {:.0}MB)",
+                    requested_mb, subsystem, free_mb, largest_free_block_mb
+                )
+            }
         }
     }
 }
@@ -647,8 +1145,28 @@ impl std::error::Error for GpuError {}
 pub struct SubsystemStats {
     #[ts(type = "number")]
     pub budget_mb: f32,
+    /// Includes reserved memory
     #[ts(type = "number")]
     pub used_mb: f32,
+    /// Reserved but not yet committed
+    #[ts(type = "number")]
+    pub reserved_mb: f32,
+}
+
+/// An uncommitted reservation, as listed in `GpuStats`.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/gpu/GpuReservationInfo.ts"
+)]
+pub struct GpuReservationInfo {
+    pub id: String,
+    pub subsystem: String,
+    pub priority: GpuPriority,
+    #[ts(type = "number")]
+    pub mb: f32,
+    #[ts(type = "number")]
+    pub expires_in_ms: u64,
 }
 
 /// Live allocation counts per priority level.
@@ -675,8 +1193,17 @@ pub struct GpuStats {
     pub gpu_name: String,
     #[ts(type = "number")]
     pub total_vram_mb: f32,
+    /// Includes reserved memory
     #[ts(type = "number")]
     pub total_used_mb: f32,
+    /// Reserved but not yet committed
+    #[ts(type = "number")]
+    pub total_reserved_mb: f32,
+    /// Largest single allocation that would fit right now
+    #[ts(type = "number")]
+    pub largest_free_block_mb: f32,
+    /// 1 - largest_free_block / total_free: 0.0 = contiguous, →1.0 = shattered
+    pub fragmentation: f32,
     pub pressure: f32,
     pub rendering: SubsystemStats,
     pub inference: SubsystemStats,
@@ -691,6 +1218,8 @@ pub struct GpuStats {
     pub critical_threshold: f32,
     /// Live allocation counts per priority level
     pub allocations_by_priority: AllocationsByPriority,
+    /// Uncommitted reservations, by id
+    pub reservations: Vec<GpuReservationInfo>,
 }
 
 // =============================================================================
@@ -857,6 +1386,11 @@ mod tests {
                 AtomicU32::new(0),
             ],
             eviction_registry: EvictionRegistry::new(),
+            evict_callbacks: RwLock::new(Vec::new()),
+            reservations: Mutex::new(HashMap::new()),
+            next_reservation_token: AtomicU64::new(0),
+            layout: Mutex::new(BlockLayout::new(usable)),
+            deny_fragmented: AtomicBool::new(false),
         })
     }
 
@@ -1186,6 +1720,287 @@ mod tests {
         }
     }
 
+    // ── Eviction callback tests ───────────────────────────────────────
+
+    #[test]
+    fn test_eviction_unloads_victim_and_retries() {
+        use super::super::eviction_registry::make_entry;
+        use std::sync::Mutex;
+
+        let mgr = test_manager(1024);
+        let usable = 1024_u64 * 1024 * 1024 - (1024_u64 * 1024 * 1024 * 5 / 100);
+
+        // An idle model holds 70%
+        let old_bytes = (usable as f64 * 0.70) as u64;
+        let old_guard = mgr
+            .allocate(GpuSubsystem::Inference, old_bytes, GpuPriority::Interactive)
+            .unwrap();
+        let loaded = Arc::new(Mutex::new(Some(old_guard)));
+        mgr.eviction_registry.register(make_entry(
+            "candle:model:old",
+            "Old model",
+            GpuPriority::Interactive,
+            old_bytes,
+        ));
+
+        // Without a hook, another 20% at Interactive (gate 80%) is refused
+        let new_bytes = (usable as f64 * 0.20) as u64;
+        assert!(mgr
+            .allocate(GpuSubsystem::Inference, new_bytes, GpuPriority::Interactive)
+            .is_err());
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        {
+            let seen = seen.clone();
+            mgr.on_evict(move |victim| {
+                seen.lock().unwrap().push(victim.id.clone());
+                false // Some other owner's hook
+            });
+        }
+        {
+            let loaded = loaded.clone();
+            mgr.on_evict(move |victim| {
+                victim.id == "candle:model:old" && loaded.lock().unwrap().take().is_some()
+            });
+        }
+
+        let guard = mgr
+            .allocate(GpuSubsystem::Inference, new_bytes, GpuPriority::Interactive)
+            .expect("eviction should free room");
+        assert_eq!(guard.bytes(), new_bytes);
+        assert_eq!(*seen.lock().unwrap(), vec!["candle:model:old"]);
+        assert!(loaded.lock().unwrap().is_none());
+        assert!(mgr.eviction_registry.is_empty());
+    }
+
+    #[test]
+    fn test_eviction_never_takes_higher_priority_victims() {
+        use super::super::eviction_registry::make_entry;
+
+        let mgr = test_manager(1024);
+        let usable = 1024_u64 * 1024 * 1024 - (1024_u64 * 1024 * 1024 * 5 / 100);
+
+        let bytes = (usable as f64 * 0.55) as u64;
+        let _interactive = mgr
+            .allocate(GpuSubsystem::Inference, bytes, GpuPriority::Interactive)
+            .unwrap();
+        mgr.eviction_registry.register(make_entry(
+            "candle:model:chat",
+            "Chat model",
+            GpuPriority::Interactive,
+            bytes,
+        ));
+        mgr.on_evict(|_| panic!("Batch work must not evict an Interactive model"));
+
+        // Batch (gate 50%) is refused rather than evicting
+        assert!(mgr
+            .allocate(GpuSubsystem::Inference, 1024 * 1024, GpuPriority::Batch)
+            .is_err());
+        assert_eq!(mgr.eviction_registry.len(), 1);
+    }
+
+    #[test]
+    fn test_evict_hook_unregisters_on_drop() {
+        let mgr = test_manager(1024);
+        let state = Arc::new(());
+        let hook = {
+            let state = state.clone();
+            mgr.evict_hook(move |_| {
+                let _ = &state;
+                false
+            })
+        };
+        assert!(mgr.has_evict_callbacks());
+        assert_eq!(Arc::strong_count(&state), 2);
+
+        drop(hook);
+        assert!(!mgr.has_evict_callbacks());
+        assert_eq!(Arc::strong_count(&state), 1);
+    }
+
+    // ── Reservation tests ─────────────────────────────────────────────
+
+    #[test]
+    fn test_reservation_counts_against_pressure_and_commits() {
+        let mgr = test_manager(1024);
+        let usable = 1024_u64 * 1024 * 1024 - (1024_u64 * 1024 * 1024 * 5 / 100);
+        let bytes = (usable as f64 * 0.5) as u64;
+
+        let reservation = mgr
+            .reserve(
+                "model:llama",
+                GpuSubsystem::Inference,
+                bytes,
+                GpuPriority::Interactive,
+                Duration::from_secs(60),
+            )
+            .unwrap();
+        assert!((mgr.pressure() - 0.5).abs() < 0.01);
+
+        let stats = mgr.stats();
+        assert!((stats.inference.reserved_mb - stats.inference.used_mb).abs() < 0.01);
+        assert_eq!(stats.reservations.len(), 1);
+        assert_eq!(stats.reservations[0].id, "model:llama");
+
+        // Reserved memory blocks a second allocation that would pass the gate
+        assert!(mgr
+            .allocate(GpuSubsystem::Inference, bytes, GpuPriority::Interactive)
+            .is_err());
+
+        let guard = reservation.commit().unwrap();
+        assert_eq!(guard.bytes(), bytes);
+        let stats = mgr.stats();
+        assert_eq!(stats.inference.reserved_mb, 0.0);
+        assert!(stats.reservations.is_empty());
+        assert_eq!(mgr.allocation_count(GpuPriority::Interactive), 1);
+
+        drop(guard);
+        assert_eq!(mgr.pressure(), 0.0);
+        assert_eq!(mgr.allocation_count(GpuPriority::Interactive), 0);
+    }
+
+    #[test]
+    fn test_reservation_expires_and_cancels() {
+        let mgr = test_manager(1024);
+        let mb100 = 100 * 1024 * 1024;
+
+        let expired = mgr
+            .reserve(
+                "model:a",
+                GpuSubsystem::Inference,
+                mb100,
+                GpuPriority::Interactive,
+                Duration::ZERO,
+            )
+            .unwrap();
+        assert_eq!(mgr.expire_reservations(), 1);
+        assert_eq!(mgr.pressure(), 0.0);
+        assert_eq!(mgr.allocation_count(GpuPriority::Interactive), 0);
+        assert!(matches!(
+            expired.commit(),
+            Err(GpuError::ReservationExpired { .. })
+        ));
+        assert_eq!(mgr.pressure(), 0.0);
+
+        // Dropping an uncommitted reservation gives the memory back
+        let cancelled = mgr
+            .reserve(
+                "model:b",
+                GpuSubsystem::Tts,
+                mb100,
+                GpuPriority::Interactive,
+                Duration::from_secs(60),
+            )
+            .unwrap();
+        assert!(mgr.pressure() > 0.0);
+        cancelled.cancel();
+        assert_eq!(mgr.pressure(), 0.0);
+        assert_eq!(mgr.reservation_count(), 0);
+
+        // Re-reserving an id replaces the old reservation; the stale
+        // handle can no longer commit or release the new one
+        let stale = mgr
+            .reserve(
+                "model:c",
+                GpuSubsystem::Inference,
+                mb100,
+                GpuPriority::Interactive,
+                Duration::from_secs(60),
+            )
+            .unwrap();
+        let fresh = mgr
+            .reserve(
+                "model:c",
+                GpuSubsystem::Inference,
+                mb100,
+                GpuPriority::Interactive,
+                Duration::from_secs(60),
+            )
+            .unwrap();
+        drop(stale);
+        assert_eq!(mgr.reservation_count(), 1);
+        let _guard = fresh.commit().unwrap();
+        assert!((mgr.stats().inference.used_mb - 100.0).abs() < 0.01);
+    }
+
+    // ── Fragmentation tests ───────────────────────────────────────────
+
+    #[test]
+    fn test_fragmentation_stress() {
+        use super::super::eviction_registry::make_entry;
+
+        let mgr = test_manager(1024);
+        let usable = 1024_u64 * 1024 * 1024 - (1024_u64 * 1024 * 1024 * 5 / 100);
+        let chunk = usable / 25; // 4%
+
+        // Load/unload churn: 20 models side by side, then every other one
+        // unloaded — 60% free, but only as 4% holes plus the tail
+        let mut guards: Vec<Option<GpuAllocationGuard>> = (0..20)
+            .map(|_| {
+                Some(
+                    mgr.allocate(GpuSubsystem::Inference, chunk, GpuPriority::Realtime)
+                        .unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(mgr.stats().fragmentation, 0.0);
+        for guard in guards.iter_mut().skip(1).step_by(2) {
+            guard.take();
+        }
+
+        let stats = mgr.stats();
+        assert!((stats.pressure - 0.40).abs() < 0.01);
+        assert!((stats.largest_free_block_mb - 0.24 * usable as f32 / 1048576.0).abs() < 1.0);
+        assert!(
+            stats.fragmentation > 0.55,
+            "fragmentation {:.2} after churn",
+            stats.fragmentation
+        );
+
+        // 30% passes the pressure gate but fits in no single block. Only
+        // reported by default; denied once opted in.
+        let big = usable * 30 / 100;
+        let unplaced = mgr
+            .allocate(GpuSubsystem::Inference, big, GpuPriority::Realtime)
+            .expect("fragmentation alone doesn't deny by default");
+        drop(unplaced);
+        mgr.set_deny_fragmented(true);
+        match mgr.allocate(GpuSubsystem::Inference, big, GpuPriority::Realtime) {
+            Err(GpuError::Fragmented {
+                largest_free_block_mb,
+                free_mb,
+                ..
+            }) => assert!(largest_free_block_mb < free_mb),
+            other => panic!("expected fragmentation denial, got {:?}", other),
+        }
+        assert!((mgr.pressure() - 0.40).abs() < 0.01);
+
+        // Evicting the models between the holes frees a contiguous range
+        let guards = Arc::new(Mutex::new(guards));
+        for (i, guard) in guards.lock().unwrap().iter().enumerate() {
+            if guard.is_some() {
+                mgr.eviction_registry.register(make_entry(
+                    &format!("model:{i}"),
+                    "Churned model",
+                    GpuPriority::Interactive,
+                    chunk,
+                ));
+            }
+        }
+        {
+            let guards = guards.clone();
+            mgr.on_evict(move |victim| {
+                let i: usize = victim.id["model:".len()..].parse().unwrap();
+                guards.lock().unwrap()[i].take().is_some()
+            });
+        }
+        let guard = mgr
+            .allocate(GpuSubsystem::Inference, big, GpuPriority::Interactive)
+            .expect("eviction should defragment enough room");
+        assert_eq!(guard.bytes(), big);
+        assert!(mgr.eviction_registry.len() < 10);
+    }
+
     // ── Allocation counter tests ──────────────────────────────────────
 
     #[test]
@@ -1428,4 +2243,10 @@ mod tests {
         let cfg = ts_rs::Config::default();
         AllocationsByPriority::export_all(&cfg).unwrap();
     }
+
+    #[test]
+    fn export_bindings_gpu_reservation_info() {
+        let cfg = ts_rs::Config::default();
+        GpuReservationInfo::export_all(&cfg).unwrap();
+    }
 }
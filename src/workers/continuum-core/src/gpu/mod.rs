@@ -7,7 +7,9 @@
 //!
 //! GpuMemoryManager detects real VRAM at startup (Metal/CUDA), enforces
 //! per-subsystem budgets, and provides an RAII allocation guard pattern.
+//! A first-fit block layout of the usable range tracks fragmentation.
 
+pub mod block_layout;
 pub mod eviction_registry;
 pub mod memory_manager;
 pub mod tracker;
@@ -16,7 +18,8 @@ pub use eviction_registry::{
     make_entry, EvictableEntry, EvictionRegistry, EvictionRegistrySnapshot,
 };
 pub use memory_manager::{
-    AllocationsByPriority, GpuAllocationGuard, GpuError, GpuMemoryManager, GpuPriority, GpuStats,
-    GpuSubsystem, SubsystemStats, PRESSURE_CRITICAL, PRESSURE_HIGH, PRESSURE_WARNING,
+    AllocationsByPriority, EvictCallback, GpuAllocationGuard, GpuError, GpuMemoryManager,
+    GpuPriority, GpuReservation, GpuReservationInfo, GpuStats, GpuSubsystem, SubsystemStats,
+    PRESSURE_CRITICAL, PRESSURE_HIGH, PRESSURE_WARNING,
 };
 pub use tracker::GpuModelTracker;
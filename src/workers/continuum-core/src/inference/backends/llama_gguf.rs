@@ -211,6 +211,10 @@ impl ModelBackend for LlamaGgufBackend {
             .map_err(|e| format!("Decode failed: {e}"))
     }
 
+    fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
     // ── LoRA Support (Mixed-Precision Merge) ──
 
     fn supports_lora(&self) -> bool {
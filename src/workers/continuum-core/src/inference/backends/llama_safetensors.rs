@@ -18,6 +18,7 @@ use super::{
     GenomeAdapter, GpuMemoryManager, GpuPriority, GpuSubsystem, ModelBackend, ModelFormat,
 };
 use crate::inference::model::rebuild_with_stacked_lora;
+use crate::inference::weight_bytes::safetensors_weight_bytes;
 use crate::runtime;
 
 /// BF16 full-batch prefill on Metal creates an O(n²) attention matrix.
@@ -195,12 +196,20 @@ impl ModelBackend for LlamaSafetensorsBackend {
             .map_err(|e| format!("Decode failed: {e}"))
     }
 
+    fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+
+    /// Element count × load dtype, from the safetensors headers. File size
+    /// would undercount by half when a BF16 checkpoint is loaded as F32.
     fn estimated_vram_bytes(&self) -> u64 {
-        self.weight_paths
-            .iter()
-            .filter_map(|p| std::fs::metadata(p).ok())
-            .map(|m| m.len())
-            .sum()
+        safetensors_weight_bytes(&self.weight_paths, self.dtype).unwrap_or_else(|_| {
+            self.weight_paths
+                .iter()
+                .filter_map(|p| std::fs::metadata(p).ok())
+                .map(|m| m.len())
+                .sum()
+        })
     }
 
     // ── LoRA Support ──
@@ -23,10 +23,11 @@ use std::time::Instant;
 
 use candle_core::quantized::gguf_file;
 use candle_core::{Device, Tensor};
-use candle_transformers::generation::LogitsProcessor;
+use candle_transformers::generation::{LogitsProcessor, Sampling};
 use rand::Rng;
 use tokenizers::Tokenizer;
 
+use crate::ai::{TokenLogprob, TopLogprob};
 use crate::gpu::memory_manager::{GpuMemoryManager, GpuPriority, GpuSubsystem};
 use crate::inference::lora::LoRAWeights;
 use crate::runtime;
@@ -60,6 +61,9 @@ const GPU_SYNC_INTERVAL: usize = 16;
 /// Check for NaN only on first N generated tokens.
 const NAN_CHECK_TOKENS: usize = 3;
 
+/// Default window of recent tokens the repetition penalty looks at.
+pub const DEFAULT_REPEAT_LAST_N: usize = 64;
+
 /// Unified trait for ALL local model backends.
 ///
 /// Every local model — regardless of format (GGUF, safetensors) or
@@ -103,6 +107,25 @@ pub trait ModelBackend: Send + Sync {
     /// - Safetensors BF16: full-batch (proper causal masking, GPU-efficient)
     fn prefill(&mut self, tokens: &[u32]) -> Result<Tensor, String>;
 
+    /// Continue an existing KV cache: process `tokens` starting at position
+    /// `start_pos` and return logits from the final one.
+    ///
+    /// Default is one forward pass per token, which is correct for every
+    /// backend. Override if a backend can batch at a non-zero offset.
+    fn prefill_from(&mut self, tokens: &[u32], start_pos: usize) -> Result<Tensor, String> {
+        let mut logits = None;
+        for (i, &token) in tokens.iter().enumerate() {
+            let input = Tensor::new(&[token], self.device())
+                .and_then(|t| t.unsqueeze(0))
+                .map_err(|e| format!("Tensor creation failed: {e}"))?;
+            logits = Some(
+                self.forward(&input, start_pos + i)
+                    .map_err(|e| format!("Forward failed at position {}: {e}", start_pos + i))?,
+            );
+        }
+        logits.ok_or_else(|| "prefill_from called with no tokens".to_string())
+    }
+
     /// Clear KV cache for a fresh generation.
     fn clear_cache(&mut self) -> Result<(), String>;
 
@@ -114,6 +137,10 @@ pub trait ModelBackend: Send + Sync {
     /// Decode token IDs back to text.
     fn decode(&self, tokens: &[u32]) -> Result<String, String>;
 
+    /// The model's tokenizer, for callers that need options `tokenize` and
+    /// `decode` don't expose (special tokens).
+    fn tokenizer(&self) -> &Tokenizer;
+
     // ── Memory ──
 
     /// Estimated VRAM consumed by this model's weights (bytes).
@@ -149,21 +176,223 @@ pub trait ModelBackend: Send + Sync {
 
 // ─── Unified Text Generation ─────────────────────────────────────────────────
 
+/// Per-request generation settings.
+#[derive(Debug, Clone)]
+pub struct GenerateParams {
+    pub max_tokens: usize,
+    pub temperature: f64,
+    /// Nucleus sampling: keep the smallest token set with cumulative
+    /// probability >= top_p. `None` (or >= 1.0) disables.
+    pub top_p: Option<f64>,
+    /// Sample only among the k most likely tokens. `None` (or 0) disables.
+    pub top_k: Option<usize>,
+    /// Divide logits of recently seen tokens by this factor (llama.cpp
+    /// style; 1.1 is a typical value). `None` (or 1.0) disables.
+    pub repeat_penalty: Option<f32>,
+    /// How many trailing tokens (prompt included) the penalty considers.
+    pub repeat_last_n: usize,
+    /// Halt as soon as any of these strings appears in the output.
+    /// The matched stop string (and anything after it) is trimmed.
+    pub stop: Vec<String>,
+    /// Conversation the KV cache belongs to (e.g. a persona handle).
+    pub session: Option<String>,
+    /// Clear the KV cache before generating. When false and the prompt
+    /// extends the session's previous turn, only the new tokens are fed.
+    pub reset_context: bool,
+    /// Record the top-N alternatives (and the chosen token's logprob) at
+    /// every generated position. `None` skips the extra softmax per token.
+    pub logprobs: Option<usize>,
+    /// Sampling seed. The same seed, prompt and settings reproduce the same
+    /// output; `None` draws a fresh random seed per request.
+    pub seed: Option<u64>,
+}
+
+impl GenerateParams {
+    pub fn new(max_tokens: usize, temperature: f64) -> Self {
+        Self {
+            max_tokens,
+            temperature,
+            top_p: None,
+            top_k: None,
+            repeat_penalty: None,
+            repeat_last_n: DEFAULT_REPEAT_LAST_N,
+            stop: Vec::new(),
+            session: None,
+            reset_context: true,
+            logprobs: None,
+            seed: None,
+        }
+    }
+
+    /// Collect per-token logprobs with up to `top_n` alternatives each
+    /// (capped at `MAX_TOP_LOGPROBS`).
+    pub fn with_logprobs(mut self, top_n: Option<usize>) -> Self {
+        self.logprobs = top_n.map(|n| n.min(MAX_TOP_LOGPROBS));
+        self
+    }
+
+    /// Keep the KV cache warm across turns of `session`.
+    pub fn with_session(mut self, session: Option<String>, reset_context: bool) -> Self {
+        self.session = session;
+        self.reset_context = reset_context;
+        self
+    }
+
+    pub fn with_top_p(mut self, top_p: Option<f64>) -> Self {
+        self.top_p = top_p;
+        self
+    }
+
+    pub fn with_top_k(mut self, top_k: Option<usize>) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    pub fn with_repeat_penalty(mut self, penalty: Option<f32>, last_n: Option<usize>) -> Self {
+        self.repeat_penalty = penalty;
+        if let Some(n) = last_n {
+            self.repeat_last_n = n;
+        }
+        self
+    }
+
+    /// Sampling strategy for the logits processor. With no top-p/top-k this
+    /// is exactly what `LogitsProcessor::new(seed, Some(temperature), None)`
+    /// used to pick, so defaults are unchanged.
+    fn sampling(&self) -> Sampling {
+        let temperature = self.temperature;
+        if temperature < 1e-7 {
+            return Sampling::ArgMax;
+        }
+        let top_p = self.top_p.filter(|&p| p > 0.0 && p < 1.0);
+        let top_k = self.top_k.filter(|&k| k > 0);
+        match (top_k, top_p) {
+            (None, None) => Sampling::All { temperature },
+            (Some(k), None) => Sampling::TopK { k, temperature },
+            (None, Some(p)) => Sampling::TopP { p, temperature },
+            (Some(k), Some(p)) => Sampling::TopKThenTopP { k, p, temperature },
+        }
+    }
+
+    fn active_repeat_penalty(&self) -> Option<f32> {
+        self.repeat_penalty
+            .filter(|&p| (p - 1.0).abs() > f32::EPSILON && self.repeat_last_n > 0)
+    }
+
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Sampler for one generation, seeded from `seed` when set
+    fn logits_processor(&self) -> LogitsProcessor {
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen::<u64>());
+        LogitsProcessor::from_sampling(seed, self.sampling())
+    }
+
+    pub fn with_stop(mut self, stop: Vec<String>) -> Self {
+        self.stop = stop.into_iter().filter(|s| !s.is_empty()).collect();
+        self
+    }
+}
+
+/// Upper bound on alternatives per position (matches OpenAI's limit).
+pub const MAX_TOP_LOGPROBS: usize = 20;
+
+/// Result of one `generate_streaming` call.
+#[derive(Debug, Clone, Default)]
+pub struct GenerateOutput {
+    pub text: String,
+    /// Number of generated tokens (EOS excluded)
+    pub tokens: usize,
+    /// One entry per generated token, when `params.logprobs` was set
+    pub logprobs: Option<Vec<TokenLogprob>>,
+}
+
+/// What a backend's KV cache currently holds.
+///
+/// A backend has one KV cache, so one session can be warm at a time. The
+/// owner of the backend keeps this alongside it and hands it to
+/// `generate_streaming`; any other use of the backend (LoRA rebuild, reload)
+/// must `invalidate()` it.
+#[derive(Debug, Default)]
+pub struct KvCacheState {
+    session: Option<String>,
+    /// Tokens whose keys/values are in the cache, in position order
+    tokens: Vec<u32>,
+}
+
+impl KvCacheState {
+    /// Forget the cached contents; the next generation starts clean.
+    pub fn invalidate(&mut self) {
+        self.session = None;
+        self.tokens.clear();
+    }
+
+    /// Number of leading prompt tokens already in the cache, if the prompt
+    /// strictly extends what this session cached last turn. Anything else
+    /// (other session, edited history, re-tokenized boundary) needs a clear —
+    /// the cache can be appended to but not rewound.
+    fn reusable_prefix(&self, session: Option<&str>, prompt_tokens: &[u32]) -> Option<usize> {
+        let cached = self.tokens.len();
+        let same_session = session.is_some() && self.session.as_deref() == session;
+        (same_session
+            && cached > 0
+            && prompt_tokens.len() > cached
+            && prompt_tokens.starts_with(&self.tokens))
+        .then_some(cached)
+    }
+
+    /// Record what the cache holds after a generation that returned the
+    /// first `returned` of `tokens` (prompt included). Tokens past that — the
+    /// start of a trimmed stop string — never reach the caller's next prompt,
+    /// and since the cache can't be rewound it is left invalid.
+    fn commit(&mut self, session: Option<String>, tokens: &[u32], returned: usize) {
+        if tokens.len() <= returned {
+            self.session = session;
+            self.tokens = tokens.to_vec();
+        }
+    }
+}
+
 /// Generate text from a prompt using ANY ModelBackend.
 ///
 /// One function for all local models. Handles:
 /// - Context length validation
 /// - Prefill via backend strategy (token-by-token or full-batch)
-/// - Token generation with sampling
+/// - KV cache reuse across turns of a session (see `generate_streaming`)
+/// - Token generation with temperature/top-k/top-p sampling
+/// - Repetition penalty over the trailing `repeat_last_n` tokens
+/// - Stop sequences (matched on incrementally decoded text, so matches
+///   spanning several tokens are caught)
 /// - NaN detection and prompt replay on failure
 /// - GPU sync management
 pub fn generate(
     backend: &mut dyn ModelBackend,
     prompt: &str,
-    max_tokens: usize,
-    temperature: f64,
+    params: &GenerateParams,
 ) -> Result<(String, usize), String> {
+    generate_streaming(backend, prompt, params, None, None).map(|out| (out.text, out.tokens))
+}
+
+/// `generate()` with incremental output.
+///
+/// `on_text` receives each newly decoded piece of text as soon as it is
+/// complete (never a partial UTF-8 sequence, never any part of a stop
+/// string). Returning `false` from the callback cancels generation — use
+/// this to stop work when the consumer has gone away.
+///
+/// With a `cache` state and `params.reset_context == false`, a prompt that
+/// strictly extends the session's previous turn only prefills the new tokens.
+pub fn generate_streaming(
+    backend: &mut dyn ModelBackend,
+    prompt: &str,
+    params: &GenerateParams,
+    mut cache: Option<&mut KvCacheState>,
+    mut on_text: Option<&mut dyn FnMut(&str) -> bool>,
+) -> Result<GenerateOutput, String> {
     let log = runtime::logger("candle");
+    let max_tokens = params.max_tokens;
     let start = Instant::now();
 
     // Tokenize
@@ -197,11 +426,32 @@ pub fn generate(
         backend.format()
     ));
 
-    // Clear KV cache
-    backend.clear_cache()?;
+    // Decide whether the warm cache can be extended. Either way the recorded
+    // state is invalid until this generation completes successfully.
+    let reusable = match cache.as_deref() {
+        Some(state) if !params.reset_context => {
+            state.reusable_prefix(params.session.as_deref(), &prompt_tokens)
+        }
+        _ => None,
+    };
+    if let Some(state) = cache.as_deref_mut() {
+        state.invalidate();
+    }
 
     // ── Phase 1: Prefill ──
-    let prefill_logits = backend.prefill(&prompt_tokens)?;
+    let prefill_logits = match reusable {
+        Some(cached) => {
+            log.debug(&format!(
+                "KV cache reuse: {} of {} prompt tokens already cached",
+                cached, prompt_len
+            ));
+            backend.prefill_from(&prompt_tokens[cached..], cached)?
+        }
+        None => {
+            backend.clear_cache()?;
+            backend.prefill(&prompt_tokens)?
+        }
+    };
     let prefill_logits = extract_last_logits(&prefill_logits)?;
     let (prefill_logits, had_nan) = sanitize_logits_with_flag(&prefill_logits, backend.device())?;
     if had_nan {
@@ -213,25 +463,65 @@ pub fn generate(
     }
 
     // Setup sampler
-    let seed = rand::thread_rng().gen::<u64>();
-    let mut logits_processor = LogitsProcessor::new(seed, Some(temperature), None);
+    let mut logits_processor = params.logits_processor();
+    let repeat_penalty = params.active_repeat_penalty();
+    let penalize = |logits: &Tensor, context: &[u32]| -> Result<Tensor, String> {
+        match repeat_penalty {
+            Some(penalty) => {
+                let start = context.len().saturating_sub(params.repeat_last_n);
+                candle_transformers::utils::apply_repeat_penalty(logits, penalty, &context[start..])
+                    .map_err(|e| format!("Repeat penalty failed: {e}"))
+            }
+            None => Ok(logits.clone()),
+        }
+    };
 
     let mut all_tokens = prompt_tokens;
+    // Tokens whose keys/values are in the cache (the newest sampled token
+    // isn't until it has been fed back through forward)
+    let mut kv_len = prompt_len;
+
+    // Incremental decoding is only needed to stream or to watch for stop strings
+    let mut text_stream =
+        (on_text.is_some() || !params.stop.is_empty()).then(|| TextStream::new(&params.stop));
+    let mut logprobs: Option<Vec<TokenLogprob>> = params.logprobs.map(|_| Vec::new());
 
     // Sample first token from prefill logits
+    let prefill_logits = penalize(&prefill_logits, &all_tokens)?;
     let first_token = logits_processor
         .sample(&prefill_logits)
         .map_err(|e| format!("First token sampling failed: {e}"))?;
 
     if backend.eos_token_ids().contains(&first_token) {
-        return Ok((String::new(), 0));
+        return Ok(GenerateOutput {
+            logprobs,
+            ..Default::default()
+        });
+    }
+    if let (Some(entries), Some(top_n)) = (logprobs.as_mut(), params.logprobs) {
+        entries.push(token_logprob(
+            &*backend,
+            &prefill_logits,
+            first_token,
+            top_n,
+        )?);
     }
     all_tokens.push(first_token);
+    let mut halted = advance_stream(
+        &mut text_stream,
+        &mut on_text,
+        &*backend,
+        &all_tokens[prompt_len..],
+    )?;
 
     // ── Phase 2: Generate ──
     let mut nan_count = 0;
 
     for i in 1..max_tokens {
+        if halted != Halt::No {
+            break;
+        }
+
         let token = *all_tokens.last().ok_or("Empty token sequence")?;
         let input = Tensor::new(&[token], backend.device())
             .map_err(|e| format!("Tensor creation failed: {e}"))?
@@ -252,6 +542,7 @@ pub fn generate(
         let logits = backend
             .forward(&input, pos)
             .map_err(|e| format!("Forward failed at token {i}: {e}"))?;
+        kv_len = pos + 1;
 
         // GPU sync periodically
         if (i + 1) % GPU_SYNC_INTERVAL == 0 {
@@ -285,6 +576,7 @@ pub fn generate(
         } else {
             logits
         };
+        let logits = penalize(&logits, &all_tokens)?;
 
         // Sample next token
         let next_token = match logits_processor.sample(&logits) {
@@ -317,7 +609,16 @@ pub fn generate(
         if backend.eos_token_ids().contains(&next_token) {
             break;
         }
+        if let (Some(entries), Some(top_n)) = (logprobs.as_mut(), params.logprobs) {
+            entries.push(token_logprob(&*backend, &logits, next_token, top_n)?);
+        }
         all_tokens.push(next_token);
+        halted = advance_stream(
+            &mut text_stream,
+            &mut on_text,
+            &*backend,
+            &all_tokens[prompt_len..],
+        )?;
     }
 
     // Final GPU sync
@@ -328,7 +629,36 @@ pub fn generate(
 
     // Decode
     let generated_tokens = &all_tokens[prompt_len..];
-    let output_text = backend.decode(generated_tokens)?;
+    let (output_text, returned_tokens) = trim_at_stop(
+        |t| backend.decode(t),
+        generated_tokens,
+        &params.stop,
+        &mut logprobs,
+    )?;
+
+    if let Some(state) = cache {
+        state.commit(
+            params.session.clone(),
+            &all_tokens[..kv_len],
+            prompt_len + returned_tokens,
+        );
+    }
+
+    // Deliver whatever the stream was still holding back
+    if halted == Halt::No {
+        if let (Some(stream), Some(callback)) = (text_stream.as_mut(), on_text.as_mut()) {
+            let rest = stream.finish(&*backend, generated_tokens)?;
+            if !rest.is_empty() {
+                callback(&rest);
+            }
+        }
+    }
+    if halted == Halt::Cancelled {
+        log.info(&format!(
+            "Generation cancelled by consumer after {} tokens",
+            generated_tokens.len()
+        ));
+    }
 
     let duration = start.elapsed();
     log.info(&format!(
@@ -340,7 +670,123 @@ pub fn generate(
         prompt_len
     ));
 
-    Ok((output_text, generated_tokens.len()))
+    Ok(GenerateOutput {
+        text: output_text,
+        tokens: returned_tokens,
+        logprobs,
+    })
+}
+
+/// Decode `generated` and cut it at the first stop string. Returns the text
+/// and how many tokens it accounts for: tokens from the one a stop string
+/// starts in onward are dropped, along with their logprobs.
+fn trim_at_stop(
+    decode: impl Fn(&[u32]) -> Result<String, String>,
+    generated: &[u32],
+    stop: &[String],
+    logprobs: &mut Option<Vec<TokenLogprob>>,
+) -> Result<(String, usize), String> {
+    let mut text = decode(generated)?;
+    let Some(pos) = find_stop(&text, stop) else {
+        return Ok((text, generated.len()));
+    };
+    text.truncate(pos);
+    let returned = stop_token_boundary(decode, generated, pos)?;
+    if let Some(entries) = logprobs.as_mut() {
+        entries.truncate(returned);
+    }
+    Ok((text, returned))
+}
+
+/// Number of leading `tokens` that decode to text ending at or before byte
+/// `stop_pos`, i.e. the token boundary a stop string starting there trims to.
+fn stop_token_boundary(
+    decode: impl Fn(&[u32]) -> Result<String, String>,
+    tokens: &[u32],
+    stop_pos: usize,
+) -> Result<usize, String> {
+    // Generation halts as soon as the stop string appears, so the boundary is
+    // within the last few tokens
+    for kept in (0..=tokens.len()).rev() {
+        if decode(&tokens[..kept])?.len() <= stop_pos {
+            return Ok(kept);
+        }
+    }
+    Ok(0)
+}
+
+/// Logprob of `chosen` and the `top_n` most likely tokens under `logits`.
+///
+/// Computed from the logits the sampler saw (after repeat penalty, before
+/// temperature), so they describe the model rather than the sampling knobs.
+fn token_logprob(
+    backend: &dyn ModelBackend,
+    logits: &Tensor,
+    chosen: u32,
+    top_n: usize,
+) -> Result<TokenLogprob, String> {
+    let values: Vec<f32> = logits
+        .to_dtype(candle_core::DType::F32)
+        .and_then(|t| t.to_vec1())
+        .map_err(|e| format!("Logprob extraction failed: {e}"))?;
+    let (logprob, top) = logprobs_from_logits(&values, chosen, top_n);
+    let top_logprobs = top
+        .into_iter()
+        .map(|(token_id, logprob)| {
+            Ok(TopLogprob {
+                token: backend.decode(&[token_id])?,
+                token_id,
+                logprob,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(TokenLogprob {
+        token: backend.decode(&[chosen])?,
+        token_id: chosen,
+        logprob,
+        top_logprobs,
+    })
+}
+
+/// Log-softmax of `values` at `chosen`, plus the `top_n` highest entries in
+/// descending order. Non-finite logits count as impossible.
+fn logprobs_from_logits(values: &[f32], chosen: u32, top_n: usize) -> (f32, Vec<(u32, f32)>) {
+    let max = values
+        .iter()
+        .copied()
+        .filter(|v| v.is_finite())
+        .fold(f32::NEG_INFINITY, f32::max);
+    let sum: f32 = values
+        .iter()
+        .filter(|v| v.is_finite())
+        .map(|&v| (v - max).exp())
+        .sum();
+    let log_z = max + sum.ln();
+    let logprob_of = |v: f32| {
+        if v.is_finite() {
+            v - log_z
+        } else {
+            f32::NEG_INFINITY
+        }
+    };
+
+    let chosen_logprob = values
+        .get(chosen as usize)
+        .map_or(f32::NEG_INFINITY, |&v| logprob_of(v));
+
+    let mut ranked: Vec<(u32, f32)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| (i as u32, logprob_of(v)))
+        .collect();
+    let top_n = top_n.min(ranked.len());
+    let by_logprob = |a: &(u32, f32), b: &(u32, f32)| b.1.total_cmp(&a.1);
+    if top_n > 0 && top_n < ranked.len() {
+        ranked.select_nth_unstable_by(top_n - 1, by_logprob);
+    }
+    ranked.truncate(top_n);
+    ranked.sort_by(by_logprob);
+    (chosen_logprob, ranked)
 }
 
 // ─── GGUF Metadata ───────────────────────────────────────────────────────────
@@ -444,6 +890,130 @@ pub fn load_gguf_backend(
 
 // ─── Helpers ─────────────────────────────────────────────────────────────────
 
+/// Byte offset of the earliest stop string in `text`, if any.
+fn find_stop(text: &str, stop: &[String]) -> Option<usize> {
+    stop.iter().filter_map(|s| text.find(s.as_str())).min()
+}
+
+/// Length of the longest suffix of `text` that could be the start of a stop
+/// string. That much text must be held back until the next token decides it.
+fn stop_prefix_len(text: &str, stop: &[String]) -> usize {
+    stop.iter()
+        .filter_map(|s| {
+            (1..s.len())
+                .rev()
+                .find(|&k| s.is_char_boundary(k) && text.ends_with(&s[..k]))
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Why the generation loop should stop early, if at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Halt {
+    No,
+    StopSequence,
+    Cancelled,
+}
+
+/// Incremental detokenizer for streaming and stop-sequence detection.
+///
+/// Each step decodes only the tokens since the last emitted piece, so cost
+/// stays constant per token. A piece ending in U+FFFD is an incomplete
+/// multi-byte character and waits for the next token.
+struct TextStream<'a> {
+    stop: &'a [String],
+    /// Start of the decode window (tokens already emitted before this point)
+    prefix_offset: usize,
+    /// End of the text already accounted for within the window
+    read_offset: usize,
+    /// Decoded text not yet handed to the consumer
+    pending: String,
+}
+
+impl<'a> TextStream<'a> {
+    fn new(stop: &'a [String]) -> Self {
+        Self {
+            stop,
+            prefix_offset: 0,
+            read_offset: 0,
+            pending: String::new(),
+        }
+    }
+
+    /// Account for newly generated tokens. Returns text safe to emit and
+    /// whether a stop string has appeared.
+    fn push(
+        &mut self,
+        backend: &dyn ModelBackend,
+        generated: &[u32],
+    ) -> Result<(String, bool), String> {
+        self.absorb(backend, generated)?;
+        Ok(self.drain(false))
+    }
+
+    /// Final flush at end of generation (EOS or max_tokens).
+    fn finish(&mut self, backend: &dyn ModelBackend, generated: &[u32]) -> Result<String, String> {
+        let prefix = backend.decode(&generated[self.prefix_offset..self.read_offset])?;
+        let full = backend.decode(&generated[self.prefix_offset..])?;
+        if let Some(rest) = full.get(prefix.len()..) {
+            self.pending.push_str(rest);
+        }
+        self.read_offset = generated.len();
+        Ok(self.drain(true).0)
+    }
+
+    fn absorb(&mut self, backend: &dyn ModelBackend, generated: &[u32]) -> Result<(), String> {
+        let prefix = backend.decode(&generated[self.prefix_offset..self.read_offset])?;
+        let full = backend.decode(&generated[self.prefix_offset..])?;
+        if full.len() > prefix.len() && !full.ends_with('\u{FFFD}') {
+            if let Some(delta) = full.get(prefix.len()..) {
+                self.pending.push_str(delta);
+            }
+            self.prefix_offset = self.read_offset;
+            self.read_offset = generated.len();
+        }
+        Ok(())
+    }
+
+    fn drain(&mut self, flush: bool) -> (String, bool) {
+        if let Some(pos) = find_stop(&self.pending, self.stop) {
+            self.pending.truncate(pos);
+            return (std::mem::take(&mut self.pending), true);
+        }
+        let hold = if flush {
+            0
+        } else {
+            stop_prefix_len(&self.pending, self.stop)
+        };
+        let held = self.pending.split_off(self.pending.len() - hold);
+        (std::mem::replace(&mut self.pending, held), false)
+    }
+}
+
+/// Feed the latest token to the text stream and the consumer callback.
+fn advance_stream(
+    stream: &mut Option<TextStream<'_>>,
+    on_text: &mut Option<&mut dyn FnMut(&str) -> bool>,
+    backend: &dyn ModelBackend,
+    generated: &[u32],
+) -> Result<Halt, String> {
+    let Some(stream) = stream.as_mut() else {
+        return Ok(Halt::No);
+    };
+    let (text, hit_stop) = stream.push(backend, generated)?;
+    if let Some(callback) = on_text.as_mut() {
+        if !text.is_empty() && !callback(&text) {
+            return Ok(Halt::Cancelled);
+        }
+    }
+    Ok(if hit_stop {
+        Halt::StopSequence
+    } else {
+        Halt::No
+    })
+}
+
 /// Extract logits for the last token position from model output.
 fn extract_last_logits(logits: &Tensor) -> Result<Tensor, String> {
     let logits = logits
@@ -521,3 +1091,229 @@ fn save_prompt_replay(prompt: &str, tokens: &[u32], error: &str) {
         Err(e) => log.warn(&format!("Failed to save prompt replay: {}", e)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stops(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_find_stop_earliest_wins() {
+        let text = "answer</answer>\nUser: hi";
+        assert_eq!(find_stop(text, &stops(&["\nUser:", "</answer>"])), Some(6));
+    }
+
+    #[test]
+    fn test_find_stop_none() {
+        assert_eq!(find_stop("hello world", &stops(&["\nUser:"])), None);
+        assert_eq!(find_stop("hello world", &[]), None);
+    }
+
+    #[test]
+    fn test_kv_cache_reuse_requires_strict_extension() {
+        let mut state = KvCacheState::default();
+        assert_eq!(state.reusable_prefix(Some("a"), &[1, 2, 3]), None);
+
+        state.commit(Some("a".to_string()), &[1, 2, 3], 3);
+        assert_eq!(state.reusable_prefix(Some("a"), &[1, 2, 3, 4, 5]), Some(3));
+        // Same prompt again: nothing new to prefill from
+        assert_eq!(state.reusable_prefix(Some("a"), &[1, 2, 3]), None);
+        // Diverging history
+        assert_eq!(state.reusable_prefix(Some("a"), &[1, 9, 3, 4]), None);
+        // Different or missing session
+        assert_eq!(state.reusable_prefix(Some("b"), &[1, 2, 3, 4]), None);
+        assert_eq!(state.reusable_prefix(None, &[1, 2, 3, 4]), None);
+
+        state.invalidate();
+        assert_eq!(state.reusable_prefix(Some("a"), &[1, 2, 3, 4]), None);
+    }
+
+    #[test]
+    fn test_kv_cache_trimmed_on_stop_boundary() {
+        // Tokens decode one character each: "ab</x>"
+        let text = "ab</x>";
+        let decode = |t: &[u32]| Ok(text[..t.len()].to_string());
+        let generated = [10, 11, 12, 13, 14, 15];
+        let stop = find_stop(text, &stops(&["</"])).unwrap();
+        assert_eq!(stop_token_boundary(decode, &generated, stop), Ok(2));
+
+        // Prompt [1, 2] plus "ab<" in the cache, but only "ab" returned: the
+        // next turn can't extend it, so nothing is recorded
+        let mut state = KvCacheState::default();
+        state.commit(Some("a".to_string()), &[1, 2, 10, 11, 12], 4);
+        assert_eq!(state.reusable_prefix(Some("a"), &[1, 2, 10, 11, 20]), None);
+        assert_eq!(
+            state.reusable_prefix(Some("a"), &[1, 2, 10, 11, 12, 20]),
+            None
+        );
+
+        // Stop string starting right after the cached tokens: reusable
+        state.commit(Some("a".to_string()), &[1, 2, 10, 11], 4);
+        assert_eq!(
+            state.reusable_prefix(Some("a"), &[1, 2, 10, 11, 20]),
+            Some(4)
+        );
+    }
+
+    #[test]
+    fn test_logprobs_trimmed_with_tokens() {
+        let text = "ab</x>";
+        let decode = |t: &[u32]| Ok(text[..t.len()].to_string());
+        let generated = [10, 11, 12, 13, 14, 15];
+        let entry = |token_id| TokenLogprob {
+            token: String::new(),
+            token_id,
+            logprob: -0.5,
+            top_logprobs: Vec::new(),
+        };
+        let mut logprobs = Some(generated.iter().map(|&t| entry(t)).collect::<Vec<_>>());
+
+        let (trimmed, tokens) =
+            trim_at_stop(decode, &generated, &stops(&["</"]), &mut logprobs).unwrap();
+        assert_eq!(trimmed, "ab");
+        assert_eq!(tokens, 2);
+        let ids: Vec<u32> = logprobs.unwrap().iter().map(|e| e.token_id).collect();
+        assert_eq!(ids, [10, 11]);
+
+        // No stop string: everything is returned
+        let mut logprobs = Some(vec![entry(10)]);
+        let (full, tokens) = trim_at_stop(decode, &generated, &[], &mut logprobs).unwrap();
+        assert_eq!((full.as_str(), tokens), (text, 6));
+        assert_eq!(logprobs.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_stop_prefix_len() {
+        let stop = stops(&["\nUser:", "</answer>"]);
+        assert_eq!(stop_prefix_len("hello\nUs", &stop), 3);
+        assert_eq!(stop_prefix_len("42</ans", &stop), 5);
+        assert_eq!(stop_prefix_len("hello", &stop), 0);
+    }
+
+    #[test]
+    fn test_drain_holds_back_partial_stop() {
+        let stop = stops(&["\nUser:"]);
+        let mut stream = TextStream::new(&stop);
+
+        stream.pending.push_str("Sure thing.\nUs");
+        assert_eq!(stream.drain(false), ("Sure thing.".to_string(), false));
+
+        // The held-back text turns out not to be a stop string after all
+        stream.pending.push_str("ually");
+        assert_eq!(stream.drain(false), ("\nUsually".to_string(), false));
+
+        // A stop string spanning two pushes is trimmed, never emitted
+        stream.pending.push_str(" ok\nUse");
+        assert_eq!(stream.drain(false), (" ok".to_string(), false));
+        stream.pending.push_str("r: hi");
+        assert_eq!(stream.drain(false), (String::new(), true));
+    }
+
+    #[test]
+    fn test_drain_flush_releases_everything() {
+        let stop = stops(&["</answer>"]);
+        let mut stream = TextStream::new(&stop);
+        stream.pending.push_str("done</ans");
+        assert_eq!(stream.drain(true), ("done</ans".to_string(), false));
+    }
+
+    #[test]
+    fn test_default_sampling_matches_legacy() {
+        let params = GenerateParams::new(16, 0.7);
+        assert!(matches!(params.sampling(), Sampling::All { temperature } if temperature == 0.7));
+        assert!(params.active_repeat_penalty().is_none());
+        let greedy = GenerateParams::new(16, 0.0).with_top_k(Some(40));
+        assert!(matches!(greedy.sampling(), Sampling::ArgMax));
+    }
+
+    #[test]
+    fn test_sampling_selection() {
+        let base = GenerateParams::new(16, 0.8);
+        assert!(matches!(
+            base.clone().with_top_k(Some(40)).sampling(),
+            Sampling::TopK { k: 40, .. }
+        ));
+        assert!(matches!(
+            base.clone().with_top_p(Some(0.9)).sampling(),
+            Sampling::TopP { .. }
+        ));
+        assert!(matches!(
+            base.clone()
+                .with_top_k(Some(40))
+                .with_top_p(Some(0.9))
+                .sampling(),
+            Sampling::TopKThenTopP { k: 40, .. }
+        ));
+        // Out-of-range values fall back to plain temperature sampling
+        assert!(matches!(
+            base.with_top_k(Some(0)).with_top_p(Some(1.0)).sampling(),
+            Sampling::All { .. }
+        ));
+    }
+
+    #[test]
+    fn test_seeded_sampling_is_reproducible() {
+        let logits = Tensor::new(&[0.1f32, 0.5, 0.2, 0.9, 0.3, 0.4], &Device::Cpu).unwrap();
+        let sample = |params: &GenerateParams| -> Vec<u32> {
+            let mut processor = params.logits_processor();
+            (0..32)
+                .map(|_| processor.sample(&logits).unwrap())
+                .collect()
+        };
+
+        let seeded = GenerateParams::new(16, 1.0).with_seed(Some(42));
+        assert_eq!(sample(&seeded), sample(&seeded));
+        assert_ne!(sample(&seeded), sample(&seeded.clone().with_seed(Some(43))));
+    }
+
+    #[test]
+    fn test_repeat_penalty_activation() {
+        let params = GenerateParams::new(16, 0.8);
+        assert!(params
+            .clone()
+            .with_repeat_penalty(Some(1.0), None)
+            .active_repeat_penalty()
+            .is_none());
+        assert!(params
+            .clone()
+            .with_repeat_penalty(Some(1.1), Some(0))
+            .active_repeat_penalty()
+            .is_none());
+        let active = params.with_repeat_penalty(Some(1.1), None);
+        assert_eq!(active.active_repeat_penalty(), Some(1.1));
+        assert_eq!(active.repeat_last_n, DEFAULT_REPEAT_LAST_N);
+    }
+
+    #[test]
+    fn test_logprobs_from_logits() {
+        let values = [0.0f32, 2.0, f32::NAN, 1.0];
+        let (chosen, top) = logprobs_from_logits(&values, 3, 2);
+
+        let log_z = (1.0f32 + 2f32.exp() + 1f32.exp()).ln();
+        assert!((chosen - (1.0 - log_z)).abs() < 1e-5);
+        assert_eq!(top.iter().map(|t| t.0).collect::<Vec<_>>(), vec![1, 3]);
+        assert!((top[0].1 - (2.0 - log_z)).abs() < 1e-5);
+
+        // Non-finite logits are impossible, and top_n is clamped
+        let (nan, all) = logprobs_from_logits(&values, 2, 10);
+        assert_eq!(nan, f32::NEG_INFINITY);
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[3].0, 2);
+    }
+
+    #[test]
+    fn test_logprobs_param_capped() {
+        assert_eq!(GenerateParams::new(8, 0.7).logprobs, None);
+        let params = GenerateParams::new(8, 0.7).with_logprobs(Some(100));
+        assert_eq!(params.logprobs, Some(MAX_TOP_LOGPROBS));
+    }
+
+    #[test]
+    fn test_params_drop_empty_stop_strings() {
+        let params = GenerateParams::new(16, 0.7).with_stop(stops(&["", "</s>"]));
+        assert_eq!(params.stop, stops(&["</s>"]));
+    }
+}
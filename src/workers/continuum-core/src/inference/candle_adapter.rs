@@ -10,27 +10,34 @@
 use async_trait::async_trait;
 use parking_lot::RwLock;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 use crate::ai::{
     AIProviderAdapter, ActiveAdapterRequest, AdapterCapabilities, AdapterConfig, ApiStyle,
     FinishReason, HealthState, HealthStatus, LoRAAdapterInfo, LoRACapabilities, ModelCapability,
-    ModelInfo, RoutingInfo, TextGenerationRequest, TextGenerationResponse, UsageMetrics,
+    ModelInfo, RoutingInfo, TextCallback, TextGenerationRequest, TextGenerationResponse,
+    UsageMetrics,
 };
 use crate::gpu::make_entry;
-use crate::gpu::memory_manager::{GpuAllocationGuard, GpuMemoryManager, GpuPriority, GpuSubsystem};
+use crate::gpu::memory_manager::{
+    EvictHook, GpuAllocationGuard, GpuMemoryManager, GpuPriority, GpuSubsystem,
+};
+use crate::rag::RagEngine;
 use crate::runtime;
 
 use super::backends::llama_safetensors::BF16_PRACTICAL_CONTEXT;
-use super::backends::{self, GenomeAdapter, ModelBackend, ModelFormat};
+use super::backends::{
+    self, GenerateOutput, GenerateParams, GenomeAdapter, KvCacheState, ModelBackend, ModelFormat,
+};
 use super::lora::{load_lora_adapter, LoadedAdapter};
 use super::model::load_model_by_id;
-use super::quantized::load_default_quantized;
+use super::quantized::{is_gguf_repo, load_default_quantized, load_gguf_by_id};
 
 // SAFETY: ModelBackend contains GPU tensors pinned to creation thread.
 // All model access happens within spawn_blocking on a consistent thread pool.
 // Sync is required because CandleAdapter is shared via Arc<RwLock<>> in async context.
-struct BackendWrapper(Box<dyn ModelBackend>);
+struct BackendWrapper(Box<dyn ModelBackend>, KvCacheState);
 unsafe impl Send for BackendWrapper {}
 unsafe impl Sync for BackendWrapper {}
 
@@ -43,21 +50,120 @@ pub struct CandleAdapter {
     /// The model backend (GGUF or safetensors — doesn't matter)
     backend: Arc<RwLock<Option<BackendWrapper>>>,
     /// Loaded LoRA adapters (may or may not be active)
-    loaded_adapters: RwLock<HashMap<String, LoadedAdapter>>,
+    loaded_adapters: Arc<RwLock<HashMap<String, LoadedAdapter>>>,
     /// Currently active adapter IDs (order matters for stacking)
-    active_adapters: RwLock<Vec<String>>,
+    active_adapters: Arc<RwLock<Vec<String>>>,
     /// Use quantized model
     use_quantized: bool,
     /// GPU memory manager for VRAM allocation tracking
     gpu_manager: Option<Arc<GpuMemoryManager>>,
     /// RAII guard for base model VRAM allocation
-    model_guard: RwLock<Option<GpuAllocationGuard>>,
+    model_guard: Arc<RwLock<Option<GpuAllocationGuard>>>,
     /// RAII guards for per-adapter VRAM allocations
-    adapter_guards: RwLock<HashMap<String, GpuAllocationGuard>>,
+    adapter_guards: Arc<RwLock<HashMap<String, GpuAllocationGuard>>>,
     /// Pressure-aware inference gate: limits concurrent local inference based on
     /// system memory pressure. Prevents 4 personas from all piling into
     /// spawn_blocking simultaneously (40GB peak → controlled sequential).
     inference_semaphore: Arc<tokio::sync::Semaphore>,
+    /// When a generation last started or finished (None = nothing loaded
+    /// since the last unload). Drives idle eviction.
+    last_used: Arc<RwLock<Option<Instant>>>,
+    /// This adapter's eviction hook on `gpu_manager`, unregistered on drop
+    evict_hook: Option<EvictHook>,
+    /// Told each loaded model's tokenizer and context window
+    rag_engine: Option<Arc<RagEngine>>,
+}
+
+/// Shared handles to everything an unload drops. The GPU manager's eviction
+/// hook holds a copy, since it can't borrow the adapter itself.
+#[derive(Clone)]
+struct Residency {
+    backend: Arc<RwLock<Option<BackendWrapper>>>,
+    loaded_adapters: Arc<RwLock<HashMap<String, LoadedAdapter>>>,
+    active_adapters: Arc<RwLock<Vec<String>>>,
+    model_guard: Arc<RwLock<Option<GpuAllocationGuard>>>,
+    adapter_guards: Arc<RwLock<HashMap<String, GpuAllocationGuard>>>,
+    last_used: Arc<RwLock<Option<Instant>>>,
+    inference_semaphore: Arc<tokio::sync::Semaphore>,
+}
+
+impl Residency {
+    /// Unload the model and its LoRA adapters, returning the model ID and
+    /// the dropped adapter IDs. With `expected`, only unloads that model.
+    ///
+    /// Never unloads under a generation: a running or queued request holds
+    /// the inference permit, and the backend lock is only tried, not awaited.
+    fn unload_model(&self, expected: Option<&str>) -> Option<(String, Vec<String>)> {
+        let _permit = self.inference_semaphore.try_acquire().ok()?;
+        let mut backend_guard = self.backend.try_write()?;
+        let loaded = backend_guard.as_ref()?.0.model_id().to_string();
+        if expected.is_some_and(|id| id != loaded) {
+            return None;
+        }
+        drop(backend_guard.take());
+        drop(backend_guard);
+
+        *self.model_guard.write() = None;
+        let adapter_ids: Vec<String> =
+            self.loaded_adapters.write().drain().map(|(id, _)| id).collect();
+        self.active_adapters.write().clear();
+        self.adapter_guards.write().clear();
+        *self.last_used.write() = None;
+        Some((loaded, adapter_ids))
+    }
+
+    /// Unload a LoRA adapter that isn't active. Active adapters are merged
+    /// into the running model, so they go with `unload_model` instead.
+    fn unload_inactive_adapter(&self, adapter_id: &str) -> bool {
+        let Some(active) = self.active_adapters.try_read() else {
+            return false;
+        };
+        if active.iter().any(|id| id == adapter_id) {
+            return false;
+        }
+        let Some(mut loaded) = self.loaded_adapters.try_write() else {
+            return false;
+        };
+        let Some(mut guards) = self.adapter_guards.try_write() else {
+            return false;
+        };
+        let removed = loaded.remove(adapter_id).is_some();
+        guards.remove(adapter_id);
+        removed
+    }
+
+    /// `GpuMemoryManager::on_evict` hook for this adapter's entries
+    fn evict(&self, mgr: &Weak<GpuMemoryManager>, victim_id: &str) -> bool {
+        let log = runtime::logger("candle");
+        if let Some(model_id) = victim_id.strip_prefix("candle:model:") {
+            let Some((model_id, adapter_ids)) = self.unload_model(Some(model_id)) else {
+                return false;
+            };
+            if let Some(mgr) = mgr.upgrade() {
+                for adapter_id in &adapter_ids {
+                    mgr.eviction_registry
+                        .unregister(&format!("candle:adapter:{}", adapter_id));
+                }
+            }
+            log.info(&format!(
+                "Evicted model '{}' under GPU pressure ({} adapters dropped)",
+                model_id,
+                adapter_ids.len()
+            ));
+            true
+        } else if let Some(adapter_id) = victim_id.strip_prefix("candle:adapter:") {
+            let evicted = self.unload_inactive_adapter(adapter_id);
+            if evicted {
+                log.info(&format!(
+                    "Evicted LoRA adapter '{}' under GPU pressure",
+                    adapter_id
+                ));
+            }
+            evicted
+        } else {
+            false
+        }
+    }
 }
 
 impl CandleAdapter {
@@ -74,24 +180,64 @@ impl CandleAdapter {
                 retry_delay_ms: 0,
             },
             backend: Arc::new(RwLock::new(None)),
-            loaded_adapters: RwLock::new(HashMap::new()),
-            active_adapters: RwLock::new(Vec::new()),
+            loaded_adapters: Arc::new(RwLock::new(HashMap::new())),
+            active_adapters: Arc::new(RwLock::new(Vec::new())),
             use_quantized: false,
             gpu_manager: None,
-            model_guard: RwLock::new(None),
-            adapter_guards: RwLock::new(HashMap::new()),
+            model_guard: Arc::new(RwLock::new(None)),
+            adapter_guards: Arc::new(RwLock::new(HashMap::new())),
             // Serialize: 1 permit. Only one Candle inference at a time.
             // Multiple concurrent inferences pile up KV caches + Metal state,
             // causing 40GB+ peaks. Sequential keeps peak at ~10GB above baseline.
             inference_semaphore: Arc::new(tokio::sync::Semaphore::new(1)),
+            last_used: Arc::new(RwLock::new(None)),
+            evict_hook: None,
+            rag_engine: None,
         }
     }
 
-    /// Set GPU memory manager for VRAM allocation tracking.
+    /// Set GPU memory manager for VRAM allocation tracking, and let it
+    /// evict this adapter's idle model and inactive LoRAs under pressure.
+    /// Calling it again replaces the previous hook.
     pub fn set_gpu_manager(&mut self, mgr: Arc<GpuMemoryManager>) {
+        let residency = self.residency();
+        let weak = Arc::downgrade(&mgr);
+        self.evict_hook = Some(mgr.evict_hook(move |victim| residency.evict(&weak, &victim.id)));
         self.gpu_manager = Some(mgr);
     }
 
+    /// Register each model this adapter loads with `rag`, so context packing
+    /// counts its tokens and knows its window
+    pub fn set_rag_engine(&mut self, rag: Arc<RagEngine>) {
+        self.rag_engine = Some(rag);
+    }
+
+    /// Register the loaded model's tokenizer and context window with RAG
+    fn register_with_rag(&self) {
+        let Some(rag) = &self.rag_engine else { return };
+        let backend_guard = self.backend.read();
+        if let Some(wrapper) = backend_guard.as_ref() {
+            let model = &wrapper.0;
+            rag.register_tokenizer(
+                model.model_id(),
+                Arc::new(model.tokenizer().clone()),
+                model.context_length(),
+            );
+        }
+    }
+
+    fn residency(&self) -> Residency {
+        Residency {
+            backend: Arc::clone(&self.backend),
+            loaded_adapters: Arc::clone(&self.loaded_adapters),
+            active_adapters: Arc::clone(&self.active_adapters),
+            model_guard: Arc::clone(&self.model_guard),
+            adapter_guards: Arc::clone(&self.adapter_guards),
+            last_used: Arc::clone(&self.last_used),
+            inference_semaphore: Arc::clone(&self.inference_semaphore),
+        }
+    }
+
     pub fn with_model(model_id: &str) -> Self {
         let mut adapter = Self::new();
         adapter.config.default_model = model_id.to_string();
@@ -323,6 +469,7 @@ impl CandleAdapter {
         // Use the trait method
         let mut backend_guard = self.backend.write();
         let wrapper = backend_guard.as_mut().ok_or("Model not loaded")?;
+        wrapper.1.invalidate();
         let backend = &mut wrapper.0;
 
         if !backend.supports_lora() {
@@ -336,8 +483,62 @@ impl CandleAdapter {
     async fn reload_base_model(&self) -> Result<(), String> {
         let mut backend_guard = self.backend.write();
         let wrapper = backend_guard.as_mut().ok_or("Model not loaded")?;
+        wrapper.1.invalidate();
         wrapper.0.reload_base()
     }
+
+    /// Unload the model (and its LoRA adapters) if no generation has touched
+    /// it for `idle_timeout`. The next request reloads it lazily.
+    fn unload_if_idle(&self, idle_timeout: Duration) -> bool {
+        let idle = self
+            .last_used
+            .read()
+            .is_some_and(|t| t.elapsed() >= idle_timeout);
+        if !idle {
+            return false;
+        }
+        let Some((model_id, adapter_ids)) = self.residency().unload_model(None) else {
+            return false;
+        };
+
+        if let Some(mgr) = &self.gpu_manager {
+            mgr.eviction_registry.unregister(&format!("candle:model:{}", model_id));
+            for adapter_id in &adapter_ids {
+                mgr.eviction_registry.unregister(&format!("candle:adapter:{}", adapter_id));
+            }
+        }
+        runtime::logger("candle").info(&format!(
+            "Unloaded idle model '{}' (idle > {:?}, {} adapters dropped)",
+            model_id,
+            idle_timeout,
+            adapter_ids.len()
+        ));
+        true
+    }
+
+    /// Run `f` against the loaded model's tokenizer. Never triggers a load:
+    /// token counts only make sense for the model that will generate.
+    fn with_loaded_tokenizer<T>(
+        &self,
+        model: Option<&str>,
+        f: impl FnOnce(&tokenizers::Tokenizer) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let backend_guard = self.backend.read();
+        let wrapper = backend_guard
+            .as_ref()
+            .ok_or("Model not loaded — tokenizer is available after the first generation")?;
+        let loaded = wrapper.0.model_id();
+        if let Some(requested) = model {
+            let requested = resolve_model_id(requested);
+            if requested != loaded {
+                return Err(format!(
+                    "Model '{}' is not loaded (loaded: '{}')",
+                    requested, loaded
+                ));
+            }
+        }
+        f(wrapper.0.tokenizer())
+    }
 }
 
 impl Default for CandleAdapter {
@@ -354,9 +555,9 @@ fn inference_inner(
     use_quantized: bool,
     resolved_model: &str,
     prompt: &str,
-    max_tokens: usize,
-    temperature: f64,
-) -> Result<((String, usize), Option<GpuAllocationGuard>), String> {
+    params: &GenerateParams,
+    mut on_text: Option<TextCallback>,
+) -> Result<(GenerateOutput, Option<GpuAllocationGuard>), String> {
     let log = runtime::logger("candle");
 
     let mut backend_guard = backend_arc.write();
@@ -365,7 +566,10 @@ fn inference_inner(
     // Lazy load: if model not loaded yet, load it now
     if backend_guard.is_none() {
         log.info(&format!("Loading model: {}", resolved_model));
-        let model: Box<dyn ModelBackend> = if use_quantized {
+        let model: Box<dyn ModelBackend> = if is_gguf_repo(resolved_model) {
+            load_gguf_by_id(resolved_model)
+                .map_err(|e| format!("Failed to load GGUF model '{}': {e}", resolved_model))?
+        } else if use_quantized {
             load_default_quantized()
                 .map_err(|e| format!("Failed to load quantized model: {e}"))?
         } else {
@@ -401,74 +605,30 @@ fn inference_inner(
             }
         }
 
-        *backend_guard = Some(BackendWrapper(model));
+        *backend_guard = Some(BackendWrapper(model, KvCacheState::default()));
     }
 
     let wrapper = backend_guard.as_mut().expect("just loaded");
-    let gen_result = backends::generate(&mut *wrapper.0, prompt, max_tokens, temperature);
+    let gen_result = backends::generate_streaming(
+        &mut *wrapper.0,
+        prompt,
+        params,
+        Some(&mut wrapper.1),
+        on_text.as_mut().map(|f| f.as_mut() as &mut dyn FnMut(&str) -> bool),
+    );
     gen_result.map(|r| (r, new_model_guard))
 }
 
-#[async_trait]
-impl AIProviderAdapter for CandleAdapter {
-    fn provider_id(&self) -> &str {
-        &self.config.provider_id
-    }
-
-    fn name(&self) -> &str {
-        &self.config.name
-    }
-
-    fn capabilities(&self) -> AdapterCapabilities {
-        AdapterCapabilities {
-            supports_text_generation: true,
-            supports_chat: true,
-            supports_tool_use: false,
-            supports_vision: false,
-            supports_streaming: false,
-            supports_embeddings: false,
-            supports_audio: false,
-            supports_image_generation: false,
-            is_local: true,
-            max_context_window: BF16_PRACTICAL_CONTEXT as u32,
-        }
-    }
-
-    fn api_style(&self) -> ApiStyle {
-        ApiStyle::Local
-    }
-
-    fn default_model(&self) -> &str {
-        &self.config.default_model
-    }
-
-    async fn initialize(&mut self) -> Result<(), String> {
-        let log = runtime::logger("candle");
-        log.info(&format!(
-            "Candle adapter ready (quantized={}, model will load on first use)",
-            self.use_quantized
-        ));
-        // Model loads lazily on first generate_text() call.
-        // This keeps IPC socket creation fast — no 30s model loading during startup.
-        Ok(())
-    }
-
-    async fn shutdown(&mut self) -> Result<(), String> {
-        runtime::logger("candle").info("Shutting down Candle adapter");
-        let mut backend = self.backend.write();
-        *backend = None;
-        // Release all GPU allocation guards
-        *self.model_guard.write() = None;
-        self.adapter_guards.write().clear();
-        Ok(())
-    }
-
-    async fn generate_text(
+impl CandleAdapter {
+    /// Shared body of `generate_text` and `generate_text_stream`.
+    async fn run_generation(
         &self,
         request: TextGenerationRequest,
+        on_text: Option<TextCallback>,
     ) -> Result<TextGenerationResponse, String> {
         let log = runtime::logger("candle");
         let start = std::time::Instant::now();
+        *self.last_used.write() = Some(start);
 
         log.info(&format!(
             "generate_text called, use_quantized={}, self_ptr={:p}",
@@ -574,6 +734,22 @@ impl AIProviderAdapter for CandleAdapter {
         // Without an autorelease pool on the spawn_blocking thread, these objects
         // accumulate in the thread-local default pool and are never released,
         // causing GB-scale memory growth per inference call.
+        let params = GenerateParams::new(max_tokens, temperature)
+            .with_top_p(request.top_p.map(|p| p as f64))
+            .with_top_k(request.top_k.map(|k| k as usize))
+            .with_repeat_penalty(
+                request.repeat_penalty,
+                request.repeat_last_n.map(|n| n as usize),
+            )
+            .with_stop(request.stop_sequences.clone().unwrap_or_default())
+            .with_session(
+                request.handle_id.clone(),
+                request.reset_context.unwrap_or(true),
+            )
+            .with_logprobs(request.logprobs.map(|n| n as usize))
+            .with_seed(request.seed);
+        // Generations are serialized by the permit, so nothing else loads
+        let loading = self.backend.read().is_none();
         let result = tokio::task::spawn_blocking(move || {
             #[cfg(target_os = "macos")]
             extern "C" {
@@ -585,7 +761,7 @@ impl AIProviderAdapter for CandleAdapter {
             let pool = unsafe { objc_autoreleasePoolPush() };
 
             let result = inference_inner(
-                backend_arc, gpu_mgr, use_quantized, &resolved_model, &prompt, max_tokens, temperature,
+                backend_arc, gpu_mgr, use_quantized, &resolved_model, &prompt, &params, on_text,
             );
 
             #[cfg(target_os = "macos")]
@@ -596,12 +772,16 @@ impl AIProviderAdapter for CandleAdapter {
         .await
         .map_err(|e| format!("Inference task panicked: {e}"))?;
 
-        let ((output_text, completion_tokens), new_model_guard) = result?;
+        let (output, new_model_guard) = result?;
+        *self.last_used.write() = Some(Instant::now());
 
         // Store model guard if this was a first load
         if let Some(guard) = new_model_guard {
             *self.model_guard.write() = Some(guard);
         }
+        if loading {
+            self.register_with_rag();
+        }
 
         // Touch eviction registry entries (model + active adapters) on use
         if let Some(mgr) = &self.gpu_manager {
@@ -615,10 +795,10 @@ impl AIProviderAdapter for CandleAdapter {
 
         let duration = start.elapsed();
         let input_tokens = (prompt_len / 4) as u32;
-        let output_tokens = completion_tokens as u32;
+        let output_tokens = output.tokens as u32;
 
         Ok(TextGenerationResponse {
-            text: output_text,
+            text: output.text,
             model: model_id,
             provider: "candle".to_string(),
             finish_reason: FinishReason::Stop,
@@ -644,9 +824,111 @@ impl AIProviderAdapter for CandleAdapter {
                     model_requested: None,
                 })
             },
+            logprobs: output.logprobs,
             error: None,
         })
     }
+}
+
+#[async_trait]
+impl AIProviderAdapter for CandleAdapter {
+    fn provider_id(&self) -> &str {
+        &self.config.provider_id
+    }
+
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn capabilities(&self) -> AdapterCapabilities {
+        AdapterCapabilities {
+            supports_text_generation: true,
+            supports_chat: true,
+            supports_tool_use: false,
+            supports_vision: false,
+            supports_streaming: true,
+            supports_embeddings: false,
+            supports_audio: false,
+            supports_image_generation: false,
+            is_local: true,
+            max_context_window: BF16_PRACTICAL_CONTEXT as u32,
+        }
+    }
+
+    fn api_style(&self) -> ApiStyle {
+        ApiStyle::Local
+    }
+
+    fn default_model(&self) -> &str {
+        &self.config.default_model
+    }
+
+    async fn initialize(&mut self) -> Result<(), String> {
+        let log = runtime::logger("candle");
+        log.info(&format!(
+            "Candle adapter ready (quantized={}, model will load on first use)",
+            self.use_quantized
+        ));
+        // Model loads lazily on first generate_text() call.
+        // This keeps IPC socket creation fast — no 30s model loading during startup.
+        Ok(())
+    }
+
+    async fn shutdown(&mut self) -> Result<(), String> {
+        runtime::logger("candle").info("Shutting down Candle adapter");
+        let mut backend = self.backend.write();
+        *backend = None;
+        // Release all GPU allocation guards
+        *self.model_guard.write() = None;
+        self.adapter_guards.write().clear();
+        Ok(())
+    }
+
+    async fn generate_text(
+        &self,
+        request: TextGenerationRequest,
+    ) -> Result<TextGenerationResponse, String> {
+        self.run_generation(request, None).await
+    }
+
+    async fn generate_text_stream(
+        &self,
+        request: TextGenerationRequest,
+        on_text: TextCallback,
+    ) -> Result<TextGenerationResponse, String> {
+        self.run_generation(request, Some(on_text)).await
+    }
+
+    fn release_if_idle(&self, idle_timeout: Duration) -> bool {
+        self.unload_if_idle(idle_timeout)
+    }
+
+    fn tokenize(
+        &self,
+        model: Option<&str>,
+        text: &str,
+        add_special_tokens: bool,
+    ) -> Result<Vec<u32>, String> {
+        self.with_loaded_tokenizer(model, |tokenizer| {
+            tokenizer
+                .encode(text, add_special_tokens)
+                .map(|encoding| encoding.get_ids().to_vec())
+                .map_err(|e| format!("Tokenization failed: {e}"))
+        })
+    }
+
+    fn detokenize(
+        &self,
+        model: Option<&str>,
+        tokens: &[u32],
+        skip_special_tokens: bool,
+    ) -> Result<String, String> {
+        self.with_loaded_tokenizer(model, |tokenizer| {
+            tokenizer
+                .decode(tokens, skip_special_tokens)
+                .map_err(|e| format!("Decode failed: {e}"))
+        })
+    }
 
     async fn health_check(&self) -> HealthStatus {
         let backend = self.backend.read();
@@ -691,7 +973,7 @@ impl AIProviderAdapter for CandleAdapter {
             context_window: BF16_PRACTICAL_CONTEXT as u32,
             max_output_tokens: Some(4096),
             cost_per_1k_tokens: None,
-            supports_streaming: false,
+            supports_streaming: true,
             supports_tools: false,
         }]
     }
@@ -837,6 +1119,20 @@ mod tests {
         assert!(prompt.ends_with("<|start_header_id|>assistant<|end_header_id|>\n\n"));
     }
 
+    #[test]
+    fn test_idle_unload_needs_idle_loaded_model() {
+        let adapter = CandleAdapter::new();
+        // Never used: nothing to unload
+        assert!(!adapter.unload_if_idle(Duration::ZERO));
+
+        // Marked used but no backend loaded (e.g. load failed)
+        *adapter.last_used.write() = Some(Instant::now());
+        assert!(!adapter.unload_if_idle(Duration::ZERO));
+
+        // Recently used is never idle
+        assert!(!adapter.unload_if_idle(Duration::from_secs(3600)));
+    }
+
     #[test]
     fn test_prompt_format_with_system() {
         let messages = vec![msg("system", "You are a pirate."), msg("user", "Hello!")];
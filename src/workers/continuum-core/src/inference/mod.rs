@@ -20,6 +20,8 @@ pub mod lora;
 pub mod model;
 pub mod quantized;
 pub mod vendored;
+#[path = "../../../shared/weight_bytes.rs"]
+pub mod weight_bytes;
 
 // Re-export commonly used types
 pub use backends::{
@@ -28,4 +30,4 @@ pub use backends::{
 pub use candle_adapter::CandleAdapter;
 pub use lora::{load_lora_adapter, merge_lora_weight, LoRAWeights, LoadedAdapter};
 pub use model::{load_model_by_id, rebuild_with_stacked_lora};
-pub use quantized::{load_default_quantized, load_quantized_model};
+pub use quantized::{load_default_quantized, load_gguf_by_id, load_quantized_model};
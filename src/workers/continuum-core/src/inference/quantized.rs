@@ -114,6 +114,68 @@ pub fn load_quantized_model(
     Ok(backend)
 }
 
+/// Quantization levels to pick when a repo ships several GGUF files, best
+/// size/quality trade-off first. `INFERENCE_GGUF_QUANT` overrides.
+const GGUF_QUANT_PREFERENCE: &[&str] = &["Q4_K_M", "Q5_K_M", "Q4_K_S", "Q6_K", "Q8_0", "Q4_0"];
+
+/// Whether a model ID names a GGUF repository (HF convention: `-GGUF` suffix).
+pub fn is_gguf_repo(model_id: &str) -> bool {
+    model_id.to_ascii_lowercase().ends_with("-gguf")
+}
+
+/// Choose which `.gguf` file in a repo to download.
+///
+/// Split files (`-00001-of-00003.gguf`) are skipped — candle reads a single
+/// file. Among the rest, `preferred` wins, then `GGUF_QUANT_PREFERENCE`
+/// order, then the alphabetically first.
+fn select_gguf_file(files: &[String], preferred: Option<&str>) -> Option<String> {
+    let mut candidates: Vec<&String> = files
+        .iter()
+        .filter(|f| f.to_ascii_lowercase().ends_with(".gguf") && !f.contains("-of-"))
+        .collect();
+    candidates.sort();
+
+    let matches = |file: &str, quant: &str| {
+        file.to_ascii_uppercase()
+            .contains(&quant.to_ascii_uppercase())
+    };
+    preferred
+        .into_iter()
+        .chain(GGUF_QUANT_PREFERENCE.iter().copied())
+        .find_map(|quant| candidates.iter().find(|f| matches(f, quant)))
+        .or(candidates.first())
+        .map(|f| f.to_string())
+}
+
+/// Load any GGUF repo from HuggingFace by ID (e.g.
+/// `bartowski/Qwen2.5-7B-Instruct-GGUF`).
+///
+/// The repo's file list is fetched to pick a quantization; the architecture
+/// comes from the file's GGUF metadata. The tokenizer is taken from the repo
+/// if it ships one, otherwise from the fallbacks in `load_quantized_model`.
+pub fn load_gguf_by_id(
+    repo_id: &str,
+) -> Result<Box<dyn ModelBackend>, Box<dyn std::error::Error + Send + Sync>> {
+    let api = Api::new()?;
+    let info = api
+        .repo(Repo::new(repo_id.to_string(), RepoType::Model))
+        .info()?;
+    let files: Vec<String> = info.siblings.into_iter().map(|s| s.rfilename).collect();
+
+    let preferred = std::env::var("INFERENCE_GGUF_QUANT").ok();
+    let filename = select_gguf_file(&files, preferred.as_deref())
+        .ok_or_else(|| format!("No single-file .gguf weights found in {repo_id}"))?;
+
+    let gguf_path = download_gguf_model(repo_id, &filename)?;
+    let metadata = backends::read_gguf_metadata(&gguf_path)?;
+    runtime::logger("candle").info(&format!(
+        "Selected {} (arch={}, ctx={})",
+        filename, metadata.architecture, metadata.context_length
+    ));
+
+    load_quantized_model(&gguf_path, repo_id, repo_id)
+}
+
 /// Load default quantized model (Q8_0 Llama 3.2 3B).
 pub fn load_default_quantized(
 ) -> Result<Box<dyn ModelBackend>, Box<dyn std::error::Error + Send + Sync>> {
@@ -134,6 +196,51 @@ mod tests {
     use super::super::backends;
     use super::*;
 
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_is_gguf_repo() {
+        assert!(is_gguf_repo("bartowski/Qwen2.5-7B-Instruct-GGUF"));
+        assert!(is_gguf_repo(
+            "hugging-quants/Llama-3.2-3B-Instruct-Q8_0-gguf"
+        ));
+        assert!(!is_gguf_repo("unsloth/Llama-3.2-3B-Instruct"));
+    }
+
+    #[test]
+    fn test_select_gguf_file_prefers_q4_k_m() {
+        let repo = files(&[
+            "README.md",
+            "model-Q8_0.gguf",
+            "model-Q4_K_M.gguf",
+            "model-Q2_K.gguf",
+        ]);
+        assert_eq!(
+            select_gguf_file(&repo, None).as_deref(),
+            Some("model-Q4_K_M.gguf")
+        );
+        assert_eq!(
+            select_gguf_file(&repo, Some("q8_0")).as_deref(),
+            Some("model-Q8_0.gguf")
+        );
+    }
+
+    #[test]
+    fn test_select_gguf_file_skips_split_files() {
+        let repo = files(&[
+            "big-Q4_K_M-00001-of-00002.gguf",
+            "big-Q4_K_M-00002-of-00002.gguf",
+            "big-IQ3_XS.gguf",
+        ]);
+        assert_eq!(
+            select_gguf_file(&repo, None).as_deref(),
+            Some("big-IQ3_XS.gguf")
+        );
+        assert_eq!(select_gguf_file(&files(&["config.json"]), None), None);
+    }
+
     #[test]
     #[ignore] // Requires model download
     fn test_context_length_from_model() {
@@ -151,8 +258,12 @@ mod tests {
         let mut backend = load_default_quantized().expect("Failed to load");
 
         let prompt = "<|begin_of_text|><|start_header_id|>user<|end_header_id|>\n\nSay hello.<|eot_id|><|start_header_id|>assistant<|end_header_id|>\n\n";
-        let (output, tokens) =
-            backends::generate(&mut *backend, prompt, 30, 0.3).expect("Generation failed");
+        let (output, tokens) = backends::generate(
+            &mut *backend,
+            prompt,
+            &backends::GenerateParams::new(30, 0.3),
+        )
+        .expect("Generation failed");
 
         println!("Generated {} tokens: {}", tokens, output);
         assert!(!output.contains('\u{FFFD}'), "Output contains garbage");
@@ -171,7 +282,11 @@ mod tests {
             filler
         );
 
-        let result = backends::generate(&mut *backend, &prompt, 10, 0.3);
+        let result = backends::generate(
+            &mut *backend,
+            &prompt,
+            &backends::GenerateParams::new(10, 0.3),
+        );
         assert!(result.is_err(), "Should reject oversized prompt");
     }
 }
@@ -34,7 +34,7 @@ use crate::modules::vision::VisionModule;
 /// - Modular runtime routes commands through ServiceModule trait (Phase 1+)
 use crate::persona::{ChannelRegistry, PersonaState};
 use crate::rag::RagEngine;
-use crate::runtime::{CommandResult, Runtime};
+use crate::runtime::{server_stats, with_connection, CommandResult, Runtime};
 use crate::system_resources::SystemResourceMonitor;
 use crate::{log_debug, log_error, log_info};
 use dashmap::DashMap;
@@ -42,6 +42,7 @@ use serde::{Deserialize, Serialize};
 use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use ts_rs::TS;
 use uuid::Uuid;
@@ -83,56 +84,9 @@ pub struct InboxMessageRequest {
 // Memory Diagnostics — track RSS per IPC command to find leaks
 // ============================================================================
 
-/// Get current process RSS in MB using macOS task_info API.
-/// Returns actual resident memory (not peak like getrusage ru_maxrss).
-#[cfg(target_os = "macos")]
+/// Get current process RSS in MB (see runtime::server_stats).
 fn current_rss_mb() -> u64 {
-    #[repr(C)]
-    struct MachTaskBasicInfo {
-        virtual_size: u64,
-        resident_size: u64,
-        resident_size_max: u64,
-        user_time_seconds: u32,
-        user_time_microseconds: u32,
-        system_time_seconds: u32,
-        system_time_microseconds: u32,
-        policy: i32,
-        suspend_count: i32,
-    }
-
-    extern "C" {
-        fn mach_task_self() -> u32;
-        fn task_info(
-            target_task: u32,
-            flavor: u32,
-            task_info: *mut MachTaskBasicInfo,
-            task_info_count: *mut u32,
-        ) -> i32;
-    }
-
-    const MACH_TASK_BASIC_INFO: u32 = 20;
-
-    unsafe {
-        let mut info: MachTaskBasicInfo = std::mem::zeroed();
-        let mut count =
-            (std::mem::size_of::<MachTaskBasicInfo>() / std::mem::size_of::<u32>()) as u32;
-        let kr = task_info(
-            mach_task_self(),
-            MACH_TASK_BASIC_INFO,
-            &mut info,
-            &mut count,
-        );
-        if kr == 0 {
-            info.resident_size / (1024 * 1024)
-        } else {
-            0
-        }
-    }
-}
-
-#[cfg(not(target_os = "macos"))]
-fn current_rss_mb() -> u64 {
-    0 // No-op on non-macOS
+    crate::runtime::server_stats::current_rss_bytes() / (1024 * 1024)
 }
 
 /// Periodic RSS reporter — logs every 10s so we can see growth trends.
@@ -346,6 +300,10 @@ fn send_binary_frame(
     stream.flush()
 }
 
+/// Monotonic id per accepted connection; commands run with it as
+/// `runtime::current_connection_id()`.
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
 /// Handle a single IPC client connection with concurrent request processing.
 ///
 /// Architecture:
@@ -358,6 +316,8 @@ fn send_binary_frame(
 /// RAGComposer (global-awareness, semantic-memory, etc.) were serialized per-connection.
 fn handle_client(stream: UnixStream, state: Arc<ServerState>) -> std::io::Result<()> {
     let peer_addr = stream.peer_addr()?;
+    let connection_id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+    let _connection = server_stats().track_connection();
     log_debug!("ipc", "server", "Client connected: {:?}", peer_addr);
 
     let reader = BufReader::new(stream.try_clone()?);
@@ -393,8 +353,16 @@ fn handle_client(stream: UnixStream, state: Arc<ServerState>) -> std::io::Result
 
     // Reader loop — parse requests and dispatch to tokio for concurrent processing.
     // No longer blocks waiting for handle_request() to complete before reading next request.
+    // A read error still falls through to the disconnect cleanup below
+    let mut read_error = None;
     for line in reader.lines() {
-        let line = line?;
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                read_error = Some(e);
+                break;
+            }
+        };
         if line.is_empty() {
             continue;
         }
@@ -433,10 +401,17 @@ fn handle_client(stream: UnixStream, state: Arc<ServerState>) -> std::io::Result
         let state = state.clone();
         let tx = tx.clone();
         let rt_handle = state.rt_handle.clone();
+        let request = server_stats().track_request();
         rt_handle.spawn(async move {
+            let _request = request;
             let handle_result = if let Some(ref cmd) = command {
                 let rss_before = current_rss_mb();
-                let result = state.runtime.route_command(cmd, json_value.clone()).await;
+                // Lets modules scope state (e.g. data transactions) to this connection
+                let result = with_connection(
+                    connection_id,
+                    state.runtime.route_command(cmd, json_value.clone()),
+                )
+                .await;
                 let rss_after = current_rss_mb();
                 log_command_rss_delta(cmd, rss_before, rss_after);
 
@@ -470,6 +445,20 @@ fn handle_client(stream: UnixStream, state: Arc<ServerState>) -> std::io::Result
                         json_header: Response::success(metadata),
                         binary_data: data,
                     },
+                    Some(Ok(CommandResult::Stream(mut chunks))) => {
+                        // Forward each chunk as its own frame. A send error means the
+                        // writer thread exited (client gone) — dropping `chunks` then
+                        // tells the producer to stop.
+                        while let Some(chunk) = chunks.recv().await {
+                            if tx
+                                .send((request_id, HandleResult::Json(Response::success(chunk))))
+                                .is_err()
+                            {
+                                break;
+                            }
+                        }
+                        return;
+                    }
                     Some(Err(e)) => HandleResult::Json(Response::error(e)),
                     None => HandleResult::Json(Response::error(format!(
                         "Unknown command: '{}'. No module registered for this command prefix.",
@@ -489,8 +478,17 @@ fn handle_client(stream: UnixStream, state: Arc<ServerState>) -> std::io::Result
     drop(tx);
     let _ = writer_handle.join();
 
+    // Release connection-scoped module state (rolls back open transactions)
+    let runtime = state.runtime.clone();
+    state
+        .rt_handle
+        .spawn(async move { runtime.connection_closed(connection_id).await });
+
     log_debug!("ipc", "server", "Client disconnected: {:?}", peer_addr);
-    Ok(())
+    match read_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
 }
 
 // ============================================================================
@@ -633,6 +631,110 @@ mod tests {
         assert_eq!(parsed["requestId"], 42);
     }
 
+    // ========================================================================
+    // Streaming Round-Trip: CommandResult::Stream over a socket pair
+    // ========================================================================
+
+    /// Emits three chunks for `test/stream`, the last one `done: true`
+    struct StreamModule;
+
+    #[async_trait::async_trait]
+    impl crate::runtime::ServiceModule for StreamModule {
+        fn config(&self) -> crate::runtime::ModuleConfig {
+            crate::runtime::ModuleConfig {
+                name: "test-stream",
+                priority: crate::runtime::ModulePriority::Normal,
+                command_prefixes: &["test/"],
+                event_subscriptions: &[],
+                needs_dedicated_thread: false,
+                max_concurrency: 0,
+                tick_interval: None,
+            }
+        }
+
+        async fn initialize(&self, _ctx: &crate::runtime::ModuleContext) -> Result<(), String> {
+            Ok(())
+        }
+
+        async fn handle_command(
+            &self,
+            _command: &str,
+            _params: serde_json::Value,
+        ) -> Result<CommandResult, String> {
+            let (tx, rx) = tokio::sync::mpsc::channel(3);
+            for (i, token) in ["a", "b"].into_iter().enumerate() {
+                let _ =
+                    tx.try_send(serde_json::json!({ "token": token, "index": i, "done": false }));
+            }
+            let _ = tx.try_send(serde_json::json!({ "done": true, "generated_tokens": 2 }));
+            Ok(CommandResult::Stream(rx))
+        }
+
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+    }
+
+    fn read_frame(stream: &mut UnixStream) -> serde_json::Value {
+        let mut len_buf = [0u8; 4];
+        std::io::Read::read_exact(stream, &mut len_buf).unwrap();
+        let mut payload = vec![0u8; u32::from_be_bytes(len_buf) as usize];
+        std::io::Read::read_exact(stream, &mut payload).unwrap();
+        serde_json::from_slice(&payload).unwrap()
+    }
+
+    #[test]
+    fn test_stream_result_roundtrip() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let runtime = Arc::new(Runtime::new());
+        runtime.register(Arc::new(StreamModule));
+        let (pressure_tx, pressure_rx) = tokio::sync::watch::channel(0.0f32);
+        let state = Arc::new(ServerState::new_with_shared_state(
+            rt.handle().clone(),
+            Arc::new(crate::memory::PersonaMemoryManager::new(Arc::new(
+                crate::memory::embedding::DeterministicEmbeddingProvider,
+            ))),
+            runtime,
+            Arc::new(DashMap::new()),
+            Arc::new(RagEngine::new()),
+            Arc::new(crate::live::session::voice_service::VoiceService::new()),
+            Arc::new(crate::live::audio::buffer::AudioBufferPool::new()),
+            Arc::new(DashMap::new()),
+            Arc::new(DashMap::new()),
+            Arc::new(GpuMemoryManager::new_for_test(
+                0,
+                "Test GPU".to_string(),
+                0,
+                0,
+                0,
+                0,
+                pressure_tx,
+                pressure_rx,
+            )),
+        ));
+
+        let (server, mut client) = UnixStream::pair().unwrap();
+        let server_thread = std::thread::spawn(move || handle_client(server, state));
+
+        client
+            .write_all(b"{\"command\":\"test/stream\",\"requestId\":7}\n")
+            .unwrap();
+        let frames: Vec<serde_json::Value> = (0..3).map(|_| read_frame(&mut client)).collect();
+
+        // Every chunk is its own frame under the caller's requestId, in order
+        for frame in &frames {
+            assert_eq!(frame["success"], true);
+            assert_eq!(frame["requestId"], 7);
+        }
+        assert_eq!(frames[0]["result"]["token"], "a");
+        assert_eq!(frames[1]["result"]["token"], "b");
+        assert_eq!(frames[2]["result"]["done"], true);
+        assert_eq!(frames[2]["result"]["generated_tokens"], 2);
+
+        client.shutdown(std::net::Shutdown::Both).unwrap();
+        server_thread.join().unwrap().unwrap();
+    }
+
     // ========================================================================
     // Integration Test: Full IPC Round-Trip via Unix Socket
     // Requires: continuum-core-server running (cargo test --ignored)
@@ -814,17 +916,20 @@ pub fn start_server(
     runtime.register(Arc::new(MemoryModule::new(memory_state)));
 
     // Phase 3: RagModule (batched RAG composition with parallel Rayon loading)
-    let rag_state = Arc::new(RagState::new(memory_manager.clone()));
+    let rag_state = Arc::new(RagState::new(memory_manager.clone(), rag_engine.clone()));
     runtime.register(Arc::new(RagModule::new(rag_state)));
 
     // Phase 3: VoiceModule (wraps VoiceService, CallManager, AudioBufferPool)
     let voice_service = Arc::new(crate::live::session::voice_service::VoiceService::new());
     let audio_pool = Arc::new(crate::live::audio::buffer::AudioBufferPool::new());
-    let voice_state = Arc::new(VoiceState::new(
-        voice_service.clone(),
-        livekit_manager.clone(),
-        audio_pool.clone(),
-    ));
+    let voice_state = Arc::new(
+        VoiceState::new(
+            voice_service.clone(),
+            livekit_manager.clone(),
+            audio_pool.clone(),
+        )
+        .with_persona_states(channel_registries.clone()),
+    );
     runtime.register(Arc::new(VoiceModule::new(voice_state)));
 
     // Phase 3: CodeModule (wraps file engines and shell sessions per-persona)
@@ -870,9 +975,12 @@ pub fn start_server(
     // AIProviderModule: Unified AI provider for cloud and local inference
     // Provides ai/generate, ai/providers/list, ai/providers/health
     // Routes to DeepSeek, Anthropic, OpenAI, Together, Groq, Fireworks, XAI, Google
-    runtime.register(Arc::new(AIProviderModule::with_gpu_manager(
-        gpu_manager.clone(),
-    )));
+    // Shared with FFI generation so it doesn't load a second copy of a model
+    let ai_provider = Arc::new(
+        AIProviderModule::with_gpu_manager(gpu_manager.clone()).with_rag_engine(rag_engine.clone()),
+    );
+    crate::ffi::set_ai_provider(ai_provider.clone());
+    runtime.register(ai_provider);
 
     // SentinelModule: Concurrent, fault-tolerant build/task execution
     // Provides sentinel/execute, sentinel/status, sentinel/cancel, sentinel/list
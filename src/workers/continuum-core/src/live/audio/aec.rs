@@ -0,0 +1,245 @@
+//! Acoustic Echo Cancellation
+//!
+//! On speakerphone the mic picks up the persona's own TTS, which would be
+//! transcribed as if the caller said it. `AecStage` subtracts that echo from
+//! the near-end (mic) signal. An NLMS adaptive filter models the path from
+//! speaker to mic, learned from the far-end reference: the output side
+//! pushes every frame it plays into `AecReference`, and the stage consumes
+//! one reference sample per mic sample.
+//!
+//! While the caller talks over the persona (double-talk) the mic holds more
+//! than echo, and adapting then would teach the filter the caller's voice.
+//! A Geigel detector freezes adaptation whenever the mic is louder than the
+//! echo could be, i.e. above half the recent reference peak.
+
+use super::stage_chain::AudioStage;
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Reference audio held for the mic to catch up with (1s); older is dropped
+const MAX_REFERENCE_SAMPLES: usize = AUDIO_SAMPLE_RATE as usize;
+
+/// Mic level, relative to the reference peak, that signals double-talk
+const GEIGEL_THRESHOLD: f32 = 0.5;
+
+/// Samples adaptation stays frozen after double-talk (15ms)
+const DOUBLE_TALK_HOLD: usize = AUDIO_SAMPLE_RATE as usize * 15 / 1000;
+
+/// Keeps the NLMS step finite when the reference is near silent
+const REGULARIZATION: f32 = 1e-3;
+
+/// Echo canceller settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AecConfig {
+    /// Filter length; must cover the speaker-to-mic delay plus the room's
+    /// echo tail (1024 taps = 64ms at 16kHz)
+    pub filter_taps: usize,
+    /// NLMS step size (0-1): higher converges faster but tracks less precisely
+    pub step_size: f32,
+}
+
+impl Default for AecConfig {
+    fn default() -> Self {
+        Self {
+            filter_taps: 1024,
+            step_size: 0.3,
+        }
+    }
+}
+
+/// NLMS filter state
+struct EchoCanceller {
+    weights: Vec<f32>,
+    /// Reference ring stored twice over, so the last `taps` samples are
+    /// always one contiguous slice
+    history: Vec<f32>,
+    position: usize,
+    /// Sum of squares over the window
+    energy: f32,
+    step_size: f32,
+    double_talk_hold: usize,
+}
+
+impl EchoCanceller {
+    fn new(config: AecConfig) -> Self {
+        let taps = config.filter_taps.max(1);
+        Self {
+            weights: vec![0.0; taps],
+            history: vec![0.0; taps * 2],
+            position: 0,
+            energy: 0.0,
+            step_size: config.step_size.clamp(0.0, 1.0),
+            double_talk_hold: 0,
+        }
+    }
+
+    /// Cancel echo from one mic sample, given the reference sample played
+    /// at the same time
+    fn process(&mut self, near: f32, reference: f32) -> f32 {
+        let taps = self.weights.len();
+        let oldest = self.history[self.position];
+        self.history[self.position] = reference;
+        self.history[self.position + taps] = reference;
+        self.energy = (self.energy + reference * reference - oldest * oldest).max(0.0);
+        let window = &self.history[self.position + 1..=self.position + taps];
+        self.position = (self.position + 1) % taps;
+        if self.position == 0 {
+            // Resum once per window so rounding in the running sum can't drift
+            self.energy = window.iter().map(|x| x * x).sum();
+        }
+
+        let mut estimate = 0.0;
+        let mut peak = 0.0f32;
+        for (w, x) in self.weights.iter().zip(window) {
+            estimate += w * x;
+            peak = peak.max(x.abs());
+        }
+        let error = near - estimate;
+
+        if near.abs() > GEIGEL_THRESHOLD * peak {
+            self.double_talk_hold = DOUBLE_TALK_HOLD;
+        } else if self.double_talk_hold > 0 {
+            self.double_talk_hold -= 1;
+        } else if peak > 0.0 {
+            let gain = self.step_size * error / (self.energy + REGULARIZATION);
+            for (w, x) in self.weights.iter_mut().zip(window) {
+                *w += gain * x;
+            }
+        }
+        error
+    }
+}
+
+/// Output side of an `AecStage`: push each frame as it is played
+#[derive(Clone)]
+pub struct AecReference(Arc<Mutex<VecDeque<i16>>>);
+
+impl AecReference {
+    pub fn push(&self, samples: &[i16]) {
+        let mut queue = self.0.lock();
+        queue.extend(samples);
+        let excess = queue.len().saturating_sub(MAX_REFERENCE_SAMPLES);
+        queue.drain(..excess);
+    }
+}
+
+/// Removes the persona's own playback from the mic signal
+pub struct AecStage {
+    canceller: EchoCanceller,
+    reference: Arc<Mutex<VecDeque<i16>>>,
+}
+
+impl AecStage {
+    pub fn new(config: AecConfig) -> (Self, AecReference) {
+        let reference = Arc::new(Mutex::new(VecDeque::new()));
+        let stage = Self {
+            canceller: EchoCanceller::new(config),
+            reference: reference.clone(),
+        };
+        (stage, AecReference(reference))
+    }
+}
+
+impl AudioStage for AecStage {
+    fn name(&self) -> &str {
+        "aec"
+    }
+
+    fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+        let mut reference = self.reference.lock();
+        samples
+            .iter()
+            .map(|&near| {
+                // Nothing playing: the reference is silence
+                let far = reference.pop_front().unwrap_or(0);
+                let out = self
+                    .canceller
+                    .process(near as f32 / 32768.0, far as f32 / 32768.0);
+                (out * 32768.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic white-ish noise, standing in for TTS
+    fn noise(count: usize, seed: u32) -> Vec<i16> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                ((state >> 16) as i16) / 4
+            })
+            .collect()
+    }
+
+    /// The far end as heard by the mic: delayed and attenuated
+    fn echo_of(far: &[i16], delay: usize) -> Vec<i16> {
+        (0..far.len())
+            .map(|i| {
+                if i < delay {
+                    0
+                } else {
+                    (far[i - delay] as f32 * 0.4) as i16
+                }
+            })
+            .collect()
+    }
+
+    fn energy(samples: &[i16]) -> f64 {
+        samples.iter().map(|&s| (s as f64).powi(2)).sum()
+    }
+
+    #[test]
+    fn test_cancels_echo() {
+        let (mut stage, reference) = AecStage::new(AecConfig {
+            filter_taps: 64,
+            step_size: 0.5,
+        });
+        let far = noise(32_000, 7);
+        let mic = echo_of(&far, 20);
+
+        let mut out = Vec::new();
+        for (far, mic) in far.chunks(320).zip(mic.chunks(320)) {
+            reference.push(far);
+            out.extend(stage.process(mic));
+        }
+
+        // Last second: echo reduced by over 20dB
+        let tail = 16_000..;
+        assert!(energy(&out[tail.clone()]) * 100.0 < energy(&mic[tail]));
+    }
+
+    #[test]
+    fn test_near_end_speech_passes_through() {
+        let (mut stage, reference) = AecStage::new(AecConfig {
+            filter_taps: 64,
+            step_size: 0.5,
+        });
+        let far = noise(16_000, 3);
+        for (far, mic) in far.chunks(320).zip(echo_of(&far, 20).chunks(320)) {
+            reference.push(far);
+            stage.process(mic);
+        }
+
+        // Persona done, caller talking: nothing to cancel
+        stage.process(&[0; 320]);
+        let speech = noise(3_200, 11);
+        assert_eq!(stage.process(&speech), speech);
+
+        // Caller talks over the persona: their speech survives, the
+        // converged filter isn't thrown off by it
+        let far = noise(3_200, 5);
+        let echo = echo_of(&far, 20);
+        let mixed: Vec<i16> = speech.iter().zip(&echo).map(|(s, e)| s + e).collect();
+        reference.push(&far);
+        let out = stage.process(&mixed);
+        let residual: Vec<i16> = out.iter().zip(&speech).map(|(o, s)| o - s).collect();
+        assert!(energy(&residual[320..]) * 10.0 < energy(&echo[320..]));
+    }
+}
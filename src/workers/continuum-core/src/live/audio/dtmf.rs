@@ -0,0 +1,352 @@
+//! DTMF (keypad tone) detection
+//!
+//! Goertzel filters at the eight DTMF frequencies run over fixed 25ms blocks.
+//! A block counts as a digit when one row and one column tone together hold
+//! most of its energy (speech and music spread theirs out), neither dominates
+//! the other by more than the allowed twist, and no second row/column tone
+//! competes. A digit is reported once it holds for `MIN_ON_BLOCKS` blocks,
+//! and not again until the line has been quiet for `MIN_OFF_BLOCKS` — so one
+//! keypress is one event however long the key is held.
+//!
+//! Carriers that signal DTMF out of band (Twilio `dtmf` messages) produce the
+//! same `DtmfEvent`, tagged with its source.
+
+use super::stage_chain::AudioStage;
+use crate::live::handle::Handle;
+use std::f32::consts::PI;
+use tokio::sync::broadcast;
+
+/// Row (low group) frequencies in Hz
+const ROW_FREQS: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+
+/// Column (high group) frequencies in Hz
+const COL_FREQS: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
+
+/// Keypad layout, indexed [row][col]
+const KEYPAD: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+/// Detection block length
+const BLOCK_MS: u32 = 25;
+
+/// Minimum block RMS to consider (~-46 dBFS); quieter blocks are silence
+const MIN_BLOCK_RMS: f32 = 160.0;
+
+/// Share of block energy the row + column tones must hold
+const MIN_TONE_ENERGY_RATIO: f32 = 0.6;
+
+/// Max power ratio between row and column tone (~8 dB twist)
+const MAX_TWIST: f32 = 6.3;
+
+/// A competing tone in the same group must be this much weaker (power)
+const MIN_PEAK_RATIO: f32 = 6.0;
+
+/// Consecutive blocks a digit must hold before it's reported (50ms)
+const MIN_ON_BLOCKS: u32 = 2;
+
+/// Consecutive quiet blocks before the same digit can fire again (50ms)
+const MIN_OFF_BLOCKS: u32 = 2;
+
+/// Buffered DTMF events per subscriber
+const DTMF_CHANNEL_CAPACITY: usize = 32;
+
+/// Where a DTMF digit was detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DtmfSource {
+    /// Goertzel detection on the audio itself
+    InBand,
+    /// Signalled by the carrier (e.g. Twilio `dtmf` message)
+    OutOfBand,
+}
+
+/// A confirmed keypress
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DtmfEvent {
+    pub handle: Handle,
+    pub digit: char,
+    pub source: DtmfSource,
+}
+
+/// Validate a carrier-supplied digit string ("0"-"9", "*", "#", "A"-"D")
+pub fn parse_digit(digit: &str) -> Option<char> {
+    let mut chars = digit.chars();
+    let c = chars.next()?.to_ascii_uppercase();
+    if chars.next().is_some() {
+        return None;
+    }
+    KEYPAD.iter().flatten().find(|&&k| k == c).copied()
+}
+
+/// Streaming Goertzel DTMF detector with debouncing
+pub struct DtmfDetector {
+    block_size: usize,
+    row_coeffs: [f32; 4],
+    col_coeffs: [f32; 4],
+    block: Vec<i16>,
+    candidate: Option<char>,
+    candidate_blocks: u32,
+    /// Digit already reported for the current keypress
+    active: Option<char>,
+    quiet_blocks: u32,
+}
+
+impl DtmfDetector {
+    pub fn new(sample_rate: u32) -> Self {
+        let coeff = |f: f32| 2.0 * (2.0 * PI * f / sample_rate as f32).cos();
+        let block_size = (sample_rate * BLOCK_MS / 1000) as usize;
+        Self {
+            block_size,
+            row_coeffs: ROW_FREQS.map(coeff),
+            col_coeffs: COL_FREQS.map(coeff),
+            block: Vec::with_capacity(block_size),
+            candidate: None,
+            candidate_blocks: 0,
+            active: None,
+            quiet_blocks: 0,
+        }
+    }
+
+    /// Feed samples; returns digits confirmed by this call (usually none)
+    pub fn process(&mut self, samples: &[i16]) -> Vec<char> {
+        let mut digits = Vec::new();
+        for &sample in samples {
+            self.block.push(sample);
+            if self.block.len() == self.block_size {
+                let detected = self.detect_block();
+                self.block.clear();
+                if let Some(digit) = self.debounce(detected) {
+                    digits.push(digit);
+                }
+            }
+        }
+        digits
+    }
+
+    /// Forget any partial block and keypress state
+    pub fn reset(&mut self) {
+        self.block.clear();
+        self.candidate = None;
+        self.candidate_blocks = 0;
+        self.active = None;
+        self.quiet_blocks = 0;
+    }
+
+    fn debounce(&mut self, detected: Option<char>) -> Option<char> {
+        let Some(digit) = detected else {
+            self.candidate = None;
+            self.candidate_blocks = 0;
+            self.quiet_blocks += 1;
+            if self.quiet_blocks >= MIN_OFF_BLOCKS {
+                self.active = None;
+            }
+            return None;
+        };
+
+        self.quiet_blocks = 0;
+        if self.candidate == Some(digit) {
+            self.candidate_blocks += 1;
+        } else {
+            self.candidate = Some(digit);
+            self.candidate_blocks = 1;
+        }
+        if self.candidate_blocks >= MIN_ON_BLOCKS && self.active != Some(digit) {
+            self.active = Some(digit);
+            return Some(digit);
+        }
+        None
+    }
+
+    fn detect_block(&self) -> Option<char> {
+        let n = self.block.len() as f32;
+        let energy: f32 = self.block.iter().map(|&s| (s as f32).powi(2)).sum();
+        if (energy / n).sqrt() < MIN_BLOCK_RMS {
+            return None;
+        }
+
+        let rows = self.row_coeffs.map(|c| goertzel_power(&self.block, c));
+        let cols = self.col_coeffs.map(|c| goertzel_power(&self.block, c));
+        let (row, row_power) = strongest(&rows)?;
+        let (col, col_power) = strongest(&cols)?;
+
+        // A sinusoid's Goertzel power is ~N/2 times its share of block energy
+        let tone_ratio = 2.0 * (row_power + col_power) / (n * energy);
+        let twist = row_power.max(col_power) / row_power.min(col_power).max(f32::MIN_POSITIVE);
+        if tone_ratio < MIN_TONE_ENERGY_RATIO || twist > MAX_TWIST {
+            return None;
+        }
+        Some(KEYPAD[row][col])
+    }
+}
+
+/// Index and power of the strongest tone, if it clearly beats the others
+fn strongest(powers: &[f32; 4]) -> Option<(usize, f32)> {
+    let (best, &power) = powers
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    let competing = powers
+        .iter()
+        .enumerate()
+        .any(|(i, &p)| i != best && p * MIN_PEAK_RATIO > power);
+    (!competing).then_some((best, power))
+}
+
+/// Goertzel power of one frequency (`coeff = 2cos(2πf/fs)`)
+fn goertzel_power(samples: &[i16], coeff: f32) -> f32 {
+    let (mut s1, mut s2) = (0.0f32, 0.0f32);
+    for &sample in samples {
+        let s0 = sample as f32 + coeff * s1 - s2;
+        s2 = s1;
+        s1 = s0;
+    }
+    s1 * s1 + s2 * s2 - coeff * s1 * s2
+}
+
+/// Pass-through AudioStage that reports keypresses on a broadcast channel
+pub struct DtmfStage {
+    handle: Handle,
+    detector: DtmfDetector,
+    events: broadcast::Sender<DtmfEvent>,
+}
+
+impl DtmfStage {
+    pub fn new(handle: Handle, sample_rate: u32) -> Self {
+        let (events, _) = broadcast::channel(DTMF_CHANNEL_CAPACITY);
+        Self::with_sender(handle, sample_rate, events)
+    }
+
+    /// Publish onto an existing channel, e.g. one that also carries a
+    /// carrier's out-of-band events for the same call.
+    pub fn with_sender(
+        handle: Handle,
+        sample_rate: u32,
+        events: broadcast::Sender<DtmfEvent>,
+    ) -> Self {
+        Self {
+            handle,
+            detector: DtmfDetector::new(sample_rate),
+            events,
+        }
+    }
+
+    /// Subscribe before handing the stage to a StageChain
+    pub fn subscribe(&self) -> broadcast::Receiver<DtmfEvent> {
+        self.events.subscribe()
+    }
+}
+
+impl AudioStage for DtmfStage {
+    fn name(&self) -> &str {
+        "dtmf"
+    }
+
+    fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+        for digit in self.detector.process(samples) {
+            // No subscribers is fine
+            let _ = self.events.send(DtmfEvent {
+                handle: self.handle,
+                digit,
+                source: DtmfSource::InBand,
+            });
+        }
+        samples.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_constants::AUDIO_SAMPLE_RATE;
+
+    fn tone(digit: char, ms: u32, sample_rate: u32) -> Vec<i16> {
+        let index = KEYPAD.iter().flatten().position(|&k| k == digit).unwrap();
+        let (row, col) = (ROW_FREQS[index / 4], COL_FREQS[index % 4]);
+        let count = (sample_rate * ms / 1000) as usize;
+        (0..count)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                (6000.0 * ((2.0 * PI * row * t).sin() + (2.0 * PI * col * t).sin())) as i16
+            })
+            .collect()
+    }
+
+    fn silence(ms: u32, sample_rate: u32) -> Vec<i16> {
+        vec![0; (sample_rate * ms / 1000) as usize]
+    }
+
+    #[test]
+    fn test_detects_every_key_once() {
+        for sample_rate in [8000, AUDIO_SAMPLE_RATE] {
+            let mut detector = DtmfDetector::new(sample_rate);
+            let mut digits = Vec::new();
+            for &key in KEYPAD.iter().flatten() {
+                // Held for 200ms — still a single keypress
+                digits.extend(detector.process(&tone(key, 200, sample_rate)));
+                digits.extend(detector.process(&silence(80, sample_rate)));
+            }
+            let expected: Vec<char> = KEYPAD.iter().flatten().copied().collect();
+            assert_eq!(digits, expected, "{sample_rate}Hz");
+        }
+    }
+
+    #[test]
+    fn test_repeated_key_needs_a_gap() {
+        let mut detector = DtmfDetector::new(AUDIO_SAMPLE_RATE);
+        let mut audio = tone('5', 100, AUDIO_SAMPLE_RATE);
+        audio.extend(silence(20, AUDIO_SAMPLE_RATE));
+        audio.extend(tone('5', 100, AUDIO_SAMPLE_RATE));
+        audio.extend(silence(100, AUDIO_SAMPLE_RATE));
+        audio.extend(tone('5', 100, AUDIO_SAMPLE_RATE));
+        // A 20ms dropout is one press; a 100ms gap is a second one
+        assert_eq!(detector.process(&audio), vec!['5', '5']);
+    }
+
+    #[test]
+    fn test_single_tones_and_noise_rejected() {
+        let mut detector = DtmfDetector::new(AUDIO_SAMPLE_RATE);
+        let single: Vec<i16> = (0..8000)
+            .map(|i| (8000.0 * (2.0 * PI * 770.0 * i as f32 / 16000.0).sin()) as i16)
+            .collect();
+        assert!(detector.process(&single).is_empty());
+
+        let mut seed = 12345u32;
+        let noise: Vec<i16> = (0..8000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                ((seed >> 16) as i16) / 4
+            })
+            .collect();
+        assert!(detector.process(&noise).is_empty());
+    }
+
+    #[test]
+    fn test_stage_passes_audio_and_emits_events() {
+        let handle = Handle::new();
+        let mut stage = DtmfStage::new(handle, AUDIO_SAMPLE_RATE);
+        let mut events = stage.subscribe();
+        let audio = tone('#', 100, AUDIO_SAMPLE_RATE);
+        assert_eq!(stage.process(&audio), audio);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            DtmfEvent {
+                handle,
+                digit: '#',
+                source: DtmfSource::InBand
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_digit() {
+        assert_eq!(parse_digit("7"), Some('7'));
+        assert_eq!(parse_digit("#"), Some('#'));
+        assert_eq!(parse_digit("b"), Some('B'));
+        assert_eq!(parse_digit("12"), None);
+        assert_eq!(parse_digit("x"), None);
+        assert_eq!(parse_digit(""), None);
+    }
+}
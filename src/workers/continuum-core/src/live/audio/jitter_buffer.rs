@@ -0,0 +1,375 @@
+//! Jitter Buffer
+//!
+//! Network audio (WebRTC RTP, Twilio media) arrives in bursts and out of
+//! order, while the stages after it expect one frame per frame interval.
+//! The transport pushes each frame with its sequence number as it arrives
+//! (`JitterInput::push`); the chain is clocked once per frame interval and
+//! `JitterBufferStage` plays out the next frame in sequence order.
+//!
+//! Playout starts once `target_depth` frames are buffered, which is the
+//! latency spent absorbing jitter. A frame still missing when its turn comes
+//! is concealed — the last frame repeated at falling gain (PLC), or silence —
+//! so the timeline keeps its length. If it turns up afterwards it is late and
+//! dropped, unless it is more than `late_discard` frames behind: no straggler
+//! is that late, so the sender restarted its numbering and the buffer
+//! resyncs on it. A burst that leaves more than twice the target depth
+//! buffered skips the oldest frames to bring latency back down. When the
+//! buffer runs dry (sender paused), playout stops until it has refilled.
+//!
+//! This is where a network stream's timeline is restored, so played frames
+//! are stamped here: `seq` in playout order, `pts_ms` from the samples
+//! played so far, concealment included.
+
+use super::stage_chain::AudioStage;
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
+use crate::live::types::{AudioFrame, FrameSequencer};
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Samples in a 20ms frame, the concealment length before any frame arrives
+const DEFAULT_FRAME_SAMPLES: usize = AUDIO_SAMPLE_RATE as usize * 20 / 1000;
+
+/// Consecutive concealed frames before PLC fades to silence
+const MAX_PLC_FRAMES: u32 = 3;
+
+/// Jitter buffer settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitterConfig {
+    /// Frames buffered before playout starts (3 × 20ms = 60ms of latency)
+    pub target_depth: usize,
+    /// A frame arriving at most this many frames after its turn is dropped
+    /// as late; one further behind resyncs the buffer
+    pub late_discard: u16,
+    /// Conceal missing frames by repeating the last one (fading out) rather
+    /// than with silence
+    pub plc: bool,
+}
+
+impl Default for JitterConfig {
+    fn default() -> Self {
+        Self {
+            target_depth: 3,
+            late_discard: 50,
+            plc: true,
+        }
+    }
+}
+
+/// Counters since the buffer was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JitterStats {
+    /// Frames currently held
+    pub buffered: usize,
+    /// Frames that arrived after their turn and were dropped
+    pub late: u64,
+    /// Missing frames filled in by concealment
+    pub concealed: u64,
+    /// Frames skipped to shed latency after a burst, and duplicates
+    pub dropped: u64,
+    /// Sequence restarts
+    pub resyncs: u64,
+}
+
+/// Sequence-ordered frame store behind `JitterBufferStage`
+pub struct JitterBuffer {
+    config: JitterConfig,
+    /// Sequence number of `slots[0]`; None until the first frame
+    next_sequence: Option<u16>,
+    /// Frames from `next_sequence` on; None marks one not yet arrived
+    slots: VecDeque<Option<Vec<i16>>>,
+    playing: bool,
+    last_frame: Vec<i16>,
+    /// Consecutive frames concealed so far
+    concealing: u32,
+    stats: JitterStats,
+}
+
+impl JitterBuffer {
+    pub fn new(config: JitterConfig) -> Self {
+        Self {
+            config: JitterConfig {
+                target_depth: config.target_depth.max(1),
+                ..config
+            },
+            next_sequence: None,
+            slots: VecDeque::new(),
+            playing: false,
+            last_frame: Vec::new(),
+            concealing: 0,
+            stats: JitterStats::default(),
+        }
+    }
+
+    pub fn config(&self) -> JitterConfig {
+        self.config
+    }
+
+    pub fn stats(&self) -> JitterStats {
+        JitterStats {
+            buffered: self.slots.iter().flatten().count(),
+            ..self.stats
+        }
+    }
+
+    /// Store a frame that arrived from the network
+    pub fn push(&mut self, sequence: u16, samples: Vec<i16>) {
+        let next = *self.next_sequence.get_or_insert(sequence);
+        let ahead = sequence.wrapping_sub(next);
+        if ahead >= 0x8000 {
+            let behind = next.wrapping_sub(sequence);
+            if behind > self.config.late_discard {
+                self.resync(sequence);
+            } else if self.playing {
+                self.stats.late += 1;
+                return;
+            } else {
+                // Still buffering: an earlier frame just arrived second
+                for _ in 0..behind {
+                    self.slots.push_front(None);
+                }
+                self.next_sequence = Some(sequence);
+            }
+        }
+
+        let offset = sequence.wrapping_sub(self.next_sequence.unwrap_or(sequence)) as usize;
+        if offset >= self.slots.len() {
+            self.slots.resize(offset + 1, None);
+        }
+        let slot = &mut self.slots[offset];
+        if slot.is_some() {
+            self.stats.dropped += 1;
+            return;
+        }
+        *slot = Some(samples);
+
+        // Shed latency after a burst, keeping the newest target_depth frames
+        let max_depth = self.config.target_depth * 2;
+        if self.slots.len() > max_depth {
+            let skip = self.slots.len() - self.config.target_depth;
+            let skipped = self.slots.drain(..skip).flatten().count();
+            self.stats.dropped += skipped as u64;
+            self.advance(skip);
+        }
+    }
+
+    /// Next frame of the paced stream; None while (re)buffering
+    pub fn pop(&mut self) -> Option<Vec<i16>> {
+        if !self.playing {
+            if self.slots.len() < self.config.target_depth {
+                return None;
+            }
+            self.playing = true;
+        }
+
+        let Some(slot) = self.slots.pop_front() else {
+            // Ran dry: wait for target_depth frames again
+            self.playing = false;
+            return None;
+        };
+        self.advance(1);
+        match slot {
+            Some(frame) => {
+                self.concealing = 0;
+                self.last_frame.clone_from(&frame);
+                Some(frame)
+            }
+            None => {
+                self.stats.concealed += 1;
+                self.concealing += 1;
+                Some(self.conceal())
+            }
+        }
+    }
+
+    /// Stand-in for a missing frame
+    fn conceal(&self) -> Vec<i16> {
+        if !self.config.plc || self.last_frame.is_empty() || self.concealing > MAX_PLC_FRAMES {
+            let len = match self.last_frame.len() {
+                0 => DEFAULT_FRAME_SAMPLES,
+                n => n,
+            };
+            return vec![0; len];
+        }
+        // Halve the gain on each further loss so a long gap fades out
+        let shift = self.concealing;
+        self.last_frame.iter().map(|&s| s >> shift).collect()
+    }
+
+    fn advance(&mut self, frames: usize) {
+        if let Some(next) = &mut self.next_sequence {
+            *next = next.wrapping_add(frames as u16);
+        }
+    }
+
+    fn resync(&mut self, sequence: u16) {
+        self.stats.resyncs += 1;
+        self.stats.dropped += self.slots.iter().flatten().count() as u64;
+        self.slots.clear();
+        self.next_sequence = Some(sequence);
+        self.playing = false;
+    }
+}
+
+/// Transport side of a `JitterBufferStage`: pushes frames as they arrive
+#[derive(Clone)]
+pub struct JitterInput(Arc<Mutex<JitterBuffer>>);
+
+impl JitterInput {
+    pub fn push(&self, sequence: u16, samples: Vec<i16>) {
+        self.0.lock().push(sequence, samples);
+    }
+
+    pub fn stats(&self) -> JitterStats {
+        self.0.lock().stats()
+    }
+}
+
+/// Plays out network frames in sequence order, one per `process` call.
+/// The chain is clocked once per frame interval; the samples it is clocked
+/// with are ignored, since input arrives through `JitterInput`.
+pub struct JitterBufferStage {
+    buffer: Arc<Mutex<JitterBuffer>>,
+    sequencer: FrameSequencer,
+}
+
+impl JitterBufferStage {
+    pub fn new(config: JitterConfig) -> (Self, JitterInput) {
+        let buffer = Arc::new(Mutex::new(JitterBuffer::new(config)));
+        let input = JitterInput(buffer.clone());
+        let stage = Self {
+            buffer,
+            sequencer: FrameSequencer::new(),
+        };
+        (stage, input)
+    }
+}
+
+impl AudioStage for JitterBufferStage {
+    fn name(&self) -> &str {
+        "jitter-buffer"
+    }
+
+    fn process(&mut self, _samples: &[i16]) -> Vec<i16> {
+        self.buffer.lock().pop().unwrap_or_default()
+    }
+
+    fn process_frame(&mut self, frame: AudioFrame) -> AudioFrame {
+        match self.buffer.lock().pop() {
+            Some(samples) => self.sequencer.audio(samples),
+            None => AudioFrame {
+                meta: frame.meta,
+                samples: Vec::new(),
+            },
+        }
+    }
+
+    fn flush(&mut self) -> Vec<i16> {
+        let mut buffer = self.buffer.lock();
+        let mut tail = Vec::new();
+        buffer.playing = true;
+        while let Some(frame) = buffer.pop() {
+            tail.extend(frame);
+        }
+        tail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::audio::stage_chain::StageChain;
+
+    fn frame(value: i16) -> Vec<i16> {
+        vec![value; 4]
+    }
+
+    #[test]
+    fn test_reorders_and_conceals_gaps() {
+        let mut buffer = JitterBuffer::new(JitterConfig::default());
+
+        // Nothing plays until target_depth frames are buffered
+        buffer.push(11, frame(200));
+        buffer.push(10, frame(100));
+        assert_eq!(buffer.pop(), None);
+        buffer.push(12, frame(300));
+        assert_eq!(buffer.pop(), Some(frame(100)));
+        assert_eq!(buffer.pop(), Some(frame(200)));
+
+        // 13 lost: concealed from 300 at half gain, then 14 plays on time
+        buffer.push(14, frame(500));
+        assert_eq!(buffer.pop(), Some(frame(300)));
+        assert_eq!(buffer.pop(), Some(frame(150)));
+        assert_eq!(buffer.pop(), Some(frame(500)));
+
+        // 13 turning up now is late
+        buffer.push(13, frame(400));
+        let stats = buffer.stats();
+        assert_eq!(stats.late, 1);
+        assert_eq!(stats.concealed, 1);
+        assert_eq!(stats.buffered, 0);
+
+        // Ran dry: rebuffer rather than conceal a pause
+        assert_eq!(buffer.pop(), None);
+        buffer.push(15, frame(600));
+        assert_eq!(buffer.pop(), None);
+    }
+
+    #[test]
+    fn test_silence_wraparound_and_resync() {
+        let mut buffer = JitterBuffer::new(JitterConfig {
+            target_depth: 2,
+            late_discard: 10,
+            plc: false,
+        });
+
+        buffer.push(u16::MAX, frame(1));
+        buffer.push(1, frame(3));
+        assert_eq!(buffer.pop(), Some(frame(1)));
+        assert_eq!(buffer.pop(), Some(frame(0)));
+        assert_eq!(buffer.pop(), Some(frame(3)));
+
+        // A burst past twice the depth keeps only the newest frames
+        for sequence in 2..7 {
+            buffer.push(sequence, frame(sequence as i16));
+        }
+        assert_eq!(buffer.stats().buffered, 2);
+        assert_eq!(buffer.stats().dropped, 3);
+        assert_eq!(buffer.pop(), Some(frame(5)));
+
+        // Far behind the playout point: the sender restarted
+        buffer.push(1000, frame(9));
+        buffer.push(1001, frame(10));
+        assert_eq!(buffer.stats().resyncs, 0);
+        buffer.push(900, frame(11));
+        buffer.push(901, frame(12));
+        assert_eq!(buffer.stats().resyncs, 1);
+        assert_eq!(buffer.pop(), Some(frame(11)));
+    }
+
+    #[test]
+    fn test_stage_paces_chain() {
+        let (chain, input) = StageChain::network(JitterConfig {
+            target_depth: 2,
+            ..Default::default()
+        });
+
+        input.push(0, frame(1));
+        assert!(chain.process(&[]).is_empty());
+        input.push(1, frame(2));
+        assert_eq!(chain.process(&[]), frame(1));
+
+        // Played frames are stamped in playout order
+        input.push(2, frame(3));
+        let played = chain.process_frame(&AudioFrame {
+            meta: Default::default(),
+            samples: Vec::new(),
+        });
+        assert_eq!(played.samples, frame(2));
+        assert_eq!(played.meta.seq, 1);
+
+        input.push(3, frame(4));
+        let (_, tail) = chain.remove_stage(0).unwrap();
+        assert_eq!(tail, [frame(3), frame(4)].concat());
+    }
+}
@@ -216,6 +216,23 @@ impl ParticipantStream {
         Ok(())
     }
 
+    /// Set this participant's VAD speech threshold (see
+    /// `ProductionVADConfig::silero_threshold`). False if it has no VAD.
+    pub fn set_vad_threshold(&mut self, threshold: f32) -> bool {
+        match &mut self.vad {
+            Some(vad) => {
+                vad.set_silero_threshold(threshold);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// This participant's VAD speech threshold; None if it has no VAD
+    pub fn vad_threshold(&self) -> Option<f32> {
+        self.vad.as_ref().map(|vad| vad.config().silero_threshold)
+    }
+
     /// Update audio frame with new samples
     /// Returns PushAudioResult indicating if transcription should run
     ///
@@ -379,12 +396,60 @@ impl ParticipantStream {
         }
     }
 
+    /// Borrow up to `max_samples` buffered AI samples without copying, so a
+    /// batch of frames can be processed in one pass.
+    ///
+    /// Only the first contiguous run is exposed: if the buffered audio wraps
+    /// past the end of the ring, the batch stops there and the remainder
+    /// comes in the next batch. Empty for humans and muted participants.
+    pub fn peek_batch(&mut self, max_samples: usize) -> BatchPeekGuard<'_> {
+        let len = if self.ai_ring_buffer.is_some() && !self.muted {
+            max_samples
+                .min(self.ai_ring_available)
+                .min(AI_RING_BUFFER_SIZE - self.ai_ring_read)
+        } else {
+            0
+        };
+        BatchPeekGuard { stream: self, len }
+    }
+
     /// Check if currently speaking (for UI indicators)
     pub fn is_currently_speaking(&self) -> bool {
         self.is_speaking
     }
 }
 
+/// Zero-copy view of a contiguous run of buffered AI audio, from
+/// `ParticipantStream::peek_batch`. Dropping the guard leaves the samples
+/// buffered; `commit` consumes the whole batch at once.
+pub struct BatchPeekGuard<'a> {
+    stream: &'a mut ParticipantStream,
+    len: usize,
+}
+
+impl BatchPeekGuard<'_> {
+    pub fn samples(&self) -> &[i16] {
+        match self.stream.ai_ring_buffer {
+            Some(ref ring) => &ring[self.stream.ai_ring_read..self.stream.ai_ring_read + self.len],
+            None => &[],
+        }
+    }
+
+    /// Consume the peeked samples
+    pub fn commit(self) {
+        self.stream.ai_ring_read = (self.stream.ai_ring_read + self.len) % AI_RING_BUFFER_SIZE;
+        self.stream.ai_ring_available -= self.len;
+    }
+}
+
+impl std::ops::Deref for BatchPeekGuard<'_> {
+    type Target = [i16];
+
+    fn deref(&self) -> &[i16] {
+        self.samples()
+    }
+}
+
 /// Result of pushing audio to mixer - includes participant info if transcription ready
 #[derive(Debug)]
 pub struct MixerPushResult {
@@ -504,6 +569,11 @@ impl AudioMixer {
         self.participants.len()
     }
 
+    /// Number of participants who aren't AI
+    pub fn human_count(&self) -> usize {
+        self.participants.values().filter(|p| !p.is_ai).count()
+    }
+
     /// Mix all participants (sum all streams)
     /// Note: Requires &mut self because AI participants pull from ring buffer
     pub fn mix_all(&mut self) -> Vec<i16> {
@@ -920,6 +990,32 @@ mod tests {
         assert!(!is_silence(&mix_for_ai, 100.0));
     }
 
+    #[test]
+    fn test_peek_batch_stops_at_wraparound() {
+        let mut stream = ParticipantStream::new_ai(Handle::new(), "ai".into(), "AI".into());
+        // Start 100 samples before the end of the ring so the push wraps
+        stream.ai_ring_read = AI_RING_BUFFER_SIZE - 100;
+        stream.ai_ring_write = AI_RING_BUFFER_SIZE - 100;
+        let audio: Vec<i16> = (0..500).collect();
+        stream.push_audio(audio.clone());
+
+        // Dropped without commit: nothing consumed
+        assert_eq!(stream.peek_batch(AUDIO_FRAME_SIZE * 10).len(), 100);
+        assert_eq!(stream.ai_ring_available, 500);
+
+        let batch = stream.peek_batch(AUDIO_FRAME_SIZE * 10);
+        assert_eq!(&batch[..], &audio[..100]);
+        batch.commit();
+
+        let batch = stream.peek_batch(AUDIO_FRAME_SIZE * 10);
+        assert_eq!(&batch[..], &audio[100..]);
+        batch.commit();
+        assert!(stream.peek_batch(AUDIO_FRAME_SIZE).is_empty());
+
+        let mut human = ParticipantStream::new(Handle::new(), "u".into(), "User".into());
+        assert!(human.peek_batch(AUDIO_FRAME_SIZE).is_empty());
+    }
+
     #[tokio::test]
     async fn test_mix_minus_all() {
         let mut mixer = AudioMixer::default_voice();
@@ -1,9 +1,17 @@
+pub mod aec;
 pub mod buffer;
 pub mod capabilities;
+pub mod dtmf;
+pub mod jitter_buffer;
 pub mod mixer;
+pub mod noise_suppress;
+pub mod recording;
 pub mod reloadable;
+pub mod resample;
 pub mod resource_lifecycle;
 pub mod router;
+pub mod sliding_buffer;
+pub mod stage_chain;
 pub mod stt;
 pub mod stt_service;
 pub mod tts;
@@ -0,0 +1,415 @@
+//! Call Recording
+//!
+//! Tees the per-sender frames produced by the call's audio tick into WAV
+//! files. The tick only clones the frames into a bounded channel; mixing and
+//! disk I/O happen on a dedicated writer thread, so a slow disk never delays
+//! the live call. If the writer falls more than `RECORDING_CHANNEL_CAPACITY`
+//! frames behind, frames are dropped (recorded as silence) and counted.
+//!
+//! Every frame carries its position on the call's sample clock. Tracks are
+//! zero-filled across gaps (a participant not speaking), and `timestamps.json`
+//! records where each track starts, so multi-track files can be lined up.
+
+use crate::clog_warn;
+use crate::live::handle::Handle;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Frames buffered for the writer thread (~60s at 20ms frames)
+const RECORDING_CHANNEL_CAPACITY: usize = 3000;
+
+/// File name of the mixed track in `Mixed` mode
+const MIXED_TRACK_FILE: &str = "mixed.wav";
+
+/// File name of the timing sidecar
+const TIMESTAMPS_FILE: &str = "timestamps.json";
+
+/// What a recording captures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordingMode {
+    /// One WAV with every sender summed (what a listener hears)
+    Mixed,
+    /// One WAV per participant, including AI participants' TTS
+    MultiTrack,
+}
+
+/// Placement of one recorded track on the call timeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackTiming {
+    pub file: String,
+    /// Sender user id (None for the mixed track)
+    pub user_id: Option<String>,
+    /// Offset of the track's first sample from the start of the recording
+    pub start_sample: u64,
+    pub start_offset_ms: u64,
+    pub samples: u64,
+}
+
+/// Result of a finished recording (also written as `timestamps.json`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingSummary {
+    pub mode: RecordingMode,
+    pub sample_rate: u32,
+    /// Wall-clock start of the recording (Unix ms)
+    pub started_at_ms: u64,
+    pub dropped_frames: u64,
+    pub tracks: Vec<TrackTiming>,
+}
+
+/// One audio tick: call-clock position and the per-sender frames
+struct RecordedTick {
+    position: u64,
+    frames: Vec<(Handle, String, Vec<i16>)>,
+}
+
+/// Handle to an in-progress recording
+pub struct CallRecorder {
+    sender: Option<SyncSender<RecordedTick>>,
+    writer: Option<JoinHandle<Result<RecordingSummary, String>>>,
+    dropped_frames: Arc<AtomicU64>,
+}
+
+impl CallRecorder {
+    /// Start recording into directory `dir` (created if missing).
+    /// `origin` is the call-clock sample position that maps to offset 0.
+    pub fn start(
+        dir: impl AsRef<Path>,
+        mode: RecordingMode,
+        sample_rate: u32,
+        origin: u64,
+    ) -> Result<Self, String> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create recording dir {}: {e}", dir.display()))?;
+
+        let (sender, receiver) = mpsc::sync_channel::<RecordedTick>(RECORDING_CHANNEL_CAPACITY);
+        let dropped_frames = Arc::new(AtomicU64::new(0));
+        let dropped = dropped_frames.clone();
+        let started_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        let writer = std::thread::Builder::new()
+            .name("call-recorder".into())
+            .spawn(move || {
+                let mut tracks = TrackWriters::new(dir, mode, sample_rate);
+                let mut failure = None;
+                while let Ok(tick) = receiver.recv() {
+                    let position = tick.position.saturating_sub(origin);
+                    if let Err(e) = tracks.write_tick(position, tick.frames) {
+                        clog_warn!("Call recording stopped: {}", e);
+                        failure = Some(e);
+                        break;
+                    }
+                }
+                // Finalize whatever was written, but report the original failure
+                let summary = tracks.finish(started_at_ms, dropped.load(Ordering::Relaxed));
+                match failure {
+                    Some(e) => Err(e),
+                    None => summary,
+                }
+            })
+            .map_err(|e| format!("Failed to spawn recorder thread: {e}"))?;
+
+        Ok(Self {
+            sender: Some(sender),
+            writer: Some(writer),
+            dropped_frames,
+        })
+    }
+
+    /// Queue one tick's frames. Never blocks — drops the tick if the writer
+    /// is too far behind.
+    pub fn record(&self, position: u64, frames: &[(Handle, String, Vec<i16>)]) {
+        let Some(sender) = &self.sender else { return };
+        let tick = RecordedTick {
+            position,
+            frames: frames.to_vec(),
+        };
+        if let Err(TrySendError::Full(_)) = sender.try_send(tick) {
+            self.dropped_frames.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Flush queued frames, finalize WAV headers and write the sidecar.
+    /// Blocks until the writer thread finishes.
+    pub fn stop(mut self) -> Result<RecordingSummary, String> {
+        self.sender.take();
+        match self.writer.take() {
+            Some(writer) => writer
+                .join()
+                .map_err(|_| "Recorder thread panicked".to_string())?,
+            None => Err("Recording already stopped".to_string()),
+        }
+    }
+}
+
+impl Drop for CallRecorder {
+    fn drop(&mut self) {
+        // Closing the channel lets the writer finalize on its own
+        self.sender.take();
+    }
+}
+
+/// One open WAV and where it sits on the timeline
+struct Track {
+    file: String,
+    user_id: Option<String>,
+    writer: hound::WavWriter<BufWriter<File>>,
+    start_sample: u64,
+    written: u64,
+}
+
+impl Track {
+    /// Append `samples` at timeline `position`, zero-filling any gap
+    fn write_at(&mut self, position: u64, samples: &[i16]) -> Result<(), String> {
+        let end = self.start_sample + self.written;
+        for _ in end..position {
+            self.write_sample(0)?;
+        }
+        for &sample in samples {
+            self.write_sample(sample)?;
+        }
+        Ok(())
+    }
+
+    fn write_sample(&mut self, sample: i16) -> Result<(), String> {
+        self.writer
+            .write_sample(sample)
+            .map_err(|e| format!("Failed to write {}: {e}", self.file))?;
+        self.written += 1;
+        Ok(())
+    }
+}
+
+/// Writer-thread state: open tracks keyed by sender (or one mixed track)
+struct TrackWriters {
+    dir: PathBuf,
+    mode: RecordingMode,
+    sample_rate: u32,
+    tracks: HashMap<Handle, Track>,
+    /// Track creation order, for a stable sidecar
+    order: Vec<Handle>,
+    mixed: Option<Track>,
+}
+
+impl TrackWriters {
+    fn new(dir: PathBuf, mode: RecordingMode, sample_rate: u32) -> Self {
+        Self {
+            dir,
+            mode,
+            sample_rate,
+            tracks: HashMap::new(),
+            order: Vec::new(),
+            mixed: None,
+        }
+    }
+
+    fn open(
+        &self,
+        file: String,
+        user_id: Option<String>,
+        start_sample: u64,
+    ) -> Result<Track, String> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: self.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let writer = hound::WavWriter::create(self.dir.join(&file), spec)
+            .map_err(|e| format!("Failed to create {file}: {e}"))?;
+        Ok(Track {
+            file,
+            user_id,
+            writer,
+            start_sample,
+            written: 0,
+        })
+    }
+
+    fn write_tick(
+        &mut self,
+        position: u64,
+        frames: Vec<(Handle, String, Vec<i16>)>,
+    ) -> Result<(), String> {
+        match self.mode {
+            RecordingMode::Mixed => {
+                if frames.is_empty() {
+                    return Ok(());
+                }
+                let mixed = mix_frames(&frames);
+                if self.mixed.is_none() {
+                    // The mixed track always starts at offset 0
+                    self.mixed = Some(self.open(MIXED_TRACK_FILE.to_string(), None, 0)?);
+                }
+                if let Some(track) = self.mixed.as_mut() {
+                    track.write_at(position, &mixed)?;
+                }
+            }
+            RecordingMode::MultiTrack => {
+                for (handle, user_id, audio) in frames {
+                    if !self.tracks.contains_key(&handle) {
+                        let file = format!("{}-{}.wav", sanitize(&user_id), handle.short());
+                        let track = self.open(file, Some(user_id), position)?;
+                        self.tracks.insert(handle, track);
+                        self.order.push(handle);
+                    }
+                    if let Some(track) = self.tracks.get_mut(&handle) {
+                        track.write_at(position, &audio)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(
+        mut self,
+        started_at_ms: u64,
+        dropped_frames: u64,
+    ) -> Result<RecordingSummary, String> {
+        let mut tracks: Vec<Track> = self.mixed.take().into_iter().collect();
+        for handle in &self.order {
+            if let Some(track) = self.tracks.remove(handle) {
+                tracks.push(track);
+            }
+        }
+
+        let mut timings = Vec::with_capacity(tracks.len());
+        for track in tracks {
+            timings.push(TrackTiming {
+                file: track.file.clone(),
+                user_id: track.user_id,
+                start_sample: track.start_sample,
+                start_offset_ms: track.start_sample * 1000 / self.sample_rate.max(1) as u64,
+                samples: track.written,
+            });
+            // finalize() rewrites the RIFF/data sizes in the header
+            track
+                .writer
+                .finalize()
+                .map_err(|e| format!("Failed to finalize {}: {e}", track.file))?;
+        }
+
+        let summary = RecordingSummary {
+            mode: self.mode,
+            sample_rate: self.sample_rate,
+            started_at_ms,
+            dropped_frames,
+            tracks: timings,
+        };
+        let json = serde_json::to_string_pretty(&summary)
+            .map_err(|e| format!("Failed to serialize recording timestamps: {e}"))?;
+        std::fs::write(self.dir.join(TIMESTAMPS_FILE), json)
+            .map_err(|e| format!("Failed to write {TIMESTAMPS_FILE}: {e}"))?;
+        Ok(summary)
+    }
+}
+
+/// Sum per-sender frames with saturation
+fn mix_frames(frames: &[(Handle, String, Vec<i16>)]) -> Vec<i16> {
+    let len = frames.iter().map(|(_, _, a)| a.len()).max().unwrap_or(0);
+    let mut sum = vec![0i32; len];
+    for (_, _, audio) in frames {
+        for (acc, &sample) in sum.iter_mut().zip(audio) {
+            *acc += sample as i32;
+        }
+    }
+    sum.into_iter()
+        .map(|s| s.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+        .collect()
+}
+
+/// Make a user id safe to use in a file name
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_wav(path: &Path) -> (u32, Vec<i16>) {
+        let mut reader = hound::WavReader::open(path).unwrap();
+        let rate = reader.spec().sample_rate;
+        (rate, reader.samples::<i16>().map(|s| s.unwrap()).collect())
+    }
+
+    #[test]
+    fn test_multitrack_recording_aligns_tracks() {
+        let dir = tempfile::tempdir().unwrap();
+        let (alice, bot) = (Handle::new(), Handle::new());
+        let recorder =
+            CallRecorder::start(dir.path(), RecordingMode::MultiTrack, 16000, 100).unwrap();
+
+        recorder.record(100, &[(alice, "alice".into(), vec![1; 4])]);
+        // Alice silent for one frame, bot's TTS starts later
+        recorder.record(
+            108,
+            &[
+                (alice, "alice".into(), vec![2; 4]),
+                (bot, "ai/bot".into(), vec![3; 4]),
+            ],
+        );
+        let summary = recorder.stop().unwrap();
+
+        assert_eq!(summary.tracks.len(), 2);
+        let (rate, alice_samples) = read_wav(&dir.path().join(&summary.tracks[0].file));
+        assert_eq!(rate, 16000);
+        assert_eq!(alice_samples, [vec![1; 4], vec![0; 4], vec![2; 4]].concat());
+
+        let bot_track = &summary.tracks[1];
+        assert_eq!(bot_track.user_id.as_deref(), Some("ai/bot"));
+        assert!(bot_track.file.starts_with("ai_bot-"));
+        assert_eq!(bot_track.start_sample, 8);
+        assert_eq!(bot_track.samples, 4);
+
+        let sidecar: RecordingSummary = serde_json::from_str(
+            &std::fs::read_to_string(dir.path().join(TIMESTAMPS_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(sidecar.tracks[1].start_sample, 8);
+    }
+
+    #[test]
+    fn test_mixed_recording_sums_senders() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (Handle::new(), Handle::new());
+        let recorder = CallRecorder::start(dir.path(), RecordingMode::Mixed, 16000, 0).unwrap();
+
+        recorder.record(
+            4,
+            &[
+                (a, "a".into(), vec![100, i16::MAX]),
+                (b, "b".into(), vec![50, 10]),
+            ],
+        );
+        let summary = recorder.stop().unwrap();
+
+        assert_eq!(summary.tracks.len(), 1);
+        assert_eq!(summary.tracks[0].file, MIXED_TRACK_FILE);
+        let (_, samples) = read_wav(&dir.path().join(MIXED_TRACK_FILE));
+        assert_eq!(samples, vec![0, 0, 0, 0, 150, i16::MAX]);
+    }
+}
@@ -86,6 +86,15 @@ impl<T> ReloadableModel<T> {
         }
     }
 
+    /// Swap in a different model (e.g. another size), loaded or not.
+    ///
+    /// Returns `true` if it replaced a loaded model. There is no moment
+    /// where `get()` finds nothing: callers see either the old model or the
+    /// new one, and in-flight callers finish on the old one.
+    pub fn replace(&self, model: T) -> bool {
+        self.inner.write().replace(Arc::new(model)).is_some()
+    }
+
     /// The label for this model (used in logging).
     pub fn label(&self) -> &'static str {
         self.label
@@ -190,6 +199,17 @@ mod tests {
         assert_eq!(*model.get().unwrap(), "v2");
     }
 
+    #[test]
+    fn test_replace_keeps_inflight() {
+        let model = ReloadableModel::new("test");
+        assert!(!model.replace("base".to_string()));
+        let inflight = model.get().unwrap();
+
+        assert!(model.replace("medium".to_string()));
+        assert_eq!(*model.get().unwrap(), "medium");
+        assert_eq!(*inflight, "base");
+    }
+
     #[test]
     fn test_load_with_error() {
         let model: ReloadableModel<String> = ReloadableModel::new("test");
@@ -0,0 +1,235 @@
+//! Streaming Sample-Rate Conversion
+//!
+//! Adapters don't agree on sample rate: Twilio delivers 8kHz, desktop
+//! microphones 48kHz, and Whisper wants 16kHz. `ResampleStage` sits between
+//! a producer and a consumer and converts a continuous stream chunk by chunk.
+//!
+//! Unlike `utils::audio::resample` (one-shot, whole buffer), this keeps state
+//! across chunks so frame boundaries don't click and the output length never
+//! drifts. The read position is tracked as an exact rational (in units of
+//! `1/up` input samples), so non-integer ratios like 44100→16000 accumulate
+//! no rounding error no matter how many frames pass through.
+//!
+//! Interpolation is linear. That is adequate for speech going into STT/VAD;
+//! music-grade conversion should use rubato via `utils::audio::resample`.
+
+use super::stage_chain::AudioStage;
+
+/// Stateful linear-interpolating resampler for a mono i16 stream.
+#[derive(Debug, Clone)]
+pub struct ResampleStage {
+    from_hz: u32,
+    to_hz: u32,
+    /// Upsampling factor (to_hz / gcd)
+    up: u64,
+    /// Downsampling factor (from_hz / gcd)
+    down: u64,
+    /// Read position into the virtual buffer `[carry] ++ chunk`, in 1/up units
+    phase: u64,
+    /// Last input sample of the previous chunk (interpolation left edge)
+    carry: Option<i16>,
+    input_samples: u64,
+    output_samples: u64,
+}
+
+impl ResampleStage {
+    /// Create a resampler converting `from_hz` to `to_hz`.
+    ///
+    /// Panics if either rate is zero.
+    pub fn new(from_hz: u32, to_hz: u32) -> Self {
+        assert!(from_hz > 0 && to_hz > 0, "sample rates must be non-zero");
+        let g = gcd(from_hz as u64, to_hz as u64);
+        Self {
+            from_hz,
+            to_hz,
+            up: to_hz as u64 / g,
+            down: from_hz as u64 / g,
+            phase: 0,
+            carry: None,
+            input_samples: 0,
+            output_samples: 0,
+        }
+    }
+
+    /// Resampler to insert between two adapters, or `None` if their rates
+    /// already match and samples can pass through untouched.
+    pub fn between(from_hz: u32, to_hz: u32) -> Option<Self> {
+        (from_hz != to_hz).then(|| Self::new(from_hz, to_hz))
+    }
+
+    pub fn from_hz(&self) -> u32 {
+        self.from_hz
+    }
+
+    pub fn to_hz(&self) -> u32 {
+        self.to_hz
+    }
+
+    /// Convert the next chunk of the stream.
+    ///
+    /// Chunks may be any size (including empty). Splitting a stream into
+    /// different chunk sizes produces identical output.
+    pub fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+        self.input_samples += samples.len() as u64;
+
+        if self.up == self.down {
+            self.output_samples += samples.len() as u64;
+            return samples.to_vec();
+        }
+
+        let buf: Vec<i16> = match self.carry {
+            Some(prev) => std::iter::once(prev)
+                .chain(samples.iter().copied())
+                .collect(),
+            None => samples.to_vec(),
+        };
+        if buf.is_empty() {
+            return Vec::new();
+        }
+
+        let expected = (buf.len() as u64 * self.up / self.down) as usize + 1;
+        let mut out = Vec::with_capacity(expected);
+        loop {
+            let idx = (self.phase / self.up) as usize;
+            if idx + 1 >= buf.len() {
+                break;
+            }
+            let frac = (self.phase % self.up) as f32 / self.up as f32;
+            let a = buf[idx] as f32;
+            let b = buf[idx + 1] as f32;
+            out.push((a + (b - a) * frac).round() as i16);
+            self.phase += self.down;
+        }
+
+        // Re-base so the last sample becomes index 0 of the next virtual buffer.
+        let consumed = (buf.len() - 1) as u64;
+        self.phase -= consumed * self.up;
+        self.carry = buf.last().copied();
+        self.output_samples += out.len() as u64;
+        out
+    }
+
+    /// Total input samples seen so far.
+    pub fn input_samples(&self) -> u64 {
+        self.input_samples
+    }
+
+    /// Total output samples produced so far.
+    pub fn output_samples(&self) -> u64 {
+        self.output_samples
+    }
+
+    /// Duration of audio produced so far. Output lags input by at most one
+    /// input sample of interpolation delay, so frame timestamps carry over
+    /// unchanged.
+    pub fn output_position_ms(&self) -> u64 {
+        self.output_samples * 1000 / self.to_hz as u64
+    }
+
+    /// Drop interpolation state (e.g. after a stream discontinuity).
+    pub fn reset(&mut self) {
+        self.phase = 0;
+        self.carry = None;
+        self.input_samples = 0;
+        self.output_samples = 0;
+    }
+}
+
+impl AudioStage for ResampleStage {
+    fn name(&self) -> &str {
+        "resample"
+    }
+
+    fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+        ResampleStage::process(self, samples)
+    }
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::audio::mixer::test_utils::{detect_frequency_approx, generate_sine_wave};
+
+    #[test]
+    fn test_same_rate_passthrough() {
+        assert!(ResampleStage::between(16000, 16000).is_none());
+        let mut stage = ResampleStage::new(16000, 16000);
+        let input = vec![1i16, 2, 3, 4];
+        assert_eq!(stage.process(&input), input);
+    }
+
+    #[test]
+    fn test_chunking_is_invisible() {
+        let input = generate_sine_wave(440.0, 48000, 4800);
+
+        let mut whole = ResampleStage::new(48000, 16000);
+        let expected = whole.process(&input);
+
+        let mut chunked = ResampleStage::new(48000, 16000);
+        let mut actual = Vec::new();
+        for chunk in input.chunks(317) {
+            actual.extend(chunked.process(chunk));
+        }
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_no_drift_over_long_stream() {
+        // 44.1k → 16k is a non-integer ratio (160/441)
+        let mut stage = ResampleStage::new(44100, 16000);
+        let frame = vec![0i16; 441];
+        for _ in 0..10_000 {
+            stage.process(&frame);
+        }
+        // 10_000 frames of 10ms each = 100s → exactly 1.6M samples (±1 edge)
+        let out = stage.output_samples() as i64;
+        assert!((out - 1_600_000).abs() <= 1, "drifted: {out}");
+        assert!((99_999..=100_000).contains(&stage.output_position_ms()));
+    }
+
+    #[test]
+    fn test_upsample_8k_to_16k_interpolates() {
+        let mut stage = ResampleStage::new(8000, 16000);
+        let out = stage.process(&[0, 100, 200]);
+        assert_eq!(out, vec![0, 50, 100, 150]);
+        // Continues smoothly across the boundary using the carried sample
+        let out = stage.process(&[300]);
+        assert_eq!(out, vec![200, 250]);
+    }
+
+    #[test]
+    fn test_preserves_frequency() {
+        let input = generate_sine_wave(440.0, 48000, 48000);
+        let mut stage = ResampleStage::new(48000, 16000);
+        let out = stage.process(&input);
+        let freq = detect_frequency_approx(&out, 16000);
+        assert!((freq - 440.0).abs() < 20.0, "got {freq}Hz");
+    }
+
+    #[test]
+    fn test_resample_stage_in_chain() {
+        use crate::live::audio::stage_chain::StageChain;
+
+        let chain = StageChain::new();
+        chain.push_stage(Box::new(ResampleStage::new(8000, 16000)));
+        assert_eq!(chain.stage_names(), vec!["resample"]);
+        let out = chain.process(&[0; 160]);
+        assert!((318..=320).contains(&out.len()));
+    }
+
+    #[test]
+    fn test_reset_clears_state() {
+        let mut stage = ResampleStage::new(8000, 16000);
+        stage.process(&[1000; 80]);
+        stage.reset();
+        assert_eq!(stage.input_samples(), 0);
+        assert_eq!(stage.process(&[0, 100]), vec![0, 50]);
+    }
+}
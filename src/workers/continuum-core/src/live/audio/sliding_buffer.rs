@@ -0,0 +1,365 @@
+//! Sliding Audio Buffer
+//!
+//! Splits continuous audio into fixed-length STT windows. With an overlap,
+//! each window starts `overlap_secs` before the previous one ended, so a word
+//! cut at a window boundary is heard whole by the next window instead of
+//! being lost. The same words then come back at the start of the next
+//! transcript; `merge_transcript` drops them by matching the longest suffix
+//! of the text so far against the prefix of the new window's text.
+//!
+//! Defaults (10s windows, no overlap) match the VAD's forced sentence split,
+//! where windows simply abut and transcripts are concatenated as-is.
+//!
+//! With endpointing, frames are pushed along with their VAD result and a
+//! window also ends as soon as speech is followed by `endpoint_silence_ms`
+//! of non-speech, so an utterance is transcribed when the speaker stops
+//! rather than when the window fills. A window cut at a pause shares no
+//! audio with the next one.
+
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
+use crate::live::audio::vad::VADResult;
+
+/// Default window length (matches ProductionVAD's ~10s forced split)
+pub const DEFAULT_WINDOW_SECS: f32 = 10.0;
+
+/// Default overlap between consecutive windows
+pub const DEFAULT_OVERLAP_SECS: f32 = 0.0;
+
+/// Default trailing silence that ends an utterance when endpointing
+/// (matches ProductionVAD's 8-frame silence threshold)
+pub const DEFAULT_ENDPOINT_SILENCE_MS: u32 = 256;
+
+/// Words of merged transcript kept for seam matching
+const SEAM_TAIL_WORDS: usize = 32;
+
+/// One window of audio, positioned on the input timeline
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioWindow {
+    /// Index of the window's first sample in the overall input
+    pub start_sample: u64,
+    pub samples: Vec<i16>,
+    /// Leading samples the previous window already covered
+    pub overlap_samples: usize,
+}
+
+impl AudioWindow {
+    pub fn start_ms(&self) -> u64 {
+        self.start_sample * 1000 / AUDIO_SAMPLE_RATE as u64
+    }
+}
+
+/// Windowing plus seam-aware transcript merging for 16kHz mono audio
+#[derive(Clone)]
+pub struct SlidingAudioBuffer {
+    window_samples: usize,
+    overlap_samples: usize,
+    /// Audio not yet fully covered by an emitted window
+    pending: Vec<i16>,
+    /// Input index of `pending[0]`
+    pending_start: u64,
+    /// Whether `pending` begins with audio an earlier window already covered
+    emitted: bool,
+    /// Trailing words of the merged transcript
+    tail: Vec<String>,
+    /// Non-speech that ends an utterance (None = fixed windows only)
+    endpoint_silence_samples: Option<usize>,
+    /// Speech has been heard since the last endpoint
+    heard_speech: bool,
+    /// Non-speech samples since the last speech
+    trailing_silence: usize,
+}
+
+impl SlidingAudioBuffer {
+    /// Windows of `window_secs`, each overlapping the previous by
+    /// `overlap_secs` (0 ≤ overlap < window).
+    pub fn new(window_secs: f32, overlap_secs: f32) -> Result<Self, String> {
+        if window_secs <= 0.0 || !window_secs.is_finite() {
+            return Err(format!("Window must be positive, got {window_secs}s"));
+        }
+        if !(0.0..window_secs).contains(&overlap_secs) {
+            return Err(format!(
+                "Overlap must be in [0, {window_secs}) seconds, got {overlap_secs}s"
+            ));
+        }
+        let to_samples = |secs: f32| (secs * AUDIO_SAMPLE_RATE as f32).round() as usize;
+        Ok(Self {
+            window_samples: to_samples(window_secs).max(1),
+            overlap_samples: to_samples(overlap_secs),
+            pending: Vec::new(),
+            pending_start: 0,
+            emitted: false,
+            tail: Vec::new(),
+            endpoint_silence_samples: None,
+            heard_speech: false,
+            trailing_silence: 0,
+        })
+    }
+
+    /// Also end a window once speech is followed by `endpoint_silence_ms`
+    /// of non-speech (see `push_frame`)
+    pub fn with_endpointing(mut self, endpoint_silence_ms: u32) -> Result<Self, String> {
+        if endpoint_silence_ms == 0 {
+            return Err("Endpoint silence must be positive".to_string());
+        }
+        self.endpoint_silence_samples =
+            Some(endpoint_silence_ms as usize * AUDIO_SAMPLE_RATE as usize / 1000);
+        Ok(self)
+    }
+
+    /// Add audio; returns every window it completes, in order
+    pub fn push(&mut self, samples: &[i16]) -> Vec<AudioWindow> {
+        self.pending.extend_from_slice(samples);
+        let mut windows = Vec::new();
+        while self.pending.len() >= self.window_samples {
+            windows.push(AudioWindow {
+                start_sample: self.pending_start,
+                samples: self.pending[..self.window_samples].to_vec(),
+                overlap_samples: if self.emitted {
+                    self.overlap_samples
+                } else {
+                    0
+                },
+            });
+            // Keep the overlap as the start of the next window
+            let advance = self.window_samples - self.overlap_samples;
+            self.pending.drain(..advance);
+            self.pending_start += advance as u64;
+            self.emitted = true;
+        }
+        windows
+    }
+
+    /// Add one frame with its VAD result; returns every window it completes.
+    /// Without endpointing this is `push`.
+    pub fn push_frame(&mut self, samples: &[i16], vad: &VADResult) -> Vec<AudioWindow> {
+        if vad.is_speech {
+            self.heard_speech = true;
+            self.trailing_silence = 0;
+        } else if self.heard_speech {
+            self.trailing_silence += samples.len();
+        }
+
+        let mut windows = self.push(samples);
+        let endpoint = self
+            .endpoint_silence_samples
+            .is_some_and(|limit| self.heard_speech && self.trailing_silence >= limit);
+        if endpoint {
+            windows.extend(self.flush());
+        }
+        windows
+    }
+
+    /// Final partial window, if any audio hasn't been covered yet
+    pub fn flush(&mut self) -> Option<AudioWindow> {
+        let covered = if self.emitted {
+            self.overlap_samples
+        } else {
+            0
+        };
+        let len = self.pending.len();
+        let window = (len > covered).then(|| AudioWindow {
+            start_sample: self.pending_start,
+            samples: std::mem::take(&mut self.pending),
+            overlap_samples: covered,
+        });
+        self.pending_start += len as u64;
+        self.pending.clear();
+        self.emitted = false;
+        self.heard_speech = false;
+        self.trailing_silence = 0;
+        window
+    }
+
+    /// Merge the next window's transcript (call once per window, in order).
+    /// Returns only the text that wasn't already said at the end of the
+    /// previous window.
+    pub fn merge_transcript(&mut self, text: &str) -> String {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let repeated = if self.overlap_samples > 0 {
+            seam_overlap(&self.tail, &words)
+        } else {
+            0
+        };
+        let new_words = &words[repeated..];
+
+        self.tail.extend(new_words.iter().map(|w| w.to_string()));
+        if self.tail.len() > SEAM_TAIL_WORDS {
+            self.tail.drain(..self.tail.len() - SEAM_TAIL_WORDS);
+        }
+        new_words.join(" ")
+    }
+
+    /// Merge the transcript of `window`. A window sharing no audio with the
+    /// previous one (e.g. cut at a pause) is kept whole.
+    pub fn merge_window_transcript(&mut self, window: &AudioWindow, text: &str) -> String {
+        if window.overlap_samples == 0 {
+            self.tail.clear();
+        }
+        self.merge_transcript(text)
+    }
+
+    pub fn window_samples(&self) -> usize {
+        self.window_samples
+    }
+
+    pub fn overlap_samples(&self) -> usize {
+        self.overlap_samples
+    }
+
+    pub fn endpoint_silence_ms(&self) -> Option<u32> {
+        self.endpoint_silence_samples
+            .map(|samples| (samples * 1000 / AUDIO_SAMPLE_RATE as usize) as u32)
+    }
+}
+
+impl Default for SlidingAudioBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_SECS, DEFAULT_OVERLAP_SECS)
+            .expect("default window parameters are valid")
+    }
+}
+
+/// Length of the longest suffix of `tail` equal to a prefix of `words`,
+/// ignoring case and punctuation ("Hello," matches "hello")
+fn seam_overlap(tail: &[String], words: &[&str]) -> usize {
+    let max = tail.len().min(words.len());
+    (1..=max)
+        .rev()
+        .find(|&k| {
+            tail[tail.len() - k..]
+                .iter()
+                .zip(&words[..k])
+                .all(|(a, b)| normalize(a) == normalize(b))
+        })
+        .unwrap_or(0)
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_overlap_by_configured_amount() {
+        // 1s windows, 0.25s overlap → windows start every 0.75s
+        let mut buffer = SlidingAudioBuffer::new(1.0, 0.25).unwrap();
+        let audio: Vec<i16> = (0..42000).map(|i| (i % 1000) as i16).collect();
+
+        let windows = buffer.push(&audio);
+        let starts: Vec<u64> = windows.iter().map(|w| w.start_sample).collect();
+        assert_eq!(starts, vec![0, 12000, 24000]);
+        assert!(windows.iter().all(|w| w.samples.len() == 16000));
+        assert_eq!(windows[1].samples[..4000], windows[0].samples[12000..]);
+        assert_eq!(windows[2].start_ms(), 1500);
+
+        // Remainder: 40000..42000 plus the 4000-sample overlap before it
+        let last = buffer.flush().unwrap();
+        assert_eq!(last.start_sample, 36000);
+        assert_eq!(last.samples.len(), 6000);
+        assert!(buffer.flush().is_none());
+
+        // Audio that ends exactly on a window leaves nothing to flush
+        let mut exact = SlidingAudioBuffer::new(1.0, 0.25).unwrap();
+        assert_eq!(exact.push(&audio[..28000]).len(), 2);
+        assert!(exact.flush().is_none());
+    }
+
+    #[test]
+    fn test_default_windows_abut() {
+        let mut buffer = SlidingAudioBuffer::default();
+        let windows = buffer.push(&vec![0; 16000 * 25]);
+        let starts: Vec<u64> = windows.iter().map(|w| w.start_sample).collect();
+        assert_eq!(starts, vec![0, 160000]);
+        assert_eq!(buffer.flush().unwrap().samples.len(), 80000);
+
+        // No overlap means no dedup, even of a genuinely repeated word
+        assert_eq!(buffer.merge_transcript("go"), "go");
+        assert_eq!(buffer.merge_transcript("go home"), "go home");
+    }
+
+    #[test]
+    fn test_merge_removes_repeated_seam_words() {
+        let mut buffer = SlidingAudioBuffer::new(10.0, 1.0).unwrap();
+        assert_eq!(
+            buffer.merge_transcript("The quick brown fox jumps"),
+            "The quick brown fox jumps"
+        );
+        // Next window re-hears "fox jumps" (case and punctuation may differ)
+        assert_eq!(
+            buffer.merge_transcript("Fox jumps, over the lazy dog."),
+            "over the lazy dog."
+        );
+        // Nothing in common: kept whole
+        assert_eq!(
+            buffer.merge_transcript("Then it rained."),
+            "Then it rained."
+        );
+        assert_eq!(buffer.merge_transcript(""), "");
+    }
+
+    #[test]
+    fn test_endpoint_ends_window_at_pause() {
+        let speech = VADResult {
+            is_speech: true,
+            confidence: 0.9,
+        };
+        let silence = VADResult {
+            is_speech: false,
+            confidence: 0.0,
+        };
+        // 10s windows, 0.5s overlap, 96ms (3 frames of 512) ends an utterance
+        let mut buffer = SlidingAudioBuffer::new(10.0, 0.5)
+            .unwrap()
+            .with_endpointing(96)
+            .unwrap();
+        assert_eq!(buffer.endpoint_silence_ms(), Some(96));
+        let frame = [100i16; 512];
+
+        // Leading silence alone never endpoints
+        assert!(buffer.push_frame(&frame, &silence).is_empty());
+        for _ in 0..10 {
+            assert!(buffer.push_frame(&frame, &speech).is_empty());
+        }
+        assert!(buffer.push_frame(&frame, &silence).is_empty());
+        assert!(buffer.push_frame(&frame, &silence).is_empty());
+        let windows = buffer.push_frame(&frame, &silence);
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].start_sample, 0);
+        assert_eq!(windows[0].samples.len(), 14 * 512);
+        assert_eq!(windows[0].overlap_samples, 0);
+
+        // The next utterance starts fresh: no shared audio, no seam dedup
+        buffer.push_frame(&frame, &speech);
+        buffer.push_frame(&frame, &silence);
+        buffer.push_frame(&frame, &silence);
+        let next = buffer.push_frame(&frame, &silence);
+        assert_eq!(next[0].start_sample, 14 * 512);
+        assert_eq!(next[0].overlap_samples, 0);
+        assert!(buffer.flush().is_none());
+
+        assert_eq!(
+            buffer.merge_window_transcript(&windows[0], "go home"),
+            "go home"
+        );
+        assert_eq!(
+            buffer.merge_window_transcript(&next[0], "home now"),
+            "home now"
+        );
+
+        assert!(SlidingAudioBuffer::default().with_endpointing(0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(SlidingAudioBuffer::new(0.0, 0.0).is_err());
+        assert!(SlidingAudioBuffer::new(5.0, 5.0).is_err());
+        assert!(SlidingAudioBuffer::new(5.0, -1.0).is_err());
+        assert!(SlidingAudioBuffer::new(5.0, 4.9).is_ok());
+    }
+}
@@ -0,0 +1,527 @@
+//! Reconfigurable Audio Stage Chain
+//!
+//! A call's audio path is a short chain of per-frame transforms (resample,
+//! gain, VAD gating, translation taps). `StageChain` runs them in order and
+//! lets stages be inserted or removed while the call is live — e.g. toggling
+//! a stage from the UI without tearing down the session.
+//!
+//! Splicing takes the same lock as `process`, so it always lands between two
+//! frames: every frame goes through either the old chain or the new one,
+//! never half of each, and none is dropped or repeated. A removed stage is
+//! flushed into the stages after it so audio it was holding isn't lost.
+//!
+//! Each stage is timed with `StageMetrics`; `metrics()` reports them in
+//! current chain order.
+//!
+//! A deadlocked stage holds the chain lock forever and frames silently stop.
+//! `spawn_watchdog` watches the chain's last progress (lock-free) and emits
+//! `StreamEvent::Stalled` once nothing has moved for the stall timeout,
+//! optionally cancelling the chain so later frames fail fast instead of
+//! queueing behind the stuck stage.
+
+use super::aec::{AecConfig, AecReference, AecStage};
+use super::dtmf::{DtmfEvent, DtmfStage};
+use super::jitter_buffer::{JitterBufferStage, JitterConfig, JitterInput};
+use crate::live::handle::Handle;
+use crate::live::types::{AudioFrame, FrameMeta};
+use crate::runtime::stage_metrics::{PipelineMetrics, StageMetrics};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+/// Stall timeout for live voice (a dozen missed 20ms frames is audible)
+pub const VOICE_STALL_TIMEOUT_MS: u64 = 250;
+
+/// Marker for "no frame in flight" in `ChainProgress::current_stage`
+const NO_STAGE: usize = usize::MAX;
+
+/// Health events from a running chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StreamEvent {
+    /// Nothing moved for the stall timeout. `stage_index` is the stage a
+    /// frame is stuck in, or None when frames stopped arriving at all.
+    Stalled {
+        stage_index: Option<usize>,
+        idle_ms: u64,
+    },
+    /// Audio and video pts are further apart than the sync threshold
+    /// (positive: audio ahead). Emitted by `SyncStage` once per excursion.
+    SyncDrift { drift_ms: i64 },
+}
+
+/// Watchdog settings for `StageChain::spawn_watchdog`
+#[derive(Debug, Clone, Copy)]
+pub struct StallConfig {
+    pub stall_timeout: Duration,
+    /// Cancel the chain on the first stall
+    pub auto_cancel: bool,
+}
+
+impl StallConfig {
+    pub fn new(stall_timeout_ms: u64, auto_cancel: bool) -> Self {
+        Self {
+            stall_timeout: Duration::from_millis(stall_timeout_ms),
+            auto_cancel,
+        }
+    }
+}
+
+/// Progress markers updated on every frame, readable without the chain lock
+struct ChainProgress {
+    epoch: Instant,
+    /// Ms since `epoch` of the last frame arriving or stage finishing
+    last_progress_ms: AtomicU64,
+    /// Stage a frame is currently inside (NO_STAGE between frames)
+    current_stage: AtomicUsize,
+    frames: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+impl ChainProgress {
+    fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last_progress_ms: AtomicU64::new(0),
+            current_stage: AtomicUsize::new(NO_STAGE),
+            frames: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    fn mark(&self, stage: usize) {
+        self.last_progress_ms
+            .store(self.now_ms(), Ordering::Relaxed);
+        self.current_stage.store(stage, Ordering::Relaxed);
+    }
+}
+
+/// One per-frame transform in a StageChain.
+pub trait AudioStage: Send {
+    /// Stable name for metrics and logging
+    fn name(&self) -> &str;
+
+    /// Transform one frame. May return more, fewer, or no samples.
+    fn process(&mut self, samples: &[i16]) -> Vec<i16>;
+
+    /// `process` with the frame's timing. Stages that need `seq` or
+    /// `pts_ms` (loss detection, A/V sync) override this; the default keeps
+    /// the input's `FrameMeta` on the output.
+    fn process_frame(&mut self, frame: AudioFrame) -> AudioFrame {
+        AudioFrame {
+            meta: frame.meta,
+            samples: self.process(&frame.samples),
+        }
+    }
+
+    /// Emit any buffered samples. Called when the stage is removed.
+    fn flush(&mut self) -> Vec<i16> {
+        Vec::new()
+    }
+}
+
+struct ChainEntry {
+    stage: Box<dyn AudioStage>,
+    metrics: Arc<StageMetrics>,
+}
+
+/// Ordered, live-reconfigurable chain of AudioStages.
+pub struct StageChain {
+    stages: Mutex<Vec<ChainEntry>>,
+    progress: ChainProgress,
+    events: broadcast::Sender<StreamEvent>,
+}
+
+impl Default for StageChain {
+    fn default() -> Self {
+        let (events, _) = broadcast::channel(16);
+        Self {
+            stages: Mutex::new(Vec::new()),
+            progress: ChainProgress::new(),
+            events,
+        }
+    }
+}
+
+impl StageChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Chain for IVR calls. DTMF detection is first, so it sees the
+    /// caller's audio before any other stage alters it; keypresses are
+    /// published on `events`, which may also carry the carrier's
+    /// out-of-band keypresses for the same call.
+    pub fn ivr(handle: Handle, sample_rate: u32, events: broadcast::Sender<DtmfEvent>) -> Self {
+        let chain = Self::new();
+        chain.push_stage(Box::new(DtmfStage::with_sender(
+            handle,
+            sample_rate,
+            events,
+        )));
+        chain
+    }
+
+    /// Chain for audio from a network transport (WebRTC, Twilio). The
+    /// jitter buffer is first, so every later stage sees one ordered frame
+    /// per tick; the transport pushes frames into the returned input and the
+    /// chain is clocked with `process(&[])` once per frame interval.
+    pub fn network(config: JitterConfig) -> (Self, JitterInput) {
+        let (jitter, input) = JitterBufferStage::new(config);
+        let chain = Self::new();
+        chain.push_stage(Box::new(jitter));
+        (chain, input)
+    }
+
+    /// Chain for hands-free calls, where the mic hears the persona's own
+    /// playback. Echo cancellation is first, so no other stage sees the
+    /// echo; the output path pushes every frame it plays into the returned
+    /// reference.
+    pub fn hands_free(config: AecConfig) -> (Self, AecReference) {
+        let (aec, reference) = AecStage::new(config);
+        let chain = Self::new();
+        chain.push_stage(Box::new(aec));
+        (chain, reference)
+    }
+
+    /// Append a stage to the end of the chain
+    pub fn push_stage(&self, stage: Box<dyn AudioStage>) {
+        self.stages.lock().push(Self::entry(stage));
+    }
+
+    /// Insert a stage before `position` (== len appends). Takes effect from
+    /// the next frame.
+    pub fn insert_stage(&self, position: usize, stage: Box<dyn AudioStage>) -> Result<(), String> {
+        let mut stages = self.stages.lock();
+        if position > stages.len() {
+            return Err(format!(
+                "Stage position {position} out of range (chain has {} stages)",
+                stages.len()
+            ));
+        }
+        stages.insert(position, Self::entry(stage));
+        Ok(())
+    }
+
+    /// Remove the stage at `index`. Its flushed samples run through the
+    /// stages after it (as an unstamped frame) and are returned, so the
+    /// caller can forward them like any other output.
+    pub fn remove_stage(&self, index: usize) -> Result<(Box<dyn AudioStage>, Vec<i16>), String> {
+        let mut stages = self.stages.lock();
+        if index >= stages.len() {
+            return Err(format!(
+                "Stage index {index} out of range (chain has {} stages)",
+                stages.len()
+            ));
+        }
+        let mut removed = stages.remove(index);
+        let tail = AudioFrame {
+            meta: FrameMeta::default(),
+            samples: removed.stage.flush(),
+        };
+        let tail = self.run(&mut stages[index..], index, tail);
+        Ok((removed.stage, tail.samples))
+    }
+
+    /// Run one unstamped frame through every stage in order. Stages see a
+    /// default `FrameMeta`; input adapters use `process_frame`.
+    pub fn process(&self, samples: &[i16]) -> Vec<i16> {
+        self.process_frame(&AudioFrame {
+            meta: FrameMeta::default(),
+            samples: samples.to_vec(),
+        })
+        .samples
+    }
+
+    /// Run one stamped frame through every stage in order. Stages see the
+    /// timing the input adapter assigned, and the output keeps it unless a
+    /// stage retimes it. A cancelled chain returns no samples without
+    /// waiting for the stages.
+    pub fn process_frame(&self, frame: &AudioFrame) -> AudioFrame {
+        if self.is_cancelled() {
+            return AudioFrame {
+                meta: frame.meta,
+                samples: Vec::new(),
+            };
+        }
+        self.progress.frames.fetch_add(1, Ordering::Relaxed);
+        self.progress.mark(NO_STAGE);
+        let mut stages = self.stages.lock();
+        self.run(&mut stages, 0, frame.clone())
+    }
+
+    /// Stop processing frames (e.g. after a stall)
+    pub fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.progress.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Receive StreamEvents (stalls reported by the watchdog)
+    pub fn subscribe(&self) -> broadcast::Receiver<StreamEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stall event if the chain has processed frames but made no progress
+    /// for `timeout`. Never takes the chain lock.
+    pub fn check_stall(&self, timeout: Duration) -> Option<StreamEvent> {
+        let progress = &self.progress;
+        if progress.frames.load(Ordering::Relaxed) == 0 || self.is_cancelled() {
+            return None;
+        }
+        let idle_ms = progress
+            .now_ms()
+            .saturating_sub(progress.last_progress_ms.load(Ordering::Relaxed));
+        if idle_ms < timeout.as_millis() as u64 {
+            return None;
+        }
+        let stage = progress.current_stage.load(Ordering::Relaxed);
+        Some(StreamEvent::Stalled {
+            stage_index: (stage != NO_STAGE).then_some(stage),
+            idle_ms,
+        })
+    }
+
+    /// Poll for stalls in the background until the chain is dropped (or
+    /// cancelled). Each stall is reported once; a new one is only reported
+    /// after the chain has made progress again.
+    pub fn spawn_watchdog(self: &Arc<Self>, config: StallConfig) -> tokio::task::JoinHandle<()> {
+        let chain: Weak<Self> = Arc::downgrade(self);
+        let poll = (config.stall_timeout / 4).max(Duration::from_millis(5));
+        tokio::spawn(async move {
+            let mut reported_at = None;
+            loop {
+                tokio::time::sleep(poll).await;
+                let Some(chain) = chain.upgrade() else { break };
+                if chain.is_cancelled() {
+                    break;
+                }
+                let last = chain.progress.last_progress_ms.load(Ordering::Relaxed);
+                let Some(event) = chain.check_stall(config.stall_timeout) else {
+                    continue;
+                };
+                if reported_at == Some(last) {
+                    continue;
+                }
+                reported_at = Some(last);
+                let _ = chain.events.send(event);
+                if config.auto_cancel {
+                    chain.cancel();
+                    break;
+                }
+            }
+        })
+    }
+
+    /// Stage names in chain order
+    pub fn stage_names(&self) -> Vec<String> {
+        self.stages
+            .lock()
+            .iter()
+            .map(|e| e.stage.name().to_string())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.lock().is_empty()
+    }
+
+    /// Per-stage latency, in current chain order
+    pub fn metrics(&self) -> PipelineMetrics {
+        PipelineMetrics {
+            stages: self
+                .stages
+                .lock()
+                .iter()
+                .map(|e| e.metrics.stats())
+                .collect(),
+        }
+    }
+
+    fn entry(stage: Box<dyn AudioStage>) -> ChainEntry {
+        let metrics = Arc::new(StageMetrics::new(stage.name()));
+        ChainEntry { stage, metrics }
+    }
+
+    /// Run `frame` through `stages`, the first of which is chain index
+    /// `first_index`
+    fn run(
+        &self,
+        stages: &mut [ChainEntry],
+        first_index: usize,
+        mut frame: AudioFrame,
+    ) -> AudioFrame {
+        for (i, entry) in stages.iter_mut().enumerate() {
+            self.progress.mark(first_index + i);
+            let stage = &mut entry.stage;
+            frame = entry.metrics.time(|| stage.process_frame(frame));
+        }
+        self.progress.mark(NO_STAGE);
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Gain(i16);
+
+    impl AudioStage for Gain {
+        fn name(&self) -> &str {
+            "gain"
+        }
+
+        fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+            samples.iter().map(|s| s.saturating_mul(self.0)).collect()
+        }
+    }
+
+    /// Holds one frame back (like a lookahead stage)
+    #[derive(Default)]
+    struct Delay(Vec<i16>);
+
+    impl AudioStage for Delay {
+        fn name(&self) -> &str {
+            "delay"
+        }
+
+        fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+            std::mem::replace(&mut self.0, samples.to_vec())
+        }
+
+        fn flush(&mut self) -> Vec<i16> {
+            std::mem::take(&mut self.0)
+        }
+    }
+
+    #[test]
+    fn test_splice_between_frames_loses_nothing() {
+        let chain = StageChain::new();
+        chain.push_stage(Box::new(Gain(2)));
+        assert_eq!(chain.process(&[1, 2]), vec![2, 4]);
+
+        chain.insert_stage(0, Box::new(Delay::default())).unwrap();
+        assert_eq!(chain.stage_names(), vec!["delay", "gain"]);
+        assert_eq!(chain.process(&[3]), Vec::<i16>::new());
+        assert_eq!(chain.process(&[4]), vec![6]);
+
+        // Removing the delay flushes its held frame through the gain stage
+        let (removed, tail) = chain.remove_stage(0).unwrap();
+        assert_eq!(removed.name(), "delay");
+        assert_eq!(tail, vec![8]);
+        assert_eq!(chain.process(&[5]), vec![10]);
+
+        assert!(chain.insert_stage(5, Box::new(Gain(1))).is_err());
+        assert!(chain.remove_stage(1).is_err());
+
+        let metrics = chain.metrics();
+        assert_eq!(metrics.stages.len(), 1);
+        assert_eq!(metrics.stages[0].stage, "gain");
+        assert_eq!(metrics.stages[0].frames_processed, 5);
+    }
+
+    #[test]
+    fn test_process_frame_keeps_meta() {
+        let chain = StageChain::new();
+        chain.push_stage(Box::new(Gain(3)));
+        let mut sequencer = crate::live::types::FrameSequencer::new();
+        sequencer.audio(vec![0; 320]);
+        let frame = sequencer.audio(vec![1, 2]);
+
+        let out = chain.process_frame(&frame);
+        assert_eq!(out.meta, frame.meta);
+        assert_eq!(out.samples, vec![3, 6]);
+    }
+
+    #[test]
+    fn test_ivr_chain_includes_dtmf() {
+        let (sender, mut events) = broadcast::channel(4);
+        let chain = StageChain::ivr(Handle::new(), 16000, sender);
+        assert_eq!(chain.stage_names(), vec!["dtmf"]);
+        assert_eq!(chain.process(&[0; 320]), vec![0; 320]);
+        assert!(events.try_recv().is_err());
+    }
+
+    /// Blocks on every frame until its sender is dropped (a deadlocked stage)
+    struct Stuck(std::sync::mpsc::Receiver<()>);
+
+    impl AudioStage for Stuck {
+        fn name(&self) -> &str {
+            "stuck"
+        }
+
+        fn process(&mut self, samples: &[i16]) -> Vec<i16> {
+            let _ = self.0.recv();
+            samples.to_vec()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watchdog_reports_stuck_stage_and_cancels() {
+        let (release, blocked) = std::sync::mpsc::channel();
+        let chain = Arc::new(StageChain::new());
+        chain.push_stage(Box::new(Gain(1)));
+        chain.push_stage(Box::new(Stuck(blocked)));
+        let mut events = chain.subscribe();
+        chain.spawn_watchdog(StallConfig::new(40, true));
+
+        let worker = {
+            let chain = chain.clone();
+            std::thread::spawn(move || chain.process(&[1, 2, 3]))
+        };
+
+        let event = tokio::time::timeout(Duration::from_secs(2), events.recv())
+            .await
+            .expect("watchdog should report the stall")
+            .unwrap();
+        match event {
+            StreamEvent::Stalled {
+                stage_index,
+                idle_ms,
+            } => {
+                assert_eq!(stage_index, Some(1));
+                assert!(idle_ms >= 40);
+            }
+            other => panic!("unexpected event {other:?}"),
+        }
+
+        // Cancelled: new frames return at once instead of queueing
+        assert!(chain.is_cancelled());
+        assert!(chain.process(&[4]).is_empty());
+
+        drop(release);
+        assert_eq!(worker.join().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_stall_when_frames_stop_arriving() {
+        let chain = StageChain::new();
+        chain.push_stage(Box::new(Gain(1)));
+        // Not started yet: not a stall
+        assert!(chain.check_stall(Duration::ZERO).is_none());
+
+        chain.process(&[1]);
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(matches!(
+            chain.check_stall(Duration::from_millis(20)),
+            Some(StreamEvent::Stalled {
+                stage_index: None,
+                ..
+            })
+        ));
+        assert!(chain.check_stall(Duration::from_secs(10)).is_none());
+    }
+}
@@ -0,0 +1,204 @@
+//! Prosodic Affect Estimate
+//!
+//! Coarse arousal/valence for an utterance from how it was said, not what
+//! was said: loudness, pitch variation and energy variation. It's a
+//! heuristic, not an emotion classifier. It can tell an agitated caller from
+//! a calm one, but not sarcasm, and it has no speaker baseline.
+//!
+//! - Arousal rises with loudness, pitch movement and energy swings.
+//! - Valence is positive for lively, varied pitch at a comfortable level and
+//!   negative for loud, flat delivery (tense/angry) or quiet, flat delivery
+//!   (flat/sad).
+//!
+//! Costs an autocorrelation pitch track per utterance, so STT only runs it
+//! when asked to (`SttListenerConfig::estimate_affect`, `voice/transcribe`
+//! with `affect`).
+
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
+
+/// Analysis frame (40ms, enough for two periods at the lowest pitch) and hop
+const FRAME_SIZE: usize = AUDIO_SAMPLE_RATE as usize / 25;
+const HOP_SIZE: usize = FRAME_SIZE / 2;
+
+/// Pitch search range covering adult and child voices
+const MIN_PITCH_HZ: f32 = 75.0;
+const MAX_PITCH_HZ: f32 = 400.0;
+
+/// Frames quieter than this are pauses, not speech
+const SILENCE_DBFS: f32 = -50.0;
+
+/// Normalized autocorrelation peak a frame needs to count as voiced
+const VOICING_THRESHOLD: f32 = 0.5;
+
+/// Fewer voiced frames than this (~200ms) is too little to judge
+const MIN_VOICED_FRAMES: usize = 10;
+
+/// Ranges mapped onto 0..1 for each feature
+const LOUDNESS_DBFS: (f32, f32) = (-40.0, -12.0);
+const PITCH_SPREAD_SEMITONES: (f32, f32) = (1.0, 5.0);
+const ENERGY_SPREAD_DB: (f32, f32) = (3.0, 12.0);
+
+/// How an utterance sounded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Affect {
+    /// Activation, 0 (calm) to 1 (agitated)
+    pub arousal: f32,
+    /// -1 (negative) to 1 (positive)
+    pub valence: f32,
+}
+
+/// Estimate affect from 16kHz mono PCM; None if the utterance has too
+/// little voiced speech
+pub fn estimate_affect(samples: &[i16]) -> Option<Affect> {
+    let mut speech_db = Vec::new();
+    let mut pitch_semitones = Vec::new();
+
+    let mut start = 0;
+    while start + FRAME_SIZE <= samples.len() {
+        let frame: Vec<f32> = samples[start..start + FRAME_SIZE]
+            .iter()
+            .map(|&s| s as f32 / 32768.0)
+            .collect();
+        start += HOP_SIZE;
+
+        let rms = (frame.iter().map(|s| s * s).sum::<f32>() / FRAME_SIZE as f32).sqrt();
+        let db = 20.0 * rms.max(1e-9).log10();
+        if db < SILENCE_DBFS {
+            continue;
+        }
+        speech_db.push(db);
+        if let Some(hz) = frame_pitch(&frame) {
+            pitch_semitones.push(12.0 * (hz / MIN_PITCH_HZ).log2());
+        }
+    }
+
+    if pitch_semitones.len() < MIN_VOICED_FRAMES {
+        return None;
+    }
+
+    let loudness = scale(mean(&speech_db), LOUDNESS_DBFS);
+    let pitch_spread = scale(std_dev(&pitch_semitones), PITCH_SPREAD_SEMITONES);
+    let energy_spread = scale(std_dev(&speech_db), ENERGY_SPREAD_DB);
+
+    let arousal = 0.5 * loudness + 0.3 * pitch_spread + 0.2 * energy_spread;
+    let flatness = 1.0 - pitch_spread;
+    let valence = 1.5 * (pitch_spread - 0.3) - 0.8 * loudness * flatness;
+    Some(Affect {
+        arousal: arousal.clamp(0.0, 1.0),
+        valence: valence.clamp(-1.0, 1.0),
+    })
+}
+
+/// Fundamental frequency of a frame by normalized autocorrelation, or None
+/// if it isn't voiced. Takes the shortest lag whose peak is close to the
+/// best one, so a strong peak at twice the period isn't read as an octave
+/// down. A best lag at the edge of the range (pitch near `MIN_PITCH_HZ`)
+/// isn't a local peak, so that lag itself is taken.
+fn frame_pitch(frame: &[f32]) -> Option<f32> {
+    let min_lag = (AUDIO_SAMPLE_RATE as f32 / MAX_PITCH_HZ) as usize;
+    let max_lag = ((AUDIO_SAMPLE_RATE as f32 / MIN_PITCH_HZ) as usize).min(frame.len() / 2);
+
+    let correlation: Vec<f32> = (min_lag..=max_lag)
+        .map(|lag| {
+            let (head, tail) = (&frame[..frame.len() - lag], &frame[lag..]);
+            let dot: f32 = head.iter().zip(tail).map(|(a, b)| a * b).sum();
+            let energy: f32 =
+                head.iter().map(|a| a * a).sum::<f32>() * tail.iter().map(|b| b * b).sum::<f32>();
+            if energy > 0.0 {
+                dot / energy.sqrt()
+            } else {
+                0.0
+            }
+        })
+        .collect();
+
+    let best = correlation.iter().copied().fold(f32::MIN, f32::max);
+    if best < VOICING_THRESHOLD {
+        return None;
+    }
+    let index = (1..correlation.len() - 1)
+        .find(|&i| {
+            correlation[i] >= 0.9 * best
+                && correlation[i] >= correlation[i - 1]
+                && correlation[i] >= correlation[i + 1]
+        })
+        .or_else(|| {
+            correlation
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(i, _)| i)
+        })?;
+    Some(AUDIO_SAMPLE_RATE as f32 / (min_lag + index) as f32)
+}
+
+fn scale(value: f32, (low, high): (f32, f32)) -> f32 {
+    ((value - low) / (high - low)).clamp(0.0, 1.0)
+}
+
+fn mean(values: &[f32]) -> f32 {
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+fn std_dev(values: &[f32]) -> f32 {
+    let mean = mean(values);
+    (values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    /// One second of a tone at `amplitude` whose pitch follows `pitch_at(t)`
+    fn tone(amplitude: f32, pitch_at: impl Fn(f32) -> f32) -> Vec<i16> {
+        let mut phase = 0.0;
+        (0..AUDIO_SAMPLE_RATE)
+            .map(|i| {
+                let t = i as f32 / AUDIO_SAMPLE_RATE as f32;
+                phase += 2.0 * PI * pitch_at(t) / AUDIO_SAMPLE_RATE as f32;
+                (amplitude * phase.sin() * 32767.0) as i16
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_affect_from_prosody() {
+        assert_eq!(frame_pitch(&vec![0.0; FRAME_SIZE]), None);
+        let frame: Vec<f32> = tone(0.5, |_| 200.0)[..FRAME_SIZE]
+            .iter()
+            .map(|&s| s as f32 / 32768.0)
+            .collect();
+        let hz = frame_pitch(&frame).unwrap();
+        assert!((195.0..=205.0).contains(&hz), "{hz}");
+        // The lowest pitch peaks on the last lag searched
+        let frame: Vec<f32> = tone(0.5, |_| MIN_PITCH_HZ)[..FRAME_SIZE]
+            .iter()
+            .map(|&s| s as f32 / 32768.0)
+            .collect();
+        let hz = frame_pitch(&frame).unwrap();
+        assert!(
+            (MIN_PITCH_HZ - 2.0..=MIN_PITCH_HZ + 2.0).contains(&hz),
+            "{hz}"
+        );
+
+        // Loud and flat: agitated, negative
+        let shouting = estimate_affect(&tone(0.8, |_| 220.0)).unwrap();
+        // Moderate level, pitch swinging an octave: lively, positive
+        let lively =
+            estimate_affect(&tone(0.1, |t| 150.0 * 2f32.powf((PI * 3.0 * t).sin()))).unwrap();
+        // Quiet and flat: calm
+        let flat = estimate_affect(&tone(0.02, |_| 120.0)).unwrap();
+
+        assert!(shouting.arousal > flat.arousal);
+        assert!(lively.arousal > flat.arousal);
+        assert!(shouting.valence < -0.5, "{shouting:?}");
+        assert!(lively.valence > 0.3, "{lively:?}");
+
+        assert_eq!(estimate_affect(&[0; AUDIO_SAMPLE_RATE as usize]), None);
+        assert_eq!(
+            estimate_affect(&tone(0.5, |_| 200.0)[..FRAME_SIZE * 2]),
+            None
+        );
+    }
+}
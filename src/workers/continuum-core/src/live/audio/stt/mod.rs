@@ -8,12 +8,16 @@
 //! - Azure Cognitive Services
 //!
 //! Uses trait-based polymorphism (OpenCV-style) for runtime flexibility.
+//! Downstream crates plug in their own backend with `register_adapter`
+//! before `initialize()`; see `SpeechToText` for the adapter contract.
 
+mod affect;
 mod moonshine;
 mod openai_realtime;
 mod stub;
 mod whisper;
 
+pub use affect::{estimate_affect, Affect};
 pub use moonshine::MoonshineStt;
 pub use openai_realtime::{OpenAIRealtimeSTT, TurnDetection, TurnDetectionType};
 pub use stub::StubSTT;
@@ -53,9 +57,15 @@ pub enum STTError {
 #[derive(Debug, Clone)]
 pub struct TranscriptResult {
     pub text: String,
+    /// Source-language text when `text` is an English translation
+    /// (`SttTask::Translate { keep_original: true }` on non-English audio)
+    pub original_text: Option<String>,
     pub language: String,
     pub confidence: f32,
     pub segments: Vec<TranscriptSegment>,
+    /// Prosody-based arousal/valence, when requested (adapters leave it
+    /// None; see `estimate_affect`)
+    pub affect: Option<Affect>,
 }
 
 /// Word/phrase segment with timing
@@ -66,9 +76,50 @@ pub struct TranscriptSegment {
     pub end_ms: i64,
 }
 
+/// What an STT pass produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SttTask {
+    /// Text in the spoken language
+    #[default]
+    Transcribe,
+    /// English text whatever the spoken language. `keep_original` also
+    /// returns the source-language text (a second pass on non-English audio).
+    Translate { keep_original: bool },
+}
+
+impl SttTask {
+    /// Parse an IPC `task` value ("transcribe" or "translate")
+    pub fn parse(task: &str, keep_original: bool) -> Result<Self, STTError> {
+        match task {
+            "transcribe" => Ok(Self::Transcribe),
+            "translate" => Ok(Self::Translate { keep_original }),
+            other => Err(STTError::InvalidAudio(format!(
+                "Unknown STT task '{other}' (expected 'transcribe' or 'translate')"
+            ))),
+        }
+    }
+}
+
 /// Speech-to-Text adapter trait
 ///
-/// Implement this for each STT backend (Whisper, Deepgram, etc.)
+/// Implement this for each STT backend (Whisper, Deepgram, etc.) and add it
+/// with `register_adapter`.
+///
+/// # Contract for implementers
+///
+/// - **Audio**: `transcribe` gets mono f32 samples in -1.0..=1.0 at
+///   `AUDIO_SAMPLE_RATE` (16kHz). Resample inside the adapter if the backend
+///   wants another rate.
+/// - **Framing**: each call is one whole utterance, not a fixed-size frame.
+///   The VAD cuts speech at pauses and force-splits at ~10s, so expect
+///   ~100ms to ~10s (longer via `voice/transcribe`). Pad audio the backend
+///   finds too short; reject empty input with `STTError::InvalidAudio`.
+/// - **Concurrency**: calls overlap (one per speaker per call). Serialize
+///   internally if the backend can't run them in parallel.
+/// - **Lifecycle**: `initialize` is idempotent and may be called again after
+///   `shutdown`; `transcribe` before it returns `STTError::ModelNotLoaded`.
+/// - **Naming**: `name()` is the registry key. Registering a second adapter
+///   with the same name replaces the first.
 #[async_trait]
 pub trait SpeechToText: Send + Sync {
     /// Adapter name (e.g., "whisper", "deepgram")
@@ -94,6 +145,38 @@ pub trait SpeechToText: Send + Sync {
         language: Option<&str>,
     ) -> Result<TranscriptResult, STTError>;
 
+    /// Run `task` on audio samples. Adapters that can translate override
+    /// this; the default only handles `SttTask::Transcribe`.
+    async fn transcribe_task(
+        &self,
+        samples: Vec<f32>,
+        language: Option<&str>,
+        task: SttTask,
+    ) -> Result<TranscriptResult, STTError> {
+        match task {
+            SttTask::Transcribe => self.transcribe(samples, language).await,
+            SttTask::Translate { .. } => Err(STTError::InferenceFailed(format!(
+                "STT adapter '{}' does not support translation",
+                self.name()
+            ))),
+        }
+    }
+
+    /// An adapter for one session that biases decoding toward `terms`
+    /// (names, jargon, product words) and otherwise shares this adapter's
+    /// model and settings. Default: the adapter can't be biased.
+    fn with_vocabulary(&self, _terms: Vec<String>) -> Result<Arc<dyn SpeechToText>, STTError> {
+        Err(STTError::InferenceFailed(format!(
+            "STT adapter '{}' does not support vocabulary biasing",
+            self.name()
+        )))
+    }
+
+    /// Whether `transcribe_task` accepts `SttTask::Translate`
+    fn supports_translation(&self) -> bool {
+        false
+    }
+
     /// Get supported languages
     fn supported_languages(&self) -> Vec<&'static str> {
         vec!["en"] // Default to English only
@@ -109,6 +192,17 @@ pub trait SpeechToText: Send + Sync {
         Ok(())
     }
 
+    /// Switch to another model, e.g. a smaller one for lower latency.
+    /// `model` is whatever the adapter accepts (a size name, a file path);
+    /// returns the name of the model now loaded. Default: the adapter has
+    /// a single model.
+    async fn load_model(&self, _model: &str) -> Result<String, STTError> {
+        Err(STTError::InferenceFailed(format!(
+            "STT adapter '{}' does not support model selection",
+            self.name()
+        )))
+    }
+
     /// Shut down the adapter, releasing loaded models and GPU allocations.
     ///
     /// In-flight inference that already cloned the model Arc continues safely.
@@ -125,6 +219,8 @@ pub trait SpeechToText: Send + Sync {
 pub struct STTRegistry {
     adapters: HashMap<&'static str, Arc<dyn SpeechToText>>,
     active: Option<&'static str>,
+    /// Model chosen with `load_model`, per adapter
+    models: HashMap<&'static str, String>,
 }
 
 impl STTRegistry {
@@ -132,6 +228,7 @@ impl STTRegistry {
         Self {
             adapters: HashMap::new(),
             active: None,
+            models: HashMap::new(),
         }
     }
 
@@ -158,6 +255,14 @@ impl STTRegistry {
         }
     }
 
+    /// Register an adapter and make it the active one
+    pub fn register_active(&mut self, adapter: Arc<dyn SpeechToText>) {
+        let name = adapter.name();
+        self.register(adapter);
+        self.active = Some(name);
+        clog_info!("STT: Active adapter set to '{}'", name);
+    }
+
     /// Get the active adapter
     pub fn get_active(&self) -> Option<Arc<dyn SpeechToText>> {
         self.active
@@ -170,6 +275,15 @@ impl STTRegistry {
         self.adapters.get(name).cloned()
     }
 
+    /// Get adapter by name, or an error listing the registered names
+    pub fn resolve(&self, name: &str) -> Result<Arc<dyn SpeechToText>, STTError> {
+        self.get(name).ok_or_else(|| {
+            let mut available: Vec<&str> = self.adapters.keys().copied().collect();
+            available.sort_unstable();
+            STTError::AdapterNotFound(format!("'{name}' (available: {})", available.join(", ")))
+        })
+    }
+
     /// List all registered adapters
     pub fn list(&self) -> Vec<(&'static str, bool)> {
         self.adapters
@@ -178,6 +292,22 @@ impl STTRegistry {
             .collect()
     }
 
+    /// Record the model an adapter switched to
+    pub fn set_model(&mut self, adapter: &'static str, model: String) {
+        clog_info!("STT: Adapter '{}' now using model '{}'", adapter, model);
+        self.models.insert(adapter, model);
+    }
+
+    /// Model an adapter was switched to, if `load_model` was used on it
+    pub fn model(&self, adapter: &str) -> Option<&str> {
+        self.models.get(adapter).map(String::as_str)
+    }
+
+    /// Model of the active adapter, if one was chosen
+    pub fn active_model(&self) -> Option<&str> {
+        self.active.and_then(|name| self.model(name))
+    }
+
     /// Check if any adapter is initialized
     pub fn is_initialized(&self) -> bool {
         self.get_active()
@@ -234,6 +364,19 @@ pub fn get_registry() -> Arc<RwLock<STTRegistry>> {
     })
 }
 
+/// Add an adapter to the global registry and make it active.
+///
+/// Call before `initialize()` to swap in a backend from another crate; the
+/// built-in adapters stay registered and selectable by name.
+pub fn register_adapter(adapter: Box<dyn SpeechToText>) {
+    get_registry().write().register_active(Arc::from(adapter));
+}
+
+/// Look up a registered adapter by name
+pub fn get_adapter(name: &str) -> Result<Arc<dyn SpeechToText>, STTError> {
+    get_registry().read().resolve(name)
+}
+
 /// Check if STT is initialized (convenience function)
 pub fn is_initialized() -> bool {
     get_registry().read().is_initialized()
@@ -252,7 +395,58 @@ pub async fn transcribe(
     adapter.transcribe(samples, language).await
 }
 
-/// Initialize the active adapter
+/// Transcribe or translate using the active adapter (convenience function)
+pub async fn transcribe_task(
+    samples: Vec<f32>,
+    language: Option<&str>,
+    task: SttTask,
+) -> Result<TranscriptResult, STTError> {
+    let adapter = get_registry()
+        .read()
+        .get_active()
+        .ok_or_else(|| STTError::AdapterNotFound("No active STT adapter".to_string()))?;
+
+    adapter.transcribe_task(samples, language, task).await
+}
+
+/// Switch an adapter (by name, or the active one) to another model and
+/// record it in the registry. Returns the name of the model now loaded.
+pub async fn load_model(adapter: Option<&str>, model: &str) -> Result<String, STTError> {
+    let adapter = {
+        let registry = get_registry();
+        let reg = registry.read();
+        match adapter {
+            Some(name) => reg.resolve(name)?,
+            None => reg
+                .get_active()
+                .ok_or_else(|| STTError::AdapterNotFound("No active STT adapter".to_string()))?,
+        }
+    };
+
+    let loaded = adapter.load_model(model).await?;
+    get_registry()
+        .write()
+        .set_model(adapter.name(), loaded.clone());
+    Ok(loaded)
+}
+
+/// Set a configuration parameter on an adapter (by name, or the active one)
+pub fn set_param(adapter: Option<&str>, name: &str, value: &str) -> Result<(), STTError> {
+    let adapter = {
+        let registry = get_registry();
+        let reg = registry.read();
+        match adapter {
+            Some(name) => reg.resolve(name)?,
+            None => reg
+                .get_active()
+                .ok_or_else(|| STTError::AdapterNotFound("No active STT adapter".to_string()))?,
+        }
+    };
+    adapter.set_param(name, value)
+}
+
+/// Initialize the active adapter (the last one passed to `register_adapter`,
+/// otherwise Whisper)
 pub async fn initialize() -> Result<(), STTError> {
     let adapter = get_registry()
         .read()
@@ -264,3 +458,111 @@ pub async fn initialize() -> Result<(), STTError> {
 
 // Audio utility functions moved to crate::utils::audio
 // Use crate::utils::audio::{i16_to_f32, resample, resample_to_16k} instead
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio_constants::AUDIO_SAMPLE_RATE;
+
+    #[test]
+    fn test_stt_task_parse() {
+        assert_eq!(
+            SttTask::parse("transcribe", true).unwrap(),
+            SttTask::Transcribe
+        );
+        assert_eq!(
+            SttTask::parse("translate", true).unwrap(),
+            SttTask::Translate {
+                keep_original: true
+            }
+        );
+        assert!(SttTask::parse("summarize", false).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_default_transcribe_task_rejects_translate() {
+        let adapter = StubSTT::new();
+        adapter.initialize().await.unwrap();
+        assert!(!adapter.supports_translation());
+
+        let one_second = || vec![0.0; AUDIO_SAMPLE_RATE as usize];
+        let result = adapter
+            .transcribe_task(one_second(), None, SttTask::Transcribe)
+            .await
+            .unwrap();
+        assert!(result.original_text.is_none());
+
+        let translate = SttTask::Translate {
+            keep_original: false,
+        };
+        let err = adapter
+            .transcribe_task(one_second(), None, translate)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not support translation"));
+    }
+
+    #[test]
+    fn test_registered_adapter_becomes_active() {
+        let mut registry = STTRegistry::new();
+        registry.register(Arc::new(StubSTT::new()));
+        assert_eq!(registry.get_active().unwrap().name(), "stub");
+
+        registry.register_active(Arc::new(NamedStt("cloud")));
+        assert_eq!(registry.get_active().unwrap().name(), "cloud");
+        assert_eq!(registry.resolve("stub").unwrap().name(), "stub");
+
+        let err = registry.resolve("deepgram").err().unwrap().to_string();
+        assert!(err.contains("'deepgram' (available: cloud, stub)"));
+    }
+
+    #[tokio::test]
+    async fn test_registry_tracks_loaded_model() {
+        let mut registry = STTRegistry::new();
+        registry.register(Arc::new(NamedStt("cloud")));
+        assert_eq!(registry.active_model(), None);
+
+        // Single-model adapters say so
+        let err = registry
+            .resolve("cloud")
+            .unwrap()
+            .load_model("tiny.en")
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("does not support model selection"));
+
+        registry.register(Arc::new(StubSTT::new()));
+        registry.set_model("cloud", "tiny.en".into());
+        assert_eq!(registry.active_model(), Some("tiny.en"));
+        assert_eq!(registry.model("stub"), None);
+    }
+
+    struct NamedStt(&'static str);
+
+    #[async_trait]
+    impl SpeechToText for NamedStt {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn description(&self) -> &'static str {
+            "test adapter"
+        }
+
+        fn is_initialized(&self) -> bool {
+            true
+        }
+
+        async fn initialize(&self) -> Result<(), STTError> {
+            Ok(())
+        }
+
+        async fn transcribe(
+            &self,
+            _samples: Vec<f32>,
+            _language: Option<&str>,
+        ) -> Result<TranscriptResult, STTError> {
+            Err(STTError::InferenceFailed("unused".into()))
+        }
+    }
+}
@@ -320,9 +320,11 @@ impl MoonshineStt {
         if current_token == EOS_TOKEN_ID {
             return Ok(TranscriptResult {
                 text: String::new(),
+                original_text: None,
                 language: "en".to_string(),
                 confidence: 0.0,
                 segments: vec![],
+                affect: None,
             });
         }
         generated_tokens.push(current_token);
@@ -402,6 +404,7 @@ impl MoonshineStt {
 
         Ok(TranscriptResult {
             text,
+            original_text: None,
             language: "en".to_string(),
             confidence: 0.9, // Moonshine doesn't expose per-token confidence
             segments: vec![TranscriptSegment {
@@ -409,6 +412,7 @@ impl MoonshineStt {
                 start_ms: 0,
                 end_ms: duration_ms as i64,
             }],
+            affect: None,
         })
     }
 
@@ -304,9 +304,11 @@ impl OpenAIRealtimeSTT {
 
         Ok(TranscriptResult {
             text: transcript.trim().to_string(),
+            original_text: None,
             language: "en".to_string(),
             confidence: 0.95,
             segments: vec![],
+            affect: None,
         })
     }
 }
@@ -101,6 +101,7 @@ impl SpeechToText for StubSTT {
 
         Ok(TranscriptResult {
             text: text.clone(),
+            original_text: None,
             language: lang,
             confidence: STUB_CONFIDENCE,
             segments: vec![TranscriptSegment {
@@ -108,6 +109,7 @@ impl SpeechToText for StubSTT {
                 start_ms: 0,
                 end_ms: duration_ms,
             }],
+            affect: None,
         })
     }
 
@@ -2,16 +2,85 @@
 //!
 //! Local Whisper inference using whisper-rs (bindings to whisper.cpp).
 //! Runs on CPU with optional GPU acceleration.
+//!
+//! The model is picked from disk at startup, and can be switched at runtime
+//! with `load_model` (e.g. `tiny.en` on edge devices, `medium` on servers);
+//! a named model missing from disk is downloaded from HuggingFace.
 
-use super::{STTError, SpeechToText, TranscriptResult, TranscriptSegment};
+use super::{STTError, SpeechToText, SttTask, TranscriptResult, TranscriptSegment};
 use crate::audio_constants::AUDIO_SAMPLE_RATE;
 use crate::{clog_info, clog_warn};
 use async_trait::async_trait;
 use crate::live::audio::reloadable::ReloadableModel;
 use parking_lot::Mutex;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+use whisper_rs::{
+    FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperToken,
+};
+
+/// No-speech probability above which Whisper treats a window as silence
+/// (whisper.cpp's own default)
+pub const DEFAULT_NO_SPEECH_MAX: f32 = 0.6;
+
+/// HuggingFace repo that publishes whisper.cpp's ggml models
+const HF_REPO: &str = "ggerganov/whisper.cpp";
+
+/// Model names `load_model` accepts, each `ggml-{name}.bin` in `HF_REPO`
+const MODEL_SIZES: &[&str] = &[
+    "tiny",
+    "tiny.en",
+    "base",
+    "base.en",
+    "small",
+    "small.en",
+    "medium",
+    "medium.en",
+    "large-v3",
+    "large-v3-turbo",
+];
+
+/// Vocabulary prompt length limit. Whisper keeps at most 224 prompt tokens
+/// and drops the oldest; at ~4 chars per token this stays inside that.
+const MAX_PROMPT_CHARS: usize = 800;
+
+/// Average token log-probability below which a segment is dropped as a
+/// likely hallucination (Whisper's standard "decode failed" threshold)
+pub const DEFAULT_AVG_LOGPROB_MIN: f32 = -1.0;
+
+/// Drops the text Whisper invents on silence ("Thank you.", "Subtitles by…").
+///
+/// `no_speech_max` goes to whisper.cpp, which discards a decoding window
+/// when its no-speech probability exceeds it and the decode itself isn't
+/// confident (whisper-rs doesn't expose per-segment no-speech probability,
+/// so it can't be checked here). `avg_logprob_min` is checked per segment
+/// against the mean log-probability of its text tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HallucinationFilter {
+    no_speech_max: f32,
+    avg_logprob_min: f32,
+}
+
+impl Default for HallucinationFilter {
+    fn default() -> Self {
+        Self {
+            no_speech_max: DEFAULT_NO_SPEECH_MAX,
+            avg_logprob_min: DEFAULT_AVG_LOGPROB_MIN,
+        }
+    }
+}
+
+impl HallucinationFilter {
+    /// Whether to keep a segment with the given text-token log-probabilities.
+    /// Segments with no text tokens have nothing to judge and are kept.
+    fn keeps(&self, token_logprobs: &[f32]) -> bool {
+        if token_logprobs.is_empty() {
+            return true;
+        }
+        let avg = token_logprobs.iter().sum::<f32>() / token_logprobs.len() as f32;
+        avg >= self.avg_logprob_min
+    }
+}
 
 /// Pre-allocated Whisper state (loaded once, reused for all transcriptions).
 ///
@@ -24,24 +93,75 @@ use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextPar
 /// so it's fully self-contained — no lifetime issues.
 struct WhisperRuntime {
     state: whisper_rs::WhisperState,
+    /// `.en` models can only transcribe English; translation needs a multilingual model
+    multilingual: bool,
+    /// Token ids from here up are special (timestamps, task/language markers)
+    token_eot: WhisperToken,
 }
 
 static WHISPER_RT: ReloadableModel<Mutex<WhisperRuntime>> = ReloadableModel::new("Whisper");
 
+/// Settings shared by an adapter and its per-session vocabulary copies
+#[derive(Default)]
+struct WhisperSettings {
+    /// Set by the constructor or `load_model`; reloads after an idle
+    /// unload use it too
+    model_path: Mutex<Option<PathBuf>>,
+    filter: Mutex<HallucinationFilter>,
+}
+
 /// Whisper STT Adapter - local inference
 pub struct WhisperSTT {
-    model_path: Option<PathBuf>,
+    settings: Arc<WhisperSettings>,
+    /// Initial prompt built from the vocabulary (see `with_vocabulary`)
+    prompt: Option<String>,
 }
 
 impl WhisperSTT {
     pub fn new() -> Self {
-        Self { model_path: None }
+        Self {
+            settings: Arc::default(),
+            prompt: None,
+        }
     }
 
     pub fn with_model_path(model_path: PathBuf) -> Self {
-        Self {
-            model_path: Some(model_path),
+        let adapter = Self::new();
+        *adapter.settings.model_path.lock() = Some(model_path);
+        adapter
+    }
+
+    /// Configure hallucination suppression: windows whose no-speech
+    /// probability exceeds `no_speech_max` (0.0-1.0) and segments whose
+    /// average token log-probability falls below `avg_logprob_min` are
+    /// dropped from the transcript. `f32::NEG_INFINITY` disables the
+    /// log-probability floor.
+    pub fn set_hallucination_filter(&self, no_speech_max: f32, avg_logprob_min: f32) {
+        *self.settings.filter.lock() = HallucinationFilter {
+            no_speech_max: no_speech_max.clamp(0.0, 1.0),
+            avg_logprob_min,
+        };
+    }
+
+    fn vocabulary_prompt(terms: &[String]) -> Option<String> {
+        let mut prompt = String::new();
+        let mut seen = std::collections::HashSet::new();
+        for term in terms {
+            // whisper.cpp takes the prompt as a C string
+            let term = term.replace('\0', "");
+            let term = term.trim();
+            if term.is_empty() || !seen.insert(term.to_lowercase()) {
+                continue;
+            }
+            if prompt.len() + term.len() + 2 > MAX_PROMPT_CHARS {
+                break;
+            }
+            if !prompt.is_empty() {
+                prompt.push_str(", ");
+            }
+            prompt.push_str(term);
         }
+        (!prompt.is_empty()).then_some(prompt)
     }
 
     /// Model preference order: best quality/speed ratio first.
@@ -71,9 +191,9 @@ impl WhisperSTT {
     /// 2. `WHISPER_MODEL` env var (user override)
     /// 3. Auto-select: scan disk for best available (turbo > large-v3 > medium > small > base)
     fn find_model_path(&self) -> PathBuf {
-        // 1. Explicit model path from constructor
-        if let Some(ref path) = self.model_path {
-            return path.clone();
+        // 1. Explicit model path from constructor or load_model
+        if let Some(path) = self.settings.model_path.lock().clone() {
+            return path;
         }
 
         let search_dirs = Self::model_search_dirs();
@@ -132,11 +252,93 @@ impl WhisperSTT {
         PathBuf::from("models/whisper/ggml-large-v3-turbo.bin")
     }
 
-    /// Synchronous transcription using pre-allocated state (runs on blocking thread)
+    /// Path of a named model: on disk if present, otherwise downloaded from
+    /// HuggingFace (blocking; hf-hub caches it after the first run)
+    fn fetch_model(size: &str) -> Result<PathBuf, STTError> {
+        let file = format!("ggml-{size}.bin");
+        if let Some(path) = Self::model_search_dirs()
+            .into_iter()
+            .map(|dir| dir.join(&file))
+            .find(|path| path.exists())
+        {
+            return Ok(path);
+        }
+
+        clog_info!(
+            "Whisper: {} not found locally, downloading from https://huggingface.co/{}",
+            file,
+            HF_REPO
+        );
+        let api = hf_hub::api::sync::Api::new().map_err(|e| {
+            STTError::ModelNotLoaded(format!("HuggingFace client init failed: {e}"))
+        })?;
+        api.model(HF_REPO.to_string()).get(&file).map_err(|e| {
+            clog_warn!("Whisper: Download of {} failed: {}", file, e);
+            clog_warn!(
+                "  Download manually from: https://huggingface.co/{}/tree/main",
+                HF_REPO
+            );
+            clog_warn!("  Place in: models/whisper/");
+            STTError::ModelNotLoaded(format!("Failed to download {file}: {e}"))
+        })
+    }
+
+    /// A model name from `MODEL_SIZES`, or a path to a ggml model file
+    fn resolve_model(path_or_size: &str) -> Result<PathBuf, STTError> {
+        if MODEL_SIZES.contains(&path_or_size) {
+            return Self::fetch_model(path_or_size);
+        }
+        let path = PathBuf::from(path_or_size);
+        if !path.exists() {
+            return Err(STTError::ModelNotLoaded(format!(
+                "Model not found: {path:?} (expected a model file or one of: {})",
+                MODEL_SIZES.join(", ")
+            )));
+        }
+        Ok(path)
+    }
+
+    /// `ggml-tiny.en.bin` → `tiny.en`; other file names as they are
+    fn model_label(path: &Path) -> String {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        name.strip_prefix("ggml-")
+            .and_then(|n| n.strip_suffix(".bin"))
+            .map(str::to_string)
+            .unwrap_or(name)
+    }
+
+    /// Load a model file and allocate its reusable state
+    fn load_runtime(model_path: &Path) -> Result<WhisperRuntime, STTError> {
+        let params = WhisperContextParameters::default();
+        let ctx = WhisperContext::new_with_params(model_path.to_str().unwrap_or(""), params)
+            .map_err(|e| STTError::ModelNotLoaded(e.to_string()))?;
+
+        // Create ONE state that holds an Arc to the context internally.
+        // This state is reused for all transcriptions — no 407MB allocation per call.
+        let state = ctx
+            .create_state()
+            .map_err(|e| STTError::ModelNotLoaded(format!("Failed to create state: {e}")))?;
+
+        Ok(WhisperRuntime {
+            state,
+            multilingual: ctx.is_multilingual(),
+            token_eot: ctx.token_eot(),
+        })
+    }
+
+    /// Synchronous transcription using pre-allocated state (runs on blocking thread).
+    /// With `translate`, Whisper emits English text; `language` in the result
+    /// is still the detected spoken language.
     fn transcribe_sync(
         rt: &Arc<Mutex<WhisperRuntime>>,
         mut samples: Vec<f32>,
         language: Option<&str>,
+        translate: bool,
+        filter: HallucinationFilter,
+        prompt: Option<&str>,
     ) -> Result<TranscriptResult, STTError> {
         if samples.is_empty() {
             return Err(STTError::InvalidAudio("Empty audio samples".into()));
@@ -167,6 +369,11 @@ impl WhisperSTT {
         }
 
         let mut rt_guard = rt.lock();
+        if translate && !rt_guard.multilingual {
+            return Err(STTError::InferenceFailed(
+                "Loaded Whisper model is English-only and cannot translate; use a multilingual model (e.g. ggml-large-v3.bin)".into(),
+            ));
+        }
 
         // Configure parameters
         let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
@@ -180,13 +387,17 @@ impl WhisperSTT {
 
         // Performance settings
         params.set_n_threads(num_cpus::get().min(4) as i32);
-        params.set_translate(false);
+        params.set_translate(translate);
         params.set_no_context(true);
         params.set_single_segment(false);
         params.set_print_special(false);
         params.set_print_progress(false);
         params.set_print_realtime(false);
         params.set_print_timestamps(false);
+        params.set_no_speech_thold(filter.no_speech_max);
+        if let Some(prompt) = prompt {
+            params.set_initial_prompt(prompt);
+        }
 
         // Reuse pre-allocated state — no 407MB allocation per call
         rt_guard.state
@@ -200,6 +411,7 @@ impl WhisperSTT {
 
         let mut full_text = String::new();
         let mut segments = Vec::new();
+        let mut token_logprobs = Vec::new();
 
         for i in 0..num_segments {
             let segment_text = rt_guard.state.full_get_segment_text(i).map_err(|e| {
@@ -216,6 +428,27 @@ impl WhisperSTT {
                 .map_err(|_| STTError::InferenceFailed("Failed to get segment end".into()))?
                 * 10;
 
+            let num_tokens = rt_guard
+                .state
+                .full_n_tokens(i)
+                .map_err(|e| STTError::InferenceFailed(format!("Failed to get tokens: {e}")))?;
+            token_logprobs.clear();
+            for t in 0..num_tokens {
+                let token = rt_guard.state.full_get_token_data(i, t).map_err(|e| {
+                    STTError::InferenceFailed(format!("Failed to get token data: {e}"))
+                })?;
+                if token.id < rt_guard.token_eot {
+                    token_logprobs.push(token.plog);
+                }
+            }
+            if !filter.keeps(&token_logprobs) {
+                clog_info!(
+                    "Whisper: Dropped low-confidence segment {:?} (likely hallucination)",
+                    segment_text.trim()
+                );
+                continue;
+            }
+
             full_text.push_str(&segment_text);
 
             segments.push(TranscriptSegment {
@@ -234,9 +467,11 @@ impl WhisperSTT {
 
         Ok(TranscriptResult {
             text: full_text.trim().to_string(),
+            original_text: None,
             language: detected_lang,
             confidence: 0.9, // Whisper doesn't expose confidence easily
             segments,
+            affect: None,
         })
     }
 }
@@ -280,18 +515,7 @@ impl SpeechToText for WhisperSTT {
             )));
         }
 
-        // Load model
-        let params = WhisperContextParameters::default();
-        let ctx = WhisperContext::new_with_params(model_path.to_str().unwrap_or(""), params)
-            .map_err(|e| STTError::ModelNotLoaded(e.to_string()))?;
-
-        // Create ONE state that holds an Arc to the context internally.
-        // This state is reused for all transcriptions — no 407MB allocation per call.
-        let state = ctx
-            .create_state()
-            .map_err(|e| STTError::ModelNotLoaded(format!("Failed to create state: {e}")))?;
-
-        let runtime = WhisperRuntime { state };
+        let runtime = Self::load_runtime(&model_path)?;
 
         WHISPER_RT
             .load_with(|| Ok::<_, STTError>(Mutex::new(runtime)))
@@ -320,11 +544,111 @@ impl SpeechToText for WhisperSTT {
             })?;
 
         let lang = language.map(|s| s.to_string());
+        let filter = *self.settings.filter.lock();
+        let prompt = self.prompt.clone();
 
         // Run inference on blocking thread pool — reuses pre-allocated state
-        tokio::task::spawn_blocking(move || Self::transcribe_sync(&rt, samples, lang.as_deref()))
-            .await
-            .map_err(|e| STTError::InferenceFailed(format!("Task join error: {e}")))?
+        tokio::task::spawn_blocking(move || {
+            Self::transcribe_sync(
+                &rt,
+                samples,
+                lang.as_deref(),
+                false,
+                filter,
+                prompt.as_deref(),
+            )
+        })
+        .await
+        .map_err(|e| STTError::InferenceFailed(format!("Task join error: {e}")))?
+    }
+
+    async fn transcribe_task(
+        &self,
+        samples: Vec<f32>,
+        language: Option<&str>,
+        task: SttTask,
+    ) -> Result<TranscriptResult, STTError> {
+        let keep_original = match task {
+            SttTask::Transcribe => return self.transcribe(samples, language).await,
+            SttTask::Translate { keep_original } => keep_original,
+        };
+
+        let rt = WHISPER_RT
+            .get()
+            .ok_or_else(|| {
+                STTError::ModelNotLoaded("Whisper not initialized. Call initialize() first.".into())
+            })?;
+
+        let lang = language.map(|s| s.to_string());
+        let filter = *self.settings.filter.lock();
+        let prompt = self.prompt.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let original_samples = keep_original.then(|| samples.clone());
+            let mut result = Self::transcribe_sync(
+                &rt,
+                samples,
+                lang.as_deref(),
+                true,
+                filter,
+                prompt.as_deref(),
+            )?;
+
+            // English audio is already its own translation — skip the second pass
+            if let Some(original_samples) = original_samples {
+                if result.language != "en" {
+                    let original = Self::transcribe_sync(
+                        &rt,
+                        original_samples,
+                        Some(&result.language),
+                        false,
+                        filter,
+                        prompt.as_deref(),
+                    )?;
+                    result.original_text = Some(original.text);
+                }
+            }
+            Ok(result)
+        })
+        .await
+        .map_err(|e| STTError::InferenceFailed(format!("Task join error: {e}")))?
+    }
+
+    /// `model` is a name from `MODEL_SIZES` (downloaded if missing) or a
+    /// model file path. The current model keeps serving until the new one
+    /// is ready, so both are briefly in memory.
+    async fn load_model(&self, model: &str) -> Result<String, STTError> {
+        let model = model.to_string();
+        let (path, runtime) = tokio::task::spawn_blocking(move || {
+            let path = Self::resolve_model(&model)?;
+            clog_info!("Whisper: Loading model from {:?}", path);
+            let runtime = Self::load_runtime(&path)?;
+            Ok::<_, STTError>((path, runtime))
+        })
+        .await
+        .map_err(|e| STTError::ModelNotLoaded(format!("Task join error: {e}")))??;
+
+        WHISPER_RT.replace(Mutex::new(runtime));
+        let label = Self::model_label(&path);
+        clog_info!("Whisper: Switched to model '{}' ({:?})", label, path);
+        *self.settings.model_path.lock() = Some(path);
+        Ok(label)
+    }
+
+    /// Terms go to Whisper as its initial prompt, which it reads as
+    /// preceding text and so favours the same spellings. Blank and repeated
+    /// terms are skipped, and terms past the prompt limit are dropped. The
+    /// copy shares this adapter's model path and hallucination filter, so
+    /// model switches and filter changes reach it too.
+    fn with_vocabulary(&self, terms: Vec<String>) -> Result<Arc<dyn SpeechToText>, STTError> {
+        Ok(Arc::new(Self {
+            settings: self.settings.clone(),
+            prompt: Self::vocabulary_prompt(&terms),
+        }))
+    }
+
+    fn supports_translation(&self) -> bool {
+        WHISPER_RT.get().is_some_and(|rt| rt.lock().multilingual)
     }
 
     async fn shutdown(&self) -> Result<(), STTError> {
@@ -334,6 +658,34 @@ impl SpeechToText for WhisperSTT {
         Ok(())
     }
 
+    /// `no_speech_max` and `avg_logprob_min` tune the hallucination filter
+    /// (see `set_hallucination_filter`)
+    fn get_param(&self, name: &str) -> Option<String> {
+        let filter = *self.settings.filter.lock();
+        match name {
+            "no_speech_max" => Some(filter.no_speech_max.to_string()),
+            "avg_logprob_min" => Some(filter.avg_logprob_min.to_string()),
+            _ => None,
+        }
+    }
+
+    fn set_param(&self, name: &str, value: &str) -> Result<(), STTError> {
+        let value: f32 = value.trim().parse().map_err(|_| {
+            STTError::InferenceFailed(format!("Whisper: '{name}' must be a number, got '{value}'"))
+        })?;
+        let filter = *self.settings.filter.lock();
+        match name {
+            "no_speech_max" => self.set_hallucination_filter(value, filter.avg_logprob_min),
+            "avg_logprob_min" => self.set_hallucination_filter(filter.no_speech_max, value),
+            other => {
+                return Err(STTError::InferenceFailed(format!(
+                    "Whisper: unknown parameter '{other}'"
+                )));
+            }
+        }
+        Ok(())
+    }
+
     fn supported_languages(&self) -> Vec<&'static str> {
         vec![
             "en", "zh", "de", "es", "ru", "ko", "fr", "ja", "pt", "tr", "pl", "ca", "nl", "ar",
@@ -375,6 +727,76 @@ mod tests {
         assert_eq!(adapter.find_model_path(), path);
     }
 
+    #[test]
+    fn test_model_names_and_paths() {
+        assert_eq!(
+            WhisperSTT::model_label(Path::new("models/whisper/ggml-tiny.en.bin")),
+            "tiny.en"
+        );
+        assert_eq!(
+            WhisperSTT::model_label(Path::new("/opt/custom-whisper.bin")),
+            "custom-whisper.bin"
+        );
+
+        // Neither a known size nor an existing file
+        let err = WhisperSTT::resolve_model("/nonexistent/ggml-huge.bin").unwrap_err();
+        assert!(err.to_string().contains("tiny.en"));
+        let err = WhisperSTT::resolve_model("gigantic").unwrap_err();
+        assert!(err.to_string().contains("Model not found"));
+    }
+
+    #[test]
+    fn test_hallucination_filter() {
+        let filter = HallucinationFilter::default();
+        // Confident speech is kept, a shaky guess is dropped
+        assert!(filter.keeps(&[-0.1, -0.3, -0.2]));
+        assert!(!filter.keeps(&[-2.5, -1.8, -0.9]));
+        // Nothing to judge: keep
+        assert!(filter.keeps(&[]));
+
+        let adapter = WhisperSTT::new();
+        adapter.set_hallucination_filter(1.5, f32::NEG_INFINITY);
+        let configured = *adapter.settings.filter.lock();
+        assert_eq!(configured.no_speech_max, 1.0);
+        assert!(configured.keeps(&[-9.0]));
+
+        adapter.set_param("avg_logprob_min", "-0.5").unwrap();
+        assert_eq!(
+            adapter.get_param("avg_logprob_min").as_deref(),
+            Some("-0.5")
+        );
+        assert_eq!(adapter.get_param("no_speech_max").as_deref(), Some("1"));
+        assert!(adapter.set_param("avg_logprob_min", "low").is_err());
+        assert!(adapter.set_param("temperature", "0.2").is_err());
+    }
+
+    #[test]
+    fn test_vocabulary_prompt() {
+        let terms = ["Continuum", " LiveKit ", "", "continuum", "Ka\0rpathy"];
+        let terms: Vec<String> = terms.iter().map(|t| t.to_string()).collect();
+        assert_eq!(
+            WhisperSTT::vocabulary_prompt(&terms).as_deref(),
+            Some("Continuum, LiveKit, Karpathy")
+        );
+
+        // Capped to the prompt limit, never cut mid-term
+        let many: Vec<String> = (0..5).map(|i| format!("{}{i}", "x".repeat(300))).collect();
+        let prompt = WhisperSTT::vocabulary_prompt(&many).unwrap();
+        assert!(prompt.len() <= MAX_PROMPT_CHARS);
+        assert!(prompt.ends_with('1'));
+
+        // Session copies share the adapter's settings
+        let adapter = WhisperSTT::new();
+        let session = WhisperSTT {
+            settings: adapter.settings.clone(),
+            prompt: WhisperSTT::vocabulary_prompt(&terms),
+        };
+        adapter.set_hallucination_filter(0.3, -2.0);
+        assert_eq!(session.get_param("no_speech_max").as_deref(), Some("0.3"));
+        assert!(session.prompt.is_some());
+        assert!(adapter.with_vocabulary(Vec::new()).is_ok());
+    }
+
     #[test]
     fn test_model_search_dirs_not_empty() {
         let dirs = WhisperSTT::model_search_dirs();
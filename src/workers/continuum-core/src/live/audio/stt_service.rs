@@ -3,7 +3,12 @@
 //! This is the proper layer between IPC and the STT adapters.
 //! IPC should NOT directly call STT - it should call this service.
 
-use crate::live::audio::stt::{self, STTError, TranscriptResult};
+use crate::audio_constants::AUDIO_FRAME_SIZE;
+use crate::live::audio::sliding_buffer::SlidingAudioBuffer;
+use crate::live::audio::stt::{
+    self, STTError, SpeechToText, SttTask, TranscriptResult, TranscriptSegment,
+};
+use crate::live::audio::vad::ProductionVAD;
 use crate::utils::audio::i16_to_f32;
 
 /// Transcribe speech from audio samples (async version).
@@ -17,6 +22,19 @@ use crate::utils::audio::i16_to_f32;
 pub async fn transcribe_speech_async(
     samples: &[i16],
     language: Option<&str>,
+) -> Result<TranscriptResult, STTError> {
+    transcribe_speech_task_async(samples, language, SttTask::Transcribe).await
+}
+
+/// Transcribe or translate speech from audio samples (async version).
+///
+/// With `SttTask::Translate`, `text` is English and `original_text` carries
+/// the spoken-language text if requested. Fails if the active adapter
+/// can't translate.
+pub async fn transcribe_speech_task_async(
+    samples: &[i16],
+    language: Option<&str>,
+    task: SttTask,
 ) -> Result<TranscriptResult, STTError> {
     let f32_samples = i16_to_f32(samples);
 
@@ -27,7 +45,121 @@ pub async fn transcribe_speech_async(
     }
 
     // Use active adapter (configured in registry)
-    stt::transcribe(f32_samples, language).await
+    stt::transcribe_task(f32_samples, language, task).await
+}
+
+/// Transcribe or translate with a specific adapter (resolved from the
+/// registry by the caller), initializing it on first use.
+pub async fn transcribe_speech_with_async(
+    adapter: &dyn SpeechToText,
+    samples: &[i16],
+    language: Option<&str>,
+    task: SttTask,
+) -> Result<TranscriptResult, STTError> {
+    if !adapter.is_initialized() {
+        adapter.initialize().await?;
+    }
+    adapter
+        .transcribe_task(i16_to_f32(samples), language, task)
+        .await
+}
+
+/// Transcribe audio longer than one STT window.
+///
+/// Audio is split into `windows`' (possibly overlapping) windows, which are
+/// transcribed in order. Text repeated at the seams is merged away, and
+/// segment times are shifted onto the full input's timeline; segments that
+/// lie entirely inside the previous window are dropped as duplicates.
+///
+/// If `windows` has endpointing, frames are classified by the production
+/// VAD and windows also end at pauses in speech.
+pub async fn transcribe_windowed_async(
+    samples: &[i16],
+    language: Option<&str>,
+    task: SttTask,
+    mut windows: SlidingAudioBuffer,
+) -> Result<TranscriptResult, STTError> {
+    let mut audio_windows = if windows.endpoint_silence_ms().is_some() {
+        // Silero costs ~54ms per speech frame; keep it off the async runtime
+        let samples = samples.to_vec();
+        let (endpointed, audio_windows) = tokio::task::spawn_blocking(move || {
+            let mut vad = ProductionVAD::new();
+            vad.initialize()
+                .map_err(|e| STTError::ModelNotLoaded(format!("VAD: {e}")))?;
+            let mut audio_windows = Vec::new();
+            for frame in samples.chunks(AUDIO_FRAME_SIZE) {
+                // The VAD models want whole frames; pad the tail with silence
+                let mut padded = frame.to_vec();
+                padded.resize(AUDIO_FRAME_SIZE, 0);
+                let result = vad
+                    .detect(&padded)
+                    .map_err(|e| STTError::InvalidAudio(e.to_string()))?;
+                audio_windows.extend(windows.push_frame(frame, &result));
+            }
+            Ok::<_, STTError>((windows, audio_windows))
+        })
+        .await
+        .map_err(|e| STTError::InferenceFailed(format!("Task join error: {e}")))??;
+        windows = endpointed;
+        audio_windows
+    } else {
+        windows.push(samples)
+    };
+    audio_windows.extend(windows.flush());
+    // Original-language text has its own seams to merge
+    let mut original_windows = windows.clone();
+
+    let mut texts: Vec<String> = Vec::new();
+    let mut original_texts: Vec<String> = Vec::new();
+    let mut segments = Vec::new();
+    let mut detected_language = None;
+    let mut confidence_sum = 0.0;
+    let mut covered_until_ms: i64 = 0;
+
+    for window in &audio_windows {
+        let result = transcribe_speech_task_async(&window.samples, language, task).await?;
+        let offset_ms = window.start_ms() as i64;
+
+        let text = windows.merge_window_transcript(window, result.text.trim());
+        if !text.is_empty() {
+            texts.push(text);
+        }
+        if let Some(original) = &result.original_text {
+            let original = original_windows.merge_window_transcript(window, original.trim());
+            if !original.is_empty() {
+                original_texts.push(original);
+            }
+        }
+        segments.extend(
+            result
+                .segments
+                .into_iter()
+                .map(|seg| TranscriptSegment {
+                    text: seg.text,
+                    start_ms: seg.start_ms + offset_ms,
+                    end_ms: seg.end_ms + offset_ms,
+                })
+                .filter(|seg| seg.end_ms > covered_until_ms),
+        );
+        covered_until_ms = offset_ms
+            + (window.samples.len() as i64 * 1000)
+                / crate::audio_constants::AUDIO_SAMPLE_RATE as i64;
+        detected_language.get_or_insert(result.language);
+        confidence_sum += result.confidence;
+    }
+
+    Ok(TranscriptResult {
+        text: texts.join(" "),
+        original_text: (!original_texts.is_empty()).then(|| original_texts.join(" ")),
+        language: detected_language.unwrap_or_else(|| language.unwrap_or("en").to_string()),
+        confidence: if audio_windows.is_empty() {
+            0.0
+        } else {
+            confidence_sum / audio_windows.len() as f32
+        },
+        segments,
+        affect: None,
+    })
 }
 
 /// Transcribe speech from audio samples (sync version).
@@ -0,0 +1,256 @@
+//! Synthesis Result Cache
+//!
+//! IVR prompts and other fixed phrases are synthesized over and over with
+//! the same voice. Audio is a pure function of (adapter, voice, text,
+//! prosody), so those results are kept in a bounded LRU and served without
+//! running the model again.
+//!
+//! Bounded both by entry count and by PCM bytes; whichever limit is hit
+//! first evicts the least recently used entry.
+
+use super::{Prosody, SynthesisResult};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Default limits: ~600 three-second prompts fit in 32MB of 16kHz PCM
+pub const DEFAULT_MAX_ENTRIES: usize = 512;
+pub const DEFAULT_MAX_BYTES: usize = 32 * 1024 * 1024;
+
+/// Everything that determines the synthesized audio
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SynthesisKey {
+    pub adapter: &'static str,
+    /// Resolved adapter voice (after UUID/gender → voice mapping)
+    pub voice: String,
+    pub text: String,
+    /// Prosody as raw bits so the key is hashable
+    speed_bits: u32,
+    pitch_bits: u32,
+}
+
+impl SynthesisKey {
+    pub fn new(adapter: &'static str, voice: &str, text: &str, prosody: Prosody) -> Self {
+        Self {
+            adapter,
+            voice: voice.to_string(),
+            text: text.to_string(),
+            speed_bits: prosody.speed.to_bits(),
+            pitch_bits: prosody.pitch_semitones.to_bits(),
+        }
+    }
+
+    fn bytes(&self) -> usize {
+        self.voice.len() + self.text.len()
+    }
+}
+
+struct CachedSynthesis {
+    result: SynthesisResult,
+    bytes: usize,
+    /// Access tick — smallest is least recently used
+    last_used: u64,
+}
+
+/// Cache counters for `voice/tts-cache-stats`
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SynthesisCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub max_entries: usize,
+    pub max_bytes: usize,
+}
+
+/// Bounded LRU of synthesis results
+pub struct SynthesisCache {
+    entries: HashMap<SynthesisKey, CachedSynthesis>,
+    max_entries: usize,
+    max_bytes: usize,
+    bytes: usize,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl SynthesisCache {
+    /// `max_entries == 0` or `max_bytes == 0` disables caching
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_entries,
+            max_bytes,
+            bytes: 0,
+            tick: 0,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &SynthesisKey) -> Option<SynthesisResult> {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                entry.last_used = self.tick;
+                self.hits += 1;
+                Some(entry.result.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a result, evicting least recently used entries to make room.
+    /// Results larger than the whole byte budget are not cached.
+    pub fn insert(&mut self, key: SynthesisKey, result: SynthesisResult) {
+        let bytes = key.bytes() + result.samples.len() * std::mem::size_of::<i16>();
+        if self.max_entries == 0 || bytes > self.max_bytes {
+            return;
+        }
+        if let Some(old) = self.entries.remove(&key) {
+            self.bytes -= old.bytes;
+        }
+        self.evict_to_fit(self.max_entries - 1, self.max_bytes - bytes);
+
+        self.tick += 1;
+        self.bytes += bytes;
+        self.entries.insert(
+            key,
+            CachedSynthesis {
+                result,
+                bytes,
+                last_used: self.tick,
+            },
+        );
+    }
+
+    /// Change the limits, evicting immediately if the cache is now over them
+    pub fn set_limits(&mut self, max_entries: usize, max_bytes: usize) {
+        self.max_entries = max_entries;
+        self.max_bytes = max_bytes;
+        self.evict_to_fit(max_entries, max_bytes);
+    }
+
+    /// Drop every entry (counters are kept)
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.bytes = 0;
+    }
+
+    pub fn stats(&self) -> SynthesisCacheStats {
+        SynthesisCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            entries: self.entries.len(),
+            bytes: self.bytes,
+            max_entries: self.max_entries,
+            max_bytes: self.max_bytes,
+        }
+    }
+
+    fn evict_to_fit(&mut self, max_entries: usize, max_bytes: usize) {
+        while self.entries.len() > max_entries || self.bytes > max_bytes {
+            let Some(lru) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&lru) {
+                self.bytes -= entry.bytes;
+                self.evictions += 1;
+            }
+        }
+    }
+}
+
+impl Default for SynthesisCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_ENTRIES, DEFAULT_MAX_BYTES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(samples: usize) -> SynthesisResult {
+        SynthesisResult {
+            samples: vec![1; samples],
+            sample_rate: 16000,
+            duration_ms: (samples / 16) as u64,
+            voice_name: Some("af".into()),
+        }
+    }
+
+    fn key(text: &str) -> SynthesisKey {
+        SynthesisKey::new("kokoro", "af", text, Prosody::default())
+    }
+
+    #[test]
+    fn test_hit_miss_and_prosody_in_key() {
+        let mut cache = SynthesisCache::default();
+        assert!(cache.get(&key("Press 1 for sales")).is_none());
+        cache.insert(key("Press 1 for sales"), result(1600));
+
+        let hit = cache.get(&key("Press 1 for sales")).unwrap();
+        assert_eq!(hit.samples.len(), 1600);
+
+        // Same text at another speed is a different entry
+        let faster = SynthesisKey::new(
+            "kokoro",
+            "af",
+            "Press 1 for sales",
+            Prosody::new(1.2, 0.0).unwrap(),
+        );
+        assert!(cache.get(&faster).is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 2, 1));
+
+        cache.clear();
+        assert!(cache.get(&key("Press 1 for sales")).is_none());
+        assert_eq!(cache.stats().bytes, 0);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = SynthesisCache::new(2, usize::MAX);
+        cache.insert(key("one"), result(10));
+        cache.insert(key("two"), result(10));
+        cache.get(&key("one"));
+        cache.insert(key("three"), result(10));
+
+        assert!(cache.get(&key("two")).is_none());
+        assert!(cache.get(&key("one")).is_some());
+        assert!(cache.get(&key("three")).is_some());
+        assert_eq!(cache.stats().evictions, 1);
+    }
+
+    #[test]
+    fn test_byte_budget() {
+        // Each entry: 200 sample bytes + 5 key bytes ("af" + 3-char text)
+        let mut cache = SynthesisCache::new(100, 450);
+        cache.insert(key("one"), result(100));
+        cache.insert(key("two"), result(100));
+        cache.insert(key("six"), result(100));
+        assert_eq!(cache.stats().entries, 2);
+        assert_eq!(cache.stats().bytes, 410);
+
+        // Larger than the whole budget: not cached, nothing evicted
+        cache.insert(key("big"), result(1000));
+        assert_eq!(cache.stats().entries, 2);
+
+        cache.set_limits(1, 450);
+        assert_eq!(cache.stats().entries, 1);
+        assert!(cache.get(&key("six")).is_some());
+    }
+}
@@ -58,6 +58,11 @@ impl TextToSpeech for EdgeTTS {
         "Microsoft Edge neural TTS (online, free, 300+ voices)"
     }
 
+    /// Server-side neural voices vary between requests
+    fn deterministic(&self) -> bool {
+        false
+    }
+
     fn is_initialized(&self) -> bool {
         self.initialized.load(Ordering::Relaxed)
     }
@@ -2,13 +2,16 @@
 //!
 //! Local TTS inference using Kokoro-82M v1.0 via ONNX Runtime.
 //! Uses espeak-ng for phonemization + Kokoro vocab for tokenization.
+//! Speaks with a preset voice pack, or with a caller-supplied speaker
+//! embedding (`synthesize_with_embedding`) for voices not shipped as files.
 //!
 //! GPU Acceleration:
 //! - CoreML (Apple Silicon) - macOS
 //! - CUDA (NVIDIA GPUs) - Linux/Windows
 
 use super::audio_utils;
-use super::{SynthesisResult, TTSError, TextToSpeech, VoiceInfo};
+use super::{prosody, Prosody, SynthesisResult, TTSError, TextToSpeech, VoiceInfo};
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
 use crate::gpu::memory_manager::{GpuPriority, GpuSubsystem};
 use crate::gpu::tracker::GpuModelTracker;
 use crate::{clog_info, clog_warn};
@@ -36,6 +39,8 @@ struct KokoroModel {
     voices_dir: PathBuf,
     /// Cached voice embeddings: voice_id -> (N, 256) float32 array
     voice_cache: HashMap<String, Vec<Vec<f32>>>,
+    /// Width of the graph's `style` input (256 for Kokoro v1.0)
+    style_dim: usize,
     #[allow(dead_code)]
     sample_rate: u32,
 }
@@ -43,6 +48,21 @@ struct KokoroModel {
 /// Max phoneme/token length for Kokoro v1.0
 const MAX_TOKEN_LENGTH: usize = 510;
 
+/// Style vector width in Kokoro v1.0 voice packs, used when the ONNX graph
+/// doesn't declare it
+const STYLE_DIM: usize = 256;
+
+/// Where a synthesis call gets its style vector
+enum Style<'a> {
+    /// Preset voice pack from the voices directory
+    Preset(&'a str),
+    /// Caller-supplied embedding: one style vector, or a pack of them
+    Embedding(&'a [f32]),
+}
+
+/// Pause inserted where `synthesize_segments` changes speaker
+const TURN_GAP_MS: u32 = 250;
+
 /// Available Kokoro voices
 const KOKORO_VOICES: &[(&str, &str, &str)] = &[
     ("af", "American Female (default)", "en-US"),
@@ -75,6 +95,108 @@ impl KokoroTTS {
         }
     }
 
+    /// Synthesize with a custom speaker embedding instead of a preset voice.
+    ///
+    /// `speaker_embedding` is one style vector of the model's style width
+    /// (256 for Kokoro v1.0), or a whole voice pack of N such vectors in the
+    /// layout of the `voices/*.bin` files. Returns `TTSError::InvalidEmbedding`
+    /// if its length doesn't fit the loaded model.
+    pub async fn synthesize_with_embedding(
+        &self,
+        text: &str,
+        speaker_embedding: &[f32],
+    ) -> Result<SynthesisResult, TTSError> {
+        KOKORO_GPU.touch();
+
+        let session = KOKORO_SESSION
+            .get()
+            .ok_or_else(|| TTSError::ModelNotLoaded("Kokoro not initialized".into()))?;
+
+        let text = text.to_string();
+        let embedding = speaker_embedding.to_vec();
+
+        tokio::task::spawn_blocking(move || {
+            Self::synthesize_sync(&session, &text, Style::Embedding(&embedding), 1.0)
+        })
+        .await
+        .map_err(|e| TTSError::SynthesisFailed(format!("Task join error: {e}")))?
+    }
+
+    /// Synthesize dialogue: each `(text, voice)` segment in its own voice,
+    /// in order, with a `TURN_GAP_MS` pause wherever the speaker changes.
+    ///
+    /// Every voice is checked against `available_voices()` before anything is
+    /// synthesized. Consecutive segments by the same voice are one turn and
+    /// are synthesized together, so prosody carries across them. The result
+    /// has no `voice_name`, since it holds several.
+    pub async fn synthesize_segments(
+        &self,
+        segments: Vec<(String, String)>,
+    ) -> Result<SynthesisResult, TTSError> {
+        let turns = Self::speaker_turns(&self.available_voices(), segments)?;
+        KOKORO_GPU.touch();
+
+        let session = KOKORO_SESSION
+            .get()
+            .ok_or_else(|| TTSError::ModelNotLoaded("Kokoro not initialized".into()))?;
+
+        tokio::task::spawn_blocking(move || {
+            let gap = vec![0i16; (AUDIO_SAMPLE_RATE * TURN_GAP_MS / 1000) as usize];
+            let mut samples = Vec::new();
+            for (i, (voice, text)) in turns.iter().enumerate() {
+                if i > 0 {
+                    samples.extend_from_slice(&gap);
+                }
+                let turn = Self::synthesize_sync(&session, text, Style::Preset(voice), 1.0)?;
+                samples.extend(turn.samples);
+            }
+            clog_info!(
+                "Kokoro synthesized {} speaker turns ({} samples)",
+                turns.len(),
+                samples.len()
+            );
+            Ok(SynthesisResult {
+                duration_ms: audio_utils::duration_ms(samples.len(), AUDIO_SAMPLE_RATE),
+                samples,
+                sample_rate: AUDIO_SAMPLE_RATE,
+                voice_name: None,
+            })
+        })
+        .await
+        .map_err(|e| TTSError::SynthesisFailed(format!("Task join error: {e}")))?
+    }
+
+    /// Validate `(text, voice)` segments and merge consecutive ones by the
+    /// same voice into `(voice, text)` turns
+    fn speaker_turns(
+        voices: &[VoiceInfo],
+        segments: Vec<(String, String)>,
+    ) -> Result<Vec<(String, String)>, TTSError> {
+        if segments.is_empty() {
+            return Err(TTSError::InvalidText("No segments to synthesize".into()));
+        }
+        let mut turns: Vec<(String, String)> = Vec::new();
+        for (i, (text, voice)) in segments.into_iter().enumerate() {
+            if !voices.iter().any(|v| v.id == voice) {
+                return Err(TTSError::VoiceNotFound(format!(
+                    "Segment {i}: unknown Kokoro voice '{voice}'"
+                )));
+            }
+            let text = text.trim();
+            if text.is_empty() {
+                return Err(TTSError::InvalidText(format!("Segment {i} has no text")));
+            }
+            match turns.last_mut() {
+                Some((last_voice, turn)) if *last_voice == voice => {
+                    turn.push(' ');
+                    turn.push_str(text);
+                }
+                _ => turns.push((voice, text.to_string())),
+            }
+        }
+        Ok(turns)
+    }
+
     /// Find model ONNX file in common locations
     fn find_model_path(&self) -> Option<PathBuf> {
         if let Some(ref path) = self.model_path {
@@ -209,7 +331,7 @@ impl KokoroTTS {
         }
 
         // Reshape to (N, 256)
-        let embedding_dim = 256;
+        let embedding_dim = STYLE_DIM;
         let num_rows = num_floats / embedding_dim;
         let mut embeddings = Vec::with_capacity(num_rows);
         for row in 0..num_rows {
@@ -292,20 +414,60 @@ impl KokoroTTS {
         tokens
     }
 
+    /// Style vector width the graph's `style` input declares, if static
+    fn model_style_dim(session: &Session) -> Option<usize> {
+        session
+            .inputs()
+            .iter()
+            .find(|input| input.name() == "style")
+            .and_then(|input| input.dtype().tensor_shape())
+            .and_then(|shape| shape.last().copied())
+            .filter(|&dim| dim > 0)
+            .map(|dim| dim as usize)
+    }
+
+    /// Pick the style vector for `token_count` tokens from a speaker
+    /// embedding: either one `style_dim` vector, or an (N, style_dim) pack
+    /// laid out like the voice files (row = token count).
+    fn embedding_style_row(
+        embedding: &[f32],
+        style_dim: usize,
+        token_count: usize,
+    ) -> Result<&[f32], TTSError> {
+        if embedding.is_empty() || embedding.len() % style_dim != 0 {
+            return Err(TTSError::InvalidEmbedding(format!(
+                "Kokoro expects {style_dim} floats (or a multiple of {style_dim} for a voice pack), got {}",
+                embedding.len()
+            )));
+        }
+        if let Some(bad) = embedding.iter().position(|v| !v.is_finite()) {
+            return Err(TTSError::InvalidEmbedding(format!(
+                "Non-finite value at index {bad}"
+            )));
+        }
+        let rows = embedding.len() / style_dim;
+        let row = token_count.min(rows - 1);
+        Ok(&embedding[row * style_dim..(row + 1) * style_dim])
+    }
+
     /// Synchronous synthesis
     fn synthesize_sync(
         session: &Arc<Mutex<KokoroModel>>,
         text: &str,
-        voice: &str,
+        style: Style<'_>,
         speed: f32,
     ) -> Result<SynthesisResult, TTSError> {
         if text.is_empty() {
             return Err(TTSError::InvalidText("Text cannot be empty".into()));
         }
 
-        let voice_id = Self::normalize_voice(voice);
         let mut model = session.lock();
 
+        // Reject a bad embedding before spending time on phonemization
+        if let Style::Embedding(embedding) = style {
+            Self::embedding_style_row(embedding, model.style_dim, 0)?;
+        }
+
         // Step 1: Phonemize text via espeak-ng
         let phonemes = Self::phonemize(text)?;
         clog_info!(
@@ -325,20 +487,37 @@ impl KokoroTTS {
             ));
         }
 
-        // Step 3: Get voice embedding for this token count
-        // Load voice if not cached
-        if !model.voice_cache.contains_key(voice_id) {
-            let embeddings = Self::load_voice_embedding(&model.voices_dir, voice_id)?;
-            model.voice_cache.insert(voice_id.to_string(), embeddings);
-        }
+        // Step 3: Get style vector for this token count
+        let style_vector = match style {
+            Style::Preset(voice) => {
+                let voice_id = Self::normalize_voice(voice);
+                // Load voice if not cached
+                if !model.voice_cache.contains_key(voice_id) {
+                    let embeddings = Self::load_voice_embedding(&model.voices_dir, voice_id)?;
+                    model.voice_cache.insert(voice_id.to_string(), embeddings);
+                }
 
-        let voice_embeddings = model.voice_cache.get(voice_id).ok_or_else(|| {
-            TTSError::VoiceNotFound(format!("Voice '{voice_id}' missing from cache after load"))
-        })?;
+                let voice_embeddings = model.voice_cache.get(voice_id).ok_or_else(|| {
+                    TTSError::VoiceNotFound(format!(
+                        "Voice '{voice_id}' missing from cache after load"
+                    ))
+                })?;
 
-        // Select style vector based on token count (clamped to available range)
-        let style_idx = token_count.min(voice_embeddings.len().saturating_sub(1));
-        let style_vector = &voice_embeddings[style_idx];
+                // Select style vector based on token count (clamped to available range)
+                let style_idx = token_count.min(voice_embeddings.len().saturating_sub(1));
+                voice_embeddings[style_idx].clone()
+            }
+            Style::Embedding(embedding) => {
+                Self::embedding_style_row(embedding, model.style_dim, token_count)?.to_vec()
+            }
+        };
+        if style_vector.len() != model.style_dim {
+            return Err(TTSError::InvalidEmbedding(format!(
+                "Voice pack has {}-dim style vectors but the model expects {}",
+                style_vector.len(),
+                model.style_dim
+            )));
+        }
 
         // Step 4: Build ONNX input tensors
         // input_ids: shape (1, token_count)
@@ -348,7 +527,7 @@ impl KokoroTTS {
 
         // style: shape (1, 256)
         let style =
-            ndarray::Array2::from_shape_vec((1, 256), style_vector.clone()).map_err(|e| {
+            ndarray::Array2::from_shape_vec((1, model.style_dim), style_vector).map_err(|e| {
                 TTSError::SynthesisFailed(format!("Failed to create style tensor: {e}"))
             })?;
 
@@ -468,12 +647,14 @@ impl TextToSpeech for KokoroTTS {
             .with_intra_threads(intra_threads)?
             .with_inter_threads(inter_threads)?
             .commit_from_file(&model_path)?;
+        let style_dim = Self::model_style_dim(&session).unwrap_or(STYLE_DIM);
 
         let model = KokoroModel {
             session,
             vocab,
             voices_dir,
             voice_cache: HashMap::new(),
+            style_dim,
             sample_rate: 24000,
         };
 
@@ -492,6 +673,18 @@ impl TextToSpeech for KokoroTTS {
     }
 
     async fn synthesize(&self, text: &str, voice: &str) -> Result<SynthesisResult, TTSError> {
+        self.synthesize_with_prosody(text, voice, Prosody::default())
+            .await
+    }
+
+    /// Speed goes to the model's duration predictor (natural-sounding rate
+    /// change); only pitch is post-processed.
+    async fn synthesize_with_prosody(
+        &self,
+        text: &str,
+        voice: &str,
+        prosody: Prosody,
+    ) -> Result<SynthesisResult, TTSError> {
         KOKORO_GPU.touch();
 
         let session = KOKORO_SESSION
@@ -501,9 +694,12 @@ impl TextToSpeech for KokoroTTS {
         let text = text.to_string();
         let voice = voice.to_string();
 
-        tokio::task::spawn_blocking(move || Self::synthesize_sync(&session, &text, &voice, 1.0))
-            .await
-            .map_err(|e| TTSError::SynthesisFailed(format!("Task join error: {e}")))?
+        let result = tokio::task::spawn_blocking(move || {
+            Self::synthesize_sync(&session, &text, Style::Preset(&voice), prosody.speed)
+        })
+        .await
+        .map_err(|e| TTSError::SynthesisFailed(format!("Task join error: {e}")))??;
+        Ok(prosody::apply_to_result(result, prosody.pitch_only()))
     }
 
     async fn shutdown(&self) -> Result<(), TTSError> {
@@ -539,7 +735,6 @@ impl TextToSpeech for KokoroTTS {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::audio_constants::AUDIO_SAMPLE_RATE;
     use std::path::PathBuf;
 
     // ========================================================================
@@ -656,6 +851,80 @@ mod tests {
         assert_eq!(KokoroTTS::normalize_voice(""), "af");
     }
 
+    #[test]
+    fn test_speaker_turns_merge_and_validate() {
+        let voices = KokoroTTS::new().available_voices();
+        let segment = |text: &str, voice: &str| (text.to_string(), voice.to_string());
+
+        let turns = KokoroTTS::speaker_turns(
+            &voices,
+            vec![
+                segment("Where were you?", "af_bella"),
+                segment(" I waited. ", "af_bella"),
+                segment("The train was late.", "am_adam"),
+                segment("Again?", "af_bella"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            turns,
+            vec![
+                (
+                    "af_bella".to_string(),
+                    "Where were you? I waited.".to_string()
+                ),
+                ("am_adam".to_string(), "The train was late.".to_string()),
+                ("af_bella".to_string(), "Again?".to_string()),
+            ]
+        );
+
+        // Unknown voices are rejected, not silently replaced with the default
+        let err = KokoroTTS::speaker_turns(
+            &voices,
+            vec![segment("Hi", "af"), segment("Hello", "zz_nobody")],
+        )
+        .unwrap_err();
+        assert!(matches!(err, TTSError::VoiceNotFound(msg) if msg.contains("Segment 1")));
+        assert!(matches!(
+            KokoroTTS::speaker_turns(&voices, vec![segment("  ", "af")]),
+            Err(TTSError::InvalidText(_))
+        ));
+        assert!(KokoroTTS::speaker_turns(&voices, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_embedding_style_row() {
+        // Single style vector: used for every token count
+        let single = vec![0.5f32; STYLE_DIM];
+        let row = KokoroTTS::embedding_style_row(&single, STYLE_DIM, 40).unwrap();
+        assert_eq!(row.len(), STYLE_DIM);
+
+        // Voice pack: row chosen by token count, clamped to the last row
+        let pack: Vec<f32> = (0..3)
+            .flat_map(|row| std::iter::repeat(row as f32).take(STYLE_DIM))
+            .collect();
+        assert_eq!(
+            KokoroTTS::embedding_style_row(&pack, STYLE_DIM, 1).unwrap()[0],
+            1.0
+        );
+        assert_eq!(
+            KokoroTTS::embedding_style_row(&pack, STYLE_DIM, 99).unwrap()[0],
+            2.0
+        );
+
+        // Wrong width, empty, or non-finite values are rejected
+        for bad in [
+            vec![0.0; STYLE_DIM - 1],
+            Vec::new(),
+            vec![f32::NAN; STYLE_DIM],
+        ] {
+            assert!(matches!(
+                KokoroTTS::embedding_style_row(&bad, STYLE_DIM, 0),
+                Err(TTSError::InvalidEmbedding(_))
+            ));
+        }
+    }
+
     // ========================================================================
     // Integration Tests (require model files on disk)
     // These tests are #[ignore]d by default. Run with:
@@ -843,4 +1112,40 @@ mod tests {
             max_amplitude
         );
     }
+
+    #[test]
+    #[ignore] // Requires Kokoro ONNX model + voices on disk
+    fn test_kokoro_synthesize_with_embedding() {
+        let original_cwd = set_jtag_cwd();
+
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let adapter = KokoroTTS::new();
+        let voices_dir = KokoroTTS::find_voices_dir().expect("Kokoro voices directory not found");
+
+        // A preset voice pack passed as a runtime embedding
+        let embedding: Vec<f32> = KokoroTTS::load_voice_embedding(&voices_dir, "af")
+            .expect("Failed to load af voice")
+            .concat();
+
+        let (result, mismatch) = rt.block_on(async {
+            adapter
+                .initialize()
+                .await
+                .expect("Kokoro initialization failed");
+            (
+                adapter
+                    .synthesize_with_embedding("Hello, this is a test.", &embedding)
+                    .await,
+                adapter
+                    .synthesize_with_embedding("Hello.", &embedding[..100])
+                    .await,
+            )
+        });
+
+        std::env::set_current_dir(original_cwd).unwrap();
+
+        let synthesis = result.expect("Synthesis with embedding failed");
+        assert!(synthesis.samples.len() > 1000);
+        assert!(matches!(mismatch, Err(TTSError::InvalidEmbedding(_))));
+    }
 }
@@ -11,27 +11,32 @@
 //! Uses trait-based polymorphism for runtime flexibility.
 
 pub(crate) mod audio_utils;
+pub mod cache;
 mod edge;
 mod kokoro;
 mod orpheus;
 mod phonemizer;
 mod piper;
 mod pocket;
+pub mod prosody;
 mod silence;
 
+pub use cache::{SynthesisCacheStats, SynthesisKey};
 pub use edge::EdgeTTS;
 pub use kokoro::KokoroTTS;
 pub use orpheus::OrpheusTts;
 pub(crate) use phonemizer::Phonemizer;
 pub use piper::PiperTTS;
 pub use pocket::PocketTTS;
+pub use prosody::Prosody;
 pub use silence::SilenceTTS;
 
 use crate::gpu::memory_manager::GpuMemoryManager;
 use crate::{clog_info, clog_warn};
 use async_trait::async_trait;
+use cache::SynthesisCache;
 use once_cell::sync::OnceCell;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use std::collections::HashMap;
 use std::sync::{Arc, OnceLock};
 use thiserror::Error;
@@ -94,6 +99,9 @@ pub enum TTSError {
     #[error("Voice not found: {0}")]
     VoiceNotFound(String),
 
+    #[error("Invalid speaker embedding: {0}")]
+    InvalidEmbedding(String),
+
     #[error("Adapter not found: {0}")]
     AdapterNotFound(String),
 
@@ -136,6 +144,14 @@ pub trait TextToSpeech: Send + Sync {
     /// Human-readable description
     fn description(&self) -> &'static str;
 
+    /// Whether the same text and voice always synthesize the same audio.
+    ///
+    /// Only deterministic adapters are cached; sampling backends would
+    /// otherwise replay one frozen rendition for every repeat.
+    fn deterministic(&self) -> bool {
+        true
+    }
+
     /// Check if adapter is ready for use
     fn is_initialized(&self) -> bool;
 
@@ -149,6 +165,21 @@ pub trait TextToSpeech: Send + Sync {
     /// * `voice` - Voice ID (adapter-specific)
     async fn synthesize(&self, text: &str, voice: &str) -> Result<SynthesisResult, TTSError>;
 
+    /// Synthesize with speech rate and pitch control.
+    ///
+    /// Neutral prosody is exactly `synthesize`. Otherwise the default
+    /// time-stretches / pitch-shifts the output (see `prosody`); adapters
+    /// with a native rate knob override this.
+    async fn synthesize_with_prosody(
+        &self,
+        text: &str,
+        voice: &str,
+        prosody: Prosody,
+    ) -> Result<SynthesisResult, TTSError> {
+        let result = self.synthesize(text, voice).await?;
+        Ok(prosody::apply_to_result(result, prosody))
+    }
+
     /// Get available voices
     fn available_voices(&self) -> Vec<VoiceInfo>;
 
@@ -217,11 +248,14 @@ pub trait TextToSpeech: Send + Sync {
 ///
 /// Uses Vec to preserve registration order (= priority order).
 /// First registered = highest priority = tried first during init.
+/// Also owns the synthesis result cache shared by all adapters.
 pub struct TTSRegistry {
     adapters: HashMap<&'static str, Arc<dyn TextToSpeech>>,
     /// Registration order — determines init priority (first = highest)
     priority: Vec<&'static str>,
     active: Option<&'static str>,
+    /// Own lock so lookups only need the registry's read lock
+    cache: Mutex<SynthesisCache>,
 }
 
 impl TTSRegistry {
@@ -230,6 +264,7 @@ impl TTSRegistry {
             adapters: HashMap::new(),
             priority: Vec::new(),
             active: None,
+            cache: Mutex::new(SynthesisCache::default()),
         }
     }
 
@@ -287,6 +322,30 @@ impl TTSRegistry {
             .map(|a| a.is_initialized())
             .unwrap_or(false)
     }
+
+    /// Cached result for `key` (counts a cache hit or miss)
+    pub fn cached(&self, key: &SynthesisKey) -> Option<SynthesisResult> {
+        self.cache.lock().get(key)
+    }
+
+    /// Store a result for later `cached` lookups
+    pub fn cache_result(&self, key: SynthesisKey, result: SynthesisResult) {
+        self.cache.lock().insert(key, result);
+    }
+
+    /// Drop all cached results (e.g. after replacing voice files)
+    pub fn clear_cache(&self) {
+        self.cache.lock().clear();
+    }
+
+    /// Set cache limits; 0 for either disables caching
+    pub fn set_cache_limits(&self, max_entries: usize, max_bytes: usize) {
+        self.cache.lock().set_limits(max_entries, max_bytes);
+    }
+
+    pub fn cache_stats(&self) -> SynthesisCacheStats {
+        self.cache.lock().stats()
+    }
 }
 
 impl Default for TTSRegistry {
@@ -404,6 +463,7 @@ pub async fn synthesize(
     text: &str,
     voice: &str,
     gender_hint: Option<&str>,
+    prosody: Prosody,
 ) -> Result<SynthesisResult, TTSError> {
     let adapter = get_registry()
         .read()
@@ -418,8 +478,38 @@ pub async fn synthesize(
         gender_hint.unwrap_or("any"),
         adapter.name()
     );
-    let mut result = adapter.synthesize(text, &resolved).await?;
+    synthesize_cached(adapter.as_ref(), text, resolved, prosody).await
+}
+
+/// Synthesize with a resolved voice, serving repeats from the registry cache
+/// when the adapter is deterministic
+async fn synthesize_cached(
+    adapter: &dyn TextToSpeech,
+    text: &str,
+    resolved: String,
+    prosody: Prosody,
+) -> Result<SynthesisResult, TTSError> {
+    if !adapter.deterministic() {
+        let mut result = adapter
+            .synthesize_with_prosody(text, &resolved, prosody)
+            .await?;
+        result.voice_name = Some(resolved);
+        return Ok(result);
+    }
+
+    let registry = get_registry();
+    let key = SynthesisKey::new(adapter.name(), &resolved, text, prosody);
+    let hit = registry.read().cached(&key);
+    if let Some(hit) = hit {
+        clog_info!("TTS: cache hit for '{}'", truncate_str(text, 30));
+        return Ok(hit);
+    }
+
+    let mut result = adapter
+        .synthesize_with_prosody(text, &resolved, prosody)
+        .await?;
     result.voice_name = Some(resolved);
+    registry.read().cache_result(key, result.clone());
     Ok(result)
 }
 
@@ -491,6 +581,7 @@ pub async fn synthesize_with(
     voice: &str,
     adapter_name: &str,
     gender_hint: Option<&str>,
+    prosody: Prosody,
 ) -> Result<SynthesisResult, TTSError> {
     let adapter = get_registry()
         .read()
@@ -509,9 +600,7 @@ pub async fn synthesize_with(
         gender_hint.unwrap_or("any"),
         adapter_name
     );
-    let mut result = adapter.synthesize(text, &resolved).await?;
-    result.voice_name = Some(resolved);
-    Ok(result)
+    synthesize_cached(adapter.as_ref(), text, resolved, prosody).await
 }
 
 /// Get available voices from active adapter
@@ -579,6 +668,38 @@ mod tests {
         assert!(registry.set_active("nonexistent").is_err());
     }
 
+    #[test]
+    fn test_tts_registry_result_cache() {
+        let registry = TTSRegistry::new();
+        let key = SynthesisKey::new("silence", "default", "Hello", Prosody::default());
+        assert!(registry.cached(&key).is_none());
+
+        let result = || SynthesisResult {
+            samples: vec![0; 1600],
+            sample_rate: 16000,
+            duration_ms: 100,
+            voice_name: Some("default".into()),
+        };
+        registry.cache_result(key.clone(), result());
+        assert_eq!(registry.cached(&key).unwrap().samples.len(), 1600);
+
+        let stats = registry.cache_stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+
+        registry.clear_cache();
+        assert!(registry.cached(&key).is_none());
+
+        // Zero limits disable caching
+        registry.set_cache_limits(0, 0);
+        registry.cache_result(key.clone(), result());
+        assert_eq!(registry.cache_stats().entries, 0);
+
+        // Sampling backends are never cached
+        assert!(SilenceTTS::new().deterministic());
+        assert!(!OrpheusTts::new().deterministic());
+        assert!(!EdgeTTS::new().deterministic());
+    }
+
     #[test]
     fn test_silence_adapter_synthesize() {
         let rt = tokio::runtime::Runtime::new().unwrap();
@@ -604,6 +725,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_default_prosody_scales_duration() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let silence = SilenceTTS::new();
+
+        let (plain, neutral, fast) = rt.block_on(async {
+            silence.initialize().await.expect("Init should succeed");
+            (
+                silence.synthesize("test text", "default").await.unwrap(),
+                silence
+                    .synthesize_with_prosody("test text", "default", Prosody::default())
+                    .await
+                    .unwrap(),
+                silence
+                    .synthesize_with_prosody(
+                        "test text",
+                        "default",
+                        Prosody::new(2.0, 3.0).unwrap(),
+                    )
+                    .await
+                    .unwrap(),
+            )
+        });
+
+        // Neutral prosody reproduces plain synthesis exactly
+        assert_eq!(neutral.samples, plain.samples);
+        assert_eq!(neutral.duration_ms, plain.duration_ms);
+
+        // Double speed halves the duration
+        assert_eq!(fast.samples.len(), plain.samples.len().div_ceil(2));
+        assert_eq!(fast.duration_ms, plain.duration_ms / 2);
+    }
+
     #[test]
     fn test_tts_error_variants() {
         // Ensure error types are constructible and displayable
@@ -497,6 +497,11 @@ impl TextToSpeech for OrpheusTts {
         "Orpheus TTS (Llama-3B, GGUF) — expressive speech with emotion tags <laugh> <sigh> <gasp>"
     }
 
+    /// Audio tokens are sampled, so each rendition differs
+    fn deterministic(&self) -> bool {
+        false
+    }
+
     fn is_initialized(&self) -> bool {
         ORPHEUS_MODEL.is_loaded()
     }
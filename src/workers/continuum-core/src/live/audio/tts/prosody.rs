@@ -0,0 +1,278 @@
+//! Prosody Control (speech rate and pitch)
+//!
+//! Post-processing for adapters without a native rate/pitch knob:
+//! - Speed: WSOLA time-stretch — duration changes, pitch doesn't.
+//! - Pitch: WSOLA-stretch by the pitch ratio, then resample to the length
+//!   the speed asks for — pitch changes, duration doesn't.
+//!
+//! Pitch shifting is resampling-based, so formants move with the pitch;
+//! shifts of a few semitones still sound natural, an octave does not.
+//! Neutral prosody (speed 1.0, pitch 0.0) returns the audio untouched.
+
+use super::{audio_utils, SynthesisResult};
+use std::f32::consts::PI;
+
+/// Slowest / fastest supported speech rate
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 2.0;
+
+/// Largest supported pitch shift in either direction
+pub const MAX_PITCH_SEMITONES: f32 = 12.0;
+
+/// WSOLA frame length — a few pitch periods of speech
+const WSOLA_FRAME_MS: usize = 20;
+
+/// Speech rate and pitch for one synthesis request
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prosody {
+    /// Rate multiplier: 1.2 = 20% faster, 0.8 = 20% slower
+    pub speed: f32,
+    /// Pitch shift in semitones: +2.0 = two semitones higher
+    pub pitch_semitones: f32,
+}
+
+impl Default for Prosody {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            pitch_semitones: 0.0,
+        }
+    }
+}
+
+impl Prosody {
+    /// Validated prosody (speed in [MIN_SPEED, MAX_SPEED], pitch within
+    /// ±MAX_PITCH_SEMITONES)
+    pub fn new(speed: f32, pitch_semitones: f32) -> Result<Self, String> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(format!(
+                "Speed must be in [{MIN_SPEED}, {MAX_SPEED}], got {speed}"
+            ));
+        }
+        if !(-MAX_PITCH_SEMITONES..=MAX_PITCH_SEMITONES).contains(&pitch_semitones) {
+            return Err(format!(
+                "Pitch must be within ±{MAX_PITCH_SEMITONES} semitones, got {pitch_semitones}"
+            ));
+        }
+        Ok(Self {
+            speed,
+            pitch_semitones,
+        })
+    }
+
+    /// True when applying this prosody would leave audio unchanged
+    pub fn is_neutral(&self) -> bool {
+        self.speed == 1.0 && self.pitch_semitones == 0.0
+    }
+
+    /// Frequency multiplier for the pitch shift
+    pub fn pitch_ratio(&self) -> f32 {
+        2f32.powf(self.pitch_semitones / 12.0)
+    }
+
+    /// The pitch part only (for adapters that handle speed natively)
+    pub fn pitch_only(&self) -> Self {
+        Self {
+            speed: 1.0,
+            ..*self
+        }
+    }
+}
+
+/// Apply prosody to i16 PCM. Neutral prosody returns the input as-is.
+pub fn apply(samples: &[i16], sample_rate: u32, prosody: Prosody) -> Vec<i16> {
+    if prosody.is_neutral() || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let input: Vec<f32> = samples.iter().map(|&s| s as f32 / 32767.0).collect();
+    let pitch = prosody.pitch_ratio();
+    let target_len = (input.len() as f64 / prosody.speed as f64).round() as usize;
+
+    // Stretch to target_len * pitch, then resampling down to target_len
+    // raises pitch by `pitch` (and vice versa)
+    let stretch = pitch / prosody.speed;
+    let stretched = if (stretch - 1.0).abs() > f32::EPSILON {
+        wsola(&input, stretch, sample_rate)
+    } else {
+        input
+    };
+    let output = if pitch != 1.0 {
+        resample_linear(&stretched, target_len)
+    } else {
+        stretched
+    };
+
+    audio_utils::f32_to_i16(&output)
+}
+
+/// Apply prosody to a synthesis result, keeping `duration_ms` in sync
+pub fn apply_to_result(mut result: SynthesisResult, prosody: Prosody) -> SynthesisResult {
+    if prosody.is_neutral() {
+        return result;
+    }
+    result.samples = apply(&result.samples, result.sample_rate, prosody);
+    result.duration_ms = audio_utils::duration_ms(result.samples.len(), result.sample_rate);
+    result
+}
+
+/// Waveform-similarity overlap-add time stretch to `ratio` × input length.
+///
+/// Frames are Hann-windowed with 50% overlap. Each frame is taken from
+/// near its nominal input position, at the offset whose start best matches
+/// the natural continuation of the previous frame, so pitch periods line up
+/// and the overlap doesn't comb-filter.
+fn wsola(input: &[f32], ratio: f32, sample_rate: u32) -> Vec<f32> {
+    let frame = (sample_rate as usize * WSOLA_FRAME_MS / 1000).max(16) & !1;
+    let hop = frame / 2;
+    let tolerance = hop / 2;
+    let out_len = (input.len() as f64 * ratio as f64).round() as usize;
+    if input.len() < frame + tolerance {
+        return resample_linear(input, out_len);
+    }
+
+    let window: Vec<f32> = (0..frame)
+        .map(|n| 0.5 - 0.5 * (2.0 * PI * n as f32 / frame as f32).cos())
+        .collect();
+    let max_start = input.len() - frame;
+
+    let mut output = vec![0.0f32; out_len + frame];
+    let mut prev_start = 0;
+    let mut out_pos = 0;
+    while out_pos < out_len {
+        let start = if out_pos == 0 {
+            0
+        } else {
+            let nominal = ((out_pos as f64 / ratio as f64).round() as usize).min(max_start);
+            let natural = (prev_start + hop).min(max_start);
+            best_offset(input, natural, nominal, tolerance, max_start, hop)
+        };
+
+        for (i, &w) in window.iter().enumerate() {
+            // No fade-in on the very first frame
+            let w = if out_pos == 0 && i < hop { 1.0 } else { w };
+            output[out_pos + i] += input[start + i] * w;
+        }
+        prev_start = start;
+        out_pos += hop;
+    }
+
+    output.truncate(out_len);
+    output
+}
+
+/// Start in `nominal ± tolerance` whose first `len` samples correlate best
+/// with the samples at `natural`
+fn best_offset(
+    input: &[f32],
+    natural: usize,
+    nominal: usize,
+    tolerance: usize,
+    max_start: usize,
+    len: usize,
+) -> usize {
+    let reference = &input[natural..natural + len];
+    let lo = nominal.saturating_sub(tolerance);
+    let hi = (nominal + tolerance).min(max_start);
+    (lo..=hi)
+        .map(|start| {
+            let score: f32 = reference
+                .iter()
+                .zip(&input[start..start + len])
+                .map(|(a, b)| a * b)
+                .sum();
+            (start, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(start, _)| start)
+        .unwrap_or(nominal)
+}
+
+/// Linear-interpolation resample to exactly `out_len` samples
+fn resample_linear(input: &[f32], out_len: usize) -> Vec<f32> {
+    match (input.len(), out_len) {
+        (_, 0) => Vec::new(),
+        (0, _) => vec![0.0; out_len],
+        (1, _) | (_, 1) => vec![input[0]; out_len],
+        (in_len, _) => {
+            let step = (in_len - 1) as f64 / (out_len - 1) as f64;
+            (0..out_len)
+                .map(|i| {
+                    let pos = i as f64 * step;
+                    let idx = (pos as usize).min(in_len - 2);
+                    let frac = (pos - idx as f64) as f32;
+                    input[idx] * (1.0 - frac) + input[idx + 1] * frac
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    fn sine(freq: f32, secs: f32) -> Vec<i16> {
+        (0..(RATE as f32 * secs) as usize)
+            .map(|i| ((2.0 * PI * freq * i as f32 / RATE as f32).sin() * 16000.0) as i16)
+            .collect()
+    }
+
+    /// Dominant frequency estimate from zero crossings, ignoring the edges
+    fn frequency(samples: &[i16]) -> f32 {
+        let body = &samples[samples.len() / 10..samples.len() * 9 / 10];
+        let crossings = body.windows(2).filter(|w| (w[0] < 0) != (w[1] < 0)).count();
+        crossings as f32 / 2.0 / (body.len() as f32 / RATE as f32)
+    }
+
+    fn assert_frequency(samples: &[i16], expected: f32, tolerance: f32) {
+        let actual = frequency(samples);
+        assert!(
+            (actual - expected).abs() < tolerance,
+            "expected ~{expected}Hz, got {actual}Hz"
+        );
+    }
+
+    #[test]
+    fn test_neutral_prosody_is_identity() {
+        let audio = sine(220.0, 0.5);
+        assert_eq!(apply(&audio, RATE, Prosody::default()), audio);
+    }
+
+    #[test]
+    fn test_speed_changes_duration_not_pitch() {
+        let audio = sine(200.0, 1.0);
+
+        let fast = apply(&audio, RATE, Prosody::new(1.25, 0.0).unwrap());
+        assert_eq!(fast.len(), 12800);
+        assert_frequency(&fast, 200.0, 10.0);
+
+        let slow = apply(&audio, RATE, Prosody::new(0.8, 0.0).unwrap());
+        assert_eq!(slow.len(), 20000);
+        assert_frequency(&slow, 200.0, 10.0);
+    }
+
+    #[test]
+    fn test_pitch_changes_pitch_not_duration() {
+        let audio = sine(200.0, 1.0);
+
+        let up = apply(&audio, RATE, Prosody::new(1.0, 12.0).unwrap());
+        assert_eq!(up.len(), audio.len());
+        assert_frequency(&up, 400.0, 20.0);
+
+        let down_and_fast = apply(&audio, RATE, Prosody::new(2.0, -12.0).unwrap());
+        assert_eq!(down_and_fast.len(), 8000);
+        assert_frequency(&down_and_fast, 100.0, 10.0);
+    }
+
+    #[test]
+    fn test_prosody_validation() {
+        assert!(Prosody::new(0.4, 0.0).is_err());
+        assert!(Prosody::new(2.5, 0.0).is_err());
+        assert!(Prosody::new(1.0, 13.0).is_err());
+        assert!(Prosody::new(f32::NAN, 0.0).is_err());
+        assert!(Prosody::new(1.5, -3.0).unwrap().pitch_only().speed == 1.0);
+    }
+}
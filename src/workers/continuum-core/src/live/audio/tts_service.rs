@@ -3,7 +3,7 @@
 //! This is the proper layer between IPC and the TTS adapters.
 //! IPC should NOT directly call TTS - it should call this service.
 
-use crate::live::audio::tts::{self, SynthesisResult, TTSError};
+use crate::live::audio::tts::{self, Prosody, SynthesisResult, TTSError};
 
 /// Synthesize speech from text using a TTS adapter
 ///
@@ -20,7 +20,19 @@ pub async fn synthesize_speech_async(
     adapter: Option<&str>,
     gender_hint: Option<&str>,
 ) -> Result<SynthesisResult, TTSError> {
-    synthesize_speech_impl(text, voice, adapter, gender_hint).await
+    synthesize_speech_impl(text, voice, adapter, gender_hint, Prosody::default()).await
+}
+
+/// `synthesize_speech_async` with speech rate / pitch control.
+/// `Prosody::default()` produces exactly the same audio.
+pub async fn synthesize_speech_prosody_async(
+    text: &str,
+    voice: Option<&str>,
+    adapter: Option<&str>,
+    gender_hint: Option<&str>,
+    prosody: Prosody,
+) -> Result<SynthesisResult, TTSError> {
+    synthesize_speech_impl(text, voice, adapter, gender_hint, prosody).await
 }
 
 /// This is a synchronous wrapper that creates its own tokio runtime.
@@ -41,7 +53,9 @@ pub fn synthesize_speech_sync(
 ) -> Result<SynthesisResult, TTSError> {
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| TTSError::SynthesisFailed(format!("Failed to create runtime: {e}")))?;
-    rt.block_on(async { synthesize_speech_impl(text, voice, adapter, gender_hint).await })
+    rt.block_on(async {
+        synthesize_speech_impl(text, voice, adapter, gender_hint, Prosody::default()).await
+    })
 }
 
 async fn synthesize_speech_impl(
@@ -49,6 +63,7 @@ async fn synthesize_speech_impl(
     voice: Option<&str>,
     adapter: Option<&str>,
     gender_hint: Option<&str>,
+    prosody: Prosody,
 ) -> Result<SynthesisResult, TTSError> {
     // Initialize TTS system if needed
     if !tts::is_initialized() {
@@ -60,8 +75,8 @@ async fn synthesize_speech_impl(
 
     // Use specific adapter if requested, otherwise use active adapter
     match adapter {
-        Some(name) => tts::synthesize_with(text, voice_id, name, gender_hint).await,
-        None => tts::synthesize(text, voice_id, gender_hint).await,
+        Some(name) => tts::synthesize_with(text, voice_id, name, gender_hint, prosody).await,
+        None => tts::synthesize(text, voice_id, gender_hint, prosody).await,
     }
 }
 
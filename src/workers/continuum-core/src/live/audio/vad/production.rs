@@ -6,7 +6,8 @@
 //! - Low latency (fast silence detection)
 //! - Perfect noise rejection
 
-use super::{SileroRawVAD, VADError, VoiceActivityDetection, WebRtcVAD};
+use super::{SileroRawVAD, VADError, VADResult, VoiceActivityDetection, WebRtcVAD};
+use crate::audio_constants::AUDIO_FRAME_SIZE;
 use crate::{clog_debug, clog_info};
 use std::collections::VecDeque;
 use std::time::Instant;
@@ -73,6 +74,17 @@ impl Default for ProductionVADConfig {
     }
 }
 
+impl ProductionVADConfig {
+    /// End an utterance after `endpoint_silence_ms` of non-speech instead
+    /// of the default 256ms, rounded up to whole frames
+    pub fn with_endpoint_silence_ms(mut self, endpoint_silence_ms: u32) -> Self {
+        let frame_ms =
+            (AUDIO_FRAME_SIZE as u32 * 1000 / crate::audio_constants::AUDIO_SAMPLE_RATE).max(1);
+        self.silence_threshold_frames = endpoint_silence_ms.div_ceil(frame_ms).max(1);
+        self
+    }
+}
+
 /// Sentence buffer for capturing complete utterances
 struct SentenceBuffer {
     /// Buffered audio chunks
@@ -222,9 +234,38 @@ impl ProductionVAD {
         }
 
         self.frame_count += 1;
+        let is_speech = self.detect(audio)?.is_speech;
+
+        // Add to buffer
+        self.buffer.add_frame(audio, is_speech);
+
+        // Check if we have a complete sentence
+        if self.buffer.should_transcribe() {
+            let complete_audio = self.buffer.get_audio();
+            clog_info!(
+                "VAD: Sentence complete! speech_frames={}, total_samples={}",
+                self.buffer.speech_frames,
+                complete_audio.len()
+            );
+            self.buffer.clear();
+            Ok(Some(complete_audio))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Classify one frame with the configured stages, without buffering it
+    /// into an utterance
+    pub fn detect(&self, audio: &[i16]) -> Result<VADResult, VADError> {
+        if !self.initialized {
+            return Err(VADError::ModelNotLoaded(
+                "ProductionVAD not initialized".into(),
+            ));
+        }
+
         let max_amp = audio.iter().map(|s| s.unsigned_abs()).max().unwrap_or(0);
 
-        let is_speech = if self.config.use_two_stage {
+        let result = if self.config.use_two_stage {
             // Stage 1: Fast pre-filter (1-10μs)
             let quick_result = self.webrtc.detect(audio)?;
 
@@ -235,7 +276,7 @@ impl ProductionVAD {
                         self.frame_count, max_amp, self.buffer.speech_frames, self.buffer.silence_frames);
                 }
                 // Definite silence - skip expensive Silero check
-                false
+                quick_result
             } else {
                 // Possible speech - confirm with Silero (54ms)
                 let accurate_result = self.silero.detect(audio)?;
@@ -246,36 +287,32 @@ impl ProductionVAD {
                         self.frame_count, accurate_result.confidence, self.config.silero_threshold,
                         confirmed, max_amp);
                 }
-                confirmed
+                VADResult {
+                    is_speech: confirmed,
+                    confidence: accurate_result.confidence,
+                }
             }
         } else {
             // Single-stage: Silero only (54ms every frame)
             let result = self.silero.detect(audio)?;
-            result.confidence > self.config.silero_threshold
+            VADResult {
+                is_speech: result.confidence > self.config.silero_threshold,
+                confidence: result.confidence,
+            }
         };
-
-        // Add to buffer
-        self.buffer.add_frame(audio, is_speech);
-
-        // Check if we have a complete sentence
-        if self.buffer.should_transcribe() {
-            let complete_audio = self.buffer.get_audio();
-            clog_info!(
-                "VAD: Sentence complete! speech_frames={}, total_samples={}",
-                self.buffer.speech_frames,
-                complete_audio.len()
-            );
-            self.buffer.clear();
-            Ok(Some(complete_audio))
-        } else {
-            Ok(None)
-        }
+        Ok(result)
     }
 
     /// Get current configuration
     pub fn config(&self) -> &ProductionVADConfig {
         &self.config
     }
+
+    /// Change the Silero confidence threshold mid-stream (lower = more
+    /// sensitive). Buffered speech is kept.
+    pub fn set_silero_threshold(&mut self, threshold: f32) {
+        self.config.silero_threshold = threshold;
+    }
 }
 
 impl Default for ProductionVAD {
@@ -322,5 +359,10 @@ mod tests {
         assert_eq!(config.silero_threshold, 0.3); // Lowered for production
         assert_eq!(config.silence_threshold_frames, 8); // 0.256s (faster feedback)
         assert!(config.use_two_stage); // Performance optimization
+
+        let config = ProductionVADConfig::default().with_endpoint_silence_ms(500);
+        assert_eq!(config.silence_threshold_frames, 16); // rounded up to 512ms
+        let config = ProductionVADConfig::default().with_endpoint_silence_ms(0);
+        assert_eq!(config.silence_threshold_frames, 1);
     }
 }
@@ -0,0 +1,281 @@
+//! A/V Sync — lines avatar video up with the speech driving it.
+//!
+//! A talking head's audio (TTS) and video (the avatar renderer) are produced
+//! independently and at different rates, so without alignment the lips
+//! drift away from the speech. `SyncStage` buffers both streams and releases
+//! frames in pts order: a frame goes out once the other stream has reached
+//! its pts, so neither runs ahead of the other.
+//!
+//! Holding is bounded. A frame held for more than `max_hold_ms` of its own
+//! stream is released anyway, so a stalled renderer or TTS can't stall the
+//! output. Whenever the two streams are further apart than
+//! `drift_threshold_ms`, `StreamEvent::SyncDrift` reports by how much, once
+//! per excursion, so the app can compensate (skip avatar frames, drop
+//! render resolution).
+//!
+//! Both streams' pts must be on the same clock (ms since the call started).
+//! When a stream stops on purpose (end of an utterance), `flush` releases
+//! everything so the pause isn't counted as drift.
+//!
+//! The call server's forwarders run one per sender that sends both audio
+//! and video, so each connection receives them lined up.
+
+use crate::live::audio::stage_chain::StreamEvent;
+use crate::live::types::{AudioFrame, Frame, FrameMeta, VideoFrame};
+use std::collections::VecDeque;
+use tokio::sync::broadcast;
+
+/// Buffered drift events per subscriber
+const SYNC_CHANNEL_CAPACITY: usize = 16;
+
+/// Sync bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncConfig {
+    /// Longest a frame waits for the other stream to catch up
+    pub max_hold_ms: u64,
+    /// Audio/video offset beyond which `SyncDrift` is emitted. Lip sync is
+    /// noticeable from roughly 45ms of audio lead or 125ms of lag.
+    pub drift_threshold_ms: u64,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            max_hold_ms: 200,
+            drift_threshold_ms: 80,
+        }
+    }
+}
+
+/// A frame released by `SyncStage`
+#[derive(Debug, Clone)]
+pub enum SyncedFrame {
+    Audio(AudioFrame),
+    Video(VideoFrame),
+}
+
+impl Frame for SyncedFrame {
+    fn meta(&self) -> FrameMeta {
+        match self {
+            SyncedFrame::Audio(frame) => frame.meta(),
+            SyncedFrame::Video(frame) => frame.meta(),
+        }
+    }
+}
+
+/// Aligns an audio and a video stream by pts
+pub struct SyncStage {
+    config: SyncConfig,
+    audio: VecDeque<AudioFrame>,
+    video: VecDeque<VideoFrame>,
+    /// Newest pts pushed on each stream; None until its first frame
+    audio_pts: Option<u64>,
+    video_pts: Option<u64>,
+    /// A drift excursion has been reported and hasn't ended yet
+    drifting: bool,
+    events: broadcast::Sender<StreamEvent>,
+}
+
+impl SyncStage {
+    pub fn new(config: SyncConfig) -> Self {
+        let (events, _) = broadcast::channel(SYNC_CHANNEL_CAPACITY);
+        Self {
+            config,
+            audio: VecDeque::new(),
+            video: VecDeque::new(),
+            audio_pts: None,
+            video_pts: None,
+            drifting: false,
+            events,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StreamEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn push_audio(&mut self, frame: AudioFrame) {
+        let pts = frame.meta.pts_ms;
+        self.audio_pts = Some(self.audio_pts.map_or(pts, |newest| newest.max(pts)));
+        self.audio.push_back(frame);
+        self.check_drift();
+    }
+
+    pub fn push_video(&mut self, frame: VideoFrame) {
+        let pts = frame.meta().pts_ms;
+        self.video_pts = Some(self.video_pts.map_or(pts, |newest| newest.max(pts)));
+        self.video.push_back(frame);
+        self.check_drift();
+    }
+
+    /// Next frame in pts order, or None while it is waiting for the other
+    /// stream. Audio goes first on a tie.
+    pub fn pop(&mut self) -> Option<SyncedFrame> {
+        let audio_head = self.audio.front().map(|f| f.meta.pts_ms);
+        let video_head = self.video.front().map(|f| f.meta().pts_ms);
+        let (take_audio, pts) = match (audio_head, video_head) {
+            (Some(a), Some(v)) if a <= v => (true, a),
+            (_, Some(v)) => (false, v),
+            (Some(a), None) => (true, a),
+            (None, None) => return None,
+        };
+        let (own, other) = if take_audio {
+            (self.audio_pts, self.video_pts)
+        } else {
+            (self.video_pts, self.audio_pts)
+        };
+
+        let other_reached = other.is_some_and(|other| other >= pts);
+        let held_too_long = own.unwrap_or(pts).saturating_sub(pts) > self.config.max_hold_ms;
+        if !other_reached && !held_too_long {
+            return None;
+        }
+        if take_audio {
+            self.audio.pop_front().map(SyncedFrame::Audio)
+        } else {
+            self.video.pop_front().map(SyncedFrame::Video)
+        }
+    }
+
+    /// Every frame that can be released now
+    pub fn drain(&mut self) -> Vec<SyncedFrame> {
+        std::iter::from_fn(|| self.pop()).collect()
+    }
+
+    /// Release everything buffered, in pts order, and start over: the next
+    /// frames on either stream aren't measured against the old ones
+    pub fn flush(&mut self) -> Vec<SyncedFrame> {
+        let mut frames: Vec<SyncedFrame> = self
+            .audio
+            .drain(..)
+            .map(SyncedFrame::Audio)
+            .chain(self.video.drain(..).map(SyncedFrame::Video))
+            .collect();
+        // Stable, so each stream keeps its order and audio wins ties
+        frames.sort_by_key(|f| f.meta().pts_ms);
+        self.audio_pts = None;
+        self.video_pts = None;
+        self.drifting = false;
+        frames
+    }
+
+    /// How far audio is ahead of video (negative: behind); 0 until both
+    /// streams have started
+    pub fn drift_ms(&self) -> i64 {
+        match (self.audio_pts, self.video_pts) {
+            (Some(audio), Some(video)) => audio as i64 - video as i64,
+            _ => 0,
+        }
+    }
+
+    pub fn buffered(&self) -> (usize, usize) {
+        (self.audio.len(), self.video.len())
+    }
+
+    fn check_drift(&mut self) {
+        let drift_ms = self.drift_ms();
+        if drift_ms.unsigned_abs() <= self.config.drift_threshold_ms {
+            self.drifting = false;
+        } else if !self.drifting {
+            self.drifting = true;
+            let _ = self.events.send(StreamEvent::SyncDrift { drift_ms });
+        }
+    }
+}
+
+impl Default for SyncStage {
+    fn default() -> Self {
+        Self::new(SyncConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::types::{VideoFrameHeader, VideoPixelFormat};
+
+    fn audio(pts_ms: u64) -> AudioFrame {
+        AudioFrame {
+            meta: FrameMeta {
+                seq: pts_ms / 20,
+                pts_ms,
+            },
+            samples: vec![0; 320],
+        }
+    }
+
+    fn video(pts_ms: u32) -> VideoFrame {
+        VideoFrame {
+            header: VideoFrameHeader {
+                width: 2,
+                height: 2,
+                pixel_format: VideoPixelFormat::RGBA8,
+                timestamp_ms: pts_ms,
+                sequence: pts_ms / 33,
+                keyframe: true,
+            },
+            data: vec![0; 16],
+        }
+    }
+
+    fn order(frames: &[SyncedFrame]) -> Vec<(char, u64)> {
+        frames
+            .iter()
+            .map(|f| match f {
+                SyncedFrame::Audio(a) => ('a', a.meta.pts_ms),
+                SyncedFrame::Video(v) => ('v', v.meta().pts_ms),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_releases_in_pts_order_holding_faster_stream() {
+        let mut sync = SyncStage::default();
+        for pts in [0, 20, 40, 60] {
+            sync.push_audio(audio(pts));
+        }
+        // Nothing from the renderer yet: audio waits for it
+        assert!(sync.drain().is_empty());
+
+        sync.push_video(video(0));
+        assert_eq!(order(&sync.drain()), vec![('a', 0), ('v', 0)]);
+
+        sync.push_video(video(33));
+        assert_eq!(order(&sync.drain()), vec![('a', 20), ('v', 33)]);
+        assert_eq!(sync.buffered(), (2, 0));
+        assert_eq!(sync.drift_ms(), 27);
+    }
+
+    #[test]
+    fn test_bounded_hold_reports_drift_once() {
+        let mut sync = SyncStage::new(SyncConfig {
+            max_hold_ms: 200,
+            drift_threshold_ms: 80,
+        });
+        let mut events = sync.subscribe();
+
+        // TTS stalls after its first frame while the avatar keeps rendering
+        sync.push_audio(audio(0));
+        for pts in (0..=330).step_by(33) {
+            sync.push_video(video(pts));
+        }
+        assert_eq!(
+            events.try_recv().unwrap(),
+            StreamEvent::SyncDrift { drift_ms: -99 }
+        );
+        assert!(events.try_recv().is_err());
+
+        // Video held no more than 200ms behind its newest frame
+        assert_eq!(
+            order(&sync.drain()),
+            vec![('a', 0), ('v', 0), ('v', 33), ('v', 66), ('v', 99)]
+        );
+
+        // End of utterance: everything goes, and the gap isn't drift
+        assert_eq!(sync.flush().len(), 7);
+        sync.push_video(video(400));
+        sync.push_audio(audio(400));
+        assert_eq!(sync.drift_ms(), 0);
+        assert!(events.try_recv().is_err());
+    }
+}
@@ -15,6 +15,7 @@
 //! Module structure:
 //!   types.rs      — AvatarModel, AvatarStyle, VoiceProfile, AvatarGender, etc.
 //!   frame.rs      — RgbaFrame, AvatarConfig, ResolutionTier
+//!   av_sync.rs    — SyncStage (aligns avatar video with TTS audio by pts)
 //!   renderer.rs   — AvatarRenderer trait
 //!   catalog.rs    — AVATAR_CATALOG, avatar_model_path
 //!   selection.rs  — select_avatar_for_voice/identity/agent, allocate_avatars_batch
@@ -23,6 +24,7 @@
 //!   render_loop.rs — spawn_renderer_loop, create_renderer
 //!   backends/     — ProceduralRenderer, BevyChannelRenderer
 
+pub mod av_sync;
 pub mod backend;
 pub mod backends;
 pub mod catalog;
@@ -40,6 +42,7 @@ pub mod types;
 
 // Re-export everything at the module level for backward compatibility.
 // Call sites use `crate::live::avatar::RgbaFrame`, etc.
+pub use av_sync::{SyncConfig, SyncStage, SyncedFrame};
 pub use backend::{AvatarError, ModelFormat, RenderBackend};
 pub use backends::{
     Bevy3DBackend, BevyChannelRenderer, Live2DBackend, Live2DRenderer, ProceduralBackend,
@@ -2,10 +2,37 @@
 //!
 //! Handle is the universal correlation primitive - same as entity IDs, file descriptors,
 //! texture IDs. A UUID that identifies and correlates everything.
+//!
+//! Handles can form a tree: `parent.child()` mints a new handle and records
+//! its parent, so sub-operations (STT/LLM/TTS within a voice turn) get their
+//! own handle while staying correlated to the operation that spawned them.
 
+use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+/// child → (parent, linked at) for handles created via `Handle::child()`.
+/// Kept out of Handle itself so it stays a plain Copy UUID on the wire.
+static LINEAGE: LazyLock<DashMap<Handle, (Handle, Instant)>> = LazyLock::new(DashMap::new);
+
+/// Links older than this are dropped even if never released, so a
+/// sub-operation that forgets `release()` can't grow LINEAGE forever.
+/// Far longer than any voice turn or pipeline step.
+const LINEAGE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// `child()` sweeps expired links once every this many new links
+const LINEAGE_SWEEP_INTERVAL: usize = 1024;
+
+static LINEAGE_INSERTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Drop links created before `cutoff`
+fn sweep_lineage(cutoff: Instant) {
+    LINEAGE.retain(|_, (_, linked_at)| *linked_at >= cutoff);
+}
+
 /// Universal correlation handle.
 ///
 /// Used everywhere, in and out:
@@ -37,6 +64,44 @@ impl Handle {
     pub fn short(&self) -> String {
         self.0.to_string()[..8].to_string()
     }
+
+    /// Create a handle for a sub-operation of this one
+    pub fn child(&self) -> Self {
+        let child = Self::new();
+        let now = Instant::now();
+        LINEAGE.insert(child, (*self, now));
+        if LINEAGE_INSERTS
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(LINEAGE_SWEEP_INTERVAL)
+        {
+            if let Some(cutoff) = now.checked_sub(LINEAGE_TTL) {
+                sweep_lineage(cutoff);
+            }
+        }
+        child
+    }
+
+    /// Parent handle, if this was created via `child()` and not yet
+    /// released or expired (`LINEAGE_TTL`)
+    pub fn parent(&self) -> Option<Self> {
+        LINEAGE.get(self).map(|link| link.0)
+    }
+
+    /// Parent, grandparent, ... up to the root (nearest first)
+    pub fn ancestors(&self) -> Vec<Self> {
+        let mut ancestors = Vec::new();
+        let mut current = *self;
+        while let Some(parent) = current.parent() {
+            ancestors.push(parent);
+            current = parent;
+        }
+        ancestors
+    }
+
+    /// Forget this handle's parent link. Call when the sub-operation is done.
+    pub fn release(&self) {
+        LINEAGE.remove(self);
+    }
 }
 
 impl Default for Handle {
@@ -95,4 +160,36 @@ mod tests {
         let handle = Handle::new();
         assert_eq!(handle.short().len(), 8);
     }
+
+    #[test]
+    fn test_handle_lineage() {
+        let root = Handle::new();
+        let turn = root.child();
+        let stt = turn.child();
+
+        assert_eq!(root.parent(), None);
+        assert_eq!(stt.parent(), Some(turn));
+        assert_eq!(stt.ancestors(), vec![turn, root]);
+
+        stt.release();
+        assert_eq!(stt.parent(), None);
+        turn.release();
+    }
+
+    #[test]
+    fn test_unreleased_lineage_expires() {
+        let root = Handle::new();
+        let fresh = root.child();
+        let now = Instant::now();
+        let Some(linked_at) = now.checked_sub(LINEAGE_TTL * 2) else {
+            return; // Clock too close to its origin to backdate
+        };
+        let stale = Handle::new();
+        LINEAGE.insert(stale, (root, linked_at));
+
+        sweep_lineage(linked_at + LINEAGE_TTL);
+        assert_eq!(stale.parent(), None);
+        assert_eq!(fresh.parent(), Some(root));
+        fresh.release();
+    }
 }
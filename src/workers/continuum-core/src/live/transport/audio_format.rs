@@ -0,0 +1,468 @@
+//! Audio Format Negotiation
+//!
+//! The call server mixes and transcribes 16-bit PCM at `AUDIO_SAMPLE_RATE`,
+//! but browser clients capture at whatever the audio device runs at
+//! (44.1kHz and 48kHz are common) and AudioWorklets hand out float32.
+//! Sending those as-is used to be accepted silently and played back at the
+//! wrong speed.
+//!
+//! A client sends `AudioCapabilities` with the encodings and sample rates it
+//! can handle; the server picks one with `negotiate` and echoes it back as
+//! `AudioFormat`. The chosen format applies in both directions: `InboundAudio`
+//! converts what the client sends to the server format, `OutboundAudio`
+//! converts what the server sends back. Clients that never negotiate get the
+//! server format, as before.
+//!
+//! With the `opus-codec` feature a client can also pick `opus`: each binary
+//! audio payload is then one or more Opus packets, each prefixed with its
+//! length (u16 LE), coded by the WebRTC adapters at the server's rate.
+
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
+#[cfg(feature = "opus-codec")]
+use crate::clog_warn;
+use crate::live::audio::resample::ResampleStage;
+use crate::live::handle::Handle;
+#[cfg(feature = "opus-codec")]
+use crate::live::transport::webrtc::{OpusConfig, WebRtcInputAdapter, WebRtcOutputAdapter};
+use crate::utils::audio::bytes_to_i16;
+use std::collections::HashMap;
+
+/// Lowest and highest sample rate accepted from a client
+const MIN_SAMPLE_RATE: u32 = 8_000;
+const MAX_SAMPLE_RATE: u32 = 192_000;
+
+/// Sample encoding on the wire, little-endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioEncoding {
+    /// Signed 16-bit PCM
+    Pcm16,
+    /// 32-bit float in [-1, 1] (Web Audio's native format)
+    Float32,
+    /// Length-prefixed Opus packets
+    #[cfg(feature = "opus-codec")]
+    Opus,
+}
+
+impl AudioEncoding {
+    /// Preferred first
+    const SUPPORTED: &'static [AudioEncoding] = &[
+        AudioEncoding::Pcm16,
+        AudioEncoding::Float32,
+        #[cfg(feature = "opus-codec")]
+        AudioEncoding::Opus,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AudioEncoding::Pcm16 => "pcm16",
+            AudioEncoding::Float32 => "float32",
+            #[cfg(feature = "opus-codec")]
+            AudioEncoding::Opus => "opus",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "pcm16" | "s16le" | "pcm_s16le" => Some(AudioEncoding::Pcm16),
+            "float32" | "f32le" | "pcm_f32le" => Some(AudioEncoding::Float32),
+            #[cfg(feature = "opus-codec")]
+            "opus" => Some(AudioEncoding::Opus),
+            _ => None,
+        }
+    }
+}
+
+/// Format of audio on one connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireAudioFormat {
+    pub encoding: AudioEncoding,
+    pub sample_rate: u32,
+}
+
+impl Default for WireAudioFormat {
+    fn default() -> Self {
+        Self {
+            encoding: AudioEncoding::Pcm16,
+            sample_rate: AUDIO_SAMPLE_RATE,
+        }
+    }
+}
+
+/// Pick a format from what the client supports.
+///
+/// The encoding is the first of ours the client lists. The sample rate is
+/// the server's own when offered (no conversion), otherwise the lowest
+/// offered rate above it (nothing lost, least bandwidth), otherwise the
+/// highest offered. Opus is always coded at the server's rate.
+pub fn negotiate(encodings: &[String], sample_rates: &[u32]) -> Result<WireAudioFormat, String> {
+    let offered: Vec<AudioEncoding> = encodings
+        .iter()
+        .filter_map(|name| AudioEncoding::parse(name))
+        .collect();
+    let encoding = AudioEncoding::SUPPORTED
+        .iter()
+        .copied()
+        .find(|encoding| offered.contains(encoding))
+        .ok_or_else(|| {
+            let supported: Vec<&str> = AudioEncoding::SUPPORTED
+                .iter()
+                .map(|e| e.as_str())
+                .collect();
+            format!(
+                "No supported audio encoding in {encodings:?} (server supports {})",
+                supported.join(", ")
+            )
+        })?;
+    #[cfg(feature = "opus-codec")]
+    if encoding == AudioEncoding::Opus {
+        return Ok(WireAudioFormat {
+            encoding,
+            sample_rate: AUDIO_SAMPLE_RATE,
+        });
+    }
+
+    let rates: Vec<u32> = sample_rates
+        .iter()
+        .copied()
+        .filter(|rate| (MIN_SAMPLE_RATE..=MAX_SAMPLE_RATE).contains(rate))
+        .collect();
+    let sample_rate = if rates.contains(&AUDIO_SAMPLE_RATE) {
+        AUDIO_SAMPLE_RATE
+    } else if let Some(&rate) = rates.iter().filter(|&&r| r > AUDIO_SAMPLE_RATE).min() {
+        rate
+    } else {
+        rates.iter().copied().max().ok_or_else(|| {
+            format!(
+                "No usable sample rate in {sample_rates:?} ({MIN_SAMPLE_RATE}-{MAX_SAMPLE_RATE}Hz)"
+            )
+        })?
+    };
+
+    Ok(WireAudioFormat {
+        encoding,
+        sample_rate,
+    })
+}
+
+/// Client → server: wire audio to server-format PCM
+#[derive(Debug, Default)]
+pub struct InboundAudio {
+    format: WireAudioFormat,
+    resampler: Option<ResampleStage>,
+    #[cfg(feature = "opus-codec")]
+    opus: Option<OpusInbound>,
+}
+
+impl InboundAudio {
+    pub fn new(format: WireAudioFormat) -> Self {
+        Self {
+            format,
+            resampler: ResampleStage::between(format.sample_rate, AUDIO_SAMPLE_RATE),
+            #[cfg(feature = "opus-codec")]
+            opus: (format.encoding == AudioEncoding::Opus).then(OpusInbound::default),
+        }
+    }
+
+    pub fn format(&self) -> WireAudioFormat {
+        self.format
+    }
+
+    /// Decode a binary audio payload. A PCM payload that isn't a whole
+    /// number of samples is an error rather than silently dropped.
+    pub fn decode(&mut self, bytes: &[u8]) -> Result<Vec<i16>, String> {
+        let samples = match self.format.encoding {
+            AudioEncoding::Pcm16 => {
+                check_whole_samples(bytes, 2, AudioEncoding::Pcm16)?;
+                bytes_to_i16(bytes)
+            }
+            AudioEncoding::Float32 => {
+                check_whole_samples(bytes, 4, AudioEncoding::Float32)?;
+                float32_to_i16(bytes)
+            }
+            #[cfg(feature = "opus-codec")]
+            AudioEncoding::Opus => match &mut self.opus {
+                Some(opus) => opus.decode(bytes),
+                None => Vec::new(),
+            },
+        };
+        Ok(self.resample(samples))
+    }
+
+    /// Bring already-decoded PCM (legacy base64 audio) to the server rate
+    pub fn resample(&mut self, samples: Vec<i16>) -> Vec<i16> {
+        match &mut self.resampler {
+            Some(resampler) => resampler.process(&samples),
+            None => samples,
+        }
+    }
+}
+
+/// Server → client: server-format PCM to wire audio. Each sender's audio is
+/// a separate stream, so each gets its own resampler.
+#[derive(Debug, Default)]
+pub struct OutboundAudio {
+    format: WireAudioFormat,
+    resamplers: HashMap<Handle, ResampleStage>,
+    #[cfg(feature = "opus-codec")]
+    encoders: HashMap<Handle, OpusOutbound>,
+}
+
+impl OutboundAudio {
+    pub fn new(format: WireAudioFormat) -> Self {
+        Self {
+            format,
+            resamplers: HashMap::new(),
+            #[cfg(feature = "opus-codec")]
+            encoders: HashMap::new(),
+        }
+    }
+
+    pub fn format(&self) -> WireAudioFormat {
+        self.format
+    }
+
+    /// Drop the per-sender state kept for `sender`, once it has left
+    pub fn remove_sender(&mut self, sender: Handle) {
+        self.resamplers.remove(&sender);
+        #[cfg(feature = "opus-codec")]
+        self.encoders.remove(&sender);
+    }
+
+    /// Append `samples` from `sender`, encoded, to `out`
+    pub fn encode_into(&mut self, sender: Handle, samples: &[i16], out: &mut Vec<u8>) {
+        let resampled;
+        let samples = if self.format.sample_rate == AUDIO_SAMPLE_RATE {
+            samples
+        } else {
+            let rate = self.format.sample_rate;
+            resampled = self
+                .resamplers
+                .entry(sender)
+                .or_insert_with(|| ResampleStage::new(AUDIO_SAMPLE_RATE, rate))
+                .process(samples);
+            &resampled
+        };
+        match self.format.encoding {
+            AudioEncoding::Pcm16 => out.extend(samples.iter().flat_map(|&s| s.to_le_bytes())),
+            AudioEncoding::Float32 => out.extend(
+                samples
+                    .iter()
+                    .flat_map(|&s| (s as f32 / 32768.0).to_le_bytes()),
+            ),
+            #[cfg(feature = "opus-codec")]
+            AudioEncoding::Opus => self
+                .encoders
+                .entry(sender)
+                .or_insert_with(|| OpusOutbound::new(sender))
+                .encode_into(samples, out),
+        }
+    }
+}
+
+/// Inbound Opus for one connection. A WebSocket delivers every packet in
+/// order, so packets are numbered as they arrive.
+#[cfg(feature = "opus-codec")]
+#[derive(Default)]
+struct OpusInbound {
+    decoder: WebRtcInputAdapter,
+    sequence: u16,
+}
+
+#[cfg(feature = "opus-codec")]
+impl OpusInbound {
+    fn decode(&mut self, bytes: &[u8]) -> Vec<i16> {
+        let mut samples = Vec::new();
+        for packet in opus_packets(bytes) {
+            match self.decoder.decode(0, self.sequence, packet) {
+                Ok(Some(audio)) => samples.extend_from_slice(&audio.frame.samples),
+                Ok(None) => {}
+                Err(e) => clog_warn!("Dropping Opus packet: {}", e),
+            }
+            self.sequence = self.sequence.wrapping_add(1);
+        }
+        samples
+    }
+}
+
+#[cfg(feature = "opus-codec")]
+impl std::fmt::Debug for OpusInbound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OpusInbound")
+            .field("sequence", &self.sequence)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Outbound Opus for one sender's stream; None if the encoder failed to
+/// start, in which case that sender's audio is dropped
+#[cfg(feature = "opus-codec")]
+struct OpusOutbound(Option<WebRtcOutputAdapter>);
+
+#[cfg(feature = "opus-codec")]
+impl OpusOutbound {
+    fn new(sender: Handle) -> Self {
+        match WebRtcOutputAdapter::new(sender, OpusConfig::default()) {
+            Ok(encoder) => Self(Some(encoder)),
+            Err(e) => {
+                clog_warn!("No Opus encoder for {}: {}", sender.short(), e);
+                Self(None)
+            }
+        }
+    }
+
+    fn encode_into(&mut self, samples: &[i16], out: &mut Vec<u8>) {
+        let Some(encoder) = &mut self.0 else {
+            return;
+        };
+        match encoder.encode(samples) {
+            Ok(packets) => {
+                for packet in packets {
+                    out.extend_from_slice(&(packet.len() as u16).to_le_bytes());
+                    out.extend_from_slice(&packet);
+                }
+            }
+            Err(e) => clog_warn!("Opus encode for {} failed: {}", encoder.handle().short(), e),
+        }
+    }
+}
+
+#[cfg(feature = "opus-codec")]
+impl std::fmt::Debug for OpusOutbound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("OpusOutbound")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+/// Split a payload of length-prefixed Opus packets; a truncated last
+/// packet is dropped
+#[cfg(feature = "opus-codec")]
+fn opus_packets(mut bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+    std::iter::from_fn(move || {
+        let (len, rest) = bytes.split_first_chunk::<2>()?;
+        let len = u16::from_le_bytes(*len) as usize;
+        if rest.len() < len {
+            bytes = &[];
+            return None;
+        }
+        let (packet, rest) = rest.split_at(len);
+        bytes = rest;
+        Some(packet)
+    })
+}
+
+/// Error unless `bytes` holds whole `sample_size`-byte samples
+fn check_whole_samples(
+    bytes: &[u8],
+    sample_size: usize,
+    encoding: AudioEncoding,
+) -> Result<(), String> {
+    if bytes.len().is_multiple_of(sample_size) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} audio payload of {} bytes is not a whole number of {}-byte samples",
+            encoding.as_str(),
+            bytes.len(),
+            sample_size
+        ))
+    }
+}
+
+/// Little-endian f32 samples to i16; a trailing partial sample is ignored
+fn float32_to_i16(bytes: &[u8]) -> Vec<i16> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| {
+            let sample = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            (sample * 32768.0).clamp(i16::MIN as f32, i16::MAX as f32) as i16
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let format = negotiate(&names(&["float32", "pcm16"]), &[48_000, 16_000]).unwrap();
+        assert_eq!(format, WireAudioFormat::default());
+
+        // Browser without 16kHz: smallest rate above ours
+        let format = negotiate(&names(&["f32le"]), &[96_000, 44_100, 48_000]).unwrap();
+        assert_eq!(format.encoding, AudioEncoding::Float32);
+        assert_eq!(format.sample_rate, 44_100);
+
+        assert_eq!(
+            negotiate(&names(&["pcm16"]), &[8_000]).unwrap().sample_rate,
+            8_000
+        );
+        #[cfg(not(feature = "opus-codec"))]
+        assert!(negotiate(&names(&["opus"]), &[16_000]).is_err());
+        assert!(negotiate(&names(&["pcm16"]), &[0, 1_000_000]).is_err());
+    }
+
+    #[test]
+    fn test_round_trip_at_client_rate() {
+        let format = WireAudioFormat {
+            encoding: AudioEncoding::Float32,
+            sample_rate: 48_000,
+        };
+
+        // 20ms at 48kHz from the client becomes 20ms at 16kHz
+        let wire: Vec<u8> = (0..960)
+            .flat_map(|i| ((i as f32 * 0.05).sin() * 0.5).to_le_bytes())
+            .collect();
+        let mut inbound = InboundAudio::new(format);
+        let samples = inbound.decode(&wire).unwrap();
+        assert!((319..=320).contains(&samples.len()));
+        // A partial sample is reported, not dropped silently
+        assert!(inbound.decode(&wire[..wire.len() - 1]).is_err());
+        assert!(samples.iter().any(|&s| s > 15_000));
+
+        // And back out at 48kHz float32
+        let mut outbound = OutboundAudio::new(format);
+        let sender = Handle::new();
+        let mut bytes = Vec::new();
+        outbound.encode_into(sender, &[16_384; 320], &mut bytes);
+        assert!((957 * 4..=960 * 4).contains(&bytes.len()));
+        assert_eq!(f32::from_le_bytes(bytes[4..8].try_into().unwrap()), 0.5);
+        outbound.remove_sender(sender);
+        assert!(outbound.resamplers.is_empty());
+
+        // Unnegotiated connections pass PCM16 through untouched
+        let mut inbound = InboundAudio::default();
+        assert_eq!(inbound.decode(&[1, 0, 255, 255]).unwrap(), vec![1, -1]);
+        assert!(inbound.decode(&[1, 0, 255]).is_err());
+    }
+
+    #[cfg(feature = "opus-codec")]
+    #[test]
+    fn test_opus_round_trip() {
+        let format = negotiate(&names(&["opus"]), &[48_000]).unwrap();
+        assert_eq!(format.encoding, AudioEncoding::Opus);
+        assert_eq!(format.sample_rate, AUDIO_SAMPLE_RATE);
+
+        // Two 512-sample mixer frames: 1024 samples make three 20ms packets
+        let tone: Vec<i16> = (0..1024)
+            .map(|i| ((i as f32 * 0.17).sin() * 8000.0) as i16)
+            .collect();
+        let mut outbound = OutboundAudio::new(format);
+        let sender = Handle::new();
+        let mut bytes = Vec::new();
+        outbound.encode_into(sender, &tone[..512], &mut bytes);
+        outbound.encode_into(sender, &tone[512..], &mut bytes);
+        assert_eq!(opus_packets(&bytes).count(), 3);
+
+        let mut inbound = InboundAudio::new(format);
+        assert_eq!(inbound.decode(&bytes).unwrap().len(), 3 * 320);
+        // A truncated packet is dropped rather than misread
+        assert!(inbound.decode(&bytes[..bytes.len() - 1]).unwrap().len() < 3 * 320);
+    }
+}
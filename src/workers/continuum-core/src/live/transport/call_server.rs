@@ -4,16 +4,24 @@
 //! Each call has multiple participants, audio is mixed with mix-minus.
 
 use crate::audio_constants::AUDIO_SAMPLE_RATE;
+use crate::concurrent::{EventBus, FileEventSink};
 use crate::live::audio::capabilities::ModelCapabilityRegistry;
 use crate::live::audio::mixer::{AudioMixer, ParticipantStream};
+use crate::live::audio::recording::{CallRecorder, RecordingMode, RecordingSummary};
 use crate::live::audio::router::{AudioRouter, RoutedParticipant};
+use crate::live::audio::stage_chain::StreamEvent;
 use crate::live::audio::stt;
+use crate::live::audio::tts::{self, Prosody, VoiceInfo};
+use crate::live::avatar::{SyncConfig, SyncStage, SyncedFrame};
 use crate::live::handle::Handle;
-use crate::live::types::FrameKind;
-use crate::live::video::source::{TestPatternSource, VideoSource};
-use crate::utils::audio::{
-    base64_decode_i16, bytes_to_i16, i16_to_f32, is_silence, resample_to_16k,
+use crate::live::transport::audio_format::{
+    negotiate, InboundAudio, OutboundAudio, WireAudioFormat,
 };
+use crate::live::transport::turn::TurnTracker;
+use crate::live::types::{AudioFrame, FrameKind, FrameMeta, VideoFrame, VideoFrameHeader};
+use crate::live::video::keyframe_gate::KeyframeGate;
+use crate::live::video::source::{TestPatternSource, VideoSource};
+use crate::utils::audio::{base64_decode_i16, i16_to_f32, is_silence, resample_to_16k};
 use crate::{clog_error, clog_info, clog_warn};
 use futures_util::{SinkExt, StreamExt};
 use once_cell::sync::Lazy;
@@ -21,15 +29,49 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::Cursor;
 use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{broadcast, mpsc, RwLock, Semaphore};
+use tokio::sync::{broadcast, mpsc, watch, RwLock, Semaphore};
 use tokio_tungstenite::{accept_async, tungstenite::Message};
 use ts_rs::TS;
 
 /// Maximum characters to show in truncated text previews (logs, errors)
 const TEXT_PREVIEW_LENGTH: usize = 30;
 
+/// Transcription language for participants that haven't set one
+const DEFAULT_STT_LANGUAGE: &str = "en";
+
+/// Range accepted for a session's VAD threshold; outside it the VAD hears
+/// speech everywhere or nowhere
+const MIN_VAD_THRESHOLD: f32 = 0.05;
+const MAX_VAD_THRESHOLD: f32 = 0.95;
+
+/// Longest accepted TTS voice id
+const MAX_VOICE_ID_LEN: usize = 64;
+
+/// How long a participant whose WebSocket dropped stays in the call,
+/// waiting for the client to reconnect and resume (flaky mobile networks)
+const DEFAULT_RECONNECT_GRACE_SECS: u64 = 30;
+
+/// Live buffer per `CallManager::events()` subscriber, and how many recent
+/// events per participant a late subscriber is replayed
+const CALL_EVENT_CAPACITY: usize = 64;
+const CALL_EVENT_REPLAY_DEPTH: usize = 8;
+
+/// When set, participant lifecycle events are journaled to this JSONL file
+/// (see `FileEventSink`) so dropped calls can be reconstructed after a crash
+const CALL_EVENT_JOURNAL_ENV: &str = "CALL_EVENT_JOURNAL";
+
+/// How far one of a sender's streams may run ahead of the other before the
+/// other counts as stopped and the sender's frames go out unaligned again
+const AV_STREAM_STOPPED_MS: u64 = 1000;
+
+/// Env var enabling the WebSocket call server: a port, or `host:port`
+pub const CALL_SERVER_PORT_ENV: &str = "CONTINUUM_CALL_SERVER_PORT";
+
 /// Maximum concurrent transcription tasks
 /// With base model (~10x realtime), 2 concurrent should handle bursts
 /// If this fills up, we drop new audio rather than accumulate backlog
@@ -78,6 +120,17 @@ pub enum CallMessage {
         is_ai: bool, // AI participants get server-side audio buffering
     },
 
+    /// Session assigned on join (server → client). Present it in `Resume`
+    /// to re-attach after a dropped connection.
+    Joined { session_id: String },
+
+    /// Re-attach to a session whose connection dropped (client → server).
+    /// Audio sent while disconnected is not replayed.
+    Resume { session_id: String },
+
+    /// Session re-attached (server → client)
+    Resumed { session_id: String },
+
     /// Leave the call
     Leave,
 
@@ -89,6 +142,47 @@ pub enum CallMessage {
     /// Mute/unmute
     Mute { muted: bool },
 
+    /// Audio formats the client can send and play (client → server).
+    /// Encodings: "pcm16", "float32" (and "opus" in builds with the
+    /// `opus-codec` feature); sample rates in Hz.
+    AudioCapabilities {
+        encodings: Vec<String>,
+        sample_rates: Vec<u32>,
+    },
+
+    /// Override voice settings for this participant's session only
+    /// (client → server). Omitted fields keep their current value.
+    ConfigureSession {
+        /// VAD speech threshold, 0.05-0.95: lower catches quieter speech,
+        /// higher ignores more background noise
+        #[serde(default)]
+        vad_threshold: Option<f32>,
+        /// TTS voice for speech synthesized into this call
+        #[serde(default)]
+        tts_voice: Option<String>,
+        /// TTS rate multiplier, 0.5-2.0
+        #[serde(default)]
+        tts_speed: Option<f32>,
+        /// Language of this participant's speech ("en", "de", ...; "auto"
+        /// to detect)
+        #[serde(default)]
+        stt_language: Option<String>,
+    },
+
+    /// Overrides in effect after `ConfigureSession` (server → client);
+    /// null fields use the server defaults
+    SessionConfigured {
+        vad_threshold: Option<f32>,
+        tts_voice: Option<String>,
+        tts_speed: Option<f32>,
+        stt_language: Option<String>,
+    },
+
+    /// Format picked from `AudioCapabilities` (server → client). Binary
+    /// audio uses it in both directions; without negotiation it is pcm16 at
+    /// the server's sample rate.
+    AudioFormat { encoding: String, sample_rate: u32 },
+
     /// Participant joined notification
     ParticipantJoined {
         user_id: String,
@@ -177,6 +271,85 @@ impl Default for AudioConfig {
     }
 }
 
+/// Voice settings a participant overrides for their own session with
+/// `ConfigureSession`; None keeps the global setting
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionConfig {
+    pub vad_threshold: Option<f32>,
+    pub tts_voice: Option<String>,
+    pub tts_speed: Option<f32>,
+    pub stt_language: Option<String>,
+}
+
+impl SessionConfig {
+    /// Apply the fields set in `update`. All or nothing: if any field is
+    /// invalid, nothing changes. A TTS voice must be one of `voices` (the
+    /// active adapter's) unless that list is empty.
+    pub fn merge(&mut self, update: SessionConfig, voices: &[VoiceInfo]) -> Result<(), String> {
+        let mut merged = self.clone();
+        if let Some(threshold) = update.vad_threshold {
+            if !(MIN_VAD_THRESHOLD..=MAX_VAD_THRESHOLD).contains(&threshold) {
+                return Err(format!(
+                    "VAD threshold must be in [{MIN_VAD_THRESHOLD}, {MAX_VAD_THRESHOLD}], got {threshold}"
+                ));
+            }
+            merged.vad_threshold = Some(threshold);
+        }
+        if let Some(voice) = update.tts_voice {
+            let voice = voice.trim();
+            if voice.is_empty() || voice.len() > MAX_VOICE_ID_LEN {
+                return Err(format!(
+                    "TTS voice must be 1-{MAX_VOICE_ID_LEN} characters, got {:?}",
+                    voice
+                ));
+            }
+            if !voices.is_empty() && !voices.iter().any(|v| v.id == voice) {
+                let ids: Vec<&str> = voices.iter().map(|v| v.id.as_str()).collect();
+                return Err(format!(
+                    "Unknown TTS voice {voice:?}; available: {}",
+                    ids.join(", ")
+                ));
+            }
+            merged.tts_voice = Some(voice.to_string());
+        }
+        if let Some(speed) = update.tts_speed {
+            Prosody::new(speed, 0.0)?;
+            merged.tts_speed = Some(speed);
+        }
+        if let Some(language) = update.stt_language {
+            let language = language.trim().to_lowercase();
+            let is_code = (2..=3).contains(&language.len())
+                && language.chars().all(|c| c.is_ascii_lowercase());
+            if !is_code && language != "auto" {
+                return Err(format!(
+                    "STT language must be a language code like \"en\" or \"auto\", got {language:?}"
+                ));
+            }
+            merged.stt_language = Some(language);
+        }
+        *self = merged;
+        Ok(())
+    }
+
+    /// Language to transcribe with; None lets Whisper detect it
+    pub fn transcription_language(&self) -> Option<String> {
+        match self.stt_language.as_deref() {
+            Some("auto") => None,
+            Some(language) => Some(language.to_string()),
+            None => Some(DEFAULT_STT_LANGUAGE.to_string()),
+        }
+    }
+
+    fn to_message(&self) -> CallMessage {
+        CallMessage::SessionConfigured {
+            vad_threshold: self.vad_threshold,
+            tts_voice: self.tts_voice.clone(),
+            tts_speed: self.tts_speed,
+            stt_language: self.stt_language.clone(),
+        }
+    }
+}
+
 /// Transcription event for broadcasting to participants
 #[derive(Debug, Clone)]
 pub struct TranscriptionEvent {
@@ -193,13 +366,15 @@ pub struct Call {
     pub mixer: AudioMixer,
     /// Broadcast channel for per-sender audio (SFU pattern: sender handle, user_id, raw audio)
     /// Browser handles mixing — enables per-participant audio/video synchronization
-    pub audio_tx: broadcast::Sender<(Handle, String, Vec<i16>)>,
+    pub audio_tx: broadcast::Sender<(Handle, String, AudioFrame)>,
     /// Broadcast channel for sending transcriptions to participants
     pub transcription_tx: broadcast::Sender<TranscriptionEvent>,
     /// Broadcast channel for video frames (handle for mix-minus, user_id for routing, data is raw frame)
     pub video_tx: broadcast::Sender<(Handle, String, Vec<u8>)>,
     /// Broadcast channel for general JSON messages (avatar updates, video config, etc.)
     pub message_tx: broadcast::Sender<CallMessage>,
+    /// Start of the call clock: audio and video pts are ms since this
+    epoch: Instant,
     /// Total samples processed (for stats)
     pub samples_processed: u64,
     /// Current position in hold music (sample index)
@@ -212,13 +387,21 @@ pub struct Call {
     shutdown_tx: Option<mpsc::Sender<()>>,
     /// Whether any participant has video enabled
     pub has_video: bool,
+    /// Active recording (tees tick output to WAV on a writer thread)
+    recorder: Option<CallRecorder>,
+    /// Per-participant overrides from `ConfigureSession`
+    session_configs: HashMap<Handle, SessionConfig>,
+    /// Participant whose TTS overrides apply to speech synthesized into
+    /// the call. Only a call with a single human has one, since everyone
+    /// hears that speech.
+    tts_session: Option<Handle>,
 }
 
 /// Result of joining a call — all the broadcast receivers a participant needs
 pub struct CallJoinResult {
     pub handle: Handle,
     /// Per-sender audio (SFU): (sender_handle, sender_user_id, audio_frame)
-    pub audio_rx: broadcast::Receiver<(Handle, String, Vec<i16>)>,
+    pub audio_rx: broadcast::Receiver<(Handle, String, AudioFrame)>,
     pub transcription_rx: broadcast::Receiver<TranscriptionEvent>,
     pub video_rx: broadcast::Receiver<(Handle, String, Vec<u8>)>,
     pub message_rx: broadcast::Receiver<CallMessage>,
@@ -254,15 +437,30 @@ impl Call {
             transcription_tx,
             video_tx,
             message_tx,
+            epoch: Instant::now(),
             samples_processed: 0,
             hold_music_position: 0,
             hold_music_handle: Handle::new(),
             config,
             shutdown_tx: None,
             has_video: false,
+            recorder: None,
+            session_configs: HashMap::new(),
+            tts_session: None,
         }
     }
 
+    /// TTS (voice, speed) overrides for speech synthesized into this call.
+    /// None once another human has joined: the speech is no longer theirs alone.
+    pub fn tts_overrides(&self) -> (Option<String>, Option<f32>) {
+        self.tts_session
+            .filter(|_| self.mixer.human_count() <= 1)
+            .and_then(|handle| self.session_configs.get(&handle))
+            .map_or((None, None), |config| {
+                (config.tts_voice.clone(), config.tts_speed)
+            })
+    }
+
     /// Generate hold music from pre-decoded samples
     fn generate_hold_tone(&mut self, frame_size: usize) -> Vec<i16> {
         let samples = &*HOLD_MUSIC_SAMPLES;
@@ -296,15 +494,22 @@ impl Call {
     }
 
     /// Generate per-sender audio frames (SFU pattern, called by audio loop).
-    /// Returns (sender_handle, sender_user_id, audio_frame) for each active sender.
+    /// Returns (sender_handle, sender_user_id, audio_frame) for each active sender,
+    /// stamped with the tick's position on the call clock.
     /// Browser handles mixing — this enables per-participant audio/video synchronization.
-    pub fn tick(&mut self) -> Vec<(Handle, String, Vec<i16>)> {
+    pub fn tick(&mut self) -> Vec<(Handle, String, AudioFrame)> {
         let frame_size = self.config.frame_size;
+        let position = self.samples_processed;
         self.samples_processed += frame_size as u64;
 
         let is_alone = self.mixer.participant_count() == 1;
         let mut frames = self.mixer.pull_all_audio();
 
+        // Record before hold music is added — it's not part of the call
+        if let Some(recorder) = &self.recorder {
+            recorder.record(position, &frames);
+        }
+
         // If participant is alone and nobody is producing audio, inject hold music
         // as a synthetic sender so the lonely participant hears something
         if is_alone && frames.iter().all(|(_, _, audio)| is_silence(audio, 50.0)) {
@@ -315,7 +520,51 @@ impl Call {
             ));
         }
 
+        let meta = FrameMeta {
+            seq: position / frame_size as u64,
+            pts_ms: self.clock_ms(),
+        };
         frames
+            .into_iter()
+            .map(|(handle, user_id, samples)| (handle, user_id, AudioFrame { meta, samples }))
+            .collect()
+    }
+
+    /// Ms since the call started
+    pub fn clock_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    /// Start recording this call into directory `path`.
+    /// Mixed writes `mixed.wav`; MultiTrack writes one WAV per participant
+    /// (AI participants' TTS included). Both write a `timestamps.json` sidecar.
+    pub fn start_recording(
+        &mut self,
+        path: impl AsRef<Path>,
+        mode: RecordingMode,
+    ) -> Result<(), String> {
+        if self.recorder.is_some() {
+            return Err(format!("Call {} is already being recorded", self.id));
+        }
+        let recorder =
+            CallRecorder::start(path, mode, self.mixer.sample_rate(), self.samples_processed)?;
+        self.recorder = Some(recorder);
+        clog_info!("Recording started for call {} ({:?})", self.id, mode);
+        Ok(())
+    }
+
+    /// Stop recording, finalizing WAV headers and the timestamp sidecar.
+    /// Blocks while queued frames are flushed to disk.
+    pub fn stop_recording(&mut self) -> Result<RecordingSummary, String> {
+        let recorder = self
+            .recorder
+            .take()
+            .ok_or_else(|| format!("Call {} is not being recorded", self.id))?;
+        recorder.stop()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
     }
 
     /// Set shutdown sender (called by CallManager when starting audio loop)
@@ -331,6 +580,55 @@ impl Call {
     }
 }
 
+/// Running totals for the metrics endpoint, shared with the audio loops
+/// and forwarders
+#[derive(Default)]
+struct CallCounters {
+    /// Audio ticks mixed across all calls
+    frames_processed: AtomicU64,
+    /// Audio frames a lagging participant's forwarder skipped
+    dropped_frames: AtomicU64,
+    /// Finished utterances dropped because transcription was saturated
+    dropped_utterances: AtomicU64,
+    /// Times a sender's audio and video drifted apart (see `AvAligner`)
+    av_drift_events: AtomicU64,
+}
+
+/// Snapshot of CallManager activity, for the metrics endpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallMetrics {
+    pub active_calls: usize,
+    pub participants: usize,
+    /// Participants with a live WebSocket
+    pub connections: usize,
+    /// Participants disconnected but inside the reconnect grace window
+    pub pending_reconnects: usize,
+    pub frames_processed: u64,
+    pub dropped_frames: u64,
+    pub dropped_utterances: u64,
+    pub av_drift_events: u64,
+}
+
+/// Participant lifecycle, published on `CallManager::events()` under the
+/// participant's handle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CallEvent {
+    #[serde(rename_all = "camelCase")]
+    Joined {
+        call_id: String,
+        user_id: String,
+    },
+    /// Connection dropped; the participant stays for the reconnect grace window
+    Disconnected,
+    Resumed,
+    Muted {
+        muted: bool,
+    },
+    /// Last event for the handle; its channel closes after it
+    Left,
+}
+
 /// Call manager - tracks all active calls with server-driven audio loops
 pub struct CallManager {
     calls: RwLock<HashMap<String, Arc<RwLock<Call>>>>,
@@ -344,10 +642,27 @@ pub struct CallManager {
     audio_router: AudioRouter,
     /// Model capability registry for looking up what models can do
     capability_registry: Arc<ModelCapabilityRegistry>,
+    /// WebSocket attachment generation per participant. Bumped on every
+    /// (re)attach so a stale connection closing late can't evict a resumed one.
+    connection_generations: RwLock<HashMap<Handle, u64>>,
+    /// Pending removals for participants whose connection dropped
+    reconnect_timers: RwLock<HashMap<Handle, tokio::task::JoinHandle<()>>>,
+    /// Grace window before a disconnected participant is removed (0 = immediately)
+    reconnect_grace_secs: u64,
+    counters: Arc<CallCounters>,
+    events: EventBus<CallEvent>,
+    turns: TurnTracker,
 }
 
 impl CallManager {
     pub fn new() -> Self {
+        let events = EventBus::new_with_replay(CALL_EVENT_CAPACITY, CALL_EVENT_REPLAY_DEPTH);
+        if let Ok(path) = std::env::var(CALL_EVENT_JOURNAL_ENV) {
+            match FileEventSink::open(&path) {
+                Ok(sink) => events.add_sink(Box::new(sink)),
+                Err(e) => clog_warn!("Call event journal disabled: {}", e),
+            }
+        }
         Self {
             calls: RwLock::new(HashMap::new()),
             participant_calls: RwLock::new(HashMap::new()),
@@ -355,9 +670,31 @@ impl CallManager {
             video_source_shutdowns: RwLock::new(HashMap::new()),
             audio_router: AudioRouter::new(),
             capability_registry: Arc::new(ModelCapabilityRegistry::new()),
+            connection_generations: RwLock::new(HashMap::new()),
+            reconnect_timers: RwLock::new(HashMap::new()),
+            reconnect_grace_secs: DEFAULT_RECONNECT_GRACE_SECS,
+            counters: Arc::new(CallCounters::default()),
+            events,
+            turns: TurnTracker::new(),
         }
     }
 
+    /// Participant lifecycle events, keyed by participant handle
+    pub fn events(&self) -> &EventBus<CallEvent> {
+        &self.events
+    }
+
+    /// Voice turns, each under a child of the speaking participant's handle
+    pub fn turns(&self) -> &TurnTracker {
+        &self.turns
+    }
+
+    /// Set the reconnect grace window (0 disables resume)
+    pub fn with_reconnect_grace_secs(mut self, secs: u64) -> Self {
+        self.reconnect_grace_secs = secs;
+        self
+    }
+
     /// Get or create a call, starting audio loop if new
     async fn get_or_create_call(&self, call_id: &str) -> Arc<RwLock<Call>> {
         let mut calls = self.calls.write().await;
@@ -404,6 +741,7 @@ impl CallManager {
 
         let call_clone = call.clone();
         let call_id_clone = call_id.clone();
+        let counters = self.counters.clone();
 
         // Spawn the audio loop task
         let handle = tokio::spawn(async move {
@@ -431,6 +769,7 @@ impl CallManager {
 
                             // Pull per-sender audio frames (SFU pattern)
                             let frames = c.tick();
+                            counters.frames_processed.fetch_add(1, Ordering::Relaxed);
                             let audio_tx = c.audio_tx.clone();
 
                             (frames, audio_tx)
@@ -531,6 +870,13 @@ impl CallManager {
             handle.short(),
             call_id
         );
+        self.events.publish(
+            handle,
+            CallEvent::Joined {
+                call_id: call_id.to_string(),
+                user_id: user_id.to_string(),
+            },
+        );
         CallJoinResult {
             handle,
             audio_rx,
@@ -622,8 +968,100 @@ impl CallManager {
         }
     }
 
+    /// Record a WebSocket (re)attaching to a participant. Cancels any pending
+    /// reconnect removal and returns the new connection generation.
+    async fn attach(&self, handle: Handle) -> u64 {
+        if let Some(timer) = self.reconnect_timers.write().await.remove(&handle) {
+            timer.abort();
+        }
+        let mut generations = self.connection_generations.write().await;
+        let generation = generations.entry(handle).or_insert(0);
+        *generation += 1;
+        *generation
+    }
+
+    /// Re-attach to a participant whose connection dropped.
+    ///
+    /// The participant never left the mixer, so VAD and call state carry on.
+    /// Receivers are fresh subscriptions: audio broadcast during the gap is
+    /// skipped rather than replayed stale. Returns the join result and the
+    /// new connection generation, or None if the session is gone.
+    pub async fn resume(&self, handle: Handle) -> Option<(CallJoinResult, u64)> {
+        let call_id = {
+            let participant_calls = self.participant_calls.read().await;
+            participant_calls.get(&handle).cloned()
+        }?;
+        let call = {
+            let calls = self.calls.read().await;
+            calls.get(&call_id).cloned()
+        }?;
+
+        let join = {
+            let call = call.read().await;
+            CallJoinResult {
+                handle,
+                audio_rx: call.audio_tx.subscribe(),
+                transcription_rx: call.transcription_tx.subscribe(),
+                video_rx: call.video_tx.subscribe(),
+                message_rx: call.message_tx.subscribe(),
+            }
+        };
+        let generation = self.attach(handle).await;
+
+        clog_info!("Participant {} resumed call {}", handle.short(), call_id);
+        self.events.publish(handle, CallEvent::Resumed);
+        Some((join, generation))
+    }
+
+    /// A participant's connection dropped without a Leave. Keep them in the
+    /// call for the grace window; remove them if no Resume arrives. Ignored
+    /// if a newer connection has already resumed this participant.
+    async fn detach(self: &Arc<Self>, handle: Handle, generation: u64) {
+        if self.connection_generations.read().await.get(&handle) != Some(&generation) {
+            return;
+        }
+        if self.reconnect_grace_secs == 0 {
+            self.leave_call(&handle).await;
+            return;
+        }
+
+        let grace = self.reconnect_grace_secs;
+        clog_info!(
+            "Participant {} disconnected — holding session for {}s",
+            handle.short(),
+            grace
+        );
+        self.events.publish(handle, CallEvent::Disconnected);
+        let manager = Arc::clone(self);
+        let timer = tokio::spawn(async move {
+            tokio::time::sleep(tokio::time::Duration::from_secs(grace)).await;
+            let still_detached = {
+                let mut timers = manager.reconnect_timers.write().await;
+                timers.remove(&handle).is_some()
+            };
+            let current =
+                manager.connection_generations.read().await.get(&handle) == Some(&generation);
+            if still_detached && current {
+                clog_info!(
+                    "Participant {} did not resume — leaving call",
+                    handle.short()
+                );
+                manager.leave_call(&handle).await;
+            }
+        });
+        if let Some(previous) = self.reconnect_timers.write().await.insert(handle, timer) {
+            previous.abort();
+        }
+    }
+
     /// Leave a call
     pub async fn leave_call(&self, handle: &Handle) {
+        self.connection_generations.write().await.remove(handle);
+        // A grace timer removes itself before calling leave_call
+        if let Some(timer) = self.reconnect_timers.write().await.remove(handle) {
+            timer.abort();
+        }
+
         let call_id = {
             let mut participant_calls = self.participant_calls.write().await;
             participant_calls.remove(handle)
@@ -634,6 +1072,10 @@ impl CallManager {
                 let calls = self.calls.read().await;
                 if let Some(call) = calls.get(&call_id) {
                     let mut call = call.write().await;
+                    call.session_configs.remove(handle);
+                    if call.tts_session == Some(*handle) {
+                        call.tts_session = None;
+                    }
                     let user_id = if let Some(stream) = call.mixer.remove_participant(handle) {
                         clog_info!(
                             "Participant {} ({}) left call {}",
@@ -641,6 +1083,10 @@ impl CallManager {
                             handle.short(),
                             call_id
                         );
+                        // Clients and forwarders drop what they kept for it
+                        let _ = call.message_tx.send(CallMessage::ParticipantLeft {
+                            user_id: stream.user_id.clone(),
+                        });
                         Some(stream.user_id.clone())
                     } else {
                         None
@@ -657,6 +1103,9 @@ impl CallManager {
                 self.audio_router.remove_participant(&user_id).await;
             }
 
+            self.events.publish(*handle, CallEvent::Left);
+            self.events.remove_handle(handle);
+
             // Cleanup empty call
             if should_cleanup {
                 self.stop_audio_loop(&call_id).await;
@@ -682,6 +1131,7 @@ impl CallManager {
 
                 let mut calls = self.calls.write().await;
                 calls.remove(&call_id);
+                self.turns.end_call(&call_id);
                 clog_info!("Call {} cleaned up (no participants)", call_id);
             }
         }
@@ -698,18 +1148,24 @@ impl CallManager {
 
         if let Some(call_id) = call_id {
             // STEP 2: Push audio and check for speech end (minimized write lock)
-            let (result, transcription_tx) = {
+            let (result, transcription_tx, language) = {
                 let calls = self.calls.read().await;
                 if let Some(call) = calls.get(&call_id) {
                     // Only hold write lock for the actual push operation
                     let mut call = call.write().await;
                     let result = call.push_audio(handle, samples);
                     let transcription_tx = call.transcription_tx.clone();
+                    let language = call
+                        .session_configs
+                        .get(handle)
+                        .cloned()
+                        .unwrap_or_default()
+                        .transcription_language();
                     drop(call); // Explicitly release write lock early
 
-                    (Some(result), Some(transcription_tx))
+                    (Some(result), Some(transcription_tx), language)
                 } else {
-                    (None, None)
+                    (None, None, None)
                 }
             };
 
@@ -724,21 +1180,28 @@ impl CallManager {
                         let semaphore = TRANSCRIPTION_SEMAPHORE.clone();
                         match semaphore.clone().try_acquire_owned() {
                             Ok(permit) => {
+                                let turn = self.turns.begin(*handle);
+                                let turns = self.turns.clone();
                                 // Spawn transcription task with permit
                                 tokio::spawn(async move {
-                                    Self::transcribe_and_broadcast(
+                                    let transcribed = Self::transcribe_and_broadcast(
                                         transcription_tx,
                                         user_id,
                                         display_name,
                                         speech_samples,
+                                        language,
                                     )
                                     .await;
+                                    turns.transcribed(&call_id, turn, transcribed);
                                     // Permit automatically released when dropped
                                     drop(permit);
                                 });
                             }
                             Err(_) => {
                                 // Queue full - drop this audio to stay current
+                                self.counters
+                                    .dropped_utterances
+                                    .fetch_add(1, Ordering::Relaxed);
                                 clog_warn!(
                                     "🚨 Dropping audio from {} - transcription queue full ({} max)",
                                     display_name,
@@ -769,6 +1232,13 @@ impl CallManager {
                     .mixer
                     .find_user_id_by_handle(handle)
                     .unwrap_or_else(|| "unknown".to_string());
+                // Clients stamp frames on their own clock; restamp them on
+                // arrival so forwarders can line them up with the call's audio
+                let mut frame_data = frame_data;
+                if let Some(mut header) = VideoFrameHeader::decode(&frame_data) {
+                    header.timestamp_ms = call.clock_ms() as u32;
+                    frame_data[..VideoFrameHeader::WIRE_SIZE].copy_from_slice(&header.encode());
+                }
                 // Broadcast with sender handle + user_id — receivers filter out their own frames
                 if call.video_tx.send((*handle, user_id, frame_data)).is_err() {
                     // No receivers — this is fine, means nobody has video enabled
@@ -792,17 +1262,19 @@ impl CallManager {
         }
     }
 
-    /// Transcribe speech samples and broadcast to all participants
+    /// Transcribe speech samples and broadcast to all participants.
+    /// `language` None auto-detects. Returns whether a transcript went out.
     async fn transcribe_and_broadcast(
         transcription_tx: broadcast::Sender<TranscriptionEvent>,
         user_id: String,
         display_name: String,
         samples: Vec<i16>,
-    ) {
+        language: Option<String>,
+    ) -> bool {
         // Check if STT is initialized
         if !stt::is_initialized() {
             clog_warn!("STT adapter not initialized - skipping transcription");
-            return;
+            return false;
         }
 
         clog_info!(
@@ -819,7 +1291,7 @@ impl CallManager {
         let samples_16k = resample_to_16k(&f32_samples, AUDIO_SAMPLE_RATE);
 
         // Transcribe
-        match stt::transcribe(samples_16k, Some("en")).await {
+        match stt::transcribe(samples_16k, language.as_deref()).await {
             Ok(result) => {
                 let text = result.text.trim();
                 if !text.is_empty() {
@@ -852,13 +1324,17 @@ impl CallManager {
                         );
 
                         // Critical issue already logged via tracing::error above
+                        return false;
                     }
+                    true
                 } else {
                     clog_info!("📝 Empty transcription result from {}", display_name);
+                    false
                 }
             }
             Err(e) => {
                 clog_error!("Transcription failed for {}: {}", display_name, e);
+                false
             }
         }
     }
@@ -877,13 +1353,68 @@ impl CallManager {
                 if let Some(participant) = call.mixer.get_participant_mut(handle) {
                     participant.muted = muted;
                     clog_info!("Participant {} muted: {}", handle.short(), muted);
+                    self.events.publish(*handle, CallEvent::Muted { muted });
                 }
             }
         }
     }
 
+    /// Apply `ConfigureSession` overrides to a participant's session. The
+    /// VAD threshold takes effect on the next frame; the STT language on
+    /// the next utterance; TTS settings on the next speech synthesized
+    /// into the call. Everyone in the call hears that speech, so TTS
+    /// settings are rejected while another human is in it. Returns the
+    /// overrides now in effect.
+    pub async fn configure_session(
+        &self,
+        handle: &Handle,
+        update: SessionConfig,
+    ) -> Result<SessionConfig, String> {
+        let voices = if update.tts_voice.is_some() {
+            tts::available_voices()
+        } else {
+            Vec::new()
+        };
+        let call_id = {
+            let participant_calls = self.participant_calls.read().await;
+            participant_calls.get(handle).cloned()
+        }
+        .ok_or_else(|| "Join a call before configuring the session".to_string())?;
+        let calls = self.calls.read().await;
+        let call = calls
+            .get(&call_id)
+            .ok_or_else(|| format!("Call '{call_id}' not found"))?;
+        let mut call = call.write().await;
+
+        let sets_tts = update.tts_voice.is_some() || update.tts_speed.is_some();
+        if sets_tts && call.mixer.human_count() > 1 {
+            return Err(
+                "TTS voice and speed apply to everyone in the call; they can only be set when no other human is in it"
+                    .to_string(),
+            );
+        }
+        let mut config = call
+            .session_configs
+            .get(handle)
+            .cloned()
+            .unwrap_or_default();
+        config.merge(update, &voices)?;
+        if let Some(threshold) = config.vad_threshold {
+            if let Some(participant) = call.mixer.get_participant_mut(handle) {
+                participant.set_vad_threshold(threshold);
+            }
+        }
+        if sets_tts {
+            call.tts_session = Some(*handle);
+        }
+        call.session_configs.insert(*handle, config.clone());
+        clog_info!("Session {} configured: {:?}", handle.short(), config);
+        Ok(config)
+    }
+
     /// Synthesize text and inject directly into a call's mixer.
     /// Audio never leaves the Rust process — TypeScript only gets metadata back.
+    /// The call's `ConfigureSession` TTS overrides apply unless `voice` is given.
     /// Returns (num_samples, duration_ms, sample_rate) on success.
     pub async fn speak_in_call(
         &self,
@@ -896,7 +1427,7 @@ impl CallManager {
         use crate::live::audio::tts_service;
 
         // Step 1: Verify participant is in this call
-        let (handle, display_name) = {
+        let (handle, display_name, (voice_override, speed)) = {
             let calls = self.calls.read().await;
             let call = calls
                 .get(call_id)
@@ -911,13 +1442,23 @@ impl CallManager {
                 .get_participant(&handle)
                 .map(|p| p.display_name.clone())
                 .unwrap_or_else(|| user_id.to_string());
-            (handle, display_name)
+            (handle, display_name, call.tts_overrides())
         };
 
-        // Step 2: Synthesize (async — runs in current tokio context)
-        let synthesis = tts_service::synthesize_speech_async(text, voice, adapter, None)
-            .await
-            .map_err(|e| format!("TTS failed: {e}"))?;
+        // Step 2: Synthesize (async — runs in current tokio context). This
+        // answers the call's latest transcribed turn, if one is waiting.
+        let turn = self.turns.responding(call_id);
+        let voice = voice.or(voice_override.as_deref());
+        let prosody = Prosody {
+            speed: speed.unwrap_or(1.0),
+            ..Prosody::default()
+        };
+        let synthesis =
+            tts_service::synthesize_speech_prosody_async(text, voice, adapter, None, prosody)
+                .await
+                .map_err(|e| format!("TTS failed: {e}"));
+        self.turns.spoken(turn, synthesis.is_ok());
+        let synthesis = synthesis?;
 
         let num_samples = synthesis.samples.len();
         let duration_ms = synthesis.duration_ms;
@@ -1079,11 +1620,11 @@ impl CallManager {
     /// Accepts the Call Arc directly to avoid deadlocks when called from
     /// get_or_create_call() which already holds the calls write lock.
     fn start_test_video_source_for(call: &Arc<RwLock<Call>>, call_id: &str) -> mpsc::Sender<()> {
-        let video_tx = {
+        let (video_tx, epoch) = {
             let call_guard = call
                 .try_read()
                 .expect("Call should be available (just created)");
-            call_guard.video_tx.clone()
+            (call_guard.video_tx.clone(), call_guard.epoch)
         };
 
         let source_handle = Handle::new();
@@ -1091,7 +1632,7 @@ impl CallManager {
 
         clog_info!("Starting {} for call {}", source.name(), call_id);
 
-        source.start(video_tx, source_handle)
+        source.start(video_tx, source_handle, epoch)
     }
 
     /// Add a video source to a call. The source starts producing frames immediately.
@@ -1109,9 +1650,9 @@ impl CallManager {
                 .ok_or_else(|| format!("Call '{call_id}' not found"))?
         };
 
-        let video_tx = {
+        let (video_tx, epoch) = {
             let call = call.read().await;
-            call.video_tx.clone()
+            (call.video_tx.clone(), call.epoch)
         };
 
         let handle = Handle::new();
@@ -1125,7 +1666,7 @@ impl CallManager {
             call_id
         );
 
-        let shutdown_tx = source.start(video_tx, handle);
+        let shutdown_tx = source.start(video_tx, handle, epoch);
 
         {
             let mut shutdowns = self.video_source_shutdowns.write().await;
@@ -1150,6 +1691,70 @@ impl CallManager {
         }
     }
 
+    /// Start recording a call (see `Call::start_recording`)
+    pub async fn start_recording(
+        &self,
+        call_id: &str,
+        path: impl AsRef<Path>,
+        mode: RecordingMode,
+    ) -> Result<(), String> {
+        let calls = self.calls.read().await;
+        let call = calls
+            .get(call_id)
+            .ok_or_else(|| format!("Call {call_id} not found"))?;
+        let mut call = call.write().await;
+        call.start_recording(path, mode)
+    }
+
+    /// Stop a call's recording. The recorder is detached under the lock and
+    /// finalized on a blocking thread, so the audio loop never waits on disk.
+    /// A call that empties out while recording finalizes its files on drop.
+    pub async fn stop_recording(&self, call_id: &str) -> Result<RecordingSummary, String> {
+        let recorder = {
+            let calls = self.calls.read().await;
+            let call = calls
+                .get(call_id)
+                .ok_or_else(|| format!("Call {call_id} not found"))?;
+            let mut call = call.write().await;
+            call.recorder
+                .take()
+                .ok_or_else(|| format!("Call {call_id} is not being recorded"))?
+        };
+        let summary = tokio::task::spawn_blocking(move || recorder.stop())
+            .await
+            .map_err(|e| format!("Recording finalize task failed: {e}"))??;
+        clog_info!(
+            "Recording stopped for call {} ({} tracks)",
+            call_id,
+            summary.tracks.len()
+        );
+        Ok(summary)
+    }
+
+    /// Current activity across all calls
+    pub async fn metrics(&self) -> CallMetrics {
+        let (active_calls, participants) = {
+            let calls = self.calls.read().await;
+            let mut participants = 0;
+            for call in calls.values() {
+                participants += call.read().await.mixer.participant_count();
+            }
+            (calls.len(), participants)
+        };
+        let attached = self.connection_generations.read().await.len();
+        let pending_reconnects = self.reconnect_timers.read().await.len();
+        CallMetrics {
+            active_calls,
+            participants,
+            connections: attached.saturating_sub(pending_reconnects),
+            pending_reconnects,
+            frames_processed: self.counters.frames_processed.load(Ordering::Relaxed),
+            dropped_frames: self.counters.dropped_frames.load(Ordering::Relaxed),
+            dropped_utterances: self.counters.dropped_utterances.load(Ordering::Relaxed),
+            av_drift_events: self.counters.av_drift_events.load(Ordering::Relaxed),
+        }
+    }
+
     /// Get call stats
     pub async fn get_stats(&self, handle: &Handle) -> Option<(usize, u64)> {
         let call_id = {
@@ -1174,6 +1779,282 @@ impl Default for CallManager {
     }
 }
 
+/// A frame on its way to one connection
+enum Outgoing {
+    Audio {
+        sender: Handle,
+        user_id: String,
+        frame: AudioFrame,
+    },
+    Video {
+        sender: Handle,
+        user_id: String,
+        frame: VideoFrame,
+    },
+}
+
+/// One sender's streams as seen by a connection
+#[derive(Default)]
+struct SenderStreams {
+    audio: Option<Handle>,
+    video: Option<Handle>,
+    sync: Option<(SyncStage, broadcast::Receiver<StreamEvent>)>,
+}
+
+/// Lines up each sender's audio and video for one connection. Senders are
+/// keyed by user id, since an avatar's video comes from its own source
+/// handle. A sender with one stream passes straight through; once both are
+/// flowing, its frames go through a `SyncStage` and are released in pts
+/// order. Both streams are on the call clock (`Call::clock_ms`).
+#[derive(Default)]
+struct AvAligner {
+    senders: HashMap<String, SenderStreams>,
+}
+
+impl AvAligner {
+    /// Forget a sender that left, with any frames still held for it
+    fn remove(&mut self, user_id: &str) {
+        self.senders.remove(user_id);
+    }
+
+    /// Frames that can go out now, `frame` included unless it is held
+    fn push(&mut self, frame: Outgoing, counters: &CallCounters) -> Vec<Outgoing> {
+        let user_id = match &frame {
+            Outgoing::Audio { user_id, .. } | Outgoing::Video { user_id, .. } => user_id.clone(),
+        };
+        let streams = self.senders.entry(user_id.clone()).or_default();
+        match &frame {
+            Outgoing::Audio { sender, .. } => streams.audio = Some(*sender),
+            Outgoing::Video { sender, .. } => streams.video = Some(*sender),
+        }
+        let (Some(audio), Some(video)) = (streams.audio, streams.video) else {
+            return vec![frame];
+        };
+
+        let (stage, drift_events) = streams.sync.get_or_insert_with(|| {
+            let stage = SyncStage::new(SyncConfig::default());
+            let events = stage.subscribe();
+            (stage, events)
+        });
+        match frame {
+            Outgoing::Audio { frame, .. } => stage.push_audio(frame),
+            Outgoing::Video { frame, .. } => stage.push_video(frame),
+        }
+        while let Ok(event) = drift_events.try_recv() {
+            if let StreamEvent::SyncDrift { drift_ms } = event {
+                counters.av_drift_events.fetch_add(1, Ordering::Relaxed);
+                clog_warn!(
+                    "A/V drift of {}ms for '{}' (positive: audio ahead)",
+                    drift_ms,
+                    user_id
+                );
+            }
+        }
+
+        let drift_ms = stage.drift_ms();
+        let released = if drift_ms.unsigned_abs() > AV_STREAM_STOPPED_MS {
+            // The lagging stream stopped: release everything held and pass
+            // frames through until it starts again
+            let released = stage.flush();
+            streams.sync = None;
+            if drift_ms > 0 {
+                streams.video = None;
+            } else {
+                streams.audio = None;
+            }
+            released
+        } else {
+            stage.drain()
+        };
+        released
+            .into_iter()
+            .map(|frame| match frame {
+                SyncedFrame::Audio(frame) => Outgoing::Audio {
+                    sender: audio,
+                    user_id: user_id.clone(),
+                    frame,
+                },
+                SyncedFrame::Video(frame) => Outgoing::Video {
+                    sender: video,
+                    user_id: user_id.clone(),
+                    frame,
+                },
+            })
+            .collect()
+    }
+}
+
+/// Spawn the tasks that forward a participant's call broadcasts to its
+/// WebSocket. They exit when the connection's sender channel closes.
+fn spawn_forwarders(
+    join: CallJoinResult,
+    msg_tx: &mpsc::Sender<Message>,
+    format_rx: watch::Receiver<WireAudioFormat>,
+    label: String,
+    counters: Arc<CallCounters>,
+) {
+    let handle = join.handle;
+    let mut audio_rx = join.audio_rx;
+    let mut transcription_rx = join.transcription_rx;
+    let mut video_rx = join.video_rx;
+    let mut message_rx = join.message_rx;
+    let mut left_rx = message_rx.resubscribe();
+
+    // Audio and video forwarding: SFU per-sender, mix-minus (skip our own)
+    // Wire: [FrameKind][sender_id_len: u8][sender_id: UTF-8][payload]
+    // Audio samples are in the connection's negotiated format (PCM16 i16 LE
+    // by default); video is [VideoFrameHeader 16b][pixels]. The browser
+    // routes by senderId, and each sender's audio and video go out in pts
+    // order (see `AvAligner`).
+    // A slow connection that falls behind skips the audio frames it missed
+    // and carries on from the oldest one still buffered. Each sender's video
+    // starts at its next keyframe, so joining mid-GOP doesn't hand the
+    // client inter-frames it can't decode. Per-sender state is dropped when
+    // the sender leaves the call.
+    let msg_tx_av = msg_tx.clone();
+    tokio::spawn(async move {
+        let mut outbound = OutboundAudio::new(*format_rx.borrow());
+        let mut keyframe_gate = KeyframeGate::new();
+        let mut aligner = AvAligner::default();
+        let mut audio_senders: HashMap<String, Handle> = HashMap::new();
+        let mut video_open = true;
+        let mut left_open = true;
+        loop {
+            let ready = tokio::select! {
+                audio = audio_rx.recv() => match audio {
+                    Ok((sender, user_id, frame)) if sender != handle => {
+                        audio_senders.insert(user_id.clone(), sender);
+                        aligner.push(Outgoing::Audio { sender, user_id, frame }, &counters)
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        counters
+                            .dropped_frames
+                            .fetch_add(skipped, Ordering::Relaxed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                video = video_rx.recv(), if video_open => match video {
+                    Ok((sender, user_id, data))
+                        if sender != handle && keyframe_gate.admit(sender, &data) =>
+                    {
+                        match VideoFrame::from_bytes(&data) {
+                            Some(frame) => {
+                                aligner.push(Outgoing::Video { sender, user_id, frame }, &counters)
+                            }
+                            None => continue,
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => {
+                        video_open = false;
+                        continue;
+                    }
+                },
+                message = left_rx.recv(), if left_open => {
+                    match message {
+                        Ok(CallMessage::ParticipantLeft { user_id }) => {
+                            aligner.remove(&user_id);
+                            if let Some(sender) = audio_senders.remove(&user_id) {
+                                outbound.remove_sender(sender);
+                            }
+                        }
+                        Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => left_open = false,
+                    }
+                    continue;
+                },
+            };
+
+            for out in ready {
+                let (kind, user_id) = match &out {
+                    Outgoing::Audio { user_id, .. } => (FrameKind::Audio, user_id),
+                    Outgoing::Video { user_id, .. } => (FrameKind::Video, user_id),
+                };
+                let id_bytes = user_id.as_bytes();
+                let id_len = id_bytes.len().min(255) as u8;
+                let mut bytes = vec![kind as u8, id_len];
+                bytes.extend_from_slice(&id_bytes[..id_len as usize]);
+                let header_len = bytes.len();
+                match &out {
+                    Outgoing::Audio { sender, frame, .. } => {
+                        let format = *format_rx.borrow();
+                        if format != outbound.format() {
+                            outbound = OutboundAudio::new(format);
+                        }
+                        outbound.encode_into(*sender, &frame.samples, &mut bytes);
+                    }
+                    Outgoing::Video { frame, .. } => bytes.extend_from_slice(&frame.to_bytes()),
+                }
+                if bytes.len() == header_len {
+                    // Opus holds back a partial packet until the next frame
+                    continue;
+                }
+                if msg_tx_av.send(Message::Binary(bytes.into())).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    // Transcription forwarding (JSON text frames)
+    let msg_tx_transcription = msg_tx.clone();
+    tokio::spawn(async move {
+        while let Ok(event) = transcription_rx.recv().await {
+            clog_info!(
+                "[STEP 7] 🌐 WebSocket sending transcription to {}: \"{}\"",
+                label,
+                event
+                    .text
+                    .chars()
+                    .take(TEXT_PREVIEW_LENGTH)
+                    .collect::<String>()
+            );
+            let msg = CallMessage::Transcription {
+                user_id: event.user_id,
+                display_name: event.display_name,
+                text: event.text,
+                confidence: event.confidence,
+                language: event.language,
+            };
+            if let Ok(json) = serde_json::to_string(&msg) {
+                if msg_tx_transcription
+                    .send(Message::Text(json.into()))
+                    .await
+                    .is_err()
+                {
+                    clog_warn!("[STEP 7] ❌ WebSocket send FAILED for {}", label);
+                    break;
+                }
+            }
+        }
+    });
+
+    // General message forwarding (avatar updates, video config, etc.)
+    let msg_tx_messages = msg_tx.clone();
+    tokio::spawn(async move {
+        while let Ok(call_msg) = message_rx.recv().await {
+            if let Ok(json) = serde_json::to_string(&call_msg) {
+                if msg_tx_messages
+                    .send(Message::Text(json.into()))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Send a JSON control message to this connection's WebSocket
+async fn send_call_message(msg_tx: &mpsc::Sender<Message>, msg: &CallMessage) {
+    if let Ok(json) = serde_json::to_string(msg) {
+        let _ = msg_tx.send(Message::Text(json.into())).await;
+    }
+}
+
 /// Handle a single WebSocket connection
 async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<CallManager>) {
     let ws_stream = match accept_async(stream).await {
@@ -1188,8 +2069,13 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<Cal
 
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
     let mut participant_handle: Option<Handle> = None;
+    let mut connection_generation = 0;
     let mut is_muted = false; // Track mute state at connection level
 
+    // Client audio format; forwarders watch it for what they send back
+    let mut inbound = InboundAudio::default();
+    let (format_tx, format_rx) = watch::channel(inbound.format());
+
     // Channel for sending messages from audio receiver task
     let (msg_tx, mut msg_rx) = mpsc::channel::<Message>(64);
 
@@ -1213,89 +2099,44 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<Cal
                             Ok(CallMessage::Join { call_id, user_id, display_name, is_ai }) => {
                                 let join = manager.join_call(&call_id, &user_id, &display_name, is_ai).await;
                                 let handle = join.handle;
-                                let mut audio_rx = join.audio_rx;
-                                let mut transcription_rx = join.transcription_rx;
-                                let mut video_rx = join.video_rx;
-                                let mut message_rx = join.message_rx;
                                 participant_handle = Some(handle);
-
-                                // Audio forwarding: SFU per-sender with sender_id in wire format
-                                // Wire: [0x01 FrameKind::Audio][sender_id_len: u8][sender_id: UTF-8][PCM16 i16 LE]
-                                // Same pattern as video — browser routes by senderId for A/V sync
-                                let msg_tx_audio = msg_tx.clone();
-                                tokio::spawn(async move {
-                                    while let Ok((sender_handle, sender_user_id, audio)) = audio_rx.recv().await {
-                                        // Mix-minus: skip our own audio frames
-                                        if sender_handle != handle {
-                                            let id_bytes = sender_user_id.as_bytes();
-                                            let id_len = id_bytes.len().min(255) as u8;
-                                            let mut bytes = Vec::with_capacity(1 + 1 + id_len as usize + audio.len() * 2);
-                                            bytes.push(FrameKind::Audio as u8);
-                                            bytes.push(id_len);
-                                            bytes.extend_from_slice(&id_bytes[..id_len as usize]);
-                                            bytes.extend(audio.iter().flat_map(|&s| s.to_le_bytes()));
-                                            if msg_tx_audio.send(Message::Binary(bytes.into())).await.is_err() {
-                                                break;
-                                            }
-                                        }
-                                    }
-                                });
-
-                                // Transcription forwarding (JSON text frames)
-                                let msg_tx_transcription = msg_tx.clone();
-                                let ws_display_name = display_name.clone();
-                                tokio::spawn(async move {
-                                    while let Ok(event) = transcription_rx.recv().await {
-                                        clog_info!("[STEP 7] 🌐 WebSocket sending transcription to {}: \"{}\"",
-                                            ws_display_name, event.text.chars().take(TEXT_PREVIEW_LENGTH).collect::<String>());
-                                        let msg = CallMessage::Transcription {
-                                            user_id: event.user_id,
-                                            display_name: event.display_name,
-                                            text: event.text,
-                                            confidence: event.confidence,
-                                            language: event.language,
-                                        };
-                                        if let Ok(json) = serde_json::to_string(&msg) {
-                                            if msg_tx_transcription.send(Message::Text(json.into())).await.is_err() {
-                                                clog_warn!("[STEP 7] ❌ WebSocket send FAILED for {}", ws_display_name);
-                                                break;
-                                            }
-                                        }
-                                    }
-                                });
-
-                                // Video forwarding: mix-minus (see everyone but yourself)
-                                // Wire format: [0x02 FrameKind::Video][sender_id_len: u8][sender_id: UTF-8][VideoFrameHeader 16b][pixels]
-                                let msg_tx_video = msg_tx.clone();
-                                tokio::spawn(async move {
-                                    while let Ok((sender_handle, sender_user_id, video_data)) = video_rx.recv().await {
-                                        // Mix-minus: skip our own video frames
-                                        if sender_handle != handle {
-                                            let id_bytes = sender_user_id.as_bytes();
-                                            let id_len = id_bytes.len().min(255) as u8;
-                                            let mut frame = Vec::with_capacity(1 + 1 + id_len as usize + video_data.len());
-                                            frame.push(FrameKind::Video as u8);
-                                            frame.push(id_len);
-                                            frame.extend_from_slice(&id_bytes[..id_len as usize]);
-                                            frame.extend_from_slice(&video_data);
-                                            if msg_tx_video.send(Message::Binary(frame.into())).await.is_err() {
-                                                break;
-                                            }
-                                        }
+                                connection_generation = manager.attach(handle).await;
+                                spawn_forwarders(
+                                    join,
+                                    &msg_tx,
+                                    format_rx.clone(),
+                                    display_name,
+                                    manager.counters.clone(),
+                                );
+                                send_call_message(&msg_tx, &CallMessage::Joined { session_id: handle.to_string() }).await;
+                            }
+                            Ok(CallMessage::Resume { session_id }) => {
+                                if participant_handle.is_some() {
+                                    send_call_message(&msg_tx, &CallMessage::Error { message: "Already joined on this connection".to_string() }).await;
+                                    continue;
+                                }
+                                let resumed = match session_id.parse::<Handle>() {
+                                    Ok(handle) => manager.resume(handle).await,
+                                    Err(_) => None,
+                                };
+                                match resumed {
+                                    Some((join, generation)) => {
+                                        let handle = join.handle;
+                                        participant_handle = Some(handle);
+                                        connection_generation = generation;
+                                        spawn_forwarders(
+                                            join,
+                                            &msg_tx,
+                                            format_rx.clone(),
+                                            handle.short(),
+                                            manager.counters.clone(),
+                                        );
+                                        send_call_message(&msg_tx, &CallMessage::Resumed { session_id }).await;
                                     }
-                                });
-
-                                // General message forwarding (avatar updates, video config, etc.)
-                                let msg_tx_messages = msg_tx.clone();
-                                tokio::spawn(async move {
-                                    while let Ok(call_msg) = message_rx.recv().await {
-                                        if let Ok(json) = serde_json::to_string(&call_msg) {
-                                            if msg_tx_messages.send(Message::Text(json.into())).await.is_err() {
-                                                break;
-                                            }
-                                        }
+                                    None => {
+                                        send_call_message(&msg_tx, &CallMessage::Error { message: format!("Session {session_id} not found or expired") }).await;
                                     }
-                                });
+                                }
                             }
                             Ok(CallMessage::Leave) => {
                                 if let Some(handle) = participant_handle.take() {
@@ -1310,7 +2151,7 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<Cal
                                 }
                                 if let Some(handle) = &participant_handle {
                                     if let Some(samples) = base64_decode_i16(&data) {
-                                        manager.push_audio(handle, samples).await;
+                                        manager.push_audio(handle, inbound.resample(samples)).await;
                                     }
                                 }
                             }
@@ -1321,6 +2162,36 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<Cal
                                 }
                                 clog_info!("Connection mute state set: {}", muted);
                             }
+                            Ok(CallMessage::ConfigureSession { vad_threshold, tts_voice, tts_speed, stt_language }) => {
+                                let update = SessionConfig { vad_threshold, tts_voice, tts_speed, stt_language };
+                                let reply = match &participant_handle {
+                                    Some(handle) => match manager.configure_session(handle, update).await {
+                                        Ok(config) => config.to_message(),
+                                        Err(message) => CallMessage::Error { message },
+                                    },
+                                    None => CallMessage::Error { message: "Join a call before configuring the session".to_string() },
+                                };
+                                send_call_message(&msg_tx, &reply).await;
+                            }
+                            Ok(CallMessage::AudioCapabilities { encodings, sample_rates }) => {
+                                match negotiate(&encodings, &sample_rates) {
+                                    Ok(format) => {
+                                        clog_info!(
+                                            "🔊 Audio format for {}: {} @{}Hz",
+                                            addr, format.encoding.as_str(), format.sample_rate
+                                        );
+                                        inbound = InboundAudio::new(format);
+                                        format_tx.send_replace(format);
+                                        send_call_message(&msg_tx, &CallMessage::AudioFormat {
+                                            encoding: format.encoding.as_str().to_string(),
+                                            sample_rate: format.sample_rate,
+                                        }).await;
+                                    }
+                                    Err(message) => {
+                                        send_call_message(&msg_tx, &CallMessage::Error { message }).await;
+                                    }
+                                }
+                            }
                             Ok(CallMessage::VideoConfig { width, height, fps, format }) => {
                                 clog_info!(
                                     "📹 Video config from {}: {}x{} @{}fps format={}",
@@ -1355,9 +2226,14 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<Cal
                         if let Some(handle) = &participant_handle {
                             match FrameKind::from_byte(data[0]) {
                                 Some(FrameKind::Audio) => {
-                                    // [0x01][PCM16 i16 LE bytes]
-                                    let samples = bytes_to_i16(&data[1..]);
-                                    manager.push_audio(handle, samples).await;
+                                    // [0x01][samples in the negotiated format]
+                                    match inbound.decode(&data[1..]) {
+                                        Ok(samples) => manager.push_audio(handle, samples).await,
+                                        Err(message) => {
+                                            clog_warn!("Dropping audio from {}: {}", addr, message);
+                                            send_call_message(&msg_tx, &CallMessage::Error { message }).await;
+                                        }
+                                    }
                                 }
                                 Some(FrameKind::Video) => {
                                     // [0x02][VideoFrameHeader 16 bytes][pixel data]
@@ -1369,8 +2245,13 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<Cal
                                 }
                                 None => {
                                     // Legacy: no FrameKind prefix, treat entire payload as raw audio
-                                    let samples = bytes_to_i16(&data);
-                                    manager.push_audio(handle, samples).await;
+                                    match inbound.decode(&data) {
+                                        Ok(samples) => manager.push_audio(handle, samples).await,
+                                        Err(message) => {
+                                            clog_warn!("Dropping audio from {}: {}", addr, message);
+                                            send_call_message(&msg_tx, &CallMessage::Error { message }).await;
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -1390,9 +2271,10 @@ async fn handle_connection(stream: TcpStream, addr: SocketAddr, manager: Arc<Cal
         }
     }
 
-    // Cleanup
+    // Cleanup: an unexpected drop holds the session open for Resume
+    // (explicit Leave already removed the participant above)
     if let Some(handle) = participant_handle {
-        manager.leave_call(&handle).await;
+        manager.detach(handle, connection_generation).await;
     }
 
     clog_info!("WebSocket connection closed for {}", addr);
@@ -1420,6 +2302,7 @@ pub async fn start_call_server(
 mod tests {
     use super::*;
     use crate::audio_constants::{AUDIO_FRAME_SIZE, AUDIO_SAMPLE_RATE};
+    use crate::concurrent::SubscriptionEvent;
     use crate::live::audio::mixer::test_utils::*;
     use crate::utils::audio::base64_encode_i16;
 
@@ -1479,9 +2362,90 @@ mod tests {
         let stats = manager.get_stats(&join_a.handle).await;
         assert!(stats.unwrap().1 > 0);
 
+        let metrics = manager.metrics().await;
+        assert_eq!(metrics.active_calls, 1);
+        assert_eq!(metrics.participants, 2);
+        assert_eq!(metrics.connections, 0);
+        assert!(metrics.frames_processed > 0);
+
         // Leave
         manager.leave_call(&join_a.handle).await;
         manager.leave_call(&join_b.handle).await;
+        assert_eq!(manager.metrics().await.participants, 0);
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_resume() {
+        let manager = Arc::new(CallManager::new().with_reconnect_grace_secs(0));
+
+        let join = manager
+            .join_call("test-call", "user-1", "Alice", false)
+            .await;
+        let first = manager.attach(join.handle).await;
+        // Subscribed after the join, which is replayed
+        let mut events = manager.events().subscribe_handle(join.handle, None);
+
+        // A new connection resumes the session; the old one's late detach is ignored
+        let (resumed, second) = manager.resume(join.handle).await.unwrap();
+        assert_eq!(resumed.handle, join.handle);
+        assert!(second > first);
+        manager.detach(join.handle, first).await;
+        assert_eq!(manager.get_stats(&join.handle).await.unwrap().0, 1);
+
+        // With no grace window the current connection's drop leaves immediately
+        manager.detach(join.handle, second).await;
+        assert!(manager.get_stats(&join.handle).await.is_none());
+        assert!(manager.resume(join.handle).await.is_none());
+
+        let mut received = Vec::new();
+        while let Some(SubscriptionEvent::Event(event)) = events.recv().await {
+            received.push(event);
+        }
+        assert_eq!(
+            received,
+            vec![
+                CallEvent::Joined {
+                    call_id: "test-call".to_string(),
+                    user_id: "user-1".to_string()
+                },
+                CallEvent::Resumed,
+                CallEvent::Left,
+            ]
+        );
+        assert_eq!(manager.events().handle_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_call_recording() {
+        let manager = CallManager::new();
+        let dir = tempfile::tempdir().unwrap();
+
+        let join = manager
+            .join_call("test-call", "user-1", "Alice", false)
+            .await;
+        assert!(manager.stop_recording("test-call").await.is_err());
+        manager
+            .start_recording("test-call", dir.path(), RecordingMode::MultiTrack)
+            .await
+            .unwrap();
+        assert!(manager
+            .start_recording("test-call", dir.path(), RecordingMode::Mixed)
+            .await
+            .is_err());
+
+        let audio = generate_sine_wave(440.0, AUDIO_SAMPLE_RATE, AUDIO_FRAME_SIZE);
+        manager.push_audio(&join.handle, audio).await;
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+
+        // Hold music (Alice is alone) is not recorded
+        let summary = manager.stop_recording("test-call").await.unwrap();
+        assert!(summary
+            .tracks
+            .iter()
+            .all(|t| t.user_id.as_deref() == Some("user-1")));
+        assert!(dir.path().join("timestamps.json").exists());
+
+        manager.leave_call(&join.handle).await;
     }
 
     #[tokio::test]
@@ -1501,6 +2465,100 @@ mod tests {
         manager.leave_call(&join.handle).await;
     }
 
+    #[tokio::test]
+    async fn test_configure_session() {
+        let manager = CallManager::new();
+        let join = manager
+            .join_call("test-call", "user-1", "Alice", false)
+            .await;
+
+        let config = manager
+            .configure_session(
+                &join.handle,
+                SessionConfig {
+                    vad_threshold: Some(0.7),
+                    tts_speed: Some(1.25),
+                    stt_language: Some(" DE ".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(config.stt_language.as_deref(), Some("de"));
+        assert_eq!(config.transcription_language().as_deref(), Some("de"));
+        let call = manager.calls.read().await["test-call"].clone();
+        let vad_threshold = |call: &Call| {
+            call.mixer
+                .get_participant(&join.handle)
+                .and_then(ParticipantStream::vad_threshold)
+        };
+        assert_eq!(vad_threshold(&*call.read().await), Some(0.7));
+
+        // One bad field rejects the whole update; later updates merge
+        let bad = SessionConfig {
+            tts_voice: Some("af_bella".to_string()),
+            tts_speed: Some(5.0),
+            ..Default::default()
+        };
+        assert!(manager.configure_session(&join.handle, bad).await.is_err());
+        let voice = SessionConfig {
+            tts_voice: Some("af_bella".to_string()),
+            stt_language: Some("auto".to_string()),
+            ..Default::default()
+        };
+        let config = manager
+            .configure_session(&join.handle, voice)
+            .await
+            .unwrap();
+        assert_eq!(config.vad_threshold, Some(0.7));
+        assert_eq!(config.tts_speed, Some(1.25));
+        assert_eq!(config.transcription_language(), None);
+        assert_eq!(
+            SessionConfig::default().transcription_language().as_deref(),
+            Some("en")
+        );
+
+        // Voices are checked against the adapter's list
+        let voices = [VoiceInfo {
+            id: "af_bella".to_string(),
+            name: "Bella".to_string(),
+            language: "en".to_string(),
+            gender: None,
+            description: None,
+        }];
+        let typo = SessionConfig {
+            tts_voice: Some("af_bela".to_string()),
+            ..Default::default()
+        };
+        assert!(SessionConfig::default()
+            .merge(typo.clone(), &voices)
+            .is_err());
+        assert!(SessionConfig::default().merge(typo, &[]).is_ok());
+
+        // With a second human, TTS overrides would change what they hear:
+        // they are rejected, and the ones already set stop applying
+        let (voice, speed) = call.read().await.tts_overrides();
+        assert_eq!((voice.as_deref(), speed), (Some("af_bella"), Some(1.25)));
+        let other = manager.join_call("test-call", "user-2", "Bob", false).await;
+        let speed = SessionConfig {
+            tts_speed: Some(1.5),
+            ..Default::default()
+        };
+        assert!(manager
+            .configure_session(&join.handle, speed.clone())
+            .await
+            .is_err());
+        assert_eq!(call.read().await.tts_overrides(), (None, None));
+        manager.leave_call(&other.handle).await;
+        assert!(manager.configure_session(&join.handle, speed).await.is_ok());
+
+        manager.leave_call(&join.handle).await;
+        assert!(manager
+            .configure_session(&join.handle, SessionConfig::default())
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_video_push_broadcast() {
         let manager = CallManager::new();
@@ -1524,7 +2582,9 @@ mod tests {
             Ok((sender, user_id, data)) => {
                 assert_eq!(sender, join_a.handle);
                 assert_eq!(user_id, "user-a");
-                assert_eq!(data, fake_frame);
+                // Restamped on the call clock; everything else is untouched
+                assert_eq!(data[..6], fake_frame[..6]);
+                assert_eq!(data[10..], fake_frame[10..]);
             }
             Err(_) => {
                 // Broadcast delivery is async, this is acceptable in tests
@@ -1534,4 +2594,160 @@ mod tests {
         manager.leave_call(&join_a.handle).await;
         manager.leave_call(&join_b.handle).await;
     }
+
+    #[test]
+    fn test_av_aligner_holds_audio_for_video() {
+        use crate::live::types::VideoPixelFormat;
+
+        let (audio_handle, video_handle) = (Handle::new(), Handle::new());
+        let audio = |pts_ms: u64| Outgoing::Audio {
+            sender: audio_handle,
+            user_id: "persona".into(),
+            frame: AudioFrame {
+                meta: FrameMeta {
+                    seq: pts_ms / 20,
+                    pts_ms,
+                },
+                samples: vec![0; 320],
+            },
+        };
+        let video = |pts_ms: u32| Outgoing::Video {
+            sender: video_handle,
+            user_id: "persona".into(),
+            frame: VideoFrame {
+                header: VideoFrameHeader {
+                    width: 2,
+                    height: 2,
+                    pixel_format: VideoPixelFormat::RGBA8,
+                    timestamp_ms: pts_ms,
+                    sequence: pts_ms / 33,
+                    keyframe: true,
+                },
+                data: vec![0; 16],
+            },
+        };
+        let pts = |frames: Vec<Outgoing>| -> Vec<(char, u64)> {
+            frames
+                .iter()
+                .map(|out| match out {
+                    Outgoing::Audio { frame, .. } => ('a', frame.meta.pts_ms),
+                    Outgoing::Video { frame, .. } => ('v', frame.header.timestamp_ms as u64),
+                })
+                .collect()
+        };
+        let counters = CallCounters::default();
+        let mut aligner = AvAligner::default();
+
+        // Audio alone passes straight through
+        assert_eq!(pts(aligner.push(audio(0), &counters)), vec![('a', 0)]);
+
+        // Once both flow, each stream waits for the other to reach its pts
+        assert!(aligner.push(video(0), &counters).is_empty());
+        assert_eq!(pts(aligner.push(audio(20), &counters)), vec![('v', 0)]);
+        assert_eq!(pts(aligner.push(video(33), &counters)), vec![('a', 20)]);
+        assert_eq!(pts(aligner.push(audio(40), &counters)), vec![('v', 33)]);
+
+        // Video stopping releases the held audio and passes audio through again
+        let released = pts(aligner.push(audio(2000), &counters));
+        assert_eq!(released, vec![('a', 40), ('a', 2000)]);
+        assert_eq!(counters.av_drift_events.load(Ordering::Relaxed), 1);
+        assert_eq!(pts(aligner.push(audio(2020), &counters)), vec![('a', 2020)]);
+    }
+
+    #[tokio::test]
+    async fn test_audio_format_exchange() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let manager = Arc::new(CallManager::new());
+        let server_manager = manager.clone();
+        tokio::spawn(async move {
+            let (stream, peer) = listener.accept().await.unwrap();
+            handle_connection(stream, peer, server_manager).await;
+        });
+        let tcp = TcpStream::connect(addr).await.unwrap();
+        let (mut ws, _) = tokio_tungstenite::client_async(format!("ws://{addr}/"), tcp)
+            .await
+            .unwrap();
+
+        async fn send(ws: &mut (impl SinkExt<Message> + Unpin), msg: &CallMessage) {
+            let json = serde_json::to_string(msg).unwrap();
+            let _ = ws.send(Message::Text(json.into())).await;
+        }
+        // Next control message, skipping any audio or video frames
+        async fn next_message<S>(ws: &mut S) -> CallMessage
+        where
+            S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+        {
+            loop {
+                let msg = tokio::time::timeout(tokio::time::Duration::from_secs(5), ws.next())
+                    .await
+                    .expect("timed out waiting for a control message")
+                    .unwrap()
+                    .unwrap();
+                if let Message::Text(text) = msg {
+                    return serde_json::from_str(&text).unwrap();
+                }
+            }
+        }
+
+        // A browser at 48kHz float32 gets exactly that echoed back
+        send(
+            &mut ws,
+            &CallMessage::AudioCapabilities {
+                encodings: vec!["float32".into()],
+                sample_rates: vec![48_000],
+            },
+        )
+        .await;
+        match next_message(&mut ws).await {
+            CallMessage::AudioFormat {
+                encoding,
+                sample_rate,
+            } => {
+                assert_eq!(encoding, "float32");
+                assert_eq!(sample_rate, 48_000);
+            }
+            other => panic!("expected AudioFormat, got {other:?}"),
+        }
+
+        // Nothing usable is an error, and the negotiated format stays
+        send(
+            &mut ws,
+            &CallMessage::AudioCapabilities {
+                encodings: vec!["mp3".into()],
+                sample_rates: vec![48_000],
+            },
+        )
+        .await;
+        assert!(matches!(
+            next_message(&mut ws).await,
+            CallMessage::Error { .. }
+        ));
+
+        send(
+            &mut ws,
+            &CallMessage::Join {
+                call_id: "format-call".into(),
+                user_id: "user-1".into(),
+                display_name: "Alice".into(),
+                is_ai: false,
+            },
+        )
+        .await;
+        assert!(matches!(
+            next_message(&mut ws).await,
+            CallMessage::Joined { .. }
+        ));
+
+        // Half a float32 sample in the negotiated format is reported
+        let _ = ws
+            .send(Message::Binary(vec![FrameKind::Audio as u8, 0, 0].into()))
+            .await;
+        match next_message(&mut ws).await {
+            CallMessage::Error { message } => assert!(message.contains("float32"), "{message}"),
+            other => panic!("expected Error, got {other:?}"),
+        }
+
+        send(&mut ws, &CallMessage::Leave).await;
+    }
 }
@@ -17,6 +17,14 @@
 use crate::audio_constants::{
     AUDIO_SAMPLE_RATE, LIVEKIT_DEV_KEY, LIVEKIT_DEV_SECRET, LIVEKIT_PORT,
 };
+use crate::live::audio::aec::{AecConfig, AecReference};
+use crate::live::audio::noise_suppress::NoiseSuppressStage;
+use crate::live::audio::stage_chain::{
+    StageChain, StallConfig, StreamEvent, VOICE_STALL_TIMEOUT_MS,
+};
+use crate::live::audio::stt::{estimate_affect, SpeechToText, SttTask};
+use crate::live::transport::turn::TurnTracker;
+use crate::live::types::FrameSequencer;
 use crate::secrets::get_secret;
 
 use livekit::options::{TrackPublishOptions, VideoEncoding};
@@ -33,6 +41,7 @@ use crate::{clog_error, clog_info, clog_warn};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
 
@@ -135,13 +144,47 @@ pub struct TranscriptionEntry {
     pub speaker_id: String,
     pub speaker_name: String,
     pub text: String,
+    /// Spoken-language text when the listener translates (`SttTask::Translate`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_text: Option<String>,
     pub timestamp_ms: u64,
 }
 
+/// How a call's STT listener transcribes.
+#[derive(Clone, Default)]
+pub struct SttListenerConfig {
+    /// Transcribe, or translate to English
+    pub task: SttTask,
+    /// Backend resolved from the STT registry when the listener joins.
+    /// `None` follows the registry's active adapter.
+    pub adapter: Option<Arc<dyn SpeechToText>>,
+    /// Attach a prosody-based arousal/valence estimate to each transcript
+    /// for AI routing (extra per-utterance compute)
+    pub estimate_affect: bool,
+    /// Trailing non-speech that ends an utterance and sends it to STT.
+    /// `None` keeps the VAD's default (256ms).
+    pub endpoint_silence_ms: Option<u32>,
+    /// Hands-free (speakerphone) callers: cancel the personas' TTS echo
+    /// from each speaker's audio before VAD
+    pub echo_cancellation: Option<AecConfig>,
+    /// Denoise each speaker's audio before VAD, at this aggressiveness (0-3)
+    pub noise_suppression: Option<u8>,
+}
+
 /// Shared buffer for storing transcriptions from STT listeners.
 /// Tests poll this via `voice/poll-transcriptions`.
 pub type TranscriptionBuffer = Arc<Mutex<VecDeque<TranscriptionEntry>>>;
 
+/// Input stage chain per transcribed speaker, keyed by (call_id, speaker_id).
+/// Each speaker's audio runs through its chain before VAD; stages can be
+/// inserted or removed while the call is live.
+pub type InputChains = Arc<Mutex<HashMap<(String, String), Arc<StageChain>>>>;
+
+/// Echo canceller reference of each hands-free speaker, keyed like
+/// `InputChains`. Agents push their TTS into every reference for their call
+/// as it is played, so only one persona should speak at a time.
+pub type EchoReferences = Arc<Mutex<HashMap<(String, String), AecReference>>>;
+
 const MAX_TRANSCRIPTION_BUFFER: usize = 100;
 
 /// Audio samples per 10ms at 16kHz — LiveKit processes in 10ms chunks
@@ -195,6 +238,9 @@ pub struct LiveKitAgent {
     identity: String,
     /// Display name (persona name) for transcription attribution
     display_name: String,
+    call_id: String,
+    /// Speakers' echo cancellers, fed with everything `speak` plays
+    echo_references: EchoReferences,
     /// TTS voice name — set on first speak, used for gender-matched avatar selection.
     /// First-speak-wins: once set, the avatar doesn't change mid-call.
     voice_name: std::sync::Mutex<Option<String>>,
@@ -222,6 +268,7 @@ impl LiveKitAgent {
         call_id: &str,
         persona_id: &str,
         persona_name: &str,
+        echo_references: EchoReferences,
     ) -> Result<(Self, mpsc::UnboundedReceiver<AgentEvent>), String> {
         // Generate access token with metadata for role classification
         let metadata = ParticipantMetadata::new(ParticipantRole::AiPersona);
@@ -471,6 +518,8 @@ impl LiveKitAgent {
             _event_tx: event_tx,
             identity,
             display_name: persona_name.to_string(),
+            call_id: call_id.to_string(),
+            echo_references,
             voice_name: std::sync::Mutex::new(None),
             resolution_rx,
             shutdown_tx,
@@ -482,8 +531,18 @@ impl LiveKitAgent {
     /// Feed TTS-synthesized PCM audio to the LiveKit room.
     /// Accepts our standard format: Vec<i16> at 16kHz mono.
     /// Splits into 10ms chunks for LiveKit's AudioFrame.
+    /// Each chunk is also pushed to the call's echo canceller references as
+    /// it is handed to LiveKit.
     pub async fn speak(&self, samples: Vec<i16>) -> Result<(), String> {
         let chunk_size = SAMPLES_PER_10MS as usize;
+        let references: Vec<AecReference> = self
+            .echo_references
+            .lock()
+            .await
+            .iter()
+            .filter(|((call_id, _), _)| *call_id == self.call_id)
+            .map(|(_, reference)| reference.clone())
+            .collect();
 
         for chunk in samples.chunks(chunk_size) {
             let frame = AudioFrame {
@@ -497,6 +556,9 @@ impl LiveKitAgent {
                 .capture_frame(&frame)
                 .await
                 .map_err(|e| format!("Failed to capture audio frame: {}", e))?;
+            for reference in &references {
+                reference.push(chunk);
+            }
         }
 
         Ok(())
@@ -1072,7 +1134,12 @@ fn start_video_loop(agent: Arc<LiveKitAgent>) {
 async fn spawn_stt_listener(
     livekit_url: &str,
     call_id: &str,
+    stt_config: SttListenerConfig,
     transcription_buffer: TranscriptionBuffer,
+    input_chains: InputChains,
+    echo_references: EchoReferences,
+    dropped_utterances: Arc<AtomicU64>,
+    turns: TurnTracker,
 ) -> Result<Arc<Room>, String> {
     let listener_id = format!(
         "{}{}",
@@ -1148,6 +1215,10 @@ async fn spawn_stt_listener(
                             let room_ref = room_for_events.clone();
                             let cid = call_id_owned.clone();
                             let tbuf = transcription_buffer.clone();
+                            let chains = input_chains.clone();
+                            let references = echo_references.clone();
+                            let dropped = dropped_utterances.clone();
+                            let speaker_turns = turns.clone();
                             tokio::spawn(async move {
                                 listen_and_transcribe(
                                     audio_track,
@@ -1156,7 +1227,12 @@ async fn spawn_stt_listener(
                                     track_sid,
                                     room_ref,
                                     cid,
+                                    stt_config.clone(),
                                     tbuf,
+                                    chains,
+                                    references,
+                                    dropped,
+                                    speaker_turns,
                                 )
                                 .await;
                             });
@@ -1197,11 +1273,19 @@ async fn spawn_stt_listener(
     Ok(room)
 }
 
-/// Process a single audio track: VAD → STT → publish transcription → notify AI.
+/// Process a single audio track: input stages → VAD → STT → publish
+/// transcription → notify AI.
 ///
 /// Runs in its own tokio task. One instance per human participant per call.
+/// The speaker's input `StageChain` is registered in `input_chains` for as
+/// long as the track is being transcribed, and with echo cancellation its
+/// `AecReference` in `echo_references`.
 /// `track_sid` is the remote audio track's SID — used for native transcription
 /// sync so subtitles align with audio playback in the browser.
+/// `stt_config` picks the backend and whether speech in any language is
+/// published as English (`SttTask::Translate`).
+/// Each utterance starts a turn in `turns` under the speaker's root handle.
+#[allow(clippy::too_many_arguments)]
 async fn listen_and_transcribe(
     audio_track: RemoteAudioTrack,
     speaker_id: String,
@@ -1209,10 +1293,15 @@ async fn listen_and_transcribe(
     track_sid: String,
     room: Arc<Room>,
     call_id: String,
+    stt_config: SttListenerConfig,
     transcription_buffer: TranscriptionBuffer,
+    input_chains: InputChains,
+    echo_references: EchoReferences,
+    dropped_utterances: Arc<AtomicU64>,
+    turns: TurnTracker,
 ) {
     use crate::live::audio::stt_service;
-    use crate::live::audio::vad::ProductionVAD;
+    use crate::live::audio::vad::{ProductionVAD, ProductionVADConfig};
     use livekit::webrtc::audio_stream::native::NativeAudioStream;
     use tokio_stream::StreamExt;
 
@@ -1222,8 +1311,13 @@ async fn listen_and_transcribe(
         1, // mono
     );
 
-    // Initialize ProductionVAD — two-stage (WebRTC fast filter → Silero confirmation)
-    let mut vad = ProductionVAD::new();
+    // Initialize ProductionVAD — two-stage (WebRTC fast filter → Silero confirmation).
+    // An utterance is finalized as soon as speech is followed by the endpoint silence.
+    let mut vad_config = ProductionVADConfig::default();
+    if let Some(endpoint_silence_ms) = stt_config.endpoint_silence_ms {
+        vad_config = vad_config.with_endpoint_silence_ms(endpoint_silence_ms);
+    }
+    let mut vad = ProductionVAD::with_config(vad_config);
     if let Err(e) = vad.initialize() {
         clog_error!("🎤 STT: Failed to init VAD for '{}': {}", speaker_name, e);
         return;
@@ -1231,6 +1325,56 @@ async fn listen_and_transcribe(
 
     clog_info!("🎤 STT: VAD initialized, listening to '{}'", speaker_name);
 
+    // Hands-free: echo cancellation first, fed with the personas' TTS, then
+    // noise suppression when configured. Stages can be added mid-call too.
+    let chain_key = (call_id.clone(), speaker_id.clone());
+    let chain = match stt_config.echo_cancellation {
+        Some(aec) => {
+            let (chain, reference) = StageChain::hands_free(aec);
+            echo_references
+                .lock()
+                .await
+                .insert(chain_key.clone(), reference);
+            Arc::new(chain)
+        }
+        None => Arc::new(StageChain::new()),
+    };
+    if let Some(level) = stt_config.noise_suppression {
+        chain.push_stage(Box::new(NoiseSuppressStage::new(level)));
+    }
+    input_chains
+        .lock()
+        .await
+        .insert(chain_key.clone(), chain.clone());
+
+    // Report a stage that deadlocks mid-frame. Frames simply stopping (the
+    // speaker muted or left) isn't a stall worth logging.
+    let mut stalls = chain.subscribe();
+    chain.spawn_watchdog(StallConfig::new(VOICE_STALL_TIMEOUT_MS, false));
+    let stall_speaker = speaker_name.clone();
+    tokio::spawn(async move {
+        use tokio::sync::broadcast::error::RecvError;
+        loop {
+            match stalls.recv().await {
+                Ok(StreamEvent::Stalled {
+                    stage_index: Some(stage),
+                    idle_ms,
+                }) => {
+                    clog_warn!(
+                        "🎤 STT: input stage {} for '{}' stalled for {}ms",
+                        stage,
+                        stall_speaker,
+                        idle_ms
+                    );
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    let speaker_handle = turns.speaker(&call_id, &speaker_id);
+
     // Transcription semaphore: max 2 concurrent STT operations per speaker
     let semaphore = Arc::new(tokio::sync::Semaphore::new(2));
     let mut frame_count: u64 = 0;
@@ -1242,8 +1386,13 @@ async fn listen_and_transcribe(
     const VAD_FRAME_SIZE: usize = crate::audio_constants::AUDIO_FRAME_SIZE;
     let mut accum_buf: Vec<i16> = Vec::with_capacity(VAD_FRAME_SIZE);
 
+    // LiveKit frames carry no timing of their own; stamp them in arrival order
+    let mut sequencer = FrameSequencer::new();
+
     while let Some(frame) = audio_stream.next().await {
-        let samples: &[i16] = frame.data.as_ref();
+        let samples = chain
+            .process_frame(&sequencer.audio(frame.data.to_vec()))
+            .samples;
         frame_count += 1;
 
         // Log first frame + every 3000th frame
@@ -1260,7 +1409,7 @@ async fn listen_and_transcribe(
         }
 
         // Accumulate until we have a full VAD frame
-        accum_buf.extend_from_slice(samples);
+        accum_buf.extend_from_slice(&samples);
         if accum_buf.len() < VAD_FRAME_SIZE {
             continue;
         }
@@ -1275,6 +1424,7 @@ async fn listen_and_transcribe(
                     let permit = match semaphore.clone().try_acquire_owned() {
                         Ok(permit) => permit,
                         Err(_) => {
+                            dropped_utterances.fetch_add(1, Ordering::Relaxed);
                             clog_warn!(
                                 "🎤 STT: Dropping utterance from '{}' — transcription queue full",
                                 speaker_name
@@ -1289,15 +1439,52 @@ async fn listen_and_transcribe(
                     let room_ref = room.clone();
                     let cid = call_id.clone();
                     let tbuf = transcription_buffer.clone();
+                    let stt = stt_config.clone();
+                    let turns = turns.clone();
+                    let turn = turns.begin(speaker_handle);
 
                     tokio::spawn(async move {
                         let _permit = permit; // Hold until done
 
-                        match stt_service::transcribe_speech_async(&sentence_samples, Some("en"))
-                            .await
-                        {
-                            Ok(transcript) => {
+                        // Translation needs the spoken language detected, not forced to English
+                        let language = match stt.task {
+                            SttTask::Transcribe => Some("en"),
+                            SttTask::Translate { .. } => None,
+                        };
+                        let transcribed = match &stt.adapter {
+                            Some(adapter) => {
+                                stt_service::transcribe_speech_with_async(
+                                    adapter.as_ref(),
+                                    &sentence_samples,
+                                    language,
+                                    stt.task,
+                                )
+                                .await
+                            }
+                            None => {
+                                stt_service::transcribe_speech_task_async(
+                                    &sentence_samples,
+                                    language,
+                                    stt.task,
+                                )
+                                .await
+                            }
+                        };
+                        match transcribed {
+                            Ok(mut transcript) => {
+                                if stt.estimate_affect {
+                                    // A pitch track over the utterance: keep it off the runtime
+                                    transcript.affect = tokio::task::spawn_blocking(move || {
+                                        estimate_affect(&sentence_samples)
+                                    })
+                                    .await
+                                    .unwrap_or_else(|e| {
+                                        clog_warn!("🎤 STT: Affect estimate failed: {}", e);
+                                        None
+                                    });
+                                }
                                 let text = transcript.text.trim();
+                                turns.transcribed(&cid, turn, !text.is_empty());
                                 if text.is_empty() {
                                     return;
                                 }
@@ -1319,6 +1506,7 @@ async fn listen_and_transcribe(
                                         speaker_id: sid.clone(),
                                         speaker_name: sname.clone(),
                                         text: text.to_string(),
+                                        original_text: transcript.original_text.clone(),
                                         timestamp_ms: std::time::SystemTime::now()
                                             .duration_since(std::time::UNIX_EPOCH)
                                             .unwrap_or_default()
@@ -1351,6 +1539,8 @@ async fn listen_and_transcribe(
                                     "transcript": text,
                                     "confidence": 1.0,
                                     "language": "en",
+                                    "arousal": transcript.affect.map(|a| a.arousal),
+                                    "valence": transcript.affect.map(|a| a.valence),
                                     "timestamp": std::time::SystemTime::now()
                                         .duration_since(std::time::UNIX_EPOCH)
                                         .unwrap_or_default()
@@ -1375,6 +1565,7 @@ async fn listen_and_transcribe(
                             }
                             Err(e) => {
                                 clog_warn!("📝 STT: Transcription failed for '{}': {}", sname, e);
+                                turns.transcribed(&cid, turn, false);
                             }
                         }
                     });
@@ -1387,6 +1578,16 @@ async fn listen_and_transcribe(
         } // end inner while (accum_buf drain)
     } // end outer while (audio_stream)
 
+    // A resubscribed track may already have registered its own chain
+    let mut chains = input_chains.lock().await;
+    if chains
+        .get(&chain_key)
+        .is_some_and(|current| Arc::ptr_eq(current, &chain))
+    {
+        chains.remove(&chain_key);
+        echo_references.lock().await.remove(&chain_key);
+    }
+
     clog_info!("🎤 STT: Audio stream ended for '{}'", speaker_name);
 }
 
@@ -1410,6 +1611,14 @@ pub struct LiveKitAgentManager {
     livekit_url: String,
     /// Transcription buffer from STT listeners (polled by tests via voice/poll-transcriptions)
     transcription_buffer: TranscriptionBuffer,
+    /// Input stage chains of the speakers STT listeners are transcribing
+    input_chains: InputChains,
+    /// Echo canceller references of hands-free speakers
+    echo_references: EchoReferences,
+    /// Utterances STT listeners dropped because transcription was saturated
+    dropped_utterances: Arc<AtomicU64>,
+    /// Voice turns, each under a child of its speaker's root handle
+    turns: TurnTracker,
 }
 
 impl Default for LiveKitAgentManager {
@@ -1431,9 +1640,19 @@ impl LiveKitAgentManager {
             listeners: Arc::new(RwLock::new(HashMap::new())),
             livekit_url,
             transcription_buffer: Arc::new(Mutex::new(VecDeque::new())),
+            input_chains: Arc::new(Mutex::new(HashMap::new())),
+            echo_references: Arc::new(Mutex::new(HashMap::new())),
+            dropped_utterances: Arc::new(AtomicU64::new(0)),
+            turns: TurnTracker::new(),
         }
     }
 
+    /// Voice turns; `turns().speaker(call_id, speaker_id)` is the handle to
+    /// subscribe a speaker's turns under
+    pub fn turns(&self) -> &TurnTracker {
+        &self.turns
+    }
+
     /// Get the LiveKit server URL this manager is configured for.
     pub fn url(&self) -> &str {
         &self.livekit_url
@@ -1443,8 +1662,13 @@ impl LiveKitAgentManager {
     /// runs VAD → STT, and publishes transcriptions via LiveKit.
     ///
     /// Called when `voice/register-session` fires (human joins a call).
+    /// `stt_config` selects the STT backend and transcription vs translation.
     /// Idempotent — no-op if already listening on this call.
-    pub async fn join_as_listener(&self, call_id: &str) -> Result<(), String> {
+    pub async fn join_as_listener(
+        &self,
+        call_id: &str,
+        stt_config: SttListenerConfig,
+    ) -> Result<(), String> {
         // Check if already listening
         {
             let listeners = self.listeners.read().await;
@@ -1460,7 +1684,12 @@ impl LiveKitAgentManager {
         let room = spawn_stt_listener(
             &self.livekit_url,
             call_id,
+            stt_config,
             self.transcription_buffer.clone(),
+            self.input_chains.clone(),
+            self.echo_references.clone(),
+            self.dropped_utterances.clone(),
+            self.turns.clone(),
         )
         .await?;
         self.listeners
@@ -1531,6 +1760,7 @@ impl LiveKitAgentManager {
             call_id,
             user_id, // Identity = persona's userId (unique UUID, no prefix needed)
             name,    // Display name shown in browser
+            self.echo_references.clone(),
         )
         .await?;
 
@@ -1589,6 +1819,17 @@ impl LiveKitAgentManager {
         }
     }
 
+    /// Input stage chain for a speaker the call's STT listener is
+    /// transcribing, to insert or remove stages mid-call. None until the
+    /// speaker's audio track is subscribed.
+    pub async fn input_chain(&self, call_id: &str, speaker_id: &str) -> Option<Arc<StageChain>> {
+        self.input_chains
+            .lock()
+            .await
+            .get(&(call_id.to_string(), speaker_id.to_string()))
+            .cloned()
+    }
+
     /// Remove a listener room when a call ends.
     pub async fn remove_listener(&self, call_id: &str) {
         let removed = self.listeners.write().await.remove(call_id);
@@ -1599,6 +1840,7 @@ impl LiveKitAgentManager {
             );
             let _ = room.close().await;
         }
+        self.turns.end_call(call_id);
     }
 
     /// Synthesize TTS and inject into a call (replaces CallManager::speak_in_call).
@@ -1608,6 +1850,9 @@ impl LiveKitAgentManager {
     /// frames arrive via WebRTC. Without this ordering, audio plays first and
     /// subtitles appear late because the data channel is instant but audio has
     /// WebRTC buffering/encoding latency.
+    ///
+    /// `prosody` is the speaker's speech rate and pitch (e.g. from its mood).
+    #[allow(clippy::too_many_arguments)]
     pub async fn speak_in_call(
         &self,
         call_id: &str,
@@ -1616,6 +1861,7 @@ impl LiveKitAgentManager {
         voice: Option<&str>,
         adapter: Option<&str>,
         display_name: Option<&str>,
+        prosody: crate::live::audio::tts::Prosody,
     ) -> Result<(usize, u64, u32), String> {
         use crate::live::audio::tts_service;
         use crate::live::avatar::gender::gender_from_identity;
@@ -1629,10 +1875,19 @@ impl LiveKitAgentManager {
             AvatarGender::Female => "female",
         };
 
-        let synthesis =
-            tts_service::synthesize_speech_async(text, voice, adapter, Some(gender_str))
-                .await
-                .map_err(|e| format!("TTS synthesis failed: {}", e))?;
+        // Speech answers the call's latest transcribed turn, if one is waiting
+        let turn = self.turns.responding(call_id);
+        let synthesis = tts_service::synthesize_speech_prosody_async(
+            text,
+            voice,
+            adapter,
+            Some(gender_str),
+            prosody,
+        )
+        .await
+        .map_err(|e| format!("TTS synthesis failed: {}", e));
+        self.turns.spoken(turn, synthesis.is_ok());
+        let synthesis = synthesis?;
 
         let num_samples = synthesis.samples.len();
         let duration_ms = synthesis.duration_ms;
@@ -1777,6 +2032,32 @@ impl LiveKitAgentManager {
         Err(format!("Ambient handle not found in call {}", call_id))
     }
 
+    /// Number of (TTS agents, STT listener rooms) currently connected
+    pub async fn active_counts(&self) -> (usize, usize) {
+        let agents = self.agents.read().await.len();
+        let listeners = self.listeners.read().await.len();
+        (agents, listeners)
+    }
+
+    /// Utterances dropped untranscribed since startup
+    pub fn dropped_utterances(&self) -> u64 {
+        self.dropped_utterances.load(Ordering::Relaxed)
+    }
+
+    /// Frames each input stage has processed, summed over the live chains
+    pub async fn input_stage_frames(&self) -> Vec<(String, u64)> {
+        let mut totals: Vec<(String, u64)> = Vec::new();
+        for chain in self.input_chains.lock().await.values() {
+            for stats in chain.metrics().stages {
+                match totals.iter_mut().find(|(stage, _)| *stage == stats.stage) {
+                    Some((_, frames)) => *frames += stats.frames_processed,
+                    None => totals.push((stats.stage, stats.frames_processed)),
+                }
+            }
+        }
+        totals
+    }
+
     /// Poll and drain the transcription buffer (for tests).
     /// Returns all transcriptions since the last poll, optionally filtered by call_id.
     pub async fn poll_transcriptions(&self, call_id: Option<&str>) -> Vec<TranscriptionEntry> {
@@ -1,3 +1,8 @@
+pub mod audio_format;
 pub mod call_server;
 pub mod livekit_agent;
 pub mod media;
+pub mod turn;
+pub mod twilio;
+#[cfg(feature = "opus-codec")]
+pub mod webrtc;
@@ -0,0 +1,246 @@
+//! Voice Turn Timeline
+//!
+//! A turn follows one utterance through the voice pipeline: transcription
+//! (STT), the response to it (LLM — generated outside this process, so
+//! timed as the gap until speech is requested), and synthesis (TTS).
+//!
+//! Each turn gets a `child()` of its speaker's handle and publishes its
+//! stage events under that, so `EventBus::subscribe_tree` on the speaker
+//! yields every turn, each on its own handle, with stage durations for a
+//! per-turn timeline.
+
+use crate::concurrent::EventBus;
+use crate::live::handle::Handle;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Live buffer per `TurnTracker::events()` subscriber
+const TURN_EVENT_CAPACITY: usize = 64;
+
+/// Stage of a voice turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TurnStage {
+    Stt,
+    Llm,
+    Tts,
+}
+
+/// Published under a turn's handle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TurnEvent {
+    /// A stage finished; timed from the end of the previous one (or the
+    /// end of the utterance, for STT)
+    #[serde(rename_all = "camelCase")]
+    Stage {
+        stage: TurnStage,
+        duration_ms: u64,
+        ok: bool,
+    },
+    /// Last event for the turn; its channel closes after it
+    Ended,
+}
+
+/// A turn in flight
+#[derive(Debug)]
+pub struct VoiceTurn {
+    handle: Handle,
+    /// When the stage being timed started
+    mark: Instant,
+}
+
+impl VoiceTurn {
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+}
+
+/// Turn bookkeeping for one transport. Cheap to clone; clones share state.
+#[derive(Clone)]
+pub struct TurnTracker {
+    events: Arc<EventBus<TurnEvent>>,
+    /// Call → the turn awaiting a response (the newest transcript's)
+    awaiting: Arc<Mutex<HashMap<String, VoiceTurn>>>,
+    /// (call, speaker id) → root handle, for speakers the transport
+    /// identifies by id rather than by participant handle
+    speakers: Arc<Mutex<HashMap<(String, String), Handle>>>,
+}
+
+impl Default for TurnTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TurnTracker {
+    pub fn new() -> Self {
+        Self {
+            events: Arc::new(EventBus::new(TURN_EVENT_CAPACITY)),
+            awaiting: Arc::new(Mutex::new(HashMap::new())),
+            speakers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Turn events, keyed by turn handle
+    pub fn events(&self) -> &EventBus<TurnEvent> {
+        &self.events
+    }
+
+    /// Root handle for `speaker_id` in `call_id`, minted on first use
+    pub fn speaker(&self, call_id: &str, speaker_id: &str) -> Handle {
+        *self
+            .speakers
+            .lock()
+            .entry((call_id.to_string(), speaker_id.to_string()))
+            .or_default()
+    }
+
+    /// `speaker`'s utterance ended: start a turn, timing its transcription
+    pub fn begin(&self, speaker: Handle) -> VoiceTurn {
+        VoiceTurn {
+            handle: speaker.child(),
+            mark: Instant::now(),
+        }
+    }
+
+    /// Transcription finished. A transcript (`ok`) leaves the turn awaiting
+    /// the call's response, replacing any turn still waiting; anything else
+    /// ends it.
+    pub fn transcribed(&self, call_id: &str, mut turn: VoiceTurn, ok: bool) {
+        self.finish_stage(&mut turn, TurnStage::Stt, ok);
+        if !ok {
+            self.end(turn);
+            return;
+        }
+        let replaced = self.awaiting.lock().insert(call_id.to_string(), turn);
+        if let Some(replaced) = replaced {
+            self.end(replaced);
+        }
+    }
+
+    /// Speech was requested in `call_id`: the awaiting turn's response is
+    /// done. Returns the turn to time its synthesis, if one was waiting.
+    pub fn responding(&self, call_id: &str) -> Option<VoiceTurn> {
+        let mut turn = self.awaiting.lock().remove(call_id)?;
+        self.finish_stage(&mut turn, TurnStage::Llm, true);
+        Some(turn)
+    }
+
+    /// Synthesis for `turn` (from `responding`) finished; the turn ends
+    pub fn spoken(&self, turn: Option<VoiceTurn>, ok: bool) {
+        if let Some(mut turn) = turn {
+            self.finish_stage(&mut turn, TurnStage::Tts, ok);
+            self.end(turn);
+        }
+    }
+
+    /// End the call's awaiting turn and forget its speakers
+    pub fn end_call(&self, call_id: &str) {
+        let turn = self.awaiting.lock().remove(call_id);
+        if let Some(turn) = turn {
+            self.end(turn);
+        }
+        self.speakers.lock().retain(|(call, _), _| call != call_id);
+    }
+
+    fn finish_stage(&self, turn: &mut VoiceTurn, stage: TurnStage, ok: bool) {
+        let now = Instant::now();
+        let duration_ms = now.duration_since(turn.mark).as_millis() as u64;
+        turn.mark = now;
+        self.events.publish(
+            turn.handle,
+            TurnEvent::Stage {
+                stage,
+                duration_ms,
+                ok,
+            },
+        );
+    }
+
+    fn end(&self, turn: VoiceTurn) {
+        self.events.publish(turn.handle, TurnEvent::Ended);
+        self.events.remove_handle(&turn.handle);
+        turn.handle.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(rx: &mut tokio::sync::broadcast::Receiver<(Handle, TurnEvent)>) -> (Handle, TurnEvent) {
+        rx.try_recv().expect("event published")
+    }
+
+    fn stage_of(event: &TurnEvent) -> Option<(TurnStage, bool)> {
+        match event {
+            TurnEvent::Stage { stage, ok, .. } => Some((*stage, *ok)),
+            TurnEvent::Ended => None,
+        }
+    }
+
+    #[test]
+    fn test_turn_stages_publish_under_a_child_of_the_speaker() {
+        let tracker = TurnTracker::new();
+        let speaker = Handle::new();
+        let mut tree = tracker.events().subscribe_tree(speaker);
+
+        let turn = tracker.begin(speaker);
+        let turn_handle = turn.handle();
+        assert_eq!(turn_handle.parent(), Some(speaker));
+
+        tracker.transcribed("call", turn, true);
+        let responding = tracker.responding("call");
+        assert!(tracker.responding("call").is_none());
+        tracker.spoken(responding, true);
+
+        let events: Vec<_> = (0..4).map(|_| next(&mut tree)).collect();
+        assert!(events.iter().all(|(handle, _)| *handle == turn_handle));
+        let stages: Vec<_> = events.iter().map(|(_, e)| stage_of(e)).collect();
+        assert_eq!(
+            stages,
+            vec![
+                Some((TurnStage::Stt, true)),
+                Some((TurnStage::Llm, true)),
+                Some((TurnStage::Tts, true)),
+                None,
+            ]
+        );
+
+        // Ended turns leave nothing behind
+        assert_eq!(tracker.events().handle_count(), 0);
+        assert_eq!(turn_handle.parent(), None);
+    }
+
+    #[test]
+    fn test_turn_without_transcript_or_response_ends() {
+        let tracker = TurnTracker::new();
+        let speaker = tracker.speaker("call", "alice");
+        assert_eq!(tracker.speaker("call", "alice"), speaker);
+        let mut tree = tracker.events().subscribe_tree(speaker);
+
+        // Nothing transcribed: the turn ends after STT
+        tracker.transcribed("call", tracker.begin(speaker), false);
+        assert_eq!(stage_of(&next(&mut tree).1), Some((TurnStage::Stt, false)));
+        assert_eq!(next(&mut tree).1, TurnEvent::Ended);
+
+        // A newer transcript replaces an unanswered one
+        let first = tracker.begin(speaker);
+        let first_handle = first.handle();
+        tracker.transcribed("call", first, true);
+        tracker.transcribed("call", tracker.begin(speaker), true);
+        next(&mut tree);
+        next(&mut tree);
+        assert_eq!(next(&mut tree), (first_handle, TurnEvent::Ended));
+
+        // The call ending closes the one still waiting
+        tracker.end_call("call");
+        assert_eq!(next(&mut tree).1, TurnEvent::Ended);
+        assert_eq!(tracker.events().handle_count(), 0);
+        assert_ne!(tracker.speaker("call", "alice"), speaker);
+    }
+}
@@ -0,0 +1,1038 @@
+//! Twilio Media Streams Adapter
+//!
+//! Bridges Twilio's bidirectional media stream WebSocket protocol to the
+//! internal 16kHz i16 PCM pipeline. Twilio sends JSON envelopes
+//! (`connected`, `start`, `media`, `mark`, `stop`) whose `media.payload` is
+//! base64 G.711 audio at 8kHz — µ-law by default, A-law on some carriers.
+//!
+//! Inbound: base64 → G.711 decode → 8k→16k resample → `TwilioEvent::Audio`
+//! Outbound: 16k PCM → 16k→8k resample → G.711 encode → base64 `media` JSON
+//!
+//! Each `streamSid` is correlated to a pipeline `Handle` when its `start`
+//! event arrives. Gaps in the inbound timeline are filled with comfort noise
+//! so downstream VAD/STT sees a continuous stream. Outbound, with
+//! `set_comfort_noise(level_db)` enabled, `encode_output()` fills frames the
+//! pipeline has nothing for with low-level noise so the far end doesn't hear
+//! dead air between utterances.
+//!
+//! Inbound audio runs through an IVR `StageChain` per stream, so keypad
+//! tones in the audio are detected in-band. Out-of-band `dtmf` messages
+//! become `TwilioEvent::Dtmf`. Both kinds reach `subscribe_dtmf()` as the
+//! same `DtmfEvent`, tagged with their source.
+//!
+//! With `set_jitter_buffer(Some(config))`, later streams put a jitter buffer
+//! at the front of that chain instead: `media` chunks are held in order of
+//! their chunk number (`TwilioEvent::Buffered`), and the caller clocks
+//! `play_out()` once per 20ms to take a steady stream, with missing chunks
+//! concealed rather than gap-filled.
+
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
+use crate::live::audio::dtmf::{parse_digit, DtmfEvent, DtmfSource, DtmfStage};
+use crate::live::audio::jitter_buffer::{JitterConfig, JitterInput};
+use crate::live::audio::resample::ResampleStage;
+use crate::live::audio::stage_chain::StageChain;
+use crate::live::handle::Handle;
+use crate::live::types::{AudioFrame, FrameSequencer};
+use crate::{clog_info, clog_warn};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+
+/// Twilio media streams are always 8kHz mono
+pub const TWILIO_SAMPLE_RATE: u32 = 8000;
+
+/// Gaps shorter than this are treated as jitter, not missing audio
+const GAP_TOLERANCE_MS: u64 = 40;
+
+/// Never fill more than this much missing audio in one go
+const MAX_GAP_FILL_MS: u64 = 2000;
+
+/// Peak amplitude of inbound gap-fill comfort noise (~-60 dBFS)
+const COMFORT_NOISE_AMPLITUDE: i16 = 32;
+
+/// Outbound comfort noise level used by `comfort_noise()` when none is set
+const DEFAULT_COMFORT_NOISE_DB: f32 = -60.0;
+
+/// Buffered keypresses per `subscribe_dtmf()` receiver
+const DTMF_CAPACITY: usize = 32;
+
+// ============================================================================
+// G.711 codec
+// ============================================================================
+
+/// G.711 companding law
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum G711Law {
+    #[default]
+    MuLaw,
+    ALaw,
+}
+
+impl G711Law {
+    /// Parse a Twilio `mediaFormat.encoding` value
+    pub fn from_encoding(encoding: &str) -> Option<Self> {
+        match encoding {
+            "audio/x-mulaw" | "audio/PCMU" => Some(Self::MuLaw),
+            "audio/x-alaw" | "audio/PCMA" => Some(Self::ALaw),
+            _ => None,
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> Vec<i16> {
+        match self {
+            Self::MuLaw => bytes.iter().map(|&b| mulaw_to_linear(b)).collect(),
+            Self::ALaw => bytes.iter().map(|&b| alaw_to_linear(b)).collect(),
+        }
+    }
+
+    pub fn encode(self, samples: &[i16]) -> Vec<u8> {
+        match self {
+            Self::MuLaw => samples.iter().map(|&s| linear_to_mulaw(s)).collect(),
+            Self::ALaw => samples.iter().map(|&s| linear_to_alaw(s)).collect(),
+        }
+    }
+}
+
+const MULAW_BIAS: i32 = 0x84;
+const MULAW_CLIP: i32 = 32635;
+
+/// Decode one µ-law byte to 16-bit linear PCM
+pub fn mulaw_to_linear(byte: u8) -> i16 {
+    let u = !byte;
+    let sign = u & 0x80;
+    let exponent = ((u >> 4) & 0x07) as i32;
+    let mantissa = (u & 0x0F) as i32;
+    let magnitude = (((mantissa << 3) + MULAW_BIAS) << exponent) - MULAW_BIAS;
+    if sign != 0 {
+        -magnitude as i16
+    } else {
+        magnitude as i16
+    }
+}
+
+/// Encode 16-bit linear PCM to one µ-law byte
+pub fn linear_to_mulaw(sample: i16) -> u8 {
+    let mut pcm = sample as i32;
+    let sign = if pcm < 0 {
+        pcm = -pcm;
+        0x80
+    } else {
+        0x00
+    };
+    pcm = pcm.min(MULAW_CLIP) + MULAW_BIAS;
+
+    let segment = (pcm >> 7) as u8;
+    let exponent = if segment == 0 {
+        0
+    } else {
+        7 - segment.leading_zeros() as i32
+    };
+    let mantissa = (pcm >> (exponent + 3)) & 0x0F;
+    !(sign | (exponent << 4) as u8 | mantissa as u8)
+}
+
+/// Decode one A-law byte to 16-bit linear PCM
+pub fn alaw_to_linear(byte: u8) -> i16 {
+    let a = byte ^ 0x55;
+    let sign = a & 0x80;
+    let exponent = ((a >> 4) & 0x07) as i32;
+    let mantissa = (a & 0x0F) as i32;
+    let magnitude = if exponent == 0 {
+        (mantissa << 4) + 8
+    } else {
+        ((mantissa << 4) + 0x108) << (exponent - 1)
+    };
+    if sign != 0 {
+        magnitude as i16
+    } else {
+        -magnitude as i16
+    }
+}
+
+/// Upper bound of each A-law segment (13-bit magnitude)
+const ALAW_SEGMENT_END: [i32; 8] = [0x1F, 0x3F, 0x7F, 0xFF, 0x1FF, 0x3FF, 0x7FF, 0xFFF];
+
+/// Encode 16-bit linear PCM to one A-law byte
+pub fn linear_to_alaw(sample: i16) -> u8 {
+    let pcm = (sample as i32) >> 3;
+    let (mask, pcm) = if pcm >= 0 {
+        (0xD5u8, pcm)
+    } else {
+        (0x55u8, -pcm - 1)
+    };
+    let Some(segment) = ALAW_SEGMENT_END.iter().position(|&end| pcm <= end) else {
+        return 0x7F ^ mask;
+    };
+    let mantissa = if segment < 2 {
+        (pcm >> 1) & 0x0F
+    } else {
+        (pcm >> segment) & 0x0F
+    };
+    (((segment as u8) << 4) | mantissa as u8) ^ mask
+}
+
+// ============================================================================
+// Twilio wire protocol
+// ============================================================================
+
+/// Inbound Twilio media stream message (subset we act on)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum TwilioInbound {
+    Connected {
+        #[serde(default)]
+        protocol: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Start {
+        stream_sid: String,
+        start: TwilioStart,
+    },
+    #[serde(rename_all = "camelCase")]
+    Media {
+        stream_sid: String,
+        media: TwilioMedia,
+    },
+    #[serde(rename_all = "camelCase")]
+    Mark {
+        stream_sid: String,
+        mark: TwilioMark,
+    },
+    #[serde(rename_all = "camelCase")]
+    Dtmf {
+        stream_sid: String,
+        dtmf: TwilioDtmf,
+    },
+    #[serde(rename_all = "camelCase")]
+    Stop { stream_sid: String },
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwilioStart {
+    #[serde(default)]
+    pub call_sid: String,
+    #[serde(default)]
+    pub media_format: Option<TwilioMediaFormat>,
+    #[serde(default)]
+    pub custom_parameters: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TwilioMediaFormat {
+    pub encoding: String,
+    #[serde(default)]
+    pub sample_rate: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwilioMedia {
+    /// Milliseconds since stream start (Twilio sends this as a string)
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    /// Media chunk number, counting from 1 (also a string)
+    #[serde(default)]
+    pub chunk: Option<String>,
+    pub payload: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwilioDtmf {
+    pub digit: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwilioMark {
+    pub name: String,
+}
+
+/// Outbound message to Twilio
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum TwilioOutbound {
+    #[serde(rename_all = "camelCase")]
+    Media {
+        stream_sid: String,
+        media: TwilioOutboundMedia,
+    },
+    #[serde(rename_all = "camelCase")]
+    Mark {
+        stream_sid: String,
+        mark: TwilioMark,
+    },
+    /// Flush audio Twilio has buffered but not yet played (barge-in)
+    #[serde(rename_all = "camelCase")]
+    Clear { stream_sid: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TwilioOutboundMedia {
+    pub payload: String,
+}
+
+/// What a Twilio message means for the pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub enum TwilioEvent {
+    Started {
+        stream_sid: String,
+        call_sid: String,
+        handle: Handle,
+    },
+    /// 16kHz PCM, including any comfort noise filling a preceding gap
+    Audio {
+        handle: Handle,
+        samples: Vec<i16>,
+        timestamp_ms: u64,
+    },
+    /// Audio held by the stream's jitter buffer until `play_out()`
+    Buffered {
+        handle: Handle,
+        timestamp_ms: u64,
+    },
+    Mark {
+        handle: Handle,
+        name: String,
+    },
+    /// Keypress signalled out of band by Twilio
+    Dtmf(DtmfEvent),
+    Stopped {
+        handle: Handle,
+    },
+    /// Message carried nothing for the pipeline (connected, unknown events)
+    Ignored,
+}
+
+// ============================================================================
+// Adapter
+// ============================================================================
+
+/// Per-stream codec and resampling state
+struct TwilioStream {
+    handle: Handle,
+    law: G711Law,
+    upsampler: ResampleStage,
+    downsampler: ResampleStage,
+    /// Inbound stages after resampling; starts as the IVR chain, behind a
+    /// jitter buffer when one is configured
+    input: StageChain,
+    /// Feeds the jitter buffer at the front of `input`
+    jitter: Option<JitterInput>,
+    /// Numbers chunks passed straight to `input` (the jitter buffer stamps
+    /// its own)
+    sequencer: FrameSequencer,
+    /// End of the last inbound chunk on Twilio's timeline
+    next_expected_ms: Option<u64>,
+    /// Last outbound frame was comfort noise (downsampler holds stale audio)
+    sending_comfort_noise: bool,
+}
+
+/// Decodes inbound and encodes outbound Twilio media for any number of
+/// concurrent streams.
+pub struct TwilioMediaAdapter {
+    default_law: G711Law,
+    streams: HashMap<String, TwilioStream>,
+    /// Outbound comfort noise level in dBFS (None = send nothing in gaps)
+    comfort_noise_db: Option<f32>,
+    /// Jitter buffer for streams started from now on (None = pass through)
+    jitter: Option<JitterConfig>,
+    /// In-band and out-of-band keypresses from every stream
+    dtmf: broadcast::Sender<DtmfEvent>,
+}
+
+impl TwilioMediaAdapter {
+    /// Create an adapter. `default_law` applies when a `start` event doesn't
+    /// declare its encoding.
+    pub fn new(default_law: G711Law) -> Self {
+        Self {
+            default_law,
+            streams: HashMap::new(),
+            comfort_noise_db: None,
+            jitter: None,
+            dtmf: broadcast::channel(DTMF_CAPACITY).0,
+        }
+    }
+
+    /// Keypresses on every stream, whether detected in the audio or
+    /// signalled out of band by Twilio
+    pub fn subscribe_dtmf(&self) -> broadcast::Receiver<DtmfEvent> {
+        self.dtmf.subscribe()
+    }
+
+    /// Inbound stage chain for a stream, to insert or remove stages mid-call
+    pub fn input_chain(&self, stream_sid: &str) -> Option<&StageChain> {
+        self.streams.get(stream_sid).map(|s| &s.input)
+    }
+
+    /// Fill outbound gaps with comfort noise at `level_db` dBFS (e.g. -60),
+    /// or `None` to send nothing while the pipeline is silent.
+    pub fn set_comfort_noise(&mut self, level_db: Option<f32>) {
+        self.comfort_noise_db = level_db;
+    }
+
+    /// Buffer inbound audio of streams started from now on, to be played
+    /// out with `play_out()`, or `None` to pass it straight through
+    pub fn set_jitter_buffer(&mut self, config: Option<JitterConfig>) {
+        self.jitter = config;
+    }
+
+    /// Pipeline handle for a Twilio stream, if it has started
+    pub fn handle_for(&self, stream_sid: &str) -> Option<Handle> {
+        self.streams.get(stream_sid).map(|s| s.handle)
+    }
+
+    pub fn stream_count(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Process one inbound WebSocket text frame
+    pub fn handle_message(&mut self, text: &str) -> Result<TwilioEvent, String> {
+        let message: TwilioInbound =
+            serde_json::from_str(text).map_err(|e| format!("Invalid Twilio message: {e}"))?;
+
+        match message {
+            TwilioInbound::Start { stream_sid, start } => {
+                let law = start
+                    .media_format
+                    .as_ref()
+                    .and_then(|f| G711Law::from_encoding(&f.encoding))
+                    .unwrap_or(self.default_law);
+                let handle = Handle::new();
+                clog_info!(
+                    "Twilio stream {} started (call {}, {:?}) → handle {}",
+                    stream_sid,
+                    start.call_sid,
+                    law,
+                    handle.short()
+                );
+                let (input, jitter) = match self.jitter {
+                    Some(config) => {
+                        let (chain, jitter) = StageChain::network(config);
+                        chain.push_stage(Box::new(DtmfStage::with_sender(
+                            handle,
+                            AUDIO_SAMPLE_RATE,
+                            self.dtmf.clone(),
+                        )));
+                        (chain, Some(jitter))
+                    }
+                    None => (
+                        StageChain::ivr(handle, AUDIO_SAMPLE_RATE, self.dtmf.clone()),
+                        None,
+                    ),
+                };
+                self.streams.insert(
+                    stream_sid.clone(),
+                    TwilioStream {
+                        handle,
+                        law,
+                        upsampler: ResampleStage::new(TWILIO_SAMPLE_RATE, AUDIO_SAMPLE_RATE),
+                        downsampler: ResampleStage::new(AUDIO_SAMPLE_RATE, TWILIO_SAMPLE_RATE),
+                        input,
+                        jitter,
+                        sequencer: FrameSequencer::new(),
+                        next_expected_ms: None,
+                        sending_comfort_noise: false,
+                    },
+                );
+                Ok(TwilioEvent::Started {
+                    stream_sid,
+                    call_sid: start.call_sid,
+                    handle,
+                })
+            }
+            TwilioInbound::Media { stream_sid, media } => {
+                let stream = self
+                    .streams
+                    .get_mut(&stream_sid)
+                    .ok_or_else(|| format!("Media for unknown Twilio stream {stream_sid}"))?;
+                let bytes = STANDARD
+                    .decode(&media.payload)
+                    .map_err(|e| format!("Invalid Twilio media payload: {e}"))?;
+                let pcm_8k = stream.law.decode(&bytes);
+                let chunk_ms = pcm_8k.len() as u64 * 1000 / TWILIO_SAMPLE_RATE as u64;
+
+                let timestamp_ms = media
+                    .timestamp
+                    .as_deref()
+                    .and_then(|t| t.parse::<u64>().ok())
+                    .or(stream.next_expected_ms)
+                    .unwrap_or(0);
+
+                if let Some(jitter) = &stream.jitter {
+                    // The jitter buffer conceals missing chunks itself
+                    let chunk = media
+                        .chunk
+                        .as_deref()
+                        .and_then(|c| c.parse::<u64>().ok())
+                        .ok_or_else(|| {
+                            format!("Twilio media without a chunk number on {stream_sid}")
+                        })?;
+                    jitter.push(chunk as u16, stream.upsampler.process(&pcm_8k));
+                    stream.next_expected_ms = Some(timestamp_ms + chunk_ms);
+                    return Ok(TwilioEvent::Buffered {
+                        handle: stream.handle,
+                        timestamp_ms,
+                    });
+                }
+
+                let mut samples = Vec::new();
+                let mut pts_ms = timestamp_ms;
+                if let Some(expected) = stream.next_expected_ms {
+                    let gap_ms = timestamp_ms.saturating_sub(expected);
+                    if gap_ms > GAP_TOLERANCE_MS {
+                        let fill_ms = gap_ms.min(MAX_GAP_FILL_MS);
+                        clog_warn!(
+                            "Twilio stream {} gap of {}ms, filling {}ms comfort noise",
+                            stream_sid,
+                            gap_ms,
+                            fill_ms
+                        );
+                        stream.upsampler.reset();
+                        pts_ms -= fill_ms;
+                        samples.extend(comfort_noise_samples(
+                            (fill_ms * AUDIO_SAMPLE_RATE as u64 / 1000) as usize,
+                            COMFORT_NOISE_AMPLITUDE,
+                        ));
+                    }
+                }
+                samples.extend(stream.upsampler.process(&pcm_8k));
+                let frame = AudioFrame {
+                    meta: stream.sequencer.stamp(pts_ms),
+                    samples,
+                };
+                let samples = stream.input.process_frame(&frame).samples;
+                stream.next_expected_ms = Some(timestamp_ms + chunk_ms);
+
+                Ok(TwilioEvent::Audio {
+                    handle: stream.handle,
+                    samples,
+                    timestamp_ms,
+                })
+            }
+            TwilioInbound::Mark { stream_sid, mark } => match self.handle_for(&stream_sid) {
+                Some(handle) => Ok(TwilioEvent::Mark {
+                    handle,
+                    name: mark.name,
+                }),
+                None => Ok(TwilioEvent::Ignored),
+            },
+            TwilioInbound::Dtmf { stream_sid, dtmf } => {
+                let (Some(handle), Some(digit)) =
+                    (self.handle_for(&stream_sid), parse_digit(&dtmf.digit))
+                else {
+                    clog_warn!(
+                        "Ignoring Twilio DTMF '{}' for stream {}",
+                        dtmf.digit,
+                        stream_sid
+                    );
+                    return Ok(TwilioEvent::Ignored);
+                };
+                let event = DtmfEvent {
+                    handle,
+                    digit,
+                    source: DtmfSource::OutOfBand,
+                };
+                // No subscribers is fine
+                let _ = self.dtmf.send(event);
+                Ok(TwilioEvent::Dtmf(event))
+            }
+            TwilioInbound::Stop { stream_sid } => match self.streams.remove(&stream_sid) {
+                Some(stream) => {
+                    clog_info!("Twilio stream {} stopped", stream_sid);
+                    Ok(TwilioEvent::Stopped {
+                        handle: stream.handle,
+                    })
+                }
+                None => Ok(TwilioEvent::Ignored),
+            },
+            TwilioInbound::Connected { .. } | TwilioInbound::Unknown => Ok(TwilioEvent::Ignored),
+        }
+    }
+
+    /// Next 20ms of a jitter-buffered stream's audio, in chunk order and
+    /// through the rest of its input chain. Call once per 20ms; empty while
+    /// the buffer fills.
+    pub fn play_out(&mut self, stream_sid: &str) -> Result<Vec<i16>, String> {
+        let stream = self
+            .streams
+            .get(stream_sid)
+            .ok_or_else(|| format!("Unknown Twilio stream {stream_sid}"))?;
+        if stream.jitter.is_none() {
+            return Err(format!("Twilio stream {stream_sid} has no jitter buffer"));
+        }
+        Ok(stream.input.process(&[]))
+    }
+
+    /// Encode 16kHz PCM as an outbound `media` message for a stream
+    pub fn encode_media(&mut self, stream_sid: &str, samples: &[i16]) -> Result<String, String> {
+        let stream = self
+            .streams
+            .get_mut(stream_sid)
+            .ok_or_else(|| format!("Unknown Twilio stream {stream_sid}"))?;
+        if stream.sending_comfort_noise {
+            // Speech resumes: drop interpolation state from before the gap
+            // so nothing stale bleeds into the first frame
+            stream.downsampler.reset();
+            stream.sending_comfort_noise = false;
+        }
+        let pcm_8k = stream.downsampler.process(samples);
+        media_message(stream_sid, stream.law, &pcm_8k)
+    }
+
+    /// Outbound comfort noise covering `duration_ms`, sent while the pipeline
+    /// has nothing to say so the far end doesn't hear the line drop. Uses the
+    /// `set_comfort_noise` level, or -60 dBFS if none is set.
+    pub fn comfort_noise(&mut self, stream_sid: &str, duration_ms: u64) -> Result<String, String> {
+        let level_db = self.comfort_noise_db.unwrap_or(DEFAULT_COMFORT_NOISE_DB);
+        let stream = self
+            .streams
+            .get_mut(stream_sid)
+            .ok_or_else(|| format!("Unknown Twilio stream {stream_sid}"))?;
+        // Generated at 8kHz so it never passes through the speech resampler
+        let noise = comfort_noise_samples(
+            (duration_ms * TWILIO_SAMPLE_RATE as u64 / 1000) as usize,
+            db_to_amplitude(level_db),
+        );
+        stream.sending_comfort_noise = true;
+        media_message(stream_sid, stream.law, &noise)
+    }
+
+    /// Next outbound frame: `frame` if the pipeline produced one, otherwise
+    /// `frame_ms` of comfort noise when enabled. Ok(None) means send nothing.
+    /// Real frames always win, so noise stops on the frame speech resumes.
+    pub fn encode_output(
+        &mut self,
+        stream_sid: &str,
+        frame: Option<&[i16]>,
+        frame_ms: u64,
+    ) -> Result<Option<String>, String> {
+        match (frame, self.comfort_noise_db) {
+            (Some(samples), _) => self.encode_media(stream_sid, samples).map(Some),
+            (None, Some(_)) => self.comfort_noise(stream_sid, frame_ms).map(Some),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Outbound `clear` message — drops audio Twilio has queued (barge-in)
+    pub fn encode_clear(&self, stream_sid: &str) -> Result<String, String> {
+        serialize_outbound(&TwilioOutbound::Clear {
+            stream_sid: stream_sid.to_string(),
+        })
+    }
+}
+
+impl Default for TwilioMediaAdapter {
+    fn default() -> Self {
+        Self::new(G711Law::MuLaw)
+    }
+}
+
+fn media_message(stream_sid: &str, law: G711Law, pcm_8k: &[i16]) -> Result<String, String> {
+    let payload = STANDARD.encode(law.encode(pcm_8k));
+    serialize_outbound(&TwilioOutbound::Media {
+        stream_sid: stream_sid.to_string(),
+        media: TwilioOutboundMedia { payload },
+    })
+}
+
+fn serialize_outbound(message: &TwilioOutbound) -> Result<String, String> {
+    serde_json::to_string(message).map_err(|e| format!("Failed to serialize Twilio message: {e}"))
+}
+
+/// Very low-level white noise. Pure digital silence makes some carriers
+/// assume the line is dead; this sits well under any VAD threshold.
+fn comfort_noise_samples(count: usize, amplitude: i16) -> Vec<i16> {
+    let mut rng = rand::thread_rng();
+    (0..count)
+        .map(|_| rng.gen_range(-amplitude..=amplitude))
+        .collect()
+}
+
+/// Peak sample amplitude for a dBFS level
+fn db_to_amplitude(level_db: f32) -> i16 {
+    (i16::MAX as f32 * 10f32.powf(level_db / 20.0))
+        .round()
+        .clamp(0.0, i16::MAX as f32) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start_msg(sid: &str, encoding: &str) -> String {
+        format!(
+            r#"{{"event":"start","sequenceNumber":"1","streamSid":"{sid}","start":{{"accountSid":"AC1","callSid":"CA1","tracks":["inbound"],"mediaFormat":{{"encoding":"{encoding}","sampleRate":8000,"channels":1}},"customParameters":{{}}}}}}"#
+        )
+    }
+
+    fn media_msg(sid: &str, timestamp: u64, payload: &[u8]) -> String {
+        format!(
+            r#"{{"event":"media","sequenceNumber":"2","streamSid":"{sid}","media":{{"track":"inbound","chunk":"1","timestamp":"{timestamp}","payload":"{}"}}}}"#,
+            STANDARD.encode(payload)
+        )
+    }
+
+    fn chunk_msg(sid: &str, chunk: u64, payload: &[u8]) -> String {
+        format!(
+            r#"{{"event":"media","sequenceNumber":"{}","streamSid":"{sid}","media":{{"track":"inbound","chunk":"{chunk}","timestamp":"{}","payload":"{}"}}}}"#,
+            chunk + 1,
+            (chunk - 1) * 20,
+            STANDARD.encode(payload)
+        )
+    }
+
+    #[test]
+    fn test_mulaw_known_values() {
+        assert_eq!(mulaw_to_linear(0xFF), 0);
+        assert_eq!(mulaw_to_linear(0x7F), 0);
+        assert_eq!(mulaw_to_linear(0x00), -32124);
+        assert_eq!(mulaw_to_linear(0x80), 32124);
+        assert_eq!(linear_to_mulaw(0), 0xFF);
+    }
+
+    #[test]
+    fn test_mulaw_roundtrip_all_codes() {
+        for byte in 0..=255u8 {
+            let linear = mulaw_to_linear(byte);
+            let back = linear_to_mulaw(linear);
+            // 0x7F and 0xFF both decode to zero
+            assert_eq!(mulaw_to_linear(back), linear, "byte {byte:#04x}");
+        }
+    }
+
+    #[test]
+    fn test_alaw_roundtrip_all_codes() {
+        for byte in 0..=255u8 {
+            let linear = alaw_to_linear(byte);
+            assert_eq!(linear_to_alaw(linear), byte, "byte {byte:#04x}");
+        }
+    }
+
+    #[test]
+    fn test_companding_error_is_bounded() {
+        for law in [G711Law::MuLaw, G711Law::ALaw] {
+            for sample in (-32000i16..32000).step_by(97) {
+                let decoded = law.decode(&law.encode(&[sample]))[0];
+                let error = (decoded as i32 - sample as i32).abs();
+                // Quantization step grows with magnitude (~3% worst case)
+                assert!(
+                    error <= (sample as i32).abs() / 16 + 16,
+                    "{law:?}: {sample} → {decoded}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_start_correlates_stream_to_handle() {
+        let mut adapter = TwilioMediaAdapter::default();
+        let event = adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        let TwilioEvent::Started {
+            stream_sid,
+            call_sid,
+            handle,
+        } = event
+        else {
+            panic!("expected Started, got {event:?}");
+        };
+        assert_eq!(stream_sid, "MZ1");
+        assert_eq!(call_sid, "CA1");
+        assert_eq!(adapter.handle_for("MZ1"), Some(handle));
+    }
+
+    #[test]
+    fn test_media_decodes_and_upsamples() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+
+        // 20ms of µ-law silence = 160 bytes at 8kHz
+        let event = adapter
+            .handle_message(&media_msg("MZ1", 0, &[0xFF; 160]))
+            .unwrap();
+        let TwilioEvent::Audio { samples, .. } = event else {
+            panic!("expected Audio");
+        };
+        // ~320 samples at 16kHz (one held back for interpolation)
+        assert!((318..=320).contains(&samples.len()), "{}", samples.len());
+        assert!(samples.iter().all(|&s| s == 0));
+    }
+
+    #[test]
+    fn test_alaw_selected_from_media_format() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-alaw"))
+            .unwrap();
+        let event = adapter
+            .handle_message(&media_msg("MZ1", 0, &[linear_to_alaw(0); 160]))
+            .unwrap();
+        let TwilioEvent::Audio { samples, .. } = event else {
+            panic!("expected Audio");
+        };
+        assert!(samples.iter().all(|&s| s.abs() <= 8));
+    }
+
+    #[test]
+    fn test_gap_is_filled_with_comfort_noise() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        adapter
+            .handle_message(&media_msg("MZ1", 0, &[0xFF; 160]))
+            .unwrap();
+
+        // Next chunk arrives 200ms later than expected
+        let event = adapter
+            .handle_message(&media_msg("MZ1", 220, &[0xFF; 160]))
+            .unwrap();
+        let TwilioEvent::Audio {
+            samples,
+            timestamp_ms,
+            ..
+        } = event
+        else {
+            panic!("expected Audio");
+        };
+        assert_eq!(timestamp_ms, 220);
+        // 200ms of fill (3200 samples) + the 20ms chunk
+        assert!(samples.len() >= 3200 + 318, "{}", samples.len());
+        assert!(samples.iter().all(|&s| s.abs() <= COMFORT_NOISE_AMPLITUDE));
+    }
+
+    #[test]
+    fn test_jitter_buffer_plays_chunks_in_order() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter.set_jitter_buffer(Some(JitterConfig {
+            target_depth: 2,
+            ..JitterConfig::default()
+        }));
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+
+        // Chunk 2 (loud) overtakes chunk 1 (silence)
+        let loud = [linear_to_mulaw(8000); 160];
+        let event = adapter.handle_message(&chunk_msg("MZ1", 2, &loud)).unwrap();
+        assert!(matches!(event, TwilioEvent::Buffered { .. }));
+        assert!(adapter.play_out("MZ1").unwrap().is_empty());
+        adapter
+            .handle_message(&chunk_msg("MZ1", 1, &[0xFF; 160]))
+            .unwrap();
+
+        let mean_level = |samples: &[i16]| {
+            samples
+                .iter()
+                .map(|&s| s.unsigned_abs() as u64)
+                .sum::<u64>()
+                / samples.len() as u64
+        };
+        let first = adapter.play_out("MZ1").unwrap();
+        let second = adapter.play_out("MZ1").unwrap();
+        assert!(mean_level(&first) < mean_level(&second));
+        assert!(adapter.play_out("MZ1").unwrap().is_empty());
+
+        // Streams started without a jitter buffer aren't clocked
+        adapter.set_jitter_buffer(None);
+        adapter
+            .handle_message(&start_msg("MZ2", "audio/x-mulaw"))
+            .unwrap();
+        assert!(adapter.play_out("MZ2").is_err());
+    }
+
+    #[test]
+    fn test_media_for_unknown_stream_errors() {
+        let mut adapter = TwilioMediaAdapter::default();
+        assert!(adapter
+            .handle_message(&media_msg("MZ404", 0, &[0xFF; 160]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_stop_releases_stream() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        let handle = adapter.handle_for("MZ1").unwrap();
+        let event = adapter
+            .handle_message(r#"{"event":"stop","sequenceNumber":"5","streamSid":"MZ1","stop":{"accountSid":"AC1","callSid":"CA1"}}"#)
+            .unwrap();
+        assert_eq!(event, TwilioEvent::Stopped { handle });
+        assert_eq!(adapter.stream_count(), 0);
+    }
+
+    #[test]
+    fn test_connected_and_unknown_are_ignored() {
+        let mut adapter = TwilioMediaAdapter::default();
+        assert_eq!(
+            adapter
+                .handle_message(r#"{"event":"connected","protocol":"Call","version":"1.0.0"}"#)
+                .unwrap(),
+            TwilioEvent::Ignored
+        );
+        assert_eq!(
+            adapter
+                .handle_message(r#"{"event":"transcription","streamSid":"MZ1"}"#)
+                .unwrap(),
+            TwilioEvent::Ignored
+        );
+    }
+
+    #[test]
+    fn test_out_of_band_dtmf() {
+        let mut adapter = TwilioMediaAdapter::default();
+        // Before start there's no handle to attribute the keypress to
+        assert_eq!(
+            adapter
+                .handle_message(r#"{"event":"dtmf","streamSid":"MZ1","dtmf":{"digit":"1"}}"#)
+                .unwrap(),
+            TwilioEvent::Ignored
+        );
+
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        let handle = adapter.handle_for("MZ1").unwrap();
+        let event = adapter
+            .handle_message(r##"{"event":"dtmf","streamSid":"MZ1","sequenceNumber":"5","dtmf":{"track":"inbound_track","digit":"#"}}"##)
+            .unwrap();
+        assert_eq!(
+            event,
+            TwilioEvent::Dtmf(DtmfEvent {
+                handle,
+                digit: '#',
+                source: DtmfSource::OutOfBand,
+            })
+        );
+    }
+
+    #[test]
+    fn test_in_band_and_out_of_band_dtmf_share_one_stream() {
+        let mut adapter = TwilioMediaAdapter::default();
+        let mut keypresses = adapter.subscribe_dtmf();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        let handle = adapter.handle_for("MZ1").unwrap();
+        assert_eq!(
+            adapter.input_chain("MZ1").unwrap().stage_names(),
+            vec!["dtmf"]
+        );
+
+        // 120ms of '5' (770 + 1336 Hz) in 20ms µ-law chunks
+        let tone: Vec<i16> = (0..960)
+            .map(|i| {
+                let t = i as f32 / TWILIO_SAMPLE_RATE as f32;
+                let wave = (2.0 * std::f32::consts::PI * 770.0 * t).sin()
+                    + (2.0 * std::f32::consts::PI * 1336.0 * t).sin();
+                (wave * 6000.0) as i16
+            })
+            .collect();
+        for (i, chunk) in tone.chunks(160).enumerate() {
+            let payload = G711Law::MuLaw.encode(chunk);
+            adapter
+                .handle_message(&media_msg("MZ1", i as u64 * 20, &payload))
+                .unwrap();
+        }
+        adapter
+            .handle_message(r#"{"event":"dtmf","streamSid":"MZ1","dtmf":{"digit":"9"}}"#)
+            .unwrap();
+
+        let in_band = keypresses.try_recv().unwrap();
+        assert_eq!(
+            in_band,
+            DtmfEvent {
+                handle,
+                digit: '5',
+                source: DtmfSource::InBand,
+            }
+        );
+        assert_eq!(keypresses.try_recv().unwrap().digit, '9');
+        assert!(keypresses.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_encode_media_envelope() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        let json = adapter.encode_media("MZ1", &[0i16; 320]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["event"], "media");
+        assert_eq!(value["streamSid"], "MZ1");
+        let payload = STANDARD
+            .decode(value["media"]["payload"].as_str().unwrap())
+            .unwrap();
+        // 320 samples at 16k → ~160 at 8k
+        assert!((159..=160).contains(&payload.len()));
+        assert!(payload.iter().all(|&b| mulaw_to_linear(b) == 0));
+    }
+
+    #[test]
+    fn test_comfort_noise_outbound() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        let json = adapter.comfort_noise("MZ1", 20).unwrap();
+        assert!(json.contains(r#""event":"media""#));
+        assert!(adapter.comfort_noise("MZ404", 20).is_err());
+    }
+
+    fn payload_samples(json: &str) -> Vec<i16> {
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        let bytes = STANDARD
+            .decode(value["media"]["payload"].as_str().unwrap())
+            .unwrap();
+        G711Law::MuLaw.decode(&bytes)
+    }
+
+    #[test]
+    fn test_encode_output_fills_gaps_only_when_enabled() {
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        assert_eq!(adapter.encode_output("MZ1", None, 20).unwrap(), None);
+
+        adapter.set_comfort_noise(Some(-50.0));
+        let noise = payload_samples(&adapter.encode_output("MZ1", None, 20).unwrap().unwrap());
+        assert_eq!(noise.len(), 160);
+        let peak = db_to_amplitude(-50.0);
+        assert!(noise.iter().all(|&s| s.abs() <= peak + peak / 8));
+        assert!(noise.iter().any(|&s| s != 0));
+    }
+
+    #[test]
+    fn test_speech_after_comfort_noise_has_no_overlap() {
+        let speech: Vec<i16> = (0..320).map(|i| ((i % 40) * 500 - 10000) as i16).collect();
+
+        let mut clean = TwilioMediaAdapter::default();
+        clean
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        let expected = clean.encode_output("MZ1", Some(&speech), 20).unwrap();
+
+        // Speech, then a noise gap, then speech again: the resumed frame is
+        // exactly what a fresh stream would send — no noise or old speech in it
+        let mut adapter = TwilioMediaAdapter::default();
+        adapter.set_comfort_noise(Some(-40.0));
+        adapter
+            .handle_message(&start_msg("MZ1", "audio/x-mulaw"))
+            .unwrap();
+        adapter.encode_output("MZ1", Some(&speech), 20).unwrap();
+        adapter.encode_output("MZ1", None, 20).unwrap();
+        let resumed = adapter.encode_output("MZ1", Some(&speech), 20).unwrap();
+        assert_eq!(resumed, expected);
+    }
+}
@@ -0,0 +1,366 @@
+//! WebRTC Opus Media Adapters
+//!
+//! Browser audio arrives as Opus over RTP, while the pipeline works in
+//! 16kHz i16 PCM. `WebRtcInputAdapter` decodes every inbound RTP stream,
+//! correlating each `ssrc` to a pipeline `Handle` on its first packet.
+//! `WebRtcOutputAdapter` encodes one outbound track's PCM into Opus packets.
+//! Opus codes 16kHz natively, so neither direction resamples.
+//!
+//! Packet loss is detected from RTP sequence numbers. The decoder conceals
+//! every lost packet (PLC), so its state stays in step with the sender and
+//! the stream keeps its length. With in-band FEC the packet after a gap
+//! also carries a low-bitrate copy of the one before it, which then stands
+//! in for the last concealed frame. Late and duplicate packets are dropped:
+//! decoding them out of order would desync the decoder.
+//!
+//! The call server uses them for clients that negotiate `opus` audio (see
+//! `audio_format`). Built with the `opus-codec` feature (links libopus).
+
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
+use crate::live::handle::Handle;
+use crate::live::types::{AudioFrame, Frame, FrameMeta};
+use crate::{clog_info, clog_warn};
+use opus::{Application, Bitrate, Channels, Decoder, Encoder};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Duration of each outbound Opus packet (WebRTC's default ptime)
+pub const OPUS_FRAME_MS: usize = 20;
+
+/// Samples in one outbound packet
+const OPUS_FRAME_SAMPLES: usize = AUDIO_SAMPLE_RATE as usize * OPUS_FRAME_MS / 1000;
+
+/// Longest Opus packet (120ms) — sizes the decode buffer
+const MAX_PACKET_SAMPLES: usize = AUDIO_SAMPLE_RATE as usize * 120 / 1000;
+
+/// Encoded packet size limit (one MTU)
+const MAX_PACKET_BYTES: usize = 1500;
+
+/// Conceal at most this many consecutive lost packets; after a longer gap
+/// the decoder is reset instead of synthesising seconds of audio
+const MAX_CONCEALED_PACKETS: u16 = 10;
+
+/// Outbound Opus settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpusConfig {
+    /// Target bitrate in bits/s
+    pub bitrate_bps: i32,
+    /// Carry in-band FEC for the previous frame in each packet
+    pub fec: bool,
+    /// Expected packet loss (0-100). Opus only spends bits on FEC when this
+    /// is non-zero.
+    pub packet_loss_pct: u8,
+}
+
+impl Default for OpusConfig {
+    fn default() -> Self {
+        Self {
+            bitrate_bps: 24_000,
+            fec: true,
+            packet_loss_pct: 10,
+        }
+    }
+}
+
+fn opus_error(context: &str, e: opus::Error) -> String {
+    format!("Opus {context} failed: {e}")
+}
+
+// ============================================================================
+// Inbound
+// ============================================================================
+
+/// Audio decoded from one RTP packet
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebRtcAudio {
+    pub handle: Handle,
+    /// Packets lost just before this one
+    pub lost_packets: u16,
+    /// 16kHz PCM: audio standing in for any lost packets, then this
+    /// packet's. `meta.seq` is the RTP sequence number extended past
+    /// wraparound, counted from the stream's first packet; `meta.pts_ms` is
+    /// the time of the first sample (concealment included).
+    pub frame: AudioFrame,
+}
+
+impl Frame for WebRtcAudio {
+    fn meta(&self) -> FrameMeta {
+        self.frame.meta
+    }
+}
+
+struct InboundStream {
+    handle: Handle,
+    decoder: Decoder,
+    next_sequence: u16,
+    /// `next_sequence` without wraparound
+    next_seq: u64,
+    /// Samples output so far, the stream's clock
+    samples_out: u64,
+    /// Length of the last decoded frame, the length PLC fills per lost packet
+    frame_samples: usize,
+}
+
+/// Decodes inbound Opus RTP payloads for any number of concurrent streams
+pub struct WebRtcInputAdapter {
+    fec: bool,
+    streams: HashMap<u32, InboundStream>,
+    buffer: Vec<i16>,
+}
+
+impl WebRtcInputAdapter {
+    /// `fec`: recover the packet before a gap from the FEC copy in the
+    /// packet after it (when the sender includes one), instead of PLC
+    pub fn new(fec: bool) -> Self {
+        Self {
+            fec,
+            streams: HashMap::new(),
+            buffer: vec![0; MAX_PACKET_SAMPLES],
+        }
+    }
+
+    /// Pipeline handle for an RTP stream, if it has sent anything
+    pub fn handle_for(&self, ssrc: u32) -> Option<Handle> {
+        self.streams.get(&ssrc).map(|s| s.handle)
+    }
+
+    pub fn stream_count(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Forget a stream (RTCP BYE or track ended), returning its handle
+    pub fn remove(&mut self, ssrc: u32) -> Option<Handle> {
+        self.streams.remove(&ssrc).map(|s| s.handle)
+    }
+
+    /// Decode one RTP packet's Opus payload. The first packet from an `ssrc`
+    /// starts its stream with a new handle. Ok(None) means the packet was
+    /// late or a duplicate and was dropped.
+    pub fn decode(
+        &mut self,
+        ssrc: u32,
+        sequence: u16,
+        payload: &[u8],
+    ) -> Result<Option<WebRtcAudio>, String> {
+        let stream = match self.streams.entry(ssrc) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let decoder = Decoder::new(AUDIO_SAMPLE_RATE, Channels::Mono)
+                    .map_err(|e| opus_error("decoder init", e))?;
+                let handle = Handle::new();
+                clog_info!("WebRTC stream ssrc={} → handle {}", ssrc, handle.short());
+                entry.insert(InboundStream {
+                    handle,
+                    decoder,
+                    next_sequence: sequence,
+                    next_seq: 0,
+                    samples_out: 0,
+                    frame_samples: OPUS_FRAME_SAMPLES,
+                })
+            }
+        };
+
+        let lost = sequence.wrapping_sub(stream.next_sequence);
+        if lost >= 0x8000 {
+            return Ok(None);
+        }
+
+        if lost > MAX_CONCEALED_PACKETS {
+            clog_warn!(
+                "WebRTC stream ssrc={} lost {} packets, resetting decoder",
+                ssrc,
+                lost
+            );
+            stream
+                .decoder
+                .reset_state()
+                .map_err(|e| opus_error("decoder reset", e))?;
+            // Nothing is output for the gap, but the clock still covers it
+            stream.samples_out += lost as u64 * stream.frame_samples as u64;
+        }
+        let meta = FrameMeta {
+            seq: stream.next_seq + lost as u64,
+            pts_ms: stream.samples_out * 1000 / AUDIO_SAMPLE_RATE as u64,
+        };
+        let buffer = &mut self.buffer;
+        let mut samples = Vec::new();
+        if lost > 0 && lost <= MAX_CONCEALED_PACKETS {
+            let frame = &mut buffer[..stream.frame_samples];
+            let concealed = if self.fec { lost - 1 } else { lost };
+            for _ in 0..concealed {
+                let n = stream
+                    .decoder
+                    .decode(&[], frame, false)
+                    .map_err(|e| opus_error("concealment", e))?;
+                samples.extend_from_slice(&frame[..n]);
+            }
+            if self.fec {
+                // Without FEC data in the packet this decodes as PLC
+                let n = stream
+                    .decoder
+                    .decode(payload, frame, true)
+                    .map_err(|e| opus_error("FEC decode", e))?;
+                samples.extend_from_slice(&frame[..n]);
+            }
+        }
+
+        let n = stream
+            .decoder
+            .decode(payload, buffer, false)
+            .map_err(|e| opus_error("decode", e))?;
+        samples.extend_from_slice(&buffer[..n]);
+        stream.frame_samples = n;
+        stream.next_sequence = sequence.wrapping_add(1);
+        stream.next_seq = meta.seq + 1;
+        stream.samples_out += samples.len() as u64;
+
+        Ok(Some(WebRtcAudio {
+            handle: stream.handle,
+            lost_packets: lost,
+            frame: AudioFrame { meta, samples },
+        }))
+    }
+}
+
+impl Default for WebRtcInputAdapter {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+// ============================================================================
+// Outbound
+// ============================================================================
+
+/// Encodes one outbound track's pipeline audio into Opus packets
+pub struct WebRtcOutputAdapter {
+    handle: Handle,
+    encoder: Encoder,
+    /// PCM waiting to fill the next packet
+    pending: Vec<i16>,
+}
+
+impl WebRtcOutputAdapter {
+    pub fn new(handle: Handle, config: OpusConfig) -> Result<Self, String> {
+        let encoder = Encoder::new(AUDIO_SAMPLE_RATE, Channels::Mono, Application::Voip)
+            .map_err(|e| opus_error("encoder init", e))?;
+        let mut adapter = Self {
+            handle,
+            encoder,
+            pending: Vec::with_capacity(OPUS_FRAME_SAMPLES * 2),
+        };
+        adapter.configure(config)?;
+        Ok(adapter)
+    }
+
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+
+    /// Apply settings mid-call, e.g. expected loss from receiver reports
+    pub fn configure(&mut self, config: OpusConfig) -> Result<(), String> {
+        self.encoder
+            .set_bitrate(Bitrate::Bits(config.bitrate_bps))
+            .map_err(|e| opus_error("set bitrate", e))?;
+        self.encoder
+            .set_inband_fec(config.fec)
+            .map_err(|e| opus_error("set FEC", e))?;
+        self.encoder
+            .set_packet_loss_perc(config.packet_loss_pct.min(100) as i32)
+            .map_err(|e| opus_error("set packet loss", e))
+    }
+
+    /// Encode pipeline PCM of any length into `OPUS_FRAME_MS` packets.
+    /// A trailing partial frame waits for the next call.
+    pub fn encode(&mut self, samples: &[i16]) -> Result<Vec<Vec<u8>>, String> {
+        self.pending.extend_from_slice(samples);
+        let mut packets = Vec::new();
+        for frame in self.pending.chunks_exact(OPUS_FRAME_SAMPLES) {
+            let packet = self
+                .encoder
+                .encode_vec(frame, MAX_PACKET_BYTES)
+                .map_err(|e| opus_error("encode", e))?;
+            packets.push(packet);
+        }
+        self.pending.drain(..packets.len() * OPUS_FRAME_SAMPLES);
+        Ok(packets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(frames: usize) -> Vec<i16> {
+        (0..frames * OPUS_FRAME_SAMPLES)
+            .map(|i| ((i as f32 * 440.0 * std::f32::consts::TAU / 16000.0).sin() * 8000.0) as i16)
+            .collect()
+    }
+
+    #[test]
+    fn test_output_packetizes_pipeline_frames() {
+        let mut output = WebRtcOutputAdapter::new(Handle::new(), OpusConfig::default()).unwrap();
+
+        // 512-sample pipeline frames don't line up with 20ms packets
+        let pcm = tone(4);
+        let first = output.encode(&pcm[..512]).unwrap();
+        assert_eq!(first.len(), 1);
+        let rest = output.encode(&pcm[512..]).unwrap();
+        assert_eq!(rest.len(), 3);
+        assert!(output.encode(&[0; 100]).unwrap().is_empty());
+
+        output
+            .configure(OpusConfig {
+                bitrate_bps: 12_000,
+                fec: false,
+                packet_loss_pct: 0,
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_input_conceals_loss_and_drops_late_packets() {
+        let mut output = WebRtcOutputAdapter::new(Handle::new(), OpusConfig::default()).unwrap();
+        let packets = output.encode(&tone(6)).unwrap();
+        let mut input = WebRtcInputAdapter::new(true);
+
+        let first = input.decode(1234, 100, &packets[0]).unwrap().unwrap();
+        assert_eq!(first.frame.samples.len(), OPUS_FRAME_SAMPLES);
+        assert_eq!(first.lost_packets, 0);
+        assert_eq!(first.meta(), FrameMeta::default());
+        assert_eq!(input.handle_for(1234), Some(first.handle));
+
+        // 101 and 102 lost: both are filled in, so the timeline stays intact
+        let after_gap = input.decode(1234, 103, &packets[3]).unwrap().unwrap();
+        assert_eq!(after_gap.lost_packets, 2);
+        assert_eq!(after_gap.frame.samples.len(), 3 * OPUS_FRAME_SAMPLES);
+        assert_eq!(after_gap.handle, first.handle);
+        assert_eq!(after_gap.frame.meta.seq, 3);
+        assert_eq!(after_gap.frame.meta.pts_ms, OPUS_FRAME_MS as u64);
+
+        // 102 turning up late is dropped
+        assert!(input.decode(1234, 102, &packets[2]).unwrap().is_none());
+        assert!(input.decode(1234, 103, &packets[3]).unwrap().is_none());
+
+        // Sequence numbers wrap
+        let mut input = WebRtcInputAdapter::new(false);
+        input.decode(7, u16::MAX, &packets[4]).unwrap();
+        let wrapped = input.decode(7, 0, &packets[5]).unwrap().unwrap();
+        assert_eq!(wrapped.lost_packets, 0);
+        assert_eq!(wrapped.frame.samples.len(), OPUS_FRAME_SAMPLES);
+        assert_eq!(wrapped.frame.meta.seq, 1);
+
+        // A gap too long to conceal resets the decoder; pts still covers it
+        let after_reset = input.decode(7, 20, &packets[0]).unwrap().unwrap();
+        assert_eq!(after_reset.lost_packets, 19);
+        assert_eq!(after_reset.frame.samples.len(), OPUS_FRAME_SAMPLES);
+        assert_eq!(after_reset.frame.meta.seq, 21);
+        assert_eq!(after_reset.frame.meta.pts_ms, 21 * OPUS_FRAME_MS as u64);
+
+        // A second ssrc is its own stream
+        let other = input.decode(8, 0, &packets[0]).unwrap().unwrap();
+        assert_ne!(other.handle, wrapped.handle);
+        assert_eq!(input.stream_count(), 2);
+        assert_eq!(input.remove(8), Some(other.handle));
+    }
+}
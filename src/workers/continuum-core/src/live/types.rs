@@ -1,3 +1,4 @@
+use crate::audio_constants::AUDIO_SAMPLE_RATE;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 use uuid::Uuid;
@@ -99,6 +100,69 @@ impl FrameKind {
     }
 }
 
+/// Timing every media frame carries, so stages can detect loss (a gap in
+/// `seq`) and line audio up with video (`pts_ms`) without knowing where
+/// the frame came from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameMeta {
+    /// Position in its stream, counting up from 0; a gap means frames
+    /// were lost
+    pub seq: u64,
+    /// Presentation timestamp, ms since the stream started
+    pub pts_ms: u64,
+}
+
+/// A media frame of any kind
+pub trait Frame {
+    fn meta(&self) -> FrameMeta;
+}
+
+/// A frame of 16kHz mono PCM and its timing
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioFrame {
+    pub meta: FrameMeta,
+    pub samples: Vec<i16>,
+}
+
+impl Frame for AudioFrame {
+    fn meta(&self) -> FrameMeta {
+        self.meta
+    }
+}
+
+/// Stamps frames for a stream whose transport carries no sequence numbers
+/// or timestamps of its own: one per stream, numbering frames in arrival
+/// order. Audio pts is derived from the samples already stamped, so it
+/// can't drift from the audio however irregularly frames arrive.
+#[derive(Debug, Clone, Default)]
+pub struct FrameSequencer {
+    next_seq: u64,
+    samples: u64,
+}
+
+impl FrameSequencer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wrap the stream's next run of 16kHz samples
+    pub fn audio(&mut self, samples: Vec<i16>) -> AudioFrame {
+        let pts_ms = self.samples * 1000 / AUDIO_SAMPLE_RATE as u64;
+        self.samples += samples.len() as u64;
+        AudioFrame {
+            meta: self.stamp(pts_ms),
+            samples,
+        }
+    }
+
+    /// Next sequence number, for a frame timed by its source
+    pub fn stamp(&mut self, pts_ms: u64) -> FrameMeta {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        FrameMeta { seq, pts_ms }
+    }
+}
+
 /// Video pixel format
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../../shared/generated/VideoPixelFormat.ts")]
@@ -115,6 +179,14 @@ pub enum VideoPixelFormat {
     JPEG,
 }
 
+impl VideoPixelFormat {
+    /// Formats where every frame is a complete picture (no inter-frame
+    /// prediction), so any frame is a valid point to start decoding
+    pub fn is_intra_only(&self) -> bool {
+        !matches!(self, Self::VP8 | Self::H264)
+    }
+}
+
 /// A video frame header — precedes the raw pixel/encoded data in a binary message.
 ///
 /// Wire format: [FrameKind::Video (1 byte)] [VideoFrameHeader (fixed)] [pixel data]
@@ -123,9 +195,9 @@ pub enum VideoPixelFormat {
 ///   bytes 0-1:  width  (u16)
 ///   bytes 2-3:  height (u16)
 ///   byte  4:    pixel_format (VideoPixelFormat as u8)
-///   byte  5:    flags (reserved, 0 for now)
-///   bytes 6-9:  timestamp_ms (u32, relative to call start)
-///   bytes 10-13: sequence (u32, frame counter)
+///   byte  5:    flags (bit 0 = keyframe, others reserved)
+///   bytes 6-9:  timestamp_ms (u32, relative to call start) — the frame's pts
+///   bytes 10-13: sequence (u32, frame counter) — the frame's index
 ///   bytes 14-15: reserved (0)
 #[derive(Debug, Clone, Copy)]
 pub struct VideoFrameHeader {
@@ -134,23 +206,38 @@ pub struct VideoFrameHeader {
     pub pixel_format: VideoPixelFormat,
     pub timestamp_ms: u32,
     pub sequence: u32,
+    /// Frame decodes on its own (start of a GOP). Inter-frames need the
+    /// frames since the last keyframe.
+    pub keyframe: bool,
 }
 
 impl VideoFrameHeader {
     pub const WIRE_SIZE: usize = 16;
+    /// Flags bit marking a keyframe
+    pub const FLAG_KEYFRAME: u8 = 0x01;
 
     pub fn encode(&self) -> [u8; Self::WIRE_SIZE] {
         let mut buf = [0u8; Self::WIRE_SIZE];
         buf[0..2].copy_from_slice(&self.width.to_le_bytes());
         buf[2..4].copy_from_slice(&self.height.to_le_bytes());
         buf[4] = self.pixel_format as u8;
-        buf[5] = 0; // flags reserved
+        buf[5] = if self.keyframe {
+            Self::FLAG_KEYFRAME
+        } else {
+            0
+        };
         buf[6..10].copy_from_slice(&self.timestamp_ms.to_le_bytes());
         buf[10..14].copy_from_slice(&self.sequence.to_le_bytes());
         buf[14..16].copy_from_slice(&[0, 0]); // reserved
         buf
     }
 
+    /// Whether a receiver can start decoding at this frame: flagged as a
+    /// keyframe, or in a format with no inter-frame prediction
+    pub fn is_keyframe(&self) -> bool {
+        self.keyframe || self.pixel_format.is_intra_only()
+    }
+
     pub fn decode(buf: &[u8]) -> Option<Self> {
         if buf.len() < Self::WIRE_SIZE {
             return None;
@@ -165,6 +252,7 @@ impl VideoFrameHeader {
             4 => VideoPixelFormat::JPEG,
             _ => return None,
         };
+        let keyframe = buf[5] & Self::FLAG_KEYFRAME != 0;
         let timestamp_ms = u32::from_le_bytes([buf[6], buf[7], buf[8], buf[9]]);
         let sequence = u32::from_le_bytes([buf[10], buf[11], buf[12], buf[13]]);
         Some(Self {
@@ -173,6 +261,7 @@ impl VideoFrameHeader {
             pixel_format,
             timestamp_ms,
             sequence,
+            keyframe,
         })
     }
 }
@@ -208,6 +297,30 @@ impl VideoFrame {
     pub fn data_size(&self) -> usize {
         self.data.len()
     }
+
+    /// Whether a receiver can start decoding at this frame
+    pub fn is_keyframe(&self) -> bool {
+        self.header.is_keyframe()
+    }
+
+    /// Position of this frame in its source's stream
+    pub fn frame_index(&self) -> u32 {
+        self.header.sequence
+    }
+
+    /// Presentation timestamp (ms since call start)
+    pub fn pts_ms(&self) -> u32 {
+        self.header.timestamp_ms
+    }
+}
+
+impl Frame for VideoFrame {
+    fn meta(&self) -> FrameMeta {
+        FrameMeta {
+            seq: self.header.sequence as u64,
+            pts_ms: self.header.timestamp_ms as u64,
+        }
+    }
 }
 
 /// Avatar animation state — sent from server to browser for driving avatar rendering.
@@ -304,6 +417,7 @@ mod frame_tests {
             pixel_format: VideoPixelFormat::VP8,
             timestamp_ms: 12345,
             sequence: 42,
+            keyframe: true,
         };
         let bytes = header.encode();
         assert_eq!(bytes.len(), VideoFrameHeader::WIRE_SIZE);
@@ -314,6 +428,13 @@ mod frame_tests {
         assert_eq!(decoded.pixel_format, VideoPixelFormat::VP8);
         assert_eq!(decoded.timestamp_ms, 12345);
         assert_eq!(decoded.sequence, 42);
+        assert!(decoded.keyframe);
+
+        let delta = VideoFrameHeader {
+            keyframe: false,
+            ..header
+        };
+        assert!(!VideoFrameHeader::decode(&delta.encode()).unwrap().keyframe);
     }
 
     #[test]
@@ -325,6 +446,7 @@ mod frame_tests {
                 pixel_format: VideoPixelFormat::JPEG,
                 timestamp_ms: 1000,
                 sequence: 1,
+                keyframe: false,
             },
             data: vec![0xFF, 0xD8, 0xFF, 0xE0], // JPEG magic bytes
         };
@@ -336,6 +458,42 @@ mod frame_tests {
         assert_eq!(decoded.header.width, 320);
         assert_eq!(decoded.header.height, 240);
         assert_eq!(decoded.data, vec![0xFF, 0xD8, 0xFF, 0xE0]);
+        assert_eq!(decoded.frame_index(), 1);
+        assert_eq!(decoded.pts_ms(), 1000);
+        assert_eq!(
+            decoded.meta(),
+            FrameMeta {
+                seq: 1,
+                pts_ms: 1000
+            }
+        );
+        // JPEG frames stand alone even without the keyframe flag
+        assert!(decoded.is_keyframe());
+
+        let vp8 = VideoFrame {
+            header: VideoFrameHeader {
+                pixel_format: VideoPixelFormat::VP8,
+                ..frame.header
+            },
+            data: vec![],
+        };
+        assert!(!vp8.is_keyframe());
+    }
+
+    #[test]
+    fn test_frame_sequencer_stamps_in_order() {
+        let mut sequencer = FrameSequencer::new();
+        // 20ms, then an irregular 10ms, then 20ms
+        let frames: Vec<AudioFrame> = [320, 160, 320]
+            .into_iter()
+            .map(|n| sequencer.audio(vec![0; n]))
+            .collect();
+        let metas: Vec<(u64, u64)> = frames
+            .iter()
+            .map(|f| (f.meta().seq, f.meta().pts_ms))
+            .collect();
+        assert_eq!(metas, vec![(0, 0), (1, 20), (2, 30)]);
+        assert_eq!(sequencer.stamp(40).seq, 3);
     }
 
     #[test]
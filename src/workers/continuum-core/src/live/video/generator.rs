@@ -90,6 +90,8 @@ impl TestPatternGenerator {
             pixel_format: VideoPixelFormat::RGBA8,
             timestamp_ms,
             sequence: self.sequence,
+            // Raw RGBA: every frame is a full picture
+            keyframe: true,
         };
 
         self.sequence += 1;
@@ -134,6 +136,7 @@ mod tests {
         assert_eq!(frame.header.height, 120);
         assert_eq!(frame.header.pixel_format, VideoPixelFormat::RGBA8);
         assert_eq!(frame.header.sequence, 0);
+        assert!(frame.is_keyframe());
         // 160 * 120 * 4 (RGBA) = 76800 bytes
         assert_eq!(frame.data.len(), 76800);
     }
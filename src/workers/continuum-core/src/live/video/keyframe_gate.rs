@@ -0,0 +1,135 @@
+//! Keyframe Gate
+//!
+//! Encoded video (VP8/H264) is a sequence of GOPs: a keyframe that decodes
+//! on its own, followed by inter-frames that only make sense relative to
+//! the frames before them. A receiver that joins mid-GOP, or that misses a
+//! frame, would decode garbage until the next keyframe.
+//!
+//! The gate sits on the receiving side of a video stream and holds back
+//! each sender's frames until one it can start decoding from arrives. A gap
+//! in a sender's frame indices breaks the reference chain, so the gate
+//! drops that sender back to waiting for a keyframe.
+
+use crate::live::handle::Handle;
+use crate::live::types::VideoFrameHeader;
+use std::collections::HashMap;
+
+/// Per-sender decode state
+#[derive(Debug, Clone, Copy)]
+struct SenderState {
+    /// A keyframe has been admitted and no frame has been missed since
+    synced: bool,
+    /// Index of the last frame seen from this sender
+    last_index: u32,
+}
+
+/// Drops each sender's frames until its next keyframe
+#[derive(Debug, Default)]
+pub struct KeyframeGate {
+    senders: HashMap<Handle, SenderState>,
+}
+
+impl KeyframeGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decide whether to forward a frame from `sender`.
+    /// `frame_data` is [VideoFrameHeader][pixel data]; frames with an
+    /// unreadable header are dropped.
+    pub fn admit(&mut self, sender: Handle, frame_data: &[u8]) -> bool {
+        let Some(header) = VideoFrameHeader::decode(frame_data) else {
+            return false;
+        };
+
+        let keyframe = header.is_keyframe();
+        let state = self.senders.entry(sender).or_insert(SenderState {
+            synced: false,
+            last_index: header.sequence,
+        });
+
+        if keyframe {
+            state.synced = true;
+        } else if state.synced && header.sequence != state.last_index.wrapping_add(1) {
+            // Missed a frame: later inter-frames reference something we never sent
+            state.synced = false;
+        }
+        state.last_index = header.sequence;
+        state.synced
+    }
+
+    /// Whether frames from `sender` are currently being forwarded
+    pub fn is_synced(&self, sender: &Handle) -> bool {
+        self.senders.get(sender).is_some_and(|s| s.synced)
+    }
+
+    /// Forget `sender` (left the call); its next stream starts unsynced
+    pub fn remove(&mut self, sender: &Handle) {
+        self.senders.remove(sender);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::live::types::{VideoFrame, VideoPixelFormat};
+
+    fn vp8(sequence: u32, keyframe: bool) -> Vec<u8> {
+        VideoFrame {
+            header: VideoFrameHeader {
+                width: 64,
+                height: 48,
+                pixel_format: VideoPixelFormat::VP8,
+                timestamp_ms: sequence * 33,
+                sequence,
+                keyframe,
+            },
+            data: vec![0; 8],
+        }
+        .to_bytes()
+    }
+
+    #[test]
+    fn test_join_mid_gop_waits_for_keyframe() {
+        let mut gate = KeyframeGate::new();
+        let sender = Handle::new();
+
+        // Joined mid-GOP: inter-frames are dropped
+        assert!(!gate.admit(sender, &vp8(5, false)));
+        assert!(!gate.admit(sender, &vp8(6, false)));
+        assert!(!gate.is_synced(&sender));
+
+        // Keyframe starts the stream, following inter-frames pass
+        assert!(gate.admit(sender, &vp8(7, true)));
+        assert!(gate.admit(sender, &vp8(8, false)));
+        assert!(gate.is_synced(&sender));
+
+        // Senders are tracked independently
+        let other = Handle::new();
+        assert!(!gate.admit(other, &vp8(100, false)));
+        assert!(gate.admit(sender, &vp8(9, false)));
+    }
+
+    #[test]
+    fn test_gap_resyncs_and_intra_formats_pass() {
+        let mut gate = KeyframeGate::new();
+        let sender = Handle::new();
+
+        assert!(gate.admit(sender, &vp8(0, true)));
+        assert!(gate.admit(sender, &vp8(1, false)));
+        // Frame 2 lost: 3 references it, so wait for the next keyframe
+        assert!(!gate.admit(sender, &vp8(3, false)));
+        assert!(!gate.admit(sender, &vp8(4, false)));
+        assert!(gate.admit(sender, &vp8(5, true)));
+
+        gate.remove(&sender);
+        assert!(!gate.is_synced(&sender));
+
+        // Raw RGBA frames decode on their own, flagged or not
+        let mut rgba = VideoFrame::from_bytes(&vp8(0, false)).unwrap();
+        rgba.header.pixel_format = VideoPixelFormat::RGBA8;
+        assert!(gate.admit(Handle::new(), &rgba.to_bytes()));
+
+        assert!(!gate.admit(sender, &[0x00, 0x01]));
+    }
+}
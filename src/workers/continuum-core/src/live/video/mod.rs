@@ -1,6 +1,7 @@
 pub mod bevy_renderer;
 pub mod capture;
 pub mod generator;
+pub mod keyframe_gate;
 pub mod memory_reporter;
 #[cfg(target_os = "macos")]
 pub mod metal_gpu_convert;
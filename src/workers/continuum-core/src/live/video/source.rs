@@ -20,6 +20,7 @@
 use crate::clog_info;
 use crate::live::handle::Handle;
 use crate::live::video::generator::TestPatternGenerator;
+use std::time::Instant;
 use tokio::sync::{broadcast, mpsc};
 
 /// Pluggable video source — anything that produces frames for a participant.
@@ -34,11 +35,14 @@ pub trait VideoSource: Send + 'static {
     fn user_id(&self) -> &str;
 
     /// Start producing frames. Called once. Spawns an internal loop.
+    /// Frames are timestamped in ms since `epoch`, the call's clock, so
+    /// they can be lined up with the call's audio.
     /// Returns a shutdown sender — send () to stop the generator.
     fn start(
         self: Box<Self>,
         video_tx: broadcast::Sender<(Handle, String, Vec<u8>)>,
         handle: Handle,
+        epoch: Instant,
     ) -> mpsc::Sender<()>;
 }
 
@@ -81,6 +85,7 @@ impl VideoSource for TestPatternSource {
         self: Box<Self>,
         video_tx: broadcast::Sender<(Handle, String, Vec<u8>)>,
         handle: Handle,
+        epoch: Instant,
     ) -> mpsc::Sender<()> {
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
         let interval_ms = 1000 / self.fps.max(1) as u64;
@@ -90,7 +95,6 @@ impl VideoSource for TestPatternSource {
             let mut generator = TestPatternGenerator::new(self.width, self.height);
             let mut interval =
                 tokio::time::interval(tokio::time::Duration::from_millis(interval_ms));
-            let start = std::time::Instant::now();
 
             clog_info!(
                 "{} video source started ({}x{} @{}fps, user_id={})",
@@ -104,7 +108,7 @@ impl VideoSource for TestPatternSource {
             loop {
                 tokio::select! {
                     _ = interval.tick() => {
-                        let timestamp_ms = start.elapsed().as_millis() as u32;
+                        let timestamp_ms = epoch.elapsed().as_millis() as u32;
                         let frame = generator.next_frame(timestamp_ms);
                         let frame_bytes = frame.to_bytes();
 
@@ -154,7 +158,7 @@ mod tests {
         let (video_tx, mut video_rx) = broadcast::channel::<(Handle, String, Vec<u8>)>(16);
         let handle = Handle::new();
 
-        let shutdown = source.start(video_tx, handle);
+        let shutdown = source.start(video_tx, handle, Instant::now());
 
         // Wait for at least one frame
         tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
@@ -14,6 +14,9 @@ static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 #[unsafe(export_name = "malloc_conf")]
 pub static malloc_conf: &[u8] = b"dirty_decay_ms:1000,muzzy_decay_ms:2000\0";
 
+use continuum_core::live::transport::call_server::{
+    start_call_server, CallManager, CALL_SERVER_PORT_ENV,
+};
 use continuum_core::live::transport::livekit_agent::LiveKitAgentManager;
 use continuum_core::memory::{ModuleBackedEmbeddingProvider, PersonaMemoryManager};
 /// Continuum Core Server - Unified Modular Rust Runtime
@@ -29,6 +32,7 @@ use continuum_core::memory::{ModuleBackedEmbeddingProvider, PersonaMemoryManager
 ///
 /// Usage: continuum-core-server <socket-path>
 /// Example: continuum-core-server /tmp/continuum-core.sock
+use continuum_core::runtime::metrics_endpoint;
 use continuum_core::start_server;
 use std::env;
 use std::sync::Arc;
@@ -196,6 +200,34 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
+    // WebSocket call server, only when CONTINUUM_CALL_SERVER_PORT is set
+    let call_manager = metrics_endpoint::listen_addr_from_env(CALL_SERVER_PORT_ENV).map(|addr| {
+        let manager = Arc::new(CallManager::new());
+        let server_manager = manager.clone();
+        tokio::spawn(async move {
+            if let Err(e) = start_call_server(&addr, server_manager).await {
+                tracing::warn!("📞 Call server not available: {}", e);
+            }
+        });
+        manager
+    });
+
+    // Prometheus scrape target, only when CONTINUUM_METRICS_PORT is set
+    if let Some(addr) = metrics_endpoint::metrics_addr_from_env() {
+        let mut sources: Vec<Arc<dyn metrics_endpoint::MetricsSource>> = vec![
+            Arc::new(metrics_endpoint::ProcessMetrics),
+            livekit_manager.clone(),
+        ];
+        if let Some(manager) = &call_manager {
+            sources.push(manager.clone());
+        }
+        tokio::spawn(async move {
+            if let Err(e) = metrics_endpoint::serve_metrics(&addr, sources).await {
+                tracing::warn!("📈 Metrics endpoint not available: {}", e);
+            }
+        });
+    }
+
     // Install signal handlers BEFORE declaring ready — ensures cleanup on any exit path
     install_shutdown_handlers();
 
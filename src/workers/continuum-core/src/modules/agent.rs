@@ -625,6 +625,12 @@ async fn call_llm(
         top_p: None,
         top_k: None,
         stop_sequences: None,
+        repeat_penalty: None,
+        repeat_last_n: None,
+        handle_id: None,
+        reset_context: None,
+        logprobs: None,
+        seed: None,
         tools: None,
         tool_choice: None,
         request_id: None,
@@ -15,12 +15,19 @@
 //!
 //! Commands:
 //! - ai/generate: Generate text with optional tool calling
+//! - ai/generate/stream: Same, streamed as `{"token","done":false}` chunks
+//!   ending with `{"done":true,"generated_tokens":N}`
+//! - ai/generate/batch: Many prompts with shared settings in one call;
+//!   per-prompt results (an error fails only its own entry)
+//! - ai/tokenize: Token IDs for `text` using a loaded local model's tokenizer
+//! - ai/detokenize: Text for a list of token IDs
 //! - ai/providers/list: List available providers
 //! - ai/providers/health: Check provider health
 
 use crate::ai::{
     AdapterRegistry, AnthropicAdapter, CandleAdapter, ChatMessage, MessageContent,
-    OpenAICompatibleAdapter, RoutingInfo, TextGenerationRequest, TextGenerationResponse,
+    OpenAICompatibleAdapter, RoutingInfo, TextCallback, TextGenerationRequest,
+    TextGenerationResponse,
 };
 use crate::logging::TimingGuard;
 use crate::runtime::{
@@ -33,6 +40,7 @@ use once_cell::sync::Lazy;
 use serde_json::{json, Value};
 use std::any::Any;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{OnceCell, RwLock};
 
 /// Global singleton registry - survives module recreation on server restart
@@ -42,12 +50,79 @@ static GLOBAL_REGISTRY: Lazy<Arc<RwLock<AdapterRegistry>>> =
 /// Track if we've done first-time initialization
 static INITIALIZED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 
+/// Upper bound on prompts per `ai/generate/batch` call.
+const MAX_BATCH_PROMPTS: usize = 1024;
+
+/// Default for `INFERENCE_IDLE_TIMEOUT_SECS`: 0 keeps local models resident.
+/// Deployments that want them unloaded after N idle seconds opt in.
+const DEFAULT_IDLE_TIMEOUT_SECS: u64 = 0;
+
+/// Chunks buffered ahead of a slow `ai/generate/stream` client
+const STREAM_CHUNK_BUFFER: usize = 64;
+
+/// Idle timeout for local models, from the environment.
+fn idle_timeout_from_env() -> Option<Duration> {
+    let secs = std::env::var("INFERENCE_IDLE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_IDLE_TIMEOUT_SECS);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// `ai/generate/batch` response: one entry per prompt, in prompt order.
+fn batch_to_json(
+    provider_id: &str,
+    outcomes: Vec<Result<TextGenerationResponse, String>>,
+    response_time_ms: u64,
+) -> Value {
+    let mut total_output_tokens = 0u64;
+    let mut succeeded = 0usize;
+    let results: Vec<Value> = outcomes
+        .into_iter()
+        .enumerate()
+        .map(|(index, outcome)| match outcome {
+            Ok(response) => {
+                succeeded += 1;
+                total_output_tokens += response.usage.output_tokens as u64;
+                json!({
+                    "index": index,
+                    "success": true,
+                    "text": response.text,
+                    "finishReason": format!("{}", response.finish_reason),
+                    "inputTokens": response.usage.input_tokens,
+                    "outputTokens": response.usage.output_tokens,
+                    "responseTimeMs": response.response_time_ms
+                })
+            }
+            Err(e) => json!({
+                "index": index,
+                "success": false,
+                "error": e
+            }),
+        })
+        .collect();
+
+    json!({
+        "success": succeeded > 0,
+        "provider": provider_id,
+        "count": results.len(),
+        "succeeded": succeeded,
+        "totalOutputTokens": total_output_tokens,
+        "responseTimeMs": response_time_ms,
+        "results": results
+    })
+}
+
 /// AIProviderModule - ServiceModule implementation for AI inference
 pub struct AIProviderModule {
     registry: Arc<RwLock<AdapterRegistry>>,
     log: OnceCell<Arc<ModuleLogger>>,
     /// GPU memory manager — passed to CandleAdapter for VRAM allocation tracking.
     gpu_manager: Option<Arc<crate::gpu::memory_manager::GpuMemoryManager>>,
+    /// Unload local models idle this long (checked on tick). None disables.
+    idle_timeout: Option<Duration>,
+    /// Passed to CandleAdapter, which registers loaded models' tokenizers.
+    rag_engine: Option<Arc<crate::rag::RagEngine>>,
 }
 
 impl AIProviderModule {
@@ -56,6 +131,8 @@ impl AIProviderModule {
             registry: GLOBAL_REGISTRY.clone(),
             log: OnceCell::new(),
             gpu_manager: None,
+            idle_timeout: idle_timeout_from_env(),
+            rag_engine: None,
         }
     }
 
@@ -67,9 +144,17 @@ impl AIProviderModule {
             registry: GLOBAL_REGISTRY.clone(),
             log: OnceCell::new(),
             gpu_manager: Some(gpu_manager),
+            idle_timeout: idle_timeout_from_env(),
+            rag_engine: None,
         }
     }
 
+    /// Register local models with `rag_engine` as they load.
+    pub fn with_rag_engine(mut self, rag_engine: Arc<crate::rag::RagEngine>) -> Self {
+        self.rag_engine = Some(rag_engine);
+        self
+    }
+
     /// Get logger (panics if called before initialize)
     fn log(&self) -> &ModuleLogger {
         self.log
@@ -162,6 +247,9 @@ impl AIProviderModule {
             if let Some(mgr) = &self.gpu_manager {
                 candle.set_gpu_manager(mgr.clone());
             }
+            if let Some(rag) = &self.rag_engine {
+                candle.set_rag_engine(rag.clone());
+            }
             registry.register(Box::new(candle), priority);
         }
 
@@ -217,6 +305,16 @@ impl AIProviderModule {
             stop_sequences: p
                 .json_opt("stop_sequences")
                 .or_else(|| p.json_opt("stopSequences")),
+            repeat_penalty: p
+                .f64_opt_alias("repeat_penalty", "repeatPenalty")
+                .map(|v| v as f32),
+            repeat_last_n: p
+                .u64_opt_alias("repeat_last_n", "repeatLastN")
+                .map(|v| v as u32),
+            handle_id: p.string_opt_alias("handle_id", "handleId"),
+            reset_context: p.bool_opt_alias("reset_context", "resetContext"),
+            logprobs: p.u64_opt("logprobs").map(|n| n as u32),
+            seed: p.u64_opt("seed"),
             tools: p.json_opt("tools"),
             tool_choice: p.json_opt("tool_choice"),
             active_adapters: p.json_opt("activeAdapters"),
@@ -260,6 +358,11 @@ impl AIProviderModule {
             result["routing"] = serde_json::to_value(routing).unwrap_or(json!({}));
         }
 
+        // Add per-token logprobs if requested
+        if let Some(logprobs) = &response.logprobs {
+            result["logprobs"] = serde_json::to_value(logprobs).unwrap_or(json!([]));
+        }
+
         result
     }
 }
@@ -280,7 +383,10 @@ impl ServiceModule for AIProviderModule {
             event_subscriptions: &[],
             needs_dedicated_thread: false,
             max_concurrency: 10, // Allow parallel inference requests
-            tick_interval: None,
+            // Check for idle local models a few times per timeout window
+            tick_interval: self
+                .idle_timeout
+                .map(|t| (t / 4).clamp(Duration::from_secs(1), Duration::from_secs(60))),
         }
     }
 
@@ -290,6 +396,24 @@ impl ServiceModule for AIProviderModule {
         self.register_adapters().await
     }
 
+    /// Idle eviction: ask every adapter to release resources unused for
+    /// `idle_timeout`. Adapters skip anything mid-generation.
+    async fn tick(&self) -> Result<(), String> {
+        let Some(idle_timeout) = self.idle_timeout else {
+            return Ok(());
+        };
+        let registry = self.registry.read().await;
+        for id in registry.available() {
+            if let Some(adapter) = registry.get(id) {
+                if adapter.release_if_idle(idle_timeout) {
+                    self.log()
+                        .info(&format!("Released idle resources for provider '{}'", id));
+                }
+            }
+        }
+        Ok(())
+    }
+
     async fn handle_command(&self, command: &str, params: Value) -> Result<CommandResult, String> {
         match command {
             "ai/generate" => {
@@ -345,6 +469,185 @@ impl ServiceModule for AIProviderModule {
                 Ok(CommandResult::Json(self.response_to_json(&response)))
             }
 
+            "ai/generate/stream" => {
+                let request = self.parse_request(&params)?;
+
+                // Fail fast if nothing can serve the request
+                if self
+                    .registry
+                    .read()
+                    .await
+                    .select(request.provider.as_deref(), request.model.as_deref())
+                    .is_none()
+                {
+                    return Err(format!(
+                        "Requested provider/model not available. Available: {:?}",
+                        self.registry.read().await.available()
+                    ));
+                }
+
+                // The token callback is sync and runs on the runtime, so it
+                // can't block on the bounded stream; a forwarder feeds it.
+                let (tx, mut tokens) = tokio::sync::mpsc::unbounded_channel();
+                let (stream_tx, rx) = tokio::sync::mpsc::channel(STREAM_CHUNK_BUFFER);
+                tokio::spawn(async move {
+                    while let Some(chunk) = tokens.recv().await {
+                        if stream_tx.send(chunk).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+                let registry = self.registry.clone();
+                let log = self
+                    .log
+                    .get()
+                    .cloned()
+                    .ok_or("AIProviderModule not initialized")?;
+
+                tokio::spawn(async move {
+                    let registry = registry.read().await;
+                    let Some((provider_id, adapter)) =
+                        registry.select(request.provider.as_deref(), request.model.as_deref())
+                    else {
+                        let _ = tx.send(json!({ "done": true, "error": "Provider unavailable" }));
+                        return;
+                    };
+                    log.info(&format!(
+                        "Streaming via {} adapter for model {:?}",
+                        provider_id, request.model
+                    ));
+
+                    // A failed send means the client is gone — returning false
+                    // cancels generation instead of running to max_tokens.
+                    let token_tx = tx.clone();
+                    let on_text: TextCallback = Box::new(move |token: &str| {
+                        token_tx
+                            .send(json!({ "token": token, "done": false }))
+                            .is_ok()
+                    });
+
+                    let final_chunk = match adapter.generate_text_stream(request, on_text).await {
+                        Ok(response) => json!({
+                            "done": true,
+                            "generated_tokens": response.usage.output_tokens,
+                            "finishReason": format!("{}", response.finish_reason),
+                            "model": response.model,
+                            "provider": provider_id,
+                        }),
+                        Err(e) => json!({ "done": true, "error": e }),
+                    };
+                    let _ = tx.send(final_chunk);
+                });
+
+                Ok(CommandResult::Stream(rx))
+            }
+
+            "ai/generate/batch" => {
+                let _timer = TimingGuard::new("module", "ai_generate_batch");
+                let start = std::time::Instant::now();
+
+                let prompts: Vec<String> = Params::new(&params).json("prompts")?;
+                if prompts.is_empty() {
+                    return Err("prompts cannot be empty".to_string());
+                }
+                if prompts.len() > MAX_BATCH_PROMPTS {
+                    return Err(format!(
+                        "Batch of {} prompts exceeds limit of {}",
+                        prompts.len(),
+                        MAX_BATCH_PROMPTS
+                    ));
+                }
+
+                // Every prompt shares the remaining settings; parse once per
+                // prompt so each gets its own request id and message list.
+                let mut shared = params.clone();
+                if let Some(obj) = shared.as_object_mut() {
+                    obj.remove("prompts");
+                    obj.remove("messages");
+                    if !obj.contains_key("model") {
+                        if let Some(model) = obj.get("model_id").or_else(|| obj.get("modelId")) {
+                            obj.insert("model".to_string(), model.clone());
+                        }
+                    }
+                }
+                let requests = prompts
+                    .iter()
+                    .map(|prompt| {
+                        shared["prompt"] = json!(prompt);
+                        self.parse_request(&shared)
+                    })
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                let registry = self.registry.read().await;
+                let (provider_id, adapter) = registry
+                    .select(
+                        requests[0].provider.as_deref(),
+                        requests[0].model.as_deref(),
+                    )
+                    .ok_or_else(|| {
+                        format!(
+                            "Requested provider/model not available. Available: {:?}",
+                            registry.available()
+                        )
+                    })?;
+                self.log().info(&format!(
+                    "Batch of {} prompts on {} adapter",
+                    requests.len(),
+                    provider_id
+                ));
+
+                let outcomes = adapter.generate_text_batch(requests).await;
+                Ok(CommandResult::Json(batch_to_json(
+                    provider_id,
+                    outcomes,
+                    start.elapsed().as_millis() as u64,
+                )))
+            }
+
+            "ai/tokenize" => {
+                let p = Params::new(&params);
+                let text = p.str("text")?;
+                let model = p.str_opt_alias("model", "model_id");
+                // Defaults match generation, which applies its own chat template
+                let add_special_tokens = p
+                    .bool_opt_alias("add_special_tokens", "addSpecialTokens")
+                    .unwrap_or(false);
+
+                let registry = self.registry.read().await;
+                let (provider_id, adapter) = registry
+                    .select(p.str_opt("provider").or(Some("candle")), model)
+                    .ok_or("No local provider available for tokenization")?;
+                let tokens = adapter.tokenize(model, text, add_special_tokens)?;
+
+                Ok(CommandResult::Json(json!({
+                    "success": true,
+                    "count": tokens.len(),
+                    "tokens": tokens,
+                    "provider": provider_id
+                })))
+            }
+
+            "ai/detokenize" => {
+                let p = Params::new(&params);
+                let tokens: Vec<u32> = p.json("tokens")?;
+                let model = p.str_opt_alias("model", "model_id");
+                let skip_special_tokens = p
+                    .bool_opt_alias("skip_special_tokens", "skipSpecialTokens")
+                    .unwrap_or(true);
+
+                let registry = self.registry.read().await;
+                let (provider_id, adapter) = registry
+                    .select(p.str_opt("provider").or(Some("candle")), model)
+                    .ok_or("No local provider available for detokenization")?;
+                let text = adapter.detokenize(model, &tokens, skip_special_tokens)?;
+
+                Ok(CommandResult::Json(json!({
+                    "success": true,
+                    "text": text,
+                    "provider": provider_id
+                })))
+            }
+
             "ai/providers/list" => {
                 let registry = self.registry.read().await;
                 let available = registry.available();
@@ -536,3 +839,114 @@ pub async fn generate_text(
 
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{
+        AIProviderAdapter, AdapterCapabilities, ApiStyle, FinishReason, HealthStatus, ModelInfo,
+        UsageMetrics,
+    };
+
+    /// Echoes the prompt back; prompts containing "fail" error out.
+    struct EchoAdapter;
+
+    #[async_trait]
+    impl AIProviderAdapter for EchoAdapter {
+        fn provider_id(&self) -> &str {
+            "echo"
+        }
+        fn name(&self) -> &str {
+            "Echo"
+        }
+        fn capabilities(&self) -> AdapterCapabilities {
+            AdapterCapabilities::default()
+        }
+        fn api_style(&self) -> ApiStyle {
+            ApiStyle::OpenAI
+        }
+        fn default_model(&self) -> &str {
+            "echo"
+        }
+        async fn initialize(&mut self) -> Result<(), String> {
+            Ok(())
+        }
+        async fn shutdown(&mut self) -> Result<(), String> {
+            Ok(())
+        }
+        async fn generate_text(
+            &self,
+            request: TextGenerationRequest,
+        ) -> Result<TextGenerationResponse, String> {
+            let prompt = request.messages[0].content_text();
+            if prompt.contains("fail") {
+                return Err(format!("cannot answer {prompt}"));
+            }
+            let words = prompt.split_whitespace().count() as u32;
+            Ok(TextGenerationResponse {
+                text: prompt,
+                finish_reason: FinishReason::Stop,
+                model: "echo".to_string(),
+                provider: "echo".to_string(),
+                usage: UsageMetrics {
+                    input_tokens: words,
+                    output_tokens: words,
+                    total_tokens: words * 2,
+                    estimated_cost: None,
+                },
+                response_time_ms: 1,
+                request_id: String::new(),
+                content: None,
+                tool_calls: None,
+                routing: None,
+                logprobs: None,
+                error: None,
+            })
+        }
+        async fn health_check(&self) -> HealthStatus {
+            HealthStatus::default()
+        }
+        async fn get_available_models(&self) -> Vec<ModelInfo> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_generate_batch_reports_each_prompt() {
+        let module = AIProviderModule::new();
+        let requests = ["hello there", "please fail", "one two three"]
+            .iter()
+            .map(|prompt| module.parse_request(&json!({ "prompt": prompt, "max_tokens": 16 })))
+            .collect::<Result<Vec<_>, String>>()
+            .unwrap();
+
+        let outcomes = EchoAdapter.generate_text_batch(requests).await;
+        let body = batch_to_json("echo", outcomes, 3);
+
+        assert_eq!(body["success"], true);
+        assert_eq!(body["count"], 3);
+        assert_eq!(body["succeeded"], 2);
+        assert_eq!(body["totalOutputTokens"], 5);
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results[0]["text"], "hello there");
+        assert_eq!(results[0]["outputTokens"], 2);
+        assert_eq!(results[1]["success"], false);
+        assert!(results[1]["error"]
+            .as_str()
+            .unwrap()
+            .contains("please fail"));
+        assert_eq!(results[2]["index"], 2);
+        assert_eq!(results[2]["outputTokens"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_generate_batch_rejects_empty_prompts() {
+        let module = AIProviderModule::new();
+        let err = module
+            .handle_command("ai/generate/batch", json!({ "prompts": [] }))
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("empty"));
+    }
+}
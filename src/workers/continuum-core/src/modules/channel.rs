@@ -5,7 +5,8 @@
 //! stateless HealthModule.
 //!
 //! Handles: channel/enqueue, channel/dequeue, channel/status,
-//!          channel/service-cycle, channel/service-cycle-full, channel/clear
+//!          channel/service-cycle, channel/service-cycle-full, channel/clear,
+//!          channel/tick-config, channel/mood-prosody
 
 use crate::log_info;
 use crate::logging::TimingGuard;
@@ -14,7 +15,7 @@ use crate::persona::channel_types::DOMAIN_PRIORITY_ORDER;
 use crate::persona::self_task_generator::SelfTaskGenerator;
 use crate::persona::{
     ActivityDomain, ChannelEnqueueRequest, ChannelRegistry, InboxMessage, Modality,
-    PersonaCognition, PersonaState, SenderType,
+    MoodProsodyTable, PersonaCognition, PersonaState, SenderType,
 };
 use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
 use crate::utils::params::Params;
@@ -153,6 +154,7 @@ impl ServiceModule for ChannelModule {
                         .map_err(|e| format!("Invalid item: {e}"))?;
 
                 let queue_item = enqueue_request.to_queue_item()?;
+                let priority = queue_item.base_priority();
 
                 let mut entry = self
                     .state
@@ -164,9 +166,16 @@ impl ServiceModule for ChannelModule {
                 match registry.route(queue_item) {
                     Ok(domain) => {
                         let status = registry.status();
+                        // Urgent arrivals preempt the message being processed
+                        let preempted = self
+                            .state
+                            .personas
+                            .get(&persona_uuid)
+                            .is_some_and(|persona| persona.inbox.preempt_for(priority));
                         Ok(CommandResult::Json(serde_json::json!({
                             "routed_to": domain,
                             "status": status,
+                            "preempted": preempted,
                         })))
                     }
                     Err(e) => Err(e),
@@ -312,9 +321,11 @@ impl ServiceModule for ChannelModule {
                             voice_session_id: ip.uuid_opt("voiceSessionId"),
                         };
 
-                        // Get cognition engine for fast-path decision
+                        // Get cognition engine for fast-path decision. The item
+                        // is now in flight, so urgent arrivals can preempt it.
                         if let Some(persona) = self.state.personas.get(&persona_uuid) {
                             let decision = persona.engine.fast_path_decision(&inbox_msg);
+                            persona.inbox.begin(inbox_msg);
                             Some(serde_json::json!({
                                 "should_respond": decision.should_respond,
                                 "confidence": decision.confidence,
@@ -329,6 +340,10 @@ impl ServiceModule for ChannelModule {
                         None
                     }
                 } else {
+                    // Queue drained: nothing is in flight any more
+                    if let Some(persona) = self.state.personas.get(&persona_uuid) {
+                        persona.inbox.complete();
+                    }
                     None
                 };
 
@@ -402,6 +417,43 @@ impl ServiceModule for ChannelModule {
                 ))
             }
 
+            "channel/mood-prosody" => {
+                let _timer = TimingGuard::new("module", "channel_mood_prosody");
+                let persona_uuid = p.uuid("persona_id")?;
+                // Optional: replace the persona's mood → prosody table
+                let table: Option<MoodProsodyTable> = match p.value("table") {
+                    Some(_) => Some(p.json("table")?),
+                    None => None,
+                };
+                if let Some(table) = &table {
+                    table.validate()?;
+                }
+
+                let mut entry = self
+                    .state
+                    .registries
+                    .entry(persona_uuid)
+                    .or_insert_with(|| (ChannelRegistry::new(), PersonaState::new()));
+                let (_registry, state) = entry.value_mut();
+                if let Some(table) = table {
+                    state.mood_prosody = table;
+                    log_info!(
+                        "module",
+                        "channel",
+                        "Mood prosody updated for {}",
+                        persona_uuid
+                    );
+                }
+
+                let prosody = state.voice_prosody();
+                Ok(CommandResult::Json(serde_json::json!({
+                    "table": state.mood_prosody,
+                    "mood": state.mood,
+                    "speed": prosody.speed,
+                    "pitch_semitones": prosody.pitch_semitones,
+                })))
+            }
+
             _ => Err(format!("Unknown channel command: {command}")),
         }
     }
@@ -24,6 +24,10 @@
 //! - `cognition/genome-state`: Get current genome paging state
 //! - `cognition/check-adequacy`: Batch adequacy check
 //! - `inbox/create`: Create persona inbox (alias for create-engine)
+//! - `inbox/next`: Start the next message (resumes preempted ones)
+//! - `inbox/should-yield`: Checkpoint poll: has the in-flight message been preempted?
+//! - `inbox/suspend`: Yield the in-flight message with its checkpoint
+//! - `inbox/complete`: Finish the in-flight message
 //!
 //! Uses `Params` helper for typed parameter extraction.
 
@@ -220,11 +224,12 @@ impl ServiceModule for CognitionModule {
                 let inbox_msg = parse_inbox_message(message)?;
 
                 let persona = get_or_create_persona!(self, persona_uuid);
-                persona.inbox.enqueue(inbox_msg);
+                let preempted = persona.inbox.enqueue(inbox_msg);
 
                 Ok(CommandResult::Json(serde_json::json!({
                     "enqueued": true,
                     "queue_size": persona.inbox.len(),
+                    "preempted": preempted,
                 })))
             }
 
@@ -255,11 +260,90 @@ impl ServiceModule for CognitionModule {
                 let _timer = TimingGuard::new("module", "inbox_create");
                 let persona_uuid = p.uuid("persona_id")?;
                 // Ensure persona exists with all state (inbox is part of PersonaCognition)
-                get_or_create_persona!(self, persona_uuid);
+                let mut persona = get_or_create_persona!(self, persona_uuid);
+                if let Some(margin) = p.f32_opt("preempt_margin") {
+                    persona.inbox.set_preempt_margin(margin);
+                }
                 log_info!("module", "cognition", "Ensured inbox for {}", persona_uuid);
                 Ok(CommandResult::Json(serde_json::json!({ "created": true })))
             }
 
+            // ================================================================
+            // Inbox Processing + Preemption
+            // ================================================================
+            "inbox/next" => {
+                let _timer = TimingGuard::new("module", "inbox_next");
+                let persona_uuid = p.uuid("persona_id")?;
+
+                let persona = self
+                    .state
+                    .personas
+                    .get(&persona_uuid)
+                    .ok_or_else(|| format!("No cognition for {persona_uuid}"))?;
+
+                let next = persona.inbox.next();
+                Ok(CommandResult::Json(serde_json::json!({
+                    "next": next,
+                    "queue_size": persona.inbox.len(),
+                    "suspended": persona.inbox.suspended_count(),
+                })))
+            }
+
+            "inbox/should-yield" => {
+                let persona_uuid = p.uuid("persona_id")?;
+
+                let persona = self
+                    .state
+                    .personas
+                    .get(&persona_uuid)
+                    .ok_or_else(|| format!("No cognition for {persona_uuid}"))?;
+
+                Ok(CommandResult::Json(serde_json::json!({
+                    "should_yield": persona.inbox.should_yield(),
+                })))
+            }
+
+            "inbox/suspend" => {
+                let _timer = TimingGuard::new("module", "inbox_suspend");
+                let persona_uuid = p.uuid("persona_id")?;
+                let checkpoint = p.value("checkpoint").cloned().unwrap_or(Value::Null);
+
+                let persona = self
+                    .state
+                    .personas
+                    .get(&persona_uuid)
+                    .ok_or_else(|| format!("No cognition for {persona_uuid}"))?;
+
+                let message = persona.inbox.suspend(checkpoint)?;
+                log_info!(
+                    "module",
+                    "cognition",
+                    "inbox/suspend {}: preempted message {} (priority {:.2})",
+                    persona_uuid,
+                    message.id,
+                    message.priority
+                );
+                Ok(CommandResult::Json(serde_json::json!({
+                    "suspended": true,
+                    "message_id": message.id.to_string(),
+                })))
+            }
+
+            "inbox/complete" => {
+                let persona_uuid = p.uuid("persona_id")?;
+
+                let persona = self
+                    .state
+                    .personas
+                    .get(&persona_uuid)
+                    .ok_or_else(|| format!("No cognition for {persona_uuid}"))?;
+
+                persona.inbox.complete();
+                Ok(CommandResult::Json(
+                    serde_json::json!({ "completed": true }),
+                ))
+            }
+
             // ================================================================
             // Message Deduplication (single source of truth in Rust)
             // ================================================================
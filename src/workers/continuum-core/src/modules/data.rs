@@ -1,6 +1,10 @@
 //! DataModule — Storage and ORM operations via the StorageAdapter trait.
 //!
-//! Handles: data/* commands (create, read, update, delete, query, batch)
+//! Handles: data/* commands (create, read, update, delete, query, batch,
+//! create-batch for bulk inserts in one transaction)
+//! Transactions: data/begin-transaction, then create/update/delete with
+//! `transactionId` stage ops, data/commit applies them atomically,
+//! data/rollback (or the IPC connection closing) discards them.
 //! Also handles: vector/* commands (vector similarity search with in-memory caching)
 //! Uses the ORM module's StorageAdapter trait for database-agnostic operations.
 //!
@@ -9,12 +13,15 @@
 
 use crate::modules::embedding::generate_embeddings_batch;
 use crate::orm::{
-    adapter::{AdapterConfig, StorageAdapter},
+    adapter::{AdapterConfig, AdapterType, StorageAdapter},
     migration::{MigrationConfig, MigrationEngine, MigrationHandle},
     postgres::PostgresAdapter,
-    query::{FieldFilter, StorageQuery},
+    query::{keyset_sort, FieldFilter, KeysetCursor, StorageQuery},
     sqlite::SqliteAdapter,
-    types::{BatchOperation, CollectionSchema, DataRecord, RecordMetadata, UUID},
+    types::{
+        BatchOperation, BatchOperationType, CollectionSchema, DataRecord, RecordMetadata,
+        ResultMetadata, SchemaIndex, UUID,
+    },
 };
 use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
 use crate::{log_error, log_info};
@@ -39,6 +46,11 @@ use tokio::sync::{Mutex, Semaphore};
 /// timeouts under normal 15-persona load.
 const MAX_CONCURRENT_QUERIES: usize = 16;
 
+/// Open transactions not committed or rolled back within this long are
+/// discarded. Covers clients that never call commit/rollback and callers
+/// outside IPC, whose transactions no disconnect ever cleans up.
+const TRANSACTION_TTL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
 // ============================================================================
 // Vector Search Types and Cache
 // ============================================================================
@@ -69,24 +81,45 @@ struct PaginatedQueryState {
     db_path: String,
     collection: String,
     filter: Option<std::collections::HashMap<String, FieldFilter>>,
+    /// Keyset order (`keyset_sort` of the requested sort)
     sort: Option<Vec<crate::orm::query::SortSpec>>,
     page_size: usize,
     total_count: u64,
     current_page: usize,
-    /// Cursor: last ID from previous page for efficient keyset pagination
-    cursor_id: Option<String>,
+    /// Keyset cursor after the previous page's last row
+    cursor: Option<String>,
     has_more: bool,
     /// Creation time for future TTL-based cleanup of stale queries
     #[allow(dead_code)]
     created_at: std::time::Instant,
 }
 
+// ============================================================================
+// Transaction State
+// ============================================================================
+
+/// Open transaction: write ops staged in memory until commit. Nothing touches
+/// the database before commit, so reads inside the transaction don't see its
+/// own writes, and an abandoned transaction costs nothing to discard.
+#[derive(Debug)]
+struct PendingTransaction {
+    db_path: String,
+    /// IPC connection that opened it; closing that connection rolls it back
+    connection_id: Option<u64>,
+    operations: Vec<BatchOperation>,
+    created_at: std::time::Instant,
+}
+
 /// DataModule manages storage operations. Database path comes from each request.
 ///
 /// Adapter-agnostic: connection string determines which adapter is used.
 /// - File paths or `:memory:` → SqliteAdapter (worker thread with mpsc)
 /// - `postgres://` or `postgresql://` → PostgresAdapter (async connection pool)
 ///
+/// A request can also name the adapter with `adapterType` (alias
+/// `storageType`): "sqlite" or "postgres", e.g. for libpq key/value
+/// connection strings.
+///
 /// NOTE: SqliteAdapter is internally thread-safe via mpsc channels.
 /// PostgresAdapter is internally thread-safe via deadpool connection pool.
 pub struct DataModule {
@@ -101,6 +134,10 @@ pub struct DataModule {
     /// Paginated query state: queryId -> state
     /// Server-side cursor management for efficient pagination
     paginated_queries: DashMap<String, PaginatedQueryState>,
+    /// Open transactions: transactionId -> staged operations
+    transactions: DashMap<String, PendingTransaction>,
+    /// Open transactions older than this are discarded (TRANSACTION_TTL)
+    transaction_ttl: std::time::Duration,
     /// Module context for inter-module communication (event bus, shared compute)
     /// Set during initialize(), used to publish data change events
     context: RwLock<Option<Arc<ModuleContext>>>,
@@ -121,6 +158,8 @@ impl DataModule {
             init_lock: Mutex::new(()),
             vector_cache: RwLock::new(HashMap::new()),
             paginated_queries: DashMap::new(),
+            transactions: DashMap::new(),
+            transaction_ttl: TRANSACTION_TTL,
             context: RwLock::new(None),
             active_migration: Mutex::new(None),
             previous_connection: Mutex::new(None),
@@ -161,10 +200,36 @@ impl DataModule {
     /// Routing logic:
     /// - `postgres://` or `postgresql://` → PostgresAdapter (async pool, MVCC)
     /// - Everything else (file paths, `:memory:`) → SqliteAdapter (worker thread)
+    ///
+    /// Adapters opened with an explicit `adapterType` are cached under the
+    /// same connection string, so later requests find them here.
     async fn get_adapter(&self, db_path: &str) -> Result<Arc<dyn StorageAdapter>, String> {
+        self.open_adapter(db_path, None).await
+    }
+
+    /// Get or create the adapter for `db_path`, as `requested` ("sqlite" |
+    /// "postgres") or inferred from the connection string when None.
+    async fn open_adapter(
+        &self,
+        db_path: &str,
+        requested: Option<&str>,
+    ) -> Result<Arc<dyn StorageAdapter>, String> {
+        let adapter_type = AdapterType::resolve(db_path, requested)?;
+        let cached = |adapter: &Arc<dyn StorageAdapter>| {
+            if requested.is_some() && adapter.name() != adapter_type.name() {
+                return Err(format!(
+                    "{} is already open with the {} adapter, not {}",
+                    db_path,
+                    adapter.name(),
+                    adapter_type.name()
+                ));
+            }
+            Ok(adapter.clone())
+        };
+
         // Check cache first (fast path - no lock needed)
         if let Some(adapter) = self.adapters.get(db_path) {
-            return Ok(adapter.clone());
+            return cached(&adapter);
         }
 
         // Slow path: need to initialize. Use lock to prevent double-init.
@@ -172,7 +237,7 @@ impl DataModule {
 
         // Double-check after acquiring lock
         if let Some(adapter) = self.adapters.get(db_path) {
-            return Ok(adapter.clone());
+            return cached(&adapter);
         }
 
         // Scale pool size based on database role:
@@ -180,8 +245,7 @@ impl DataModule {
         // Per-persona DBs: small pool (occasional queries from one persona)
         let is_main_db = db_path.contains("database/main.db")
             || db_path.contains("database\\main.db")
-            || db_path.starts_with("postgres://")
-            || db_path.starts_with("postgresql://");
+            || adapter_type == AdapterType::Postgres;
         let max_connections = if is_main_db { 20 } else { 4 };
 
         let config = AdapterConfig {
@@ -191,9 +255,8 @@ impl DataModule {
             max_connections,
         };
 
-        // Route based on connection string
-        let adapter: Arc<dyn StorageAdapter> =
-            if db_path.starts_with("postgres://") || db_path.starts_with("postgresql://") {
+        let adapter: Arc<dyn StorageAdapter> = match adapter_type {
+            AdapterType::Postgres => {
                 log_info!(
                     "data",
                     "get_adapter",
@@ -203,11 +266,13 @@ impl DataModule {
                 let mut pg = PostgresAdapter::new();
                 pg.initialize(config).await?;
                 Arc::new(pg)
-            } else {
+            }
+            AdapterType::Sqlite => {
                 let mut sqlite = SqliteAdapter::new();
                 sqlite.initialize(config).await?;
                 Arc::new(sqlite)
-            };
+            }
+        };
 
         self.adapters.insert(db_path.to_string(), adapter.clone());
         Ok(adapter)
@@ -255,6 +320,19 @@ impl ServiceModule for DataModule {
             command,
             params
         );
+
+        // An explicit adapter type opens (and caches) that adapter for dbPath
+        // up front; handlers then find it through get_adapter()
+        let adapter_type = params
+            .get("adapterType")
+            .or_else(|| params.get("storageType"))
+            .and_then(|v| v.as_str());
+        if let (Some(adapter_type), Some(db_path)) =
+            (adapter_type, params.get("dbPath").and_then(|v| v.as_str()))
+        {
+            self.open_adapter(db_path, Some(adapter_type)).await?;
+        }
+
         match command {
             "data/create" => self.handle_create(params).await,
             "data/read" => self.handle_read(params).await,
@@ -264,12 +342,21 @@ impl ServiceModule for DataModule {
             "data/queryWithJoin" => self.handle_query_with_join(params).await,
             "data/count" => self.handle_count(params).await,
             "data/batch" => self.handle_batch(params).await,
+            "data/create-batch" => self.handle_create_batch(params).await,
             "data/ensure-schema" => self.handle_ensure_schema(params).await,
             "data/list-collections" => self.handle_list_collections(params).await,
             "data/collection-stats" => self.handle_collection_stats(params).await,
+            "data/create-index" => self.handle_create_index(params).await,
+            "data/list-indexes" => self.handle_list_indexes(params).await,
+            "data/ensure-fts" => self.handle_ensure_fts(params).await,
             "data/truncate" => self.handle_truncate(params).await,
             "data/clear-all" => self.handle_clear_all(params).await,
 
+            // Transactions - staged writes applied atomically on commit
+            "data/begin-transaction" => self.handle_begin_transaction(params).await,
+            "data/commit" => self.handle_commit(params).await,
+            "data/rollback" => self.handle_rollback(params),
+
             // Paginated queries - server-side cursor management
             "data/query-open" => self.handle_query_open(params).await,
             "data/query-next" => self.handle_query_next(params).await,
@@ -298,6 +385,27 @@ impl ServiceModule for DataModule {
         }
     }
 
+    async fn connection_closed(&self, connection_id: u64) {
+        let abandoned: Vec<String> = self
+            .transactions
+            .iter()
+            .filter(|tx| tx.connection_id == Some(connection_id))
+            .map(|tx| tx.key().clone())
+            .collect();
+        for id in &abandoned {
+            self.transactions.remove(id);
+        }
+        if !abandoned.is_empty() {
+            log_info!(
+                "data",
+                "transaction",
+                "Connection {} closed, rolled back {} open transaction(s)",
+                connection_id,
+                abandoned.len()
+            );
+        }
+    }
+
     async fn shutdown(&self) -> Result<(), String> {
         // Close all adapters - clear the DashMap
         // Adapters will clean up when their Arc refcount drops to zero
@@ -319,6 +427,9 @@ struct CreateParams {
     collection: String,
     id: Option<UUID>,
     data: Value,
+    /// Stage into this transaction instead of writing now
+    #[serde(default)]
+    transaction_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -335,9 +446,14 @@ struct UpdateParams {
     db_path: String,
     collection: String,
     id: UUID,
+    /// Partial update: only the given fields are written
+    #[serde(alias = "patch")]
     data: Value,
     #[serde(default)]
     increment_version: bool,
+    /// Stage into this transaction instead of writing now (version is always incremented)
+    #[serde(default)]
+    transaction_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -346,6 +462,21 @@ struct DeleteParams {
     db_path: String,
     collection: String,
     id: UUID,
+    /// Stage into this transaction instead of writing now
+    #[serde(default)]
+    transaction_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BeginTransactionParams {
+    db_path: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TransactionParams {
+    transaction_id: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -361,8 +492,17 @@ struct QueryParams {
     limit: Option<usize>,
     #[serde(default)]
     offset: Option<usize>,
+    /// Keyset pagination: a previous page's `metadata.nextCursor`
+    #[serde(default)]
+    after_cursor: Option<String>,
     #[serde(default)]
     select: Option<Vec<String>>,
+    /// KNN mode: `{ vectorField, queryVector, topK }`
+    #[serde(default)]
+    vector: Option<crate::orm::query::VectorQuery>,
+    /// Full-text mode: FTS5 MATCH expression (see `data/ensure-fts`)
+    #[serde(default, rename = "match")]
+    text_match: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -391,6 +531,8 @@ struct CountParams {
     collection: String,
     #[serde(default)]
     filter: Option<serde_json::Map<String, Value>>,
+    #[serde(default, rename = "match")]
+    text_match: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -400,6 +542,18 @@ struct BatchParams {
     operations: Vec<BatchOperation>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateBatchParams {
+    db_path: String,
+    collection: String,
+    /// Record data objects; an `id` string field is used if present
+    records: Vec<Value>,
+    /// Abort and roll back the whole batch on the first failed record
+    #[serde(default)]
+    stop_on_error: bool,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SchemaParams {
@@ -414,6 +568,29 @@ struct CollectionParams {
     collection: String,
 }
 
+/// Index on one or more fields; without a name it becomes
+/// `idx_{table}_{columns}`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateIndexParams {
+    db_path: String,
+    collection: String,
+    #[serde(default)]
+    name: Option<String>,
+    fields: Vec<String>,
+    #[serde(default)]
+    unique: bool,
+}
+
+/// Full-text index over text fields, queried with `match`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnsureFtsParams {
+    db_path: String,
+    collection: String,
+    fields: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct DbPathOnly {
@@ -572,6 +749,19 @@ impl DataModule {
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
         let collection = params.collection.clone();
 
+        if let Some(transaction_id) = &params.transaction_id {
+            return self.stage_operation(
+                transaction_id,
+                &params.db_path,
+                BatchOperation {
+                    operation_type: BatchOperationType::Create,
+                    collection,
+                    id: Some(id),
+                    data: Some(params.data),
+                },
+            );
+        }
+
         let record = DataRecord {
             id: id.clone(),
             collection: params.collection,
@@ -624,9 +814,26 @@ impl DataModule {
             format!("Invalid params: {e}")
         })?;
 
+        if !params.data.is_object() {
+            return Err("Update data must be an object of fields to change".to_string());
+        }
+
         let collection = params.collection.clone();
         let id = params.id.clone();
 
+        if let Some(transaction_id) = &params.transaction_id {
+            return self.stage_operation(
+                transaction_id,
+                &params.db_path,
+                BatchOperation {
+                    operation_type: BatchOperationType::Update,
+                    collection,
+                    id: Some(id),
+                    data: Some(params.data),
+                },
+            );
+        }
+
         let adapter = self.get_adapter(&params.db_path).await?;
         let result = adapter
             .update(
@@ -659,11 +866,24 @@ impl DataModule {
         let collection = params.collection.clone();
         let id = params.id.clone();
 
+        if let Some(transaction_id) = &params.transaction_id {
+            return self.stage_operation(
+                transaction_id,
+                &params.db_path,
+                BatchOperation {
+                    operation_type: BatchOperationType::Delete,
+                    collection,
+                    id: Some(id),
+                    data: None,
+                },
+            );
+        }
+
         let adapter = self.get_adapter(&params.db_path).await?;
         let result = adapter.delete(&params.collection, &params.id).await;
 
-        // Publish event on success
-        if result.success {
+        // Publish only when a row was actually removed (data = false: no such id)
+        if result.success && result.data == Some(true) {
             self.publish_event(
                 &collection,
                 "deleted",
@@ -690,13 +910,37 @@ impl DataModule {
             format!("Invalid params: {e}")
         })?;
 
+        if params.offset.is_some() && params.after_cursor.is_some() {
+            return Err("offset cannot be combined with afterCursor".to_string());
+        }
+
+        // Sorted pages get keyset pagination: the order is made total with an
+        // id tie-break, and a full page reports a nextCursor for the next one.
+        // Unlike OFFSET this stays cheap and stable however deep the page.
+        let keyset = (params.sort.is_some() || params.after_cursor.is_some())
+            && params.limit.is_some()
+            && params.vector.is_none();
+        let keys = keyset.then(|| keyset_sort(&params.sort));
+        let mut select = params.select;
+        if let (Some(select), Some(keys)) = (select.as_mut(), &keys) {
+            // The cursor is built from the last row's sort fields
+            for key in keys {
+                if !select.contains(&key.field) {
+                    select.push(key.field.clone());
+                }
+            }
+        }
+
         let query = StorageQuery {
             collection: params.collection.clone(),
             filter: params.filter,
-            sort: params.sort,
+            sort: keys.clone().or(params.sort),
             limit: params.limit,
             offset: params.offset,
-            select: params.select,
+            after_cursor: params.after_cursor,
+            select,
+            vector: params.vector,
+            text_match: params.text_match,
             ..Default::default()
         };
 
@@ -712,7 +956,17 @@ impl DataModule {
         }
 
         let adapter = self.get_adapter(&params.db_path).await?;
-        let result = adapter.query(query).await;
+        let mut result = adapter.query(query).await;
+        if let (Some(keys), Some(records)) = (&keys, &result.data) {
+            if Some(records.len()) == params.limit {
+                let next_cursor = records.last().map(|r| KeysetCursor::at(r, keys).encode());
+                // Keep whatever metadata the adapter already reported
+                result
+                    .metadata
+                    .get_or_insert_with(ResultMetadata::default)
+                    .next_cursor = next_cursor;
+            }
+        }
         let total_ms = start.elapsed().as_millis();
 
         // Log slow queries to module log file
@@ -758,6 +1012,7 @@ impl DataModule {
                     .map(|(k, v)| (k, FieldFilter::Value(v)))
                     .collect()
             }),
+            text_match: params.text_match,
             ..Default::default()
         };
 
@@ -810,6 +1065,219 @@ impl DataModule {
         CommandResult::json(&result)
     }
 
+    async fn handle_create_batch(&self, params: Value) -> Result<CommandResult, String> {
+        let start = std::time::Instant::now();
+        let params: CreateBatchParams =
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
+
+        let total = params.records.len();
+        let records: Vec<DataRecord> = params
+            .records
+            .into_iter()
+            .map(|data| DataRecord {
+                id: data
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .map(String::from)
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                collection: params.collection.clone(),
+                data,
+                metadata: RecordMetadata::default(),
+            })
+            .collect();
+
+        let adapter = self.get_adapter(&params.db_path).await?;
+        let result = adapter.create_many(records, params.stop_on_error).await;
+        self.log_slow_query(
+            "create-batch",
+            &params.collection,
+            start.elapsed().as_millis(),
+        );
+
+        let results = match (result.success, result.data) {
+            (true, Some(results)) => results,
+            _ => return Err(result.error.unwrap_or_else(|| "Batch create failed".into())),
+        };
+        let ids: Vec<&Value> = results
+            .iter()
+            .filter(|r| r["success"] == true)
+            .map(|r| &r["id"])
+            .collect();
+        let errors: Vec<&Value> = results.iter().filter(|r| r["success"] != true).collect();
+
+        if !ids.is_empty() {
+            self.publish_event(
+                &params.collection,
+                "batch",
+                json!({
+                    "collection": params.collection,
+                    "created": ids.len(),
+                    "ids": ids
+                }),
+            );
+        }
+
+        Ok(CommandResult::Json(json!({
+            "success": true,
+            "total": total,
+            "succeeded": ids.len(),
+            "failed": errors.len(),
+            "ids": ids,
+            "errors": errors
+        })))
+    }
+
+    async fn handle_begin_transaction(&self, params: Value) -> Result<CommandResult, String> {
+        let params: BeginTransactionParams =
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
+
+        let adapter = self.get_adapter(&params.db_path).await?;
+        if !adapter.capabilities().supports_transactions {
+            return Err(format!(
+                "{} adapter does not support transactions",
+                adapter.name()
+            ));
+        }
+
+        self.expire_transactions();
+
+        let transaction_id = uuid::Uuid::new_v4().to_string();
+        self.transactions.insert(
+            transaction_id.clone(),
+            PendingTransaction {
+                db_path: params.db_path,
+                connection_id: crate::runtime::current_connection_id(),
+                operations: Vec::new(),
+                created_at: std::time::Instant::now(),
+            },
+        );
+
+        Ok(CommandResult::Json(json!({
+            "success": true,
+            "transactionId": transaction_id
+        })))
+    }
+
+    /// Drop open transactions older than the TTL.
+    fn expire_transactions(&self) {
+        let before = self.transactions.len();
+        self.transactions
+            .retain(|_, tx| tx.created_at.elapsed() < self.transaction_ttl);
+        let expired = before.saturating_sub(self.transactions.len());
+        if expired > 0 {
+            log_info!(
+                "data",
+                "transaction",
+                "Discarded {} transaction(s) open longer than {:?}",
+                expired,
+                self.transaction_ttl
+            );
+        }
+    }
+
+    /// Remove and return an open transaction, failing if it has expired.
+    fn take_transaction(&self, transaction_id: &str) -> Result<PendingTransaction, String> {
+        let (_, tx) = self
+            .transactions
+            .remove(transaction_id)
+            .ok_or_else(|| format!("Unknown transaction: {transaction_id}"))?;
+        if tx.created_at.elapsed() >= self.transaction_ttl {
+            return Err(format!("Transaction {transaction_id} expired"));
+        }
+        Ok(tx)
+    }
+
+    /// Append a write to an open transaction instead of executing it.
+    fn stage_operation(
+        &self,
+        transaction_id: &str,
+        db_path: &str,
+        operation: BatchOperation,
+    ) -> Result<CommandResult, String> {
+        let mut tx = self
+            .transactions
+            .get_mut(transaction_id)
+            .ok_or_else(|| format!("Unknown transaction: {transaction_id}"))?;
+        if tx.created_at.elapsed() >= self.transaction_ttl {
+            drop(tx);
+            self.transactions.remove(transaction_id);
+            return Err(format!("Transaction {transaction_id} expired"));
+        }
+        if tx.db_path != db_path {
+            return Err(format!(
+                "Transaction {transaction_id} belongs to a different database"
+            ));
+        }
+
+        let id = operation.id.clone();
+        tx.operations.push(operation);
+
+        Ok(CommandResult::Json(json!({
+            "success": true,
+            "staged": true,
+            "transactionId": transaction_id,
+            "operationIndex": tx.operations.len() - 1,
+            "id": id
+        })))
+    }
+
+    async fn handle_commit(&self, params: Value) -> Result<CommandResult, String> {
+        let params: TransactionParams =
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
+
+        let tx = self.take_transaction(&params.transaction_id)?;
+
+        // Event payloads captured up front; ops are moved into the adapter
+        let events: Vec<(String, &'static str, Option<UUID>)> = tx
+            .operations
+            .iter()
+            .filter_map(|op| {
+                let action = match op.operation_type {
+                    BatchOperationType::Create => "created",
+                    BatchOperationType::Update => "updated",
+                    BatchOperationType::Delete => "deleted",
+                    BatchOperationType::Read => return None,
+                };
+                Some((op.collection.clone(), action, op.id.clone()))
+            })
+            .collect();
+
+        let adapter = self.get_adapter(&tx.db_path).await?;
+        let result = adapter.transaction(tx.operations).await;
+
+        // Only committed writes are announced
+        if result.success {
+            for (collection, action, id) in events {
+                self.publish_event(
+                    &collection,
+                    action,
+                    json!({
+                        "id": id,
+                        "collection": collection
+                    }),
+                );
+            }
+        }
+
+        CommandResult::json(&result)
+    }
+
+    fn handle_rollback(&self, params: Value) -> Result<CommandResult, String> {
+        let params: TransactionParams =
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
+
+        let (_, tx) = self
+            .transactions
+            .remove(&params.transaction_id)
+            .ok_or_else(|| format!("Unknown transaction: {}", params.transaction_id))?;
+
+        Ok(CommandResult::Json(json!({
+            "success": true,
+            "transactionId": params.transaction_id,
+            "discarded": tx.operations.len()
+        })))
+    }
+
     async fn handle_ensure_schema(&self, params: Value) -> Result<CommandResult, String> {
         let params: SchemaParams =
             serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
@@ -840,6 +1308,41 @@ impl DataModule {
         CommandResult::json(&result)
     }
 
+    async fn handle_create_index(&self, params: Value) -> Result<CommandResult, String> {
+        let params: CreateIndexParams =
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
+
+        let adapter = self.get_adapter(&params.db_path).await?;
+        let index = SchemaIndex {
+            name: params.name.unwrap_or_default(),
+            fields: params.fields,
+            unique: params.unique,
+        };
+        let result = adapter.create_index(&params.collection, index).await;
+
+        CommandResult::json(&result)
+    }
+
+    async fn handle_list_indexes(&self, params: Value) -> Result<CommandResult, String> {
+        let params: CollectionParams =
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
+
+        let adapter = self.get_adapter(&params.db_path).await?;
+        let result = adapter.list_indexes(&params.collection).await;
+
+        CommandResult::json(&result)
+    }
+
+    async fn handle_ensure_fts(&self, params: Value) -> Result<CommandResult, String> {
+        let params: EnsureFtsParams =
+            serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
+
+        let adapter = self.get_adapter(&params.db_path).await?;
+        let result = adapter.ensure_fts(&params.collection, params.fields).await;
+
+        CommandResult::json(&result)
+    }
+
     async fn handle_truncate(&self, params: Value) -> Result<CommandResult, String> {
         let params: CollectionParams =
             serde_json::from_value(params).map_err(|e| format!("Invalid params: {e}"))?;
@@ -1502,11 +2005,11 @@ impl DataModule {
             db_path: params.db_path.clone(),
             collection: params.collection.clone(),
             filter: params.filter,
-            sort: params.sort,
+            sort: Some(keyset_sort(&params.sort)),
             page_size: params.page_size,
             total_count,
             current_page: 0,
-            cursor_id: None,
+            cursor: None,
             has_more: total_count > 0,
             created_at: Instant::now(),
         };
@@ -1540,8 +2043,8 @@ impl DataModule {
 
     /// Get next page from paginated query
     ///
-    /// Uses keyset pagination (WHERE id > cursor) instead of OFFSET for performance.
-    /// For sorted queries, combines sort column(s) with id for deterministic ordering.
+    /// Uses keyset pagination (rows after the previous page's last sort key
+    /// and id) instead of OFFSET, so deep pages cost the same as the first.
     async fn handle_query_next(&self, params: Value) -> Result<CommandResult, String> {
         use std::time::Instant;
         let start = Instant::now();
@@ -1567,7 +2070,7 @@ impl DataModule {
                 s.page_size,
                 s.total_count,
                 s.current_page,
-                s.cursor_id.clone(),
+                s.cursor.clone(),
                 s.has_more,
             )
         });
@@ -1580,7 +2083,7 @@ impl DataModule {
             page_size,
             total_count,
             current_page,
-            _cursor_id,
+            cursor,
             has_more,
         ) = state_info.ok_or_else(|| format!("Query {} not found", params.query_id))?;
 
@@ -1598,15 +2101,13 @@ impl DataModule {
 
         let adapter = self.get_adapter(&db_path).await?;
 
-        // Build query with cursor-based pagination
-        // For simplicity, using OFFSET initially. TODO: implement true keyset pagination
-        let offset = current_page * page_size;
+        let keys = keyset_sort(&sort);
         let query = StorageQuery {
             collection: collection.clone(),
             filter: filter.clone(),
-            sort: sort.clone(),
+            sort: Some(keys.clone()),
             limit: Some(page_size),
-            offset: Some(offset),
+            after_cursor: cursor,
             ..Default::default()
         };
 
@@ -1617,15 +2118,15 @@ impl DataModule {
 
         let records = result.data.unwrap_or_default();
         let items_count = records.len();
-        let new_has_more = items_count == page_size && offset + items_count < total_count as usize;
-
-        // Get last ID for cursor
-        let new_cursor_id = records.last().map(|r| r.id.clone());
+        let new_cursor = records.last().map(|r| KeysetCursor::at(r, &keys).encode());
+        let fetched = current_page * page_size + items_count;
+        let new_has_more =
+            items_count == page_size && fetched < total_count as usize && new_cursor.is_some();
 
         // Update query state
         if let Some(mut state) = self.paginated_queries.get_mut(&params.query_id) {
             state.current_page += 1;
-            state.cursor_id = new_cursor_id;
+            state.cursor = new_cursor;
             state.has_more = new_has_more;
         }
 
@@ -1908,6 +2409,35 @@ mod tests {
         assert!(result.unwrap_err().contains("dbPath"));
     }
 
+    #[tokio::test]
+    async fn test_explicit_adapter_type() {
+        let module = DataModule::new();
+        let params = |adapter_type: &str| {
+            json!({
+                "dbPath": ":memory:",
+                "adapterType": adapter_type,
+                "collection": "test_users",
+            })
+        };
+
+        let result = module.handle_command("data/count", params("sqlite")).await;
+        assert!(result.is_ok());
+        let adapter = module.get_adapter(":memory:").await.unwrap();
+        assert_eq!(adapter.name(), "sqlite");
+
+        // Already open as SQLite, so can't be reopened as Postgres
+        let err = module
+            .handle_command("data/count", params("postgres"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("already open with the sqlite adapter"));
+        let err = module
+            .handle_command("data/count", params("mysql"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("Unknown adapter type"));
+    }
+
     #[tokio::test]
     async fn test_data_module_create_and_read() {
         let module = DataModule::new();
@@ -1924,6 +2454,7 @@ mod tests {
                 max_length: None,
             }],
             indexes: vec![],
+            migration: None,
         };
 
         let _ = module
@@ -1974,6 +2505,183 @@ mod tests {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_transaction_commit_rollback_and_disconnect() {
+        let module = DataModule::new();
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("tx.db").to_str().unwrap().to_string();
+
+        let json_of = |r: Result<CommandResult, String>| match r {
+            Ok(CommandResult::Json(v)) => v,
+            other => panic!("expected json, got {:?}", other.err()),
+        };
+        let begin = |conn: u64| {
+            crate::runtime::with_connection(
+                conn,
+                module.handle_command("data/begin-transaction", json!({ "dbPath": db_path })),
+            )
+        };
+
+        // Commit: both staged creates land together
+        let tx = json_of(begin(1).await)["transactionId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        for id in ["a", "b"] {
+            let staged = json_of(
+                module
+                    .handle_command(
+                        "data/create",
+                        json!({
+                            "dbPath": db_path, "collection": "tx_items", "id": id,
+                            "data": { "name": id }, "transactionId": tx
+                        }),
+                    )
+                    .await,
+            );
+            assert_eq!(staged["staged"], true);
+        }
+        let committed = json_of(
+            module
+                .handle_command("data/commit", json!({ "transactionId": tx }))
+                .await,
+        );
+        assert!(committed["success"].as_bool().unwrap(), "{committed}");
+
+        let read = |id: &'static str| {
+            module.handle_command(
+                "data/read",
+                json!({ "dbPath": db_path, "collection": "tx_items", "id": id }),
+            )
+        };
+        assert_eq!(json_of(read("b").await)["success"], true);
+
+        // Rollback: staged delete never runs
+        let tx = json_of(begin(1).await)["transactionId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        module
+            .handle_command(
+                "data/delete",
+                json!({ "dbPath": db_path, "collection": "tx_items", "id": "a", "transactionId": tx }),
+            )
+            .await
+            .unwrap();
+        let rolled_back = json_of(
+            module
+                .handle_command("data/rollback", json!({ "transactionId": tx }))
+                .await,
+        );
+        assert_eq!(rolled_back["discarded"], 1);
+        assert_eq!(json_of(read("a").await)["success"], true);
+
+        // Disconnect: the owning connection's transaction is discarded
+        let tx = json_of(begin(7).await)["transactionId"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        module.connection_closed(7).await;
+        assert!(module
+            .handle_command("data/commit", json!({ "transactionId": tx }))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_transaction_expires_after_ttl() {
+        let mut module = DataModule::new();
+        module.transaction_ttl = std::time::Duration::ZERO;
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("ttl.db").to_str().unwrap().to_string();
+
+        let begun = module
+            .handle_command("data/begin-transaction", json!({ "dbPath": db_path }))
+            .await;
+        let Ok(CommandResult::Json(begun)) = begun else {
+            panic!("begin failed");
+        };
+        let tx = begun["transactionId"].as_str().unwrap();
+
+        let err = module
+            .handle_command(
+                "data/create",
+                json!({
+                    "dbPath": db_path, "collection": "tx_items", "id": "a",
+                    "data": { "name": "a" }, "transactionId": tx
+                }),
+            )
+            .await
+            .err()
+            .unwrap();
+        assert!(err.contains("expired"), "{err}");
+        assert!(module.transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_update_and_delete_missing_record() {
+        let module = DataModule::new();
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("crud.db").to_str().unwrap().to_string();
+
+        module
+            .handle_command(
+                "data/create",
+                json!({
+                    "dbPath": db_path, "collection": "crudItems", "id": "r1",
+                    "data": { "displayName": "Alice", "score": 1 }
+                }),
+            )
+            .await
+            .unwrap();
+
+        // Partial patch keeps untouched fields; camelCase maps to snake_case columns
+        let Ok(CommandResult::Json(updated)) = module
+            .handle_command(
+                "data/update",
+                json!({
+                    "dbPath": db_path, "collection": "crudItems", "id": "r1",
+                    "patch": { "score": 2 }
+                }),
+            )
+            .await
+        else {
+            panic!("update failed");
+        };
+        assert_eq!(updated["data"]["data"]["score"], 2);
+        assert_eq!(updated["data"]["data"]["displayName"], "Alice");
+
+        let Ok(CommandResult::Json(missing)) = module
+            .handle_command(
+                "data/update",
+                json!({
+                    "dbPath": db_path, "collection": "crudItems", "id": "nope",
+                    "data": { "score": 3 }
+                }),
+            )
+            .await
+        else {
+            panic!("update returned Err");
+        };
+        assert_eq!(missing["success"], false);
+        assert!(missing["error"].as_str().unwrap().contains("not found"));
+
+        let delete = |id: &'static str| {
+            module.handle_command(
+                "data/delete",
+                json!({ "dbPath": db_path, "collection": "crudItems", "id": id }),
+            )
+        };
+        let Ok(CommandResult::Json(removed)) = delete("r1").await else {
+            panic!("delete failed");
+        };
+        assert_eq!(removed["data"], true);
+        let Ok(CommandResult::Json(removed)) = delete("r1").await else {
+            panic!("delete failed");
+        };
+        assert_eq!(removed["data"], false);
+    }
+
     #[tokio::test]
     async fn test_vector_index_and_stats() {
         let module = DataModule::new();
@@ -2000,6 +2708,7 @@ mod tests {
                 },
             ],
             indexes: vec![],
+            migration: None,
         };
 
         let _ = module
@@ -2097,6 +2806,7 @@ mod tests {
                 },
             ],
             indexes: vec![],
+            migration: None,
         };
 
         let _ = module
@@ -2178,6 +2888,7 @@ mod tests {
                 max_length: None,
             }],
             indexes: vec![],
+            migration: None,
         };
 
         let _ = module
@@ -2268,6 +2979,191 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_create_and_list_indexes() {
+        let module = DataModule::new();
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("indexes.db").to_string_lossy().to_string();
+
+        let field = |name: &str, indexed: bool| crate::orm::types::SchemaField {
+            name: name.to_string(),
+            field_type: crate::orm::types::FieldType::String,
+            indexed,
+            unique: false,
+            nullable: true,
+            max_length: None,
+        };
+        let schema = CollectionSchema {
+            collection: "chat_messages".to_string(),
+            fields: vec![field("roomId", true), field("senderId", false)],
+            indexes: vec![],
+            migration: None,
+        };
+        module
+            .handle_command(
+                "data/ensure-schema",
+                json!({ "dbPath": db_path, "schema": schema }),
+            )
+            .await
+            .unwrap();
+
+        let created = module
+            .handle_command(
+                "data/create-index",
+                json!({
+                    "dbPath": db_path,
+                    "collection": "chat_messages",
+                    "fields": ["roomId", "senderId"],
+                    "unique": true
+                }),
+            )
+            .await;
+        let Ok(CommandResult::Json(created)) = created else {
+            panic!("create-index failed");
+        };
+        assert_eq!(created["success"], true, "{created}");
+
+        // Unknown column surfaces the database error
+        let bad = module
+            .handle_command(
+                "data/create-index",
+                json!({ "dbPath": db_path, "collection": "chat_messages", "fields": ["nope"] }),
+            )
+            .await;
+        let Ok(CommandResult::Json(bad)) = bad else {
+            panic!("create-index failed");
+        };
+        assert_eq!(bad["success"], false);
+
+        // Same name: the identical index is a no-op, another one an error
+        let create = |params: Value| {
+            let module = &module;
+            async move {
+                let Ok(CommandResult::Json(result)) =
+                    module.handle_command("data/create-index", params).await
+                else {
+                    panic!("create-index failed");
+                };
+                result
+            }
+        };
+        let again = create(json!({
+            "dbPath": db_path,
+            "collection": "chat_messages",
+            "fields": ["roomId", "senderId"],
+            "unique": true
+        }))
+        .await;
+        assert_eq!(again["success"], true, "{again}");
+        let clash = create(json!({
+            "dbPath": db_path,
+            "collection": "chat_messages",
+            "name": "idx_chat_messages_room_id",
+            "fields": ["senderId"]
+        }))
+        .await;
+        assert_eq!(clash["success"], false);
+        assert!(clash["error"].as_str().unwrap().contains("already exists"));
+        let injected = create(json!({
+            "dbPath": db_path,
+            "collection": "chat_messages",
+            "fields": ["roomId); DROP TABLE chat_messages; --"]
+        }))
+        .await;
+        assert_eq!(injected["success"], false);
+
+        let listed = module
+            .handle_command(
+                "data/list-indexes",
+                json!({ "dbPath": db_path, "collection": "chat_messages" }),
+            )
+            .await;
+        let Ok(CommandResult::Json(listed)) = listed else {
+            panic!("list-indexes failed");
+        };
+        let mut indexes: Vec<SchemaIndex> = serde_json::from_value(listed["data"].clone()).unwrap();
+        indexes.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(indexes.len(), 2, "{listed}");
+        assert_eq!(indexes[0].name, "idx_chat_messages_room_id");
+        assert_eq!(indexes[0].fields, vec!["roomId"]);
+        assert!(!indexes[0].unique);
+        assert_eq!(indexes[1].name, "idx_chat_messages_room_id_sender_id");
+        assert_eq!(indexes[1].fields, vec!["roomId", "senderId"]);
+        assert!(indexes[1].unique);
+    }
+
+    #[tokio::test]
+    async fn test_query_cursor_pagination() {
+        let module = DataModule::new();
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cursor.db").to_string_lossy().to_string();
+
+        // Duplicate sort keys: the id tie-break keeps pages from overlapping.
+        // NULL ranks sort last descending and are paged past, not dropped.
+        let rows = [3, 1, 2, 2, 5, 4, 2].map(|rank| json!({ "rank": rank }));
+        let unranked = [json!({ "rank": null }), json!({ "label": "no rank" })];
+        for data in rows.into_iter().chain(unranked) {
+            module
+                .handle_command(
+                    "data/create",
+                    json!({
+                        "dbPath": db_path,
+                        "collection": "ranked",
+                        "data": data
+                    }),
+                )
+                .await
+                .unwrap();
+        }
+
+        let mut ranks = Vec::new();
+        let mut ids = std::collections::HashSet::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut params = json!({
+                "dbPath": db_path,
+                "collection": "ranked",
+                "sort": [{ "field": "rank", "direction": "desc" }],
+                "limit": 3,
+            });
+            if let Some(cursor) = &cursor {
+                params["afterCursor"] = json!(cursor);
+            }
+            let Ok(CommandResult::Json(page)) = module.handle_command("data/query", params).await
+            else {
+                panic!("Expected JSON result");
+            };
+            assert!(page["success"].as_bool().unwrap());
+            for record in page["data"].as_array().unwrap() {
+                ranks.push(record["data"]["rank"].as_i64());
+                ids.insert(record["id"].as_str().unwrap().to_string());
+            }
+            match page["metadata"]["nextCursor"].as_str() {
+                Some(next) => cursor = Some(next.to_string()),
+                None => break,
+            }
+        }
+
+        let expected = [5, 4, 3, 2, 2, 2, 1].map(Some);
+        assert_eq!(ranks[..7], expected);
+        assert_eq!(ranks[7..], [None, None]);
+        assert_eq!(ids.len(), 9);
+
+        let mixed = module
+            .handle_command(
+                "data/query",
+                json!({
+                    "dbPath": db_path,
+                    "collection": "ranked",
+                    "limit": 3,
+                    "offset": 3,
+                    "afterCursor": "any",
+                }),
+            )
+            .await;
+        assert!(mixed.unwrap_err().contains("offset"));
+    }
+
     #[tokio::test]
     async fn test_paginated_query() {
         let module = DataModule::new();
@@ -2284,6 +3180,7 @@ mod tests {
                 max_length: None,
             }],
             indexes: vec![],
+            migration: None,
         };
 
         let _ = module
@@ -2409,6 +3306,7 @@ mod tests {
                 },
             ],
             indexes: vec![],
+            migration: None,
         };
 
         let _ = module
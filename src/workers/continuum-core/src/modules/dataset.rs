@@ -3,13 +3,16 @@
 //! All heavy data processing (CSV parsing, JSONL conversion, file I/O)
 //! happens here in Rust off the main thread. TypeScript is a thin API layer.
 //!
-//! Supports RealClassEval (arxiv:2510.26130) and generic CSV imports.
+//! Supports RealClassEval (arxiv:2510.26130) and generic CSV imports, and
+//! exports to the schemas fine-tuning toolchains expect (ChatML, ShareGPT,
+//! and Parquet with the `parquet-export` feature).
 
 use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::any::Any;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use ts_rs::TS;
 
@@ -53,6 +56,125 @@ pub struct DatasetMetrics {
     pub avg_lines_of_code: Option<f64>,
 }
 
+/// Target schema for dataset/export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/dataset/ExportFormat.ts"
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// `{"messages": [{"role", "content"}]}` per line
+    Chatml,
+    /// `{"conversations": [{"from", "value"}]}` per line (system/human/gpt)
+    Sharegpt,
+    /// ChatML rows as a Parquet table (requires the `parquet-export` feature)
+    Parquet,
+}
+
+impl ExportFormat {
+    fn file_name(self, split: &str) -> String {
+        match self {
+            ExportFormat::Chatml => format!("{split}.chatml.jsonl"),
+            ExportFormat::Sharegpt => format!("{split}.sharegpt.jsonl"),
+            ExportFormat::Parquet => format!("{split}.parquet"),
+        }
+    }
+}
+
+/// Re-split requested with dataset/export, instead of the dataset's own
+/// train/eval files.
+///
+/// All valid conversations are pooled and shuffled with `seed` (ChaCha8,
+/// whose stream is fixed across rand releases): the same dataset, ratios
+/// and seed always produce the same partition.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/dataset/ExportSplit.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSplit {
+    /// Relative weight of the train split (e.g. 0.9)
+    pub train: f64,
+    /// Relative weight of the validation split (e.g. 0.1)
+    pub val: f64,
+    #[serde(default)]
+    #[ts(type = "number")]
+    pub seed: u64,
+    /// Top-level record field to stratify by (e.g. "persona"), so each of
+    /// its values is split in the same proportions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub stratify_by: Option<String>,
+}
+
+impl ExportSplit {
+    /// Partition (stratum, row) pairs into (train, val).
+    fn partition(&self, rows: Vec<(String, Value)>) -> (Vec<Value>, Vec<Value>) {
+        use rand::{seq::SliceRandom, SeedableRng};
+        use rand_chacha::ChaCha8Rng;
+
+        let mut strata: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+        for (stratum, row) in rows {
+            strata.entry(stratum).or_default().push(row);
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let train_fraction = self.train / (self.train + self.val);
+        let (mut train, mut val) = (Vec::new(), Vec::new());
+        for mut group in strata.into_values() {
+            group.shuffle(&mut rng);
+            let split_point = (group.len() as f64 * train_fraction).round() as usize;
+            val.extend(group.split_off(split_point));
+            train.extend(group);
+        }
+        // Interleave strata rather than leaving them in blocks
+        train.shuffle(&mut rng);
+        val.shuffle(&mut rng);
+        (train, val)
+    }
+}
+
+/// One split written by dataset/export.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/dataset/ExportedFile.ts"
+)]
+pub struct ExportedFile {
+    pub split: String,
+    pub path: String,
+    #[ts(type = "number")]
+    pub rows: usize,
+}
+
+/// A source record left out of the export, and why.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/dataset/SkippedConversation.ts"
+)]
+pub struct SkippedConversation {
+    pub split: String,
+    /// 1-based line in the split's JSONL file
+    #[ts(type = "number")]
+    pub line: usize,
+    pub reason: String,
+}
+
+/// Result of dataset/export.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/dataset/ExportResult.ts"
+)]
+pub struct ExportResult {
+    pub name: String,
+    pub format: ExportFormat,
+    pub files: Vec<ExportedFile>,
+    #[ts(type = "number")]
+    pub total_rows: usize,
+    pub skipped: Vec<SkippedConversation>,
+}
+
 pub struct DatasetModule {
     datasets_root: PathBuf,
 }
@@ -419,22 +541,128 @@ impl DatasetModule {
             .ok_or("Missing required param: name")?;
 
         let root = self.resolve_datasets_root(&params);
-        let manifest_path = root.join(name).join("manifest.json");
+        let manifest = load_manifest(&root.join(name))?;
+        CommandResult::json(&manifest)
+    }
 
-        if !manifest_path.exists() {
-            return Err(format!(
-                "Dataset '{}' not found at {}",
-                name,
-                manifest_path.display()
-            ));
+    /// Export a dataset's train/eval splits to another schema.
+    ///
+    /// Each record must be a well-formed chat: an optional system message,
+    /// then user/assistant turns alternating from user and ending with the
+    /// assistant. Malformed records are skipped and reported, not fatal.
+    /// With a `split` param (see [`ExportSplit`]) the conversations are
+    /// re-split into train/val files; otherwise the dataset's own train/eval
+    /// splits are kept. Files go to `exportDir` (default `{dataset}/exports/`).
+    async fn export_dataset(&self, params: Value) -> Result<CommandResult, String> {
+        let name = params
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or("Missing required param: name")?;
+        let format: ExportFormat = serde_json::from_value(
+            params
+                .get("format")
+                .cloned()
+                .ok_or("Missing required param: format")?,
+        )
+        .map_err(|e| format!("Invalid format (chatml, sharegpt, parquet): {e}"))?;
+        let split: Option<ExportSplit> = params
+            .get("split")
+            .map(|v| serde_json::from_value(v.clone()))
+            .transpose()
+            .map_err(|e| format!("Invalid split: {e}"))?;
+        if let Some(split) = &split {
+            // The sum bounds both weights: no infinities, nor a pair that overflows
+            if !(split.train > 0.0 && split.val > 0.0 && (split.train + split.val).is_finite()) {
+                return Err(
+                    "Invalid split: train and val must both be positive and finite".to_string(),
+                );
+            }
+        }
+
+        let dataset_dir = self.resolve_datasets_root(&params).join(name);
+        let manifest = load_manifest(&dataset_dir)?;
+        let export_dir = params
+            .get("exportDir")
+            .and_then(|v| v.as_str())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| dataset_dir.join("exports"));
+        std::fs::create_dir_all(&export_dir)
+            .map_err(|e| format!("Failed to create export directory: {e}"))?;
+
+        let stratify_by = split.as_ref().and_then(|s| s.stratify_by.as_deref());
+        let mut splits = Vec::new();
+        let mut skipped = Vec::new();
+        for (split_name, source) in [
+            ("train", &manifest.train_path),
+            ("eval", &manifest.eval_path),
+        ] {
+            let content = std::fs::read_to_string(dataset_dir.join(source))
+                .map_err(|e| format!("Failed to read {source}: {e}"))?;
+
+            let mut rows = Vec::new();
+            for (i, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = match serde_json::from_str::<Value>(line) {
+                    Ok(record) => record,
+                    Err(e) => {
+                        skipped.push(SkippedConversation {
+                            split: split_name.to_string(),
+                            line: i + 1,
+                            reason: format!("Invalid JSON: {e}"),
+                        });
+                        continue;
+                    }
+                };
+                match parse_conversation(&record) {
+                    Ok(turns) => {
+                        let stratum = stratify_by.map(|field| stratum_of(&record, field));
+                        rows.push((stratum.unwrap_or_default(), to_export_row(format, &turns)));
+                    }
+                    Err(reason) => skipped.push(SkippedConversation {
+                        split: split_name.to_string(),
+                        line: i + 1,
+                        reason,
+                    }),
+                }
+            }
+            splits.push((split_name, rows));
         }
 
-        let content = std::fs::read_to_string(&manifest_path)
-            .map_err(|e| format!("Failed to read manifest: {e}"))?;
-        let manifest: DatasetManifest =
-            serde_json::from_str(&content).map_err(|e| format!("Failed to parse manifest: {e}"))?;
+        let splits: Vec<(&str, Vec<Value>)> = match &split {
+            Some(split) => {
+                let pooled = splits.into_iter().flat_map(|(_, rows)| rows).collect();
+                let (train, val) = split.partition(pooled);
+                vec![("train", train), ("val", val)]
+            }
+            None => splits
+                .into_iter()
+                .map(|(name, rows)| (name, rows.into_iter().map(|(_, row)| row).collect()))
+                .collect(),
+        };
+
+        let mut files = Vec::new();
+        for (split_name, rows) in splits {
+            let path = export_dir.join(format.file_name(split_name));
+            match format {
+                ExportFormat::Chatml | ExportFormat::Sharegpt => write_jsonl(&path, &rows)?,
+                ExportFormat::Parquet => write_parquet(&path, &rows)?,
+            }
+            files.push(ExportedFile {
+                split: split_name.to_string(),
+                path: path.to_string_lossy().to_string(),
+                rows: rows.len(),
+            });
+        }
 
-        CommandResult::json(&manifest)
+        CommandResult::json(&ExportResult {
+            name: name.to_string(),
+            format,
+            total_rows: files.iter().map(|f| f.rows).sum(),
+            files,
+            skipped,
+        })
     }
 
     /// Split examples into train/eval, write JSONL files and manifest.
@@ -507,6 +735,7 @@ impl ServiceModule for DatasetModule {
             "dataset/import-realclasseval" => self.import_realclasseval(params).await,
             "dataset/list" => self.list_datasets(params).await,
             "dataset/info" => self.dataset_info(params).await,
+            "dataset/export" => self.export_dataset(params).await,
             _ => Err(format!("Unknown dataset command: {command}")),
         }
     }
@@ -520,6 +749,168 @@ impl ServiceModule for DatasetModule {
 // Helper functions
 // ============================================================================
 
+/// Read `manifest.json` from a dataset directory.
+fn load_manifest(dataset_dir: &Path) -> Result<DatasetManifest, String> {
+    let manifest_path = dataset_dir.join("manifest.json");
+    if !manifest_path.exists() {
+        let name = dataset_dir
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        return Err(format!(
+            "Dataset '{}' not found at {}",
+            name,
+            manifest_path.display()
+        ));
+    }
+
+    let content = std::fs::read_to_string(&manifest_path)
+        .map_err(|e| format!("Failed to read manifest: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse manifest: {e}"))
+}
+
+/// Validate a `{"messages": [...]}` record as a chat and return its
+/// (role, content) turns.
+fn parse_conversation(record: &Value) -> Result<Vec<(String, String)>, String> {
+    let messages = record
+        .get("messages")
+        .and_then(|v| v.as_array())
+        .ok_or("Missing messages array")?;
+    if messages.is_empty() {
+        return Err("Empty conversation".to_string());
+    }
+
+    fn role_of(message: &Value) -> &str {
+        message.get("role").and_then(|v| v.as_str()).unwrap_or("")
+    }
+    // An optional system prompt, then user/assistant turns alternating
+    let offset = usize::from(role_of(&messages[0]) == "system");
+
+    let mut turns = Vec::with_capacity(messages.len());
+    for (i, message) in messages.iter().enumerate() {
+        let role = role_of(message);
+        let expected = if i < offset {
+            "system"
+        } else if (i - offset) % 2 == 0 {
+            "user"
+        } else {
+            "assistant"
+        };
+        if role != expected {
+            return Err(format!(
+                "Message {i} has role '{role}', expected '{expected}'"
+            ));
+        }
+
+        let content = message
+            .get("content")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if content.trim().is_empty() {
+            return Err(format!("Message {i} has no content"));
+        }
+        turns.push((role.to_string(), content.to_string()));
+    }
+
+    if turns.last().is_some_and(|(role, _)| role != "assistant") {
+        return Err("Conversation does not end with an assistant message".to_string());
+    }
+    Ok(turns)
+}
+
+/// Stratification key of a record: its `field` value, or "" when absent.
+fn stratum_of(record: &Value, field: &str) -> String {
+    match record.get(field) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Map validated turns into one row of the export schema.
+fn to_export_row(format: ExportFormat, turns: &[(String, String)]) -> Value {
+    match format {
+        ExportFormat::Chatml | ExportFormat::Parquet => json!({
+            "messages": turns
+                .iter()
+                .map(|(role, content)| json!({ "role": role, "content": content }))
+                .collect::<Vec<_>>(),
+        }),
+        ExportFormat::Sharegpt => json!({
+            "conversations": turns
+                .iter()
+                .map(|(role, content)| {
+                    let from = match role.as_str() {
+                        "user" => "human",
+                        "assistant" => "gpt",
+                        other => other,
+                    };
+                    json!({ "from": from, "value": content })
+                })
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+/// Write rows as a Parquet table, with the schema inferred from the rows
+/// (ChatML rows become a list<struct<role, content>> column). An empty
+/// split still gets that column, so every split file has the same schema.
+#[cfg(feature = "parquet-export")]
+fn write_parquet(path: &Path, rows: &[Value]) -> Result<(), String> {
+    use arrow_json::reader::{infer_json_schema_from_iterator, ReaderBuilder};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    const BATCH_ROWS: usize = 1024;
+
+    // Nothing to infer from an empty split: use a placeholder ChatML row
+    let placeholder;
+    let schema_rows = if rows.is_empty() {
+        placeholder = [to_export_row(
+            ExportFormat::Parquet,
+            &[(String::new(), String::new())],
+        )];
+        &placeholder[..]
+    } else {
+        rows
+    };
+    let schema = infer_json_schema_from_iterator(schema_rows.iter().map(|row| Ok(row.clone())))
+        .map_err(|e| format!("Failed to infer Parquet schema: {e}"))?;
+    let schema = Arc::new(schema);
+    let mut decoder = ReaderBuilder::new(schema.clone())
+        .with_batch_size(BATCH_ROWS)
+        .build_decoder()
+        .map_err(|e| format!("Failed to build Arrow decoder: {e}"))?;
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    let mut writer = ArrowWriter::try_new(file, schema, None)
+        .map_err(|e| format!("Failed to create Parquet writer: {e}"))?;
+
+    for chunk in rows.chunks(BATCH_ROWS) {
+        decoder
+            .serialize(chunk)
+            .map_err(|e| format!("Failed to convert rows to Arrow: {e}"))?;
+        let batch = decoder
+            .flush()
+            .map_err(|e| format!("Failed to convert rows to Arrow: {e}"))?;
+        if let Some(batch) = batch {
+            writer
+                .write(&batch)
+                .map_err(|e| format!("Failed to write Parquet: {e}"))?;
+        }
+    }
+    writer
+        .close()
+        .map_err(|e| format!("Failed to finish Parquet file: {e}"))?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet-export"))]
+fn write_parquet(_path: &Path, _rows: &[Value]) -> Result<(), String> {
+    Err("Parquet export requires continuum-core built with the parquet-export feature".to_string())
+}
+
 fn find_column(headers: &csv::StringRecord, name: &str) -> Result<usize, String> {
     headers.iter().position(|h| h == name).ok_or_else(|| {
         format!(
@@ -837,4 +1228,160 @@ snippet_200,Parser,"class Parser:\n    def parse(self, text):\n        return te
         assert!(result.is_err());
         assert!(result.unwrap_err().contains("not found"));
     }
+
+    #[tokio::test]
+    async fn test_export_validates_and_converts() {
+        let tmp = TempDir::new().unwrap();
+        let dataset_dir = tmp.path().join("chat-ds");
+        std::fs::create_dir_all(&dataset_dir).unwrap();
+        let manifest = json!({
+            "name": "chat-ds",
+            "version": "1.0",
+            "total_examples": 5,
+            "train_examples": 4,
+            "eval_examples": 1,
+            "train_path": "train.jsonl",
+            "eval_path": "eval.jsonl",
+            "imported_at": "2026-03-05T00:00:00Z",
+        });
+        std::fs::write(dataset_dir.join("manifest.json"), manifest.to_string()).unwrap();
+        std::fs::write(
+            dataset_dir.join("train.jsonl"),
+            [
+                r#"{"messages":[{"role":"system","content":"Be terse"},{"role":"user","content":"Hi"},{"role":"assistant","content":"Hello"}]}"#,
+                r#"{"messages":[{"role":"user","content":"Hi"},{"role":"user","content":"Again"}]}"#,
+                r#"{"messages":[{"role":"user","content":"Hi"}]}"#,
+                "not json",
+            ]
+            .join("\n"),
+        )
+        .unwrap();
+        std::fs::write(
+            dataset_dir.join("eval.jsonl"),
+            r#"{"messages":[{"role":"user","content":"Q"},{"role":"assistant","content":"A"}]}"#,
+        )
+        .unwrap();
+
+        let module = DatasetModule::new();
+        let result = module
+            .export_dataset(json!({
+                "name": "chat-ds",
+                "format": "sharegpt",
+                "outputDir": tmp.path().to_str().unwrap(),
+            }))
+            .await
+            .unwrap();
+        let CommandResult::Json(v) = result else {
+            panic!("Expected JSON result");
+        };
+        let export: ExportResult = serde_json::from_value(v).unwrap();
+        assert_eq!(export.total_rows, 2);
+        assert_eq!(export.files[0].rows, 1);
+        assert_eq!(export.files[1].rows, 1);
+        let skipped_lines: Vec<usize> = export.skipped.iter().map(|s| s.line).collect();
+        assert_eq!(skipped_lines, vec![2, 3, 4]);
+        assert!(export.skipped[0].reason.contains("expected 'assistant'"));
+
+        let train = std::fs::read_to_string(&export.files[0].path).unwrap();
+        let row: Value = serde_json::from_str(train.trim()).unwrap();
+        assert_eq!(row["conversations"][0]["from"], "system");
+        assert_eq!(row["conversations"][1]["from"], "human");
+        assert_eq!(row["conversations"][2]["from"], "gpt");
+        assert_eq!(row["conversations"][2]["value"], "Hello");
+        assert!(dataset_dir.join("exports/eval.sharegpt.jsonl").exists());
+
+        let bad = module
+            .export_dataset(json!({
+                "name": "chat-ds",
+                "format": "alpaca",
+                "outputDir": tmp.path().to_str().unwrap(),
+            }))
+            .await;
+        assert!(bad.unwrap_err().contains("Invalid format"));
+
+        let huge = module
+            .export_dataset(json!({
+                "name": "chat-ds",
+                "format": "chatml",
+                "outputDir": tmp.path().to_str().unwrap(),
+                "split": { "train": 1e308, "val": 1e308 },
+            }))
+            .await;
+        assert!(huge.unwrap_err().contains("positive and finite"));
+    }
+
+    fn parquet_rows() -> Vec<Value> {
+        let turns = [
+            ("user".to_string(), "Hi".to_string()),
+            ("assistant".to_string(), "Hello".to_string()),
+        ];
+        vec![to_export_row(ExportFormat::Parquet, &turns); 3]
+    }
+
+    #[cfg(feature = "parquet-export")]
+    #[test]
+    fn test_write_parquet() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let tmp = TempDir::new().unwrap();
+        let read = |path: &Path| {
+            let file = std::fs::File::open(path).unwrap();
+            let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+            let rows = builder.metadata().file_metadata().num_rows();
+            (builder.schema().clone(), rows)
+        };
+
+        let train = tmp.path().join("train.parquet");
+        write_parquet(&train, &parquet_rows()).unwrap();
+        let (schema, rows) = read(&train);
+        assert_eq!(rows, 3);
+        assert!(schema.field_with_name("messages").is_ok());
+
+        // An empty split has the same columns, just no rows
+        let val = tmp.path().join("val.parquet");
+        write_parquet(&val, &[]).unwrap();
+        let (empty_schema, rows) = read(&val);
+        assert_eq!(rows, 0);
+        assert_eq!(empty_schema, schema);
+    }
+
+    #[cfg(not(feature = "parquet-export"))]
+    #[test]
+    fn test_write_parquet_needs_feature() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("train.parquet");
+        let err = write_parquet(&path, &parquet_rows()).unwrap_err();
+        assert!(err.contains("parquet-export"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_split_is_seeded_and_stratified() {
+        let rows: Vec<(String, Value)> = (0..100)
+            .map(|i| {
+                let persona = if i < 80 { "helper" } else { "teacher" };
+                (persona.to_string(), json!({ "id": i, "persona": persona }))
+            })
+            .collect();
+        let split = ExportSplit {
+            train: 0.9,
+            val: 0.1,
+            seed: 7,
+            stratify_by: Some("persona".to_string()),
+        };
+
+        let (train, val) = split.partition(rows.clone());
+        assert_eq!(train.len(), 90);
+        assert_eq!(val.len(), 10);
+        // 80/20 personas split 72/8 and 18/2
+        let teachers = val.iter().filter(|r| r["persona"] == "teacher").count();
+        assert_eq!(teachers, 2);
+
+        // Same seed, same partition; another seed, another partition
+        let (train_again, val_again) = split.partition(rows.clone());
+        assert_eq!(train, train_again);
+        assert_eq!(val, val_again);
+        let reseeded = ExportSplit { seed: 8, ..split };
+        assert_ne!(reseeded.partition(rows).1, val);
+    }
 }
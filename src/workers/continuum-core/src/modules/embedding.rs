@@ -1,7 +1,12 @@
 //! EmbeddingModule — Native text embedding generation via fastembed (ONNX).
 //!
-//! Handles: embedding/generate, embedding/model/load, embedding/model/list,
-//!          embedding/model/info, embedding/model/unload
+//! Handles: embedding/generate, embedding/generate/stream, embedding/model/load,
+//!          embedding/model/list, embedding/model/info, embedding/model/unload
+//!
+//! `embedding/generate/stream` is for batches too large to embed at once:
+//! texts are embedded `batch_size` at a time and each sub-batch is streamed
+//! back as its own JSON chunk, so memory stays bounded by the sub-batch and
+//! the caller can persist results while the rest is still running.
 //!
 //! Benefits of native embedding:
 //! - No network overhead (~5ms per embedding)
@@ -23,6 +28,7 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
+use tokenizers::Tokenizer;
 use tracing::{info, warn};
 
 use crate::gpu::make_entry;
@@ -32,6 +38,16 @@ use crate::utils::params::Params;
 /// Global model cache - models loaded on demand
 static MODEL_CACHE: OnceCell<Arc<Mutex<HashMap<String, TextEmbedding>>>> = OnceCell::new();
 
+/// Per-model input windows, built once from the loaded model's tokenizer
+static INPUT_WINDOWS: OnceCell<Mutex<HashMap<String, Arc<InputWindow>>>> = OnceCell::new();
+
+/// Texts per sub-batch in `embedding/generate/stream` when not specified
+const DEFAULT_STREAM_BATCH_SIZE: usize = 256;
+
+/// Sub-batches buffered ahead of a slow `embedding/generate/stream` client
+/// before embedding pauses
+const STREAM_CHUNK_BUFFER: usize = 2;
+
 /// GPU allocation guards for loaded embedding models (dynamic: one guard per model)
 static EMBEDDING_GPU_GUARDS: OnceCell<Mutex<HashMap<String, GpuAllocationGuard>>> = OnceCell::new();
 
@@ -184,6 +200,276 @@ fn parse_model_name(name: &str) -> Result<EmbeddingModel, String> {
     }
 }
 
+/// Which side of an asymmetric retrieval pair a text is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputType {
+    Query,
+    Passage,
+}
+
+impl InputType {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "query" => Ok(Self::Query),
+            "passage" | "document" => Ok(Self::Passage),
+            other => Err(format!(
+                "Invalid input_type '{other}' (expected 'query' or 'passage')"
+            )),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Query => "query",
+            Self::Passage => "passage",
+        }
+    }
+}
+
+/// Prefix a model was trained to expect for `input_type`, if any.
+///
+/// Nomic embeds tag both sides; BGE v1.5 only instructs the query side.
+/// Symmetric models (MiniLM) take raw text either way.
+fn input_prefix(model: &EmbeddingModel, input_type: InputType) -> Option<&'static str> {
+    match (model, input_type) {
+        (
+            EmbeddingModel::NomicEmbedTextV1 | EmbeddingModel::NomicEmbedTextV15,
+            InputType::Query,
+        ) => Some("search_query: "),
+        (
+            EmbeddingModel::NomicEmbedTextV1 | EmbeddingModel::NomicEmbedTextV15,
+            InputType::Passage,
+        ) => Some("search_document: "),
+        (
+            EmbeddingModel::BGESmallENV15
+            | EmbeddingModel::BGEBaseENV15
+            | EmbeddingModel::BGELargeENV15,
+            InputType::Query,
+        ) => Some("Represent this sentence for searching relevant passages: "),
+        _ => None,
+    }
+}
+
+/// The model's prefix for `input_type`, or "" if it takes raw text.
+fn prefix_for(model_name: &str, input_type: Option<InputType>) -> Result<&'static str, String> {
+    let Some(input_type) = input_type else {
+        return Ok("");
+    };
+    Ok(input_prefix(&parse_model_name(model_name)?, input_type).unwrap_or(""))
+}
+
+/// Apply the model's `input_type` prefix to every text (no-op without one).
+fn apply_input_prefix(
+    texts: Vec<String>,
+    model_name: &str,
+    input_type: Option<InputType>,
+) -> Result<Vec<String>, String> {
+    let prefix = prefix_for(model_name, input_type)?;
+    if prefix.is_empty() {
+        return Ok(texts);
+    }
+    Ok(texts.into_iter().map(|t| format!("{prefix}{t}")).collect())
+}
+
+/// How `embedding/generate` handles inputs longer than the model's window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Truncation {
+    /// Reject the request
+    Error,
+    /// Embed the leading window only (what fastembed does on its own)
+    Truncate,
+    /// Embed every window and mean-pool the results
+    ChunkMean,
+}
+
+impl Truncation {
+    fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "truncate" => Ok(Self::Truncate),
+            "chunk_mean" | "chunkmean" => Ok(Self::ChunkMean),
+            other => Err(format!(
+                "Invalid truncation '{other}' (expected 'error', 'truncate' or 'chunk_mean')"
+            )),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Truncate => "truncate",
+            Self::ChunkMean => "chunk_mean",
+        }
+    }
+}
+
+/// A model's input limit and what it takes to measure text against it.
+struct InputWindow {
+    /// Max sequence length, special tokens included
+    max_tokens: usize,
+    /// The model's tokenizer with truncation disabled
+    tokenizer: Tokenizer,
+    /// Tokens the tokenizer adds around every input
+    special_tokens: usize,
+}
+
+impl InputWindow {
+    fn new(model_tokenizer: &Tokenizer) -> Result<Self, String> {
+        let max_tokens = model_tokenizer
+            .get_truncation()
+            .map(|t| t.max_length)
+            .unwrap_or(512);
+        let mut tokenizer = model_tokenizer.clone();
+        tokenizer
+            .with_truncation(None)
+            .map_err(|e| format!("Failed to disable truncation: {e}"))?;
+        let special_tokens = tokenizer
+            .encode("", true)
+            .map_err(|e| format!("Tokenization failed: {e}"))?
+            .len();
+        Ok(Self {
+            max_tokens,
+            tokenizer,
+            special_tokens,
+        })
+    }
+
+    /// Tokens left for the text itself once special tokens and `prefix` are in
+    fn text_budget(&self, prefix: &str) -> Result<usize, String> {
+        let prefix_tokens = if prefix.is_empty() {
+            0
+        } else {
+            self.tokenizer
+                .encode(prefix, false)
+                .map_err(|e| format!("Tokenization failed: {e}"))?
+                .len()
+        };
+        Ok(self
+            .max_tokens
+            .saturating_sub(self.special_tokens + prefix_tokens)
+            .max(1))
+    }
+}
+
+/// The model's input window, loading the model on first use.
+fn input_window(model_name: &str) -> Result<Arc<InputWindow>, String> {
+    let windows = INPUT_WINDOWS.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(window) = windows
+        .lock()
+        .map_err(|e| format!("Lock error: {e}"))?
+        .get(model_name)
+    {
+        return Ok(window.clone());
+    }
+
+    get_or_load_model(model_name)?;
+    let window = {
+        let models = get_model_cache()
+            .lock()
+            .map_err(|e| format!("Lock error: {e}"))?;
+        let model = models
+            .get(model_name)
+            .ok_or_else(|| format!("Model not loaded: {model_name}"))?;
+        Arc::new(InputWindow::new(&model.tokenizer)?)
+    };
+    windows
+        .lock()
+        .map_err(|e| format!("Lock error: {e}"))?
+        .insert(model_name.to_string(), window.clone());
+    Ok(window)
+}
+
+/// Split `text` into pieces of at most `window` tokens. Text that fits
+/// comes back whole.
+fn split_token_windows(
+    tokenizer: &Tokenizer,
+    text: &str,
+    window: usize,
+) -> Result<Vec<String>, String> {
+    let encoding = tokenizer
+        .encode(text, false)
+        .map_err(|e| format!("Tokenization failed: {e}"))?;
+    let offsets = encoding.get_offsets();
+    if offsets.len() <= window {
+        return Ok(vec![text.to_string()]);
+    }
+
+    // Byte offsets; snap to char boundaries in case a byte-level token
+    // splits a multi-byte character.
+    let floor = |mut i: usize| {
+        while !text.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    };
+    Ok(offsets
+        .chunks(window)
+        .map(|chunk| {
+            let start = floor(chunk[0].0.min(text.len()));
+            let end = floor(chunk[chunk.len() - 1].1.min(text.len()));
+            text[start..end].to_string()
+        })
+        .filter(|piece| !piece.trim().is_empty())
+        .collect())
+}
+
+/// Element-wise mean of `vectors`, L2-normalized like the model's own output.
+fn mean_pool(vectors: &[Vec<f32>]) -> Vec<f32> {
+    let dim = vectors.first().map_or(0, |v| v.len());
+    let mut mean = vec![0f32; dim];
+    for v in vectors {
+        for (m, x) in mean.iter_mut().zip(v) {
+            *m += x;
+        }
+    }
+    let norm = mean.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        mean.iter_mut().for_each(|x| *x /= norm);
+    }
+    mean
+}
+
+/// Whether a model was trained so that a prefix of its embedding is itself
+/// a usable embedding (Matryoshka representation learning).
+fn supports_matryoshka(model: &EmbeddingModel) -> bool {
+    matches!(model, EmbeddingModel::NomicEmbedTextV15)
+}
+
+/// Validate an `output_dimensions` request against the model.
+fn check_output_dimensions(model_name: &str, dims: Option<usize>) -> Result<Option<usize>, String> {
+    let Some(dims) = dims else {
+        return Ok(None);
+    };
+    if !supports_matryoshka(&parse_model_name(model_name)?) {
+        return Err(format!(
+            "{model_name} does not support output_dimensions (Matryoshka models: NomicEmbedTextV15)"
+        ));
+    }
+    if dims == 0 {
+        return Err("output_dimensions must be at least 1".to_string());
+    }
+    Ok(Some(dims))
+}
+
+/// Shorten a Matryoshka embedding to `dims`, following Nomic's recipe:
+/// layer-norm the full vector, keep the leading `dims`, re-normalize.
+/// Vectors already at most `dims` long are returned unchanged.
+fn matryoshka_truncate(embedding: &[f32], dims: usize) -> Vec<f32> {
+    if embedding.len() <= dims {
+        return embedding.to_vec();
+    }
+    let n = embedding.len() as f32;
+    let mean = embedding.iter().sum::<f32>() / n;
+    let variance = embedding.iter().map(|x| (x - mean).powi(2)).sum::<f32>() / n;
+    let std = (variance + 1e-5).sqrt();
+    let mut out: Vec<f32> = embedding[..dims].iter().map(|x| (x - mean) / std).collect();
+    let norm = out.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        out.iter_mut().for_each(|x| *x /= norm);
+    }
+    out
+}
+
 /// Get or load a model by name.
 ///
 /// CRITICAL: Model loading (TextEmbedding::try_new) is a blocking operation that
@@ -321,6 +607,123 @@ pub fn generate_embeddings_batch(
         .map_err(|e| format!("Embedding generation failed: {e}"))
 }
 
+/// Embed `texts` through the result cache: hits are reused, misses are
+/// embedded in one model call and cached. Returns embeddings in input order
+/// plus the number of cache hits.
+fn embed_texts_cached(
+    texts: &[String],
+    model_name: &str,
+) -> Result<(Vec<Vec<f32>>, usize), String> {
+    let embed_cache = get_embedding_cache();
+    let mut embeddings: Vec<Vec<f32>> = Vec::with_capacity(texts.len());
+    let mut texts_to_generate: Vec<(usize, &str)> = Vec::new(); // (index, text)
+
+    {
+        let mut cache = embed_cache
+            .lock()
+            .map_err(|e| format!("Cache lock error: {e}"))?;
+        for (i, text) in texts.iter().enumerate() {
+            if let Some(cached) = cache.get(model_name, hash_text(text)) {
+                embeddings.push(cached);
+            } else {
+                embeddings.push(vec![]); // Placeholder
+                texts_to_generate.push((i, text));
+            }
+        }
+    }
+
+    let cache_hits = texts.len() - texts_to_generate.len();
+    if texts_to_generate.is_empty() {
+        return Ok((embeddings, cache_hits));
+    }
+
+    let text_refs: Vec<&str> = texts_to_generate.iter().map(|(_, t)| *t).collect();
+    let new_embeddings = generate_embeddings_batch(&text_refs, model_name)?;
+
+    let mut cache = embed_cache
+        .lock()
+        .map_err(|e| format!("Cache lock error: {e}"))?;
+    for ((idx, text), emb) in texts_to_generate.iter().zip(new_embeddings) {
+        cache.insert(model_name, hash_text(text), emb.clone());
+        embeddings[*idx] = emb;
+    }
+    Ok((embeddings, cache_hits))
+}
+
+/// Each text split to fit the window with `prefix` in front of every piece.
+fn prefixed_windows(
+    window: &InputWindow,
+    texts: &[String],
+    prefix: &str,
+) -> Result<Vec<Vec<String>>, String> {
+    let budget = window.text_budget(prefix)?;
+    texts
+        .iter()
+        .map(|t| {
+            Ok(split_token_windows(&window.tokenizer, t, budget)?
+                .into_iter()
+                .map(|piece| format!("{prefix}{piece}"))
+                .collect())
+        })
+        .collect()
+}
+
+/// Embed `texts`, handling inputs longer than the model's window per
+/// `truncation`. Returns embeddings in input order, cache hits, and how many
+/// inputs were over the window.
+///
+/// `prefix` (the model's `input_type` instruction) goes on every piece that
+/// is embedded, so each chunk of a long input carries it, and it is applied
+/// before the cache lookup: query and passage embeddings of the same text
+/// differ and must be cached separately.
+fn embed_with_truncation(
+    texts: &[String],
+    model_name: &str,
+    truncation: Truncation,
+    window: &InputWindow,
+    prefix: &str,
+) -> Result<(Vec<Vec<f32>>, usize, usize), String> {
+    let windows = prefixed_windows(window, texts, prefix)?;
+    let truncated = windows.iter().filter(|w| w.len() > 1).count();
+
+    let (embeddings, cache_hits) = match truncation {
+        Truncation::Error if truncated > 0 => {
+            return Err(format!(
+                "{truncated} of {} inputs exceed {model_name}'s {}-token limit",
+                texts.len(),
+                window.max_tokens
+            ));
+        }
+        Truncation::ChunkMean if truncated > 0 => {
+            let pieces: Vec<String> = windows.iter().flatten().cloned().collect();
+            let (piece_embeddings, hits) = embed_texts_cached(&pieces, model_name)?;
+            let mut remaining = piece_embeddings.as_slice();
+            let pooled = windows
+                .iter()
+                .map(|w| {
+                    let (mine, rest) = remaining.split_at(w.len());
+                    remaining = rest;
+                    mean_pool(mine)
+                })
+                .collect();
+            (pooled, hits)
+        }
+        _ => {
+            let prefixed: Vec<String> = texts.iter().map(|t| format!("{prefix}{t}")).collect();
+            embed_texts_cached(&prefixed, model_name)?
+        }
+    };
+    Ok((embeddings, cache_hits, truncated))
+}
+
+/// Index ranges of consecutive sub-batches of at most `batch_size` items
+fn sub_batches(len: usize, batch_size: usize) -> impl Iterator<Item = std::ops::Range<usize>> {
+    let batch_size = batch_size.max(1);
+    (0..len)
+        .step_by(batch_size)
+        .map(move |start| start..(start + batch_size).min(len))
+}
+
 // ─── Similarity Functions ───────────────────────────────────────────────────
 
 /// Cosine similarity between two embedding vectors.
@@ -582,7 +985,9 @@ fn get_model_info_list() -> Vec<ModelInfo> {
         ModelInfo {
             name: "NomicEmbedTextV15".to_string(),
             dimensions: 768,
-            description: "Nomic Embed Text v1.5 - 768 dimensions".to_string(),
+            description:
+                "Nomic Embed Text v1.5 - 768 dimensions, truncatable via output_dimensions"
+                    .to_string(),
             size_mb: 550,
             loaded: loaded_models.contains(&"NomicEmbedTextV15".to_string()),
         },
@@ -609,63 +1014,37 @@ impl EmbeddingModule {
         let p = Params::new(params);
         let texts: Vec<String> = p.json("texts")?;
         let model_name = p.str_or("model", "AllMiniLML6V2");
+        let input_type = p
+            .str_opt_alias("input_type", "inputType")
+            .map(InputType::parse)
+            .transpose()?;
 
         if texts.is_empty() {
             return Err("No texts provided".to_string());
         }
+        let truncation = p
+            .str_opt("truncation")
+            .map(Truncation::parse)
+            .transpose()?
+            .unwrap_or(Truncation::Truncate);
+        let output_dimensions = check_output_dimensions(
+            model_name,
+            p.u64_opt_alias("output_dimensions", "outputDimensions")
+                .map(|n| n as usize),
+        )?;
+        let prefix = prefix_for(model_name, input_type)?;
 
         let start = Instant::now();
         let batch_size = texts.len();
 
-        // Check embedding cache for each text
-        let embed_cache = get_embedding_cache();
-        let mut embeddings: Vec<Vec<f32>> = Vec::with_capacity(batch_size);
-        let mut texts_to_generate: Vec<(usize, String)> = Vec::new(); // (index, text)
-
-        {
-            let mut cache = embed_cache
-                .lock()
-                .map_err(|e| format!("Cache lock error: {e}"))?;
-            for (i, text) in texts.iter().enumerate() {
-                let text_hash = hash_text(text);
-                if let Some(cached) = cache.get(model_name, text_hash) {
-                    embeddings.push(cached);
-                } else {
-                    embeddings.push(vec![]); // Placeholder
-                    texts_to_generate.push((i, text.clone()));
-                }
-            }
-        }
-
-        let cache_hits = batch_size - texts_to_generate.len();
-
-        // Generate embeddings only for texts not in cache
-        if !texts_to_generate.is_empty() {
-            // Load model if needed
-            get_or_load_model(model_name)?;
-
-            // Get model from cache
-            let model_cache = get_model_cache();
-            let mut models = model_cache.lock().map_err(|e| format!("Lock error: {e}"))?;
-            let embedding_model = models
-                .get_mut(model_name)
-                .ok_or_else(|| format!("Model not loaded: {model_name}"))?;
-
-            // Generate embeddings for uncached texts
-            let text_refs: Vec<&str> = texts_to_generate.iter().map(|(_, t)| t.as_str()).collect();
-            let new_embeddings = embedding_model
-                .embed(text_refs, None)
-                .map_err(|e| format!("Embedding generation failed: {e}"))?;
-
-            // Store in cache and update result vector
-            let mut cache = embed_cache
-                .lock()
-                .map_err(|e| format!("Cache lock error: {e}"))?;
-            for ((idx, text), emb) in texts_to_generate.iter().zip(new_embeddings.into_iter()) {
-                let text_hash = hash_text(text);
-                cache.insert(model_name, text_hash, emb.clone());
-                embeddings[*idx] = emb;
-            }
+        let window = input_window(model_name)?;
+        let (mut embeddings, cache_hits, truncated) =
+            embed_with_truncation(&texts, model_name, truncation, &window, prefix)?;
+        if let Some(dims) = output_dimensions {
+            embeddings = embeddings
+                .iter()
+                .map(|e| matryoshka_truncate(e, dims))
+                .collect();
         }
 
         let duration_ms = start.elapsed().as_millis() as u64;
@@ -694,12 +1073,131 @@ impl EmbeddingModule {
                 "shape": [dimensions],
                 "batchSize": batch_size,
                 "durationMs": duration_ms,
-                "model": model_name
+                "model": model_name,
+                "inputType": input_type.map(InputType::as_str),
+                "maxTokens": window.max_tokens,
+                "truncation": truncation.as_str(),
+                "truncated": truncated,
+                "outputDimensions": dimensions
             }),
             data: bytes,
         })
     }
 
+    /// Sub-batched `embedding/generate`. Each sub-batch is one chunk:
+    /// `{ offset, embeddings, processed, total, batch, batches, done: false }`,
+    /// then a final `{ done: true, count, dimensions, ... }` or
+    /// `{ done: true, error }`. Inputs are checked against the model window
+    /// per sub-batch, so `truncation: "error"` fails at the sub-batch holding
+    /// the long input, after earlier ones have been sent.
+    fn handle_generate_stream(&self, params: &Value) -> Result<CommandResult, String> {
+        let p = Params::new(params);
+        let texts: Vec<String> = p.json("texts")?;
+        let model_name = p.str_or("model", "AllMiniLML6V2").to_string();
+        let input_type = p
+            .str_opt_alias("input_type", "inputType")
+            .map(InputType::parse)
+            .transpose()?;
+        let truncation = p
+            .str_opt("truncation")
+            .map(Truncation::parse)
+            .transpose()?
+            .unwrap_or(Truncation::Truncate);
+        let batch_size = p
+            .u64_opt_alias("batch_size", "batchSize")
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_STREAM_BATCH_SIZE);
+        let output_dimensions = check_output_dimensions(
+            &model_name,
+            p.u64_opt_alias("output_dimensions", "outputDimensions")
+                .map(|n| n as usize),
+        )?;
+
+        if texts.is_empty() {
+            return Err("No texts provided".to_string());
+        }
+        if batch_size == 0 {
+            return Err("batch_size must be at least 1".to_string());
+        }
+        let prefix = prefix_for(&model_name, input_type)?;
+        let window = input_window(&model_name)?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(STREAM_CHUNK_BUFFER);
+        tokio::task::spawn_blocking(move || {
+            let start = Instant::now();
+            let total = texts.len();
+            let batches = total.div_ceil(batch_size);
+            let mut cache_hits = 0;
+            let mut truncated = 0;
+            let mut dimensions = 0;
+
+            for (batch, range) in sub_batches(total, batch_size).enumerate() {
+                let offset = range.start;
+                let processed = range.end;
+                let (mut embeddings, hits, over) = match embed_with_truncation(
+                    &texts[range],
+                    &model_name,
+                    truncation,
+                    &window,
+                    prefix,
+                ) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        let _ =
+                            tx.blocking_send(json!({ "done": true, "error": e, "offset": offset }));
+                        return;
+                    }
+                };
+                if let Some(dims) = output_dimensions {
+                    embeddings = embeddings
+                        .iter()
+                        .map(|e| matryoshka_truncate(e, dims))
+                        .collect();
+                }
+                cache_hits += hits;
+                truncated += over;
+                dimensions = embeddings.first().map_or(dimensions, |e| e.len());
+
+                let chunk = json!({
+                    "done": false,
+                    "offset": offset,
+                    "embeddings": embeddings,
+                    "processed": processed,
+                    "total": total,
+                    "batch": batch + 1,
+                    "batches": batches,
+                });
+                // Blocks while the client is behind; fails once it is gone
+                if tx.blocking_send(chunk).is_err() {
+                    info!("Embedding stream cancelled after {offset}/{total} texts");
+                    return;
+                }
+            }
+
+            let duration_ms = start.elapsed().as_millis() as u64;
+            info!(
+                "Streamed {} embeddings ({}d) in {} batches, {}ms (cache: {}/{} hits)",
+                total, dimensions, batches, duration_ms, cache_hits, total
+            );
+            let _ = tx.blocking_send(json!({
+                "done": true,
+                "count": total,
+                "dimensions": dimensions,
+                "batches": batches,
+                "durationMs": duration_ms,
+                "model": model_name,
+                "inputType": input_type.map(InputType::as_str),
+                "maxTokens": window.max_tokens,
+                "truncation": truncation.as_str(),
+                "truncated": truncated,
+                "outputDimensions": dimensions,
+                "cacheHits": cache_hits,
+            }));
+        });
+
+        Ok(CommandResult::Stream(rx))
+    }
+
     fn handle_model_load(&self, params: &Value) -> Result<CommandResult, String> {
         let p = Params::new(params);
         let model = p.str("model")?;
@@ -745,6 +1243,12 @@ impl EmbeddingModule {
         let mut models = cache.lock().map_err(|e| format!("Lock error: {e}"))?;
 
         if models.remove(model).is_some() {
+            if let Some(windows) = INPUT_WINDOWS.get() {
+                windows
+                    .lock()
+                    .map_err(|e| format!("Lock error: {e}"))?
+                    .remove(model);
+            }
             info!("Unloaded embedding model: {model}");
             Ok(CommandResult::Json(json!({
                 "model": model,
@@ -758,6 +1262,9 @@ impl EmbeddingModule {
     /// Handle embedding/similarity - compute cosine similarity between two embeddings
     fn handle_similarity(&self, params: &Value) -> Result<CommandResult, String> {
         let p = Params::new(params);
+        if p.str_opt("query").is_some() {
+            return self.handle_text_similarity(params);
+        }
         let a: Vec<f32> = p.json("a")?;
         let b: Vec<f32> = p.json("b")?;
 
@@ -773,6 +1280,61 @@ impl EmbeddingModule {
         })))
     }
 
+    /// Handle embedding/similarity with text input: `{ query, candidates, model }`.
+    ///
+    /// Embeds everything here (through the result cache) and returns
+    /// candidates ranked by cosine similarity — vectors never cross the
+    /// socket. The query gets the model's query prefix and candidates its
+    /// passage prefix, so asymmetric models are used as trained.
+    fn handle_text_similarity(&self, params: &Value) -> Result<CommandResult, String> {
+        let p = Params::new(params);
+        let query = p.str("query")?;
+        let candidates: Vec<String> = p.json("candidates")?;
+        let model_name = p.str_or("model", "AllMiniLML6V2");
+        let k = p.u64_opt("k").map_or(candidates.len(), |k| k as usize);
+        let threshold = p.f64_or("threshold", -1.0) as f32;
+
+        if candidates.is_empty() {
+            return Ok(CommandResult::Json(json!({ "results": [], "count": 0 })));
+        }
+
+        let start = Instant::now();
+        let query_text =
+            apply_input_prefix(vec![query.to_string()], model_name, Some(InputType::Query))?;
+        let candidate_texts = apply_input_prefix(candidates, model_name, Some(InputType::Passage))?;
+
+        let (query_embedding, _) = embed_texts_cached(&query_text, model_name)?;
+        let (candidate_embeddings, cache_hits) = embed_texts_cached(&candidate_texts, model_name)?;
+        let query_embedding = query_embedding
+            .into_iter()
+            .next()
+            .ok_or("No embedding returned for query")?;
+
+        let ranked = top_k_similar(&query_embedding, &candidate_embeddings, k, threshold);
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        info!(
+            "Ranked {} candidates against query in {}ms (cache: {}/{} hits)",
+            candidate_embeddings.len(),
+            duration_ms,
+            cache_hits,
+            candidate_embeddings.len()
+        );
+
+        let results: Vec<Value> = ranked
+            .iter()
+            .map(|(idx, score)| json!({ "index": idx, "score": score }))
+            .collect();
+
+        Ok(CommandResult::Json(json!({
+            "results": results,
+            "count": results.len(),
+            "totalCandidates": candidate_embeddings.len(),
+            "model": model_name,
+            "durationMs": duration_ms
+        })))
+    }
+
     /// Handle embedding/similarity-matrix - compute pairwise similarities in parallel
     ///
     /// Takes an array of embeddings, returns lower-triangular similarity matrix.
@@ -1017,6 +1579,7 @@ impl ServiceModule for EmbeddingModule {
     async fn handle_command(&self, command: &str, params: Value) -> Result<CommandResult, String> {
         match command {
             "embedding/generate" => self.handle_generate(&params),
+            "embedding/generate/stream" => self.handle_generate_stream(&params),
             "embedding/similarity" => self.handle_similarity(&params),
             "embedding/similarity-matrix" => self.handle_similarity_matrix(&params),
             "embedding/top-k" => self.handle_top_k(&params),
@@ -1035,3 +1598,157 @@ impl ServiceModule for EmbeddingModule {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sub_batches() {
+        let ranges: Vec<_> = sub_batches(10, 4).collect();
+        assert_eq!(ranges, vec![0..4, 4..8, 8..10]);
+        assert_eq!(sub_batches(8, 4).count(), 2);
+        assert_eq!(sub_batches(0, 4).count(), 0);
+    }
+
+    #[test]
+    fn test_truncation_parse() {
+        assert_eq!(
+            Truncation::parse("chunk_mean").unwrap(),
+            Truncation::ChunkMean
+        );
+        assert_eq!(Truncation::parse("ERROR").unwrap(), Truncation::Error);
+        assert!(Truncation::parse("drop").is_err());
+    }
+
+    fn word_tokenizer() -> Tokenizer {
+        use tokenizers::models::wordlevel::WordLevel;
+        use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+        let vocab = [
+            ("[UNK]", 0u32),
+            ("a", 1),
+            ("b", 2),
+            ("c", 3),
+            ("é", 4),
+            ("query", 5),
+            (":", 6),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), *v))
+        .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Some(Whitespace {}));
+        tokenizer
+    }
+
+    #[test]
+    fn test_split_token_windows() {
+        let tokenizer = word_tokenizer();
+
+        assert_eq!(
+            split_token_windows(&tokenizer, "a b c", 5).unwrap(),
+            vec!["a b c"]
+        );
+        assert_eq!(
+            split_token_windows(&tokenizer, "a b c é a", 2).unwrap(),
+            vec!["a b", "c é", "a"]
+        );
+    }
+
+    #[test]
+    fn test_prefix_on_every_window() {
+        let mut tokenizer = word_tokenizer();
+        tokenizer
+            .with_truncation(Some(tokenizers::TruncationParams {
+                max_length: 4,
+                ..Default::default()
+            }))
+            .unwrap();
+        let window = InputWindow::new(&tokenizer).unwrap();
+        assert_eq!(window.max_tokens, 4);
+        // "query :" takes two of the four tokens
+        assert_eq!(window.text_budget("query: ").unwrap(), 2);
+
+        let texts = vec!["a b c é a".to_string(), "a".to_string()];
+        assert_eq!(
+            prefixed_windows(&window, &texts, "query: ").unwrap(),
+            vec![
+                vec!["query: a b", "query: c é", "query: a"],
+                vec!["query: a"],
+            ]
+        );
+        assert_eq!(prefixed_windows(&window, &texts, "").unwrap()[0].len(), 2);
+    }
+
+    #[test]
+    fn test_mean_pool_normalizes() {
+        let pooled = mean_pool(&[vec![1.0, 0.0], vec![0.0, 1.0]]);
+        let expected = 1.0 / 2f32.sqrt();
+        assert!((pooled[0] - expected).abs() < 1e-6);
+        assert!((pooled[1] - expected).abs() < 1e-6);
+        assert!(mean_pool(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_matryoshka_truncate() {
+        let full: Vec<f32> = (0..8).map(|i| i as f32).collect();
+        let short = matryoshka_truncate(&full, 4);
+        assert_eq!(short.len(), 4);
+        let norm = short.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+        // Layer norm centres the full vector first: the leading values are
+        // below its mean
+        assert!(short.iter().all(|&x| x < 0.0));
+        assert_eq!(matryoshka_truncate(&full, 16), full);
+
+        assert_eq!(
+            check_output_dimensions("NomicEmbedTextV15", Some(256)).unwrap(),
+            Some(256)
+        );
+        assert_eq!(
+            check_output_dimensions("AllMiniLML6V2", None).unwrap(),
+            None
+        );
+        assert!(check_output_dimensions("AllMiniLML6V2", Some(128)).is_err());
+        assert!(check_output_dimensions("NomicEmbedTextV15", Some(0)).is_err());
+    }
+
+    #[test]
+    fn test_input_type_parse() {
+        assert_eq!(InputType::parse("Query").unwrap(), InputType::Query);
+        assert_eq!(InputType::parse("document").unwrap(), InputType::Passage);
+        assert!(InputType::parse("question").is_err());
+    }
+
+    #[test]
+    fn test_input_prefix_per_model() {
+        let texts = || vec!["rust".to_string()];
+        assert_eq!(
+            apply_input_prefix(texts(), "NomicEmbedTextV15", Some(InputType::Passage)).unwrap(),
+            vec!["search_document: rust"]
+        );
+        assert!(
+            apply_input_prefix(texts(), "bge-small-en-v1.5", Some(InputType::Query)).unwrap()[0]
+                .starts_with("Represent this sentence")
+        );
+        // BGE passages and symmetric models are left alone
+        assert_eq!(
+            apply_input_prefix(texts(), "BGESmallENV15", Some(InputType::Passage)).unwrap(),
+            texts()
+        );
+        assert_eq!(
+            apply_input_prefix(texts(), "AllMiniLML6V2", Some(InputType::Query)).unwrap(),
+            texts()
+        );
+        assert_eq!(
+            apply_input_prefix(texts(), "unknown", None).unwrap(),
+            texts()
+        );
+    }
+}
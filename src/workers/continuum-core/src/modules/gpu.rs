@@ -1,28 +1,58 @@
 //! GpuModule — IPC commands for GPU memory management.
 //!
 //! Commands:
-//! - `gpu/stats`: Full GPU stats snapshot (total VRAM, per-subsystem budgets/usage, pressure)
+//! - `gpu/stats`: Full GPU stats snapshot (total VRAM, per-subsystem budgets/usage, pressure, fragmentation)
 //! - `gpu/pressure`: Quick pressure query (0.0-1.0)
 //! - `gpu/set-budget`: Set subsystem budget (params: subsystem, budgetMb). Returns stats snapshot.
 //! - `gpu/eviction-registry`: Full eviction registry snapshot (all tracked consumers)
 //! - `gpu/eviction-candidates`: Sorted eviction candidates (highest score first)
+//! - `gpu/reserve`: Reserve memory ahead of a load (params: id, sizeMb, ttlMs, subsystem?, priority?)
+//! - `gpu/commit-reservation`: Turn a reservation into a tracked consumer (params: id, label?)
+//! - `gpu/cancel-reservation`: Release a reservation that won't be committed (params: id)
 //!
 //! Follows the HealthModule pattern: stateless handler wrapping shared state.
+//! The only module state is the reservation handles and committed guards
+//! owned on behalf of TypeScript callers.
 
-use crate::gpu::{GpuMemoryManager, GpuPriority, GpuSubsystem};
+use crate::gpu::{
+    make_entry, GpuAllocationGuard, GpuMemoryManager, GpuPriority, GpuReservation, GpuSubsystem,
+};
 use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
 use async_trait::async_trait;
 use serde_json::Value;
 use std::any::Any;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Reservation TTL when the caller doesn't pass `ttlMs`
+const DEFAULT_RESERVATION_TTL_MS: u64 = 60_000;
 
 pub struct GpuModule {
     manager: Arc<GpuMemoryManager>,
+    /// Uncommitted reservations made over IPC, by id
+    reservations: Mutex<HashMap<String, GpuReservation>>,
+    /// Committed reservations, released by `gpu/unregister-consumer`
+    committed: Mutex<HashMap<String, GpuAllocationGuard>>,
 }
 
 impl GpuModule {
     pub fn new(manager: Arc<GpuMemoryManager>) -> Self {
-        Self { manager }
+        Self {
+            manager,
+            reservations: Mutex::new(HashMap::new()),
+            committed: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Drop handles whose reservations expired uncommitted
+    fn prune_reservations(&self) -> Result<(), String> {
+        self.manager.expire_reservations();
+        self.reservations
+            .lock()
+            .map_err(|e| format!("Lock poisoned: {e}"))?
+            .retain(|_, reservation| !reservation.is_expired());
+        Ok(())
     }
 }
 
@@ -123,14 +153,8 @@ impl ServiceModule for GpuModule {
                     .unwrap_or("batch");
                 let bytes = params.get("bytes").and_then(|v| v.as_u64()).unwrap_or(0);
 
-                let priority = match priority_str {
-                    "realtime" => GpuPriority::Realtime,
-                    "interactive" => GpuPriority::Interactive,
-                    "background" => GpuPriority::Background,
-                    _ => GpuPriority::Batch,
-                };
+                let priority = GpuPriority::from_name(priority_str).unwrap_or(GpuPriority::Batch);
 
-                use crate::gpu::make_entry;
                 self.manager
                     .eviction_registry
                     .register(make_entry(id, label, priority, bytes));
@@ -148,7 +172,9 @@ impl ServiceModule for GpuModule {
             }
 
             // Unregister a GPU consumer from TypeScript.
-            // Params: id (string), bytes (number) — bytes to release from budget
+            // Params: id (string), bytes (number) — bytes to release from budget.
+            // Consumers created by gpu/commit-reservation release their own
+            // committed size and ignore `bytes`.
             "gpu/unregister-consumer" => {
                 let id = params
                     .get("id")
@@ -157,7 +183,15 @@ impl ServiceModule for GpuModule {
                 let bytes = params.get("bytes").and_then(|v| v.as_u64()).unwrap_or(0);
 
                 self.manager.eviction_registry.unregister(id);
-                self.manager.release(GpuSubsystem::Inference, bytes);
+                let guard = self
+                    .committed
+                    .lock()
+                    .map_err(|e| format!("Lock poisoned: {e}"))?
+                    .remove(id);
+                match guard {
+                    Some(guard) => guard.release(),
+                    None => self.manager.release(GpuSubsystem::Inference, bytes),
+                }
 
                 Ok(CommandResult::Json(serde_json::json!({
                     "unregistered": true,
@@ -166,6 +200,137 @@ impl ServiceModule for GpuModule {
                 })))
             }
 
+            // Reserve memory before a load that may fail. Counts against
+            // pressure until committed, cancelled, or the TTL runs out.
+            // Params: id (string), sizeMb (number), ttlMs (number, default 60s),
+            //         subsystem (string, default inference), priority (string, default interactive)
+            "gpu/reserve" => {
+                let id = params
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .ok_or("gpu/reserve requires 'id' string")?;
+                let size_mb = params
+                    .get("sizeMb")
+                    .and_then(|v| v.as_f64())
+                    .ok_or("gpu/reserve requires 'sizeMb' number")?;
+                if size_mb <= 0.0 {
+                    return Err("sizeMb must be > 0".to_string());
+                }
+                let ttl_ms = params
+                    .get("ttlMs")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(DEFAULT_RESERVATION_TTL_MS);
+                let subsystem_name = params
+                    .get("subsystem")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("inference");
+                let subsystem = GpuSubsystem::from_name(subsystem_name).ok_or_else(|| {
+                    format!(
+                        "Unknown subsystem '{}'. Valid: rendering, inference, tts",
+                        subsystem_name
+                    )
+                })?;
+                let priority_name = params
+                    .get("priority")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("interactive");
+                let priority = GpuPriority::from_name(priority_name).ok_or_else(|| {
+                    format!(
+                        "Unknown priority '{}'. Valid: realtime, interactive, background, batch",
+                        priority_name
+                    )
+                })?;
+
+                self.prune_reservations()?;
+                let reservation = self
+                    .manager
+                    .reserve(
+                        id,
+                        subsystem,
+                        (size_mb * 1024.0 * 1024.0) as u64,
+                        priority,
+                        Duration::from_millis(ttl_ms),
+                    )
+                    .map_err(|e| e.to_string())?;
+                let bytes = reservation.bytes();
+                self.reservations
+                    .lock()
+                    .map_err(|e| format!("Lock poisoned: {e}"))?
+                    .insert(id.to_string(), reservation);
+
+                Ok(CommandResult::Json(serde_json::json!({
+                    "reserved": true,
+                    "id": id,
+                    "bytes": bytes,
+                    "ttlMs": ttl_ms,
+                    "pressure": self.manager.pressure(),
+                })))
+            }
+
+            // Commit a reservation once its load succeeded. The memory stays
+            // allocated and the consumer is registered for eviction visibility
+            // until gpu/unregister-consumer.
+            // Params: id (string), label (string, defaults to id)
+            "gpu/commit-reservation" => {
+                let id = params
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .ok_or("gpu/commit-reservation requires 'id' string")?;
+                let label = params.get("label").and_then(|v| v.as_str()).unwrap_or(id);
+
+                let reservation = self
+                    .reservations
+                    .lock()
+                    .map_err(|e| format!("Lock poisoned: {e}"))?
+                    .remove(id)
+                    .ok_or_else(|| format!("No GPU reservation '{id}'"))?;
+                let guard = reservation.commit().map_err(|e| e.to_string())?;
+
+                let bytes = guard.bytes();
+                self.manager.eviction_registry.register(make_entry(
+                    id,
+                    label,
+                    guard.priority(),
+                    bytes,
+                ));
+                self.committed
+                    .lock()
+                    .map_err(|e| format!("Lock poisoned: {e}"))?
+                    .insert(id.to_string(), guard);
+
+                Ok(CommandResult::Json(serde_json::json!({
+                    "committed": true,
+                    "id": id,
+                    "bytes": bytes,
+                    "pressure": self.manager.pressure(),
+                })))
+            }
+
+            // Release a reservation whose load failed or was abandoned.
+            // Params: id (string)
+            "gpu/cancel-reservation" => {
+                let id = params
+                    .get("id")
+                    .and_then(|v| v.as_str())
+                    .ok_or("gpu/cancel-reservation requires 'id' string")?;
+
+                let reservation = self
+                    .reservations
+                    .lock()
+                    .map_err(|e| format!("Lock poisoned: {e}"))?
+                    .remove(id);
+                let cancelled = reservation.is_some();
+                if let Some(reservation) = reservation {
+                    reservation.cancel();
+                }
+
+                Ok(CommandResult::Json(serde_json::json!({
+                    "cancelled": cancelled,
+                    "id": id,
+                    "pressure": self.manager.pressure(),
+                })))
+            }
+
             _ => Err(format!("Unknown GPU command: {command}")),
         }
     }
@@ -397,6 +562,96 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_reserve_commit_and_unregister() {
+        let module = test_gpu_module();
+        let before = module.manager.stats().inference.used_mb;
+
+        let params = serde_json::json!({ "id": "candle:model:test", "sizeMb": 64.0 });
+        let result = module.handle_command("gpu/reserve", params).await.unwrap();
+        if let CommandResult::Json(json) = result {
+            assert_eq!(json["reserved"], true);
+            assert_eq!(json["bytes"].as_u64().unwrap(), 64 * 1024 * 1024);
+        }
+        let stats = module.manager.stats();
+        assert_eq!(stats.inference.reserved_mb, 64.0);
+        assert_eq!(stats.reservations.len(), 1);
+
+        let params = serde_json::json!({ "id": "candle:model:test", "label": "Test model" });
+        module
+            .handle_command("gpu/commit-reservation", params.clone())
+            .await
+            .unwrap();
+        let stats = module.manager.stats();
+        assert_eq!(stats.inference.reserved_mb, 0.0);
+        assert_eq!(stats.inference.used_mb - before, 64.0);
+        assert_eq!(module.manager.eviction_registry.len(), 1);
+
+        // Already committed: nothing left to commit
+        assert!(module
+            .handle_command("gpu/commit-reservation", params)
+            .await
+            .is_err());
+
+        let params = serde_json::json!({ "id": "candle:model:test" });
+        module
+            .handle_command("gpu/unregister-consumer", params)
+            .await
+            .unwrap();
+        assert_eq!(module.manager.stats().inference.used_mb, before);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_expired_reservation() {
+        let module = test_gpu_module();
+        let before = module.manager.stats().tts.used_mb;
+
+        let params = serde_json::json!({
+            "id": "tts:kokoro",
+            "sizeMb": 32.0,
+            "subsystem": "tts"
+        });
+        module.handle_command("gpu/reserve", params).await.unwrap();
+        let params = serde_json::json!({ "id": "tts:kokoro" });
+        let result = module
+            .handle_command("gpu/cancel-reservation", params)
+            .await
+            .unwrap();
+        if let CommandResult::Json(json) = result {
+            assert_eq!(json["cancelled"], true);
+        }
+        assert_eq!(module.manager.stats().tts.used_mb, before);
+
+        let params = serde_json::json!({
+            "id": "tts:piper",
+            "sizeMb": 32.0,
+            "subsystem": "tts",
+            "ttlMs": 0
+        });
+        module.handle_command("gpu/reserve", params).await.unwrap();
+        assert_eq!(module.manager.stats().tts.used_mb, before);
+        let params = serde_json::json!({ "id": "tts:piper" });
+        let err = module
+            .handle_command("gpu/commit-reservation", params)
+            .await
+            .unwrap_err();
+        assert!(err.contains("expired"), "{err}");
+
+        // Expired handles are dropped on the next reserve
+        let params = serde_json::json!({
+            "id": "tts:piper",
+            "sizeMb": 32.0,
+            "subsystem": "tts",
+            "ttlMs": 0
+        });
+        module.handle_command("gpu/reserve", params).await.unwrap();
+        let params = serde_json::json!({ "id": "tts:pocket", "sizeMb": 32.0, "subsystem": "tts" });
+        module.handle_command("gpu/reserve", params).await.unwrap();
+        let reservations = module.reservations.lock().unwrap();
+        assert!(!reservations.contains_key("tts:piper"));
+        assert!(reservations.contains_key("tts:pocket"));
+    }
+
     #[tokio::test]
     async fn test_register_consumer_missing_params() {
         let module = test_gpu_module();
@@ -1,10 +1,15 @@
 //! HealthModule — the trivial outlier that validates the ServiceModule interface.
 //!
-//! Handles: health-check, get-stats
+//! Handles: health-check, health-status (alias: get-stats)
 //! This is Phase 1: if this module routes correctly through the registry,
 //! the ServiceModule trait design is proven for the simplest case.
+//!
+//! health-status reports the process-wide ServerStats (uptime, connections,
+//! queue depth, requests handled, RSS) for supervisors making load decisions.
 
-use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
+use crate::runtime::{
+    server_stats, CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule,
+};
 use async_trait::async_trait;
 use serde_json::Value;
 use std::any::Any;
@@ -57,12 +62,7 @@ impl ServiceModule for HealthModule {
                 })))
             }
 
-            "get-stats" => {
-                // Stats tracking not yet implemented — stub matches legacy behavior
-                Ok(CommandResult::Json(serde_json::json!({
-                    "note": "Performance stats tracking not yet implemented"
-                })))
-            }
+            "health-status" | "get-stats" => CommandResult::json(&server_stats().status()),
 
             _ => Err(format!("Unknown health command: {command}")),
         }
@@ -87,4 +87,18 @@ mod tests {
             assert!(json["uptime_seconds"].is_number());
         }
     }
+
+    #[tokio::test]
+    async fn test_health_status() {
+        let module = HealthModule::new();
+        let _request = server_stats().track_request();
+        let result = module.handle_command("health-status", Value::Null).await;
+        let Ok(CommandResult::Json(json)) = result else {
+            panic!("health-status failed");
+        };
+        assert!(json["queueDepth"].as_u64().unwrap() >= 1);
+        assert!(json["totalRequests"].as_u64().unwrap() >= 1);
+        assert!(json["activeConnections"].is_number());
+        assert!(json["rssBytes"].is_number());
+    }
 }
@@ -2,8 +2,11 @@
 //!
 //! Handles: voice/register-session, voice/on-utterance, voice/should-route-tts,
 //!          voice/synthesize, voice/speak-in-call, voice/synthesize-handle,
-//!          voice/play-handle, voice/discard-handle, voice/transcribe,
-//!          voice/transcribe-with-adapter, voice/stt-list,
+//!          voice/play-handle, voice/discard-handle,
+//!          voice/tts-cache-stats, voice/tts-cache-configure,
+//!          voice/tts-cache-clear, voice/transcribe,
+//!          voice/transcribe-with-adapter, voice/stt-list, voice/stt-load-model,
+//!          voice/stt-set-param,
 //!          voice/test-audio-generate,
 //!          voice/inject-audio, voice/ambient-add, voice/ambient-inject,
 //!          voice/ambient-remove, voice/poll-transcriptions,
@@ -11,20 +14,29 @@
 //!          voice/snapshot-room, voice/snapshot-participant
 //!
 //! Priority: Realtime — voice operations are time-critical.
+//!
+//! Speech prosody: explicit `speed`/`pitch_semitones` params win; otherwise
+//! the speaking persona's mood picks them from its mood → prosody table
+//! (`persona_id` for voice/synthesize*, the speaker for voice/speak-in-call).
 
+use crate::live::audio::aec::AecConfig;
 use crate::live::audio::buffer::AudioBufferPool;
 use crate::live::audio::resource_lifecycle::AudioResourceLifecycle;
+use crate::live::audio::tts::Prosody;
 use crate::live::session::voice_service::VoiceService;
-use crate::live::transport::livekit_agent::LiveKitAgentManager;
+use crate::live::transport::livekit_agent::{LiveKitAgentManager, SttListenerConfig};
 use crate::live::{UtteranceEvent, VoiceParticipant};
 use crate::logging::TimingGuard;
+use crate::persona::{ChannelRegistry, PersonaState};
 use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
 use crate::utils::params::Params;
 use crate::{log_error, log_info};
 use async_trait::async_trait;
+use dashmap::DashMap;
 use serde_json::Value;
 use std::any::Any;
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// Response field name for voice responder IDs
 const VOICE_RESPONSE_FIELD_RESPONDER_IDS: &str = "responder_ids";
@@ -35,6 +47,8 @@ pub struct VoiceState {
     pub livekit_manager: Arc<LiveKitAgentManager>,
     pub audio_pool: Arc<AudioBufferPool>,
     pub resource_lifecycle: Arc<AudioResourceLifecycle>,
+    /// Per-persona state (shared with ChannelModule) — mood drives prosody
+    pub persona_states: Option<Arc<DashMap<Uuid, (ChannelRegistry, PersonaState)>>>,
 }
 
 impl VoiceState {
@@ -49,8 +63,38 @@ impl VoiceState {
             livekit_manager,
             audio_pool,
             resource_lifecycle,
+            persona_states: None,
         }
     }
+
+    /// Use persona moods (from ChannelModule's state) to shape speech
+    pub fn with_persona_states(
+        mut self,
+        persona_states: Arc<DashMap<Uuid, (ChannelRegistry, PersonaState)>>,
+    ) -> Self {
+        self.persona_states = Some(persona_states);
+        self
+    }
+
+    /// Prosody for a persona's current mood, if it has state
+    fn mood_prosody(&self, persona_id: &str) -> Option<Prosody> {
+        let persona_id = Uuid::parse_str(persona_id).ok()?;
+        let entry = self.persona_states.as_ref()?.get(&persona_id)?;
+        Some(entry.value().1.voice_prosody())
+    }
+
+    /// Prosody for a synthesis request: explicit `speed`/`pitch_semitones`
+    /// params win, otherwise the speaking persona's mood decides
+    fn request_prosody(&self, p: &Params, persona_id: Option<&str>) -> Result<Prosody, String> {
+        let speed = p.f32_opt("speed");
+        let pitch = p.f32_opt("pitch_semitones");
+        if speed.is_none() && pitch.is_none() {
+            if let Some(prosody) = persona_id.and_then(|id| self.mood_prosody(id)) {
+                return Ok(prosody);
+            }
+        }
+        Prosody::new(speed.unwrap_or(1.0), pitch.unwrap_or(0.0))
+    }
 }
 
 pub struct VoiceModule {
@@ -94,6 +138,55 @@ impl ServiceModule for VoiceModule {
                 let session_id = p.str("session_id")?;
                 let room_id = p.str("room_id")?;
                 let participants: Vec<VoiceParticipant> = p.json_or("participants");
+                // Optional: stt_task "translate" makes the listener publish English
+                // transcripts for speech in any language; stt_adapter picks a
+                // registered backend by name instead of the active one;
+                // stt_vocabulary biases Whisper toward names and jargon for
+                // this session only; stt_affect adds arousal/valence estimates
+                // to the transcripts routed to AI participants;
+                // stt_endpoint_silence_ms sets how much trailing silence ends
+                // an utterance; hands_free cancels the personas' TTS echo from
+                // speakerphone audio; stt_noise_suppression (0-3) denoises
+                // speakers' audio before VAD
+                let stt_config = {
+                    use crate::live::audio::stt::{self, SttTask};
+                    let mut adapter = p
+                        .str_opt("stt_adapter")
+                        .map(stt::get_adapter)
+                        .transpose()
+                        .map_err(|e| e.to_string())?;
+                    let noise_suppression = match p.u32_opt("stt_noise_suppression") {
+                        Some(level @ 0..=3) => Some(level as u8),
+                        Some(level) => {
+                            return Err(format!("stt_noise_suppression must be 0-3, got {level}"))
+                        }
+                        None => None,
+                    };
+                    let vocabulary: Vec<String> = p.json_or("stt_vocabulary");
+                    if !vocabulary.is_empty() {
+                        let base = adapter
+                            .clone()
+                            .or_else(|| stt::get_registry().read().get_active())
+                            .ok_or("stt_vocabulary requires an STT adapter")?;
+                        // Shares the base adapter's model; only the prompt is per session
+                        adapter = Some(
+                            base.with_vocabulary(vocabulary)
+                                .map_err(|e| e.to_string())?,
+                        );
+                    }
+                    SttListenerConfig {
+                        task: SttTask::parse(
+                            p.str_opt("stt_task").unwrap_or("transcribe"),
+                            p.bool_or("keep_original", false),
+                        )
+                        .map_err(|e| e.to_string())?,
+                        adapter,
+                        estimate_affect: p.bool_or("stt_affect", false),
+                        endpoint_silence_ms: p.u32_opt("stt_endpoint_silence_ms"),
+                        echo_cancellation: p.bool_or("hands_free", false).then(AecConfig::default),
+                        noise_suppression,
+                    }
+                };
 
                 // Extract AI participant info BEFORE register_session consumes the vec
                 let ai_participants: Vec<(String, String)> = participants
@@ -125,7 +218,7 @@ impl ServiceModule for VoiceModule {
 
                 tokio::spawn(async move {
                     // Phase 1: STT listener (highest priority — enables transcription)
-                    if let Err(e) = livekit_manager.join_as_listener(&call_id).await {
+                    if let Err(e) = livekit_manager.join_as_listener(&call_id, stt_config).await {
                         log_error!(
                             "module",
                             "voice_register_session",
@@ -251,14 +344,19 @@ impl ServiceModule for VoiceModule {
                 let text = p.str("text")?;
                 let voice = p.str_opt("voice");
                 let adapter = p.str_opt("adapter");
+                // Optional: speech rate multiplier and pitch shift in semitones,
+                // or the mood of the persona given by persona_id
+                let prosody = self.state.request_prosody(&p, p.str_opt("persona_id"))?;
 
                 use crate::live::audio::tts_service;
-                let synthesis = tts_service::synthesize_speech_async(text, voice, adapter, None)
-                    .await
-                    .map_err(|e| {
-                        log_error!("module", "voice_synthesize", "TTS failed: {}", e);
-                        format!("TTS synthesis failed: {}", e)
-                    })?;
+                let synthesis = tts_service::synthesize_speech_prosody_async(
+                    text, voice, adapter, None, prosody,
+                )
+                .await
+                .map_err(|e| {
+                    log_error!("module", "voice_synthesize", "TTS failed: {}", e);
+                    format!("TTS synthesis failed: {}", e)
+                })?;
 
                 let pcm_bytes: Vec<u8> = synthesis
                     .samples
@@ -299,11 +397,21 @@ impl ServiceModule for VoiceModule {
                 // Tells us WHERE in the conversation this response belongs.
                 // TODO: Use for Rust-side TTS output scheduling (ordering + stale detection).
                 let _timeline_seq = p.u64_opt("timeline_seq");
+                // The speaker's mood shapes its voice unless speed/pitch are given
+                let prosody = self.state.request_prosody(&p, Some(user_id))?;
 
                 let (num_samples, duration_ms, sample_rate) = self
                     .state
                     .livekit_manager
-                    .speak_in_call(call_id, user_id, text, voice, adapter, display_name)
+                    .speak_in_call(
+                        call_id,
+                        user_id,
+                        text,
+                        voice,
+                        adapter,
+                        display_name,
+                        prosody,
+                    )
                     .await
                     .map_err(|e| {
                         log_error!(
@@ -337,14 +445,19 @@ impl ServiceModule for VoiceModule {
                 let text = p.str("text")?;
                 let voice = p.str_opt("voice");
                 let adapter = p.str_opt("adapter");
+                // Optional: speech rate multiplier and pitch shift in semitones,
+                // or the mood of the persona given by persona_id
+                let prosody = self.state.request_prosody(&p, p.str_opt("persona_id"))?;
 
                 use crate::live::audio::tts_service;
-                let synthesis = tts_service::synthesize_speech_async(text, voice, adapter, None)
-                    .await
-                    .map_err(|e| {
-                        log_error!("module", "voice_synthesize_handle", "TTS failed: {}", e);
-                        format!("TTS synthesis failed: {}", e)
-                    })?;
+                let synthesis = tts_service::synthesize_speech_prosody_async(
+                    text, voice, adapter, None, prosody,
+                )
+                .await
+                .map_err(|e| {
+                    log_error!("module", "voice_synthesize_handle", "TTS failed: {}", e);
+                    format!("TTS synthesis failed: {}", e)
+                })?;
 
                 let adapter_name = adapter.unwrap_or("default");
                 let info = self.state.audio_pool.store(
@@ -439,11 +552,48 @@ impl ServiceModule for VoiceModule {
                 ))
             }
 
+            "voice/tts-cache-stats" => {
+                use crate::live::audio::tts;
+                CommandResult::json(&tts::get_registry().read().cache_stats())
+            }
+
+            "voice/tts-cache-configure" => {
+                use crate::live::audio::tts;
+                // New limits (0 disables caching)
+                let max_entries = p.u64("max_entries")?;
+                let max_bytes = p.u64("max_bytes")?;
+                let registry = tts::get_registry();
+                let reg = registry.read();
+                reg.set_cache_limits(max_entries as usize, max_bytes as usize);
+                CommandResult::json(&reg.cache_stats())
+            }
+
+            "voice/tts-cache-clear" => {
+                use crate::live::audio::tts;
+                tts::get_registry().read().clear_cache();
+                Ok(CommandResult::Json(serde_json::json!({ "cleared": true })))
+            }
+
             "voice/transcribe" => {
                 let _timer = TimingGuard::new("module", "voice_transcribe");
                 let audio = p.str("audio")?;
                 let language = p.str_opt("language");
-
+                // Optional: split long audio into (overlapping) STT windows,
+                // also ending a window at endpointSilenceMs of non-speech
+                let window_secs = p.f32_opt("windowSecs");
+                let overlap_secs = p.f32_opt("overlapSecs").unwrap_or(0.0);
+                let endpoint_silence_ms = p.u32_opt("endpointSilenceMs");
+                // Optional: "translate" emits English text; keepOriginal also
+                // returns the spoken-language text
+                let task = crate::live::audio::stt::SttTask::parse(
+                    p.str_opt("task").unwrap_or("transcribe"),
+                    p.bool_or("keepOriginal", false),
+                )
+                .map_err(|e| e.to_string())?;
+                // Optional: estimate arousal/valence from the audio's prosody
+                let affect = p.bool_or("affect", false);
+
+                use crate::live::audio::sliding_buffer::{self, SlidingAudioBuffer};
                 use crate::live::audio::stt_service;
                 use base64::Engine;
 
@@ -471,12 +621,30 @@ impl ServiceModule for VoiceModule {
                     samples.len() as f64 / crate::audio_constants::AUDIO_SAMPLE_RATE as f64
                 );
 
-                let transcript = stt_service::transcribe_speech_async(&samples, language)
+                let mut transcript = if window_secs.is_some() || endpoint_silence_ms.is_some() {
+                    let mut windows = SlidingAudioBuffer::new(
+                        window_secs.unwrap_or(sliding_buffer::DEFAULT_WINDOW_SECS),
+                        overlap_secs,
+                    )?;
+                    if let Some(silence_ms) = endpoint_silence_ms {
+                        windows = windows.with_endpointing(silence_ms)?;
+                    }
+                    stt_service::transcribe_windowed_async(&samples, language, task, windows).await
+                } else {
+                    stt_service::transcribe_speech_task_async(&samples, language, task).await
+                }
+                .map_err(|e| {
+                    log_error!("module", "voice_transcribe", "STT failed: {}", e);
+                    format!("STT failed: {}", e)
+                })?;
+                if affect {
+                    // A pitch track over the whole clip: keep it off the runtime
+                    transcript.affect = tokio::task::spawn_blocking(move || {
+                        crate::live::audio::stt::estimate_affect(&samples)
+                    })
                     .await
-                    .map_err(|e| {
-                        log_error!("module", "voice_transcribe", "STT failed: {}", e);
-                        format!("STT failed: {}", e)
-                    })?;
+                    .map_err(|e| format!("Affect estimate failed: {e}"))?;
+                }
 
                 log_info!(
                     "module",
@@ -487,8 +655,11 @@ impl ServiceModule for VoiceModule {
                 );
                 Ok(CommandResult::Json(serde_json::json!({
                     "text": transcript.text,
+                    "originalText": transcript.original_text,
                     "language": transcript.language,
                     "confidence": transcript.confidence,
+                    "arousal": transcript.affect.map(|a| a.arousal),
+                    "valence": transcript.affect.map(|a| a.valence),
                     "segments": transcript.segments.iter().map(|s| {
                         serde_json::json!({
                             "text": s.text,
@@ -594,6 +765,7 @@ impl ServiceModule for VoiceModule {
                             "name": name,
                             "initialized": initialized,
                             "description": desc,
+                            "model": reg.model(name),
                         })
                     })
                     .collect();
@@ -606,9 +778,63 @@ impl ServiceModule for VoiceModule {
                 Ok(CommandResult::Json(serde_json::json!({
                     "adapters": adapters,
                     "active": active,
+                    "active_model": reg.active_model(),
+                })))
+            }
+
+            "voice/stt-load-model" => {
+                let _timer = TimingGuard::new("module", "voice_stt_load_model");
+                // A size name ("tiny.en", "medium") or a model file path;
+                // adapter defaults to the active one
+                let model = p.str("model")?;
+                let adapter = p.str_opt("adapter");
+
+                let loaded = crate::live::audio::stt::load_model(adapter, model)
+                    .await
+                    .map_err(|e| {
+                        log_error!(
+                            "module",
+                            "voice_stt_load_model",
+                            "Loading STT model '{}' failed: {}",
+                            model,
+                            e
+                        );
+                        format!("Loading STT model '{}' failed: {}", model, e)
+                    })?;
+
+                log_info!(
+                    "module",
+                    "voice_stt_load_model",
+                    "STT model switched to '{}'",
+                    loaded
+                );
+                Ok(CommandResult::Json(serde_json::json!({
+                    "success": true,
+                    "model": loaded,
                 })))
             }
 
+            "voice/stt-set-param" => {
+                let _timer = TimingGuard::new("module", "voice_stt_set_param");
+                // e.g. whisper's no_speech_max / avg_logprob_min;
+                // adapter defaults to the active one
+                let name = p.str("name")?;
+                let value = p.str("value")?;
+                let adapter = p.str_opt("adapter");
+
+                crate::live::audio::stt::set_param(adapter, name, value)
+                    .map_err(|e| format!("Setting STT parameter '{}' failed: {}", name, e))?;
+
+                log_info!(
+                    "module",
+                    "voice_stt_set_param",
+                    "STT parameter '{}' set to '{}'",
+                    name,
+                    value
+                );
+                Ok(CommandResult::Json(serde_json::json!({ "success": true })))
+            }
+
             "voice/transcribe-with-adapter" => {
                 let _timer = TimingGuard::new("module", "voice_transcribe_with_adapter");
                 let audio = p.str("audio")?;
@@ -3,7 +3,10 @@
 //! High-performance log file management with:
 //! - Batched flushing (every 250ms or 200 messages)
 //! - Per-category rate limiting (100 msg/sec default)
+//! - Per-connection admission control (token bucket + bounded queue)
 //! - File handle caching (files stay open)
+//! - Rotation by size and age (timestamped, gzipped, oldest pruned)
+//! - Text (default) or JSONL output, per entry or globally via LOGGER_FORMAT
 //! - Auto-recovery if log files deleted
 //! - Per-file locking (no global contention)
 //! - Global sender for clog_* macros (non-blocking)
@@ -20,16 +23,21 @@
 //!
 //! Migration from: workers/logger (222 lines main.rs + 4 modules)
 
-use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
+use crate::runtime::{
+    current_connection_id, CommandResult, ModuleConfig, ModuleContext, ModulePriority,
+    ServiceModule,
+};
 use async_trait::async_trait;
 use chrono::{SecondsFormat, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::any::Any;
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{mpsc, Arc, Mutex, OnceLock};
 use std::thread;
@@ -44,9 +52,14 @@ use ts_rs::TS;
 /// Uses SyncSender with try_send() for GUARANTEED non-blocking.
 static GLOBAL_LOG_SENDER: OnceLock<mpsc::SyncSender<WriteLogPayload>> = OnceLock::new();
 
-/// Channel capacity - if full, new messages dropped (NEVER blocks)
+/// Channel capacity (the queue's high-water mark) unless LOGGER_QUEUE_CAPACITY
+/// overrides it - if full, new messages dropped (NEVER blocks)
 const CLOG_CHANNEL_CAPACITY: usize = 4096;
 
+/// Lines/sec one IPC connection may queue unless LOGGER_CONNECTION_RATE
+/// overrides it (0 = unlimited). Bursts up to twice this are admitted.
+const CONNECTION_RATE_PER_SEC: u32 = 1000;
+
 /// Queue a log entry for async writing (called by clog_* macros).
 /// GUARANTEED NON-BLOCKING: Uses try_send(), drops if channel full.
 /// If LoggerModule not yet initialized, message is dropped.
@@ -59,6 +72,7 @@ pub fn queue_log(category: &str, level: LogLevel, component: &str, message: &str
             component: component.to_string(),
             message: message.to_string(),
             args: None,
+            format: None,
         };
         // GUARANTEED NON-BLOCKING: try_send returns immediately
         // If channel full, message dropped - NEVER blocks caller
@@ -93,6 +107,32 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
+/// How entries are written to disk.
+///
+/// Text is the human-readable format existing tailing relies on. JSONL
+/// writes one JSON object per line for log tooling, to a sibling `.jsonl`
+/// file so the two formats never share a file.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[ts(export, export_to = "../../../shared/generated/logger/LogFormat.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Jsonl,
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "jsonl" | "json" => Ok(LogFormat::Jsonl),
+            other => Err(format!("Unknown log format: {other}")),
+        }
+    }
+}
+
 /// Payload for log/write requests.
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(
@@ -108,6 +148,10 @@ pub struct WriteLogPayload {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[ts(type = "any", optional)]
     pub args: Option<Value>,
+    /// Output format for this entry; defaults to LOGGER_FORMAT (text if unset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[ts(optional)]
+    pub format: Option<LogFormat>,
 }
 
 /// Result of log/write command.
@@ -147,6 +191,8 @@ pub struct LoggerPingResult {
     pub requests_processed: u64,
     pub active_categories: usize,
     pub pending_writes: usize,
+    /// Lines refused by connection rate limits or a full queue
+    pub dropped_messages: u64,
 }
 
 // ============================================================================
@@ -227,6 +273,83 @@ impl RateLimiter {
     }
 }
 
+/// Token bucket for one IPC connection
+struct ConnectionBucket {
+    tokens: f64,
+    last_refill: Instant,
+    /// Lines dropped since the last "messages dropped" summary
+    dropped: u64,
+}
+
+/// Per-connection admission control for log/write and log/write-batch, so
+/// one client in a tight logging loop can't fill the writer queue for
+/// everyone else.
+struct ConnectionLimiter {
+    rate_per_sec: f64,
+    burst: f64,
+    connections: HashMap<u64, ConnectionBucket>,
+}
+
+impl ConnectionLimiter {
+    fn new(rate_per_sec: u32) -> Self {
+        Self {
+            rate_per_sec: rate_per_sec as f64,
+            burst: rate_per_sec as f64 * 2.0,
+            connections: HashMap::new(),
+        }
+    }
+
+    /// Take tokens for up to `count` lines; returns how many are admitted
+    fn admit(&mut self, connection: u64, count: usize, now: Instant) -> usize {
+        if self.rate_per_sec == 0.0 {
+            return count;
+        }
+        let (rate, burst) = (self.rate_per_sec, self.burst);
+        let bucket = self
+            .connections
+            .entry(connection)
+            .or_insert_with(|| ConnectionBucket {
+                tokens: burst,
+                last_refill: now,
+                dropped: 0,
+            });
+
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(burst);
+        bucket.last_refill = now;
+
+        let admitted = (bucket.tokens.floor() as usize).min(count);
+        bucket.tokens -= admitted as f64;
+        admitted
+    }
+
+    fn record_dropped(&mut self, connection: u64, dropped: u64) {
+        if let Some(bucket) = self.connections.get_mut(&connection) {
+            bucket.dropped += dropped;
+        }
+    }
+
+    /// Dropped count owed a summary line, reset to zero
+    fn take_dropped(&mut self, connection: u64) -> u64 {
+        self.connections
+            .get_mut(&connection)
+            .map(|bucket| std::mem::take(&mut bucket.dropped))
+            .unwrap_or(0)
+    }
+
+    fn remove(&mut self, connection: u64) {
+        self.connections.remove(&connection);
+    }
+}
+
+/// Read a numeric setting from the environment, falling back to `default`
+fn env_setting<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
 // ============================================================================
 // File Manager (from legacy file_manager.rs)
 // ============================================================================
@@ -279,37 +402,156 @@ fn resolve_log_path(category: &str, log_dir: &str, continuum_root: &str) -> Path
     }
 }
 
-/// Max log file size before rotation (10 MB). Prevents unbounded growth during long sessions.
+/// Max log file size before rotation (10 MB) unless LOGGER_MAX_FILE_BYTES
+/// overrides it. Prevents unbounded growth during long sessions.
 const MAX_LOG_FILE_SIZE: u64 = 10 * 1024 * 1024;
 
+/// Max log file age before rotation (1 day) unless LOGGER_MAX_FILE_AGE_SECS
+/// overrides it (0 = rotate by size only)
+const MAX_LOG_FILE_AGE_SECS: u64 = 24 * 60 * 60;
+
+/// Rotated copies kept per log file unless LOGGER_MAX_ROTATED_FILES overrides it
+const MAX_ROTATED_FILES: usize = 5;
+
+/// When a log file is rotated, and what happens to the rotated copies
+#[derive(Debug, Clone)]
+struct RotationPolicy {
+    max_bytes: u64,
+    /// Measured from the file's creation time; filesystems that don't record
+    /// one rotate by size only
+    max_age: Option<Duration>,
+    /// Rotated copies kept; older ones are deleted
+    max_files: usize,
+    /// Compress rotated copies to `.gz`
+    gzip: bool,
+}
+
+impl RotationPolicy {
+    fn from_env() -> Self {
+        let max_age_secs = env_setting("LOGGER_MAX_FILE_AGE_SECS", MAX_LOG_FILE_AGE_SECS);
+        Self {
+            max_bytes: env_setting("LOGGER_MAX_FILE_BYTES", MAX_LOG_FILE_SIZE),
+            max_age: (max_age_secs > 0).then(|| Duration::from_secs(max_age_secs)),
+            max_files: env_setting("LOGGER_MAX_ROTATED_FILES", MAX_ROTATED_FILES),
+            gzip: env_setting("LOGGER_GZIP_ROTATED", true),
+        }
+    }
+
+    fn is_due(&self, meta: &fs::Metadata) -> bool {
+        if meta.len() > self.max_bytes {
+            return true;
+        }
+        let age = meta
+            .created()
+            .ok()
+            .and_then(|created| created.elapsed().ok());
+        meta.len() > 0 && matches!((self.max_age, age), (Some(max), Some(age)) if age >= max)
+    }
+}
+
+/// Move a log file aside as `{name}.{timestamp}`, then (off the writer
+/// thread) gzip it if enabled and delete the oldest copies beyond `max_files`.
+fn rotate_log_file(path: &Path, policy: &RotationPolicy) -> std::io::Result<()> {
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S%.3f");
+    let rotated = PathBuf::from(format!("{}.{stamp}", path.display()));
+    fs::rename(path, &rotated)?;
+
+    let path = path.to_path_buf();
+    let policy = policy.clone();
+    thread::spawn(move || {
+        if policy.gzip {
+            if let Err(e) = gzip_file(&rotated) {
+                eprintln!("❌ LoggerModule gzip error for {}: {e}", rotated.display());
+            }
+        }
+        prune_rotated(&path, policy.max_files);
+    });
+    Ok(())
+}
+
+/// Compress `path` to `path.gz` and remove the original
+fn gzip_file(path: &Path) -> std::io::Result<()> {
+    let gz_path = PathBuf::from(format!("{}.gz", path.display()));
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(File::create(&gz_path)?, Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
+
+/// Delete all but the newest `max_files` rotated copies of `path`
+fn prune_rotated(path: &Path, max_files: usize) {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return;
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    // Timestamp suffixes sort chronologically
+    let mut rotated: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .strip_prefix(&prefix)
+                .is_some_and(|suffix| suffix.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    rotated.sort();
+
+    let excess = rotated.len().saturating_sub(max_files);
+    for old in &rotated[..excess] {
+        let _ = fs::remove_file(old);
+    }
+}
+
 fn ensure_file_handle(
     category: &str,
     log_file_path: &PathBuf,
     file_cache: &FileCache,
     headers_written: &HeaderTracker,
+    rotation: &RotationPolicy,
 ) -> std::io::Result<()> {
     let mut cache = file_cache.lock().unwrap_or_else(|e| e.into_inner());
 
-    // Check if cached file was deleted or exceeded size limit
-    if let Some(existing) = cache.get(category) {
-        let needs_reopen = {
+    // Check if the cached file was deleted or is due for rotation. A file
+    // left over from a previous run is checked before it's opened.
+    let (evict, rotate) = match cache.get(category) {
+        Some(existing) => {
             let file = existing.lock().unwrap_or_else(|e| e.into_inner());
             match file.metadata() {
-                Err(_) => true,                             // File deleted
-                Ok(meta) => meta.len() > MAX_LOG_FILE_SIZE, // File too large
-            }
-        };
-        if needs_reopen {
-            cache.remove(category);
-            headers_written
-                .lock()
-                .unwrap_or_else(|e| e.into_inner())
-                .remove(category);
-            // Truncate the oversized file
-            if log_file_path.exists() {
-                let _ = fs::write(log_file_path, b"");
+                Err(_) => (true, false), // File deleted
+                Ok(meta) => {
+                    let due = rotation.is_due(&meta);
+                    (due, due)
+                }
             }
         }
+        None => {
+            let due = fs::metadata(log_file_path).is_ok_and(|meta| rotation.is_due(&meta));
+            (false, due)
+        }
+    };
+
+    // Drop the handle before renaming so it's never written to again
+    if evict {
+        cache.remove(category);
+        headers_written
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(category);
+    }
+    if rotate {
+        if let Err(e) = rotate_log_file(log_file_path, rotation) {
+            eprintln!(
+                "❌ LoggerModule rotation error for {}: {e}",
+                log_file_path.display()
+            );
+        }
     }
 
     if !cache.contains_key(category) {
@@ -332,51 +574,98 @@ fn write_log_message(
     continuum_root: &str,
     file_cache: &FileCache,
     headers_written: &HeaderTracker,
+    rotation: &RotationPolicy,
+    format: LogFormat,
 ) -> std::io::Result<usize> {
-    let log_file_path = resolve_log_path(&payload.category, log_dir, continuum_root);
+    let mut log_file_path = resolve_log_path(&payload.category, log_dir, continuum_root);
     let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
 
+    // Handles and headers are tracked per file, and JSONL has its own file
+    let file_key = match format {
+        LogFormat::Text => payload.category.clone(),
+        LogFormat::Jsonl => {
+            log_file_path.set_extension("jsonl");
+            format!("{}.jsonl", payload.category)
+        }
+    };
+
     ensure_file_handle(
-        &payload.category,
+        &file_key,
         &log_file_path,
         file_cache,
         headers_written,
+        rotation,
     )?;
 
     let mut total_bytes = 0;
     let needs_header = !headers_written
         .lock()
         .unwrap_or_else(|e| e.into_inner())
-        .contains(&payload.category);
+        .contains(&file_key);
 
     if needs_header {
         total_bytes += write_header(
             &payload.component,
             &payload.category,
+            &file_key,
             &timestamp,
+            format,
             file_cache,
             headers_written,
         )?;
     }
 
-    let log_entry = format_log_entry(payload, &timestamp);
-    total_bytes += write_entry(&payload.category, &log_entry, file_cache)?;
+    let log_entry = match format {
+        LogFormat::Text => format_log_entry(payload, &timestamp),
+        LogFormat::Jsonl => format_jsonl_entry(
+            &timestamp,
+            payload.level,
+            &payload.category,
+            &payload.component,
+            &payload.message,
+            payload.args.as_ref(),
+        ),
+    };
+    total_bytes += write_entry(&file_key, &log_entry, file_cache)?;
 
     Ok(total_bytes)
 }
 
+/// Start-of-session header: a banner in text files, a session-start record
+/// in JSONL files (so every line parses the same way)
 fn write_header(
     component: &str,
     category: &str,
+    file_key: &str,
     timestamp: &str,
+    format: LogFormat,
     file_cache: &FileCache,
     headers_written: &HeaderTracker,
 ) -> std::io::Result<usize> {
+    let session = format!("session-{}", Utc::now().timestamp_millis());
+    if format == LogFormat::Jsonl {
+        let fields = serde_json::json!({ "session": session, "pid": std::process::id() });
+        let header = format_jsonl_entry(
+            timestamp,
+            LogLevel::Info,
+            category,
+            component,
+            "Session started",
+            Some(&fields),
+        );
+        write_entry(file_key, &header, file_cache)?;
+        headers_written
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(file_key.to_string());
+        return Ok(header.len());
+    }
+
     let header = format!(
         "================================================================================\n\
          COMPONENT: {}\n\
          CATEGORY: {}\n\
-         SESSION: session-{}\n\
+         SESSION: {}\n\
          STARTED: {}\n\
          PID: {}\n\
          ================================================================================\n\
@@ -395,34 +684,16 @@ fn write_header(
          \n",
         component,
         category,
-        Utc::now().timestamp_millis(),
+        session,
         timestamp,
         std::process::id()
     );
-    let bytes = header.len();
-
-    let locked_file = {
-        let cache = file_cache.lock().unwrap_or_else(|e| e.into_inner());
-        cache
-            .get(category)
-            .ok_or_else(|| {
-                std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    format!("No file handle for {category}"),
-                )
-            })?
-            .clone()
-    };
-
-    {
-        let mut file = locked_file.lock().unwrap_or_else(|e| e.into_inner());
-        file.write_all(header.as_bytes())?;
-    }
+    let bytes = write_entry(file_key, &header, file_cache)?;
 
     headers_written
         .lock()
         .unwrap_or_else(|e| e.into_inner())
-        .insert(category.to_string());
+        .insert(file_key.to_string());
     Ok(bytes)
 }
 
@@ -464,6 +735,26 @@ fn format_log_entry(payload: &WriteLogPayload, timestamp: &str) -> String {
     }
 }
 
+/// One JSONL line: timestamp, level, category, component, message, fields
+fn format_jsonl_entry(
+    timestamp: &str,
+    level: LogLevel,
+    category: &str,
+    component: &str,
+    message: &str,
+    fields: Option<&Value>,
+) -> String {
+    let entry = serde_json::json!({
+        "timestamp": timestamp,
+        "level": level,
+        "category": category,
+        "component": component,
+        "message": message,
+        "fields": fields,
+    });
+    format!("{entry}\n")
+}
+
 fn flush_all(file_cache: &FileCache) {
     let handles: Vec<LockedFile> = {
         let cache = file_cache.lock().unwrap_or_else(|e| e.into_inner());
@@ -488,9 +779,11 @@ pub struct LoggerModule {
     #[allow(dead_code)] // Used by writer thread, but compiler doesn't see through thread::spawn
     headers_written: HeaderTracker,
     log_tx: mpsc::SyncSender<WriteLogPayload>,
+    connection_limiter: Mutex<ConnectionLimiter>,
     started_at: Instant,
     requests_processed: AtomicU64,
     pending_writes: Arc<AtomicU64>,
+    dropped_messages: AtomicU64,
 }
 
 impl LoggerModule {
@@ -515,7 +808,9 @@ impl LoggerModule {
 
         // Create BOUNDED sync_channel for GUARANTEED non-blocking
         // try_send() returns immediately - if full, message dropped (NEVER blocks)
-        let (log_tx, log_rx) = mpsc::sync_channel::<WriteLogPayload>(CLOG_CHANNEL_CAPACITY);
+        let capacity = env_setting("LOGGER_QUEUE_CAPACITY", CLOG_CHANNEL_CAPACITY).max(1);
+        let (log_tx, log_rx) = mpsc::sync_channel::<WriteLogPayload>(capacity);
+        let connection_rate = env_setting("LOGGER_CONNECTION_RATE", CONNECTION_RATE_PER_SEC);
 
         // Set global sender for clog_* macros (if not already set)
         let _ = GLOBAL_LOG_SENDER.set(log_tx.clone());
@@ -526,6 +821,8 @@ impl LoggerModule {
         let writer_log_dir = log_dir.clone();
         let writer_continuum_root = continuum_root.clone();
         let writer_pending = pending_writes.clone();
+        let rotation = RotationPolicy::from_env();
+        let default_format: LogFormat = env_setting("LOGGER_FORMAT", LogFormat::Text);
 
         thread::spawn(move || {
             const FLUSH_INTERVAL: Duration = Duration::from_millis(250);
@@ -536,6 +833,7 @@ impl LoggerModule {
 
             let process_payload =
                 |payload: &WriteLogPayload, limiter: &mut RateLimiter, pending: &mut usize| {
+                    let format = payload.format.unwrap_or(default_format);
                     match limiter.check(&payload.category) {
                         RateDecision::Allow => {
                             if let Err(e) = write_log_message(
@@ -544,6 +842,8 @@ impl LoggerModule {
                                 &writer_continuum_root,
                                 &writer_file_cache,
                                 &writer_headers,
+                                &rotation,
+                                format,
                             ) {
                                 eprintln!("❌ LoggerModule write error: {e}");
                             }
@@ -560,6 +860,7 @@ impl LoggerModule {
                                     dropped, payload.category
                                 ),
                                 args: None,
+                                format: payload.format,
                             };
                             let _ = write_log_message(
                                 &warning,
@@ -567,6 +868,8 @@ impl LoggerModule {
                                 &writer_continuum_root,
                                 &writer_file_cache,
                                 &writer_headers,
+                                &rotation,
+                                format,
                             );
                             if let Err(e) = write_log_message(
                                 payload,
@@ -574,6 +877,8 @@ impl LoggerModule {
                                 &writer_continuum_root,
                                 &writer_file_cache,
                                 &writer_headers,
+                                &rotation,
+                                format,
                             ) {
                                 eprintln!("❌ LoggerModule write error: {e}");
                             }
@@ -628,10 +933,62 @@ impl LoggerModule {
             file_cache,
             headers_written,
             log_tx,
+            connection_limiter: Mutex::new(ConnectionLimiter::new(connection_rate)),
             started_at: Instant::now(),
             requests_processed: AtomicU64::new(0),
             pending_writes,
+            dropped_messages: AtomicU64::new(0),
+        }
+    }
+
+    /// Queue entries for the writer thread without blocking; returns how many
+    /// were queued. Lines over the connection's rate, or arriving while the
+    /// queue is at its high-water mark, are dropped. The connection's next
+    /// queued line is preceded by a "N messages dropped" summary.
+    fn enqueue(&self, connection_id: Option<u64>, entries: Vec<WriteLogPayload>) -> usize {
+        let total = entries.len();
+        let mut limiter = self
+            .connection_limiter
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let admitted = match connection_id {
+            Some(id) => limiter.admit(id, total, Instant::now()),
+            None => total,
+        };
+
+        let mut queued = 0;
+        for (i, entry) in entries.into_iter().take(admitted).enumerate() {
+            if let (0, Some(id)) = (i, connection_id) {
+                let dropped = limiter.take_dropped(id);
+                if dropped > 0 {
+                    let summary = WriteLogPayload {
+                        category: entry.category.clone(),
+                        level: LogLevel::Warn,
+                        component: "LoggerModule".to_string(),
+                        message: format!(
+                            "{dropped} messages dropped from connection {id} (rate limit or full queue)"
+                        ),
+                        args: None,
+                        format: entry.format,
+                    };
+                    if self.log_tx.try_send(summary).is_err() {
+                        limiter.record_dropped(id, dropped);
+                    }
+                }
+            }
+            if self.log_tx.try_send(entry).is_ok() {
+                queued += 1;
+            }
+        }
+
+        let dropped = (total - queued) as u64;
+        if dropped > 0 {
+            self.dropped_messages.fetch_add(dropped, Ordering::Relaxed);
+            if let Some(id) = connection_id {
+                limiter.record_dropped(id, dropped);
+            }
         }
+        queued
     }
 
     fn handle_write(&self, params: Value) -> Result<CommandResult, String> {
@@ -646,9 +1003,7 @@ impl LoggerModule {
         let payload: WriteLogPayload =
             serde_json::from_value(payload_value).map_err(|e| format!("Invalid payload: {e}"))?;
 
-        self.log_tx
-            .send(payload)
-            .map_err(|e| format!("Queue send failed: {e}"))?;
+        self.enqueue(current_connection_id(), vec![payload]);
 
         self.requests_processed.fetch_add(1, Ordering::Relaxed);
 
@@ -668,16 +1023,13 @@ impl LoggerModule {
         let batch: WriteLogBatchPayload = serde_json::from_value(payload_value)
             .map_err(|e| format!("Invalid batch payload: {e}"))?;
 
-        let count = batch.entries.len();
-        for entry in batch.entries {
-            // Queue each entry through the existing channel (writer thread handles actual I/O)
-            let _ = self.log_tx.try_send(entry);
-        }
+        // Queue through the existing channel (writer thread handles actual I/O)
+        let queued = self.enqueue(current_connection_id(), batch.entries);
 
         self.requests_processed.fetch_add(1, Ordering::Relaxed);
 
         CommandResult::json(&WriteLogBatchResult {
-            entries_queued: count,
+            entries_queued: queued,
         })
     }
 
@@ -693,6 +1045,7 @@ impl LoggerModule {
             requests_processed: self.requests_processed.load(Ordering::Relaxed),
             active_categories,
             pending_writes: self.pending_writes.load(Ordering::Relaxed) as usize,
+            dropped_messages: self.dropped_messages.load(Ordering::Relaxed),
         })
     }
 }
@@ -732,6 +1085,13 @@ impl ServiceModule for LoggerModule {
         }
     }
 
+    async fn connection_closed(&self, connection_id: u64) {
+        self.connection_limiter
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(connection_id);
+    }
+
     async fn shutdown(&self) -> Result<(), String> {
         // Flush any pending writes
         flush_all(&self.file_cache);
@@ -779,4 +1139,154 @@ mod tests {
         assert!(matches!(rl.check("test"), RateDecision::Allow));
         assert!(matches!(rl.check("test"), RateDecision::Drop));
     }
+
+    #[test]
+    fn test_connection_limiter() {
+        let start = Instant::now();
+        let mut limiter = ConnectionLimiter::new(10);
+
+        // Bursts to twice the rate, then drops
+        assert_eq!(limiter.admit(1, 15, start), 15);
+        assert_eq!(limiter.admit(1, 15, start), 5);
+        assert_eq!(limiter.admit(1, 1, start), 0);
+        limiter.record_dropped(1, 11);
+
+        // Other connections have their own bucket
+        assert_eq!(limiter.admit(2, 3, start), 3);
+
+        // Refills at the configured rate
+        let later = start + Duration::from_millis(500);
+        assert_eq!(limiter.admit(1, 15, later), 5);
+        assert_eq!(limiter.take_dropped(1), 11);
+        assert_eq!(limiter.take_dropped(1), 0);
+
+        limiter.remove(1);
+        assert_eq!(limiter.admit(1, 25, later), 20);
+
+        assert_eq!(
+            ConnectionLimiter::new(0).admit(1, 1_000_000, start),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn test_rotation_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("system.log");
+        let file_cache: FileCache = Arc::new(Mutex::new(HashMap::new()));
+        let headers: HeaderTracker = Arc::new(Mutex::new(HashSet::new()));
+        let rotation = RotationPolicy {
+            max_bytes: 16,
+            max_age: None,
+            max_files: 5,
+            gzip: false,
+        };
+
+        ensure_file_handle("system", &path, &file_cache, &headers, &rotation).unwrap();
+        write_entry("system", "well over sixteen bytes\n", &file_cache).unwrap();
+        headers.lock().unwrap().insert("system".to_string());
+
+        // Oversized: the handle is dropped and the file moved aside, not truncated
+        ensure_file_handle("system", &path, &file_cache, &headers, &rotation).unwrap();
+        assert!(!headers.lock().unwrap().contains("system"));
+        assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+        let rotated: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p != &path)
+            .collect();
+        assert_eq!(rotated.len(), 1);
+        assert_eq!(
+            fs::read_to_string(&rotated[0]).unwrap(),
+            "well over sixteen bytes\n"
+        );
+
+        // New writes go to the fresh file
+        write_entry("system", "fresh\n", &file_cache).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fresh\n");
+    }
+
+    #[test]
+    fn test_jsonl_entries_go_to_their_own_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().to_string_lossy().to_string();
+        let file_cache: FileCache = Arc::new(Mutex::new(HashMap::new()));
+        let headers: HeaderTracker = Arc::new(Mutex::new(HashSet::new()));
+        let rotation = RotationPolicy {
+            max_bytes: MAX_LOG_FILE_SIZE,
+            max_age: None,
+            max_files: 5,
+            gzip: false,
+        };
+        let payload = WriteLogPayload {
+            category: "system/worker".to_string(),
+            level: LogLevel::Warn,
+            component: "Worker".to_string(),
+            message: "queue \"full\"".to_string(),
+            args: Some(serde_json::json!({ "depth": 42 })),
+            format: Some(LogFormat::Jsonl),
+        };
+        for format in [LogFormat::Jsonl, LogFormat::Text] {
+            write_log_message(
+                &payload,
+                &log_dir,
+                &log_dir,
+                &file_cache,
+                &headers,
+                &rotation,
+                format,
+            )
+            .unwrap();
+        }
+        flush_all(&file_cache);
+
+        // Session-start record, then the entry; every line is one JSON object
+        let jsonl = fs::read_to_string(dir.path().join("worker.jsonl")).unwrap();
+        let lines: Vec<Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["message"], "Session started");
+        assert_eq!(lines[1]["level"], "warn");
+        assert_eq!(lines[1]["category"], "system/worker");
+        assert_eq!(lines[1]["message"], "queue \"full\"");
+        assert_eq!(lines[1]["fields"]["depth"], 42);
+
+        // Text output is unchanged, in the usual file
+        let text = fs::read_to_string(dir.path().join("worker.log")).unwrap();
+        assert!(text.starts_with("====="));
+        assert!(text.ends_with("[WARN] Worker: queue \"full\" {\"depth\":42}\n"));
+    }
+
+    #[test]
+    fn test_rotated_copies_gzipped_and_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("system.log");
+        for stamp in [
+            "20260101-000000.000",
+            "20260102-000000.000",
+            "20260103-000000.000",
+        ] {
+            fs::write(dir.path().join(format!("system.log.{stamp}")), "old").unwrap();
+        }
+        fs::write(dir.path().join("system.log.bak"), "not rotated").unwrap();
+
+        gzip_file(&dir.path().join("system.log.20260103-000000.000")).unwrap();
+        prune_rotated(&path, 2);
+
+        let mut names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "system.log.20260102-000000.000",
+                "system.log.20260103-000000.000.gz",
+                "system.log.bak",
+            ]
+        );
+    }
 }
@@ -7,7 +7,8 @@
 //! ModuleRegistry and TypeScript-generated schemas at runtime.
 //!
 //! Commands:
-//! - mcp/list-tools: Return all commands as MCP tool definitions
+//! - mcp/list-tools: Return all commands as MCP tool definitions, plus the
+//!   exact command each tool name runs (`commands`: MCP name → JTAG command)
 //! - mcp/search-tools: Search tools by keyword
 //! - mcp/tool-help: Get detailed help for a specific tool
 
@@ -34,6 +35,40 @@ pub struct MCPTool {
     pub description: String,
     #[serde(rename = "inputSchema")]
     pub input_schema: MCPInputSchema,
+    /// JTAG command this tool runs (not part of the MCP tool definition)
+    #[serde(skip)]
+    #[ts(skip)]
+    pub command: String,
+}
+
+/// TypeScript names some commands differently from the Rust module that
+/// serves them over IPC: (TypeScript prefix, Rust prefix).
+const COMMAND_ALIASES: &[(&str, &str)] = &[
+    // code/shell/execute → code/shell-execute, etc.
+    ("code/shell/", "code/shell-"),
+];
+
+/// The IPC command for a TypeScript command name
+fn ipc_command(name: &str) -> String {
+    COMMAND_ALIASES
+        .iter()
+        .find_map(|(ts, rust)| name.strip_prefix(ts).map(|rest| format!("{rust}{rest}")))
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Two different commands sanitize to the same tool name; only one of them
+/// stays reachable over MCP
+fn warn_collision(tools: &[MCPTool], tool: &MCPTool) {
+    if let Some(existing) = tools.iter().find(|t| t.name == tool.name) {
+        if existing.command != tool.command {
+            tracing::warn!(
+                "MCP tool name collision: '{}' is both {} and {}",
+                tool.name,
+                existing.command,
+                tool.command
+            );
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -317,6 +352,7 @@ impl MCPModule {
 
         Some(MCPTool {
             name: tool_name,
+            command: ipc_command(name),
             description: format!("[JTAG] {}", description),
             input_schema: MCPInputSchema {
                 schema_type: "object".to_string(),
@@ -353,6 +389,7 @@ impl MCPModule {
 
         MCPTool {
             name: tool_name,
+            command: schema.name.to_string(),
             description: format!("[JTAG] {}", schema.description),
             input_schema: MCPInputSchema {
                 schema_type: "object".to_string(),
@@ -374,6 +411,7 @@ impl MCPModule {
         // 1. Add MCP meta-tools first
         tools.push(MCPTool {
             name: "mcp_search_tools".to_string(),
+            command: "mcp/search-tools".to_string(),
             description:
                 "[JTAG] Search for tools by keyword. Returns matching tool names and descriptions."
                     .to_string(),
@@ -406,6 +444,7 @@ impl MCPModule {
 
         tools.push(MCPTool {
             name: "mcp_tool_help".to_string(),
+            command: "mcp/tool-help".to_string(),
             description: "[JTAG] Get detailed help for a specific tool.".to_string(),
             input_schema: MCPInputSchema {
                 schema_type: "object".to_string(),
@@ -432,6 +471,8 @@ impl MCPModule {
                 if !seen_names.contains(&tool.name) {
                     seen_names.insert(tool.name.clone());
                     tools.push(tool);
+                } else {
+                    warn_collision(&tools, &tool);
                 }
             }
         }
@@ -443,6 +484,7 @@ impl MCPModule {
                     let tool = self.rust_schema_to_tool(&schema);
                     // Rust schemas take precedence
                     if let Some(pos) = tools.iter().position(|t| t.name == tool.name) {
+                        warn_collision(&tools, &tool);
                         tools[pos] = tool;
                     } else {
                         seen_names.insert(tool.name.clone());
@@ -454,8 +496,8 @@ impl MCPModule {
 
         // 4. Sort by priority
         tools.sort_by(|a, b| {
-            let priority_a = self.get_priority(&a.name.replace('_', "/"));
-            let priority_b = self.get_priority(&b.name.replace('_', "/"));
+            let priority_a = self.get_priority(&a.command);
+            let priority_b = self.get_priority(&b.command);
             if priority_a != priority_b {
                 priority_a.cmp(&priority_b)
             } else {
@@ -588,9 +630,15 @@ impl ServiceModule for MCPModule {
                 let tools = self.tools_cache.read();
                 let tools = tools.as_ref().ok_or("Tools cache not initialized")?;
 
+                let commands: HashMap<&str, &str> = tools
+                    .iter()
+                    .map(|t| (t.name.as_str(), t.command.as_str()))
+                    .collect();
+
                 Ok(CommandResult::Json(json!({
                     "success": true,
                     "tools": tools,
+                    "commands": commands,
                     "count": tools.len()
                 })))
             }
@@ -1,6 +1,6 @@
 //! RagModule — Batched RAG context composition with parallel source loading.
 //!
-//! Handles: rag/compose
+//! Handles: rag/compose, rag/chunk
 //!
 //! Key optimization: Instead of TypeScript making N IPC calls (one per source),
 //! this module receives ALL source requests in ONE call and runs them in parallel
@@ -13,10 +13,17 @@
 //!
 //! This allows video games to pass scene/move context, VR apps to pass spatial
 //! data, chat to pass conversation history - all in the same batched call.
+//!
+//! rag/chunk splits a document for indexing with a ChunkStrategy (fixed
+//! tokens, sentence, paragraph or Markdown section), through
+//! `RagEngine::chunk_document` as `RagOptions::chunk_strategy`.
 
 use crate::log_info;
 use crate::logging::TimingGuard;
 use crate::memory::PersonaMemoryManager;
+use crate::rag::chunking::{ChunkStrategy, TextChunk};
+use crate::rag::rerank::{rerank, RerankOptions};
+use crate::rag::{RagEngine, RagOptions};
 use crate::runtime::{CommandResult, ModuleConfig, ModuleContext, ModulePriority, ServiceModule};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -45,6 +52,10 @@ pub struct MemorySourceParams {
     /// Specific layers to search (empty = all)
     #[ts(optional)]
     pub layers: Option<Vec<String>>,
+    /// Re-score the top recalled memories against the query
+    #[serde(default)]
+    #[ts(optional)]
+    pub rerank: Option<RerankOptions>,
 }
 
 /// Consciousness source params — temporal + cross-context awareness
@@ -352,6 +363,27 @@ pub struct RagComposeResult {
     pub sources_failed: usize,
 }
 
+/// Document chunking request.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/rag/RagChunkRequest.ts")]
+pub struct RagChunkRequest {
+    /// Document text
+    pub text: String,
+
+    /// Chunking strategy (default: paragraph)
+    #[serde(default)]
+    #[ts(optional)]
+    pub strategy: Option<ChunkStrategy>,
+}
+
+/// Document chunking result.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/rag/RagChunkResult.ts")]
+pub struct RagChunkResult {
+    pub chunks: Vec<TextChunk>,
+    pub count: usize,
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 // MODULE STATE
 // ═══════════════════════════════════════════════════════════════════════════
@@ -360,11 +392,16 @@ pub struct RagComposeResult {
 pub struct RagState {
     /// Memory manager for memory-based sources
     pub memory_manager: Arc<PersonaMemoryManager>,
+    /// Engine whose `chunk_document` splits documents for indexing
+    pub engine: Arc<RagEngine>,
 }
 
 impl RagState {
-    pub fn new(memory_manager: Arc<PersonaMemoryManager>) -> Self {
-        Self { memory_manager }
+    pub fn new(memory_manager: Arc<PersonaMemoryManager>, engine: Arc<RagEngine>) -> Self {
+        Self {
+            memory_manager,
+            engine,
+        }
     }
 }
 
@@ -387,13 +424,18 @@ impl RagState {
         // Estimate max memories from budget (assuming ~80 tokens per memory)
         let max_results = (budget_tokens / 80).max(3);
 
+        let query_text = params
+            .query_text
+            .clone()
+            .or_else(|| query_text.map(|s| s.to_string()));
+        // Reranking needs a query and recalls a wider candidate pool
+        let rerank_options = params.rerank.filter(|r| r.enabled && query_text.is_some());
+        let recall_results = rerank_options.map_or(max_results, |r| r.top_n_in.max(max_results));
+
         let req = crate::memory::MultiLayerRecallRequest {
-            query_text: params
-                .query_text
-                .clone()
-                .or_else(|| query_text.map(|s| s.to_string())),
+            query_text: query_text.clone(),
             room_id: room_id.to_string(),
-            max_results,
+            max_results: recall_results,
             layers: params.layers.clone(),
         };
 
@@ -409,8 +451,25 @@ impl RagState {
                         source_ref: Some(format!("memory:{}", mem.id)),
                     })
                     .collect();
+                let sections = match (rerank_options, query_text.as_deref()) {
+                    (Some(options), Some(query)) => rerank(
+                        query,
+                        sections,
+                        &options,
+                        |s| &s.content,
+                        |s| s.relevance.unwrap_or(0.0),
+                    )
+                    .into_iter()
+                    .map(|(section, score)| RagSection {
+                        relevance: Some(score),
+                        ..section
+                    })
+                    .collect(),
+                    _ => sections,
+                };
 
                 let tokens_used = sections.iter().map(|s| s.content.len() / 4).sum();
+                let memory_count = sections.len();
 
                 let layers_str = resp
                     .layer_timings
@@ -427,7 +486,7 @@ impl RagState {
                     success: true,
                     error: None,
                     metadata: RagSourceMetadata::Memory(MemorySourceMetadata {
-                        memory_count: resp.memories.len(),
+                        memory_count,
                         total_candidates: resp.total_candidates,
                         layers: layers_str,
                         recall_time_ms: resp.recall_time_ms,
@@ -798,6 +857,27 @@ impl ServiceModule for RagModule {
                 ))
             }
 
+            "rag/chunk" => {
+                let _timer = TimingGuard::new("module", "rag_chunk");
+
+                let req: RagChunkRequest = serde_json::from_value(params)
+                    .map_err(|e| format!("Invalid rag/chunk request: {e}"))?;
+
+                let options = RagOptions {
+                    chunk_strategy: req.strategy.unwrap_or_default(),
+                    ..Default::default()
+                };
+                let chunks = self.state.engine.chunk_document(&req.text, &options);
+                let result = RagChunkResult {
+                    count: chunks.len(),
+                    chunks,
+                };
+
+                Ok(CommandResult::Json(
+                    serde_json::to_value(&result).unwrap_or_default(),
+                ))
+            }
+
             _ => Err(format!("Unknown rag command: {command}")),
         }
     }
@@ -7,7 +7,9 @@
 //!
 //! Commands:
 //! - search/execute: Run text search algorithm
-//! - search/vector: Run vector similarity search
+//! - search/vector: Run vector similarity search (optionally MMR-diversified)
+//! - search/hybrid: BM25 + cosine fused with reciprocal-rank fusion
+//! - search/dedup: Group near-duplicate vectors by cosine similarity
 //! - search/list: List available algorithms
 //! - search/params: Get algorithm parameters
 //!
@@ -19,7 +21,8 @@ use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::any::Any;
-use std::collections::{HashMap, HashSet};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use ts_rs::TS;
 
 // ============================================================================
@@ -58,12 +61,75 @@ pub struct VectorSearchInput {
     pub normalize: bool,
     #[serde(default)]
     pub threshold: f64,
+    /// Return only the best K matches. When set, `scores` is parallel to
+    /// `ranked_indices` (not the corpus); `None` returns the full ranking.
+    #[serde(default, alias = "top_k")]
+    #[ts(optional)]
+    pub top_k: Option<usize>,
+    /// MMR lambda (0-1): rerank for diversity, trading relevance to the
+    /// query (1.0) against dissimilarity to results already picked (0.0).
+    /// `scores` is then parallel to `ranked_indices`, in selection order.
+    #[serde(default, alias = "maximal_marginal_relevance")]
+    #[ts(optional)]
+    pub maximal_marginal_relevance: Option<f64>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+/// Input for hybrid lexical + semantic search. `corpus` and `corpus_vectors`
+/// are parallel: entry i of each describes the same document.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/search/HybridSearchInput.ts"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct HybridSearchInput {
+    pub query: String,
+    pub corpus: Vec<String>,
+    #[serde(alias = "query_vector")]
+    pub query_vector: Vec<f64>,
+    #[serde(alias = "corpus_vectors")]
+    pub corpus_vectors: Vec<Vec<f64>>,
+    /// RRF constant; larger values flatten the advantage of top ranks
+    #[serde(default = "default_rrf_k")]
+    pub k: f64,
+    /// Optional BM25 parameter overrides (k1, b, ...)
+    #[serde(default)]
+    #[ts(type = "Record<string, unknown> | null")]
+    pub params: Option<HashMap<String, Value>>,
+}
+
+fn default_rrf_k() -> f64 {
+    60.0
+}
+
+/// Input for near-duplicate detection
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/search/DedupInput.ts")]
+pub struct DedupInput {
+    pub vectors: Vec<Vec<f64>>,
+    /// Cosine similarity at or above which two vectors are duplicates
+    #[serde(default = "default_dedup_threshold")]
+    pub threshold: f64,
+}
+
+fn default_dedup_threshold() -> f64 {
+    0.95
+}
+
+/// A group of near-duplicates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/search/DedupCluster.ts")]
+pub struct DedupCluster {
+    /// Index to keep: the first member in input order
+    pub representative: usize,
+    /// Every index in the group, representative included, ascending
+    pub members: Vec<usize>,
+}
+
 // ============================================================================
 // Algorithm Trait (OpenCV cv::Algorithm style)
 // ============================================================================
@@ -232,6 +298,11 @@ impl SearchAlgorithm for BowAlgorithm {
 // BM25 Algorithm
 // ============================================================================
 
+/// Okapi BM25 lexical scorer. IDF is computed over the request's corpus.
+///
+/// Tokenization is lowercase + split on non-alphanumeric characters, which
+/// suits English and other space-delimited languages; CJK text without
+/// spaces becomes one token per run and scores poorly.
 struct Bm25Algorithm {
     k1: f64,
     b: f64,
@@ -264,16 +335,13 @@ impl Bm25Algorithm {
         tf
     }
 
-    fn idf(&self, term: &str, doc_term_freqs: &[HashMap<String, usize>], n: usize) -> f64 {
-        let docs_containing = doc_term_freqs
-            .iter()
-            .filter(|tf| tf.contains_key(term))
-            .count();
-        if docs_containing == 0 {
+    /// Lucene-style IDF (always positive). `df` = documents containing the term.
+    fn idf(df: usize, n: usize) -> f64 {
+        if df == 0 {
             return 0.0;
         }
         let n_f = n as f64;
-        let df = docs_containing as f64;
+        let df = df as f64;
         ((n_f - df + 0.5) / (df + 0.5) + 1.0).ln()
     }
 
@@ -344,15 +412,24 @@ impl SearchAlgorithm for Bm25Algorithm {
             .iter()
             .map(|d| self.tokenize(d).len())
             .collect();
-        let avg_doc_len = doc_lens.iter().sum::<usize>() as f64 / n as f64;
+        // Floor at 1 so an all-empty corpus can't divide by zero
+        let avg_doc_len = (doc_lens.iter().sum::<usize>() as f64 / n as f64).max(1.0);
         let query_terms = self.tokenize(&input.query);
 
-        let mut idf_cache: HashMap<String, f64> = HashMap::new();
-        for term in &query_terms {
-            if !idf_cache.contains_key(term) {
-                idf_cache.insert(term.clone(), self.idf(term, &doc_term_freqs, n));
+        // Document frequencies for query terms only, in one pass over the corpus
+        let mut doc_freqs: HashMap<&str, usize> =
+            query_terms.iter().map(|t| (t.as_str(), 0)).collect();
+        for tf in &doc_term_freqs {
+            for (term, df) in doc_freqs.iter_mut() {
+                if tf.contains_key(*term) {
+                    *df += 1;
+                }
             }
         }
+        let idf_cache: HashMap<String, f64> = doc_freqs
+            .into_iter()
+            .map(|(term, df)| (term.to_string(), Self::idf(df, n)))
+            .collect();
 
         let mut scores: Vec<f64> = doc_term_freqs
             .iter()
@@ -374,6 +451,7 @@ impl SearchAlgorithm for Bm25Algorithm {
             "k1" => Some(json!(self.k1)),
             "b" => Some(json!(self.b)),
             "case_insensitive" => Some(json!(self.case_insensitive)),
+            "min_term_length" => Some(json!(self.min_term_length)),
             _ => None,
         }
     }
@@ -381,23 +459,36 @@ impl SearchAlgorithm for Bm25Algorithm {
     fn set_param(&mut self, name: &str, value: Value) -> Result<(), String> {
         match name {
             "k1" => {
-                self.k1 = value.as_f64().ok_or("k1 must be float")?;
+                let k1 = value.as_f64().ok_or("k1 must be float")?;
+                if k1 < 0.0 {
+                    return Err("k1 must be >= 0".to_string());
+                }
+                self.k1 = k1;
                 Ok(())
             }
             "b" => {
-                self.b = value.as_f64().ok_or("b must be float")?;
+                let b = value.as_f64().ok_or("b must be float")?;
+                if !(0.0..=1.0).contains(&b) {
+                    return Err("b must be in [0, 1]".to_string());
+                }
+                self.b = b;
                 Ok(())
             }
             "case_insensitive" => {
                 self.case_insensitive = value.as_bool().ok_or("case_insensitive must be bool")?;
                 Ok(())
             }
+            "min_term_length" => {
+                self.min_term_length =
+                    value.as_u64().ok_or("min_term_length must be uint")? as usize;
+                Ok(())
+            }
             _ => Err(format!("Unknown parameter: {name}")),
         }
     }
 
     fn param_names(&self) -> Vec<&'static str> {
-        vec!["k1", "b", "case_insensitive"]
+        vec!["k1", "b", "case_insensitive", "min_term_length"]
     }
 }
 
@@ -451,6 +542,14 @@ impl CosineAlgorithm {
             Self::l2_normalize(&mut query);
         }
 
+        if let Some(lambda) = input.maximal_marginal_relevance {
+            let k = input.top_k.unwrap_or(input.corpus_vectors.len());
+            return self.vector_search_mmr(&query, &input.corpus_vectors, k, lambda);
+        }
+        if let Some(k) = input.top_k {
+            return self.vector_search_top_k(&query, &input.corpus_vectors, k);
+        }
+
         let mut scores: Vec<f64> = Vec::with_capacity(input.corpus_vectors.len());
         for corpus_vec in &input.corpus_vectors {
             let mut cv = corpus_vec.clone();
@@ -468,8 +567,173 @@ impl CosineAlgorithm {
             ranked_indices: ranked.into_iter().map(|(i, _)| i).collect(),
         }
     }
+
+    /// Greedy near-duplicate grouping: each vector not yet grouped starts a
+    /// group and claims every later ungrouped vector within `threshold` of
+    /// it. Compares all pairs, O(n²) — fine for ingestion batches of a few
+    /// thousand, too slow for deduplicating a whole corpus.
+    fn dedup(vectors: &[Vec<f64>], threshold: f64) -> Vec<DedupCluster> {
+        let normalized: Vec<Vec<f64>> = vectors
+            .iter()
+            .map(|v| {
+                let mut v = v.clone();
+                Self::l2_normalize(&mut v);
+                v
+            })
+            .collect();
+
+        let mut grouped = vec![false; vectors.len()];
+        let mut clusters = Vec::new();
+        for i in 0..normalized.len() {
+            if grouped[i] {
+                continue;
+            }
+            let mut members = vec![i];
+            for j in i + 1..normalized.len() {
+                if !grouped[j]
+                    && Self::cosine_similarity(&normalized[i], &normalized[j]) >= threshold
+                {
+                    grouped[j] = true;
+                    members.push(j);
+                }
+            }
+            clusters.push(DedupCluster {
+                representative: i,
+                members,
+            });
+        }
+        clusters
+    }
+
+    /// Maximal marginal relevance: greedily pick the candidate maximising
+    /// `lambda * sim(query, c) - (1 - lambda) * max sim(c, picked)` until
+    /// `k` are picked. Below-threshold vectors are never candidates.
+    /// O(k·n) similarity computations.
+    fn vector_search_mmr(
+        &self,
+        query: &[f64],
+        corpus: &[Vec<f64>],
+        k: usize,
+        lambda: f64,
+    ) -> SearchOutput {
+        let corpus: Vec<Vec<f64>> = corpus
+            .iter()
+            .map(|v| {
+                let mut v = v.clone();
+                if self.normalize {
+                    Self::l2_normalize(&mut v);
+                }
+                v
+            })
+            .collect();
+        let mut candidates: Vec<(usize, f64)> = corpus
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i, Self::cosine_similarity(query, v)))
+            .filter(|&(_, relevance)| relevance >= self.threshold)
+            .collect();
+        // Most similar to anything picked so far, per candidate
+        let mut redundancy = vec![f64::NEG_INFINITY; candidates.len()];
+
+        let mut scores = Vec::new();
+        let mut ranked_indices = Vec::new();
+        while ranked_indices.len() < k && !candidates.is_empty() {
+            let mmr = |pos: usize| {
+                let penalty = if ranked_indices.is_empty() {
+                    0.0
+                } else {
+                    redundancy[pos]
+                };
+                lambda * candidates[pos].1 - (1.0 - lambda) * penalty
+            };
+            // Candidates stay in index order, so ties go to the lower index
+            let mut best = 0;
+            for pos in 1..candidates.len() {
+                if mmr(pos) > mmr(best) {
+                    best = pos;
+                }
+            }
+            let (picked, relevance) = candidates.remove(best);
+            redundancy.remove(best);
+            for ((index, _), max_sim) in candidates.iter().zip(redundancy.iter_mut()) {
+                *max_sim = max_sim.max(Self::cosine_similarity(&corpus[*index], &corpus[picked]));
+            }
+            scores.push(relevance);
+            ranked_indices.push(picked);
+        }
+        SearchOutput {
+            scores,
+            ranked_indices,
+        }
+    }
+
+    /// Keep only the best `k` in a bounded min-heap: O(n log k) and O(k)
+    /// memory instead of scoring into a corpus-sized vector and sorting it.
+    fn vector_search_top_k(&self, query: &[f64], corpus: &[Vec<f64>], k: usize) -> SearchOutput {
+        let mut heap: BinaryHeap<Reverse<Ranked>> = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            for (index, corpus_vec) in corpus.iter().enumerate() {
+                let sim = if self.normalize {
+                    let mut cv = corpus_vec.clone();
+                    Self::l2_normalize(&mut cv);
+                    Self::cosine_similarity(query, &cv)
+                } else {
+                    Self::cosine_similarity(query, corpus_vec)
+                };
+                let score = if sim >= self.threshold { sim } else { 0.0 };
+                let candidate = Ranked { score, index };
+                if heap.len() < k {
+                    heap.push(Reverse(candidate));
+                } else if heap.peek().is_some_and(|worst| candidate > worst.0) {
+                    heap.pop();
+                    heap.push(Reverse(candidate));
+                }
+            }
+        }
+
+        // Ascending by Reverse = best first
+        let (scores, ranked_indices) = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(r)| (r.score, r.index))
+            .unzip();
+        SearchOutput {
+            scores,
+            ranked_indices,
+        }
+    }
+}
+
+/// Heap entry for top-k: higher score wins, ties go to the lower index so
+/// results match the stable full-ranking sort.
+#[derive(Debug, Clone, Copy)]
+struct Ranked {
+    score: f64,
+    index: usize,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
 }
 
+impl Eq for Ranked {}
+
 impl Default for CosineAlgorithm {
     fn default() -> Self {
         Self {
@@ -548,6 +812,35 @@ impl SearchAlgorithm for CosineAlgorithm {
     }
 }
 
+// ============================================================================
+// Reciprocal Rank Fusion
+// ============================================================================
+
+/// Fuse several rankings: `score(d) = Σ 1 / (k + rank(d))`, rank 1-based.
+/// Documents missing from a ranking get nothing from it.
+fn reciprocal_rank_fusion(rankings: &[Vec<usize>], n: usize, k: f64) -> Vec<f64> {
+    let mut fused = vec![0.0; n];
+    for ranking in rankings {
+        for (rank, &idx) in ranking.iter().enumerate() {
+            if let Some(score) = fused.get_mut(idx) {
+                *score += 1.0 / (k + (rank + 1) as f64);
+            }
+        }
+    }
+    fused
+}
+
+/// Ranked indices of documents the ranker actually matched (score > 0),
+/// so BM25 misses and below-threshold vectors don't earn rank credit.
+fn matched_ranking(output: &SearchOutput) -> Vec<usize> {
+    output
+        .ranked_indices
+        .iter()
+        .copied()
+        .filter(|&i| output.scores[i] > 0.0)
+        .collect()
+}
+
 // ============================================================================
 // SearchModule — ServiceModule Implementation
 // ============================================================================
@@ -603,6 +896,12 @@ impl SearchModule {
     fn handle_vector(&self, params: Value) -> Result<CommandResult, String> {
         let input: VectorSearchInput = serde_json::from_value(params)
             .map_err(|e| format!("Invalid vector search params: {e}"))?;
+        if input
+            .maximal_marginal_relevance
+            .is_some_and(|lambda| !(0.0..=1.0).contains(&lambda))
+        {
+            return Err("maximalMarginalRelevance must be between 0 and 1".to_string());
+        }
 
         let algo = CosineAlgorithm {
             normalize: input.normalize,
@@ -614,7 +913,91 @@ impl SearchModule {
         Ok(CommandResult::Json(json!({
             "algorithm": "cosine",
             "scores": output.scores,
-            "rankedIndices": output.ranked_indices
+            "rankedIndices": output.ranked_indices,
+            "topK": input.top_k,
+            "maximalMarginalRelevance": input.maximal_marginal_relevance
+        })))
+    }
+
+    fn handle_hybrid(&self, params: Value) -> Result<CommandResult, String> {
+        let input: HybridSearchInput = serde_json::from_value(params)
+            .map_err(|e| format!("Invalid hybrid search params: {e}"))?;
+
+        if input.corpus.len() != input.corpus_vectors.len() {
+            return Err(format!(
+                "corpus ({}) and corpusVectors ({}) must be the same length",
+                input.corpus.len(),
+                input.corpus_vectors.len()
+            ));
+        }
+        if input.k.is_nan() || input.k <= 0.0 {
+            return Err("k must be > 0".to_string());
+        }
+
+        let bm25 = match &input.params {
+            Some(overrides) => self.registry.create_with_params("bm25", overrides)?,
+            None => Bm25Algorithm::create(),
+        };
+        let lexical = bm25.execute(&SearchInput {
+            query: input.query,
+            corpus: input.corpus,
+        });
+
+        let semantic = CosineAlgorithm::default().vector_search(&VectorSearchInput {
+            query_vector: input.query_vector,
+            corpus_vectors: input.corpus_vectors,
+            normalize: true,
+            threshold: 0.0,
+            top_k: None,
+            maximal_marginal_relevance: None,
+        });
+
+        let n = lexical.scores.len();
+        let fused = reciprocal_rank_fusion(
+            &[matched_ranking(&lexical), matched_ranking(&semantic)],
+            n,
+            input.k,
+        );
+        let mut ranked: Vec<(usize, f64)> = fused.iter().copied().enumerate().collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        Ok(CommandResult::Json(json!({
+            "algorithm": "hybrid",
+            "k": input.k,
+            "scores": fused,
+            "rankedIndices": ranked.into_iter().map(|(i, _)| i).collect::<Vec<_>>(),
+            "bm25Scores": lexical.scores,
+            "cosineScores": semantic.scores
+        })))
+    }
+
+    /// Every input lands in exactly one cluster; singletons are unique
+    /// inputs, so the representatives are the deduplicated set.
+    fn handle_dedup(&self, params: Value) -> Result<CommandResult, String> {
+        let input: DedupInput =
+            serde_json::from_value(params).map_err(|e| format!("Invalid dedup params: {e}"))?;
+
+        if !(-1.0..=1.0).contains(&input.threshold) {
+            return Err("threshold must be between -1 and 1".to_string());
+        }
+        if let Some(first) = input.vectors.first() {
+            if let Some(i) = input.vectors.iter().position(|v| v.len() != first.len()) {
+                return Err(format!(
+                    "vectors[{i}] has {} dimensions, expected {}",
+                    input.vectors[i].len(),
+                    first.len()
+                ));
+            }
+        }
+
+        let clusters = CosineAlgorithm::dedup(&input.vectors, input.threshold);
+        let duplicates = input.vectors.len() - clusters.len();
+
+        Ok(CommandResult::Json(json!({
+            "threshold": input.threshold,
+            "clusters": clusters,
+            "unique": clusters.len(),
+            "duplicates": duplicates
         })))
     }
 
@@ -675,6 +1058,8 @@ impl ServiceModule for SearchModule {
         match command {
             "search/execute" => self.handle_execute(params),
             "search/vector" => self.handle_vector(params),
+            "search/hybrid" => self.handle_hybrid(params),
+            "search/dedup" => self.handle_dedup(params),
             "search/list" => self.handle_list(),
             "search/params" => self.handle_params(params),
             _ => Err(format!("Unknown search command: {command}")),
@@ -724,6 +1109,130 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bm25_rare_terms_and_length_normalization() {
+        let corpus = vec![
+            "rust rust rust".to_string(),
+            "rust borrow checker".to_string(),
+            "rust ".to_string() + &"filler ".repeat(50),
+        ];
+        let input = SearchInput {
+            query: "borrow rust".to_string(),
+            corpus,
+        };
+
+        let bm25 = Bm25Algorithm::default();
+        let out = bm25.execute(&input);
+        // "borrow" is rare → doc 1 wins; long doc 2 is penalized by b
+        assert_eq!(out.ranked_indices[0], 1);
+        assert!(out.scores[0] > out.scores[2]);
+
+        // b = 0 disables length normalization: docs 0 and 2 tie on "rust"
+        let mut flat = Bm25Algorithm::default();
+        flat.set_param("b", json!(0.0)).unwrap();
+        flat.set_param("k1", json!(0.0)).unwrap();
+        let out = flat.execute(&input);
+        assert!((out.scores[0] - out.scores[2]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_bm25_params_validated() {
+        let mut bm25 = Bm25Algorithm::default();
+        assert!(bm25.set_param("b", json!(1.5)).is_err());
+        assert!(bm25.set_param("k1", json!(-1.0)).is_err());
+        bm25.set_param("min_term_length", json!(3)).unwrap();
+        assert_eq!(bm25.get_param("min_term_length"), Some(json!(3)));
+        assert!(bm25.param_names().contains(&"min_term_length"));
+    }
+
+    #[test]
+    fn test_rrf_rewards_agreement() {
+        // Doc 2 is second in both lists; docs 0 and 1 each top only one
+        let fused = reciprocal_rank_fusion(&[vec![0, 2], vec![1, 2]], 3, 60.0);
+        assert!((fused[0] - 1.0 / 61.0).abs() < 1e-12);
+        assert!(fused[2] > fused[0]);
+        assert!((fused[0] - fused[1]).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search() {
+        let module = SearchModule::new();
+        let params = json!({
+            "query": "rust borrow checker",
+            "corpus": [
+                "the borrow checker enforces aliasing rules",
+                "a recipe for banana bread",
+                "lifetimes and ownership in rust"
+            ],
+            "query_vector": [1.0, 0.0],
+            "corpus_vectors": [[0.9, 0.1], [0.0, 1.0], [0.95, 0.05]]
+        });
+        let result = module.handle_command("search/hybrid", params).await;
+        let Ok(CommandResult::Json(json)) = result else {
+            panic!("hybrid search failed");
+        };
+        assert_eq!(json["k"], 60.0);
+        let ranked = json["rankedIndices"].as_array().unwrap();
+        assert_eq!(ranked.len(), 3);
+        // Unrelated doc matches neither ranker and sinks to the bottom
+        assert_eq!(ranked[2], 1);
+        assert_eq!(json["scores"][1], 0.0);
+        assert_eq!(json["bm25Scores"].as_array().unwrap().len(), 3);
+        assert_eq!(json["cosineScores"].as_array().unwrap().len(), 3);
+
+        let mismatched = json!({
+            "query": "x",
+            "corpus": ["a", "b"],
+            "queryVector": [1.0],
+            "corpusVectors": [[1.0]]
+        });
+        assert!(module
+            .handle_command("search/hybrid", mismatched)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dedup_groups_near_duplicates() {
+        let module = SearchModule::new();
+        let params = json!({
+            "vectors": [
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [2.0, 0.02, 0.0],
+                [0.0, 0.99, 0.05],
+                [0.0, 0.0, 1.0]
+            ],
+            "threshold": 0.98
+        });
+        let result = module.handle_command("search/dedup", params).await;
+        let Ok(CommandResult::Json(json)) = result else {
+            panic!("dedup failed");
+        };
+        let clusters: Vec<DedupCluster> = serde_json::from_value(json["clusters"].clone()).unwrap();
+        assert_eq!(
+            clusters,
+            vec![
+                DedupCluster {
+                    representative: 0,
+                    members: vec![0, 2],
+                },
+                DedupCluster {
+                    representative: 1,
+                    members: vec![1, 3],
+                },
+                DedupCluster {
+                    representative: 4,
+                    members: vec![4],
+                },
+            ]
+        );
+        assert_eq!(json["duplicates"], 2);
+
+        let ragged = json!({ "vectors": [[1.0, 0.0], [1.0]] });
+        assert!(module.handle_command("search/dedup", ragged).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_vector_search() {
         let module = SearchModule::new();
@@ -744,4 +1253,56 @@ mod tests {
             assert_eq!(ranked[0], 0); // Most similar (identical) first
         }
     }
+
+    #[test]
+    fn test_vector_top_k_matches_full_ranking() {
+        let corpus_vectors: Vec<Vec<f64>> = (0..50)
+            .map(|i| vec![(i % 7) as f64, (i % 5) as f64 + 0.5, 1.0])
+            .collect();
+        let mut input = VectorSearchInput {
+            query_vector: vec![1.0, 0.2, 0.3],
+            corpus_vectors,
+            normalize: true,
+            threshold: 0.0,
+            top_k: None,
+            maximal_marginal_relevance: None,
+        };
+        let algo = CosineAlgorithm::default();
+        let full = algo.vector_search(&input);
+
+        input.top_k = Some(5);
+        let top = algo.vector_search(&input);
+        // Duplicate vectors tie; lower index must win like the stable sort
+        assert_eq!(top.ranked_indices, full.ranked_indices[..5]);
+        let expected: Vec<f64> = top.ranked_indices.iter().map(|&i| full.scores[i]).collect();
+        assert_eq!(top.scores, expected);
+
+        input.top_k = Some(0);
+        assert!(algo.vector_search(&input).ranked_indices.is_empty());
+        input.top_k = Some(500);
+        assert_eq!(algo.vector_search(&input).ranked_indices.len(), 50);
+    }
+
+    #[test]
+    fn test_vector_mmr_diversifies() {
+        // Two near-identical chunks close to the query, one different
+        // chunk slightly further away
+        let mut input = VectorSearchInput {
+            query_vector: vec![1.0, 0.0],
+            corpus_vectors: vec![vec![0.98, 0.2], vec![0.97, 0.22], vec![0.9, -0.44]],
+            normalize: true,
+            threshold: 0.0,
+            top_k: Some(2),
+            maximal_marginal_relevance: Some(0.5),
+        };
+        let algo = CosineAlgorithm::default();
+        let out = algo.vector_search(&input);
+        assert_eq!(out.ranked_indices, vec![0, 2]);
+        assert_eq!(out.scores.len(), 2);
+        assert!(out.scores[0] > out.scores[1]);
+
+        // lambda 1 is plain relevance order
+        input.maximal_marginal_relevance = Some(1.0);
+        assert_eq!(algo.vector_search(&input).ranked_indices, vec![0, 1]);
+    }
 }
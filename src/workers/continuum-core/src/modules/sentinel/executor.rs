@@ -17,9 +17,13 @@ use super::steps;
 use super::types::{
     step_type_name, ExecutionContext, Pipeline, PipelineContext, PipelineResult, StepResult,
 };
+use crate::concurrent::ProgressTracker;
 use crate::runtime::{self, message_bus::MessageBus, ModuleRegistry};
 
 /// Execute a multi-step pipeline with LLM, conditions, loops
+///
+/// A message on `cancel_rx` stops the pipeline: the running step's future is
+/// dropped at its next await point and no further steps start.
 pub async fn execute_pipeline(
     logs_base_dir: PathBuf,
     pipeline: Pipeline,
@@ -27,6 +31,7 @@ pub async fn execute_pipeline(
     working_dir: PathBuf,
     bus: Option<Arc<MessageBus>>,
     registry: Option<Arc<ModuleRegistry>>,
+    mut cancel_rx: mpsc::Receiver<()>,
 ) -> Result<(i32, String), String> {
     let log = runtime::logger("sentinel");
 
@@ -64,6 +69,7 @@ pub async fn execute_pipeline(
     let mut last_output = String::new();
     let mut failed = false;
     let mut error_msg: Option<String> = None;
+    let mut progress = ProgressTracker::new(Some(pipeline.steps.len() as u64));
 
     for (i, step) in pipeline.steps.iter().enumerate() {
         let step_type = step_type_name(step);
@@ -75,8 +81,9 @@ pub async fn execute_pipeline(
             step_type
         ));
 
-        // Emit step progress
+        // Emit step progress; the ETA comes from the smoothed time per finished step
         if let Some(ref bus) = bus {
+            let update = progress.snapshot();
             bus.publish_async_only(
                 &format!("sentinel:{handle_id}:progress"),
                 json!({
@@ -85,6 +92,9 @@ pub async fn execute_pipeline(
                     "totalSteps": pipeline.steps.len(),
                     "stepType": step_type,
                     "phase": "executing",
+                    "fraction": update.fraction,
+                    "elapsedMs": update.elapsed_ms,
+                    "etaMs": update.eta_ms,
                 }),
             );
         }
@@ -96,7 +106,16 @@ pub async fn execute_pipeline(
             steps_log_path: Some(&steps_log_path),
         };
 
-        match steps::execute_step(step, i, &mut ctx, &pipeline_ctx).await {
+        // A dropped sender (handle cleaned up) disables the cancel branch
+        let step_result = tokio::select! {
+            result = steps::execute_step(step, i, &mut ctx, &pipeline_ctx) => result,
+            Some(()) = cancel_rx.recv() => {
+                log.warn(&format!("[{handle_id}] Pipeline cancelled during step {i}"));
+                return Err("Cancelled".to_string());
+            }
+        };
+
+        match step_result {
             Ok(result) => {
                 if result.success {
                     last_output = result.output.clone().unwrap_or_default();
@@ -124,6 +143,7 @@ pub async fn execute_pipeline(
                     }
                 }
                 ctx.step_results.push(result);
+                progress.advance(1);
                 if failed {
                     break;
                 }
@@ -1243,4 +1263,45 @@ mod tests {
         assert!(!result.success);
         assert!(result.error.as_ref().unwrap().contains("registry"));
     }
+
+    /// Test cancel stops a pipeline mid-step instead of letting it run on
+    #[tokio::test]
+    async fn test_pipeline_cancel_stops_running_step() {
+        let logs_dir = std::env::temp_dir().join("sentinel-test-cancel");
+
+        let pipeline = Pipeline {
+            name: Some("cancel-test".to_string()),
+            steps: vec![PipelineStep::Shell {
+                cmd: "sleep".into(),
+                args: vec!["30".into()],
+                timeout_secs: Some(60),
+                working_dir: None,
+                allow_failure: None,
+                env: None,
+            }],
+            working_dir: Some("/tmp".to_string()),
+            timeout_secs: None,
+            inputs: HashMap::new(),
+        };
+
+        let (cancel_tx, cancel_rx) = mpsc::channel(1);
+        let run = tokio::spawn(execute_pipeline(
+            logs_dir,
+            pipeline,
+            "test-cancel".to_string(),
+            PathBuf::from("/tmp"),
+            Some(make_bus()),
+            Some(make_registry()),
+            cancel_rx,
+        ));
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        cancel_tx.send(()).await.unwrap();
+
+        let result = tokio::time::timeout(std::time::Duration::from_secs(5), run)
+            .await
+            .expect("pipeline did not stop after cancel")
+            .unwrap();
+        assert_eq!(result, Err("Cancelled".to_string()));
+    }
 }
@@ -208,6 +208,7 @@ impl SentinelModule {
                 escalation: escalation.clone(),
                 completion_tx: Some(completion_tx),
                 completion_rx,
+                abort_handle: None,
             },
         );
 
@@ -230,7 +231,7 @@ impl SentinelModule {
         let registry = self.registry.read().clone();
         let escalation_clone = escalation;
 
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             let log = runtime::logger("sentinel");
 
             // Emit start event
@@ -263,6 +264,7 @@ impl SentinelModule {
                         working_dir_clone.clone(),
                         bus.clone(),
                         registry.clone(),
+                        cancel_rx,
                     ),
                 )
                 .await
@@ -392,6 +394,9 @@ impl SentinelModule {
                 }
             }
         });
+        if let Some(mut entry) = self.sentinels.get_mut(&handle_id) {
+            entry.abort_handle = Some(task.abort_handle());
+        }
 
         Ok(CommandResult::Json(json!({
             "handle": handle_id,
@@ -428,26 +433,106 @@ impl SentinelModule {
         })))
     }
 
-    /// Cancel a running sentinel
+    /// Cancel a running sentinel.
+    ///
+    /// Without `timeoutMs` this signals and returns. With it, waits up to the
+    /// deadline for the sentinel to stop; if it hasn't (a step wedged in a
+    /// blocking call), its task is aborted and it is reported `forced: true`.
     async fn cancel_sentinel(&self, params: Value) -> Result<CommandResult, String> {
         let p = Params::new(&params);
         let handle_id = p.str("handle")?;
+        let timeout_ms = p.u64_opt("timeoutMs");
 
-        if let Some(mut entry) = self.sentinels.get_mut(handle_id) {
-            if entry.handle.status == SentinelStatus::Running {
-                if let Some(cancel_tx) = entry.cancel_tx.take() {
-                    cancel_tx.send(()).await.ok();
-                    entry.handle.status = SentinelStatus::Cancelled;
-                    return Ok(CommandResult::Json(json!({
-                        "handle": handle_id,
-                        "status": "cancelled",
-                    })));
-                }
+        let mut completion_rx = {
+            let mut entry = self
+                .sentinels
+                .get_mut(handle_id)
+                .ok_or_else(|| format!("Sentinel handle not found: {handle_id}"))?;
+            if entry.handle.status != SentinelStatus::Running {
+                return Err(format!("Sentinel {handle_id} is not running"));
             }
-            return Err(format!("Sentinel {handle_id} is not running"));
+            let cancel_tx = entry
+                .cancel_tx
+                .take()
+                .ok_or_else(|| format!("Sentinel {handle_id} is not running"))?;
+            // Capacity 1 and sent at most once — never blocks
+            let _ = cancel_tx.try_send(());
+            entry.handle.status = SentinelStatus::Cancelled;
+            entry.completion_rx.clone()
+        };
+
+        let Some(timeout_ms) = timeout_ms else {
+            return Ok(CommandResult::Json(json!({
+                "handle": handle_id,
+                "status": "cancelled",
+            })));
+        };
+
+        let acknowledged = tokio::time::timeout(
+            Duration::from_millis(timeout_ms),
+            completion_rx.wait_for(|done| *done),
+        )
+        .await
+        .is_ok();
+
+        if !acknowledged {
+            self.force_cancel(handle_id, timeout_ms);
+        }
+
+        Ok(CommandResult::Json(json!({
+            "handle": handle_id,
+            "status": "cancelled",
+            "forced": !acknowledged,
+        })))
+    }
+
+    /// Abort a sentinel that ignored cancellation and finalize its handle
+    /// (the aborted task never reaches its own completion bookkeeping).
+    fn force_cancel(&self, handle_id: &str, timeout_ms: u64) {
+        let log = crate::runtime::logger("sentinel");
+        let Some(mut entry) = self.sentinels.get_mut(handle_id) else {
+            return;
+        };
+        if let Some(abort) = entry.abort_handle.take() {
+            abort.abort();
         }
 
-        Err(format!("Sentinel handle not found: {handle_id}"))
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let error = format!("Cancelled (forced after {timeout_ms}ms)");
+        entry.handle.status = SentinelStatus::Cancelled;
+        entry.handle.end_time = Some(now);
+        entry.handle.error = Some(error.clone());
+        log.warn(&format!(
+            "Sentinel {handle_id} did not stop within {timeout_ms}ms — aborted"
+        ));
+
+        if let Some(ref bus) = *self.bus.read() {
+            bus.publish_async_only(
+                &format!("sentinel:{handle_id}:status"),
+                json!({
+                    "handle": handle_id,
+                    "type": entry.handle.sentinel_type,
+                    "status": "cancelled",
+                    "forced": true,
+                    "error": error,
+                }),
+            );
+            bus.publish_async_only(
+                "sentinel:complete",
+                json!({
+                    "handle": handle_id,
+                    "type": entry.handle.sentinel_type,
+                    "success": false,
+                }),
+            );
+        }
+
+        if let Some(tx) = entry.completion_tx.take() {
+            let _ = tx.send(true);
+        }
     }
 
     /// Await sentinel completion — blocks until done, no polling.
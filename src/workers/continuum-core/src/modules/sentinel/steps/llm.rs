@@ -192,6 +192,9 @@ async fn execute_generate_mode(
             Ok(CommandResult::Binary { .. }) => {
                 return Err(step_err(pipeline_ctx.handle_id, "LLM step", "unexpected binary response from ai/generate"));
             }
+            Ok(CommandResult::Stream(_)) => {
+                return Err(step_err(pipeline_ctx.handle_id, "LLM step", "unexpected streamed response from ai/generate"));
+            }
             Err(e) => {
                 if is_transient_error(&e) && attempt < LLM_MAX_RETRIES {
                     last_error = e;
@@ -379,6 +379,8 @@ pub struct RunningSentinel {
     /// Replaces the TS polling loop with a proper async wait.
     pub completion_tx: Option<tokio::sync::watch::Sender<bool>>,
     pub completion_rx: tokio::sync::watch::Receiver<bool>,
+    /// Aborts the sentinel's task when a cancel deadline passes unacknowledged
+    pub abort_handle: Option<tokio::task::AbortHandle>,
 }
 
 /// Safety limit for while/until/continuous loops when maxIterations is omitted
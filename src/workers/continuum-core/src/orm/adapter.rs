@@ -5,7 +5,7 @@
 //!
 //! Supported backends:
 //! - SQLite (implemented)
-//! - PostgreSQL (future)
+//! - PostgreSQL (implemented)
 //! - MySQL (future)
 //! - Oracle (future)
 //! - REST (future)
@@ -13,11 +13,11 @@
 //! - JSON file (future)
 
 use async_trait::async_trait;
-use serde_json::Value;
+use serde_json::{json, Value};
 
 use super::query::StorageQuery;
 use super::types::{
-    BatchOperation, CollectionSchema, CollectionStats, DataRecord, StorageResult, UUID,
+    BatchOperation, CollectionSchema, CollectionStats, DataRecord, SchemaIndex, StorageResult, UUID,
 };
 
 /// Storage adapter configuration
@@ -44,6 +44,44 @@ impl Default for AdapterConfig {
     }
 }
 
+/// Which adapter serves a connection string
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterType {
+    Sqlite,
+    Postgres,
+}
+
+impl AdapterType {
+    /// Pick the adapter for `connection_string`. An explicit `requested`
+    /// type ("sqlite" | "postgres") wins; otherwise `postgres://` and
+    /// `postgresql://` URLs go to Postgres and everything else (file paths,
+    /// `:memory:`) to SQLite. Explicit selection is what lets libpq
+    /// key/value strings (`host=... dbname=...`) reach Postgres.
+    pub fn resolve(connection_string: &str, requested: Option<&str>) -> Result<Self, String> {
+        match requested {
+            Some("sqlite") => Ok(AdapterType::Sqlite),
+            Some("postgres") | Some("postgresql") => Ok(AdapterType::Postgres),
+            Some(other) => Err(format!(
+                "Unknown adapter type '{other}' (expected sqlite or postgres)"
+            )),
+            None if connection_string.starts_with("postgres://")
+                || connection_string.starts_with("postgresql://") =>
+            {
+                Ok(AdapterType::Postgres)
+            }
+            None => Ok(AdapterType::Sqlite),
+        }
+    }
+
+    /// Same as the adapter's `StorageAdapter::name()`
+    pub fn name(self) -> &'static str {
+        match self {
+            AdapterType::Sqlite => "sqlite",
+            AdapterType::Postgres => "postgres",
+        }
+    }
+}
+
 /// Storage adapter capabilities
 #[derive(Debug, Clone, Default)]
 pub struct AdapterCapabilities {
@@ -109,6 +147,44 @@ pub trait StorageAdapter: Send + Sync {
     /// Execute batch operations
     async fn batch(&self, operations: Vec<BatchOperation>) -> StorageResult<Vec<Value>>;
 
+    /// Bulk insert. Returns one `{index, success, id | error}` entry per record.
+    /// `stop_on_error`: abort on the first failure (all-or-nothing where the
+    /// adapter has transactions); otherwise failures are reported per record.
+    /// Default: sequential `create` calls, each committed on its own.
+    async fn create_many(
+        &self,
+        records: Vec<DataRecord>,
+        stop_on_error: bool,
+    ) -> StorageResult<Vec<Value>> {
+        let mut results = Vec::with_capacity(records.len());
+        for (index, record) in records.into_iter().enumerate() {
+            let id = record.id.clone();
+            let r = self.create(record).await;
+            if r.success {
+                results.push(json!({"index": index, "success": true, "id": id}));
+            } else if stop_on_error {
+                return StorageResult::err(format!(
+                    "Record {} failed: {}",
+                    index,
+                    r.error.unwrap_or_default()
+                ));
+            } else {
+                results.push(json!({"index": index, "success": false, "error": r.error}));
+            }
+        }
+        StorageResult::ok(results)
+    }
+
+    /// Apply create/update/delete operations atomically: either every
+    /// operation commits or none do. Returns one result per operation.
+    /// Default: unsupported (adapter autocommits each op).
+    async fn transaction(&self, _operations: Vec<BatchOperation>) -> StorageResult<Vec<Value>> {
+        StorageResult::err(format!(
+            "{} adapter does not support atomic transactions",
+            self.name()
+        ))
+    }
+
     // ─── Schema Operations ───────────────────────────────────────────────────
 
     /// Ensure collection schema exists
@@ -120,6 +196,22 @@ pub trait StorageAdapter: Send + Sync {
     /// Get collection statistics
     async fn collection_stats(&self, collection: &str) -> StorageResult<CollectionStats>;
 
+    /// Create an index on a collection (no-op if the name already exists)
+    async fn create_index(&self, collection: &str, index: SchemaIndex) -> StorageResult<bool>;
+
+    /// List a collection's secondary indexes, with camelCase field names
+    async fn list_indexes(&self, collection: &str) -> StorageResult<Vec<SchemaIndex>>;
+
+    /// Full-text index over `fields`, kept in sync with the collection, that
+    /// `StorageQuery::text_match` queries run against.
+    /// Default: unsupported.
+    async fn ensure_fts(&self, _collection: &str, _fields: Vec<String>) -> StorageResult<bool> {
+        StorageResult::err(format!(
+            "{} adapter does not support full-text search",
+            self.name()
+        ))
+    }
+
     // ─── Maintenance Operations ──────────────────────────────────────────────
 
     /// Truncate a collection (delete all records)
@@ -205,3 +297,360 @@ pub mod naming {
         }
     }
 }
+
+/// SQL translation shared by the SQL adapters (SQLite, Postgres)
+pub mod sql {
+    use super::naming;
+    use crate::orm::query::{KeysetCursor, SortDirection, SortSpec};
+    use crate::orm::types::SchemaIndex;
+    use serde_json::Value;
+
+    /// Columns every record row carries, whatever the projection
+    const METADATA_COLUMNS: [&str; 4] = ["id", "created_at", "updated_at", "version"];
+
+    /// Build SELECT clause from optional column projection.
+    /// Converts camelCase field names to snake_case for SQL.
+    /// Always includes id, created_at, updated_at, version (metadata columns).
+    pub fn select_clause(select: &Option<Vec<String>>) -> String {
+        match select {
+            Some(cols) if !cols.is_empty() => {
+                // Adapters need the metadata columns to build a DataRecord
+                let mut selected: Vec<String> =
+                    METADATA_COLUMNS.iter().map(|c| c.to_string()).collect();
+                for col in cols {
+                    let snake = naming::to_snake_case(col);
+                    if !METADATA_COLUMNS.contains(&snake.as_str()) {
+                        selected.push(snake);
+                    }
+                }
+                selected.join(", ")
+            }
+            _ => "*".to_string(),
+        }
+    }
+
+    /// Build ORDER BY clause (empty when there is no sort). NULLs sort as
+    /// the smallest value in every adapter (SQLite's default; Postgres
+    /// would put them last ascending), which `keyset_condition` relies on.
+    pub fn order_clause(sort: &Option<Vec<SortSpec>>) -> String {
+        match sort {
+            Some(sorts) if !sorts.is_empty() => {
+                let parts: Vec<_> = sorts
+                    .iter()
+                    .map(|s| {
+                        let dir = match s.direction {
+                            SortDirection::Asc => "ASC NULLS FIRST",
+                            SortDirection::Desc => "DESC NULLS LAST",
+                        };
+                        format!("{} {}", naming::to_snake_case(&s.field), dir)
+                    })
+                    .collect();
+                format!("ORDER BY {}", parts.join(", "))
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// Condition selecting the rows after `cursor` in `keys` order:
+    /// `(k1 > v1) OR (k1 = v1 AND k2 > v2) OR ...`, with `<` for
+    /// descending keys. NULL cursor values use `IS NULL` / `IS NOT NULL`
+    /// terms instead, with NULL the smallest value as in `order_clause`.
+    /// `bind` adds a parameter for (column, value) and returns its
+    /// placeholder; it is called in placeholder order.
+    pub fn keyset_condition(
+        keys: &[SortSpec],
+        cursor: &KeysetCursor,
+        mut bind: impl FnMut(&str, &Value) -> String,
+    ) -> String {
+        let columns: Vec<String> = keys
+            .iter()
+            .map(|k| naming::to_snake_case(&k.field))
+            .collect();
+        let mut branches = Vec::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            let value = &cursor.values[i];
+            // Nothing sorts after NULL descending
+            if key.direction == SortDirection::Desc && value.is_null() {
+                continue;
+            }
+            let mut terms: Vec<String> = (0..i)
+                .map(|j| match &cursor.values[j] {
+                    Value::Null => format!("{} IS NULL", columns[j]),
+                    value => format!("{} = {}", columns[j], bind(&columns[j], value)),
+                })
+                .collect();
+            let after = match key.direction {
+                // Every non-NULL value sorts after NULL ascending
+                SortDirection::Asc if value.is_null() => format!("{} IS NOT NULL", columns[i]),
+                SortDirection::Asc => format!("{} > {}", columns[i], bind(&columns[i], value)),
+                SortDirection::Desc => format!(
+                    "({} < {} OR {} IS NULL)",
+                    columns[i],
+                    bind(&columns[i], value),
+                    columns[i]
+                ),
+            };
+            terms.push(after);
+            branches.push(format!("({})", terms.join(" AND ")));
+        }
+        if branches.is_empty() {
+            // Cursor at the very end of the order
+            return "(1 = 0)".to_string();
+        }
+        format!("({})", branches.join(" OR "))
+    }
+
+    /// CREATE INDEX statement for `index` on `table` (which may be
+    /// schema-qualified). See `index_columns` for naming.
+    pub fn create_index(
+        table: &str,
+        bare_table: &str,
+        index: &SchemaIndex,
+    ) -> Result<String, String> {
+        let (name, columns) = index_columns(bare_table, index)?;
+        let unique = if index.unique { "UNIQUE " } else { "" };
+        Ok(format!(
+            "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
+            unique,
+            name,
+            table,
+            columns.join(", ")
+        ))
+    }
+
+    /// SQL name and columns of `index`: fields map to snake_case columns,
+    /// and an empty name becomes `idx_{bare_table}_{columns}`. Both are
+    /// spliced into DDL, so anything outside `[A-Za-z0-9_]` is rejected.
+    pub fn index_columns(
+        bare_table: &str,
+        index: &SchemaIndex,
+    ) -> Result<(String, Vec<String>), String> {
+        if index.fields.is_empty() {
+            return Err("Index needs at least one field".to_string());
+        }
+        let columns = field_columns("Index field", &index.fields)?;
+        let name = if index.name.is_empty() {
+            format!("idx_{}_{}", bare_table, columns.join("_"))
+        } else {
+            identifier("Index name", naming::to_snake_case(&index.name))?
+        };
+        Ok((name, columns))
+    }
+
+    /// snake_case columns for `fields`, rejected like `index_columns` for
+    /// anything outside `[A-Za-z0-9_]`. `kind` names them in the error.
+    pub fn field_columns(kind: &str, fields: &[String]) -> Result<Vec<String>, String> {
+        fields
+            .iter()
+            .map(|f| identifier(kind, naming::to_snake_case(f)))
+            .collect()
+    }
+
+    fn identifier(kind: &str, ident: String) -> Result<String, String> {
+        if !ident.is_empty() && ident.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            Ok(ident)
+        } else {
+            Err(format!(
+                "{kind} '{ident}' may only contain letters, digits and '_'"
+            ))
+        }
+    }
+
+    /// Check an index found under the name `create_index` would use.
+    /// `IF NOT EXISTS` skips creation whenever the name is taken, so an
+    /// index on another table or with other columns is an error rather
+    /// than silently kept.
+    pub fn check_existing_index(
+        name: &str,
+        bare_table: &str,
+        columns: &[String],
+        unique: bool,
+        existing: &ExistingIndex,
+    ) -> Result<(), String> {
+        if existing.table != bare_table {
+            return Err(format!(
+                "Index '{name}' already exists on table '{}'",
+                existing.table
+            ));
+        }
+        if existing.columns != columns || existing.unique != unique {
+            let kind = |unique: bool| if unique { "unique " } else { "" };
+            return Err(format!(
+                "Index '{name}' already exists as a {}index on ({}), not a {}index on ({})",
+                kind(existing.unique),
+                existing.columns.join(", "),
+                kind(unique),
+                columns.join(", ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// An index as the database reports it, looked up by name
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ExistingIndex {
+        /// Unqualified table it is on
+        pub table: String,
+        pub unique: bool,
+        /// Column names in index order
+        pub columns: Vec<String>,
+    }
+
+    /// Add `condition` to a WHERE clause (which may be empty)
+    pub fn and_where(where_clause: &str, condition: &str) -> String {
+        if where_clause.is_empty() {
+            format!("WHERE {condition}")
+        } else {
+            format!("{where_clause} AND {condition}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adapter_type_resolve() {
+        let url = "postgres://localhost/continuum";
+        assert_eq!(AdapterType::resolve(url, None), Ok(AdapterType::Postgres));
+        assert_eq!(
+            AdapterType::resolve("/data/main.db", None),
+            Ok(AdapterType::Sqlite)
+        );
+        assert_eq!(
+            AdapterType::resolve(":memory:", None),
+            Ok(AdapterType::Sqlite)
+        );
+
+        // Explicit type routes strings the prefix can't
+        let kv = "host=localhost dbname=continuum";
+        assert_eq!(AdapterType::resolve(kv, None), Ok(AdapterType::Sqlite));
+        assert_eq!(
+            AdapterType::resolve(kv, Some("postgres")),
+            Ok(AdapterType::Postgres)
+        );
+        assert!(AdapterType::resolve(kv, Some("mysql")).is_err());
+    }
+
+    #[test]
+    fn test_create_index_sql() {
+        use crate::orm::types::SchemaIndex;
+
+        let mut index = SchemaIndex {
+            name: String::new(),
+            fields: vec!["roomId".to_string(), "createdAt".to_string()],
+            unique: false,
+        };
+        assert_eq!(
+            sql::create_index("chat_messages", "chat_messages", &index).unwrap(),
+            "CREATE INDEX IF NOT EXISTS idx_chat_messages_room_id_created_at \
+             ON chat_messages (room_id, created_at)"
+        );
+
+        index.name = "byRoom".to_string();
+        index.unique = true;
+        assert_eq!(
+            sql::create_index("app.chat_messages", "chat_messages", &index).unwrap(),
+            "CREATE UNIQUE INDEX IF NOT EXISTS by_room ON app.chat_messages (room_id, created_at)"
+        );
+
+        index.fields.clear();
+        assert!(sql::create_index("t", "t", &index).is_err());
+
+        // Identifiers are spliced into DDL: nothing but [A-Za-z0-9_]
+        index.fields = vec!["roomId".to_string()];
+        index.name = "x ON t (a); DROP TABLE users; --".to_string();
+        assert!(sql::create_index("t", "t", &index).is_err());
+        index.name = String::new();
+        index.fields = vec!["room id".to_string()];
+        assert!(sql::create_index("t", "t", &index).is_err());
+    }
+
+    #[test]
+    fn test_check_existing_index() {
+        let columns = vec!["room_id".to_string(), "created_at".to_string()];
+        let existing = sql::ExistingIndex {
+            table: "chat_messages".to_string(),
+            unique: false,
+            columns: columns.clone(),
+        };
+        assert!(
+            sql::check_existing_index("by_room", "chat_messages", &columns, false, &existing)
+                .is_ok()
+        );
+
+        let err =
+            sql::check_existing_index("by_room", "chat_messages", &columns[..1], false, &existing)
+                .unwrap_err();
+        assert!(err.contains("(room_id, created_at)"), "{err}");
+        assert!(
+            sql::check_existing_index("by_room", "chat_messages", &columns, true, &existing)
+                .is_err()
+        );
+        let err =
+            sql::check_existing_index("by_room", "users", &columns, false, &existing).unwrap_err();
+        assert!(err.contains("table 'chat_messages'"), "{err}");
+    }
+
+    #[test]
+    fn test_keyset_condition() {
+        use crate::orm::query::{keyset_sort, KeysetCursor, SortDirection, SortSpec};
+        use serde_json::json;
+
+        let keys = keyset_sort(&Some(vec![SortSpec {
+            field: "createdAt".to_string(),
+            direction: SortDirection::Desc,
+        }]));
+        assert_eq!(
+            sql::order_clause(&Some(keys.clone())),
+            "ORDER BY created_at DESC NULLS LAST, id DESC NULLS LAST"
+        );
+
+        let cursor = KeysetCursor {
+            values: vec![json!("2026-01-01T00:00:00Z"), json!("abc")],
+        };
+        let decoded = KeysetCursor::decode(&cursor.encode(), &keys).unwrap();
+        assert_eq!(decoded, cursor);
+        assert!(KeysetCursor::decode(&cursor.encode(), &keys[..1]).is_err());
+
+        let mut bound = Vec::new();
+        let condition = sql::keyset_condition(&keys, &cursor, |column, value| {
+            bound.push((column.to_string(), value.clone()));
+            format!("${}", bound.len())
+        });
+        assert_eq!(
+            condition,
+            "((created_at < $1) OR (created_at = $2 AND id < $3))"
+        );
+        assert_eq!(bound.len(), 3);
+        assert_eq!(bound[2], ("id".to_string(), json!("abc")));
+
+        // A NULL sort value pages on instead of binding `< NULL`
+        let null_cursor = KeysetCursor {
+            values: vec![json!(null), json!("abc")],
+        };
+        let mut bound = Vec::new();
+        let condition = sql::keyset_condition(&keys, &null_cursor, |_, value| {
+            bound.push(value.clone());
+            "?".to_string()
+        });
+        assert_eq!(condition, "((created_at IS NULL AND id < ?))");
+        assert_eq!(bound, vec![json!("abc")]);
+
+        let asc = keyset_sort(&Some(vec![SortSpec {
+            field: "priority".to_string(),
+            direction: SortDirection::Asc,
+        }]));
+        let mut bound = Vec::new();
+        let condition = sql::keyset_condition(&asc, &null_cursor, |_, value| {
+            bound.push(value.clone());
+            "?".to_string()
+        });
+        assert_eq!(
+            condition,
+            "((priority IS NOT NULL) OR (priority IS NULL AND id > ?))"
+        );
+        assert_eq!(bound, vec![json!("abc")]);
+    }
+}
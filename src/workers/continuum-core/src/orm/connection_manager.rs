@@ -500,6 +500,7 @@ mod tests {
                         max_length: None,
                     }],
                     indexes: vec![],
+                    migration: None,
                 },
             )
             .await;
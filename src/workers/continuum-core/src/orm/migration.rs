@@ -450,6 +450,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -515,6 +516,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -565,6 +567,7 @@ mod tests {
                 collection: "empty".to_string(),
                 fields: vec![],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -597,6 +600,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
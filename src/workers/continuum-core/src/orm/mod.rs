@@ -9,7 +9,7 @@
 //!     │       ↓ trait calls (no IPC)
 //!     └── StorageAdapter trait implementations
 //!         ├── SqliteAdapter
-//!         ├── PostgresAdapter (future)
+//!         ├── PostgresAdapter
 //!         ├── MysqlAdapter (future)
 //!         └── etc.
 //! ```
@@ -29,7 +29,7 @@ pub mod sqlite;
 pub mod types;
 pub mod vector;
 
-pub use adapter::StorageAdapter;
+pub use adapter::{AdapterType, StorageAdapter};
 pub use connection_manager::{ConnectionManager, ConnectionManagerConfig};
 pub use migration::{MigrationEngine, MigrationHandle};
 pub use postgres::PostgresAdapter;
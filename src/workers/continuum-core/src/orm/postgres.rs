@@ -22,11 +22,13 @@ use tokio::sync::RwLock;
 use tokio_postgres::types::{Json, ToSql};
 use tokio_postgres::NoTls;
 
-use super::adapter::{naming, AdapterCapabilities, AdapterConfig, ClearAllResult, StorageAdapter};
-use super::query::{FieldFilter, QueryOperator, SortDirection, StorageQuery};
+use super::adapter::{
+    naming, sql, AdapterCapabilities, AdapterConfig, ClearAllResult, StorageAdapter,
+};
+use super::query::{keyset_sort, FieldFilter, KeysetCursor, QueryOperator, StorageQuery};
 use super::types::{
     BatchOperation, BatchOperationType, CollectionSchema, CollectionStats, DataRecord,
-    RecordMetadata, StorageResult, UUID, METADATA_KEYS,
+    RecordMetadata, SchemaIndex, StorageResult, UUID, METADATA_KEYS,
 };
 
 
@@ -107,6 +109,55 @@ impl PostgresAdapter {
             format!("{}.{}", self.schema, table)
         }
     }
+
+    /// Create `index` on `collection`'s table. Index names are
+    /// schema-wide and IF NOT EXISTS keeps whatever already holds the
+    /// name, so an existing index is checked against `index` first.
+    async fn create_index_with(
+        &self,
+        client: &deadpool_postgres::Client,
+        collection: &str,
+        index: &SchemaIndex,
+    ) -> Result<(), String> {
+        let bare_table = naming::to_table_name(collection);
+        let (name, columns) = sql::index_columns(&bare_table, index)?;
+
+        let lookup = "SELECT t.relname::text, ix.indisunique, \
+                      array_agg(a.attname::text ORDER BY k.ord) \
+                      FROM pg_index ix \
+                      JOIN pg_class t ON t.oid = ix.indrelid \
+                      JOIN pg_class i ON i.oid = ix.indexrelid \
+                      JOIN pg_namespace n ON n.oid = i.relnamespace \
+                      CROSS JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS k(attnum, ord) \
+                      JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum \
+                      WHERE n.nspname = $1 AND i.relname = $2 \
+                      GROUP BY t.relname, ix.indisunique";
+        let found = client
+            .query_opt(lookup, &[&self.schema, &name])
+            .await
+            .map_err(|e| format!("Create index failed: {}", format_pg_error(&e)))?;
+        if let Some(row) = found {
+            let existing = sql::ExistingIndex {
+                table: row.get(0),
+                unique: row.get(1),
+                columns: row.get(2),
+            };
+            return sql::check_existing_index(
+                &name,
+                &bare_table,
+                &columns,
+                index.unique,
+                &existing,
+            );
+        }
+
+        let idx_sql = sql::create_index(&self.table_ref(collection), &bare_table, index)?;
+        client
+            .execute(&idx_sql, &[])
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("Create index failed: {}", format_pg_error(&e)))
+    }
 }
 
 impl Default for PostgresAdapter {
@@ -291,6 +342,7 @@ fn pg_type_from_field_type(ft: &super::types::FieldType) -> &'static str {
         super::types::FieldType::Date => "TIMESTAMPTZ",
         super::types::FieldType::Json => "JSONB",
         super::types::FieldType::Uuid => "TEXT",
+        super::types::FieldType::Vector => "JSONB",
     }
 }
 
@@ -413,52 +465,6 @@ fn build_where_clause(
     }
 }
 
-/// Build ORDER BY clause
-/// Build SELECT clause from optional column projection.
-/// Converts camelCase field names to snake_case for SQL.
-/// Always includes id, created_at, updated_at, version (metadata columns).
-fn build_select_clause(select: &Option<Vec<String>>) -> String {
-    match select {
-        Some(cols) if !cols.is_empty() => {
-            // Always include metadata columns — adapters need them for DataRecord
-            let mut selected: Vec<String> = vec![
-                "id".to_string(),
-                "created_at".to_string(),
-                "updated_at".to_string(),
-                "version".to_string(),
-            ];
-            for col in cols {
-                let snake = naming::to_snake_case(col);
-                // Don't duplicate metadata columns
-                if snake != "id" && snake != "created_at" && snake != "updated_at" && snake != "version" {
-                    selected.push(snake);
-                }
-            }
-            selected.join(", ")
-        }
-        _ => "*".to_string(),
-    }
-}
-
-fn build_order_clause(sort: &Option<Vec<super::query::SortSpec>>) -> String {
-    if let Some(sorts) = sort {
-        if !sorts.is_empty() {
-            let parts: Vec<_> = sorts
-                .iter()
-                .map(|s| {
-                    let dir = match s.direction {
-                        SortDirection::Asc => "ASC",
-                        SortDirection::Desc => "DESC",
-                    };
-                    format!("{} {}", naming::to_snake_case(&s.field), dir)
-                })
-                .collect();
-            return format!("ORDER BY {}", parts.join(", "));
-        }
-    }
-    String::new()
-}
-
 /// Convert a tokio_postgres Row to a DataRecord
 fn row_to_record(
     row: &tokio_postgres::Row,
@@ -808,6 +814,14 @@ impl StorageAdapter for PostgresAdapter {
     }
 
     async fn query(&self, query: StorageQuery) -> StorageResult<Vec<DataRecord>> {
+        if query.vector.is_some() {
+            return StorageResult::err(
+                "Vector KNN queries are not supported by the postgres adapter",
+            );
+        }
+        if query.text_match.is_some() {
+            return StorageResult::err("Full-text match is not supported by the postgres adapter");
+        }
         let pool = match self.pool() {
             Ok(p) => p,
             Err(e) => return StorageResult::err(e),
@@ -820,10 +834,24 @@ impl StorageAdapter for PostgresAdapter {
         let bare_table = naming::to_table_name(&query.collection);
         let table = self.table_ref(&query.collection);
         let col_types = self.cached_column_types(&client, &bare_table).await;
-        let (where_clause, where_params) = build_where_clause(&query.filter, 0, &col_types);
-        let order_clause = build_order_clause(&query.sort);
+        let (mut where_clause, mut where_params) = build_where_clause(&query.filter, 0, &col_types);
+        let mut order_clause = sql::order_clause(&query.sort);
+        if let Some(token) = &query.after_cursor {
+            let keys = keyset_sort(&query.sort);
+            let cursor = match KeysetCursor::decode(token, &keys) {
+                Ok(c) => c,
+                Err(e) => return StorageResult::err(e),
+            };
+            let condition = sql::keyset_condition(&keys, &cursor, |column, value| {
+                let pg_type = col_types.get(column).map(|s| s.as_str());
+                where_params.push(value_to_pg_typed(value, pg_type));
+                format!("${}", where_params.len())
+            });
+            where_clause = sql::and_where(&where_clause, &condition);
+            order_clause = sql::order_clause(&Some(keys));
+        }
 
-        let select_clause = build_select_clause(&query.select);
+        let select_clause = sql::select_clause(&query.select);
         let mut sql = format!("SELECT {} FROM {}", select_clause, table);
         if !where_clause.is_empty() {
             sql.push(' ');
@@ -939,7 +967,7 @@ impl StorageAdapter for PostgresAdapter {
         let bare_table = naming::to_table_name(&query.collection);
         let col_types = self.cached_column_types(&client, &bare_table).await;
         let (where_clause, where_params) = build_where_clause(&query.filter, 0, &col_types);
-        let order_clause = build_order_clause(&query.sort);
+        let order_clause = sql::order_clause(&query.sort);
 
         let mut sql = format!(
             "SELECT {} FROM {} {}",
@@ -985,6 +1013,9 @@ impl StorageAdapter for PostgresAdapter {
     }
 
     async fn count(&self, query: StorageQuery) -> StorageResult<usize> {
+        if query.text_match.is_some() {
+            return StorageResult::err("Full-text match is not supported by the postgres adapter");
+        }
         let pool = match self.pool() {
             Ok(p) => p,
             Err(e) => return StorageResult::err(e),
@@ -1181,6 +1212,11 @@ impl StorageAdapter for PostgresAdapter {
     }
 
     async fn ensure_schema(&self, schema: CollectionSchema) -> StorageResult<bool> {
+        if schema.migration.is_some() {
+            return StorageResult::err(
+                "Schema migrations (renames, drops) are not supported by the postgres adapter",
+            );
+        }
         let pool = match self.pool() {
             Ok(p) => p,
             Err(e) => return StorageResult::err(e),
@@ -1247,21 +1283,11 @@ impl StorageAdapter for PostgresAdapter {
 
         // Create composite indexes
         for index in &schema.indexes {
-            let cols: Vec<String> = index
-                .fields
-                .iter()
-                .map(|f| naming::to_snake_case(f))
-                .collect();
-            let unique = if index.unique { "UNIQUE " } else { "" };
-            let idx_sql = format!(
-                "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
-                unique,
-                naming::to_snake_case(&index.name),
-                table,
-                cols.join(", ")
-            );
-            if let Err(e) = client.execute(&idx_sql, &[]).await {
-                return StorageResult::err(format!("Create composite index failed: {}", format_pg_error(&e)));
+            if let Err(e) = self
+                .create_index_with(&client, &schema.collection, index)
+                .await
+            {
+                return StorageResult::err(e);
             }
         }
 
@@ -1313,6 +1339,62 @@ impl StorageAdapter for PostgresAdapter {
         })
     }
 
+    async fn create_index(&self, collection: &str, index: SchemaIndex) -> StorageResult<bool> {
+        let pool = match self.pool() {
+            Ok(p) => p,
+            Err(e) => return StorageResult::err(e),
+        };
+        let client = match pool.get().await {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(format!("Pool error: {}", e)),
+        };
+
+        match self.create_index_with(&client, collection, &index).await {
+            Ok(()) => StorageResult::ok(true),
+            Err(e) => StorageResult::err(e),
+        }
+    }
+
+    async fn list_indexes(&self, collection: &str) -> StorageResult<Vec<SchemaIndex>> {
+        let pool = match self.pool() {
+            Ok(p) => p,
+            Err(e) => return StorageResult::err(e),
+        };
+        let client = match pool.get().await {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(format!("Pool error: {}", e)),
+        };
+
+        // Columns in index order; expression indexes have no attribute and are skipped
+        let sql =
+            "SELECT i.relname::text, ix.indisunique, array_agg(a.attname::text ORDER BY k.ord) \
+             FROM pg_index ix \
+             JOIN pg_class t ON t.oid = ix.indrelid \
+             JOIN pg_class i ON i.oid = ix.indexrelid \
+             JOIN pg_namespace n ON n.oid = t.relnamespace \
+             CROSS JOIN LATERAL unnest(ix.indkey) WITH ORDINALITY AS k(attnum, ord) \
+             JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum \
+             WHERE n.nspname = $1 AND t.relname = $2 AND NOT ix.indisprimary \
+             GROUP BY i.relname, ix.indisunique \
+             ORDER BY i.relname";
+        let table = naming::to_table_name(collection);
+        match client.query(sql, &[&self.schema, &table]).await {
+            Ok(rows) => StorageResult::ok(
+                rows.iter()
+                    .map(|row| {
+                        let columns: Vec<String> = row.get(2);
+                        SchemaIndex {
+                            name: row.get(0),
+                            fields: columns.iter().map(|c| naming::to_camel_case(c)).collect(),
+                            unique: row.get(1),
+                        }
+                    })
+                    .collect(),
+            ),
+            Err(e) => StorageResult::err(format!("List indexes failed: {}", format_pg_error(&e))),
+        }
+    }
+
     async fn truncate(&self, collection: &str) -> StorageResult<bool> {
         let pool = match self.pool() {
             Ok(p) => p,
@@ -1518,6 +1600,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -1568,6 +1651,7 @@ mod tests {
                     },
                 ],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -1636,6 +1720,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -1691,6 +1776,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -1722,6 +1808,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -1784,6 +1871,7 @@ mod tests {
                     },
                 ],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -1829,6 +1917,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -2,10 +2,13 @@
 //!
 //! Provides a fluent API for building queries that adapters translate to native format.
 
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use ts_rs::TS;
 
+use super::types::DataRecord;
+
 /// Sort direction
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq)]
 #[ts(export, export_to = "../../../shared/generated/orm/SortDirection.ts")]
@@ -107,6 +110,64 @@ pub enum CursorDirection {
     After,
 }
 
+/// Sort order made total for keyset pagination: `sort` with `id` appended
+/// as the tie-breaker (in the direction of the last key), unless it already
+/// sorts by id. No sort means id ascending.
+pub fn keyset_sort(sort: &Option<Vec<SortSpec>>) -> Vec<SortSpec> {
+    let mut keys = sort.clone().unwrap_or_default();
+    if !keys.iter().any(|k| k.field == "id") {
+        let direction = keys.last().map_or(SortDirection::Asc, |k| k.direction);
+        keys.push(SortSpec {
+            field: "id".to_string(),
+            direction,
+        });
+    }
+    keys
+}
+
+/// Position just past a row in `keyset_sort` order: the row's value for
+/// each sort key, id last. Travels as an opaque URL-safe base64 token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeysetCursor {
+    pub values: Vec<Value>,
+}
+
+impl KeysetCursor {
+    /// Cursor at `record` for `keys`. A sort field the record doesn't
+    /// carry is NULL there, which `keyset_condition` pages past like any
+    /// other value.
+    pub fn at(record: &DataRecord, keys: &[SortSpec]) -> Self {
+        let values = keys
+            .iter()
+            .map(|key| match key.field.as_str() {
+                "id" => Value::String(record.id.clone()),
+                "createdAt" => Value::String(record.metadata.created_at.clone()),
+                "updatedAt" => Value::String(record.metadata.updated_at.clone()),
+                field => record.data.get(field).cloned().unwrap_or(Value::Null),
+            })
+            .collect();
+        Self { values }
+    }
+
+    pub fn encode(&self) -> String {
+        let json = Value::Array(self.values.clone()).to_string();
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decode a token from `encode`, checking it was made for `keys`
+    pub fn decode(token: &str, keys: &[SortSpec]) -> Result<Self, String> {
+        let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(token)
+            .map_err(|_| "Invalid cursor".to_string())?;
+        let values: Vec<Value> =
+            serde_json::from_slice(&bytes).map_err(|_| "Invalid cursor".to_string())?;
+        if values.len() != keys.len() {
+            return Err("Cursor does not match the query's sort".to_string());
+        }
+        Ok(Self { values })
+    }
+}
+
 /// Time range filter
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../../shared/generated/orm/TimeRange.ts")]
@@ -148,6 +209,26 @@ pub enum JoinType {
     Inner,
 }
 
+/// Key under which vector queries report each row's cosine similarity in `data`
+pub const VECTOR_SCORE_KEY: &str = "_similarity";
+
+fn default_top_k() -> usize {
+    10
+}
+
+/// Vector KNN mode for a query: rank rows by cosine similarity between
+/// `vector_field` and `query_vector`, keeping the best `top_k`.
+/// `filter` still applies; `sort`, `limit` and `offset` are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/orm/VectorQuery.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct VectorQuery {
+    pub vector_field: String,
+    pub query_vector: Vec<f32>,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+}
+
 /// Storage query - the universal query format
 #[derive(Debug, Clone, Serialize, Deserialize, TS, Default)]
 #[ts(export, export_to = "../../../shared/generated/orm/StorageQuery.ts")]
@@ -169,6 +250,12 @@ pub struct StorageQuery {
     #[ts(optional)]
     #[serde(default)]
     pub cursor: Option<Cursor>,
+    /// Keyset pagination: only rows after this cursor (a previous page's
+    /// `metadata.nextCursor`, with the same filter and sort). Rows are
+    /// ordered by `keyset_sort(sort)`; use instead of `offset`.
+    #[ts(optional)]
+    #[serde(default)]
+    pub after_cursor: Option<String>,
     #[ts(optional)]
     #[serde(default)]
     pub tags: Option<Vec<String>>,
@@ -182,6 +269,15 @@ pub struct StorageQuery {
     #[ts(optional)]
     #[serde(default)]
     pub select: Option<Vec<String>>,
+    /// Nearest-neighbour mode (see `VectorQuery`)
+    #[ts(optional)]
+    #[serde(default)]
+    pub vector: Option<VectorQuery>,
+    /// Full-text mode: an FTS5 MATCH expression over the collection's
+    /// `ensure_fts` fields. Rows come back best match first unless sorted.
+    #[ts(optional)]
+    #[serde(default, rename = "match")]
+    pub text_match: Option<String>,
 }
 
 /// Fluent query builder
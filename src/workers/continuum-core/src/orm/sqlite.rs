@@ -14,16 +14,23 @@ use crate::{clog_error, clog_info, clog_warn};
 use async_trait::async_trait;
 use rusqlite::{params, Connection, OpenFlags};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
-use super::adapter::{naming, AdapterCapabilities, AdapterConfig, ClearAllResult, StorageAdapter};
-use super::query::{FieldFilter, QueryOperator, SortDirection, StorageQuery};
+use super::adapter::{
+    naming, sql, AdapterCapabilities, AdapterConfig, ClearAllResult, StorageAdapter,
+};
+use super::query::{
+    keyset_sort, FieldFilter, KeysetCursor, QueryOperator, StorageQuery, VectorQuery,
+    VECTOR_SCORE_KEY,
+};
 use super::types::{
-    BatchOperation, BatchOperationType, CollectionSchema, CollectionStats, DataRecord,
-    RecordMetadata, StorageResult, UUID, METADATA_KEYS,
+    BatchOperation, BatchOperationType, CollectionSchema, CollectionStats, DataRecord, FieldType,
+    RecordMetadata, SchemaIndex, StorageResult, METADATA_KEYS, UUID,
 };
+use super::vector::similarity;
 
 // No artificial cap on reader pool — AdapterConfig.max_connections controls it.
 // WAL mode supports unlimited concurrent readers.
@@ -337,12 +344,40 @@ fn do_read(conn: &Connection, collection: &str, id: &UUID) -> StorageResult<Data
 }
 
 fn do_query(conn: &Connection, query: StorageQuery) -> StorageResult<Vec<DataRecord>> {
+    if let Some(knn) = &query.vector {
+        return do_vector_query(conn, &query, knn);
+    }
+
     let table = naming::to_table_name(&query.collection);
-    let (where_clause, where_params) = build_where_clause(&query.filter);
-    let order_clause = build_order_clause(&query.sort);
+    let (mut where_clause, mut where_params) = build_where_clause(&query.filter);
+    let mut order_clause = sql::order_clause(&query.sort);
+    let mut select_clause = sql::select_clause(&query.select);
+    if let Some(token) = &query.after_cursor {
+        let keys = keyset_sort(&query.sort);
+        let cursor = match KeysetCursor::decode(token, &keys) {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(e),
+        };
+        let condition = sql::keyset_condition(&keys, &cursor, |_, value| {
+            where_params.push(value.clone());
+            "?".to_string()
+        });
+        where_clause = sql::and_where(&where_clause, &condition);
+        order_clause = sql::order_clause(&Some(keys));
+    }
+    let mut from = table.clone();
+    if let Some(text) = &query.text_match {
+        from = fts_match_source(&table);
+        where_params.insert(0, json!(text));
+        if select_clause == "*" {
+            select_clause = format!("{}.*", table);
+        }
+        if order_clause.is_empty() {
+            order_clause = "ORDER BY fts.fts_rank".to_string();
+        }
+    }
 
-    let select_clause = build_select_clause(&query.select);
-    let mut sql = format!("SELECT {} FROM {}", select_clause, table);
+    let mut sql = format!("SELECT {} FROM {}", select_clause, from);
     if !where_clause.is_empty() {
         sql.push(' ');
         sql.push_str(&where_clause);
@@ -361,6 +396,9 @@ fn do_query(conn: &Connection, query: StorageQuery) -> StorageResult<Vec<DataRec
     let mut stmt = match conn.prepare(&sql) {
         Ok(s) => s,
         Err(e) => {
+            if query.text_match.is_some() && e.to_string().contains("_fts") {
+                return StorageResult::err(fts_missing(&query.collection));
+            }
             // Table doesn't exist → empty results (not an error)
             if e.to_string().contains("no such table") {
                 return StorageResult::ok(Vec::new());
@@ -388,11 +426,145 @@ fn do_query(conn: &Connection, query: StorageQuery) -> StorageResult<Vec<DataRec
     }
 }
 
-fn do_count(conn: &Connection, query: StorageQuery) -> StorageResult<usize> {
+/// Decode a stored vector: JSON array text (how arrays are written) or a raw
+/// little-endian f32 blob.
+fn decode_vector(value: rusqlite::types::ValueRef) -> Option<Vec<f32>> {
+    match value {
+        rusqlite::types::ValueRef::Text(s) => serde_json::from_slice(s).ok(),
+        rusqlite::types::ValueRef::Blob(b) if b.len() % 4 == 0 => Some(
+            b.chunks_exact(4)
+                .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Heap entry for KNN; ordered by score so the heap root is the weakest kept row.
+struct ScoredRow {
+    score: f32,
+    id: String,
+}
+
+impl PartialEq for ScoredRow {
+    fn eq(&self, other: &Self) -> bool {
+        self.score.total_cmp(&other.score).is_eq()
+    }
+}
+
+impl Eq for ScoredRow {}
+
+impl PartialOrd for ScoredRow {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScoredRow {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score.total_cmp(&other.score)
+    }
+}
+
+/// Brute-force KNN inside the reader: scan only (id, vector) for rows matching
+/// the filter, score in Rust keeping the best `top_k` in a min-heap, then load
+/// just those rows. Rows whose vector is missing, unparseable, or of another
+/// dimension are skipped.
+fn do_vector_query(
+    conn: &Connection,
+    query: &StorageQuery,
+    knn: &VectorQuery,
+) -> StorageResult<Vec<DataRecord>> {
+    if knn.query_vector.is_empty() {
+        return StorageResult::err("queryVector must not be empty");
+    }
+    if knn.top_k == 0 {
+        return StorageResult::ok(Vec::new());
+    }
+
     let table = naming::to_table_name(&query.collection);
+    let column = naming::to_snake_case(&knn.vector_field);
     let (where_clause, where_params) = build_where_clause(&query.filter);
+    let not_null = format!("{} IS NOT NULL", column);
+    let where_sql = if where_clause.is_empty() {
+        format!("WHERE {}", not_null)
+    } else {
+        format!("{} AND {}", where_clause, not_null)
+    };
+    let sql = format!("SELECT id, {} FROM {} {}", column, table, where_sql);
+
+    let mut stmt = match conn.prepare(&sql) {
+        Ok(s) => s,
+        Err(e) => {
+            if e.to_string().contains("no such table") {
+                return StorageResult::ok(Vec::new());
+            }
+            return StorageResult::err(format!("Prepare failed: {}", e));
+        }
+    };
+    let params: Vec<Box<dyn rusqlite::ToSql>> =
+        where_params.iter().map(value_to_sql_boxed).collect();
+    let params_ref: Vec<&dyn rusqlite::ToSql> = params.iter().map(|b| b.as_ref()).collect();
+
+    let mut rows = match stmt.query(params_ref.as_slice()) {
+        Ok(r) => r,
+        Err(e) => return StorageResult::err(format!("Query failed: {}", e)),
+    };
+
+    let mut heap: BinaryHeap<Reverse<ScoredRow>> = BinaryHeap::with_capacity(knn.top_k + 1);
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => break,
+            Err(e) => return StorageResult::err(format!("Row scan failed: {}", e)),
+        };
+        let Some(vector) = row.get_ref(1).ok().and_then(decode_vector) else {
+            continue;
+        };
+        if vector.len() != knn.query_vector.len() {
+            continue;
+        }
+        let score = similarity::cosine(&knn.query_vector, &vector);
+        if heap.len() == knn.top_k {
+            match heap.peek() {
+                Some(Reverse(worst)) if score > worst.score => {
+                    heap.pop();
+                }
+                _ => continue,
+            }
+        }
+        let id: String = match row.get(0) {
+            Ok(id) => id,
+            Err(e) => return StorageResult::err(format!("Row scan failed: {}", e)),
+        };
+        heap.push(Reverse(ScoredRow { score, id }));
+    }
+
+    // Ascending by Reverse = best first
+    let mut records = Vec::with_capacity(heap.len());
+    for Reverse(hit) in heap.into_sorted_vec() {
+        let r = do_read(conn, &query.collection, &hit.id);
+        let Some(mut record) = r.data else {
+            continue;
+        };
+        if let Value::Object(data) = &mut record.data {
+            data.insert(VECTOR_SCORE_KEY.to_string(), json!(hit.score));
+        }
+        records.push(record);
+    }
+    StorageResult::ok(records)
+}
 
-    let mut sql = format!("SELECT COUNT(*) FROM {}", table);
+fn do_count(conn: &Connection, query: StorageQuery) -> StorageResult<usize> {
+    let table = naming::to_table_name(&query.collection);
+    let (where_clause, mut where_params) = build_where_clause(&query.filter);
+
+    let mut from = table.clone();
+    if let Some(text) = &query.text_match {
+        from = fts_match_source(&table);
+        where_params.insert(0, json!(text));
+    }
+    let mut sql = format!("SELECT COUNT(*) FROM {}", from);
     if !where_clause.is_empty() {
         sql.push(' ');
         sql.push_str(&where_clause);
@@ -405,6 +577,9 @@ fn do_count(conn: &Connection, query: StorageQuery) -> StorageResult<usize> {
     match conn.query_row(&sql, params_ref.as_slice(), |row| row.get::<_, i64>(0)) {
         Ok(count) => StorageResult::ok(count as usize),
         Err(e) => {
+            if query.text_match.is_some() && e.to_string().contains("_fts") {
+                return StorageResult::err(fts_missing(&query.collection));
+            }
             // Table doesn't exist → count is 0 (not an error)
             if e.to_string().contains("no such table") {
                 return StorageResult::ok(0);
@@ -414,6 +589,83 @@ fn do_count(conn: &Connection, query: StorageQuery) -> StorageResult<usize> {
     }
 }
 
+/// `table` joined to the rows of `{table}_fts` matching the first bound
+/// parameter, with their bm25 rank as `fts.fts_rank` (lower is better)
+fn fts_match_source(table: &str) -> String {
+    format!(
+        "{0} JOIN (SELECT rowid AS fts_rowid, bm25({0}_fts) AS fts_rank \
+         FROM {0}_fts WHERE {0}_fts MATCH ?) fts ON fts.fts_rowid = {0}.rowid",
+        table
+    )
+}
+
+fn fts_missing(collection: &str) -> String {
+    format!(
+        "Full-text search is not enabled for {}; run ensure-fts first",
+        collection
+    )
+}
+
+/// FTS5 index `{table}_fts` over `fields`: an external-content table (the
+/// text stays in `table` only) kept in sync by insert/update/delete
+/// triggers. Rebuilt from the table's rows whenever the field list changes.
+fn do_ensure_fts(conn: &Connection, collection: &str, fields: &[String]) -> StorageResult<bool> {
+    if fields.is_empty() {
+        return StorageResult::err("Full-text index needs at least one field".to_string());
+    }
+    let table = naming::to_table_name(collection);
+    let fts = format!("{}_fts", table);
+    // Spliced into the DDL below, so only plain identifiers get through
+    let columns = match sql::field_columns("Full-text field", fields) {
+        Ok(columns) => columns,
+        Err(e) => return StorageResult::err(e),
+    };
+    match table_columns(conn, &fts) {
+        Ok(existing) if existing == columns => return StorageResult::ok(true),
+        Ok(_) => {}
+        Err(e) => return StorageResult::err(e),
+    }
+
+    let cols = columns.join(", ");
+    let prefixed = |prefix: &str| {
+        columns
+            .iter()
+            .map(|c| format!("{}.{}", prefix, c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let (new_cols, old_cols) = (prefixed("new"), prefixed("old"));
+    let ddl = format!(
+        "DROP TRIGGER IF EXISTS {fts}_ai;
+         DROP TRIGGER IF EXISTS {fts}_ad;
+         DROP TRIGGER IF EXISTS {fts}_au;
+         DROP TABLE IF EXISTS {fts};
+         CREATE VIRTUAL TABLE {fts} USING fts5({cols}, content='{table}', content_rowid='rowid');
+         CREATE TRIGGER {fts}_ai AFTER INSERT ON {table} BEGIN
+             INSERT INTO {fts}(rowid, {cols}) VALUES (new.rowid, {new_cols});
+         END;
+         CREATE TRIGGER {fts}_ad AFTER DELETE ON {table} BEGIN
+             INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.rowid, {old_cols});
+         END;
+         CREATE TRIGGER {fts}_au AFTER UPDATE ON {table} BEGIN
+             INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.rowid, {old_cols});
+             INSERT INTO {fts}(rowid, {cols}) VALUES (new.rowid, {new_cols});
+         END;
+         INSERT INTO {fts}({fts}) VALUES ('rebuild');"
+    );
+
+    let result = conn
+        .unchecked_transaction()
+        .and_then(|tx| tx.execute_batch(&ddl).and_then(|_| tx.commit()));
+    match result {
+        Ok(_) => {
+            clog_info!("Full-text index {} over {}", fts, cols);
+            StorageResult::ok(true)
+        }
+        Err(e) => StorageResult::err(format!("Create full-text index failed: {}", e)),
+    }
+}
+
 fn do_update(
     conn: &Connection,
     collection: &str,
@@ -478,9 +730,154 @@ fn do_delete(conn: &Connection, collection: &str, id: &UUID) -> StorageResult<bo
     }
 }
 
+/// Apply one write operation inside an open transaction. Reads are rejected:
+/// they wouldn't see the transaction's own writes from the caller's view.
+fn do_transaction_op(conn: &Connection, op: BatchOperation) -> Result<Value, String> {
+    match op.operation_type {
+        BatchOperationType::Create => {
+            let (Some(id), Some(data)) = (op.id, op.data) else {
+                return Err("Missing id or data".to_string());
+            };
+            let record = DataRecord {
+                id,
+                collection: op.collection,
+                data,
+                metadata: RecordMetadata::default(),
+            };
+            let r = do_create(conn, record);
+            if !r.success {
+                return Err(r.error.unwrap_or_else(|| "Create failed".to_string()));
+            }
+            Ok(json!({"success": true, "id": r.data.map(|rec| rec.id)}))
+        }
+        BatchOperationType::Update => {
+            let (Some(id), Some(data)) = (op.id, op.data) else {
+                return Err("Missing id or data".to_string());
+            };
+            let r = do_update(conn, &op.collection, &id, data, true);
+            if !r.success {
+                return Err(r.error.unwrap_or_else(|| "Update failed".to_string()));
+            }
+            Ok(json!({"success": true, "id": id}))
+        }
+        BatchOperationType::Delete => {
+            let Some(id) = op.id else {
+                return Err("Missing id".to_string());
+            };
+            let r = do_delete(conn, &op.collection, &id);
+            if !r.success {
+                return Err(r.error.unwrap_or_else(|| "Delete failed".to_string()));
+            }
+            Ok(json!({"success": true, "id": id, "deleted": r.data.unwrap_or(false)}))
+        }
+        BatchOperationType::Read => Err("Read is not allowed inside a transaction".to_string()),
+    }
+}
+
+/// Insert all records in one transaction (one fsync instead of N). A failed
+/// INSERT only undoes its own statement, so the rest can still commit.
+fn do_create_many(
+    conn: &Connection,
+    records: Vec<DataRecord>,
+    stop_on_error: bool,
+) -> StorageResult<Vec<Value>> {
+    let tx = match conn.unchecked_transaction() {
+        Ok(tx) => tx,
+        Err(e) => return StorageResult::err(format!("Begin transaction failed: {}", e)),
+    };
+    let mut results = Vec::with_capacity(records.len());
+    for (index, record) in records.into_iter().enumerate() {
+        let id = record.id.clone();
+        let r = do_create(&tx, record);
+        if r.success {
+            results.push(json!({"index": index, "success": true, "id": id}));
+        } else if stop_on_error {
+            // Dropping `tx` rolls back the records already inserted
+            return StorageResult::err(format!(
+                "Record {} failed, batch rolled back: {}",
+                index,
+                r.error.unwrap_or_default()
+            ));
+        } else {
+            results.push(json!({"index": index, "success": false, "error": r.error}));
+        }
+    }
+    match tx.commit() {
+        Ok(()) => StorageResult::ok(results),
+        Err(e) => StorageResult::err(format!("Commit failed: {}", e)),
+    }
+}
+
+/// Run all operations in one SQLite transaction; the first failure rolls back.
+fn do_transaction(conn: &Connection, operations: Vec<BatchOperation>) -> StorageResult<Vec<Value>> {
+    let tx = match conn.unchecked_transaction() {
+        Ok(tx) => tx,
+        Err(e) => return StorageResult::err(format!("Begin transaction failed: {}", e)),
+    };
+    let mut results = Vec::with_capacity(operations.len());
+    for (index, op) in operations.into_iter().enumerate() {
+        match do_transaction_op(&tx, op) {
+            Ok(result) => results.push(result),
+            Err(e) => {
+                // Dropping `tx` rolls back
+                return StorageResult::err(format!(
+                    "Operation {} failed, rolled back: {}",
+                    index, e
+                ));
+            }
+        }
+    }
+    match tx.commit() {
+        Ok(()) => StorageResult::ok(results),
+        Err(e) => StorageResult::err(format!("Commit failed: {}", e)),
+    }
+}
+
+/// Column type for a declared field
+fn sqlite_column_type(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::String => "TEXT",
+        FieldType::Number => "REAL",
+        FieldType::Boolean => "INTEGER",
+        FieldType::Date => "TEXT",
+        FieldType::Json => "TEXT",
+        FieldType::Uuid => "TEXT",
+        // JSON array text, so plain reads still hydrate to an array
+        FieldType::Vector => "TEXT",
+    }
+}
+
+/// Value existing rows get when a NOT NULL column is added to their table
+fn sqlite_column_default(field_type: &FieldType) -> &'static str {
+    match field_type {
+        FieldType::Number | FieldType::Boolean => "0",
+        FieldType::Json => "'{}'",
+        FieldType::Vector => "'[]'",
+        FieldType::String | FieldType::Date | FieldType::Uuid => "''",
+    }
+}
+
+/// Column names of `table`, empty if it doesn't exist
+fn table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, String> {
+    conn.prepare("SELECT name FROM pragma_table_info(?1)")
+        .and_then(|mut stmt| stmt.query_map([table], |row| row.get(0))?.collect())
+        .map_err(|e| format!("Read columns of {} failed: {}", table, e))
+}
+
 fn do_ensure_schema(conn: &Connection, schema: CollectionSchema) -> StorageResult<bool> {
     let table = naming::to_table_name(&schema.collection);
 
+    let existing = match table_columns(conn, &table) {
+        Ok(columns) => columns,
+        Err(e) => return StorageResult::err(e),
+    };
+    if !existing.is_empty() {
+        if let Err(e) = migrate_columns(conn, &table, &schema, existing) {
+            return StorageResult::err(e);
+        }
+        return do_ensure_indexes(conn, &table, &schema);
+    }
+
     let mut columns = vec![
         "id TEXT PRIMARY KEY".to_string(),
         "created_at TEXT NOT NULL".to_string(),
@@ -490,14 +887,7 @@ fn do_ensure_schema(conn: &Connection, schema: CollectionSchema) -> StorageResul
 
     for field in &schema.fields {
         let col_name = naming::to_snake_case(&field.name);
-        let col_type = match field.field_type {
-            super::types::FieldType::String => "TEXT",
-            super::types::FieldType::Number => "REAL",
-            super::types::FieldType::Boolean => "INTEGER",
-            super::types::FieldType::Date => "TEXT",
-            super::types::FieldType::Json => "TEXT",
-            super::types::FieldType::Uuid => "TEXT",
-        };
+        let col_type = sqlite_column_type(&field.field_type);
 
         let mut col_def = format!("{} {}", col_name, col_type);
         if !field.nullable {
@@ -519,6 +909,112 @@ fn do_ensure_schema(conn: &Connection, schema: CollectionSchema) -> StorageResul
         return StorageResult::err(format!("Create table failed: {}", e));
     }
 
+    do_ensure_indexes(conn, &table, &schema)
+}
+
+/// Bring an existing table in line with its declared schema: opt-in renames
+/// first, then declared columns it lacks are added, then (opt-in) undeclared
+/// columns are dropped. One transaction, so a failed step changes nothing.
+fn migrate_columns(
+    conn: &Connection,
+    table: &str,
+    schema: &CollectionSchema,
+    mut existing: Vec<String>,
+) -> Result<(), String> {
+    let migration = schema.migration.clone().unwrap_or_default();
+    let tx = conn
+        .unchecked_transaction()
+        .map_err(|e| format!("Migrate {} failed: {}", table, e))?;
+    let exec = |sql: &str| {
+        tx.execute(sql, [])
+            .map(|_| ())
+            .map_err(|e| format!("Migrate {} failed: {} ({})", table, e, sql))
+    };
+
+    for (from, to) in &migration.rename_fields {
+        let from = naming::to_snake_case(from);
+        let to = naming::to_snake_case(to);
+        // Nothing to do if an earlier run already renamed it
+        if !existing.contains(&from) || existing.contains(&to) {
+            continue;
+        }
+        exec(&format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            table, from, to
+        ))?;
+        clog_info!(
+            "Schema migration: renamed column {}.{} to {}",
+            table,
+            from,
+            to
+        );
+        for column in existing.iter_mut().filter(|c| **c == from) {
+            *column = to.clone();
+        }
+    }
+
+    for field in &schema.fields {
+        let column = naming::to_snake_case(&field.name);
+        if existing.contains(&column) {
+            continue;
+        }
+        let mut col_def = format!("{} {}", column, sqlite_column_type(&field.field_type));
+        if !field.nullable {
+            col_def.push_str(" NOT NULL DEFAULT ");
+            col_def.push_str(sqlite_column_default(&field.field_type));
+        }
+        exec(&format!("ALTER TABLE {} ADD COLUMN {}", table, col_def))?;
+        if field.unique {
+            // SQLite can't add a UNIQUE column; a unique index enforces the same
+            exec(&format!(
+                "CREATE UNIQUE INDEX IF NOT EXISTS idx_{0}_{1}_unique ON {0} ({1})",
+                table, column
+            ))?;
+        }
+        clog_info!("Schema migration: added column {}.{}", table, col_def);
+        existing.push(column);
+    }
+
+    if migration.drop_undeclared {
+        let declared: Vec<String> = schema
+            .fields
+            .iter()
+            .map(|f| naming::to_snake_case(&f.name))
+            .collect();
+        for column in &existing {
+            if METADATA_KEYS.contains(&column.as_str()) || declared.contains(column) {
+                continue;
+            }
+            // SQLite refuses to drop an indexed column, so its indexes go first
+            let indexes: Vec<String> = tx
+                .prepare(
+                    "SELECT DISTINCT il.name FROM pragma_index_list(?1) il \
+                     JOIN pragma_index_info(il.name) ii \
+                     WHERE il.origin = 'c' AND ii.name = ?2",
+                )
+                .and_then(|mut stmt| {
+                    stmt.query_map([table, column.as_str()], |row| row.get(0))?
+                        .collect()
+                })
+                .map_err(|e| format!("Migrate {} failed: {}", table, e))?;
+            for index in &indexes {
+                exec(&format!("DROP INDEX {}", index))?;
+            }
+            exec(&format!("ALTER TABLE {} DROP COLUMN {}", table, column))?;
+            clog_warn!("Schema migration: dropped column {}.{}", table, column);
+        }
+    }
+
+    tx.commit()
+        .map_err(|e| format!("Migrate {} failed: {}", table, e))
+}
+
+/// Declared single-field and composite indexes (CREATE INDEX IF NOT EXISTS)
+fn do_ensure_indexes(
+    conn: &Connection,
+    table: &str,
+    schema: &CollectionSchema,
+) -> StorageResult<bool> {
     // Create single-field indexes from schema
     for field in &schema.fields {
         if field.indexed {
@@ -536,31 +1032,117 @@ fn do_ensure_schema(conn: &Connection, schema: CollectionSchema) -> StorageResul
 
     // Create composite indexes from schema
     for index in &schema.indexes {
-        let cols: Vec<String> = index
-            .fields
-            .iter()
-            .map(|f| naming::to_snake_case(f))
-            .collect();
-        let unique = if index.unique { "UNIQUE " } else { "" };
-        let idx_sql = format!(
-            "CREATE {}INDEX IF NOT EXISTS {} ON {} ({})",
-            unique,
-            naming::to_snake_case(&index.name),
-            table,
-            cols.join(", ")
-        );
-        if let Err(e) = conn.execute(&idx_sql, []) {
-            return StorageResult::err(format!("Create composite index failed: {}", e));
+        let result = do_create_index(conn, &schema.collection, index);
+        if !result.success {
+            return result;
         }
     }
 
     StorageResult::ok(true)
 }
 
+fn do_create_index(
+    conn: &Connection,
+    collection: &str,
+    index: &SchemaIndex,
+) -> StorageResult<bool> {
+    let table = naming::to_table_name(collection);
+    let (name, columns) = match sql::index_columns(&table, index) {
+        Ok(ic) => ic,
+        Err(e) => return StorageResult::err(e),
+    };
+    // Index names are database-wide; IF NOT EXISTS would keep any index
+    // already holding the name, whatever it covers
+    match find_index(conn, &name) {
+        Ok(Some(existing)) => {
+            let checked =
+                sql::check_existing_index(&name, &table, &columns, index.unique, &existing);
+            return match checked {
+                Ok(()) => StorageResult::ok(true),
+                Err(e) => StorageResult::err(e),
+            };
+        }
+        Ok(None) => {}
+        Err(e) => return StorageResult::err(format!("Create index failed: {}", e)),
+    }
+    let idx_sql = match sql::create_index(&table, &table, index) {
+        Ok(s) => s,
+        Err(e) => return StorageResult::err(e),
+    };
+    match conn.execute(&idx_sql, []) {
+        Ok(_) => StorageResult::ok(true),
+        Err(e) => StorageResult::err(format!("Create index failed: {}", e)),
+    }
+}
+
+/// The index called `name`, on whichever table
+fn find_index(conn: &Connection, name: &str) -> rusqlite::Result<Option<sql::ExistingIndex>> {
+    let sql = "SELECT m.tbl_name, il.\"unique\", ii.name \
+               FROM sqlite_master m \
+               JOIN pragma_index_list(m.tbl_name) il ON il.name = m.name \
+               JOIN pragma_index_info(m.name) ii \
+               WHERE m.type = 'index' AND m.name = ?1 \
+               ORDER BY ii.seqno";
+    let mut stmt = conn.prepare(sql)?;
+    let rows = stmt
+        .query_map([name], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, bool>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let Some((table, unique, _)) = rows.first().cloned() else {
+        return Ok(None);
+    };
+    Ok(Some(sql::ExistingIndex {
+        table,
+        unique,
+        columns: rows.into_iter().map(|(_, _, column)| column).collect(),
+    }))
+}
+
+/// Indexes created with CREATE INDEX (not the automatic ones behind
+/// PRIMARY KEY / UNIQUE columns). A SELECT over the pragma functions, not a
+/// bare PRAGMA, so reader connections pick up indexes created by the writer.
+fn do_list_indexes(conn: &Connection, collection: &str) -> StorageResult<Vec<SchemaIndex>> {
+    let table = naming::to_table_name(collection);
+    let sql = "SELECT il.name, il.\"unique\", ii.name \
+               FROM pragma_index_list(?1) il JOIN pragma_index_info(il.name) ii \
+               WHERE il.origin = 'c' \
+               ORDER BY il.name, ii.seqno";
+    let rows: rusqlite::Result<Vec<(String, bool, String)>> =
+        conn.prepare(sql).and_then(|mut stmt| {
+            stmt.query_map([&table], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect()
+        });
+    let rows = match rows {
+        Ok(r) => r,
+        Err(e) => return StorageResult::err(format!("List indexes failed: {}", e)),
+    };
+
+    let mut indexes: Vec<SchemaIndex> = Vec::new();
+    for (name, unique, column) in rows {
+        let field = naming::to_camel_case(&column);
+        match indexes.last_mut() {
+            Some(index) if index.name == name => index.fields.push(field),
+            _ => indexes.push(SchemaIndex {
+                name,
+                fields: vec![field],
+                unique,
+            }),
+        }
+    }
+    StorageResult::ok(indexes)
+}
+
 fn do_list_collections(conn: &Connection) -> StorageResult<Vec<String>> {
-    let mut stmt = match conn
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'")
-    {
+    // table_list types FTS tables 'virtual' and their storage 'shadow'
+    let mut stmt = match conn.prepare(
+        "SELECT name FROM pragma_table_list \
+         WHERE schema = 'main' AND type = 'table' AND name NOT LIKE 'sqlite_%'",
+    ) {
         Ok(s) => s,
         Err(e) => return StorageResult::err(format!("Prepare failed: {}", e)),
     };
@@ -609,7 +1191,26 @@ fn do_clear_all(conn: &Connection) -> StorageResult<ClearAllResult> {
 
 fn do_cleanup(conn: &Connection) -> Result<(), String> {
     conn.execute_batch("VACUUM; ANALYZE;")
-        .map_err(|e| format!("Cleanup failed: {}", e))
+        .map_err(|e| format!("Cleanup failed: {}", e))?;
+    // VACUUM may renumber the implicit rowids of `id TEXT PRIMARY KEY`
+    // tables, which their external-content FTS indexes are keyed on
+    rebuild_fts_indexes(conn).map_err(|e| format!("Cleanup failed: {}", e))
+}
+
+/// Re-read every `do_ensure_fts` index from its content table
+fn rebuild_fts_indexes(conn: &Connection) -> rusqlite::Result<()> {
+    let fts_tables: Vec<String> = conn
+        .prepare(
+            "SELECT name FROM sqlite_master \
+             WHERE type = 'table' AND name LIKE '%\\_fts' ESCAPE '\\' \
+             AND sql LIKE 'CREATE VIRTUAL TABLE%fts5%'",
+        )?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for fts in fts_tables {
+        conn.execute_batch(&format!("INSERT INTO {fts}({fts}) VALUES ('rebuild');"))?;
+    }
+    Ok(())
 }
 
 // ─── Helper Functions ────────────────────────────────────────────────────────
@@ -806,49 +1407,6 @@ fn build_where_clause(filter: &Option<HashMap<String, FieldFilter>>) -> (String,
     }
 }
 
-/// Build SELECT clause from optional column projection.
-/// Converts camelCase field names to snake_case for SQL.
-/// Always includes id, created_at, updated_at, version (metadata columns).
-fn build_select_clause(select: &Option<Vec<String>>) -> String {
-    match select {
-        Some(cols) if !cols.is_empty() => {
-            let mut selected: Vec<String> = vec![
-                "id".to_string(),
-                "created_at".to_string(),
-                "updated_at".to_string(),
-                "version".to_string(),
-            ];
-            for col in cols {
-                let snake = naming::to_snake_case(col);
-                if snake != "id" && snake != "created_at" && snake != "updated_at" && snake != "version" {
-                    selected.push(snake);
-                }
-            }
-            selected.join(", ")
-        }
-        _ => "*".to_string(),
-    }
-}
-
-fn build_order_clause(sort: &Option<Vec<super::query::SortSpec>>) -> String {
-    if let Some(sorts) = sort {
-        if !sorts.is_empty() {
-            let parts: Vec<_> = sorts
-                .iter()
-                .map(|s| {
-                    let dir = match s.direction {
-                        SortDirection::Asc => "ASC",
-                        SortDirection::Desc => "DESC",
-                    };
-                    format!("{} {}", naming::to_snake_case(&s.field), dir)
-                })
-                .collect();
-            return format!("ORDER BY {}", parts.join(", "));
-        }
-    }
-    String::new()
-}
-
 // ─── Async Trait Implementation ──────────────────────────────────────────────
 
 #[async_trait]
@@ -861,8 +1419,9 @@ impl StorageAdapter for SqliteAdapter {
         AdapterCapabilities {
             supports_transactions: true,
             supports_indexing: true,
-            supports_full_text_search: false,
-            supports_vector_search: false,
+            supports_full_text_search: true,
+            // Brute-force KNN over vector fields (`StorageQuery::vector`)
+            supports_vector_search: true,
             supports_joins: true,
             supports_batch: true,
             max_record_size: 1_000_000_000,
@@ -1101,6 +1660,42 @@ impl StorageAdapter for SqliteAdapter {
         StorageResult::ok(results)
     }
 
+    async fn create_many(
+        &self,
+        records: Vec<DataRecord>,
+        stop_on_error: bool,
+    ) -> StorageResult<Vec<Value>> {
+        let conn = match self.get_writer() {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(e),
+        };
+        let pressure = self.last_pressure_check.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            apply_memory_pressure(&conn, &pressure);
+            do_create_many(&conn, records, stop_on_error)
+        })
+        .await
+        .unwrap_or_else(|e| StorageResult::err(format!("spawn_blocking failed: {}", e)))
+    }
+
+    async fn transaction(&self, operations: Vec<BatchOperation>) -> StorageResult<Vec<Value>> {
+        let conn = match self.get_writer() {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(e),
+        };
+        let pressure = self.last_pressure_check.clone();
+        tokio::task::spawn_blocking(move || {
+            // Holding the writer lock for the whole transaction keeps other
+            // writes from interleaving with (and committing inside) it
+            let conn = conn.lock().unwrap();
+            apply_memory_pressure(&conn, &pressure);
+            do_transaction(&conn, operations)
+        })
+        .await
+        .unwrap_or_else(|e| StorageResult::err(format!("spawn_blocking failed: {}", e)))
+    }
+
     async fn ensure_schema(&self, schema: CollectionSchema) -> StorageResult<bool> {
         let conn = match self.get_writer() {
             Ok(c) => c,
@@ -1114,6 +1709,48 @@ impl StorageAdapter for SqliteAdapter {
         .unwrap_or_else(|e| StorageResult::err(format!("spawn_blocking failed: {}", e)))
     }
 
+    async fn create_index(&self, collection: &str, index: SchemaIndex) -> StorageResult<bool> {
+        let conn = match self.get_writer() {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(e),
+        };
+        let collection = collection.to_string();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            do_create_index(&conn, &collection, &index)
+        })
+        .await
+        .unwrap_or_else(|e| StorageResult::err(format!("spawn_blocking failed: {}", e)))
+    }
+
+    async fn list_indexes(&self, collection: &str) -> StorageResult<Vec<SchemaIndex>> {
+        let conn = match self.get_reader() {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(e),
+        };
+        let collection = collection.to_string();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            do_list_indexes(&conn, &collection)
+        })
+        .await
+        .unwrap_or_else(|e| StorageResult::err(format!("spawn_blocking failed: {}", e)))
+    }
+
+    async fn ensure_fts(&self, collection: &str, fields: Vec<String>) -> StorageResult<bool> {
+        let conn = match self.get_writer() {
+            Ok(c) => c,
+            Err(e) => return StorageResult::err(e),
+        };
+        let collection = collection.to_string();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().unwrap();
+            do_ensure_fts(&conn, &collection, &fields)
+        })
+        .await
+        .unwrap_or_else(|e| StorageResult::err(format!("spawn_blocking failed: {}", e)))
+    }
+
     async fn truncate(&self, collection: &str) -> StorageResult<bool> {
         let conn = match self.get_writer() {
             Ok(c) => c,
@@ -1189,6 +1826,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
@@ -1208,6 +1846,296 @@ mod tests {
         assert_eq!(data.data["name"], "Joel");
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_ensure_schema_migrates_columns() {
+        use super::super::types::{SchemaField, SchemaMigration};
+
+        let (adapter, _dir) = setup_adapter().await;
+        let field = |name: &str, field_type, indexed, nullable| SchemaField {
+            name: name.to_string(),
+            field_type,
+            indexed,
+            unique: false,
+            nullable,
+            max_length: None,
+        };
+        let schema = |fields, migration| CollectionSchema {
+            collection: "users".to_string(),
+            fields,
+            indexes: vec![],
+            migration,
+        };
+
+        let result = adapter
+            .ensure_schema(schema(
+                vec![field("name", FieldType::String, false, false)],
+                None,
+            ))
+            .await;
+        assert!(result.success);
+        let record = DataRecord {
+            id: "u1".to_string(),
+            collection: "users".to_string(),
+            data: json!({"name": "Joel"}),
+            metadata: RecordMetadata::default(),
+        };
+        assert!(adapter.create(record).await.success);
+
+        // New fields are added to the existing table; old rows get defaults
+        let result = adapter
+            .ensure_schema(schema(
+                vec![
+                    field("name", FieldType::String, false, false),
+                    field("email", FieldType::String, true, true),
+                    field("loginCount", FieldType::Number, false, false),
+                ],
+                None,
+            ))
+            .await;
+        assert!(result.success, "{:?}", result.error);
+        let read = adapter.read("users", &"u1".to_string()).await.data.unwrap();
+        assert_eq!(read.data["loginCount"], 0.0);
+        assert!(read.data["email"].is_null());
+
+        // Undeclared columns survive unless dropping is asked for
+        let renamed = vec![
+            field("displayName", FieldType::String, false, false),
+            field("loginCount", FieldType::Number, false, false),
+        ];
+        // Chained renames apply in the order given
+        let migration = SchemaMigration {
+            rename_fields: vec![
+                ("name".to_string(), "fullName".to_string()),
+                ("fullName".to_string(), "displayName".to_string()),
+            ],
+            drop_undeclared: false,
+        };
+        let result = adapter
+            .ensure_schema(schema(renamed.clone(), Some(migration.clone())))
+            .await;
+        assert!(result.success, "{:?}", result.error);
+        let read = adapter.read("users", &"u1".to_string()).await.data.unwrap();
+        assert_eq!(read.data["displayName"], "Joel");
+        assert!(read.data.get("email").is_some());
+
+        // Dropping takes the indexed email column and its index with it
+        let migration = SchemaMigration {
+            drop_undeclared: true,
+            ..migration
+        };
+        let result = adapter
+            .ensure_schema(schema(renamed, Some(migration)))
+            .await;
+        assert!(result.success, "{:?}", result.error);
+        let read = adapter.read("users", &"u1".to_string()).await.data.unwrap();
+        assert!(read.data.get("email").is_none());
+        assert_eq!(read.data["displayName"], "Joel");
+        assert!(adapter.list_indexes("users").await.data.unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_full_text_search() {
+        let (adapter, _dir) = setup_adapter().await;
+        let notes = [
+            ("n1", "Borrow checker", "Rust lifetimes explained"),
+            ("n2", "Rust rust rust", "Everything about rust"),
+            ("n3", "Gardening", "Tomatoes need sun"),
+        ];
+        for (id, title, body) in notes {
+            let record = DataRecord {
+                id: id.to_string(),
+                collection: "notes".to_string(),
+                data: json!({"title": title, "noteBody": body}),
+                metadata: RecordMetadata::default(),
+            };
+            assert!(adapter.create(record).await.success);
+        }
+        let search = |text: &str| StorageQuery {
+            collection: "notes".to_string(),
+            text_match: Some(text.to_string()),
+            ..Default::default()
+        };
+        let ids = |result: StorageResult<Vec<DataRecord>>| -> Vec<String> {
+            assert!(result.success, "{:?}", result.error);
+            result.data.unwrap().into_iter().map(|r| r.id).collect()
+        };
+
+        let result = adapter.query(search("rust")).await;
+        assert!(result.error.unwrap().contains("ensure-fts"));
+
+        // Fields end up in DDL; anything but a plain identifier is refused
+        let malicious = vec!["title) ; DROP TABLE notes; --".to_string()];
+        let result = adapter.ensure_fts("notes", malicious).await;
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("may only contain"));
+        let all = StorageQuery {
+            collection: "notes".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(adapter.count(all).await.data, Some(3));
+
+        let fields = vec!["title".to_string(), "noteBody".to_string()];
+        assert!(adapter.ensure_fts("notes", fields.clone()).await.success);
+        assert!(adapter.ensure_fts("notes", fields).await.success);
+
+        // Existing rows are indexed; the denser match ranks first
+        assert_eq!(ids(adapter.query(search("rust")).await), vec!["n2", "n1"]);
+        assert_eq!(adapter.count(search("rust")).await.data, Some(2));
+
+        // Triggers keep the index in step with writes
+        let update = adapter
+            .update(
+                "notes",
+                &"n3".to_string(),
+                json!({"noteBody": "Rust on the tomato cage"}),
+                false,
+            )
+            .await;
+        assert!(update.success, "{:?}", update.error);
+        assert!(adapter.delete("notes", &"n2".to_string()).await.success);
+        let mut found = ids(adapter.query(search("rust")).await);
+        found.sort();
+        assert_eq!(found, vec!["n1", "n3"]);
+        assert!(ids(adapter.query(search("tomatoes")).await).is_empty());
+
+        // Still in step once VACUUM has had a chance to renumber rowids
+        adapter.cleanup().await.unwrap();
+        let mut found = ids(adapter.query(search("rust")).await);
+        found.sort();
+        assert_eq!(found, vec!["n1", "n3"]);
+
+        // Filters and FTS5 syntax combine with the match
+        let mut query = search("rust OR gardening");
+        query.filter = Some([("title".to_string(), FieldFilter::Value(json!("Gardening")))].into());
+        assert_eq!(ids(adapter.query(query).await), vec!["n3"]);
+
+        // The index's tables aren't collections
+        let collections = adapter.list_collections().await.data.unwrap();
+        assert_eq!(collections, vec!["notes"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_transaction_is_atomic() {
+        let (adapter, _dir) = setup_adapter().await;
+        let op = |kind, id: &str, data: Option<Value>| BatchOperation {
+            operation_type: kind,
+            collection: "accounts".to_string(),
+            id: Some(id.to_string()),
+            data,
+        };
+
+        let committed = adapter
+            .transaction(vec![
+                op(
+                    BatchOperationType::Create,
+                    "a",
+                    Some(json!({"balance": 10})),
+                ),
+                op(
+                    BatchOperationType::Create,
+                    "b",
+                    Some(json!({"balance": 20})),
+                ),
+                op(BatchOperationType::Update, "a", Some(json!({"balance": 5}))),
+            ])
+            .await;
+        assert!(committed.success, "Commit failed: {:?}", committed.error);
+        assert_eq!(committed.data.unwrap().len(), 3);
+        let a = adapter
+            .read("accounts", &"a".to_string())
+            .await
+            .data
+            .unwrap();
+        assert_eq!(a.data["balance"], 5);
+
+        // Second op updates a missing row → whole transaction rolls back
+        let failed = adapter
+            .transaction(vec![
+                op(BatchOperationType::Delete, "b", None),
+                op(
+                    BatchOperationType::Update,
+                    "missing",
+                    Some(json!({"balance": 1})),
+                ),
+            ])
+            .await;
+        assert!(!failed.success);
+        assert!(failed.error.unwrap().contains("Operation 1"));
+        assert!(adapter.read("accounts", &"b".to_string()).await.success);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_create_many_reports_per_record_errors() {
+        let (adapter, _dir) = setup_adapter().await;
+        let rec = |id: &str| DataRecord {
+            id: id.to_string(),
+            collection: "bulk".to_string(),
+            data: json!({"n": 1}),
+            metadata: RecordMetadata::default(),
+        };
+
+        // Duplicate id fails on its own; the others still commit
+        let r = adapter
+            .create_many(vec![rec("x"), rec("x"), rec("y")], false)
+            .await;
+        assert!(r.success, "{:?}", r.error);
+        let results = r.data.unwrap();
+        assert_eq!(results[0]["success"], true);
+        assert_eq!(results[1]["success"], false);
+        assert_eq!(results[2]["id"], "y");
+        assert!(adapter.read("bulk", &"y".to_string()).await.success);
+
+        // stop_on_error is all-or-nothing
+        let r = adapter.create_many(vec![rec("z"), rec("x")], true).await;
+        assert!(!r.success);
+        assert!(!adapter.read("bulk", &"z".to_string()).await.success);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_vector_knn_query() {
+        let (adapter, _dir) = setup_adapter().await;
+        assert!(adapter.capabilities().supports_vector_search);
+        let docs = [
+            ("exact", "a", json!([1.0, 0.0])),
+            ("close", "a", json!([0.9, 0.1])),
+            ("far", "a", json!([0.0, 1.0])),
+            ("other-kind", "b", json!([1.0, 0.0])),
+            ("wrong-dims", "a", json!([1.0, 0.0, 0.0])),
+        ];
+        for (id, kind, embedding) in docs {
+            let record = DataRecord {
+                id: id.to_string(),
+                collection: "chunks".to_string(),
+                data: json!({"kind": kind, "embedding": embedding}),
+                metadata: RecordMetadata::default(),
+            };
+            assert!(adapter.create(record).await.success);
+        }
+
+        let mut filter = HashMap::new();
+        filter.insert("kind".to_string(), FieldFilter::Value(json!("a")));
+        let result = adapter
+            .query(StorageQuery {
+                collection: "chunks".to_string(),
+                filter: Some(filter),
+                vector: Some(VectorQuery {
+                    vector_field: "embedding".to_string(),
+                    query_vector: vec![1.0, 0.0],
+                    top_k: 2,
+                }),
+                ..Default::default()
+            })
+            .await;
+        assert!(result.success, "{:?}", result.error);
+        let records = result.data.unwrap();
+        let ids: Vec<&str> = records.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["exact", "close"]);
+        let top = records[0].data[VECTOR_SCORE_KEY].as_f64().unwrap();
+        assert!((top - 1.0).abs() < 1e-6);
+        // Plain reads still hydrate the vector as an array
+        assert_eq!(records[0].data["embedding"], json!([1.0, 0.0]));
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
     async fn test_concurrent_reads() {
         let (adapter, _dir) = setup_adapter().await;
@@ -1225,6 +2153,7 @@ mod tests {
                     max_length: None,
                 }],
                 indexes: vec![],
+                migration: None,
             })
             .await;
 
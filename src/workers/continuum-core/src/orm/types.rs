@@ -31,6 +31,8 @@ pub enum FieldType {
     Date,
     Json,
     Uuid,
+    /// Embedding: array of numbers, usable as `StorageQuery::vector` field
+    Vector,
 }
 
 /// Schema field definition
@@ -73,6 +75,26 @@ pub struct CollectionSchema {
     pub fields: Vec<SchemaField>,
     #[serde(default)]
     pub indexes: Vec<SchemaIndex>,
+    /// Destructive changes to apply to an existing table (SQLite adapter;
+    /// Postgres rejects them)
+    #[ts(optional)]
+    pub migration: Option<SchemaMigration>,
+}
+
+/// Opt-in column changes for `ensure_schema`. Declared fields missing from
+/// an existing table are always added; renames and drops only happen when
+/// asked for here.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/orm/SchemaMigration.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaMigration {
+    /// (old field name, declared field name) pairs, applied in order before
+    /// columns are added, so chained renames run as listed
+    #[serde(default)]
+    pub rename_fields: Vec<(String, String)>,
+    /// Drop columns that are no longer declared (their data is lost)
+    #[serde(default)]
+    pub drop_undeclared: bool,
 }
 
 /// Record metadata - timestamps and versioning
@@ -157,7 +179,7 @@ impl<T> StorageResult<T> {
 }
 
 /// Result metadata for queries
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../../shared/generated/orm/ResultMetadata.ts")]
 #[serde(rename_all = "camelCase")]
 pub struct ResultMetadata {
@@ -167,6 +189,10 @@ pub struct ResultMetadata {
     pub query_time_ms: Option<u64>,
     #[ts(optional)]
     pub cache_hit: Option<bool>,
+    /// Keyset cursor for the next page (pass back as `afterCursor`);
+    /// absent on the last page
+    #[ts(optional)]
+    pub next_cursor: Option<String>,
 }
 
 /// Collection statistics
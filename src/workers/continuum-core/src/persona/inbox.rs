@@ -1,8 +1,71 @@
 use super::types::InboxMessage;
+use serde::Serialize;
+use serde_json::Value;
 use std::collections::BinaryHeap;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
 use uuid::Uuid;
 
+/// How far above the in-flight message's priority an incoming message must
+/// score to preempt it
+pub const DEFAULT_PREEMPT_MARGIN: f32 = 0.3;
+
+/// Yield flag for the in-flight message. Cognition checks `should_yield()`
+/// at safe checkpoints (between pipeline stages, between tool calls) and
+/// suspends the message with its progress when it is set.
+#[derive(Debug, Clone, Default)]
+pub struct PreemptSignal(Arc<AtomicBool>);
+
+impl PreemptSignal {
+    pub fn should_yield(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    fn raise(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+/// Message being processed right now
+struct InFlight {
+    message: InboxMessage,
+    signal: PreemptSignal,
+}
+
+/// A preempted message and the progress it saved when it yielded
+struct Suspended {
+    message: InboxMessage,
+    checkpoint: Value,
+}
+
+#[derive(Default)]
+struct Processing {
+    in_flight: Option<InFlight>,
+    /// Preempted messages, most recent last (resumed first)
+    suspended: Vec<Suspended>,
+}
+
+impl Processing {
+    fn start(&mut self, message: InboxMessage) {
+        self.in_flight = Some(InFlight {
+            message,
+            signal: PreemptSignal::default(),
+        });
+    }
+}
+
+/// Next message to process. `resumed` messages were preempted earlier and
+/// carry the checkpoint they yielded with, so work continues from there.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/persona/InboxNext.ts")]
+pub struct InboxNext {
+    pub message: InboxMessage,
+    pub resumed: bool,
+    #[ts(type = "any", optional)]
+    pub checkpoint: Option<Value>,
+}
+
 /// Concurrent persona inbox with priority queue
 ///
 /// Pattern: Simple synchronous priority queue with mutex
@@ -10,12 +73,21 @@ use uuid::Uuid;
 /// - dequeue() pops from heap (with lock)
 /// - No Tokio runtime required (safe to use from std::thread)
 ///
+/// Preemption: messages taken with next() or dequeue(), or handed over with
+/// begin() when they were scheduled elsewhere (the channel registry), are
+/// tracked as in flight. An incoming message scoring `preempt_margin` above
+/// the in-flight one raises its PreemptSignal; cognition yields at its next
+/// checkpoint via suspend(), the urgent message is processed, and next() then
+/// resumes the suspended one from its checkpoint instead of restarting it.
+///
 /// NOTE: This is a simpler implementation that doesn't require Tokio.
 /// For high-throughput async use cases, consider adding a Tokio-based
 /// variant with channels and spawned worker tasks.
 pub struct PersonaInbox {
     persona_id: Uuid,
     heap: Mutex<BinaryHeap<InboxMessage>>,
+    processing: Mutex<Processing>,
+    preempt_margin: f32,
 }
 
 impl PersonaInbox {
@@ -23,22 +95,133 @@ impl PersonaInbox {
         Self {
             persona_id,
             heap: Mutex::new(BinaryHeap::new()),
+            processing: Mutex::new(Processing::default()),
+            preempt_margin: DEFAULT_PREEMPT_MARGIN,
         }
     }
 
-    /// Enqueue message (non-blocking, uses mutex)
-    pub fn enqueue(&self, message: InboxMessage) {
+    pub fn set_preempt_margin(&mut self, margin: f32) {
+        self.preempt_margin = margin;
+    }
+
+    /// Enqueue message (non-blocking, uses mutex). Returns true if the
+    /// message preempts the in-flight one.
+    pub fn enqueue(&self, message: InboxMessage) -> bool {
+        let priority = message.priority;
         if let Ok(mut heap) = self.heap.lock() {
             heap.push(message);
         }
+        self.preempt_for(priority)
     }
 
-    /// Dequeue highest priority message (sync)
+    /// Raise the in-flight message's PreemptSignal if a message arriving at
+    /// `priority` outranks it by the margin. Returns true if it did.
+    pub fn preempt_for(&self, priority: f32) -> bool {
+        let Ok(processing) = self.processing.lock() else {
+            return false;
+        };
+        match &processing.in_flight {
+            Some(current)
+                if !current.signal.should_yield()
+                    && priority >= current.message.priority + self.preempt_margin =>
+            {
+                current.signal.raise();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Dequeue highest priority message (sync) and start processing it
     pub fn dequeue(&self) -> Option<InboxMessage> {
-        if let Ok(mut heap) = self.heap.lock() {
-            heap.pop()
+        let message = self.heap.lock().ok()?.pop()?;
+        self.begin(message.clone());
+        Some(message)
+    }
+
+    /// Start processing a message taken from another queue, completing the
+    /// current one
+    pub fn begin(&self, message: InboxMessage) {
+        if let Ok(mut processing) = self.processing.lock() {
+            processing.start(message);
+        }
+    }
+
+    /// Start processing the next message. A suspended message resumes first
+    /// unless a queued one would preempt it again. Starting a message
+    /// completes the current one.
+    pub fn next(&self) -> Option<InboxNext> {
+        let (Ok(mut heap), Ok(mut processing)) = (self.heap.lock(), self.processing.lock()) else {
+            return None;
+        };
+
+        let resume = match (processing.suspended.last(), heap.peek()) {
+            (Some(suspended), Some(queued)) => {
+                queued.priority < suspended.message.priority + self.preempt_margin
+            }
+            (Some(_), None) => true,
+            (None, _) => false,
+        };
+
+        let next = if resume {
+            let suspended = processing.suspended.pop()?;
+            InboxNext {
+                message: suspended.message,
+                resumed: true,
+                checkpoint: Some(suspended.checkpoint),
+            }
         } else {
-            None
+            InboxNext {
+                message: heap.pop()?,
+                resumed: false,
+                checkpoint: None,
+            }
+        };
+
+        processing.start(next.message.clone());
+        Some(next)
+    }
+
+    /// Yield signal of the in-flight message
+    pub fn preempt_signal(&self) -> Option<PreemptSignal> {
+        let processing = self.processing.lock().ok()?;
+        processing.in_flight.as_ref().map(|f| f.signal.clone())
+    }
+
+    /// Whether the in-flight message has been preempted
+    pub fn should_yield(&self) -> bool {
+        self.preempt_signal()
+            .is_some_and(|signal| signal.should_yield())
+    }
+
+    /// Suspend the in-flight message with the progress made so far.
+    /// next() hands `checkpoint` back when the message resumes.
+    pub fn suspend(&self, checkpoint: Value) -> Result<InboxMessage, String> {
+        let mut processing = self
+            .processing
+            .lock()
+            .map_err(|e| format!("Lock poisoned: {e}"))?;
+        let current = processing.in_flight.take().ok_or("No message in flight")?;
+        processing.suspended.push(Suspended {
+            message: current.message.clone(),
+            checkpoint,
+        });
+        Ok(current.message)
+    }
+
+    /// Finish the in-flight message
+    pub fn complete(&self) {
+        if let Ok(mut processing) = self.processing.lock() {
+            processing.in_flight = None;
+        }
+    }
+
+    /// Number of preempted messages waiting to resume
+    pub fn suspended_count(&self) -> usize {
+        if let Ok(processing) = self.processing.lock() {
+            processing.suspended.len()
+        } else {
+            0
         }
     }
 
@@ -122,6 +305,61 @@ mod tests {
 
         // Third should be None
         assert!(inbox.dequeue().is_none(), "Should be empty now");
+
+        // A dequeued message is in flight, so an urgent arrival preempts it
+        assert!(!inbox.should_yield());
+        assert!(inbox.preempt_for(0.9));
+        assert!(inbox.should_yield());
+    }
+
+    #[test]
+    fn test_preempt_and_resume() {
+        let inbox = PersonaInbox::new(Uuid::new_v4());
+        let message = |content: &str, priority: f32| InboxMessage {
+            id: Uuid::new_v4(),
+            room_id: Uuid::new_v4(),
+            sender_id: Uuid::new_v4(),
+            sender_name: "Test".to_string(),
+            sender_type: SenderType::Human,
+            content: content.to_string(),
+            timestamp: 1000,
+            priority,
+            source_modality: None,
+            voice_session_id: None,
+        };
+
+        inbox.enqueue(message("Rumination", 0.3));
+        let first = inbox.next().unwrap();
+        assert!(!first.resumed);
+        let signal = inbox.preempt_signal().unwrap();
+
+        // Within the margin: keeps waiting behind the in-flight message
+        assert!(!inbox.enqueue(message("Chatter", 0.4)));
+        assert!(!signal.should_yield());
+
+        // A human interrupting well above the margin preempts
+        assert!(inbox.enqueue(message("Stop, urgent", 0.9)));
+        assert!(signal.should_yield() && inbox.should_yield());
+
+        // Cognition yields at its checkpoint; the urgent message goes first
+        let suspended = inbox.suspend(serde_json::json!({ "step": 2 })).unwrap();
+        assert_eq!(suspended.content, "Rumination");
+        assert_eq!(inbox.suspended_count(), 1);
+        assert_eq!(inbox.next().unwrap().message.content, "Stop, urgent");
+        assert!(!inbox.should_yield());
+        inbox.complete();
+
+        // Then the preempted message resumes from its checkpoint, ahead of
+        // the lower-priority message that arrived meanwhile
+        let resumed = inbox.next().unwrap();
+        assert!(resumed.resumed);
+        assert_eq!(resumed.message.content, "Rumination");
+        assert_eq!(resumed.checkpoint, Some(serde_json::json!({ "step": 2 })));
+        inbox.complete();
+
+        assert_eq!(inbox.next().unwrap().message.content, "Chatter");
+        inbox.complete();
+        assert!(inbox.suspend(Value::Null).is_err());
     }
 
     #[test]
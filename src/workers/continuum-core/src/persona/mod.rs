@@ -1,9 +1,10 @@
 //! Persona Cognition Module
 //!
 //! Core persona intelligence in Rust:
-//! - PersonaInbox: Priority queue for messages/tasks (flat, legacy)
+//! - PersonaInbox: Priority queue for messages/tasks (flat, legacy), with preemption
 //! - PersonaCognitionEngine: Fast decision making
 //! - PersonaState: Energy, mood, attention tracking
+//! - mood_prosody: Per-persona mood → voice speed/pitch table
 //! - Evaluator: Unified pre-response gate (replaces 5 sequential TS gates)
 //! - Channel system: Multi-channel queue with item polymorphism (replaces flat inbox)
 //!   - channel_types: ActivityDomain enum + QueueItemBehavior trait
@@ -22,6 +23,7 @@ pub mod genome_paging;
 pub mod inbox;
 pub mod message_cache;
 pub mod model_selection;
+pub mod mood_prosody;
 pub mod self_task_generator;
 pub mod text_analysis;
 pub mod types;
@@ -40,10 +42,11 @@ pub use genome_paging::{
     ActivateSkillResult, CoverageReport, DomainActivity, GenomeAdapterInfo, GenomePagingEngine,
     GenomePagingState,
 };
-pub use inbox::PersonaInbox;
+pub use inbox::{InboxNext, PersonaInbox, PreemptSignal};
 pub use model_selection::{
     AdapterInfo, AdapterRegistry, ModelSelectionRequest, ModelSelectionResult,
 };
+pub use mood_prosody::{MoodProsody, MoodProsodyTable};
 pub use types::*;
 pub use message_cache::{
     CachedMessage, ContentDeduplicator, EchoChamberResult, ContentDedupResult,
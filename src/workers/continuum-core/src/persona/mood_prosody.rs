@@ -0,0 +1,139 @@
+//! Mood → voice prosody mapping
+//!
+//! A persona's mood nudges how it sounds in voice calls: an active persona
+//! speaks a little faster and brighter, an idle one calmer, a tired one
+//! slower and lower. The mapping is a per-persona table (set with
+//! `channel/mood-prosody`), so each voice can be tuned; the defaults below
+//! are deliberately small shifts.
+
+use super::types::Mood;
+use crate::live::audio::tts::Prosody;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Speech rate and pitch shift for one mood
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/persona/MoodProsody.ts")]
+pub struct MoodProsody {
+    /// Rate multiplier: 1.1 = 10% faster
+    pub speed: f32,
+    /// Pitch shift in semitones
+    pub pitch_semitones: f32,
+}
+
+impl MoodProsody {
+    pub const fn new(speed: f32, pitch_semitones: f32) -> Self {
+        Self {
+            speed,
+            pitch_semitones,
+        }
+    }
+}
+
+/// Per-mood prosody for one persona. Moods missing from a configured table
+/// keep their defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/persona/MoodProsodyTable.ts"
+)]
+pub struct MoodProsodyTable {
+    pub active: MoodProsody,
+    pub tired: MoodProsody,
+    pub overwhelmed: MoodProsody,
+    pub idle: MoodProsody,
+}
+
+impl Default for MoodProsodyTable {
+    fn default() -> Self {
+        Self {
+            active: MoodProsody::new(1.05, 0.5),
+            tired: MoodProsody::new(0.9, -1.0),
+            overwhelmed: MoodProsody::new(1.1, 1.0),
+            idle: MoodProsody::new(0.95, -0.5),
+        }
+    }
+}
+
+impl MoodProsodyTable {
+    /// Table with every mood at neutral prosody (mood doesn't affect voice)
+    pub fn neutral() -> Self {
+        let neutral = MoodProsody::new(1.0, 0.0);
+        Self {
+            active: neutral,
+            tired: neutral,
+            overwhelmed: neutral,
+            idle: neutral,
+        }
+    }
+
+    pub fn get(&self, mood: Mood) -> MoodProsody {
+        match mood {
+            Mood::Active => self.active,
+            Mood::Tired => self.tired,
+            Mood::Overwhelmed => self.overwhelmed,
+            Mood::Idle => self.idle,
+        }
+    }
+
+    /// Prosody for `mood`. Tables are validated when set, so this only falls
+    /// back to neutral for a table built in code with out-of-range values.
+    pub fn prosody(&self, mood: Mood) -> Prosody {
+        let entry = self.get(mood);
+        Prosody::new(entry.speed, entry.pitch_semitones).unwrap_or_default()
+    }
+
+    /// Check every entry is within the TTS prosody limits
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, entry) in [
+            ("active", self.active),
+            ("tired", self.tired),
+            ("overwhelmed", self.overwhelmed),
+            ("idle", self.idle),
+        ] {
+            Prosody::new(entry.speed, entry.pitch_semitones)
+                .map_err(|e| format!("Invalid prosody for mood '{name}': {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_table_shifts_by_mood() {
+        let table = MoodProsodyTable::default();
+        assert!(table.validate().is_ok());
+
+        let active = table.prosody(Mood::Active);
+        let tired = table.prosody(Mood::Tired);
+        assert!(active.speed > 1.0 && active.pitch_semitones > 0.0);
+        assert!(tired.speed < 1.0 && tired.pitch_semitones < 0.0);
+
+        assert!(MoodProsodyTable::neutral()
+            .prosody(Mood::Overwhelmed)
+            .is_neutral());
+    }
+
+    #[test]
+    fn test_partial_table_and_validation() {
+        // Only "tired" configured; the rest keep their defaults
+        let table: MoodProsodyTable = serde_json::from_value(serde_json::json!({
+            "tired": { "speed": 0.8, "pitch_semitones": -2.0 }
+        }))
+        .unwrap();
+        assert_eq!(table.tired, MoodProsody::new(0.8, -2.0));
+        assert_eq!(table.active, MoodProsodyTable::default().active);
+
+        let invalid = MoodProsodyTable {
+            idle: MoodProsody::new(5.0, 0.0),
+            ..Default::default()
+        };
+        let err = invalid.validate().unwrap_err();
+        assert!(err.contains("idle"), "{err}");
+        assert!(invalid.prosody(Mood::Idle).is_neutral());
+    }
+}
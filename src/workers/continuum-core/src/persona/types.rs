@@ -3,6 +3,8 @@
 //! Single source of truth for persona state and queue types
 //! Exported to TypeScript via ts-rs
 
+use super::mood_prosody::MoodProsodyTable;
+use crate::live::audio::tts::Prosody;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use ts_rs::TS;
@@ -210,6 +212,9 @@ pub struct PersonaState {
     pub response_count: u32,
     /// Compute budget remaining (rate limiting)
     pub compute_budget: f32,
+    /// How mood shifts this persona's voice (configurable per persona)
+    #[serde(default)]
+    pub mood_prosody: MoodProsodyTable,
 }
 
 impl Default for PersonaState {
@@ -222,6 +227,7 @@ impl Default for PersonaState {
             last_activity_time: 0,
             response_count: 0,
             compute_budget: 1.0,
+            mood_prosody: MoodProsodyTable::default(),
         }
     }
 }
@@ -278,6 +284,11 @@ impl PersonaState {
         };
     }
 
+    /// Voice prosody for the current mood
+    pub fn voice_prosody(&self) -> Prosody {
+        self.mood_prosody.prosody(self.mood)
+    }
+
     /// Should engage with work at given priority?
     pub fn should_engage(&self, priority: f32) -> bool {
         match self.mood {
@@ -1,8 +1,45 @@
 //! RAG Budget Manager
 //!
-//! Allocates token budget across sources based on priority
+//! Allocates token budget across sources based on priority, and packs the
+//! loaded sections into the model's context window.
+//!
+//! Sources estimate their size at ~4 chars/token, which can be far off for
+//! code or non-English text. Packing recounts with the target model's
+//! tokenizer, so an oversized context is trimmed here instead of being
+//! silently truncated by the model (which cuts the query off the end).
+
+use super::chunking::CHARS_PER_TOKEN;
+use super::types::{BudgetAllocation, RagSection};
+use std::cmp::Reverse;
+use tokenizers::Tokenizer;
+
+/// Per-message chat-template overhead (role header and delimiters)
+pub const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Separator between system prompt sections
+pub const SYSTEM_SECTION_SEPARATOR: &str = "\n\n---\n\n";
+
+/// Counts tokens the way the target model will
+pub trait TokenCounter: Send + Sync {
+    fn count(&self, text: &str) -> usize;
+}
+
+impl TokenCounter for Tokenizer {
+    fn count(&self, text: &str) -> usize {
+        self.encode(text, false)
+            .map(|encoding| encoding.len())
+            .unwrap_or_else(|_| CharEstimate.count(text))
+    }
+}
+
+/// ~4 chars/token fallback for models with no registered tokenizer
+pub struct CharEstimate;
 
-use super::types::BudgetAllocation;
+impl TokenCounter for CharEstimate {
+    fn count(&self, text: &str) -> usize {
+        text.len().div_ceil(CHARS_PER_TOKEN)
+    }
+}
 
 /// Source registration for budget allocation
 #[derive(Debug, Clone)]
@@ -53,9 +90,99 @@ impl BudgetManager {
     }
 }
 
+/// Greedily keep the highest-priority sections that fit in `available`
+/// tokens. A section whose messages don't all fit keeps its newest ones;
+/// a section with nothing that fits is dropped. Kept sections stay in
+/// their original order, with `token_count` set to the counted size.
+///
+/// Returns the kept sections and the names of the dropped ones.
+pub fn pack_sections(
+    mut sections: Vec<RagSection>,
+    priorities: &[u8],
+    available: usize,
+    counter: &dyn TokenCounter,
+) -> (Vec<RagSection>, Vec<String>) {
+    let mut order: Vec<usize> = (0..sections.len()).collect();
+    order.sort_by_key(|&i| Reverse(priorities.get(i).copied().unwrap_or(0)));
+
+    let separator_tokens = counter.count(SYSTEM_SECTION_SEPARATOR);
+    let mut keep = vec![false; sections.len()];
+    let mut remaining = available;
+
+    for i in order {
+        let section = &mut sections[i];
+        let prompt_tokens = match section.system_prompt_section.as_deref() {
+            Some(prompt) if !prompt.is_empty() => counter.count(prompt) + separator_tokens,
+            _ => 0,
+        };
+        if prompt_tokens > remaining {
+            continue;
+        }
+
+        // Newest messages first
+        let mut used = prompt_tokens;
+        let mut first_kept = section.messages.len();
+        for (j, message) in section.messages.iter().enumerate().rev() {
+            let tokens = counter.count(&message.content) + MESSAGE_OVERHEAD_TOKENS;
+            if used + tokens > remaining {
+                break;
+            }
+            used += tokens;
+            first_kept = j;
+        }
+
+        let has_content = prompt_tokens > 0 || !section.messages.is_empty();
+        if has_content && used == 0 {
+            continue;
+        }
+        section.messages.drain(..first_kept);
+        section.token_count = used;
+        remaining -= used;
+        keep[i] = true;
+    }
+
+    let mut kept = Vec::new();
+    let mut dropped = Vec::new();
+    for (section, keep) in sections.into_iter().zip(keep) {
+        if keep {
+            kept.push(section);
+        } else {
+            dropped.push(section.source_name);
+        }
+    }
+    (kept, dropped)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rag::types::{LlmMessage, MessageRole};
+
+    /// One token per word
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    fn section(name: &str, prompt: Option<&str>, messages: &[&str]) -> RagSection {
+        RagSection {
+            source_name: name.to_string(),
+            system_prompt_section: prompt.map(str::to_string),
+            messages: messages
+                .iter()
+                .map(|content| LlmMessage {
+                    role: MessageRole::User,
+                    content: content.to_string(),
+                    name: None,
+                    timestamp: None,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
 
     #[test]
     fn test_budget_allocation_by_priority() {
@@ -128,4 +255,35 @@ mod tests {
         let allocations = manager.allocate(&[]);
         assert!(allocations.is_empty());
     }
+
+    #[test]
+    fn test_pack_sections_by_priority() {
+        let sections = vec![
+            section(
+                "memory",
+                Some("one two three four five six seven eight"),
+                &[],
+            ),
+            section("identity", Some("you are helpful"), &[]),
+            section(
+                "conversation",
+                None,
+                &["oldest message here", "older one", "newest"],
+            ),
+        ];
+
+        // identity: 3 + 1 (separator); conversation: 1+4, 2+4, then 3+4 won't fit
+        let (kept, dropped) = pack_sections(sections, &[70, 95, 80], 16, &WordCounter);
+
+        // Memory (lowest priority, 8 tokens) no longer fits
+        assert_eq!(dropped, vec!["memory".to_string()]);
+        assert_eq!(kept[0].source_name, "identity");
+        assert_eq!(kept[0].token_count, 4);
+
+        // Conversation keeps only its two newest messages
+        let conversation = &kept[1];
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.messages[0].content, "older one");
+        assert_eq!(conversation.token_count, 11);
+    }
 }
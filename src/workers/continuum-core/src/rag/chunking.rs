@@ -0,0 +1,364 @@
+//! Document Chunking
+//!
+//! Splits documents into retrieval chunks before they are embedded and
+//! indexed. Fixed-size slicing cuts sentences and code blocks in half, so
+//! the structural strategies split on sentence, paragraph or Markdown
+//! section boundaries, packing whole units into a chunk until it is full.
+//! A single unit larger than a chunk is the only thing ever sliced.
+//!
+//! Token counts use the same ~4 chars/token estimate as the RAG sources.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Rough chars-per-token estimate (same as the RAG sources)
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Largest chunk the structural strategies build
+pub const MAX_CHUNK_TOKENS: usize = 512;
+
+/// How documents are split into chunks for indexing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[ts(export, export_to = "../../../shared/generated/rag/ChunkStrategy.ts")]
+pub enum ChunkStrategy {
+    /// Fixed windows of `size` tokens, each repeating the last `overlap`
+    /// tokens of the previous one. Window edges snap to whitespace.
+    FixedTokens { size: usize, overlap: usize },
+    /// Whole sentences, packed up to MAX_CHUNK_TOKENS
+    Sentence,
+    /// Whole paragraphs (blank-line separated), packed up to MAX_CHUNK_TOKENS.
+    /// Fenced code blocks count as one paragraph.
+    #[default]
+    Paragraph,
+    /// Markdown sections: a chunk never spans two headers, and every chunk
+    /// starts with its section's header line
+    Markdown,
+}
+
+/// One chunk of a document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/rag/TextChunk.ts")]
+pub struct TextChunk {
+    /// Chunk text (Markdown continuation chunks get their header prepended)
+    pub text: String,
+    /// Byte range of the chunk's content in the source document
+    pub start: usize,
+    pub end: usize,
+    /// Header line of the enclosing Markdown section
+    #[ts(optional)]
+    pub heading: Option<String>,
+    pub token_count: usize,
+}
+
+/// Split `text` into chunks. Whitespace-only chunks are dropped.
+pub fn chunk_text(text: &str, strategy: ChunkStrategy) -> Vec<TextChunk> {
+    match strategy {
+        ChunkStrategy::FixedTokens { size, overlap } => to_chunks(
+            text,
+            fixed_windows(text, 0, text.len(), size, overlap),
+            None,
+        ),
+        ChunkStrategy::Sentence => {
+            let units = sentence_units(text, 0, text.len());
+            to_chunks(text, pack(text, &units, MAX_CHUNK_TOKENS), None)
+        }
+        ChunkStrategy::Paragraph => {
+            let units = paragraph_units(text, 0, text.len());
+            to_chunks(text, pack(text, &units, MAX_CHUNK_TOKENS), None)
+        }
+        ChunkStrategy::Markdown => markdown_chunks(text),
+    }
+}
+
+fn estimate_tokens(len: usize) -> usize {
+    len.div_ceil(CHARS_PER_TOKEN)
+}
+
+fn to_chunks(text: &str, ranges: Vec<(usize, usize)>, heading: Option<&str>) -> Vec<TextChunk> {
+    ranges
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let (start, end) = trim_range(text, start, end);
+            if start == end {
+                return None;
+            }
+            let body = &text[start..end];
+            let text = match heading {
+                Some(heading) if !body.starts_with(heading) => format!("{heading}\n\n{body}"),
+                _ => body.to_string(),
+            };
+            Some(TextChunk {
+                token_count: estimate_tokens(text.len()),
+                text,
+                start,
+                end,
+                heading: heading.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Narrow a byte range to exclude leading/trailing whitespace
+fn trim_range(text: &str, start: usize, end: usize) -> (usize, usize) {
+    let slice = &text[start..end];
+    let trimmed = slice.trim_start();
+    let start = start + (slice.len() - trimmed.len());
+    (start, start + trimmed.trim_end().len())
+}
+
+/// Greedily merge consecutive units into ranges of at most `max_tokens`;
+/// a unit that alone exceeds it is sliced into fixed windows
+fn pack(text: &str, units: &[(usize, usize)], max_tokens: usize) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+
+    for &(start, end) in units {
+        if estimate_tokens(end - start) > max_tokens {
+            ranges.extend(current.take());
+            ranges.extend(fixed_windows(text, start, end, max_tokens, 0));
+            continue;
+        }
+        current = match current {
+            Some((cur_start, _)) if estimate_tokens(end - cur_start) <= max_tokens => {
+                Some((cur_start, end))
+            }
+            Some(full) => {
+                ranges.push(full);
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+    ranges.extend(current);
+    ranges
+}
+
+/// Windows of `size` tokens over `text[start..end]`, each beginning `overlap`
+/// tokens before the previous one ended
+fn fixed_windows(
+    text: &str,
+    start: usize,
+    end: usize,
+    size: usize,
+    overlap: usize,
+) -> Vec<(usize, usize)> {
+    let window = size.max(1) * CHARS_PER_TOKEN;
+    let overlap = overlap.min(size.saturating_sub(1)) * CHARS_PER_TOKEN;
+
+    let mut ranges = Vec::new();
+    let mut pos = start;
+    while pos < end {
+        let mut cut = floor_boundary(text, (pos + window).min(end));
+        if cut < end {
+            // Prefer ending at whitespace in the back half of the window
+            if let Some(ws) = text[pos..cut].rfind(char::is_whitespace) {
+                if ws >= window / 2 {
+                    cut = pos + ws;
+                }
+            }
+        }
+        if cut <= pos {
+            // Window smaller than one character
+            cut = ceil_boundary(text, pos + 1);
+        }
+        ranges.push((pos, cut));
+        if cut >= end {
+            break;
+        }
+
+        // Back up by the overlap, snapped forward to a word start
+        let mut next = floor_boundary(text, cut.saturating_sub(overlap).max(pos + 1));
+        if next > pos && next < cut {
+            if let Some(ws) = text[next..cut].find(char::is_whitespace) {
+                next += ws;
+            }
+        }
+        pos = if next > pos { next } else { cut };
+    }
+    ranges
+}
+
+/// Sentences: each ends after `.`, `!` or `?` followed by whitespace, or at
+/// a blank line
+fn sentence_units(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut units = Vec::new();
+    let mut unit_start = start;
+    let mut chars = text[start..end].char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, n)| n);
+        let boundary = match c {
+            '.' | '!' | '?' => next.is_some_and(char::is_whitespace),
+            '\n' => next == Some('\n'),
+            _ => false,
+        };
+        if boundary {
+            let unit_end = start + i + c.len_utf8();
+            units.push((unit_start, unit_end));
+            unit_start = unit_end;
+        }
+    }
+    if unit_start < end {
+        units.push((unit_start, end));
+    }
+    units
+}
+
+/// Paragraphs: runs of lines separated by blank lines. Blank lines inside a
+/// fenced code block don't split it.
+fn paragraph_units(text: &str, start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut units = Vec::new();
+    let mut unit_start: Option<usize> = None;
+    let mut in_fence = false;
+
+    for (line_start, line) in lines(text, start, end) {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        }
+        if line.trim().is_empty() && !in_fence {
+            if let Some(s) = unit_start.take() {
+                units.push((s, line_start));
+            }
+        } else if unit_start.is_none() {
+            unit_start = Some(line_start);
+        }
+    }
+    if let Some(s) = unit_start {
+        units.push((s, end));
+    }
+    units
+}
+
+/// Split at ATX headers outside code fences; each section's paragraphs are
+/// packed separately and tagged with the section header
+fn markdown_chunks(text: &str) -> Vec<TextChunk> {
+    let mut sections: Vec<(usize, Option<&str>)> = vec![(0, None)];
+    let mut in_fence = false;
+    for (line_start, line) in lines(text, 0, text.len()) {
+        if is_fence(line) {
+            in_fence = !in_fence;
+        } else if !in_fence && is_header(line) {
+            sections.push((line_start, Some(line.trim_end())));
+        }
+    }
+
+    let mut chunks = Vec::new();
+    for (i, &(start, heading)) in sections.iter().enumerate() {
+        let end = sections.get(i + 1).map_or(text.len(), |&(next, _)| next);
+        let units = paragraph_units(text, start, end);
+        chunks.extend(to_chunks(
+            text,
+            pack(text, &units, MAX_CHUNK_TOKENS),
+            heading,
+        ));
+    }
+    chunks
+}
+
+/// Lines of `text[start..end]` with their byte offsets (newline excluded)
+fn lines(text: &str, start: usize, end: usize) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = start;
+    text[start..end].split_inclusive('\n').map(move |line| {
+        let line_start = offset;
+        offset += line.len();
+        (line_start, line.trim_end_matches(['\n', '\r']))
+    })
+}
+
+fn is_fence(line: &str) -> bool {
+    let line = line.trim_start();
+    line.starts_with("```") || line.starts_with("~~~")
+}
+
+/// `#` to `######` followed by a space
+fn is_header(line: &str) -> bool {
+    let hashes = line.bytes().take_while(|&b| b == b'#').count();
+    (1..=6).contains(&hashes) && line[hashes..].starts_with(' ')
+}
+
+fn floor_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+fn ceil_boundary(text: &str, mut index: usize) -> usize {
+    while index < text.len() && !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index.min(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixed_tokens_overlap_at_word_boundaries() {
+        let text = "alpha bravo charlie delta echo foxtrot golf hotel india juliet";
+        let chunks = chunk_text(
+            text,
+            ChunkStrategy::FixedTokens {
+                size: 5,
+                overlap: 2,
+            },
+        );
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert_eq!(chunk.text, &text[chunk.start..chunk.end]);
+            assert!(chunk.text.len() <= 20);
+            // Never cut inside a word
+            assert!(text.split(' ').any(|w| chunk.text.starts_with(w)));
+        }
+        // Consecutive windows overlap
+        assert!(chunks.windows(2).all(|w| w[1].start < w[0].end));
+        assert!(chunks.last().unwrap().text.ends_with("juliet"));
+    }
+
+    #[test]
+    fn test_sentence_and_paragraph_keep_units_whole() {
+        let sentence = "This sentence is exactly long enough to matter here. ";
+        let text = sentence.repeat(60);
+        let chunks = chunk_text(&text, ChunkStrategy::Sentence);
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.token_count <= MAX_CHUNK_TOKENS);
+            assert!(chunk.text.starts_with("This") && chunk.text.ends_with("here."));
+        }
+
+        // A code block with blank lines stays one paragraph
+        let doc = "Intro.\n\n```rust\nfn a() {}\n\nfn b() {}\n```\n\nOutro.";
+        let units = paragraph_units(doc, 0, doc.len());
+        assert_eq!(units.len(), 3);
+        assert!(doc[units[1].0..units[1].1].contains("fn b()"));
+    }
+
+    #[test]
+    fn test_markdown_sections_keep_headers() {
+        let long_body = "Words in a paragraph that goes on. ".repeat(40);
+        let doc = format!(
+            "Preamble.\n\n# Install\n\nRun it.\n\n```sh\n# not a header\nmake\n```\n\n\
+             ## Usage\n\n{long_body}\n\n{long_body}\n"
+        );
+        let chunks = chunk_text(&doc, ChunkStrategy::Markdown);
+
+        assert_eq!(chunks[0].text, "Preamble.");
+        assert_eq!(chunks[0].heading, None);
+
+        // The fenced "# not a header" stays inside the Install section
+        assert_eq!(chunks[1].heading.as_deref(), Some("# Install"));
+        assert!(chunks[1].text.starts_with("# Install"));
+        assert!(chunks[1].text.contains("# not a header\nmake\n```"));
+
+        // Usage spans several chunks; each one carries the header
+        let usage: Vec<_> = chunks
+            .iter()
+            .filter(|c| c.heading.as_deref() == Some("## Usage"))
+            .collect();
+        assert!(usage.len() > 1);
+        assert!(usage.iter().all(|c| c.text.starts_with("## Usage\n\n")));
+    }
+}
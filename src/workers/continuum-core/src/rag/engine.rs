@@ -2,10 +2,26 @@
 //!
 //! The core of fast RAG: load ALL sources in parallel via rayon.
 //! Target: <500ms total (currently 20+ seconds in TypeScript)
-
-use super::budget::{BudgetManager, SourceConfig};
+//!
+//! Loaded sections are packed into the context window (minus the query and
+//! response reserve), counting tokens with the target model's tokenizer. The
+//! window is `RagOptions::context_window`, or else the one registered for
+//! `RagOptions::model_id` when the model was loaded.
+//!
+//! Sections holding vector-recalled chunks (`RagSection::recall_scores`) are
+//! reranked against the current message first, per `RagOptions::rerank`.
+
+use super::budget::{
+    self, BudgetManager, CharEstimate, SourceConfig, TokenCounter, SYSTEM_SECTION_SEPARATOR,
+};
+use super::chunking::{self, TextChunk};
+use super::rerank;
 use super::sources::RagSource;
-use super::types::{LlmMessage, RagContext, RagOptions, RagSection, SourceTiming};
+use super::types::{
+    BudgetAllocation, LlmMessage, RagContext, RagOptions, RagSection, SourceTiming,
+};
+use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Instant;
 use tracing::{info, warn};
@@ -14,6 +30,16 @@ use tracing::{info, warn};
 pub struct RagEngine {
     sources: Vec<Arc<dyn RagSource>>,
     default_budget: usize,
+    /// Loaded models by ID, for context-window packing
+    models: RwLock<HashMap<String, RegisteredModel>>,
+    /// Model IDs already warned about having no registered tokenizer
+    unregistered_warned: RwLock<HashSet<Option<String>>>,
+}
+
+/// A loaded model's tokenizer and context window
+struct RegisteredModel {
+    counter: Arc<dyn TokenCounter>,
+    context_window: usize,
 }
 
 impl RagEngine {
@@ -21,6 +47,8 @@ impl RagEngine {
         Self {
             sources: Vec::new(),
             default_budget: 8000, // Default token budget
+            models: RwLock::new(HashMap::new()),
+            unregistered_warned: RwLock::new(HashSet::new()),
         }
     }
 
@@ -29,6 +57,104 @@ impl RagEngine {
         self.sources.push(source);
     }
 
+    /// Register a loaded model's tokenizer (e.g. `ModelBackend::tokenizer()`)
+    /// and context window, used when `RagOptions::model_id` names that model
+    pub fn register_tokenizer(
+        &self,
+        model_id: &str,
+        counter: Arc<dyn TokenCounter>,
+        context_window: usize,
+    ) {
+        self.models.write().insert(
+            model_id.to_string(),
+            RegisteredModel {
+                counter,
+                context_window,
+            },
+        );
+    }
+
+    /// Context window to pack into: the requested one, else the model's
+    fn context_window(&self, options: &RagOptions) -> Option<usize> {
+        options.context_window.or_else(|| {
+            let id = options.model_id.as_ref()?;
+            self.models.read().get(id).map(|m| m.context_window)
+        })
+    }
+
+    fn token_counter(&self, options: &RagOptions) -> Arc<dyn TokenCounter> {
+        let registered = options
+            .model_id
+            .as_ref()
+            .and_then(|id| self.models.read().get(id).map(|m| m.counter.clone()));
+        registered.unwrap_or_else(|| {
+            if self
+                .unregistered_warned
+                .write()
+                .insert(options.model_id.clone())
+            {
+                warn!(
+                    "RAG: no tokenizer registered for model {:?}, estimating tokens by characters",
+                    options.model_id
+                );
+            }
+            Arc::new(CharEstimate)
+        })
+    }
+
+    /// Split a document for indexing using `options.chunk_strategy`
+    pub fn chunk_document(&self, text: &str, options: &RagOptions) -> Vec<TextChunk> {
+        chunking::chunk_text(text, options.chunk_strategy)
+    }
+
+    /// Rerank pass over vector-recalled candidates, configured by
+    /// `options.rerank` (pass-through in vector-score order when disabled)
+    pub fn rerank<T>(
+        &self,
+        query: &str,
+        candidates: Vec<T>,
+        options: &RagOptions,
+        text: impl Fn(&T) -> &str,
+        vector_score: impl Fn(&T) -> f64,
+    ) -> Vec<(T, f64)> {
+        rerank::rerank(query, candidates, &options.rerank, text, vector_score)
+    }
+
+    /// Rerank a section's recalled chunks against `query`. Sections without
+    /// one recall score per message aren't recall results and pass through.
+    fn rerank_section(
+        &self,
+        query: &str,
+        mut section: RagSection,
+        options: &RagOptions,
+    ) -> RagSection {
+        if section.recall_scores.is_empty() || section.recall_scores.len() != section.messages.len()
+        {
+            return section;
+        }
+        let counter = self.token_counter(options);
+        let candidates: Vec<(LlmMessage, f64, usize)> = std::mem::take(&mut section.messages)
+            .into_iter()
+            .zip(std::mem::take(&mut section.recall_scores))
+            .map(|(message, score)| {
+                let tokens = counter.count(&message.content) + budget::MESSAGE_OVERHEAD_TOKENS;
+                (message, score, tokens)
+            })
+            .collect();
+        let recalled_tokens: usize = candidates.iter().map(|(_, _, tokens)| tokens).sum();
+
+        let ranked = self.rerank(query, candidates, options, |c| &c.0.content, |c| c.1);
+        let kept_tokens: usize = ranked.iter().map(|((_, _, tokens), _)| tokens).sum();
+        section.token_count = section
+            .token_count
+            .saturating_sub(recalled_tokens - kept_tokens);
+        for ((message, _, _), score) in ranked {
+            section.messages.push(message);
+            section.recall_scores.push(score);
+        }
+        section
+    }
+
     /// Build RAG context - ALL sources load in PARALLEL
     pub async fn build_context(&self, options: RagOptions) -> RagContext {
         let start = Instant::now();
@@ -76,8 +202,23 @@ impl RagEngine {
             })
             .collect();
 
-        // 5. Compose final context
-        let context = self.compose(options.clone(), sections, start);
+        // 5. Rerank recalled chunks against the query
+        let sections = match options.current_message.as_deref() {
+            Some(query) if options.rerank.enabled => sections
+                .into_iter()
+                .map(|section| self.rerank_section(query, section, &options))
+                .collect(),
+            _ => sections,
+        };
+
+        // 6. Fit to the model's context window
+        let (sections, dropped) = match self.context_window(&options) {
+            Some(window) => self.fit_to_window(&options, window, sections, &allocations),
+            None => (sections, Vec::new()),
+        };
+
+        // 7. Compose final context
+        let context = self.compose(options.clone(), sections, dropped, start);
 
         info!(
             "RAG: Composed context in {:.1}ms ({} tokens, {} sources)",
@@ -89,11 +230,39 @@ impl RagEngine {
         context
     }
 
+    /// Pack sections by priority into `window` minus the query and the
+    /// response reserve
+    fn fit_to_window(
+        &self,
+        options: &RagOptions,
+        window: usize,
+        sections: Vec<RagSection>,
+        allocations: &[BudgetAllocation],
+    ) -> (Vec<RagSection>, Vec<String>) {
+        let counter = self.token_counter(options);
+        let query_tokens = options.current_message.as_deref().map_or(0, |query| {
+            counter.count(query) + budget::MESSAGE_OVERHEAD_TOKENS
+        });
+        let available = window.saturating_sub(options.response_reserve + query_tokens);
+
+        let priorities: Vec<u8> = allocations.iter().map(|a| a.priority).collect();
+        let (kept, dropped) =
+            budget::pack_sections(sections, &priorities, available, counter.as_ref());
+        if !dropped.is_empty() {
+            warn!(
+                "RAG: dropped {:?} to fit {} tokens (window {}, reserve {}, query {})",
+                dropped, available, window, options.response_reserve, query_tokens
+            );
+        }
+        (kept, dropped)
+    }
+
     /// Compose sections into final context
     fn compose(
         &self,
         options: RagOptions,
         sections: Vec<RagSection>,
+        dropped_sources: Vec<String>,
         start: Instant,
     ) -> RagContext {
         let mut system_parts: Vec<String> = Vec::new();
@@ -131,7 +300,7 @@ impl RagEngine {
         }
 
         // Build system prompt
-        let system_prompt = system_parts.join("\n\n---\n\n");
+        let system_prompt = system_parts.join(SYSTEM_SECTION_SEPARATOR);
 
         RagContext {
             persona_id: options.persona_id,
@@ -141,6 +310,7 @@ impl RagEngine {
             total_tokens,
             composition_time_ms: start.elapsed().as_secs_f64() * 1000.0,
             source_timings: timings,
+            dropped_sources,
         }
     }
 
@@ -153,6 +323,7 @@ impl RagEngine {
             total_tokens: 0,
             composition_time_ms: start.elapsed().as_secs_f64() * 1000.0,
             source_timings: Vec::new(),
+            dropped_sources: Vec::new(),
         }
     }
 }
@@ -167,6 +338,8 @@ impl Default for RagEngine {
 mod tests {
     use super::super::sources::MockSource;
     use super::*;
+    use crate::rag::chunking::ChunkStrategy;
+    use crate::rag::types::MessageRole;
     use uuid::Uuid;
 
     #[tokio::test]
@@ -224,4 +397,139 @@ mod tests {
         assert_eq!(context.source_timings.len(), 1);
         assert_eq!(context.source_timings[0].name, "identity");
     }
+
+    /// One token per word
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_context_window_drops_what_does_not_fit() {
+        let mut engine = RagEngine::new();
+        engine.register_source(Arc::new(MockSource::new("fast", 0, 100)));
+        engine.register_source(Arc::new(MockSource::new("medium", 0, 100)));
+        engine.register_tokenizer("test-model", Arc::new(WordCounter), 14);
+
+        // Each mock prompt is "Mock content from <name>" (4) + separator (1);
+        // the model's window 14 - reserve 2 - query (2 + 4 overhead) leaves
+        // room for one
+        let options = RagOptions {
+            room_id: Uuid::new_v4(),
+            persona_id: Uuid::new_v4(),
+            max_tokens: 4000,
+            current_message: Some("hello there".to_string()),
+            response_reserve: 2,
+            model_id: Some("test-model".to_string()),
+            ..Default::default()
+        };
+
+        let context = engine.build_context(options).await;
+
+        assert_eq!(context.dropped_sources, vec!["medium".to_string()]);
+        assert_eq!(context.source_timings.len(), 1);
+        assert_eq!(context.total_tokens, 5);
+        assert_eq!(context.system_prompt, "Mock content from fast");
+    }
+
+    #[test]
+    fn test_chunk_document_uses_options_strategy() {
+        let engine = RagEngine::new();
+        let doc = "# Setup\n\nInstall it.\n\n# Usage\n\nRun it.";
+
+        let paragraphs = engine.chunk_document(doc, &RagOptions::default());
+        assert_eq!(paragraphs.len(), 1);
+        assert_eq!(paragraphs[0].heading, None);
+
+        let options = RagOptions {
+            chunk_strategy: ChunkStrategy::Markdown,
+            ..Default::default()
+        };
+        let sections = engine.chunk_document(doc, &options);
+        let headings: Vec<_> = sections.iter().map(|c| c.heading.as_deref()).collect();
+        assert_eq!(headings, vec![Some("# Setup"), Some("# Usage")]);
+    }
+
+    /// Recalled chunks with their vector scores, as a memory source returns
+    struct RecallSource;
+
+    impl RagSource for RecallSource {
+        fn name(&self) -> &str {
+            "recall"
+        }
+
+        fn config(&self) -> SourceConfig {
+            SourceConfig {
+                name: "recall".to_string(),
+                priority: 50,
+                default_percent: 20,
+                min_tokens: 100,
+            }
+        }
+
+        fn is_applicable(&self, _options: &RagOptions) -> bool {
+            true
+        }
+
+        fn load(&self, _options: &RagOptions, _allocated_budget: usize) -> RagSection {
+            let chunks = [
+                ("borrow checker rules", 0.90),
+                ("tomatoes need sun", 0.95),
+                ("rust borrow checker lifetimes", 0.80),
+            ];
+            RagSection {
+                source_name: "recall".to_string(),
+                token_count: 22,
+                messages: chunks
+                    .iter()
+                    .map(|(content, _)| LlmMessage {
+                        role: MessageRole::User,
+                        content: content.to_string(),
+                        name: None,
+                        timestamp: None,
+                    })
+                    .collect(),
+                recall_scores: chunks.iter().map(|(_, score)| *score).collect(),
+                ..Default::default()
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_build_context_reranks_recalled_chunks() {
+        let mut engine = RagEngine::new();
+        engine.register_source(Arc::new(RecallSource));
+        engine.register_tokenizer("test-model", Arc::new(WordCounter), 1000);
+
+        let options = RagOptions {
+            room_id: Uuid::new_v4(),
+            persona_id: Uuid::new_v4(),
+            max_tokens: 4000,
+            current_message: Some("rust borrow checker".to_string()),
+            model_id: Some("test-model".to_string()),
+            ..Default::default()
+        };
+
+        // Disabled: recall order is kept as loaded
+        let context = engine.build_context(options.clone()).await;
+        assert_eq!(context.messages.len(), 3);
+        assert_eq!(context.messages[1].content, "tomatoes need sun");
+
+        // Enabled: lexical matches overtake the closest vector hit, which
+        // falls outside top_k_out
+        let context = engine.build_context(options.rerank(true, 3, 2)).await;
+        let contents: Vec<&str> = context
+            .messages
+            .iter()
+            .map(|m| m.content.as_str())
+            .collect();
+        assert_eq!(
+            contents,
+            vec!["rust borrow checker lifetimes", "borrow checker rules"]
+        );
+        assert_eq!(context.total_tokens, 15);
+    }
 }
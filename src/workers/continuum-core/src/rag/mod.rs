@@ -1,15 +1,21 @@
 //! RAG (Retrieval-Augmented Generation) Engine
 //!
-//! Parallel source loading, budget allocation, context composition.
+//! Parallel source loading, budget allocation, context composition,
+//! structure-aware document chunking for indexing, and reranking of
+//! vector-recalled chunks.
 //! This is the brain's memory retrieval system.
 //!
 //! Design: All sources load in PARALLEL via rayon, not serial.
 //! Target: <500ms total composition time (currently 20+ seconds in TypeScript)
 
 pub mod budget;
+pub mod chunking;
 pub mod engine;
+pub mod rerank;
 pub mod sources;
 pub mod types;
 
+pub use chunking::{ChunkStrategy, TextChunk};
 pub use engine::RagEngine;
+pub use rerank::RerankOptions;
 pub use types::*;
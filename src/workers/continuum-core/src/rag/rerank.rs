@@ -0,0 +1,161 @@
+//! Rerank Stage
+//!
+//! Vector recall finds roughly relevant chunks, but its ordering is noisy,
+//! and only a handful of chunks fit in the prompt. Reranking takes the
+//! top-N recalled candidates, re-scores each one against the query, and
+//! keeps the best K.
+//!
+//! The score blends the vector score (scaled so the best candidate's is 1.0)
+//! with lexical overlap, the share of query terms that appear in the
+//! candidate. It is a cheap stand-in for a cross-encoder that still
+//! promotes chunks containing the query's actual terms over ones that are
+//! merely close in embedding space.
+//!
+//! Configured by `RagOptions::rerank` for sections `RagEngine::build_context`
+//! loads, and per request by `MemorySourceParams::rerank` in `rag/compose`,
+//! whose memory source does the vector recall.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use ts_rs::TS;
+
+/// Weight of lexical overlap in the blended score (the rest is vector score)
+pub const LEXICAL_WEIGHT: f64 = 0.4;
+
+/// Rerank settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/rag/RerankOptions.ts")]
+pub struct RerankOptions {
+    pub enabled: bool,
+    /// Recalled candidates to re-score (best by vector score)
+    pub top_n_in: usize,
+    /// Candidates kept after reranking
+    pub top_k_out: usize,
+}
+
+impl Default for RerankOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            top_n_in: 20,
+            top_k_out: 5,
+        }
+    }
+}
+
+/// Rerank `candidates` against `query`. Returns at most `top_k_out`
+/// candidates with their blended scores, best first. When disabled, the
+/// candidates come back in vector-score order, untruncated.
+pub fn rerank<T>(
+    query: &str,
+    mut candidates: Vec<T>,
+    options: &RerankOptions,
+    text: impl Fn(&T) -> &str,
+    vector_score: impl Fn(&T) -> f64,
+) -> Vec<(T, f64)> {
+    candidates.sort_by(|a, b| vector_score(b).total_cmp(&vector_score(a)));
+    if !options.enabled {
+        return candidates
+            .into_iter()
+            .map(|c| {
+                let score = vector_score(&c);
+                (c, score)
+            })
+            .collect();
+    }
+    candidates.truncate(options.top_n_in);
+
+    // Scale vector scores so both signals span [0, 1]
+    let max = candidates.first().map(&vector_score).unwrap_or(0.0);
+
+    let query_terms = terms(query);
+    let mut scored: Vec<(T, f64)> = candidates
+        .into_iter()
+        .map(|c| {
+            let vector = if max > 0.0 {
+                (vector_score(&c) / max).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let lexical = lexical_overlap(&query_terms, text(&c));
+            let score = (1.0 - LEXICAL_WEIGHT) * vector + LEXICAL_WEIGHT * lexical;
+            (c, score)
+        })
+        .collect();
+
+    // Stable sort: ties keep vector-score order
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(options.top_k_out);
+    scored
+}
+
+/// Share of query terms present in `text` (0.0 when the query has none)
+pub fn lexical_overlap(query_terms: &HashSet<String>, text: &str) -> f64 {
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let text_terms = terms(text);
+    let matched = query_terms
+        .iter()
+        .filter(|t| text_terms.contains(*t))
+        .count();
+    matched as f64 / query_terms.len() as f64
+}
+
+/// Lowercase alphanumeric terms, skipping one- and two-letter words
+pub fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<(&'static str, f64)> {
+        vec![
+            ("Deploys run nightly from the main branch.", 0.82),
+            ("Set RUST_LOG=debug to enable verbose logging.", 0.80),
+            ("Logging levels are configured per module.", 0.78),
+            ("The office is closed on public holidays.", 0.40),
+        ]
+    }
+
+    #[test]
+    fn test_rerank_promotes_lexical_matches() {
+        let options = RerankOptions {
+            enabled: true,
+            top_n_in: 3,
+            top_k_out: 2,
+        };
+        let ranked = rerank(
+            "how do I enable debug logging",
+            candidates(),
+            &options,
+            |c| c.0,
+            |c| c.1,
+        );
+
+        // Vector order put the deploy chunk first; overlap demotes it
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked[0].0 .0.contains("RUST_LOG"));
+        assert!(ranked[1].0 .0.contains("Logging levels"));
+        assert!(ranked[0].1 >= ranked[1].1);
+    }
+
+    #[test]
+    fn test_disabled_keeps_vector_order() {
+        let ranked = rerank(
+            "debug logging",
+            candidates().into_iter().rev().collect(),
+            &RerankOptions::default(),
+            |c| c.0,
+            |c| c.1,
+        );
+        assert_eq!(ranked.len(), 4);
+        assert_eq!(ranked[0].1, 0.82);
+        assert_eq!(ranked[3].1, 0.40);
+    }
+}
@@ -63,6 +63,7 @@ impl RagSource for ConversationHistorySource {
             messages,
             system_prompt_section: None, // Conversation goes in messages, not system prompt
             metadata: Default::default(),
+            recall_scores: Vec::new(),
         }
     }
 }
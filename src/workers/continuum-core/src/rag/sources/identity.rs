@@ -65,6 +65,7 @@ impl RagSource for PersonaIdentitySource {
             messages: vec![],
             system_prompt_section: Some(system_prompt),
             metadata: Default::default(),
+            recall_scores: Vec::new(),
         }
     }
 }
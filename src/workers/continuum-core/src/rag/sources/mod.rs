@@ -91,6 +91,7 @@ impl RagSource for MockSource {
             messages: vec![],
             system_prompt_section: Some(format!("Mock content from {}", self.name)),
             metadata: Default::default(),
+            recall_scores: Vec::new(),
         }
     }
 }
@@ -3,6 +3,8 @@
 //! Single source of truth for RAG types - exported to TypeScript via ts-rs
 //! TypeScript should import from shared/generated/rag/index.ts
 
+use super::chunking::ChunkStrategy;
+use super::rerank::RerankOptions;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 use uuid::Uuid;
@@ -36,6 +38,9 @@ pub struct RagSection {
     pub messages: Vec<LlmMessage>,
     pub system_prompt_section: Option<String>,
     pub metadata: RagMetadata,
+    /// Vector-recall score of each message, for sources whose messages are
+    /// recalled chunks (reranked per `RagOptions::rerank`); empty otherwise
+    pub recall_scores: Vec<f64>,
 }
 
 /// Metadata attached to RAG sections
@@ -61,9 +66,35 @@ pub struct RagOptions {
     pub skip_semantic_search: bool,
     #[ts(optional)]
     pub current_message: Option<String>,
+    /// How documents are split when indexed
+    #[serde(default)]
+    pub chunk_strategy: ChunkStrategy,
+    /// Re-scoring of vector-recalled chunks against the query
+    #[serde(default)]
+    pub rerank: RerankOptions,
+    /// Model context window in tokens. When set, sections are packed by
+    /// priority so context + query + response reserve fit in it.
+    #[ts(optional)]
+    pub context_window: Option<usize>,
+    /// Tokens kept free for the model's response
+    #[serde(default)]
+    pub response_reserve: usize,
+    /// Target model, whose registered tokenizer counts tokens for packing
+    #[ts(optional)]
+    pub model_id: Option<String>,
 }
 
 impl RagOptions {
+    /// Rerank the best `top_n_in` recalled chunks and keep `top_k_out`
+    pub fn rerank(mut self, enabled: bool, top_n_in: usize, top_k_out: usize) -> Self {
+        self.rerank = RerankOptions {
+            enabled,
+            top_n_in,
+            top_k_out,
+        };
+        self
+    }
+
     pub fn is_voice_mode(&self) -> bool {
         self.voice_session_id.is_some()
     }
@@ -91,6 +122,9 @@ pub struct RagContext {
     pub total_tokens: usize,
     pub composition_time_ms: f64,
     pub source_timings: Vec<SourceTiming>,
+    /// Sources left out because they didn't fit in the context window
+    #[serde(default)]
+    pub dropped_sources: Vec<String>,
 }
 
 /// Budget allocation for a source (internal, not exported)
@@ -114,6 +148,22 @@ mod tests {
         assert!(opts.is_voice_mode());
     }
 
+    #[test]
+    fn test_rerank_builder() {
+        let opts = RagOptions::default();
+        assert!(!opts.rerank.enabled);
+
+        let opts = opts.rerank(true, 30, 4);
+        assert_eq!(
+            opts.rerank,
+            RerankOptions {
+                enabled: true,
+                top_n_in: 30,
+                top_k_out: 4,
+            }
+        );
+    }
+
     #[test]
     fn test_llm_message_serialization() {
         let msg = LlmMessage {
@@ -66,6 +66,14 @@ impl CommandExecutor {
         match self.execute(command, params).await? {
             CommandResult::Json(v) => Ok(v),
             CommandResult::Binary { metadata, .. } => Ok(metadata),
+            // Internal callers want the outcome, not the chunks: drain to the final one
+            CommandResult::Stream(mut chunks) => {
+                let mut last = Value::Null;
+                while let Some(chunk) = chunks.recv().await {
+                    last = chunk;
+                }
+                Ok(last)
+            }
         }
     }
 
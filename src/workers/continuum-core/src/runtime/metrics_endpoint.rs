@@ -0,0 +1,413 @@
+//! MetricsEndpoint — Prometheus scrape target over plain HTTP.
+//!
+//! Serves `GET /metrics` in the Prometheus text exposition format. Anything
+//! with numbers to report implements `MetricsSource`, and every source is
+//! read on each scrape, so values are always current and nothing samples in
+//! the background.
+//!
+//! Off unless `CONTINUUM_METRICS_PORT` is set (a port, or a full address to
+//! bind somewhere other than localhost). The HTTP handling is the minimum a
+//! scraper needs: one GET per connection.
+
+use super::server_stats::server_stats;
+use crate::live::transport::call_server::CallManager;
+use crate::live::transport::livekit_agent::LiveKitAgentManager;
+use async_trait::async_trait;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{info, warn};
+
+/// Env var enabling the endpoint: a port, or `host:port`
+pub const METRICS_PORT_ENV: &str = "CONTINUUM_METRICS_PORT";
+
+/// Request head size limit; a scrape request is a few hundred bytes
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// How long a client gets to send its request head before the connection is
+/// dropped, so idle connections don't pile up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Prometheus metric type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Gauge,
+    Counter,
+}
+
+impl MetricKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Gauge => "gauge",
+            MetricKind::Counter => "counter",
+        }
+    }
+}
+
+/// Text exposition being built for one scrape
+#[derive(Debug, Default)]
+pub struct MetricsText {
+    out: String,
+}
+
+impl MetricsText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, MetricKind::Gauge, help, &[(&[], value)]);
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, value: u64) {
+        self.family(name, MetricKind::Counter, help, &[(&[], value)]);
+    }
+
+    /// A metric with one sample per label set
+    pub fn family(
+        &mut self,
+        name: &str,
+        kind: MetricKind,
+        help: &str,
+        samples: &[(&[(&str, &str)], u64)],
+    ) {
+        let _ = writeln!(self.out, "# HELP {name} {}", help.replace('\n', " "));
+        let _ = writeln!(self.out, "# TYPE {name} {}", kind.as_str());
+        for (labels, value) in samples {
+            self.out.push_str(name);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(key, value)| format!("{key}=\"{}\"", escape_label(value)))
+                    .collect();
+                let _ = write!(self.out, "{{{}}}", labels.join(","));
+            }
+            let _ = writeln!(self.out, " {value}");
+        }
+    }
+
+    pub fn finish(self) -> String {
+        self.out
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Something that reports metrics on every scrape
+#[async_trait]
+pub trait MetricsSource: Send + Sync {
+    async fn collect(&self, out: &mut MetricsText);
+}
+
+/// Process-wide numbers from `ServerStats`
+pub struct ProcessMetrics;
+
+#[async_trait]
+impl MetricsSource for ProcessMetrics {
+    async fn collect(&self, out: &mut MetricsText) {
+        let status = server_stats().status();
+        out.gauge(
+            "continuum_uptime_seconds",
+            "Seconds since the process started",
+            status.uptime_ms / 1000,
+        );
+        out.gauge(
+            "continuum_ipc_connections",
+            "Open IPC connections",
+            status.active_connections,
+        );
+        out.gauge(
+            "continuum_ipc_queue_depth",
+            "IPC requests received but not yet answered",
+            status.queue_depth,
+        );
+        out.counter(
+            "continuum_ipc_requests_total",
+            "IPC requests received",
+            status.total_requests,
+        );
+        out.gauge(
+            "continuum_resident_memory_bytes",
+            "Resident memory of this process",
+            status.rss_bytes,
+        );
+    }
+}
+
+#[async_trait]
+impl MetricsSource for CallManager {
+    async fn collect(&self, out: &mut MetricsText) {
+        let metrics = self.metrics().await;
+        out.gauge(
+            "continuum_calls_active",
+            "Calls with a running audio loop",
+            metrics.active_calls as u64,
+        );
+        out.gauge(
+            "continuum_call_participants",
+            "Participants across all calls",
+            metrics.participants as u64,
+        );
+        out.family(
+            "continuum_call_connections",
+            MetricKind::Gauge,
+            "Call server participant connections by state",
+            &[
+                (&[("state", "connected")], metrics.connections as u64),
+                (
+                    &[("state", "reconnecting")],
+                    metrics.pending_reconnects as u64,
+                ),
+            ],
+        );
+        out.counter(
+            "continuum_call_frames_processed_total",
+            "Audio frames mixed across all calls",
+            metrics.frames_processed,
+        );
+        out.counter(
+            "continuum_call_dropped_frames_total",
+            "Audio frames skipped for participants that fell behind",
+            metrics.dropped_frames,
+        );
+        out.counter(
+            "continuum_call_dropped_utterances_total",
+            "Utterances dropped untranscribed because transcription was saturated",
+            metrics.dropped_utterances,
+        );
+        out.counter(
+            "continuum_call_av_drift_total",
+            "Times a sender's audio and video drifted apart past the sync threshold",
+            metrics.av_drift_events,
+        );
+    }
+}
+
+#[async_trait]
+impl MetricsSource for LiveKitAgentManager {
+    async fn collect(&self, out: &mut MetricsText) {
+        let (agents, listeners) = self.active_counts().await;
+        out.family(
+            "continuum_pipelines_active",
+            MetricKind::Gauge,
+            "Live voice pipelines by type",
+            &[
+                (&[("type", "tts_agent")], agents as u64),
+                (&[("type", "stt_listener")], listeners as u64),
+            ],
+        );
+        out.counter(
+            "continuum_stt_dropped_utterances_total",
+            "Utterances STT listeners dropped because transcription was saturated",
+            self.dropped_utterances(),
+        );
+        let stage_frames = self.input_stage_frames().await;
+        let samples: Vec<([(&str, &str); 1], u64)> = stage_frames
+            .iter()
+            .map(|(stage, frames)| ([("stage", stage.as_str())], *frames))
+            .collect();
+        let samples: Vec<(&[(&str, &str)], u64)> = samples
+            .iter()
+            .map(|(labels, frames)| (&labels[..], *frames))
+            .collect();
+        out.family(
+            "continuum_input_stage_frames",
+            MetricKind::Gauge,
+            "Frames processed by each input stage, over the speakers currently transcribed",
+            &samples,
+        );
+    }
+}
+
+/// Address from `CONTINUUM_METRICS_PORT`, or None when the endpoint is off
+pub fn metrics_addr_from_env() -> Option<String> {
+    listen_addr_from_env(METRICS_PORT_ENV)
+}
+
+/// Address from a port-or-`host:port` env var, or None when it's unset
+pub fn listen_addr_from_env(var: &str) -> Option<String> {
+    let value = std::env::var(var).ok()?;
+    parse_listen_addr(&value)
+}
+
+fn parse_listen_addr(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if value.contains(':') {
+        return Some(value.to_string());
+    }
+    value
+        .parse::<u16>()
+        .ok()
+        .map(|port| format!("127.0.0.1:{port}"))
+}
+
+/// Render every source into one exposition
+pub async fn render(sources: &[Arc<dyn MetricsSource>]) -> String {
+    let mut out = MetricsText::new();
+    for source in sources {
+        source.collect(&mut out).await;
+    }
+    out.finish()
+}
+
+/// Serve `GET /metrics` on `addr` until the process exits
+pub async fn serve_metrics(addr: &str, sources: Vec<Arc<dyn MetricsSource>>) -> Result<(), String> {
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|e| format!("Metrics endpoint failed to bind {addr}: {e}"))?;
+    info!("📈 Metrics endpoint listening on http://{addr}/metrics");
+
+    let sources = Arc::new(sources);
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Metrics endpoint accept failed: {e}");
+                continue;
+            }
+        };
+        let sources = sources.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle_request(stream, &sources).await {
+                warn!("Metrics request failed: {e}");
+            }
+        });
+    }
+}
+
+async fn handle_request(
+    mut stream: TcpStream,
+    sources: &[Arc<dyn MetricsSource>],
+) -> std::io::Result<()> {
+    let head = read_head(&mut stream, REQUEST_TIMEOUT).await?;
+
+    let request_line = String::from_utf8_lossy(&head);
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) if target.split('?').next() == Some("/metrics") => {
+            ("200 OK", render(sources).await)
+        }
+        (Some("GET"), Some(_)) => ("404 Not Found", "Not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "Only GET is supported\n".to_string(),
+        ),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {CONTENT_TYPE}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Read up to the end of the request head, giving up after `timeout`
+async fn read_head(stream: &mut TcpStream, timeout: Duration) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut buf = [0u8; 1024];
+    let read = async {
+        while !head.windows(4).any(|w| w == b"\r\n\r\n") && head.len() < MAX_REQUEST_BYTES {
+            let n = stream.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            head.extend_from_slice(&buf[..n]);
+        }
+        Ok::<_, std::io::Error>(())
+    };
+    tokio::time::timeout(timeout, read).await.map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            "client sent no request within the timeout",
+        )
+    })??;
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed;
+
+    #[async_trait]
+    impl MetricsSource for Fixed {
+        async fn collect(&self, out: &mut MetricsText) {
+            out.counter("test_events_total", "Events seen", 7);
+            out.family(
+                "test_queue_depth",
+                MetricKind::Gauge,
+                "Queue depth",
+                &[(&[("queue", "a\"b")], 3), (&[("queue", "c")], 0)],
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_listen_addr() {
+        assert_eq!(parse_listen_addr("9464"), Some("127.0.0.1:9464".into()));
+        assert_eq!(
+            parse_listen_addr("0.0.0.0:9464"),
+            Some("0.0.0.0:9464".into())
+        );
+        assert_eq!(parse_listen_addr(" "), None);
+        assert_eq!(parse_listen_addr("metrics"), None);
+    }
+
+    #[tokio::test]
+    async fn test_scrape_returns_text_format() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+        let sources: Vec<Arc<dyn MetricsSource>> = vec![Arc::new(Fixed)];
+        let server_addr = addr.clone();
+        tokio::spawn(async move { serve_metrics(&server_addr, sources).await });
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        let get = |path: &str| {
+            let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+            let addr = addr.clone();
+            async move {
+                let mut stream = TcpStream::connect(&addr).await.unwrap();
+                stream.write_all(request.as_bytes()).await.unwrap();
+                let mut response = String::new();
+                stream.read_to_string(&mut response).await.unwrap();
+                response
+            }
+        };
+
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE test_events_total counter\ntest_events_total 7\n"));
+        assert!(response.contains("test_queue_depth{queue=\"a\\\"b\"} 3\n"));
+        assert!(response.contains("test_queue_depth{queue=\"c\"} 0\n"));
+
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+    }
+
+    #[tokio::test]
+    async fn test_idle_client_times_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _client = TcpStream::connect(addr).await.unwrap();
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        let err = read_head(&mut stream, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    }
+}
@@ -12,6 +12,9 @@
 //! - ModuleContext: Module's view of the runtime
 //! - ModuleLogger: Per-module segregated logging
 //! - ModuleMetrics: Built-in IPC performance monitoring
+//! - StageMetrics: Per-frame stage latency (p50/p95) for processing chains
+//! - ServerStats: Process-wide IPC telemetry (connections, queue depth, RSS)
+//! - MetricsEndpoint: Prometheus `/metrics` scrape target
 //! - RuntimeControl: Priority adjustment API for UI
 //! - Runtime: Lifecycle orchestration
 //!
@@ -27,14 +30,17 @@ use std::sync::OnceLock;
 pub mod command_executor;
 pub mod control;
 pub mod message_bus;
+pub mod metrics_endpoint;
 pub mod module_context;
 pub mod module_logger;
 pub mod module_metrics;
 pub mod registry;
 #[allow(clippy::module_inception)]
 pub mod runtime;
+pub mod server_stats;
 pub mod service_module;
 pub mod shared_compute;
+pub mod stage_metrics;
 
 pub use command_executor::{
     execute as execute_command, execute_json as execute_command_json, executor, init_executor,
@@ -47,10 +53,13 @@ pub use module_logger::ModuleLogger;
 pub use module_metrics::{CommandTiming, ModuleMetrics, ModuleStats};
 pub use registry::ModuleRegistry;
 pub use runtime::Runtime;
+pub use server_stats::{server_stats, ServerStats, ServerStatus};
 pub use service_module::{
-    CommandResult, CommandSchema, ModuleConfig, ModulePriority, ParamSchema, ServiceModule,
+    current_connection_id, with_connection, CommandResult, CommandSchema, ModuleConfig,
+    ModulePriority, ParamSchema, ServiceModule,
 };
 pub use shared_compute::SharedCompute;
+pub use stage_metrics::{PipelineMetrics, PipelineMetricsRecorder, StageMetrics, StageStats};
 
 // ============================================================================
 // Global Logger Access
@@ -163,7 +163,7 @@ impl ModuleMetrics {
     }
 }
 
-fn percentile(sorted: &[u64], p: usize) -> u64 {
+pub(crate) fn percentile(sorted: &[u64], p: usize) -> u64 {
     if sorted.is_empty() {
         return 0;
     }
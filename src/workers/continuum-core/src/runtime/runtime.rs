@@ -184,6 +184,16 @@ impl Runtime {
         Some(result)
     }
 
+    /// Notify every module that an IPC connection closed, so connection-scoped
+    /// state (e.g. open data transactions) is released.
+    pub async fn connection_closed(&self, connection_id: u64) {
+        for name in self.registry.list_modules() {
+            if let Some(module) = self.registry.get_by_name(name) {
+                module.connection_closed(connection_id).await;
+            }
+        }
+    }
+
     /// Route a command synchronously (for use from rayon threads).
     /// Spawns async work on tokio and bridges via sync channel.
     /// This avoids "Cannot start a runtime from within a runtime" panics.
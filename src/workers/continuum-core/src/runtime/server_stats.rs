@@ -0,0 +1,188 @@
+//! ServerStats — process-wide IPC telemetry for health/status.
+//!
+//! The IPC server counts connections and requests here as they come and go;
+//! the health module reports them with uptime and resident memory, so a
+//! supervisor can make load decisions from real numbers.
+//! TypeScript types generated via ts-rs.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use ts_rs::TS;
+
+static SERVER_STATS: Lazy<ServerStats> = Lazy::new(ServerStats::new);
+
+/// The process's stats, shared by the IPC server and the health module
+pub fn server_stats() -> &'static ServerStats {
+    &SERVER_STATS
+}
+
+pub struct ServerStats {
+    started_at: Instant,
+    active_connections: AtomicU64,
+    in_flight_requests: AtomicU64,
+    total_requests: AtomicU64,
+}
+
+/// Snapshot returned by health-status
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/runtime/ServerStatus.ts"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerStatus {
+    pub uptime_ms: u64,
+    /// Open IPC connections
+    pub active_connections: u64,
+    /// Requests received but not yet answered (streams count until they end)
+    pub queue_depth: u64,
+    /// Requests received since startup
+    pub total_requests: u64,
+    /// Resident memory of this process; 0 where it can't be read
+    pub rss_bytes: u64,
+}
+
+/// Decrements its counter on drop, however the connection or request ends
+pub struct ActiveGuard(&'static AtomicU64);
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl ServerStats {
+    fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            active_connections: AtomicU64::new(0),
+            in_flight_requests: AtomicU64::new(0),
+            total_requests: AtomicU64::new(0),
+        }
+    }
+
+    /// Count a connection as active until the returned guard is dropped
+    pub fn track_connection(&'static self) -> ActiveGuard {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ActiveGuard(&self.active_connections)
+    }
+
+    /// Count a request, and as queued until the returned guard is dropped
+    pub fn track_request(&'static self) -> ActiveGuard {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        self.in_flight_requests.fetch_add(1, Ordering::Relaxed);
+        ActiveGuard(&self.in_flight_requests)
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        ServerStatus {
+            uptime_ms: self.started_at.elapsed().as_millis() as u64,
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+            queue_depth: self.in_flight_requests.load(Ordering::Relaxed),
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+            rss_bytes: current_rss_bytes(),
+        }
+    }
+}
+
+/// Current process RSS in bytes, via the macOS task_info API.
+/// Returns actual resident memory (not peak like getrusage ru_maxrss).
+#[cfg(target_os = "macos")]
+pub fn current_rss_bytes() -> u64 {
+    #[repr(C)]
+    struct MachTaskBasicInfo {
+        virtual_size: u64,
+        resident_size: u64,
+        resident_size_max: u64,
+        user_time_seconds: u32,
+        user_time_microseconds: u32,
+        system_time_seconds: u32,
+        system_time_microseconds: u32,
+        policy: i32,
+        suspend_count: i32,
+    }
+
+    extern "C" {
+        fn mach_task_self() -> u32;
+        fn task_info(
+            target_task: u32,
+            flavor: u32,
+            task_info: *mut MachTaskBasicInfo,
+            task_info_count: *mut u32,
+        ) -> i32;
+    }
+
+    const MACH_TASK_BASIC_INFO: u32 = 20;
+
+    unsafe {
+        let mut info: MachTaskBasicInfo = std::mem::zeroed();
+        let mut count =
+            (std::mem::size_of::<MachTaskBasicInfo>() / std::mem::size_of::<u32>()) as u32;
+        let kr = task_info(
+            mach_task_self(),
+            MACH_TASK_BASIC_INFO,
+            &mut info,
+            &mut count,
+        );
+        if kr == 0 {
+            info.resident_size
+        } else {
+            0
+        }
+    }
+}
+
+/// Current process RSS in bytes, from /proc/self/statm (resident pages).
+#[cfg(target_os = "linux")]
+pub fn current_rss_bytes() -> u64 {
+    let Ok(statm) = std::fs::read_to_string("/proc/self/statm") else {
+        return 0;
+    };
+    let pages = statm
+        .split_whitespace()
+        .nth(1)
+        .and_then(|pages| pages.parse::<u64>().ok())
+        .unwrap_or(0);
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    pages * page_size.max(0) as u64
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub fn current_rss_bytes() -> u64 {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guards_track_active_counts() {
+        let stats: &'static ServerStats = Box::leak(Box::new(ServerStats::new()));
+
+        let connection = stats.track_connection();
+        let first = stats.track_request();
+        let second = stats.track_request();
+        let status = stats.status();
+        assert_eq!(status.active_connections, 1);
+        assert_eq!(status.queue_depth, 2);
+        assert_eq!(status.total_requests, 2);
+
+        drop(first);
+        drop(connection);
+        let status = stats.status();
+        assert_eq!(status.active_connections, 0);
+        assert_eq!(status.queue_depth, 1);
+        assert_eq!(status.total_requests, 2);
+        drop(second);
+        assert_eq!(stats.status().queue_depth, 0);
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    #[test]
+    fn test_rss_is_reported() {
+        assert!(current_rss_bytes() > 0);
+    }
+}
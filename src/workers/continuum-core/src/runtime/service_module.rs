@@ -102,7 +102,7 @@ pub struct ModuleConfig {
 }
 
 /// Result of handling a command.
-/// Supports both JSON-only and binary responses (audio, embeddings).
+/// Supports JSON-only, binary (audio, embeddings) and streamed responses.
 #[derive(Debug)]
 pub enum CommandResult {
     /// Standard JSON response
@@ -112,6 +112,14 @@ pub enum CommandResult {
     /// Wire format: [JSON header bytes][\0][raw binary bytes]
     /// Used for audio synthesis, embedding vectors, etc.
     Binary { metadata: Value, data: Vec<u8> },
+
+    /// Streamed response: each JSON chunk is sent to the client as its own
+    /// frame (same requestId) as soon as it arrives. The stream ends when the
+    /// sender is dropped; producers mark the last chunk (e.g. `"done": true`).
+    /// If the client goes away the receiver is dropped, so producers should
+    /// treat a failed send as cancellation. The channel is bounded, so a slow
+    /// client applies backpressure instead of letting chunks pile up.
+    Stream(tokio::sync::mpsc::Receiver<Value>),
 }
 
 impl CommandResult {
@@ -124,6 +132,22 @@ impl CommandResult {
     }
 }
 
+tokio::task_local! {
+    static CONNECTION_ID: u64;
+}
+
+/// Run `command` on behalf of IPC connection `connection_id`, so modules can
+/// scope state to that client via `current_connection_id()`.
+pub async fn with_connection<F: std::future::Future>(connection_id: u64, command: F) -> F::Output {
+    CONNECTION_ID.scope(connection_id, command).await
+}
+
+/// IPC connection the command being handled arrived on. None outside IPC
+/// (FFI, the command executor, tests).
+pub fn current_connection_id() -> Option<u64> {
+    CONNECTION_ID.try_with(|id| *id).ok()
+}
+
 /// The ONE trait. Implement this and register — done.
 ///
 /// Every module in the system implements ServiceModule. The runtime:
@@ -164,6 +188,11 @@ pub trait ServiceModule: Send + Sync + Any {
         Ok(())
     }
 
+    /// An IPC client disconnected. Requests from that client ran with its id
+    /// as `current_connection_id()`; release any state scoped to it (open
+    /// transactions, cursors). Default: no-op.
+    async fn connection_closed(&self, _connection_id: u64) {}
+
     /// Self-adjusting priority (like CBAR's context-aware priority).
     /// Called periodically by the runtime. Return None to keep current priority.
     /// A module can detect context changes and adjust its own scheduling.
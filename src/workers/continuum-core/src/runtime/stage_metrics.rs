@@ -0,0 +1,165 @@
+//! StageMetrics — per-stage latency for frame-by-frame processing chains.
+//!
+//! ModuleMetrics times whole IPC commands in milliseconds; a voice chain
+//! (resample → VAD → STT) handles a frame every 20ms, so stages are timed in
+//! microseconds. Recording is two relaxed atomics plus one short lock to push
+//! into a fixed rolling window, cheap enough to leave on for every frame.
+
+use super::module_metrics::percentile;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use ts_rs::TS;
+
+/// Frames kept for percentiles (~10s of audio at 20ms frames)
+const FRAME_WINDOW_SIZE: usize = 512;
+
+/// Latency statistics for one stage
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../../shared/generated/runtime/StageStats.ts")]
+#[serde(rename_all = "camelCase")]
+pub struct StageStats {
+    pub stage: String,
+    #[ts(type = "number")]
+    pub frames_processed: u64,
+    #[ts(type = "number")]
+    pub total_processing_us: u64,
+    #[ts(type = "number")]
+    pub avg_us: u64,
+    #[ts(type = "number")]
+    pub p50_us: u64,
+    #[ts(type = "number")]
+    pub p95_us: u64,
+}
+
+/// Snapshot of every stage in a chain, in chain order
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../../shared/generated/runtime/PipelineMetrics.ts"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineMetrics {
+    pub stages: Vec<StageStats>,
+}
+
+/// Per-frame timing recorder for one stage
+pub struct StageMetrics {
+    name: String,
+    frames: AtomicU64,
+    total_us: AtomicU64,
+    window: Mutex<VecDeque<u64>>,
+}
+
+impl StageMetrics {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            frames: AtomicU64::new(0),
+            total_us: AtomicU64::new(0),
+            window: Mutex::new(VecDeque::with_capacity(FRAME_WINDOW_SIZE)),
+        }
+    }
+
+    /// Run one frame through `f`, recording how long it took
+    pub fn time<R>(&self, f: impl FnOnce() -> R) -> R {
+        let started = Instant::now();
+        let result = f();
+        self.record(started.elapsed());
+        result
+    }
+
+    /// Record one frame's processing time (for async stages timed by the caller)
+    pub fn record(&self, elapsed: Duration) {
+        let us = elapsed.as_micros() as u64;
+        self.frames.fetch_add(1, Ordering::Relaxed);
+        self.total_us.fetch_add(us, Ordering::Relaxed);
+
+        let mut window = self.window.lock();
+        if window.len() == FRAME_WINDOW_SIZE {
+            window.pop_front();
+        }
+        window.push_back(us);
+    }
+
+    pub fn stats(&self) -> StageStats {
+        let frames = self.frames.load(Ordering::Relaxed);
+        let total_us = self.total_us.load(Ordering::Relaxed);
+        let mut recent: Vec<u64> = self.window.lock().iter().copied().collect();
+        recent.sort_unstable();
+
+        StageStats {
+            stage: self.name.clone(),
+            frames_processed: frames,
+            total_processing_us: total_us,
+            avg_us: total_us.checked_div(frames).unwrap_or(0),
+            p50_us: percentile(&recent, 50),
+            p95_us: percentile(&recent, 95),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Ordered set of StageMetrics for one processing chain
+#[derive(Default)]
+pub struct PipelineMetricsRecorder {
+    stages: Mutex<Vec<Arc<StageMetrics>>>,
+}
+
+impl PipelineMetricsRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recorder for a stage, created on first use (appended to chain order)
+    pub fn stage(&self, name: &str) -> Arc<StageMetrics> {
+        let mut stages = self.stages.lock();
+        if let Some(existing) = stages.iter().find(|s| s.name() == name) {
+            return existing.clone();
+        }
+        let metrics = Arc::new(StageMetrics::new(name));
+        stages.push(metrics.clone());
+        metrics
+    }
+
+    pub fn metrics(&self) -> PipelineMetrics {
+        PipelineMetrics {
+            stages: self.stages.lock().iter().map(|s| s.stats()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stage_latency_stats() {
+        let recorder = PipelineMetricsRecorder::new();
+        let resample = recorder.stage("resample");
+        let stt = recorder.stage("stt");
+
+        for us in 1..=100 {
+            resample.record(Duration::from_micros(us));
+        }
+        assert_eq!(stt.time(|| 42), 42);
+        assert!(Arc::ptr_eq(&resample, &recorder.stage("resample")));
+
+        let metrics = recorder.metrics();
+        assert_eq!(metrics.stages.len(), 2);
+        let stats = &metrics.stages[0];
+        assert_eq!(stats.stage, "resample");
+        assert_eq!(stats.frames_processed, 100);
+        assert_eq!(stats.total_processing_us, 5050);
+        assert_eq!(stats.avg_us, 50);
+        assert_eq!(stats.p50_us, 51);
+        assert_eq!(stats.p95_us, 96);
+        assert_eq!(metrics.stages[1].frames_processed, 1);
+    }
+}
@@ -216,6 +216,11 @@ impl<'a> Params<'a> {
         self.str_opt_alias(key1, key2).map(String::from)
     }
 
+    /// Optional bool with alias fallback.
+    pub fn bool_opt_alias(&self, key1: &str, key2: &str) -> Option<bool> {
+        self.bool_opt(key1).or_else(|| self.bool_opt(key2))
+    }
+
     // ================================================================
     // Raw access
     // ================================================================
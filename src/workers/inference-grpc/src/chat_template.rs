@@ -0,0 +1,244 @@
+/**
+ * Chat Template Rendering
+ *
+ * Formats chat messages into a prompt using the model's own Jinja chat
+ * template (`chat_template` in tokenizer_config.json), the same way
+ * HuggingFace transformers does, so callers don't hand-build Llama-3 vs
+ * Qwen vs Gemma markup. Models without a template get a Llama-3 one.
+ */
+use hf_hub::api::sync::{Api, ApiRepo};
+use log::{info, warn};
+use minijinja::{Environment, Error, ErrorKind};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::inference::ChatMessage;
+
+/// Llama 3 format (the default model family), used when a model ships no template
+const DEFAULT_TEMPLATE: &str = "{{ bos_token }}\
+{% for message in messages %}\
+<|start_header_id|>{{ message.role }}<|end_header_id|>\n\n{{ message.content | trim }}<|eot_id|>\
+{% endfor %}\
+{% if add_generation_prompt %}<|start_header_id|>assistant<|end_header_id|>\n\n{% endif %}";
+
+const DEFAULT_BOS_TOKEN: &str = "<|begin_of_text|>";
+const DEFAULT_EOS_TOKEN: &str = "<|eot_id|>";
+
+#[derive(Serialize)]
+struct TemplateMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+/// A model's chat template plus the special tokens it refers to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatTemplate {
+    pub template: String,
+    pub bos_token: String,
+    pub eos_token: String,
+}
+
+impl Default for ChatTemplate {
+    fn default() -> Self {
+        Self {
+            template: DEFAULT_TEMPLATE.to_string(),
+            bos_token: DEFAULT_BOS_TOKEN.to_string(),
+            eos_token: DEFAULT_EOS_TOKEN.to_string(),
+        }
+    }
+}
+
+impl ChatTemplate {
+    /// Parse tokenizer_config.json. Returns None when it has no template.
+    pub fn from_tokenizer_config(config_json: &str) -> Result<Option<Self>, String> {
+        let config: Value = serde_json::from_str(config_json)
+            .map_err(|e| format!("Invalid tokenizer_config.json: {e}"))?;
+
+        // Either a single template or a list of named ones
+        let template = match config.get("chat_template") {
+            Some(Value::String(template)) => template.clone(),
+            Some(Value::Array(named)) => {
+                let Some(template) = named
+                    .iter()
+                    .find(|t| t.get("name").and_then(Value::as_str) == Some("default"))
+                    .or_else(|| named.first())
+                    .and_then(|t| t.get("template"))
+                    .and_then(Value::as_str)
+                else {
+                    return Ok(None);
+                };
+                template.to_string()
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            template,
+            bos_token: special_token(&config, "bos_token").unwrap_or_default(),
+            eos_token: special_token(&config, "eos_token").unwrap_or_default(),
+        }))
+    }
+
+    /// Load the template from a model repo, falling back to the default
+    /// when the repo has no tokenizer_config.json or no template in it
+    pub fn from_repo(repo: &ApiRepo) -> Self {
+        let loaded = repo
+            .get("tokenizer_config.json")
+            .map_err(|e| e.to_string())
+            .and_then(|path| std::fs::read_to_string(path).map_err(|e| e.to_string()))
+            .and_then(|json| Self::from_tokenizer_config(&json));
+
+        match loaded {
+            Ok(Some(template)) => {
+                info!("  Chat template loaded from tokenizer_config.json");
+                template
+            }
+            Ok(None) => {
+                info!("  No chat template in tokenizer_config.json, using default");
+                Self::default()
+            }
+            Err(e) => {
+                warn!("  Chat template unavailable ({e}), using default");
+                Self::default()
+            }
+        }
+    }
+
+    /// Load the template for a HuggingFace repo ID (see `from_repo`)
+    pub fn from_hub(repo_id: &str) -> Self {
+        match Api::new() {
+            Ok(api) => Self::from_repo(&api.model(repo_id.to_string())),
+            Err(e) => {
+                warn!("  Chat template unavailable ({e}), using default");
+                Self::default()
+            }
+        }
+    }
+
+    /// Render messages into a prompt ending with the assistant turn header.
+    ///
+    /// A leading BOS token is removed: the tokenizer adds its own when it
+    /// encodes the prompt, and two in a row degrade output.
+    pub fn render(&self, messages: &[ChatMessage]) -> Result<String, String> {
+        let mut env = Environment::new();
+        // Matches transformers' template environment
+        env.set_trim_blocks(true);
+        env.set_lstrip_blocks(true);
+        env.set_unknown_method_callback(minijinja_contrib::pycompat::unknown_method_callback);
+        env.add_function(
+            "raise_exception",
+            |message: String| -> Result<String, Error> {
+                Err(Error::new(ErrorKind::InvalidOperation, message))
+            },
+        );
+
+        let messages: Vec<TemplateMessage> = messages
+            .iter()
+            .map(|m| TemplateMessage {
+                role: &m.role,
+                content: &m.content,
+            })
+            .collect();
+
+        let prompt = env
+            .render_str(
+                &self.template,
+                minijinja::context! {
+                    messages => messages,
+                    add_generation_prompt => true,
+                    bos_token => self.bos_token,
+                    eos_token => self.eos_token,
+                },
+            )
+            .map_err(|e| format!("Chat template failed: {e}"))?;
+
+        Ok(match prompt.strip_prefix(self.bos_token.as_str()) {
+            Some(rest) if !self.bos_token.is_empty() => rest.to_string(),
+            _ => prompt,
+        })
+    }
+}
+
+/// Special tokens are either a plain string or an AddedToken object
+fn special_token(config: &Value, key: &str) -> Option<String> {
+    match config.get(key)? {
+        Value::String(token) => Some(token.clone()),
+        Value::Object(token) => token.get("content")?.as_str().map(str::to_string),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_default_template_is_llama3() {
+        let prompt = ChatTemplate::default()
+            .render(&[msg("system", "Be brief."), msg("user", "What is 2+2? ")])
+            .unwrap();
+        assert_eq!(
+            prompt,
+            "<|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nWhat is 2+2?<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_config_template() {
+        // ChatML-style template, as shipped by Qwen
+        let config = r#"{
+            "bos_token": null,
+            "eos_token": {"content": "<|im_end|>", "lstrip": false},
+            "chat_template": "{% for message in messages %}{{ '<|im_start|>' + message['role'] + '\n' + message['content'] + '<|im_end|>' + '\n' }}{% endfor %}{% if add_generation_prompt %}{{ '<|im_start|>assistant\n' }}{% endif %}"
+        }"#;
+        let template = ChatTemplate::from_tokenizer_config(config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(template.eos_token, "<|im_end|>");
+
+        let prompt = template.render(&[msg("user", "Hi")]).unwrap();
+        assert_eq!(
+            prompt,
+            "<|im_start|>user\nHi<|im_end|>\n<|im_start|>assistant\n"
+        );
+
+        // No template: caller falls back to the default
+        assert!(
+            ChatTemplate::from_tokenizer_config(r#"{"bos_token": "<s>"}"#)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_template_exceptions_and_python_methods() {
+        // Gemma-style: rejects system roles, uses Python string methods
+        let template = ChatTemplate {
+            template: "{{ bos_token }}{% for message in messages %}\
+                {% if message['role'] == 'system' %}{{ raise_exception('System role not supported') }}{% endif %}\
+                <start_of_turn>{{ message['role'] }}\n{{ message['content'].strip() }}<end_of_turn>\n\
+                {% endfor %}{{ '<start_of_turn>model\n' }}"
+                .to_string(),
+            bos_token: "<bos>".to_string(),
+            eos_token: "<eos>".to_string(),
+        };
+
+        let prompt = template.render(&[msg("user", "  Hello  ")]).unwrap();
+        assert_eq!(
+            prompt,
+            "<start_of_turn>user\nHello<end_of_turn>\n<start_of_turn>model\n"
+        );
+
+        let err = template.render(&[msg("system", "x")]).unwrap_err();
+        assert!(err.contains("System role not supported"), "{err}");
+    }
+}
@@ -0,0 +1,186 @@
+//! Compute device selection
+//!
+//! Models go on the first device available of CUDA (built with `cuda`),
+//! Metal (built with `metal`), then CPU. A silent CPU fallback only shows up
+//! as inference being an order of magnitude slower, so the device and dtype
+//! a model ended up on are reported in Ping and LoadModel responses.
+//!
+//! Overrides, from env or `~/.continuum/config.env`:
+//! - `INFERENCE_FORCE_CPU=1` skips GPU probing entirely
+//! - `INFERENCE_DEVICE_INDEX=N` picks the GPU on multi-GPU boxes
+//!
+//! A LoadModel request can override both for the model it loads. Only the
+//! default device falls back to CPU; an explicitly chosen GPU that isn't
+//! there is an error.
+
+use candle_core::{DType, Device, DeviceLocation};
+use log::info;
+use std::fs;
+use std::path::PathBuf;
+
+/// Which device to load onto
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeviceRequest {
+    /// Load on CPU even when a GPU is available
+    pub force_cpu: bool,
+    /// GPU ordinal (CUDA or Metal); None takes GPU 0, or CPU without one
+    pub device_index: Option<usize>,
+}
+
+impl DeviceRequest {
+    /// Overrides from the environment, falling back to config.env
+    pub fn from_env() -> Self {
+        let config = read_config_env();
+        let lookup = |key: &str| {
+            std::env::var(key).ok().or_else(|| {
+                config
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, v)| v.clone())
+            })
+        };
+        Self::parse(
+            lookup("INFERENCE_FORCE_CPU").as_deref(),
+            lookup("INFERENCE_DEVICE_INDEX").as_deref(),
+        )
+    }
+
+    fn parse(force_cpu: Option<&str>, device_index: Option<&str>) -> Self {
+        let force_cpu = force_cpu
+            .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        let device_index = device_index.and_then(|v| v.trim().parse().ok());
+        Self {
+            force_cpu,
+            device_index,
+        }
+    }
+
+    /// Apply per-request overrides on top of this one
+    pub fn with_overrides(self, force_cpu: bool, device_index: Option<u32>) -> Self {
+        Self {
+            force_cpu: self.force_cpu || force_cpu,
+            device_index: device_index.map(|i| i as usize).or(self.device_index),
+        }
+    }
+}
+
+/// `KEY=value` lines from `~/.continuum/config.env`
+fn read_config_env() -> Vec<(String, String)> {
+    let config_path = dirs::home_dir()
+        .map(|h| h.join(".continuum/config.env"))
+        .unwrap_or_else(|| PathBuf::from(".continuum/config.env"));
+    let Ok(content) = fs::read_to_string(&config_path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| line.trim().split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+/// Select a compute device: CUDA > Metal > CPU, unless overridden.
+/// Errors when `device_index` names a GPU that isn't available.
+pub fn select_device(request: DeviceRequest) -> Result<Device, String> {
+    if request.force_cpu {
+        info!("  Using CPU (forced)");
+        return Ok(Device::Cpu);
+    }
+    #[cfg(any(feature = "cuda", feature = "metal"))]
+    let index = request.device_index.unwrap_or(0);
+
+    #[cfg(feature = "cuda")]
+    {
+        match Device::new_cuda(index) {
+            Ok(device) => {
+                info!("  Using CUDA device {index}");
+                return Ok(device);
+            }
+            Err(e) => info!("  CUDA device {index} not available: {e}"),
+        }
+    }
+
+    #[cfg(feature = "metal")]
+    {
+        match Device::new_metal(index) {
+            Ok(device) => {
+                info!("  Using Metal device {index}");
+                return Ok(device);
+            }
+            Err(e) => info!("  Metal device {index} not available: {e}"),
+        }
+    }
+
+    if let Some(index) = request.device_index {
+        return Err(format!("GPU device {index} is not available"));
+    }
+    info!("  ⚠️ Using CPU (no GPU acceleration)");
+    Ok(Device::Cpu)
+}
+
+/// Short device name for responses: "cuda:0", "metal:0", "cpu"
+pub fn device_label(device: &Device) -> String {
+    match device.location() {
+        DeviceLocation::Cpu => "cpu".to_string(),
+        DeviceLocation::Cuda { gpu_id } => format!("cuda:{gpu_id}"),
+        DeviceLocation::Metal { gpu_id } => format!("metal:{gpu_id}"),
+    }
+}
+
+/// Lowercase dtype name for responses: "bf16", "f32"
+pub fn dtype_label(dtype: DType) -> String {
+    format!("{dtype:?}").to_lowercase()
+}
+
+/// Where a loaded model runs, as reported to clients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub device: String,
+    pub dtype: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_device_request() {
+        assert_eq!(DeviceRequest::parse(None, None), DeviceRequest::default());
+        assert_eq!(
+            DeviceRequest::parse(Some("true"), Some(" 1 ")),
+            DeviceRequest {
+                force_cpu: true,
+                device_index: Some(1),
+            }
+        );
+        assert!(!DeviceRequest::parse(Some("0"), Some("gpu")).force_cpu);
+
+        let request = DeviceRequest::default().with_overrides(false, Some(2));
+        assert_eq!(request.device_index, Some(2));
+        assert!(request.with_overrides(true, None).force_cpu);
+    }
+
+    #[test]
+    fn test_forced_cpu_labels() {
+        let device = select_device(DeviceRequest {
+            force_cpu: true,
+            device_index: Some(0),
+        })
+        .unwrap();
+        assert_eq!(device_label(&device), "cpu");
+        assert_eq!(dtype_label(DType::BF16), "bf16");
+    }
+
+    #[cfg(not(any(feature = "cuda", feature = "metal")))]
+    #[test]
+    fn test_missing_gpu_is_an_error_only_when_requested() {
+        let device = select_device(DeviceRequest::default()).unwrap();
+        assert_eq!(device_label(&device), "cpu");
+        assert!(select_device(DeviceRequest {
+            force_cpu: false,
+            device_index: Some(1),
+        })
+        .is_err());
+    }
+}
@@ -42,10 +42,13 @@ pub async fn handle_load_adapter(
         }));
     }
 
-    // Get device and dtype from current model
-    let state = service.state.read().await;
-    let (device, dtype) = match state.as_ref() {
-        Some(model_state) => (model_state.device.clone(), model_state.dtype),
+    // Get device and dtype from the default (adapter base) model
+    let model = service.models.read().await.default_model();
+    let (device, dtype) = match &model {
+        Some(model) => {
+            let model_state = model.lock().await;
+            (model_state.device.clone(), model_state.dtype)
+        }
         None => {
             return Ok(Response::new(LoadAdapterResponse {
                 success: false,
@@ -54,7 +57,6 @@ pub async fn handle_load_adapter(
             }));
         }
     };
-    drop(state);
 
     // Load LoRA weights in blocking task
     let adapter_path_clone = adapter_path.clone();
@@ -79,8 +81,8 @@ pub async fn handle_load_adapter(
             // If merge requested, rebuild model with LoRA weights
             if merge {
                 info!("  Merging LoRA weights into model...");
-                let mut state = service.state.write().await;
-                if let Some(model_state) = state.as_mut() {
+                if let Some(model) = &model {
+                    let mut model_state = model.lock().await;
                     let weight_paths = model_state.weight_paths.clone();
                     let device = model_state.device.clone();
                     let dtype = model_state.dtype;
@@ -234,8 +236,7 @@ pub async fn handle_download_adapter(
 
     // Check model is loaded
     {
-        let state = service.state.read().await;
-        if state.is_none() {
+        if service.models.read().await.is_empty() {
             return Ok(Response::new(DownloadAdapterResponse {
                 success: false,
                 error: "No model loaded - load a model first".to_string(),
@@ -264,9 +265,12 @@ pub async fn handle_download_adapter(
             let weights_path_str = downloaded.weights_path.to_string_lossy().to_string();
 
             // Now load the weights
-            let state = service.state.read().await;
-            let (device, dtype) = match state.as_ref() {
-                Some(model_state) => (model_state.device.clone(), model_state.dtype),
+            let model = service.models.read().await.default_model();
+            let (device, dtype) = match model {
+                Some(model) => {
+                    let model_state = model.lock().await;
+                    (model_state.device.clone(), model_state.dtype)
+                }
                 None => {
                     return Ok(Response::new(DownloadAdapterResponse {
                         success: false,
@@ -278,7 +282,6 @@ pub async fn handle_download_adapter(
                     }));
                 }
             };
-            drop(state);
 
             // Parse weights in blocking task
             let path_clone = weights_path_str.clone();
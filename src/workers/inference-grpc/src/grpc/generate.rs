@@ -1,24 +1,37 @@
 //! Generate handler - Text generation endpoint
 //!
+//! Streams a Token response per decoded piece of text while generating,
+//! then a final Complete with the full text and totals. GenerateChat
+//! formats chat messages with the model's chat template first.
+//!
 //! Handles inference requests with support for:
 //! - Worker pool (quantized, concurrent)
 //! - Single quantized instance (fallback)
-//! - BF16 with LoRA adapters
+//! - BF16 with LoRA adapters (one of several loaded models, by model_id)
+//! - BF16 speculative decoding, with another loaded model as the draft
 
 use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::{mpsc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex, MutexGuard, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
-use crate::inference::{generate_response, Complete, GenerateRequest, GenerateResponse};
-use crate::model::{generate_text, ModelState};
+use crate::inference::{
+    generate_response, Complete, GenerateChatRequest, GenerateRequest, GenerateResponse, Token,
+};
+use crate::json_schema::JsonSchema;
+use crate::model::{generate_text, ModelState, SamplingParams};
 use crate::priority_queue::Priority;
 use crate::quantized_model::{generate_text_quantized, QuantizedModelState};
+use crate::speculative::{
+    generate_text_speculative, DEFAULT_SPECULATION_LENGTH, MAX_SPECULATION_LENGTH,
+};
 use crate::worker_pool::WorkerPool;
 
-use super::service::ServerStats;
+use super::model::reload_if_unloaded;
+use super::service::{ModelStore, ServerStats};
 
 /// Generate text from a prompt
 ///
@@ -26,10 +39,14 @@ use super::service::ServerStats;
 /// 1. Worker pool (concurrent quantized) - best for high throughput
 /// 2. Single quantized instance - fallback when pool unavailable
 /// 3. BF16 with LoRA - when adapters are loaded
+///
+/// BF16 requests run on the model named by `model_id` (or the default model
+/// when it isn't loaded), holding only that model's lock. With
+/// `draft_model_id` they decode speculatively, holding the draft's lock too.
 pub async fn handle_generate(
     request: Request<GenerateRequest>,
     worker_pool: &Option<Arc<WorkerPool>>,
-    state: &Arc<RwLock<Option<ModelState>>>,
+    models: &Arc<RwLock<ModelStore>>,
     quantized_state: &Arc<RwLock<Option<QuantizedModelState>>>,
     stats: &Arc<ServerStats>,
     has_adapters: bool,
@@ -43,6 +60,23 @@ pub async fn handle_generate(
     } else {
         0.7
     };
+    let json_schema = if req.json_schema.is_empty() {
+        None
+    } else {
+        let schema = JsonSchema::parse(&req.json_schema).map_err(Status::invalid_argument)?;
+        Some(Arc::new(schema))
+    };
+    let sampling = SamplingParams {
+        temperature,
+        seed: req.seed,
+        json_schema,
+        timeout: req.timeout_ms.map(Duration::from_millis),
+    };
+    let draft_model_id = req.draft_model_id;
+    let speculation_length = req
+        .speculation_length
+        .map_or(DEFAULT_SPECULATION_LENGTH, |n| n as usize)
+        .min(MAX_SPECULATION_LENGTH);
 
     // Per-persona tracking (optional fields)
     let persona_name = if req.persona_name.is_empty() {
@@ -57,10 +91,17 @@ pub async fn handle_generate(
     let priority_str = format!("{:?}", priority);
 
     // Determine which backend to use
-    let has_pool = worker_pool.is_some();
-    let has_bf16 = state.read().await.is_some();
+    let use_pool = worker_pool.is_some() && !has_adapters;
+    // A model loaded before a restart comes back on its first request
+    if !use_pool && quantized_state.read().await.is_none() {
+        reload_if_unloaded(models, &model_id).await?;
+        if !draft_model_id.is_empty() {
+            reload_if_unloaded(models, &draft_model_id).await?;
+        }
+    }
+    let has_bf16 = !models.read().await.is_empty();
 
-    let backend = if has_pool && !has_adapters {
+    let backend = if use_pool {
         "pool"
     } else if has_bf16 {
         "bf16"
@@ -69,18 +110,24 @@ pub async fn handle_generate(
     };
 
     info!(
-        "🔮 Generate [{}]: model={}, prompt={} chars, max_tokens={}, temp={:.2}, backend={}, priority={}",
+        "🔮 Generate [{}]: model={}, draft={:?}, prompt={} chars, max_tokens={}, temp={:.2}, seed={:?}, json={}, timeout={:?}, backend={}, priority={}",
         persona_name,
         model_id,
+        draft_model_id,
         prompt.len(),
         max_tokens,
         temperature,
+        sampling.seed,
+        sampling.json_schema.is_some(),
+        sampling.timeout,
         backend,
         priority_str
     );
 
     let (tx, rx) = mpsc::channel(32);
-    stats.inc_pending();
+    let pending = stats.track_pending();
+    // Set by the token forwarder when the client drops the stream
+    let cancelled = Arc::new(AtomicBool::new(false));
 
     // Use worker pool for concurrent quantized inference
     if let Some(pool) = worker_pool {
@@ -90,12 +137,30 @@ pub async fn handle_generate(
             let available = pool.available_workers();
 
             info!("🏭 Using worker pool ({available} available workers)");
+            if !draft_model_id.is_empty() {
+                info!("⚠️ Speculative decoding needs BF16 models, ignoring draft {draft_model_id}");
+            }
 
             tokio::spawn(async move {
+                let _pending = pending;
                 let start = Instant::now();
+                let (token_tx, token_rx) = mpsc::unbounded_channel();
 
-                // Submit to pool and wait for response
-                let result = match pool.submit(prompt.clone(), max_tokens, temperature).await {
+                // Submit to pool, stream text until the worker finishes, then
+                // wait for the final response
+                let submitted = pool
+                    .submit(
+                        prompt.clone(),
+                        max_tokens,
+                        sampling,
+                        Some(token_tx),
+                        cancelled.clone(),
+                    )
+                    .await;
+                if submitted.is_ok() {
+                    forward_tokens(token_rx, &tx, &cancelled).await;
+                }
+                let result = match submitted {
                     Ok(rx) => match rx.await {
                         Ok(resp) => {
                             if let Some(err) = resp.error {
@@ -105,7 +170,7 @@ pub async fn handle_generate(
                                     "✅ Worker {} completed: {} tokens in {}ms",
                                     resp.worker_id, resp.tokens, resp.duration_ms
                                 );
-                                Ok((resp.text, resp.tokens))
+                                Ok((resp.text, resp.tokens, resp.truncated))
                             }
                         }
                         Err(_) => Err("Worker response channel closed".to_string()),
@@ -113,8 +178,10 @@ pub async fn handle_generate(
                     Err(e) => Err(e),
                 };
 
+                if cancelled.load(Ordering::Relaxed) {
+                    return;
+                }
                 let duration = start.elapsed().as_millis() as i32;
-                stats.dec_pending();
                 stats.inc_completed();
 
                 let response = build_response(result, duration);
@@ -129,31 +196,102 @@ pub async fn handle_generate(
     }
 
     // Fallback to single-instance mode (quantized or BF16 with LoRA)
-    let state_arc = state.clone();
     let quantized_arc = quantized_state.clone();
     let is_quantized = quantized_state.read().await.is_some();
     let stats = stats.clone();
+    let model = {
+        let models = models.read().await;
+        if !is_quantized && !model_id.is_empty() && !models.contains(&model_id) {
+            info!("⚠️ Model {model_id} not loaded, using default model");
+        }
+        models.select(&model_id)
+    };
+    let draft = if draft_model_id.is_empty() {
+        None
+    } else if is_quantized {
+        info!("⚠️ Speculative decoding needs BF16 models, ignoring draft {draft_model_id}");
+        None
+    } else {
+        let models = models.read().await;
+        if !models.contains(&draft_model_id) {
+            return Err(Status::failed_precondition(format!(
+                "Draft model {draft_model_id} not loaded"
+            )));
+        }
+        models.select(&draft_model_id)
+    };
+    if let (Some(model), Some(draft)) = (&model, &draft) {
+        if Arc::ptr_eq(model, draft) {
+            return Err(Status::invalid_argument(
+                "Draft model must differ from the model it drafts for",
+            ));
+        }
+    }
+
+    // Generation blocks its task, so text is streamed from a separate one
+    let (token_tx, token_rx) = mpsc::unbounded_channel();
+    let forwarder = {
+        let tx = tx.clone();
+        let cancelled = cancelled.clone();
+        tokio::spawn(async move { forward_tokens(token_rx, &tx, &cancelled).await })
+    };
 
     tokio::spawn(async move {
+        let _pending = pending;
         let start = Instant::now();
+        let mut on_token = |text: &str, tokens: usize| {
+            let _ = token_tx.send((text.to_string(), tokens));
+        };
 
         // Try quantized model first, fall back to full precision
         let result = if is_quantized {
             let mut q_guard = quantized_arc.write().await;
             match q_guard.as_mut() {
-                Some(q_state) => generate_text_quantized(q_state, &prompt, max_tokens, temperature),
+                Some(q_state) => generate_text_quantized(
+                    q_state,
+                    &prompt,
+                    max_tokens,
+                    &sampling,
+                    &mut on_token,
+                    &cancelled,
+                ),
                 None => Err("Quantized model not available".to_string()),
             }
         } else {
-            let mut state_guard = state_arc.write().await;
-            match state_guard.as_mut() {
-                Some(model_state) => generate_text(model_state, &prompt, max_tokens, temperature),
-                None => Err("Model not loaded".to_string()),
+            match (model, draft) {
+                (Some(model), Some(draft)) => {
+                    let (mut target, mut draft) = lock_pair(&model, &draft).await;
+                    generate_text_speculative(
+                        &mut target,
+                        &mut draft,
+                        &prompt,
+                        max_tokens,
+                        speculation_length,
+                        &sampling,
+                        &mut on_token,
+                        &cancelled,
+                    )
+                }
+                (Some(model), None) => generate_text(
+                    &mut *model.lock().await,
+                    &prompt,
+                    max_tokens,
+                    &sampling,
+                    &mut on_token,
+                    &cancelled,
+                ),
+                (None, _) => Err("Model not loaded".to_string()),
             }
         };
 
+        // Let every Token reach the client before Complete
+        drop(token_tx);
+        let _ = forwarder.await;
+
+        if cancelled.load(Ordering::Relaxed) {
+            return;
+        }
         let duration = start.elapsed().as_millis() as i32;
-        stats.dec_pending();
         stats.inc_completed();
 
         let response = build_response(result, duration);
@@ -168,14 +306,124 @@ pub async fn handle_generate(
     Ok(Response::new(ReceiverStream::new(rx)))
 }
 
-/// Build a GenerateResponse from result
-fn build_response(result: Result<(String, usize), String>, duration_ms: i32) -> GenerateResponse {
+/// Generate a reply to chat messages
+///
+/// The prompt is rendered with the chat template of the backend
+/// `handle_generate` will pick, so the markup matches the model that runs it.
+pub async fn handle_generate_chat(
+    request: Request<GenerateChatRequest>,
+    worker_pool: &Option<Arc<WorkerPool>>,
+    models: &Arc<RwLock<ModelStore>>,
+    quantized_state: &Arc<RwLock<Option<QuantizedModelState>>>,
+    stats: &Arc<ServerStats>,
+    has_adapters: bool,
+) -> Result<Response<ReceiverStream<Result<GenerateResponse, Status>>>, Status> {
+    let req = request.into_inner();
+    if req.messages.is_empty() {
+        return Err(Status::invalid_argument("No messages"));
+    }
+
+    let rendered = match worker_pool {
+        Some(pool) if !has_adapters => pool.chat_template.render(&req.messages),
+        _ => match quantized_state.read().await.as_ref() {
+            Some(q_state) => q_state.chat_template.render(&req.messages),
+            None => {
+                reload_if_unloaded(models, &req.model_id).await?;
+                let model = models.read().await.select(&req.model_id);
+                match model {
+                    Some(model) => model.lock().await.chat_template.render(&req.messages),
+                    None => return Err(Status::failed_precondition("Model not loaded")),
+                }
+            }
+        },
+    };
+    let prompt = rendered.map_err(Status::invalid_argument)?;
+
+    let request = GenerateRequest {
+        model_id: req.model_id,
+        prompt,
+        max_tokens: req.max_tokens,
+        temperature: req.temperature,
+        persona_id: req.persona_id,
+        persona_name: req.persona_name,
+        priority: req.priority,
+        seed: req.seed,
+        json_schema: req.json_schema,
+        timeout_ms: req.timeout_ms,
+        draft_model_id: req.draft_model_id,
+        speculation_length: req.speculation_length,
+    };
+    handle_generate(
+        Request::new(request),
+        worker_pool,
+        models,
+        quantized_state,
+        stats,
+        has_adapters,
+    )
+    .await
+}
+
+/// Lock a target model and its draft. Locks are always taken in the same
+/// (address) order, so two requests using the same pair of models in
+/// opposite roles can't deadlock.
+async fn lock_pair<'a>(
+    target: &'a Arc<Mutex<ModelState>>,
+    draft: &'a Arc<Mutex<ModelState>>,
+) -> (MutexGuard<'a, ModelState>, MutexGuard<'a, ModelState>) {
+    if Arc::as_ptr(target) < Arc::as_ptr(draft) {
+        let target = target.lock().await;
+        (target, draft.lock().await)
+    } else {
+        let draft = draft.lock().await;
+        (target.lock().await, draft)
+    }
+}
+
+/// Send streamed (text, tokens_generated) pieces to the client as Token
+/// responses until generation closes the channel. If the client drops the
+/// stream first, sets `cancelled` so generation stops at its next token.
+async fn forward_tokens(
+    mut token_rx: mpsc::UnboundedReceiver<(String, usize)>,
+    tx: &mpsc::Sender<Result<GenerateResponse, Status>>,
+    cancelled: &AtomicBool,
+) {
+    loop {
+        tokio::select! {
+            piece = token_rx.recv() => {
+                let Some((text, tokens_generated)) = piece else {
+                    return;
+                };
+                let response = GenerateResponse {
+                    response: Some(generate_response::Response::Token(Token {
+                        text,
+                        tokens_generated: tokens_generated as i32,
+                    })),
+                };
+                if tx.send(Ok(response)).await.is_ok() {
+                    continue;
+                }
+            }
+            _ = tx.closed() => {}
+        }
+        info!("🛑 Client disconnected, cancelling generation");
+        cancelled.store(true, Ordering::Relaxed);
+        return;
+    }
+}
+
+/// Build a GenerateResponse from (text, tokens, truncated) or an error
+fn build_response(
+    result: Result<(String, usize, bool), String>,
+    duration_ms: i32,
+) -> GenerateResponse {
     match result {
-        Ok((text, tokens)) => GenerateResponse {
+        Ok((text, tokens, truncated)) => GenerateResponse {
             response: Some(generate_response::Response::Complete(Complete {
                 text,
                 tokens: tokens as i32,
                 duration_ms,
+                truncated,
             })),
         },
         Err(e) => GenerateResponse {
@@ -183,7 +431,40 @@ fn build_response(result: Result<(String, usize), String>, duration_ms: i32) ->
                 text: format!("ERROR: {e}"),
                 tokens: 0,
                 duration_ms,
+                truncated: false,
             })),
         },
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_client_disconnect_cancels_generation() {
+        let (tx, rx) = mpsc::channel(32);
+        let (token_tx, token_rx) = mpsc::unbounded_channel();
+        let cancelled = AtomicBool::new(false);
+
+        // Client drops the stream while generation is still running
+        drop(rx);
+        token_tx.send(("Hello".to_string(), 1)).unwrap();
+        forward_tokens(token_rx, &tx, &cancelled).await;
+        assert!(cancelled.load(Ordering::Relaxed));
+
+        // Normal completion: channel closes, nothing cancelled
+        let (tx, mut rx) = mpsc::channel(32);
+        let (token_tx, token_rx) = mpsc::unbounded_channel();
+        let cancelled = AtomicBool::new(false);
+        token_tx.send(("Hello".to_string(), 1)).unwrap();
+        drop(token_tx);
+        forward_tokens(token_rx, &tx, &cancelled).await;
+        assert!(!cancelled.load(Ordering::Relaxed));
+        let first = rx.recv().await.unwrap().unwrap();
+        assert!(matches!(
+            first.response,
+            Some(generate_response::Response::Token(Token { ref text, .. })) if text == "Hello"
+        ));
+    }
+}
@@ -44,26 +44,25 @@ pub async fn handle_apply_genome(
         }));
     }
 
-    // Get model state info
-    let state = service.state.read().await;
-    let (weight_paths, device, dtype, config) = match state.as_ref() {
-        Some(model_state) => (
+    // Get model state info (adapters stack onto the default model)
+    let Some(model) = service.models.read().await.default_model() else {
+        return Ok(Response::new(ApplyGenomeResponse {
+            success: false,
+            error: "No model loaded".to_string(),
+            apply_time_ms: 0,
+            adapters_applied: 0,
+            layers_merged: 0,
+        }));
+    };
+    let (weight_paths, device, dtype, config) = {
+        let model_state = model.lock().await;
+        (
             model_state.weight_paths.clone(),
             model_state.device.clone(),
             model_state.dtype,
             model_state.config.clone(),
-        ),
-        None => {
-            return Ok(Response::new(ApplyGenomeResponse {
-                success: false,
-                error: "No model loaded".to_string(),
-                apply_time_ms: 0,
-                adapters_applied: 0,
-                layers_merged: 0,
-            }));
-        }
+        )
     };
-    drop(state);
 
     // Collect adapters with weights
     let adapters = service.adapters.read().await;
@@ -110,11 +109,9 @@ pub async fn handle_apply_genome(
         Ok(Ok(new_model)) => {
             let apply_time_ms = start.elapsed().as_millis() as i64;
 
-            let mut state = service.state.write().await;
-            if let Some(model_state) = state.as_mut() {
-                model_state.model = new_model;
-                model_state.clear_cache();
-            }
+            let mut model_state = model.lock().await;
+            model_state.model = new_model;
+            model_state.clear_cache();
 
             info!(
                 "✅ Genome applied: {} adapters, {} layers in {}ms",
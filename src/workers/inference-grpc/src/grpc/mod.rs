@@ -4,7 +4,7 @@
 //!
 //! Structure:
 //! - service.rs  - InferenceService struct and constructors
-//! - generate.rs - Text generation handler
+//! - generate.rs - Text and chat generation handlers
 //! - model.rs    - Model management handlers
 //! - adapter.rs  - LoRA adapter handlers
 //! - genome.rs   - Multi-adapter stacking handler
@@ -23,13 +23,14 @@ use tonic::{Request, Response, Status};
 use crate::inference::inference_server::Inference;
 use crate::inference::{
     ApplyGenomeRequest, ApplyGenomeResponse, DownloadAdapterRequest, DownloadAdapterResponse,
-    GenerateRequest, GenerateResponse, ListAdaptersRequest, ListAdaptersResponse,
-    ListModelsRequest, ListModelsResponse, LoadAdapterRequest, LoadAdapterResponse,
-    LoadModelRequest, LoadModelResponse, PingRequest, PingResponse, StatusRequest, StatusResponse,
-    UnloadAdapterRequest, UnloadAdapterResponse, UnloadModelRequest, UnloadModelResponse,
+    GenerateChatRequest, GenerateRequest, GenerateResponse, ListAdaptersRequest,
+    ListAdaptersResponse, ListModelsRequest, ListModelsResponse, LoadAdapterRequest,
+    LoadAdapterResponse, LoadModelRequest, LoadModelResponse, PingRequest, PingResponse,
+    StatusRequest, StatusResponse, UnloadAdapterRequest, UnloadAdapterResponse, UnloadModelRequest,
+    UnloadModelResponse,
 };
 
-pub use service::InferenceService;
+pub use service::{default_state_file, InferenceService, ServerStats};
 
 #[tonic::async_trait]
 impl Inference for InferenceService {
@@ -38,7 +39,13 @@ impl Inference for InferenceService {
     // ========================================================================
 
     async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PingResponse>, Status> {
-        status::handle_ping(request, &self.state).await
+        status::handle_ping(
+            request,
+            &self.models,
+            &self.quantized_device,
+            &self.worker_pool,
+        )
+        .await
     }
 
     // ========================================================================
@@ -55,7 +62,25 @@ impl Inference for InferenceService {
         generate::handle_generate(
             request,
             &self.worker_pool,
-            &self.state,
+            &self.models,
+            &self.quantized_state,
+            &self.stats,
+            has_adapters,
+        )
+        .await
+    }
+
+    type GenerateChatStream = ReceiverStream<Result<GenerateResponse, Status>>;
+
+    async fn generate_chat(
+        &self,
+        request: Request<GenerateChatRequest>,
+    ) -> Result<Response<Self::GenerateChatStream>, Status> {
+        let has_adapters = !self.adapters.read().await.is_empty();
+        generate::handle_generate_chat(
+            request,
+            &self.worker_pool,
+            &self.models,
             &self.quantized_state,
             &self.stats,
             has_adapters,
@@ -71,21 +96,21 @@ impl Inference for InferenceService {
         &self,
         request: Request<LoadModelRequest>,
     ) -> Result<Response<LoadModelResponse>, Status> {
-        model::handle_load_model(request, &self.state).await
+        model::handle_load_model(request, &self.models).await
     }
 
     async fn unload_model(
         &self,
         request: Request<UnloadModelRequest>,
     ) -> Result<Response<UnloadModelResponse>, Status> {
-        model::handle_unload_model(request, &self.state).await
+        model::handle_unload_model(request, &self.models).await
     }
 
     async fn list_models(
         &self,
         request: Request<ListModelsRequest>,
     ) -> Result<Response<ListModelsResponse>, Status> {
-        model::handle_list_models(request, &self.state).await
+        model::handle_list_models(request, &self.models).await
     }
 
     // ========================================================================
@@ -141,7 +166,7 @@ impl Inference for InferenceService {
     ) -> Result<Response<StatusResponse>, Status> {
         status::handle_status(
             request,
-            &self.state,
+            &self.models,
             &self.adapters,
             &self.worker_pool,
             &self.stats,
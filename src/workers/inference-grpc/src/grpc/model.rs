@@ -1,45 +1,56 @@
 //! Model management handlers
 //!
 //! Handles model loading, unloading, and listing operations.
+//! Loaded models are kept side by side; loading one never evicts another.
+//! Models known from before a restart are reloaded on first use.
 
 use log::info;
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tonic::{Request, Response, Status};
 
+use crate::device::{device_label, dtype_label, DeviceInfo, DeviceRequest};
 use crate::inference::{
     ListModelsRequest, ListModelsResponse, LoadModelRequest, LoadModelResponse, ModelInfo,
     UnloadModelRequest, UnloadModelResponse,
 };
-use crate::model::{load_model_by_id, ModelState};
+use crate::model::load_model_by_id;
 
-/// Load a model by ID
+use super::service::ModelStore;
+
+/// Load a model by ID (added alongside any already loaded; reloading an
+/// already-loaded ID replaces it)
 pub async fn handle_load_model(
     request: Request<LoadModelRequest>,
-    state: &Arc<RwLock<Option<ModelState>>>,
+    models: &Arc<RwLock<ModelStore>>,
 ) -> Result<Response<LoadModelResponse>, Status> {
     let req = request.into_inner();
     let model_id = req.model_id;
+    let device = DeviceRequest::from_env().with_overrides(req.force_cpu, req.device_index);
 
-    info!("📥 LoadModel: {model_id}");
+    info!("📥 LoadModel: {model_id} ({device:?})");
     let start = Instant::now();
 
-    let result = tokio::task::spawn_blocking(move || load_model_by_id(&model_id)).await;
+    let result = tokio::task::spawn_blocking(move || load_model_by_id(&model_id, device)).await;
 
     match result {
         Ok(Ok(new_state)) => {
             let load_time_ms = start.elapsed().as_millis() as i64;
+            let memory_bytes = new_state.memory_bytes as i64;
+            let DeviceInfo { device, dtype } = new_state.device_info();
 
-            let mut state = state.write().await;
-            *state = Some(new_state);
+            let model_id = new_state.model_id.clone();
+            models.write().await.insert(new_state);
 
-            info!("✅ Model loaded in {load_time_ms}ms");
+            info!("✅ Model {model_id} loaded in {load_time_ms}ms on {device} ({dtype})");
             Ok(Response::new(LoadModelResponse {
                 success: true,
                 error: String::new(),
                 load_time_ms,
-                memory_bytes: 0,
+                memory_bytes,
+                device,
+                dtype,
             }))
         }
         Ok(Err(e)) => {
@@ -49,6 +60,8 @@ pub async fn handle_load_model(
                 error: e.to_string(),
                 load_time_ms: 0,
                 memory_bytes: 0,
+                device: String::new(),
+                dtype: String::new(),
             }))
         }
         Err(e) => {
@@ -58,51 +71,116 @@ pub async fn handle_load_model(
                 error: format!("Task join error: {e}"),
                 load_time_ms: 0,
                 memory_bytes: 0,
+                device: String::new(),
+                dtype: String::new(),
             }))
         }
     }
 }
 
-/// Unload the current model
+/// Serializes reloads, so concurrent first requests for a model load it once
+static RELOAD_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// Reload `model_id` if it was loaded before the last restart and isn't yet.
+/// Any other id is left alone (an unknown one falls back to the default model).
+pub async fn reload_if_unloaded(
+    models: &Arc<RwLock<ModelStore>>,
+    model_id: &str,
+) -> Result<(), Status> {
+    if !models.read().await.is_unloaded(model_id) {
+        return Ok(());
+    }
+    let _reloading = RELOAD_LOCK.lock().await;
+    // Another request may have reloaded it while this one waited
+    let Some(device) = models.read().await.unloaded_device(model_id) else {
+        return Ok(());
+    };
+
+    info!("🔄 Reloading {model_id} (loaded before restart, {device:?})");
+    let start = Instant::now();
+    let id = model_id.to_string();
+    let result = tokio::task::spawn_blocking(move || load_model_by_id(&id, device)).await;
+    match result {
+        Ok(Ok(state)) => {
+            models.write().await.insert(state);
+            info!(
+                "✅ Model {model_id} reloaded in {}ms",
+                start.elapsed().as_millis()
+            );
+            Ok(())
+        }
+        Ok(Err(e)) => Err(Status::unavailable(format!(
+            "Failed to reload model {model_id}: {e}"
+        ))),
+        Err(e) => Err(Status::internal(format!("Reload task failed: {e}"))),
+    }
+}
+
+/// Unload a model by ID (the default model when no ID is given)
 pub async fn handle_unload_model(
-    _request: Request<UnloadModelRequest>,
-    state: &Arc<RwLock<Option<ModelState>>>,
+    request: Request<UnloadModelRequest>,
+    models: &Arc<RwLock<ModelStore>>,
 ) -> Result<Response<UnloadModelResponse>, Status> {
-    info!("📤 UnloadModel");
+    let requested = request.into_inner().model_id;
+    info!("📤 UnloadModel: {requested}");
 
-    let mut state = state.write().await;
-    if state.is_some() {
-        *state = None;
-        info!("✅ Model unloaded");
+    let mut models = models.write().await;
+    let model_id = if requested.is_empty() {
+        models.default_id().unwrap_or_default().to_string()
+    } else {
+        requested
+    };
+
+    // In-flight requests hold their own Arc; memory is freed when they finish.
+    // A model known from before a restart is just forgotten.
+    let was_unloaded = models.is_unloaded(&model_id);
+    if models.remove(&model_id).is_some() || was_unloaded {
+        info!("✅ Model {model_id} unloaded");
         Ok(Response::new(UnloadModelResponse {
             success: true,
             error: String::new(),
         }))
-    } else {
+    } else if model_id.is_empty() {
         Ok(Response::new(UnloadModelResponse {
             success: false,
             error: "No model loaded".to_string(),
         }))
+    } else {
+        Ok(Response::new(UnloadModelResponse {
+            success: false,
+            error: format!("Model '{model_id}' not loaded"),
+        }))
     }
 }
 
-/// List loaded models
+/// List loaded models, then those known from before a restart (not loaded)
 pub async fn handle_list_models(
     _request: Request<ListModelsRequest>,
-    state: &Arc<RwLock<Option<ModelState>>>,
+    models: &Arc<RwLock<ModelStore>>,
 ) -> Result<Response<ListModelsResponse>, Status> {
-    let state = state.read().await;
+    let (loaded, unloaded) = {
+        let models = models.read().await;
+        (models.models(), models.unloaded_ids())
+    };
 
-    let models = if let Some(ref model_state) = *state {
-        vec![ModelInfo {
+    let mut models = Vec::with_capacity(loaded.len());
+    for model in loaded {
+        let model_state = model.lock().await;
+        models.push(ModelInfo {
             model_id: model_state.model_id.clone(),
             loaded: true,
-            memory_bytes: 0,
-            dtype: format!("{:?}", model_state.dtype),
-        }]
-    } else {
-        vec![]
-    };
+            memory_bytes: model_state.memory_bytes as i64,
+            dtype: dtype_label(model_state.dtype),
+            device: device_label(&model_state.device),
+        });
+    }
+    models.extend(unloaded.into_iter().map(|model_id| ModelInfo {
+        model_id,
+        loaded: false,
+        memory_bytes: 0,
+        dtype: String::new(),
+        device: String::new(),
+    }));
 
     Ok(Response::new(ListModelsResponse { models }))
 }
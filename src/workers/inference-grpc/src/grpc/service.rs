@@ -3,12 +3,22 @@
 //! The main gRPC service implementation supporting:
 //! - Worker Pool (quantized) - Multiple model instances for concurrent inference
 //! - Single Instance (BF16) - For LoRA adapter support
+//! - Multiple BF16 models side by side, selected per request by model_id
+//!
+//! Which BF16 models are loaded is persisted (ids and the device each was
+//! asked for, not weights) so a restart doesn't invalidate the model ids
+//! clients hold: models loaded before the restart come back as known but
+//! unloaded, and the first request naming one reloads it on the same device.
 
-use log::info;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 
+use crate::device::{DeviceInfo, DeviceRequest};
 use crate::lora::LoadedAdapter;
 use crate::model::ModelState;
 use crate::quantized_model::QuantizedModelState;
@@ -39,6 +49,22 @@ impl ServerStats {
     pub fn inc_completed(&self) {
         self.requests_completed.fetch_add(1, Ordering::SeqCst);
     }
+
+    /// Count a request as pending until the returned guard is dropped
+    pub fn track_pending(self: &Arc<Self>) -> PendingGuard {
+        self.inc_pending();
+        PendingGuard(self.clone())
+    }
+}
+
+/// Decrements `requests_pending` on drop, however the request ends
+/// (completed, failed, or cancelled by the client)
+pub struct PendingGuard(Arc<ServerStats>);
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        self.0.dec_pending();
+    }
 }
 
 impl Default for ServerStats {
@@ -47,15 +73,255 @@ impl Default for ServerStats {
     }
 }
 
+/// Where the ids of loaded models are persisted across restarts
+pub fn default_state_file() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(".continuum/inference-models.json"))
+        .unwrap_or_else(|| PathBuf::from(".continuum/inference-models.json"))
+}
+
+/// A known model as saved in the state file
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedModel {
+    id: String,
+    #[serde(default)]
+    force_cpu: bool,
+    #[serde(default)]
+    device_index: Option<usize>,
+}
+
+/// State file entry: files written before devices were saved hold bare ids
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedEntry {
+    Id(String),
+    Model(SavedModel),
+}
+
+/// Writes state file snapshots in the order they were taken, dropping any
+/// that a newer one overtook
+#[derive(Default)]
+struct StateWriter {
+    taken: AtomicU64,
+    written: std::sync::Mutex<u64>,
+}
+
+impl StateWriter {
+    fn write(&self, path: &Path, seq: u64, contents: &str) {
+        let mut written = self.written.lock().unwrap_or_else(|e| e.into_inner());
+        if seq <= *written {
+            return;
+        }
+        if let Err(e) = write_atomic(path, contents) {
+            warn!("Failed to save model state to {path:?}: {e}");
+        }
+        *written = seq;
+    }
+}
+
+/// Write through a temp file and rename it into place, so a crash mid-write
+/// leaves the previous file intact rather than a torn one
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+/// Loaded full-precision models keyed by model_id.
+///
+/// Each model has its own lock, so different models generate in parallel
+/// and only requests for the same model wait on each other. The first model
+/// loaded is the default: it serves requests that name no loaded model and
+/// is the base model for LoRA adapters.
+#[derive(Default)]
+pub struct ModelStore {
+    models: HashMap<String, Arc<Mutex<ModelState>>>,
+    /// Kept outside the model locks so health checks never wait on a generation
+    devices: HashMap<String, DeviceInfo>,
+    /// Device each loaded model was asked for, saved for reloads
+    requested: HashMap<String, DeviceRequest>,
+    default_id: Option<String>,
+    /// Loaded before the last restart and not reloaded since, with the
+    /// device each was loaded on
+    unloaded: HashMap<String, DeviceRequest>,
+    /// Where known model ids are saved; None keeps the store in memory only
+    state_file: Option<PathBuf>,
+    state_writer: Arc<StateWriter>,
+}
+
+impl ModelStore {
+    pub fn new(initial: Option<ModelState>) -> Self {
+        let mut store = Self::default();
+        if let Some(state) = initial {
+            store.insert(state);
+        }
+        store
+    }
+
+    /// Persist known model ids to `path`, first restoring the ones saved
+    /// there by the previous run as unloaded
+    pub fn attach_state_file(&mut self, path: PathBuf) {
+        let saved: Vec<SavedEntry> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).ok())
+            .and_then(|state| serde_json::from_value(state["models"].clone()).ok())
+            .unwrap_or_default();
+        for entry in saved {
+            let (id, device) = match entry {
+                SavedEntry::Id(id) => (id, DeviceRequest::from_env()),
+                SavedEntry::Model(m) => (
+                    m.id,
+                    DeviceRequest {
+                        force_cpu: m.force_cpu,
+                        device_index: m.device_index,
+                    },
+                ),
+            };
+            if !self.models.contains_key(&id) {
+                self.unloaded.insert(id, device);
+            }
+        }
+        if !self.unloaded.is_empty() {
+            info!(
+                "📋 {} model(s) from before restart, reloaded on first use: {:?}",
+                self.unloaded.len(),
+                self.unloaded_ids()
+            );
+        }
+        self.state_file = Some(path);
+        self.save();
+    }
+
+    /// Snapshot known models and write them to the state file. Under a
+    /// runtime the write runs on the blocking pool, since callers hold the
+    /// store lock.
+    fn save(&self) {
+        let Some(path) = self.state_file.clone() else {
+            return;
+        };
+        let mut saved: Vec<SavedModel> = self
+            .requested
+            .iter()
+            .chain(&self.unloaded)
+            .map(|(id, device)| SavedModel {
+                id: id.clone(),
+                force_cpu: device.force_cpu,
+                device_index: device.device_index,
+            })
+            .collect();
+        saved.sort_by(|a, b| a.id.cmp(&b.id));
+        let contents = serde_json::json!({ "models": saved }).to_string();
+
+        let writer = self.state_writer.clone();
+        let seq = writer.taken.fetch_add(1, Ordering::SeqCst) + 1;
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                runtime.spawn_blocking(move || writer.write(&path, seq, &contents));
+            }
+            Err(_) => writer.write(&path, seq, &contents),
+        }
+    }
+
+    /// Add a model, replacing any loaded model with the same id
+    pub fn insert(&mut self, state: ModelState) {
+        let model_id = state.model_id.clone();
+        self.default_id.get_or_insert_with(|| model_id.clone());
+        self.devices.insert(model_id.clone(), state.device_info());
+        self.requested
+            .insert(model_id.clone(), state.device_request);
+        self.unloaded.remove(&model_id);
+        self.models.insert(model_id, Arc::new(Mutex::new(state)));
+        self.save();
+    }
+
+    /// Known from before a restart but not loaded yet
+    pub fn is_unloaded(&self, model_id: &str) -> bool {
+        self.unloaded.contains_key(model_id)
+    }
+
+    /// Device an unloaded model was on before the restart
+    pub fn unloaded_device(&self, model_id: &str) -> Option<DeviceRequest> {
+        self.unloaded.get(model_id).copied()
+    }
+
+    /// Ids known from before a restart, sorted
+    pub fn unloaded_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.unloaded.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Remove a model. If it was the default, another loaded model (if any)
+    /// becomes the default. The id is forgotten across restarts too.
+    pub fn remove(&mut self, model_id: &str) -> Option<Arc<Mutex<ModelState>>> {
+        if self.unloaded.remove(model_id).is_some() {
+            self.save();
+        }
+        let removed = self.models.remove(model_id)?;
+        self.devices.remove(model_id);
+        self.requested.remove(model_id);
+        if self.default_id.as_deref() == Some(model_id) {
+            self.default_id = self.models.keys().min().cloned();
+        }
+        self.save();
+        Some(removed)
+    }
+
+    /// The model for `model_id`, or the default when it isn't loaded
+    pub fn select(&self, model_id: &str) -> Option<Arc<Mutex<ModelState>>> {
+        self.models
+            .get(model_id)
+            .or_else(|| self.default_model_ref())
+            .cloned()
+    }
+
+    pub fn default_model(&self) -> Option<Arc<Mutex<ModelState>>> {
+        self.default_model_ref().cloned()
+    }
+
+    /// Device and dtype the default model runs on
+    pub fn default_device(&self) -> Option<&DeviceInfo> {
+        self.default_id.as_ref().and_then(|id| self.devices.get(id))
+    }
+
+    pub fn default_id(&self) -> Option<&str> {
+        self.default_id.as_deref()
+    }
+
+    pub fn contains(&self, model_id: &str) -> bool {
+        self.models.contains_key(model_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.models.is_empty()
+    }
+
+    /// All loaded models, sorted by id
+    pub fn models(&self) -> Vec<Arc<Mutex<ModelState>>> {
+        let mut ids: Vec<&String> = self.models.keys().collect();
+        ids.sort();
+        ids.into_iter().map(|id| self.models[id].clone()).collect()
+    }
+
+    fn default_model_ref(&self) -> Option<&Arc<Mutex<ModelState>>> {
+        self.default_id.as_ref().and_then(|id| self.models.get(id))
+    }
+}
+
 /// Main gRPC service struct
 /// Supports both full-precision (BF16) and quantized (GGUF Q4) models
 pub struct InferenceService {
-    /// Full-precision model state (BF16) - for LoRA support
-    pub state: Arc<RwLock<Option<ModelState>>>,
+    /// Full-precision models (BF16) by model_id - for LoRA support
+    pub models: Arc<RwLock<ModelStore>>,
     /// Quantized model state (GGUF Q4_K_M) - single instance fallback
     pub quantized_state: Arc<RwLock<Option<QuantizedModelState>>>,
     /// Worker pool for concurrent quantized inference
     pub worker_pool: Option<Arc<WorkerPool>>,
+    /// Device of the single quantized instance, readable while it generates
+    pub quantized_device: Option<DeviceInfo>,
     /// Request statistics
     pub stats: Arc<ServerStats>,
     /// Loaded LoRA adapters
@@ -67,9 +333,10 @@ impl InferenceService {
     #[allow(dead_code)]
     pub fn new(state: Option<ModelState>) -> Self {
         Self {
-            state: Arc::new(RwLock::new(state)),
+            models: Arc::new(RwLock::new(ModelStore::new(state))),
             quantized_state: Arc::new(RwLock::new(None)),
             worker_pool: None,
+            quantized_device: None,
             stats: Arc::new(ServerStats::new()),
             adapters: Arc::new(RwLock::new(Vec::new())),
         }
@@ -81,7 +348,8 @@ impl InferenceService {
         quantized: Option<QuantizedModelState>,
     ) -> Self {
         Self {
-            state: Arc::new(RwLock::new(state)),
+            models: Arc::new(RwLock::new(ModelStore::new(state))),
+            quantized_device: quantized.as_ref().map(|q| q.device_info()),
             quantized_state: Arc::new(RwLock::new(quantized)),
             worker_pool: None,
             stats: Arc::new(ServerStats::new()),
@@ -97,9 +365,10 @@ impl InferenceService {
         let num_workers = pool.num_workers;
         info!("🏭 InferenceService using worker pool ({num_workers} workers)");
         Self {
-            state: Arc::new(RwLock::new(None)),
+            models: Arc::new(RwLock::new(ModelStore::default())),
             quantized_state: Arc::new(RwLock::new(None)),
             worker_pool: Some(Arc::new(pool)),
+            quantized_device: None,
             stats: Arc::new(ServerStats::new()),
             adapters: Arc::new(RwLock::new(Vec::new())),
         }
@@ -132,8 +401,7 @@ impl InferenceService {
 
         match load_result {
             Ok(Ok(new_state)) => {
-                let mut state = self.state.write().await;
-                *state = Some(new_state);
+                self.models.write().await.insert(new_state);
                 info!("✅ Switched to BF16 mode");
                 Ok(())
             }
@@ -150,3 +418,44 @@ impl InferenceService {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_ids_survive_restart() {
+        let path =
+            std::env::temp_dir().join(format!("inference-models-{}.json", std::process::id()));
+        // Bare ids from older state files load alongside ones with devices
+        std::fs::write(
+            &path,
+            r#"{"models":["org/a","org/b",{"id":"org/c","force_cpu":true,"device_index":1}]}"#,
+        )
+        .unwrap();
+        let pinned = DeviceRequest {
+            force_cpu: true,
+            device_index: Some(1),
+        };
+
+        // Restart: saved ids come back unloaded, nothing is loaded
+        let mut store = ModelStore::default();
+        store.attach_state_file(path.clone());
+        assert!(store.is_unloaded("org/a"));
+        assert_eq!(store.unloaded_ids(), vec!["org/a", "org/b", "org/c"]);
+        assert_eq!(store.unloaded_device("org/c"), Some(pinned));
+        assert!(store.is_empty());
+        assert!(store.select("org/a").is_none());
+
+        // Unloading an id that was never reloaded forgets it for good; the
+        // rewrite keeps the devices and leaves no temp file behind
+        assert!(store.remove("org/b").is_none());
+        assert!(!path.with_extension("json.tmp").exists());
+        let mut restarted = ModelStore::default();
+        restarted.attach_state_file(path.clone());
+        assert_eq!(restarted.unloaded_ids(), vec!["org/a", "org/c"]);
+        assert_eq!(restarted.unloaded_device("org/c"), Some(pinned));
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -8,22 +8,37 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
 
+use crate::device::DeviceInfo;
 use crate::inference::{
     PingRequest, PingResponse, PriorityStats as ProtoPriorityStats, StatusRequest, StatusResponse,
 };
 use crate::lora::LoadedAdapter;
-use crate::model::ModelState;
 use crate::worker_pool::WorkerPool;
 
-use super::service::ServerStats;
+use super::service::{ModelStore, ServerStats};
 
-/// Health check ping
+/// Health check ping, with the device the default model runs on
 pub async fn handle_ping(
     _request: Request<PingRequest>,
-    state: &Arc<RwLock<Option<ModelState>>>,
+    models: &Arc<RwLock<ModelStore>>,
+    quantized_device: &Option<DeviceInfo>,
+    worker_pool: &Option<Arc<WorkerPool>>,
 ) -> Result<Response<PingResponse>, Status> {
-    let state = state.read().await;
-    let model_loaded = state.is_some();
+    let (model_loaded, default_device) = {
+        let models = models.read().await;
+        (!models.is_empty(), models.default_device().cloned())
+    };
+    let DeviceInfo { device, dtype } = default_device
+        .or_else(|| quantized_device.clone())
+        .or_else(|| {
+            worker_pool
+                .as_ref()
+                .and_then(|pool| pool.device.get().cloned())
+        })
+        .unwrap_or(DeviceInfo {
+            device: String::new(),
+            dtype: String::new(),
+        });
 
     Ok(Response::new(PingResponse {
         message: if model_loaded {
@@ -35,25 +50,32 @@ pub async fn handle_ping(
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_millis() as i64,
+        device,
+        dtype,
     }))
 }
 
 /// Server status with statistics
 pub async fn handle_status(
     _request: Request<StatusRequest>,
-    state: &Arc<RwLock<Option<ModelState>>>,
+    models: &Arc<RwLock<ModelStore>>,
     adapters: &Arc<RwLock<Vec<LoadedAdapter>>>,
     worker_pool: &Option<Arc<WorkerPool>>,
     stats: &Arc<ServerStats>,
 ) -> Result<Response<StatusResponse>, Status> {
-    let state = state.read().await;
+    let (current_model, loaded) = {
+        let models = models.read().await;
+        (
+            models.default_id().unwrap_or_default().to_string(),
+            models.models(),
+        )
+    };
+    let mut memory_used_bytes = 0;
+    for model in &loaded {
+        memory_used_bytes += model.lock().await.memory_bytes as i64;
+    }
     let adapters = adapters.read().await;
 
-    let current_model = state
-        .as_ref()
-        .map(|s| s.model_id.clone())
-        .unwrap_or_default();
-
     let active_adapters: Vec<String> = adapters
         .iter()
         .filter(|a| a.active)
@@ -82,9 +104,9 @@ pub async fn handle_status(
     };
 
     Ok(Response::new(StatusResponse {
-        healthy: state.is_some() || worker_pool.is_some(),
+        healthy: !loaded.is_empty() || worker_pool.is_some(),
         current_model,
-        memory_used_bytes: 0,
+        memory_used_bytes,
         memory_total_bytes: 0,
         requests_pending,
         requests_completed,
@@ -0,0 +1,933 @@
+//! JSON-Schema Constrained Sampling
+//!
+//! Masks logits so the model can only emit JSON matching a schema, so tool
+//! call arguments parse on the first try instead of being retried until
+//! valid.
+//!
+//! The schema is compiled into a character-level pushdown automaton
+//! (`JsonMachine`). Before each sampling step every vocabulary token is run
+//! through the current state, and tokens that would break the JSON are
+//! masked to -inf. The vocabulary is walked as a trie, so tokens sharing a
+//! prefix are only checked once.
+//!
+//! Supported keywords: `type` (a name or a list of names), `properties`,
+//! `required`, `items` and string `enum`. Objects with `properties` accept
+//! only those keys (as if `additionalProperties` were false); `{}` accepts
+//! any JSON value.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use candle_core::{DType, Tensor};
+use serde_json::Value;
+use tokenizers::Tokenizer;
+
+/// Longest run of whitespace allowed between JSON tokens. Enough for
+/// pretty-printing, short enough that the model can't pad forever.
+const MAX_WHITESPACE_RUN: u8 = 32;
+
+type NodeId = usize;
+
+/// Built-in nodes; `ANY` is the union of the others
+const ANY: NodeId = 0;
+const ANY_OBJECT: NodeId = 1;
+const ANY_ARRAY: NodeId = 2;
+const ANY_STRING: NodeId = 3;
+const ANY_NUMBER: NodeId = 4;
+const BOOLEAN: NodeId = 5;
+const NULL: NodeId = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    /// `properties: None` accepts any key with any value
+    Object {
+        properties: Option<Vec<(String, NodeId)>>,
+        required: Vec<String>,
+    },
+    Array {
+        items: NodeId,
+    },
+    String {
+        allowed: Option<Vec<String>>,
+    },
+    Number {
+        integer: bool,
+    },
+    Boolean,
+    Null,
+    /// Value of any of several types (`"type": ["string", "null"]`)
+    Union(Vec<NodeId>),
+}
+
+/// A compiled JSON Schema
+#[derive(Debug)]
+pub struct JsonSchema {
+    nodes: Vec<Node>,
+    root: NodeId,
+}
+
+impl JsonSchema {
+    pub fn parse(schema_json: &str) -> Result<Self, String> {
+        let schema: Value =
+            serde_json::from_str(schema_json).map_err(|e| format!("Invalid JSON schema: {e}"))?;
+        let mut compiled = Self {
+            nodes: vec![
+                Node::Union(vec![
+                    ANY_OBJECT, ANY_ARRAY, ANY_STRING, ANY_NUMBER, BOOLEAN, NULL,
+                ]),
+                Node::Object {
+                    properties: None,
+                    required: Vec::new(),
+                },
+                Node::Array { items: ANY },
+                Node::String { allowed: None },
+                Node::Number { integer: false },
+                Node::Boolean,
+                Node::Null,
+            ],
+            root: ANY,
+        };
+        compiled.root = compiled.compile(&schema)?;
+        Ok(compiled)
+    }
+
+    fn add(&mut self, node: Node) -> NodeId {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    fn compile(&mut self, schema: &Value) -> Result<NodeId, String> {
+        let schema = match schema {
+            Value::Bool(true) => return Ok(ANY),
+            Value::Object(schema) => schema,
+            other => return Err(format!("Unsupported schema: {other}")),
+        };
+
+        if let Some(values) = schema.get("enum") {
+            let allowed = values
+                .as_array()
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_str().map(str::to_string))
+                        .collect::<Option<Vec<_>>>()
+                })
+                .ok_or("Only string enums are supported")?;
+            return Ok(self.add(Node::String {
+                allowed: Some(allowed),
+            }));
+        }
+
+        match schema.get("type") {
+            Some(Value::String(name)) => self.compile_type(name, schema),
+            Some(Value::Array(names)) => {
+                let mut alternatives = Vec::with_capacity(names.len());
+                for name in names {
+                    let name = name.as_str().ok_or("Schema type names must be strings")?;
+                    alternatives.push(self.compile_type(name, schema)?);
+                }
+                Ok(self.add(Node::Union(alternatives)))
+            }
+            Some(other) => Err(format!("Invalid schema type: {other}")),
+            None if schema.contains_key("properties") => self.compile_type("object", schema),
+            None if schema.contains_key("items") => self.compile_type("array", schema),
+            None => Ok(ANY),
+        }
+    }
+
+    fn compile_type(
+        &mut self,
+        name: &str,
+        schema: &serde_json::Map<String, Value>,
+    ) -> Result<NodeId, String> {
+        match name {
+            "object" => {
+                let properties = match schema.get("properties").and_then(Value::as_object) {
+                    Some(props) if !props.is_empty() => {
+                        let mut compiled = Vec::with_capacity(props.len());
+                        for (key, prop) in props {
+                            compiled.push((key.clone(), self.compile(prop)?));
+                        }
+                        Some(compiled)
+                    }
+                    _ => None,
+                };
+                let required: Vec<String> = schema
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .map(|keys| {
+                        keys.iter()
+                            .filter_map(|k| k.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if let Some(props) = &properties {
+                    if let Some(missing) = required
+                        .iter()
+                        .find(|key| !props.iter().any(|(name, _)| name == *key))
+                    {
+                        return Err(format!(
+                            "Required property '{missing}' is not in properties"
+                        ));
+                    }
+                }
+                Ok(self.add(Node::Object {
+                    properties,
+                    required,
+                }))
+            }
+            "array" => {
+                let items = match schema.get("items") {
+                    Some(items) => self.compile(items)?,
+                    None => ANY,
+                };
+                Ok(self.add(Node::Array { items }))
+            }
+            "string" => Ok(ANY_STRING),
+            "number" => Ok(ANY_NUMBER),
+            "integer" => Ok(self.add(Node::Number { integer: true })),
+            "boolean" => Ok(BOOLEAN),
+            "null" => Ok(NULL),
+            other => Err(format!("Unsupported schema type: {other}")),
+        }
+    }
+
+    /// Automaton positioned before the first character of the output
+    pub fn machine(self: &Arc<Self>) -> JsonMachine {
+        JsonMachine {
+            schema: self.clone(),
+            stack: vec![Frame::Value(self.root)],
+            whitespace_run: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Escape {
+    None,
+    Backslash,
+    /// Hex digits still expected after `\u`
+    Unicode(u8),
+}
+
+/// Position inside a string. `text` is only kept when the string must be
+/// one of a fixed set (enum values, object keys).
+#[derive(Debug, Clone, PartialEq)]
+struct StringState {
+    text: Option<String>,
+    escape: Escape,
+}
+
+enum StringStep {
+    Continue,
+    Close,
+    Invalid,
+}
+
+impl StringState {
+    fn new(constrained: bool) -> Self {
+        Self {
+            text: constrained.then(String::new),
+            escape: Escape::None,
+        }
+    }
+
+    /// Whether `c` leaves this state unchanged
+    fn absorbs(&self, c: char) -> bool {
+        self.text.is_none()
+            && self.escape == Escape::None
+            && !matches!(c, '"' | '\\' | '\0'..='\x1f')
+    }
+
+    fn feed<'a>(&mut self, c: char, mut allowed: impl Iterator<Item = &'a str>) -> StringStep {
+        match self.escape {
+            Escape::Backslash => match c {
+                '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' => {
+                    self.escape = Escape::None;
+                    StringStep::Continue
+                }
+                'u' => {
+                    self.escape = Escape::Unicode(4);
+                    StringStep::Continue
+                }
+                _ => StringStep::Invalid,
+            },
+            Escape::Unicode(remaining) if c.is_ascii_hexdigit() => {
+                self.escape = match remaining {
+                    1 => Escape::None,
+                    n => Escape::Unicode(n - 1),
+                };
+                StringStep::Continue
+            }
+            Escape::Unicode(_) => StringStep::Invalid,
+            Escape::None => match (c, &mut self.text) {
+                ('"', None) => StringStep::Close,
+                ('"', Some(text)) => {
+                    if allowed.any(|a| a == text.as_str()) {
+                        StringStep::Close
+                    } else {
+                        StringStep::Invalid
+                    }
+                }
+                ('\0'..='\x1f', _) => StringStep::Invalid,
+                // Escapes aren't tracked in constrained text
+                ('\\', Some(_)) => StringStep::Invalid,
+                ('\\', None) => {
+                    self.escape = Escape::Backslash;
+                    StringStep::Continue
+                }
+                (_, None) => StringStep::Continue,
+                (c, Some(text)) => {
+                    text.push(c);
+                    if allowed.any(|a| a.starts_with(text.as_str())) {
+                        StringStep::Continue
+                    } else {
+                        StringStep::Invalid
+                    }
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NumberState {
+    Minus,
+    Zero,
+    Int,
+    Dot,
+    Frac,
+    Exp,
+    ExpSign,
+    ExpDigits,
+}
+
+impl NumberState {
+    fn start(c: char) -> Option<Self> {
+        match c {
+            '-' => Some(Self::Minus),
+            '0' => Some(Self::Zero),
+            '1'..='9' => Some(Self::Int),
+            _ => None,
+        }
+    }
+
+    fn next(self, c: char, integer: bool) -> Option<Self> {
+        use NumberState::*;
+        match (self, c) {
+            (Minus, '0') => Some(Zero),
+            (Minus, '1'..='9') | (Int, '0'..='9') => Some(Int),
+            (Zero | Int, '.') if !integer => Some(Dot),
+            (Dot | Frac, '0'..='9') => Some(Frac),
+            (Zero | Int | Frac, 'e' | 'E') if !integer => Some(Exp),
+            (Exp, '+' | '-') => Some(ExpSign),
+            (Exp | ExpSign | ExpDigits, '0'..='9') => Some(ExpDigits),
+            _ => None,
+        }
+    }
+
+    fn is_complete(self) -> bool {
+        matches!(self, Self::Zero | Self::Int | Self::Frac | Self::ExpDigits)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum ObjectState {
+    /// After `{`: a key or `}`
+    Open,
+    /// After `,`: a key
+    AfterComma,
+    InKey(StringState),
+    /// After a key: `:`. Holds the property index for typed objects.
+    AfterKey(Option<usize>),
+    /// After a value: `,` or `}`
+    AfterValue,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ArrayState {
+    Open,
+    AfterComma,
+    AfterValue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Frame {
+    /// Expecting a value of this node
+    Value(NodeId),
+    Object {
+        node: NodeId,
+        /// Indices of the properties already present
+        seen: Vec<usize>,
+        state: ObjectState,
+    },
+    Array {
+        items: NodeId,
+        state: ArrayState,
+    },
+    String {
+        node: NodeId,
+        state: StringState,
+    },
+    Number {
+        integer: bool,
+        state: NumberState,
+    },
+    Literal {
+        rest: &'static str,
+    },
+}
+
+/// Result of feeding one character to the top frame
+enum Step {
+    /// Consumed as part of a value
+    Consumed,
+    /// Consumed as whitespace between tokens
+    Whitespace,
+    /// Top frame finished without consuming; feed the character again
+    Refeed,
+    Invalid,
+}
+
+/// Character-level automaton for JSON matching a `JsonSchema`
+#[derive(Debug, Clone)]
+pub struct JsonMachine {
+    schema: Arc<JsonSchema>,
+    stack: Vec<Frame>,
+    whitespace_run: u8,
+}
+
+impl JsonMachine {
+    /// Feed the characters of `text`; false (leaving the machine in an
+    /// unspecified state) if they don't continue valid JSON
+    pub fn feed_str(&mut self, text: &str) -> bool {
+        text.chars().all(|c| self.feed(c))
+    }
+
+    pub fn feed(&mut self, c: char) -> bool {
+        loop {
+            match self.step(c) {
+                Step::Consumed => {
+                    self.whitespace_run = 0;
+                    return true;
+                }
+                Step::Whitespace => {
+                    self.whitespace_run += 1;
+                    return self.whitespace_run <= MAX_WHITESPACE_RUN;
+                }
+                Step::Refeed => continue,
+                Step::Invalid => return false,
+            }
+        }
+    }
+
+    /// The output so far is a complete value (it may still continue, e.g.
+    /// more digits of a top-level number)
+    pub fn is_complete(&self) -> bool {
+        match self.stack.as_slice() {
+            [] => true,
+            [Frame::Number { state, .. }] => state.is_complete(),
+            _ => false,
+        }
+    }
+
+    /// Nothing can follow: the value is complete and closed
+    pub fn is_finished(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    /// Whether feeding `c` would leave the machine unchanged (plain
+    /// characters inside an unconstrained string)
+    fn absorbs(&self, c: char) -> bool {
+        match self.stack.last() {
+            Some(Frame::String { state, .. }) => state.absorbs(c),
+            Some(Frame::Object {
+                state: ObjectState::InKey(key),
+                ..
+            }) => key.absorbs(c),
+            _ => false,
+        }
+    }
+
+    fn step(&mut self, c: char) -> Step {
+        let schema = self.schema.clone();
+        let Some(frame) = self.stack.last_mut() else {
+            // Nothing may follow the top-level value
+            return Step::Invalid;
+        };
+        let whitespace = matches!(c, ' ' | '\t' | '\n' | '\r');
+
+        match frame {
+            Frame::Value(_) if whitespace => Step::Whitespace,
+            Frame::Value(node) => match start_value(&schema, *node, c) {
+                Some(started) => {
+                    *frame = started;
+                    Step::Consumed
+                }
+                None => Step::Invalid,
+            },
+
+            Frame::Literal { rest } => match rest.strip_prefix(c) {
+                Some(remaining) => {
+                    *rest = remaining;
+                    if remaining.is_empty() {
+                        self.stack.pop();
+                    }
+                    Step::Consumed
+                }
+                None => Step::Invalid,
+            },
+
+            Frame::Number { integer, state } => match state.next(c, *integer) {
+                Some(next) => {
+                    *state = next;
+                    Step::Consumed
+                }
+                // A number ends at the first character that can't extend it
+                None if state.is_complete() => {
+                    self.stack.pop();
+                    if self.stack.is_empty() {
+                        Step::Invalid
+                    } else {
+                        Step::Refeed
+                    }
+                }
+                None => Step::Invalid,
+            },
+
+            Frame::String { node, state } => {
+                let allowed = match &schema.nodes[*node] {
+                    Node::String {
+                        allowed: Some(allowed),
+                    } => allowed.as_slice(),
+                    _ => &[],
+                };
+                match state.feed(c, allowed.iter().map(String::as_str)) {
+                    StringStep::Continue => Step::Consumed,
+                    StringStep::Close => {
+                        self.stack.pop();
+                        Step::Consumed
+                    }
+                    StringStep::Invalid => Step::Invalid,
+                }
+            }
+
+            Frame::Array { items, state } => {
+                let items = *items;
+                match (*state, c) {
+                    _ if whitespace => Step::Whitespace,
+                    (ArrayState::Open | ArrayState::AfterValue, ']') => {
+                        self.stack.pop();
+                        Step::Consumed
+                    }
+                    (ArrayState::AfterValue, ',') => {
+                        *state = ArrayState::AfterComma;
+                        Step::Consumed
+                    }
+                    (ArrayState::Open | ArrayState::AfterComma, _) => {
+                        *state = ArrayState::AfterValue;
+                        self.stack.push(Frame::Value(items));
+                        Step::Refeed
+                    }
+                    _ => Step::Invalid,
+                }
+            }
+
+            Frame::Object { node, seen, state } => {
+                let (properties, required) = match &schema.nodes[*node] {
+                    Node::Object {
+                        properties,
+                        required,
+                    } => (properties.as_deref(), required.as_slice()),
+                    _ => unreachable!("object frame on a non-object node"),
+                };
+                match state {
+                    ObjectState::InKey(key) => match key.feed(c, unseen_keys(properties, seen)) {
+                        StringStep::Continue => Step::Consumed,
+                        StringStep::Close => {
+                            let index = key.text.as_deref().and_then(|text| {
+                                properties?.iter().position(|(name, _)| name == text)
+                            });
+                            *state = ObjectState::AfterKey(index);
+                            Step::Consumed
+                        }
+                        StringStep::Invalid => Step::Invalid,
+                    },
+                    _ if whitespace => Step::Whitespace,
+                    ObjectState::Open | ObjectState::AfterComma if c == '"' => {
+                        *state = ObjectState::InKey(StringState::new(properties.is_some()));
+                        Step::Consumed
+                    }
+                    ObjectState::Open | ObjectState::AfterValue
+                        if c == '}' && has_required(properties, required, seen) =>
+                    {
+                        self.stack.pop();
+                        Step::Consumed
+                    }
+                    // A comma needs a key left to follow it
+                    ObjectState::AfterValue
+                        if c == ','
+                            && (properties.is_none()
+                                || unseen_keys(properties, seen).next().is_some()) =>
+                    {
+                        *state = ObjectState::AfterComma;
+                        Step::Consumed
+                    }
+                    ObjectState::AfterKey(index) if c == ':' => {
+                        let value = match (*index, properties) {
+                            (Some(i), Some(props)) => {
+                                seen.push(i);
+                                props[i].1
+                            }
+                            _ => ANY,
+                        };
+                        *state = ObjectState::AfterValue;
+                        self.stack.push(Frame::Value(value));
+                        Step::Consumed
+                    }
+                    _ => Step::Invalid,
+                }
+            }
+        }
+    }
+}
+
+/// Property names not yet present in a typed object
+fn unseen_keys<'a>(
+    properties: Option<&'a [(String, NodeId)]>,
+    seen: &'a [usize],
+) -> impl Iterator<Item = &'a str> {
+    properties
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter(move |(i, _)| !seen.contains(i))
+        .map(|(_, (name, _))| name.as_str())
+}
+
+/// Whether every required property is present
+fn has_required(
+    properties: Option<&[(String, NodeId)]>,
+    required: &[String],
+    seen: &[usize],
+) -> bool {
+    required.iter().all(|key| {
+        properties
+            .into_iter()
+            .flatten()
+            .position(|(name, _)| name == key)
+            .is_some_and(|i| seen.contains(&i))
+    })
+}
+
+/// Frame for a value of `node` whose first character is `c`
+fn start_value(schema: &JsonSchema, node: NodeId, c: char) -> Option<Frame> {
+    match (&schema.nodes[node], c) {
+        (Node::Union(alternatives), _) => alternatives
+            .iter()
+            .find_map(|&alt| start_value(schema, alt, c)),
+        (Node::Object { .. }, '{') => Some(Frame::Object {
+            node,
+            seen: Vec::new(),
+            state: ObjectState::Open,
+        }),
+        (Node::Array { items }, '[') => Some(Frame::Array {
+            items: *items,
+            state: ArrayState::Open,
+        }),
+        (Node::String { allowed }, '"') => Some(Frame::String {
+            node,
+            state: StringState::new(allowed.is_some()),
+        }),
+        (Node::Number { integer }, c) => NumberState::start(c).map(|state| Frame::Number {
+            integer: *integer,
+            state,
+        }),
+        (Node::Boolean, 't') => Some(Frame::Literal { rest: "rue" }),
+        (Node::Boolean, 'f') => Some(Frame::Literal { rest: "alse" }),
+        (Node::Null, 'n') => Some(Frame::Literal { rest: "ull" }),
+        _ => None,
+    }
+}
+
+//=============================================================================
+// TOKEN MASKING
+//=============================================================================
+
+#[derive(Default)]
+struct TrieNode {
+    children: Vec<(char, u32)>,
+    /// Tokens whose text ends at this node
+    tokens: Vec<u32>,
+}
+
+/// A tokenizer's vocabulary as a character trie, built once per model
+pub struct TokenVocab {
+    nodes: Vec<TrieNode>,
+    /// Decoded text per token id (None for special or undecodable tokens)
+    texts: Vec<Option<String>>,
+}
+
+impl TokenVocab {
+    pub fn new(tokenizer: &Tokenizer) -> Self {
+        let special: HashSet<u32> = tokenizer
+            .get_added_tokens_decoder()
+            .into_iter()
+            .filter(|(_, token)| token.special)
+            .map(|(id, _)| id)
+            .collect();
+        let texts: Vec<Option<String>> = (0..tokenizer.get_vocab_size(true) as u32)
+            .map(|id| {
+                if special.contains(&id) {
+                    None
+                } else {
+                    token_text(tokenizer, id)
+                }
+            })
+            .collect();
+
+        // Sorted insertion: a shared prefix always continues the last child
+        let mut order: Vec<usize> = (0..texts.len()).filter(|&i| texts[i].is_some()).collect();
+        order.sort_by(|&a, &b| texts[a].cmp(&texts[b]));
+
+        let mut nodes = vec![TrieNode::default()];
+        for id in order {
+            let mut node = 0;
+            for c in texts[id].as_deref().unwrap_or_default().chars() {
+                node = match nodes[node].children.last() {
+                    Some(&(last, child)) if last == c => child as usize,
+                    _ => {
+                        nodes.push(TrieNode::default());
+                        let child = nodes.len() - 1;
+                        nodes[node].children.push((c, child as u32));
+                        child
+                    }
+                };
+            }
+            nodes[node].tokens.push(id as u32);
+        }
+
+        Self { nodes, texts }
+    }
+
+    /// Tokens whose whole text `machine` accepts next
+    pub fn allowed_tokens(&self, machine: &JsonMachine) -> Vec<u32> {
+        let mut allowed = Vec::new();
+        self.walk(0, machine, &mut allowed);
+        allowed
+    }
+
+    fn walk(&self, node: usize, machine: &JsonMachine, allowed: &mut Vec<u32>) {
+        for &(c, child) in &self.nodes[node].children {
+            let child = child as usize;
+            if machine.absorbs(c) {
+                allowed.extend_from_slice(&self.nodes[child].tokens);
+                self.walk(child, machine, allowed);
+                continue;
+            }
+            let mut next = machine.clone();
+            if next.feed(c) {
+                allowed.extend_from_slice(&self.nodes[child].tokens);
+                self.walk(child, &next, allowed);
+            }
+        }
+    }
+
+    fn text(&self, token: u32) -> Option<&str> {
+        self.texts.get(token as usize)?.as_deref()
+    }
+}
+
+/// Text a token contributes to the output
+fn token_text(tokenizer: &Tokenizer, id: u32) -> Option<String> {
+    let mut text = tokenizer.decode(&[id], false).ok()?;
+    // Partial UTF-8 sequences decode to the replacement character
+    if text.is_empty() || text.contains('\u{FFFD}') {
+        return None;
+    }
+    // SentencePiece decoders drop a lone token's word-boundary space
+    if !text.starts_with(' ')
+        && tokenizer
+            .id_to_token(id)
+            .is_some_and(|piece| piece.starts_with('\u{2581}'))
+    {
+        text.insert(0, ' ');
+    }
+    Some(text)
+}
+
+/// Constraint state for one generation
+pub struct JsonConstraint {
+    machine: JsonMachine,
+    vocab: Arc<TokenVocab>,
+}
+
+impl JsonConstraint {
+    pub fn new(schema: &Arc<JsonSchema>, vocab: Arc<TokenVocab>) -> Self {
+        Self {
+            machine: schema.machine(),
+            vocab,
+        }
+    }
+
+    /// Set every token the schema doesn't allow next to -inf. EOS is only
+    /// allowed once the JSON is complete.
+    pub fn mask(&self, logits: &Tensor, eos_token_ids: &[u32]) -> Result<Tensor, String> {
+        let mut allowed = self.vocab.allowed_tokens(&self.machine);
+        if self.machine.is_complete() {
+            allowed.extend_from_slice(eos_token_ids);
+        }
+        if allowed.is_empty() {
+            return Err("JSON schema constraint has no valid continuation".to_string());
+        }
+
+        let values: Vec<f32> = logits
+            .to_dtype(DType::F32)
+            .and_then(|t| t.to_vec1())
+            .map_err(|e| format!("Failed to read logits: {e}"))?;
+        let mut masked = vec![f32::NEG_INFINITY; values.len()];
+        for token in allowed {
+            if let Some(&value) = values.get(token as usize) {
+                masked[token as usize] = value;
+            }
+        }
+        Tensor::new(masked, logits.device()).map_err(|e| format!("Failed to mask logits: {e}"))
+    }
+
+    /// Advance past a sampled token
+    pub fn advance(&mut self, token: u32) -> Result<(), String> {
+        let text = self
+            .vocab
+            .text(token)
+            .ok_or_else(|| format!("Token {token} is not allowed by the JSON schema"))?;
+        if self.machine.feed_str(text) {
+            Ok(())
+        } else {
+            Err(format!("Token {text:?} breaks the JSON schema"))
+        }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.machine.is_complete()
+    }
+
+    /// The JSON value is closed; generation can stop
+    pub fn is_finished(&self) -> bool {
+        self.machine.is_finished()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokenizers::models::wordlevel::WordLevel;
+
+    fn accepts(schema: &str, text: &str) -> bool {
+        let schema = Arc::new(JsonSchema::parse(schema).unwrap());
+        let mut machine = schema.machine();
+        machine.feed_str(text) && machine.is_complete()
+    }
+
+    const TOOL_CALL: &str = r#"{
+        "type": "object",
+        "properties": {
+            "name": { "enum": ["search", "read_file"] },
+            "limit": { "type": "integer" },
+            "tags": { "type": "array", "items": { "type": "string" } },
+            "exact": { "type": ["boolean", "null"] }
+        },
+        "required": ["name"]
+    }"#;
+
+    #[test]
+    fn test_schema_shapes() {
+        assert!(accepts(
+            TOOL_CALL,
+            r#"{"name": "search", "limit": 10, "tags": ["a", "b\"c"], "exact": null}"#
+        ));
+        assert!(accepts(TOOL_CALL, "{\n  \"name\": \"read_file\"\n}"));
+
+        // Missing required key, unknown key, enum mismatch, wrong types
+        assert!(!accepts(TOOL_CALL, r#"{"limit": 1}"#));
+        assert!(!accepts(TOOL_CALL, r#"{"name": "search", "extra": 1}"#));
+        assert!(!accepts(TOOL_CALL, r#"{"name": "delete"}"#));
+        assert!(!accepts(TOOL_CALL, r#"{"name": "search", "limit": 1.5}"#));
+        assert!(!accepts(TOOL_CALL, r#"{"name": "search", "tags": [1]}"#));
+        // Duplicate key, trailing garbage, incomplete
+        assert!(!accepts(
+            TOOL_CALL,
+            r#"{"name": "search", "name": "search"}"#
+        ));
+        assert!(!accepts(TOOL_CALL, r#"{"name": "search"} x"#));
+        assert!(!accepts(TOOL_CALL, r#"{"name": "search""#));
+    }
+
+    #[test]
+    fn test_any_json() {
+        assert!(accepts("{}", r#"[1, -2.5e3, "xé", true, {"k": [null]}]"#));
+        assert!(accepts("{}", "42"));
+        assert!(!accepts("{}", "01"));
+        assert!(!accepts("{}", "[1,]"));
+        assert!(!accepts("{}", r#"{"a" 1}"#));
+
+        let schema = Arc::new(JsonSchema::parse("{}").unwrap());
+        let mut machine = schema.machine();
+        assert!(!machine.feed_str(&" ".repeat(MAX_WHITESPACE_RUN as usize + 1)));
+
+        assert!(JsonSchema::parse(r#"{"type": "date"}"#).is_err());
+        assert!(JsonSchema::parse(
+            r#"{"type": "object", "properties": {"a": {}}, "required": ["b"]}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_token_mask() {
+        let words = [
+            "{", "}", "\"", "name", "\":", " \"", "search", "\"}", "oops", "[UNK]",
+        ];
+        let vocab: HashMap<String, u32> = words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i as u32))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab.into_iter().collect())
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+        let tokenizer = Tokenizer::new(model);
+        let token = |w: &str| words.iter().position(|x| *x == w).unwrap() as u32;
+
+        let schema = Arc::new(
+            JsonSchema::parse(
+                r#"{"type": "object", "properties": {"name": {"enum": ["search"]}}, "required": ["name"]}"#,
+            )
+            .unwrap(),
+        );
+        let vocab = Arc::new(TokenVocab::new(&tokenizer));
+        let mut constraint = JsonConstraint::new(&schema, vocab.clone());
+
+        // Only `{` can open the object
+        assert_eq!(vocab.allowed_tokens(&constraint.machine), vec![token("{")]);
+
+        for word in ["{", "\"", "name", "\":", " \"", "search"] {
+            assert!(vocab
+                .allowed_tokens(&constraint.machine)
+                .contains(&token(word)));
+            constraint.advance(token(word)).unwrap();
+        }
+        // The required key is present, so the object can close
+        let allowed = vocab.allowed_tokens(&constraint.machine);
+        assert!(allowed.contains(&token("\"}")));
+        assert!(!allowed.contains(&token("oops")));
+        constraint.advance(token("\"}")).unwrap();
+        assert!(constraint.is_finished());
+
+        let logits = Tensor::new(&[1.0f32; 10], &candle_core::Device::Cpu).unwrap();
+        let masked: Vec<f32> = constraint.mask(&logits, &[9]).unwrap().to_vec1().unwrap();
+        assert_eq!(masked[9], 1.0);
+        assert!(masked[..9].iter().all(|v| v.is_infinite()));
+    }
+}
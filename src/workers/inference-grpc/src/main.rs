@@ -8,25 +8,42 @@ use log::info;
  */
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::oneshot;
 use tonic::transport::Server;
 
 mod adapter_registry;
+mod chat_template;
+mod device;
 mod grpc;
+mod json_schema;
 mod lora;
 mod model;
 mod priority_queue;
 mod quantized_model;
+mod speculative;
+mod vendored;
+#[path = "../../shared/weight_bytes.rs"]
+mod weight_bytes;
 mod worker_pool;
 
 pub mod inference {
     tonic::include_proto!("inference");
 }
 
-use grpc::InferenceService;
+use grpc::{default_state_file, InferenceService, ServerStats};
 use inference::inference_server::InferenceServer;
 use model::load_default_model;
 use worker_pool::WorkerPool;
 
+/// How long in-flight generations get to finish after SIGTERM/SIGINT
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often the pending-request count is checked while draining
+const DRAIN_POLL: Duration = Duration::from_millis(100);
+
 /// Get number of inference workers from config or auto-detect
 fn get_num_workers() -> usize {
     // Load from ~/.continuum/config.env
@@ -93,6 +110,48 @@ impl InferenceMode {
     }
 }
 
+/// Resolve with the name of the first shutdown signal received
+async fn shutdown_signal() -> &'static str {
+    let (Ok(mut term), Ok(mut int)) = (
+        signal(SignalKind::terminate()),
+        signal(SignalKind::interrupt()),
+    ) else {
+        info!("⚠️ Could not install signal handlers, graceful shutdown disabled");
+        return std::future::pending().await;
+    };
+    tokio::select! {
+        _ = term.recv() => "SIGTERM",
+        _ = int.recv() => "SIGINT",
+    }
+}
+
+/// Wait until no generation is in flight
+async fn drain_pending(stats: &ServerStats) {
+    loop {
+        let pending = stats.requests_pending.load(Ordering::SeqCst);
+        if pending == 0 {
+            return;
+        }
+        info!("⏳ Waiting for {pending} in-flight request(s)...");
+        tokio::time::sleep(DRAIN_POLL).await;
+    }
+}
+
+/// Final stats line, so the log shows what the worker did before exiting
+fn log_final_stats(stats: &ServerStats, worker_pool: &Option<Arc<WorkerPool>>) {
+    let completed = stats.requests_completed.load(Ordering::SeqCst);
+    let pending = stats.requests_pending.load(Ordering::SeqCst);
+    match worker_pool {
+        Some(pool) => {
+            let (_, _, tokens, time_ms) = pool.stats();
+            info!(
+                "📊 Shutdown: {completed} requests completed, {pending} abandoned, {tokens} tokens in {time_ms}ms"
+            );
+        }
+        None => info!("📊 Shutdown: {completed} requests completed, {pending} abandoned"),
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
@@ -168,10 +227,56 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    Server::builder()
+    // Model ids from before a restart stay valid; see ModelStore
+    service
+        .models
+        .write()
+        .await
+        .attach_state_file(default_state_file());
+
+    let stats = service.stats.clone();
+    let worker_pool = service.worker_pool.clone();
+
+    // On SIGTERM/SIGINT: stop accepting, let in-flight generations finish
+    // (up to DRAIN_TIMEOUT), log final stats, exit
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = Server::builder()
         .add_service(InferenceServer::new(service))
-        .serve(addr)
-        .await?;
+        .serve_with_shutdown(addr, async {
+            let _ = stop_rx.await;
+        });
+    tokio::pin!(server);
+
+    let signal_name = tokio::select! {
+        result = &mut server => {
+            result?;
+            return Ok(());
+        }
+        name = shutdown_signal() => name,
+    };
 
+    info!("🛑 {signal_name} received, no longer accepting requests");
+    let _ = stop_tx.send(());
+
+    let drain = async {
+        let (result, _) = tokio::join!(server, drain_pending(&stats));
+        if let Err(e) = result {
+            info!("⚠️ Server error during shutdown: {e}");
+        }
+    };
+    let drained = tokio::time::timeout(DRAIN_TIMEOUT, drain).await.is_ok();
+    if !drained {
+        info!(
+            "⚠️ Drain timed out after {}s, abandoning in-flight requests",
+            DRAIN_TIMEOUT.as_secs()
+        );
+    }
+    log_final_stats(&stats, &worker_pool);
+
+    // Abandoned generations block runtime threads, which would hang the
+    // runtime's shutdown
+    if !drained {
+        std::process::exit(0);
+    }
     Ok(())
 }
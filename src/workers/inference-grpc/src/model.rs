@@ -1,9 +1,6 @@
 use candle_core::{DType, Device, Tensor};
 use candle_nn::VarBuilder;
 use candle_transformers::generation::LogitsProcessor;
-use candle_transformers::models::llama::{
-    Cache, Config as LlamaModelConfig, Llama, LlamaConfig, LlamaEosToks,
-};
 use hf_hub::{api::sync::Api, Repo, RepoType};
 use log::{debug, info};
 use rand::Rng;
@@ -14,10 +11,17 @@ use rand::Rng;
  * Candle, and generating text with the loaded model.
  */
 use std::collections::HashMap;
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use tokenizers::Tokenizer;
 
+use crate::chat_template::ChatTemplate;
+use crate::device::{device_label, dtype_label, select_device, DeviceInfo, DeviceRequest};
+use crate::json_schema::{JsonConstraint, JsonSchema, TokenVocab};
 use crate::lora::{map_lora_name_to_model_name, merge_lora_weight, LoRAWeights};
+use crate::vendored::llama::{Cache, Config as LlamaModelConfig, Llama, LlamaConfig, LlamaEosToks};
+use crate::weight_bytes::safetensors_weight_bytes;
 
 /// Model state containing loaded model, tokenizer, and cache
 pub struct ModelState {
@@ -31,9 +35,24 @@ pub struct ModelState {
     pub model_id: String,
     /// Original weight file paths for LoRA merging
     pub weight_paths: Vec<std::path::PathBuf>,
+    /// Bytes the weights occupy on `device` at `dtype`
+    pub memory_bytes: u64,
+    /// What the load asked for, so a reload after restart lands on the same device
+    pub device_request: DeviceRequest,
+    /// Formats chat messages for this model
+    pub chat_template: ChatTemplate,
+    /// Vocabulary trie for JSON-schema constraints, built on first use
+    pub json_vocab: OnceLock<Arc<TokenVocab>>,
 }
 
 impl ModelState {
+    pub fn device_info(&self) -> DeviceInfo {
+        DeviceInfo {
+            device: device_label(&self.device),
+            dtype: dtype_label(self.dtype),
+        }
+    }
+
     pub fn clear_cache(&mut self) {
         self.cache = Cache::new(true, self.dtype, &self.config, &self.device)
             .expect("Failed to recreate cache");
@@ -46,7 +65,7 @@ impl ModelState {
 /// - Very long contexts (RoPE position overflow)
 /// - Numerical instability
 /// - Edge case prompts
-fn sanitize_logits(logits: &Tensor, device: &Device) -> Result<Tensor, String> {
+pub fn sanitize_logits(logits: &Tensor, device: &Device) -> Result<Tensor, String> {
     // Move to CPU for inspection (fast for 1D vocab-size tensor)
     let logits_vec: Vec<f32> = logits
         .to_dtype(DType::F32)
@@ -84,13 +103,142 @@ fn sanitize_logits(logits: &Tensor, device: &Device) -> Result<Tensor, String> {
     }
 }
 
-/// Generate text from a prompt using the loaded model
+/// Incremental detokenizer for streaming generated text.
+///
+/// Decoding one token at a time mangles words and multi-byte characters
+/// that span tokens, so the recent tokens are decoded together and text is
+/// only released once it ends on an alphanumeric character.
+#[derive(Default)]
+pub struct TokenTextStream {
+    tokens: Vec<u32>,
+    prev_index: usize,
+    current_index: usize,
+}
+
+impl TokenTextStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a sampled token; returns any text that is now complete
+    pub fn next_token(
+        &mut self,
+        tokenizer: &Tokenizer,
+        token: u32,
+    ) -> Result<Option<String>, String> {
+        let prev_text = self.decode(tokenizer, self.prev_index, self.current_index)?;
+        self.tokens.push(token);
+        let text = self.decode(tokenizer, self.prev_index, self.tokens.len())?;
+        let complete = text.chars().last().is_some_and(|c| c.is_alphanumeric());
+        if text.len() > prev_text.len() && complete && text.is_char_boundary(prev_text.len()) {
+            self.prev_index = self.current_index;
+            self.current_index = self.tokens.len();
+            Ok(Some(text[prev_text.len()..].to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Text still held back at the end of generation
+    pub fn rest(&mut self, tokenizer: &Tokenizer) -> Result<Option<String>, String> {
+        let prev_text = self.decode(tokenizer, self.prev_index, self.current_index)?;
+        let text = self.decode(tokenizer, self.prev_index, self.tokens.len())?;
+        self.prev_index = self.tokens.len();
+        self.current_index = self.tokens.len();
+        if text.len() > prev_text.len() && text.is_char_boundary(prev_text.len()) {
+            Ok(Some(text[prev_text.len()..].to_string()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn token_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    fn decode(&self, tokenizer: &Tokenizer, from: usize, to: usize) -> Result<String, String> {
+        tokenizer
+            .decode(&self.tokens[from..to], true)
+            .map_err(|e| format!("Decode failed: {e}"))
+    }
+}
+
+/// Per-request sampling settings
+#[derive(Debug, Clone)]
+pub struct SamplingParams {
+    pub temperature: f64,
+    /// The same seed reproduces the same output; `None` picks a random one
+    pub seed: Option<u64>,
+    /// Restrict the output to JSON matching this schema
+    pub json_schema: Option<Arc<JsonSchema>>,
+    /// Wall-clock budget for the generation loop; once spent, generation
+    /// stops and returns the text so far as truncated
+    pub timeout: Option<Duration>,
+}
+
+impl SamplingParams {
+    pub fn logits_processor(&self) -> LogitsProcessor {
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen::<u64>());
+        LogitsProcessor::new(seed, Some(self.temperature), None)
+    }
+
+    /// Constraint for one generation, if a schema was given. The vocabulary
+    /// trie is built on the model's first constrained request.
+    pub fn json_constraint(
+        &self,
+        vocab: &OnceLock<Arc<TokenVocab>>,
+        tokenizer: &Tokenizer,
+    ) -> Option<JsonConstraint> {
+        let schema = self.json_schema.as_ref()?;
+        let vocab = vocab.get_or_init(|| Arc::new(TokenVocab::new(tokenizer)));
+        Some(JsonConstraint::new(schema, vocab.clone()))
+    }
+
+    /// Whether sampling always takes the most likely token (the temperature
+    /// below which `LogitsProcessor` switches to argmax)
+    pub fn is_greedy(&self) -> bool {
+        self.temperature < 1e-7
+    }
+
+    /// Whether a generation started at `start` has used up its timeout
+    pub fn timed_out(&self, start: Instant) -> bool {
+        self.timeout
+            .is_some_and(|timeout| start.elapsed() >= timeout)
+    }
+}
+
+/// Fail generation that stopped before its constrained JSON was complete.
+/// A truncated generation is returned as-is: the caller asked for whatever
+/// fit in its timeout.
+pub fn check_json_complete(
+    constraint: Option<&JsonConstraint>,
+    truncated: bool,
+) -> Result<(), String> {
+    match constraint {
+        Some(constraint) if !truncated && !constraint.is_complete() => {
+            Err("Generation ended before the JSON output was complete".to_string())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Generate text from a prompt using the loaded model.
+///
+/// `on_token` receives text as it is generated (with the number of tokens
+/// sampled so far); the full text is also returned at the end. Setting
+/// `cancelled` stops generation before the next token. With a JSON schema
+/// in `sampling`, generation stops as soon as the JSON value is closed.
+///
+/// Returns (text, tokens, truncated); `truncated` is set when the
+/// sampling timeout cut generation short.
 pub fn generate_text(
     state: &mut ModelState,
     prompt: &str,
     max_tokens: usize,
-    temperature: f64,
-) -> Result<(String, usize), String> {
+    sampling: &SamplingParams,
+    on_token: &mut dyn FnMut(&str, usize),
+    cancelled: &AtomicBool,
+) -> Result<(String, usize, bool), String> {
     let start = Instant::now();
 
     let encoding = state
@@ -106,12 +254,22 @@ pub fn generate_text(
 
     state.clear_cache();
 
-    let seed = rand::thread_rng().gen::<u64>();
-    let mut logits_processor = LogitsProcessor::new(seed, Some(temperature), None);
+    let mut logits_processor = sampling.logits_processor();
+    let mut constraint = sampling.json_constraint(&state.json_vocab, &state.tokenizer);
 
     let mut all_tokens = prompt_tokens.clone();
+    let mut stream = TokenTextStream::new();
+    let mut truncated = false;
 
     for i in 0..max_tokens {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        if sampling.timed_out(start) {
+            truncated = true;
+            break;
+        }
+
         let input_tokens = if i == 0 {
             all_tokens.clone()
         } else {
@@ -172,6 +330,10 @@ pub fn generate_text(
 
         // Protect against NaN/Inf in logits before sampling
         let last_logits = sanitize_logits(&last_logits, &state.device)?;
+        let last_logits = match &constraint {
+            Some(constraint) => constraint.mask(&last_logits, &state.eos_token_ids)?,
+            None => last_logits,
+        };
 
         let next_token = logits_processor
             .sample(&last_logits)
@@ -180,8 +342,20 @@ pub fn generate_text(
         if state.eos_token_ids.contains(&next_token) {
             break;
         }
+        if let Some(constraint) = &mut constraint {
+            constraint.advance(next_token)?;
+        }
 
         all_tokens.push(next_token);
+        if let Some(text) = stream.next_token(&state.tokenizer, next_token)? {
+            on_token(&text, stream.token_count());
+        }
+        if constraint.as_ref().is_some_and(JsonConstraint::is_finished) {
+            break;
+        }
+    }
+    if let Some(text) = stream.rest(&state.tokenizer)? {
+        on_token(&text, stream.token_count());
     }
 
     // Final GPU sync to ensure all work is complete before returning
@@ -192,6 +366,20 @@ pub fn generate_text(
         .map_err(|e| format!("Final GPU sync failed: {e}"))?;
 
     let generated_tokens = &all_tokens[prompt_len..];
+    if cancelled.load(Ordering::Relaxed) {
+        info!(
+            "🛑 Generation cancelled after {} tokens",
+            generated_tokens.len()
+        );
+        return Err("Generation cancelled".to_string());
+    }
+    if truncated {
+        info!(
+            "⏱️ Generation timed out after {} tokens",
+            generated_tokens.len()
+        );
+    }
+    check_json_complete(constraint.as_ref(), truncated)?;
     let output_text = state
         .tokenizer
         .decode(generated_tokens, true)
@@ -204,7 +392,7 @@ pub fn generate_text(
         duration
     );
 
-    Ok((output_text, generated_tokens.len()))
+    Ok((output_text, generated_tokens.len(), truncated))
 }
 
 /// Download model weights, handling both single file and sharded models
@@ -262,39 +450,12 @@ fn parse_eos_tokens(eos: &Option<LlamaEosToks>) -> Vec<u32> {
 /// Load a model by HuggingFace model ID
 pub fn load_model_by_id(
     model_id: &str,
+    device_request: DeviceRequest,
 ) -> Result<ModelState, Box<dyn std::error::Error + Send + Sync>> {
     info!("📥 Loading {model_id}...");
     let start = Instant::now();
 
-    // Device selection: CUDA > Metal > CPU
-    let device = select_best_device();
-
-    fn select_best_device() -> Device {
-        // Try CUDA first (RTX 5090, etc.)
-        #[cfg(feature = "cuda")]
-        {
-            if let Ok(device) = Device::new_cuda(0) {
-                info!("  Using CUDA device");
-                return device;
-            }
-            info!("  CUDA not available");
-        }
-
-        // Try Metal (macOS)
-        #[cfg(feature = "metal")]
-        {
-            if let Ok(device) = Device::new_metal(0) {
-                info!("  Using Metal device");
-                return device;
-            }
-            info!("  Metal not available");
-        }
-
-        // Fall back to CPU
-        info!("  Using CPU (no GPU acceleration)");
-        Device::Cpu
-    }
-
+    let device = select_device(device_request)?;
     info!("  Device: {device:?}");
 
     let api = Api::new()?;
@@ -307,6 +468,7 @@ pub fn load_model_by_id(
     info!("  Downloading model files...");
     let config_path = repo.get("config.json")?;
     let tokenizer_path = repo.get("tokenizer.json")?;
+    let chat_template = ChatTemplate::from_repo(&repo);
 
     let weight_paths =
         download_weights(&repo).map_err(|e| format!("Failed to download weights: {e}"))?;
@@ -328,8 +490,8 @@ pub fn load_model_by_id(
         .map_err(|e| format!("Failed to load tokenizer: {e}"))?;
 
     let dtype = match &device {
-        Device::Metal(_) => DType::BF16,
-        _ => DType::F32,
+        Device::Metal(_) | Device::Cuda(_) => DType::BF16,
+        Device::Cpu => DType::F32,
     };
     info!("  Dtype: {dtype:?}");
 
@@ -341,9 +503,13 @@ pub fn load_model_by_id(
 
     let model = Llama::load(vb, &config)?;
     let cache = Cache::new(true, dtype, &config, &device)?;
+    let memory_bytes = safetensors_weight_bytes(&weight_paths, dtype)?;
 
     let duration = start.elapsed();
-    info!("✅ Model loaded in {duration:?}");
+    info!(
+        "✅ Model loaded in {duration:?} ({:.0}MB weights)",
+        memory_bytes as f64 / (1024.0 * 1024.0)
+    );
 
     Ok(ModelState {
         model,
@@ -355,6 +521,10 @@ pub fn load_model_by_id(
         config,
         model_id: model_id.to_string(),
         weight_paths,
+        memory_bytes,
+        device_request,
+        chat_template,
+        json_vocab: OnceLock::new(),
     })
 }
 
@@ -362,7 +532,7 @@ pub fn load_model_by_id(
 pub fn load_default_model() -> Result<ModelState, Box<dyn std::error::Error + Send + Sync>> {
     let model_id = std::env::var("INFERENCE_MODEL_ID")
         .unwrap_or_else(|_| "unsloth/Llama-3.2-3B-Instruct".to_string());
-    load_model_by_id(&model_id)
+    load_model_by_id(&model_id, DeviceRequest::from_env())
 }
 
 /// Rebuild model with LoRA weights merged
@@ -628,3 +798,82 @@ pub fn rebuild_with_stacked_lora(
 
     Ok(model)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_stream_holds_back_until_word_completes() {
+        use tokenizers::models::wordlevel::WordLevel;
+
+        let vocab = [("Hello", 0), (",", 1), ("world", 2), ("[UNK]", 3)]
+            .into_iter()
+            .map(|(w, id)| (w.to_string(), id))
+            .collect();
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".into())
+            .build()
+            .unwrap();
+        let tokenizer = Tokenizer::new(model);
+
+        let mut stream = TokenTextStream::new();
+        assert_eq!(
+            stream.next_token(&tokenizer, 0).unwrap().as_deref(),
+            Some("Hello")
+        );
+        // Punctuation is held until the next word arrives
+        assert_eq!(stream.next_token(&tokenizer, 1).unwrap(), None);
+        assert_eq!(
+            stream.next_token(&tokenizer, 2).unwrap().as_deref(),
+            Some(" , world")
+        );
+        assert_eq!(stream.next_token(&tokenizer, 1).unwrap(), None);
+        assert_eq!(stream.rest(&tokenizer).unwrap().as_deref(), Some(" ,"));
+        assert_eq!(stream.token_count(), 4);
+    }
+
+    #[test]
+    fn test_weight_bytes_follow_load_dtype() {
+        let dir = std::env::temp_dir().join(format!("weight_bytes_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.safetensors");
+
+        let a = Tensor::zeros((4, 8), DType::BF16, &Device::Cpu).unwrap();
+        let b = Tensor::zeros(16, DType::BF16, &Device::Cpu).unwrap();
+        candle_core::safetensors::save(
+            &HashMap::from([("a".to_string(), a), ("b".to_string(), b)]),
+            &path,
+        )
+        .unwrap();
+
+        let paths = vec![path];
+        assert_eq!(
+            safetensors_weight_bytes(&paths, DType::BF16).unwrap(),
+            48 * 2
+        );
+        assert_eq!(
+            safetensors_weight_bytes(&paths, DType::F32).unwrap(),
+            48 * 4
+        );
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_sampling_timeout() {
+        let mut sampling = SamplingParams {
+            temperature: 0.7,
+            seed: None,
+            json_schema: None,
+            timeout: None,
+        };
+        let long_ago = Instant::now() - Duration::from_secs(60);
+        assert!(!sampling.timed_out(long_ago));
+
+        sampling.timeout = Some(Duration::from_secs(30));
+        assert!(sampling.timed_out(long_ago));
+        assert!(!sampling.timed_out(Instant::now()));
+    }
+}
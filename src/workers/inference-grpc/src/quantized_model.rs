@@ -12,17 +12,22 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Instant;
 
 use candle_core::quantized::gguf_file;
 use candle_core::{Device, Tensor};
-use candle_transformers::generation::LogitsProcessor;
 use candle_transformers::models::quantized_llama::ModelWeights;
 use hf_hub::{api::sync::Api, Repo, RepoType};
 use log::info;
-use rand::Rng;
 use tokenizers::Tokenizer;
 
+use crate::chat_template::ChatTemplate;
+use crate::device::{device_label, select_device, DeviceInfo, DeviceRequest};
+use crate::json_schema::{JsonConstraint, TokenVocab};
+use crate::model::{check_json_complete, SamplingParams, TokenTextStream};
+
 /// Quantized model state
 pub struct QuantizedModelState {
     pub model: ModelWeights,
@@ -33,9 +38,20 @@ pub struct QuantizedModelState {
     pub model_id: String,
     #[allow(dead_code)]
     pub quantization_type: String, // e.g., "Q4_K_M", "Q8_0"
+    /// Chat template from the tokenizer repo
+    pub chat_template: ChatTemplate,
+    /// Vocabulary trie for JSON-schema constraints, built on first use
+    pub json_vocab: OnceLock<Arc<TokenVocab>>,
 }
 
 impl QuantizedModelState {
+    pub fn device_info(&self) -> DeviceInfo {
+        DeviceInfo {
+            device: device_label(&self.device),
+            dtype: "gguf".to_string(),
+        }
+    }
+
     /// Clear KV cache for new generation
     #[allow(dead_code)]
     pub fn clear_cache(&mut self) {
@@ -72,35 +88,7 @@ pub fn load_quantized_model(
     info!("📥 Loading quantized model from {model_path:?}");
     let start = Instant::now();
 
-    // Device selection: CUDA > Metal > CPU
-    let device = select_best_device();
-
-    fn select_best_device() -> Device {
-        // Try CUDA first (RTX 5090, etc.) - quantized inference is FAST on CUDA
-        #[cfg(feature = "cuda")]
-        {
-            if let Ok(device) = Device::new_cuda(0) {
-                info!("  Using CUDA device (quantized + CUDA = fast!)");
-                return device;
-            }
-            info!("  CUDA not available");
-        }
-
-        // Try Metal (macOS)
-        #[cfg(feature = "metal")]
-        {
-            if let Ok(device) = Device::new_metal(0) {
-                info!("  Using Metal device");
-                return device;
-            }
-            info!("  Metal not available");
-        }
-
-        // Fall back to CPU
-        info!("  Using CPU (no GPU acceleration)");
-        Device::Cpu
-    }
-
+    let device = select_device(DeviceRequest::from_env())?;
     info!("  Device: {device:?}");
 
     // Open GGUF file
@@ -127,6 +115,7 @@ pub fn load_quantized_model(
     let api = Api::new()?;
     let tokenizer_repo = api.repo(Repo::new(tokenizer_repo.to_string(), RepoType::Model));
     let tokenizer_path = tokenizer_repo.get("tokenizer.json")?;
+    let chat_template = ChatTemplate::from_repo(&tokenizer_repo);
     let tokenizer = Tokenizer::from_file(tokenizer_path)
         .map_err(|e| format!("Failed to load tokenizer: {e}"))?;
 
@@ -150,6 +139,8 @@ pub fn load_quantized_model(
             .unwrap_or("unknown")
             .to_string(),
         quantization_type: quant_type,
+        chat_template,
+        json_vocab: OnceLock::new(),
     })
 }
 
@@ -160,13 +151,17 @@ const GPU_SYNC_INTERVAL: usize = 16;
 /// Only check for NaN on first N tokens (NaN usually appears early from bad prompts)
 const NAN_CHECK_TOKENS: usize = 3;
 
-/// Generate text from a prompt using quantized model
+/// Generate text from a prompt using quantized model.
+/// `sampling`, `on_token`, `cancelled` and the returned (text, tokens,
+/// truncated) behave as in `generate_text`.
 pub fn generate_text_quantized(
     state: &mut QuantizedModelState,
     prompt: &str,
     max_tokens: usize,
-    temperature: f64,
-) -> Result<(String, usize), String> {
+    sampling: &SamplingParams,
+    on_token: &mut dyn FnMut(&str, usize),
+    cancelled: &AtomicBool,
+) -> Result<(String, usize, bool), String> {
     let start = Instant::now();
 
     // Tokenize prompt
@@ -189,14 +184,24 @@ pub fn generate_text_quantized(
     );
 
     // Setup logits processor
-    let seed = rand::thread_rng().gen::<u64>();
-    let mut logits_processor = LogitsProcessor::new(seed, Some(temperature), None);
+    let mut logits_processor = sampling.logits_processor();
+    let mut constraint = sampling.json_constraint(&state.json_vocab, &state.tokenizer);
 
     let mut all_tokens = prompt_tokens.clone();
+    let mut stream = TokenTextStream::new();
     let mut nan_count = 0;
+    let mut truncated = false;
 
     // Generate tokens
     for i in 0..max_tokens {
+        if cancelled.load(Ordering::Relaxed) {
+            break;
+        }
+        if sampling.timed_out(start) {
+            truncated = true;
+            break;
+        }
+
         let input_tokens = if i == 0 {
             all_tokens.clone()
         } else {
@@ -260,6 +265,10 @@ pub fn generate_text_quantized(
         } else {
             logits
         };
+        let logits = match &constraint {
+            Some(constraint) => constraint.mask(&logits, &state.eos_token_ids)?,
+            None => logits,
+        };
 
         let next_token = logits_processor
             .sample(&logits)
@@ -268,8 +277,20 @@ pub fn generate_text_quantized(
         if state.eos_token_ids.contains(&next_token) {
             break;
         }
+        if let Some(constraint) = &mut constraint {
+            constraint.advance(next_token)?;
+        }
 
         all_tokens.push(next_token);
+        if let Some(text) = stream.next_token(&state.tokenizer, next_token)? {
+            on_token(&text, stream.token_count());
+        }
+        if constraint.as_ref().is_some_and(JsonConstraint::is_finished) {
+            break;
+        }
+    }
+    if let Some(text) = stream.rest(&state.tokenizer)? {
+        on_token(&text, stream.token_count());
     }
 
     // Final GPU sync to ensure all work is complete before returning
@@ -280,6 +301,20 @@ pub fn generate_text_quantized(
 
     // Decode generated tokens
     let generated_tokens = &all_tokens[prompt_len..];
+    if cancelled.load(Ordering::Relaxed) {
+        info!(
+            "🛑 Quantized generation cancelled after {} tokens",
+            generated_tokens.len()
+        );
+        return Err("Generation cancelled".to_string());
+    }
+    if truncated {
+        info!(
+            "⏱️ Quantized generation timed out after {} tokens",
+            generated_tokens.len()
+        );
+    }
+    check_json_complete(constraint.as_ref(), truncated)?;
     let output_text = state
         .tokenizer
         .decode(generated_tokens, true)
@@ -292,7 +327,7 @@ pub fn generate_text_quantized(
         duration
     );
 
-    Ok((output_text, generated_tokens.len()))
+    Ok((output_text, generated_tokens.len(), truncated))
 }
 
 /// Sanitize logits to prevent NaN/Inf from crashing the sampler
@@ -340,6 +375,9 @@ fn sanitize_logits_with_flag(logits: &Tensor, device: &Device) -> Result<(Tensor
     }
 }
 
+/// Tokenizer (and chat template) source for the default quantized model
+pub const DEFAULT_TOKENIZER_REPO: &str = "unsloth/Llama-3.2-3B-Instruct";
+
 /// Load default quantized model (Q4_K_M for best speed/quality balance)
 pub fn load_default_quantized(
 ) -> Result<QuantizedModelState, Box<dyn std::error::Error + Send + Sync>> {
@@ -350,7 +388,7 @@ pub fn load_default_quantized(
     )?;
 
     // Load with tokenizer from unsloth (same tokenizer, fully public)
-    load_quantized_model(&gguf_path, "unsloth/Llama-3.2-3B-Instruct")
+    load_quantized_model(&gguf_path, DEFAULT_TOKENIZER_REPO)
 }
 
 #[cfg(test)]
@@ -0,0 +1,454 @@
+//! Speculative Decoding
+//!
+//! Decoding is memory-bandwidth bound: each token streams every weight of
+//! the model once. A small draft model proposes the next K tokens cheaply,
+//! then the target model runs all of them in a single forward pass and
+//! keeps the longest prefix it agrees with, plus one token of its own.
+//! Every round emits at least one token, and up to K + 1.
+//!
+//! The target samples each position exactly as plain decoding would and a
+//! draft token is only kept when it equals that sample, so the output is
+//! what the target alone would produce for the same seed; the draft only
+//! changes how fast it arrives. Drafting is greedy, so it only pays off when
+//! the target samples greedily too: at temperature > 0 its samples rarely
+//! match the draft's argmax, and generation runs without the draft.
+//!
+//! Both models must share a tokenizer (same vocabulary).
+
+use candle_core::{Device, Tensor, D};
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tokenizers::Tokenizer;
+
+use crate::json_schema::JsonConstraint;
+use crate::model::{
+    check_json_complete, generate_text, sanitize_logits, ModelState, SamplingParams,
+    TokenTextStream,
+};
+use crate::vendored::llama::{Cache, Llama};
+
+/// Tokens the draft proposes per round when the request doesn't say
+pub const DEFAULT_SPECULATION_LENGTH: usize = 4;
+
+/// Upper bound on tokens per round; beyond this the draft is rarely right
+pub const MAX_SPECULATION_LENGTH: usize = 16;
+
+/// A decoder-only model with a rewindable KV cache
+pub trait CausalLM {
+    /// Run `tokens` starting at position `index_pos` (the number of tokens
+    /// already cached), returning F32 logits for the last `last_n`
+    /// positions: (last_n, vocab)
+    fn forward(
+        &mut self,
+        tokens: &[u32],
+        index_pos: usize,
+        last_n: usize,
+    ) -> Result<Tensor, String>;
+
+    /// Forget cached positions from `len` on
+    fn rewind(&mut self, len: usize) -> Result<(), String>;
+}
+
+/// A loaded model and its cache, borrowed apart from the rest of
+/// `ModelState` so the tokenizer stays usable while decoding
+pub struct CachedLlama<'a> {
+    pub model: &'a Llama,
+    pub cache: &'a mut Cache,
+    pub device: &'a Device,
+}
+
+impl<'a> CachedLlama<'a> {
+    pub fn new(state: &'a mut ModelState) -> Self {
+        Self {
+            model: &state.model,
+            cache: &mut state.cache,
+            device: &state.device,
+        }
+    }
+}
+
+impl CausalLM for CachedLlama<'_> {
+    fn forward(
+        &mut self,
+        tokens: &[u32],
+        index_pos: usize,
+        last_n: usize,
+    ) -> Result<Tensor, String> {
+        let input = Tensor::new(tokens, self.device)
+            .and_then(|t| t.unsqueeze(0))
+            .map_err(|e| format!("Tensor creation failed: {e}"))?;
+        let logits = self
+            .model
+            .forward_last(&input, index_pos, self.cache, last_n)
+            .and_then(|l| l.squeeze(0))
+            .map_err(|e| format!("Forward pass failed: {e}"))?;
+        // As in generate_text: keeps Metal command buffers from piling up
+        self.device
+            .synchronize()
+            .map_err(|e| format!("GPU sync failed: {e}"))?;
+        Ok(logits)
+    }
+
+    fn rewind(&mut self, len: usize) -> Result<(), String> {
+        self.cache
+            .truncate(len)
+            .map_err(|e| format!("Cache rewind failed: {e}"))
+    }
+}
+
+/// How much of the draft the target kept
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpeculationStats {
+    pub proposed: usize,
+    pub accepted: usize,
+    /// Target forward passes
+    pub rounds: usize,
+}
+
+/// Decode up to `max_tokens` after `prompt`, drafting `speculation_length`
+/// tokens per round.
+///
+/// `step` receives the target's logits for each next position in order,
+/// samples a token and returns it, or returns None to stop generating.
+/// Caches are reset first.
+pub fn speculative_decode(
+    target: &mut dyn CausalLM,
+    draft: &mut dyn CausalLM,
+    prompt: &[u32],
+    max_tokens: usize,
+    speculation_length: usize,
+    step: &mut dyn FnMut(&Tensor) -> Result<Option<u32>, String>,
+) -> Result<SpeculationStats, String> {
+    if prompt.is_empty() {
+        return Err("Empty prompt".to_string());
+    }
+    target.rewind(0)?;
+    draft.rewind(0)?;
+
+    let mut stats = SpeculationStats::default();
+    let mut tokens = prompt.to_vec();
+    // Positions each model has in its cache; always behind tokens.len()
+    let mut target_len = 0;
+    let mut draft_len = 0;
+    let mut generated = 0;
+
+    while generated < max_tokens {
+        let base = tokens.len();
+        // The target adds a token of its own, so propose one less than what's left
+        let k = speculation_length.min(max_tokens - generated - 1);
+        for _ in 0..k {
+            let logits = draft.forward(&tokens[draft_len..], draft_len, 1)?;
+            draft_len = tokens.len();
+            let proposal = logits
+                .squeeze(0)
+                .and_then(|l| l.argmax(D::Minus1))
+                .and_then(|t| t.to_scalar::<u32>())
+                .map_err(|e| format!("Draft argmax failed: {e}"))?;
+            tokens.push(proposal);
+        }
+
+        // One pass over everything the target hasn't seen; the last k + 1
+        // rows predict the token after base - 1 and after each proposal
+        let logits = target.forward(&tokens[target_len..], target_len, k + 1)?;
+        let proposals = tokens.split_off(base);
+        stats.rounds += 1;
+        stats.proposed += k;
+
+        let mut accepted = 0;
+        let mut stopped = false;
+        for j in 0..=k {
+            let row = logits
+                .get(j)
+                .map_err(|e| format!("Get logits row failed: {e}"))?;
+            let Some(token) = step(&row)? else {
+                stopped = true;
+                break;
+            };
+            tokens.push(token);
+            generated += 1;
+            if proposals.get(j) != Some(&token) {
+                break;
+            }
+            accepted += 1;
+        }
+        stats.accepted += accepted;
+        if stopped {
+            break;
+        }
+
+        // Cached positions past the accepted prefix hold rejected tokens
+        target_len = base + accepted;
+        draft_len = draft_len.min(target_len);
+        target.rewind(target_len)?;
+        draft.rewind(draft_len)?;
+    }
+    Ok(stats)
+}
+
+/// Whether both tokenizers map the same text to the same ids
+fn same_tokenizer(a: &Tokenizer, b: &Tokenizer) -> bool {
+    a.get_vocab_size(true) == b.get_vocab_size(true) && a.get_vocab(true) == b.get_vocab(true)
+}
+
+/// `generate_text` with a draft model proposing tokens for `state`
+///
+/// Same contract as `generate_text`: (text, tokens, truncated).
+#[allow(clippy::too_many_arguments)]
+pub fn generate_text_speculative(
+    state: &mut ModelState,
+    draft: &mut ModelState,
+    prompt: &str,
+    max_tokens: usize,
+    speculation_length: usize,
+    sampling: &SamplingParams,
+    on_token: &mut dyn FnMut(&str, usize),
+    cancelled: &AtomicBool,
+) -> Result<(String, usize, bool), String> {
+    let start = Instant::now();
+    if state.config.vocab_size != draft.config.vocab_size {
+        return Err(format!(
+            "Draft model {} has a different vocabulary than {} ({} vs {} tokens)",
+            draft.model_id, state.model_id, draft.config.vocab_size, state.config.vocab_size
+        ));
+    }
+    if !same_tokenizer(&state.tokenizer, &draft.tokenizer) {
+        return Err(format!(
+            "Draft model {} has a different tokenizer than {}",
+            draft.model_id, state.model_id
+        ));
+    }
+    if !sampling.is_greedy() {
+        info!(
+            "⚠️ Temperature {} samples too freely for greedy drafts, generating without {}",
+            sampling.temperature, draft.model_id
+        );
+        return generate_text(state, prompt, max_tokens, sampling, on_token, cancelled);
+    }
+
+    let encoding = state
+        .tokenizer
+        .encode(prompt, true)
+        .map_err(|e| format!("Tokenization failed: {e}"))?;
+    let prompt_tokens = encoding.get_ids().to_vec();
+
+    let mut logits_processor = sampling.logits_processor();
+    let mut constraint = sampling.json_constraint(&state.json_vocab, &state.tokenizer);
+    let mut generated = Vec::new();
+    let mut stream = TokenTextStream::new();
+    let mut truncated = false;
+
+    let stats = {
+        let tokenizer = &state.tokenizer;
+        let eos_token_ids = &state.eos_token_ids;
+        let device = &state.device;
+        let mut step = |logits: &Tensor| -> Result<Option<u32>, String> {
+            if cancelled.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            if sampling.timed_out(start) {
+                truncated = true;
+                return Ok(None);
+            }
+            let logits = sanitize_logits(logits, device)?;
+            let logits = match &constraint {
+                Some(constraint) => constraint.mask(&logits, eos_token_ids)?,
+                None => logits,
+            };
+            let token = logits_processor
+                .sample(&logits)
+                .map_err(|e| format!("Sampling failed: {e}"))?;
+            if eos_token_ids.contains(&token) {
+                return Ok(None);
+            }
+            if let Some(constraint) = &mut constraint {
+                constraint.advance(token)?;
+            }
+            generated.push(token);
+            if let Some(text) = stream.next_token(tokenizer, token)? {
+                on_token(&text, stream.token_count());
+            }
+            if constraint.as_ref().is_some_and(JsonConstraint::is_finished) {
+                return Ok(None);
+            }
+            Ok(Some(token))
+        };
+        // Field by field: the step closure holds the tokenizer
+        let mut target = CachedLlama {
+            model: &state.model,
+            cache: &mut state.cache,
+            device: &state.device,
+        };
+        speculative_decode(
+            &mut target,
+            &mut CachedLlama::new(draft),
+            &prompt_tokens,
+            max_tokens,
+            speculation_length,
+            &mut step,
+        )?
+    };
+    if let Some(text) = stream.rest(&state.tokenizer)? {
+        on_token(&text, stream.token_count());
+    }
+
+    if cancelled.load(Ordering::Relaxed) {
+        info!("🛑 Generation cancelled after {} tokens", generated.len());
+        return Err("Generation cancelled".to_string());
+    }
+    if truncated {
+        info!("⏱️ Generation timed out after {} tokens", generated.len());
+    }
+    check_json_complete(constraint.as_ref(), truncated)?;
+    let output_text = state
+        .tokenizer
+        .decode(&generated, true)
+        .map_err(|e| format!("Decode failed: {e}"))?;
+
+    info!(
+        "📝 Generated {} tokens in {:?} (speculative: {}/{} drafted accepted, {} passes)",
+        generated.len(),
+        start.elapsed(),
+        stats.accepted,
+        stats.proposed,
+        stats.rounds
+    );
+    Ok((output_text, generated.len(), truncated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOCAB: u32 = 16;
+
+    /// Predicts `next(previous token)` with certainty, and checks the
+    /// positions it is run at against what it has cached
+    struct Toy {
+        next: fn(u32) -> u32,
+        cached: usize,
+        passes: usize,
+    }
+
+    impl CausalLM for Toy {
+        fn forward(
+            &mut self,
+            tokens: &[u32],
+            index_pos: usize,
+            last_n: usize,
+        ) -> Result<Tensor, String> {
+            assert_eq!(index_pos, self.cached);
+            self.cached += tokens.len();
+            self.passes += 1;
+            let next = self.next;
+            let rows: Vec<f32> = tokens[tokens.len() - last_n..]
+                .iter()
+                .flat_map(|&t| (0..VOCAB).map(move |v| f32::from(u8::from(v == next(t)))))
+                .collect();
+            Tensor::from_vec(rows, (last_n, VOCAB as usize), &Device::Cpu)
+                .map_err(|e| e.to_string())
+        }
+
+        fn rewind(&mut self, len: usize) -> Result<(), String> {
+            self.cached = self.cached.min(len);
+            Ok(())
+        }
+    }
+
+    fn toy(next: fn(u32) -> u32) -> Toy {
+        Toy {
+            next,
+            cached: 0,
+            passes: 0,
+        }
+    }
+
+    fn target_next(t: u32) -> u32 {
+        (t * 7 + 3) % VOCAB
+    }
+
+    fn greedy(out: &mut Vec<u32>) -> impl FnMut(&Tensor) -> Result<Option<u32>, String> + '_ {
+        |logits| {
+            let token = logits
+                .argmax(D::Minus1)
+                .unwrap()
+                .to_scalar::<u32>()
+                .unwrap();
+            out.push(token);
+            Ok(Some(token))
+        }
+    }
+
+    #[test]
+    fn test_output_matches_target_alone() {
+        let mut expected = vec![];
+        let mut target = toy(target_next);
+        let mut draft = toy(target_next);
+        speculative_decode(
+            &mut target,
+            &mut draft,
+            &[1, 2],
+            12,
+            0,
+            &mut greedy(&mut expected),
+        )
+        .unwrap();
+        assert_eq!(expected.len(), 12);
+        assert_eq!(target.passes, 12);
+
+        // A draft that always agrees: K + 1 tokens per target pass
+        let mut out = vec![];
+        let mut target = toy(target_next);
+        let stats = speculative_decode(
+            &mut target,
+            &mut draft,
+            &[1, 2],
+            12,
+            3,
+            &mut greedy(&mut out),
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+        assert_eq!(stats.rounds, 3);
+        assert_eq!(stats.accepted, stats.proposed);
+
+        // A draft that is wrong half the time still yields the target's text
+        let mut out = vec![];
+        let mut target = toy(target_next);
+        let mut draft = toy(|t| if t % 2 == 0 { target_next(t) } else { 0 });
+        let stats = speculative_decode(
+            &mut target,
+            &mut draft,
+            &[1, 2],
+            12,
+            4,
+            &mut greedy(&mut out),
+        )
+        .unwrap();
+        assert_eq!(out, expected);
+        assert!(stats.accepted < stats.proposed);
+        assert!(stats.rounds < 12);
+    }
+
+    #[test]
+    fn test_draft_tokenizer_must_match_ids() {
+        use tokenizers::models::wordlevel::WordLevel;
+
+        let tokenizer = |words: &[(&str, u32)]| {
+            let vocab = words.iter().map(|(w, id)| (w.to_string(), *id)).collect();
+            let model = WordLevel::builder()
+                .vocab(vocab)
+                .unk_token("[UNK]".into())
+                .build()
+                .unwrap();
+            Tokenizer::new(model)
+        };
+        let target = tokenizer(&[("hello", 0), ("world", 1), ("[UNK]", 2)]);
+        let same = tokenizer(&[("hello", 0), ("world", 1), ("[UNK]", 2)]);
+        // Same size, different ids: a vocab_size check alone lets this through
+        let swapped = tokenizer(&[("hello", 1), ("world", 0), ("[UNK]", 2)]);
+
+        assert!(same_tokenizer(&target, &same));
+        assert!(!same_tokenizer(&target, &swapped));
+    }
+}
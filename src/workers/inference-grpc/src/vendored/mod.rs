@@ -0,0 +1,6 @@
+//! Vendored model implementations from candle-transformers.
+//!
+//! We vendor these where upstream lacks something generation needs.
+//! Each vendored file documents what was changed and why.
+
+pub mod llama;
@@ -10,18 +10,27 @@
 //! - Semaphore tracks available workers
 
 use log::info;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::time::Instant;
 use tokio::sync::{mpsc, oneshot, Semaphore};
 
-use crate::quantized_model::{generate_text_quantized, load_default_quantized};
+use crate::chat_template::ChatTemplate;
+use crate::device::DeviceInfo;
+use crate::model::SamplingParams;
+use crate::quantized_model::{
+    generate_text_quantized, load_default_quantized, DEFAULT_TOKENIZER_REPO,
+};
 
 /// Request sent to worker pool
 pub struct InferenceRequest {
     pub prompt: String,
     pub max_tokens: usize,
-    pub temperature: f64,
+    pub sampling: SamplingParams,
+    /// Streamed (text, tokens_generated) pieces; closed when generation ends
+    pub token_tx: Option<mpsc::UnboundedSender<(String, usize)>>,
+    /// Set when the caller goes away; the worker stops at the next token
+    pub cancelled: Arc<AtomicBool>,
     pub response_tx: oneshot::Sender<InferenceResponse>,
 }
 
@@ -29,6 +38,8 @@ pub struct InferenceRequest {
 pub struct InferenceResponse {
     pub text: String,
     pub tokens: usize,
+    /// Generation hit the request's timeout; `text` is what came before it
+    pub truncated: bool,
     pub duration_ms: u64,
     pub worker_id: usize,
     pub error: Option<String>,
@@ -59,6 +70,10 @@ pub struct WorkerPool {
     stats: Arc<PoolStats>, // Used internally by workers, not exposed
     pub num_workers: usize,
     pub available: Arc<Semaphore>,
+    /// Chat template shared by every worker's model
+    pub chat_template: ChatTemplate,
+    /// Device the first worker's model loaded onto (all load alike)
+    pub device: Arc<OnceLock<DeviceInfo>>,
 }
 
 impl WorkerPool {
@@ -76,12 +91,14 @@ impl WorkerPool {
 
         let stats = Arc::new(PoolStats::new());
         let available = Arc::new(Semaphore::new(num_workers));
+        let device = Arc::new(OnceLock::new());
 
         // Spawn worker tasks
         for worker_id in 0..num_workers {
             let rx = request_rx.clone();
             let stats = stats.clone();
             let available = available.clone();
+            let device = device.clone();
 
             tokio::spawn(async move {
                 // Each worker loads its own model instance
@@ -95,6 +112,7 @@ impl WorkerPool {
                             worker_id,
                             load_start.elapsed().as_secs_f32()
                         );
+                        device.get_or_init(|| state.device_info());
                         state
                     }
                     Err(e) => {
@@ -120,14 +138,22 @@ impl WorkerPool {
                     stats.requests_pending.fetch_add(1, Ordering::SeqCst);
                     let gen_start = Instant::now();
 
-                    // Generate response
+                    // Generate response, streaming text pieces as they decode
+                    let token_tx = request.token_tx;
+                    let mut on_token = |text: &str, tokens: usize| {
+                        if let Some(tx) = &token_tx {
+                            let _ = tx.send((text.to_string(), tokens));
+                        }
+                    };
                     let response = match generate_text_quantized(
                         &mut model_state,
                         &request.prompt,
                         request.max_tokens,
-                        request.temperature,
+                        &request.sampling,
+                        &mut on_token,
+                        &request.cancelled,
                     ) {
-                        Ok((text, tokens)) => {
+                        Ok((text, tokens, truncated)) => {
                             let duration_ms = gen_start.elapsed().as_millis() as u64;
                             stats
                                 .total_tokens_generated
@@ -139,6 +165,7 @@ impl WorkerPool {
                             InferenceResponse {
                                 text,
                                 tokens,
+                                truncated,
                                 duration_ms,
                                 worker_id,
                                 error: None,
@@ -147,6 +174,7 @@ impl WorkerPool {
                         Err(e) => InferenceResponse {
                             text: String::new(),
                             tokens: 0,
+                            truncated: false,
                             duration_ms: gen_start.elapsed().as_millis() as u64,
                             worker_id,
                             error: Some(e),
@@ -156,6 +184,9 @@ impl WorkerPool {
                     stats.requests_pending.fetch_sub(1, Ordering::SeqCst);
                     stats.requests_completed.fetch_add(1, Ordering::SeqCst);
 
+                    // Close the token stream before the final response
+                    drop(token_tx);
+
                     // Send response back (ignore error if receiver dropped)
                     let _ = request.response_tx.send(response);
 
@@ -165,6 +196,11 @@ impl WorkerPool {
             });
         }
 
+        let chat_template =
+            tokio::task::spawn_blocking(|| ChatTemplate::from_hub(DEFAULT_TOKENIZER_REPO))
+                .await
+                .unwrap_or_default();
+
         info!(
             "🏭 Worker pool ready: {} workers in {:.1}s",
             num_workers,
@@ -176,6 +212,8 @@ impl WorkerPool {
             stats,
             num_workers,
             available,
+            chat_template,
+            device,
         })
     }
 
@@ -183,11 +221,15 @@ impl WorkerPool {
     ///
     /// Returns immediately with a response channel.
     /// Caller can await the response or timeout.
+    /// Text pieces are streamed on `token_tx` while the request runs;
+    /// setting `cancelled` abandons it (even if it is still queued).
     pub async fn submit(
         &self,
         prompt: String,
         max_tokens: usize,
-        temperature: f64,
+        sampling: SamplingParams,
+        token_tx: Option<mpsc::UnboundedSender<(String, usize)>>,
+        cancelled: Arc<AtomicBool>,
     ) -> Result<oneshot::Receiver<InferenceResponse>, String> {
         // Acquire semaphore permit (blocks if all workers busy)
         // This provides backpressure to prevent queue explosion
@@ -203,7 +245,9 @@ impl WorkerPool {
         let request = InferenceRequest {
             prompt,
             max_tokens,
-            temperature,
+            sampling,
+            token_tx,
+            cancelled,
             response_tx,
         };
 
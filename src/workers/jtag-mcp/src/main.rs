@@ -5,10 +5,18 @@
 //!
 //! Architecture:
 //! - Reads JSON-RPC messages from stdin
-//! - Connects to continuum-core via Unix socket
+//! - Connects to continuum-core via Unix socket (one connection, kept alive across calls)
+//! - Survives backend restarts: a request that couldn't be sent (connect or
+//!   first write failed) is retried with exponential backoff before a tool
+//!   call or tool list gives up. A failure after the request went out is
+//!   reported, never retried, since the command may already have run
 //! - Routes MCP protocol messages to JTAG commands
-//! - Single source of truth: tools discovered from registry at runtime
+//! - Single source of truth: tools discovered from registry at runtime, along
+//!   with the exact JTAG command behind each tool name (no name guessing)
 //! - Context injection: persona_id, db_path, workspace_root auto-added to commands
+//! - Streamed results: intermediate chunks (e.g. tokens from `ai/generate/stream`)
+//!   are forwarded as `notifications/progress` when the tool call carries a
+//!   `_meta.progressToken`, so slow tools report progress instead of going silent
 //!
 //! Usage:
 //!   jtag-mcp <socket-path> [options]
@@ -35,9 +43,12 @@
 
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Map, Value};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
 use std::os::unix::net::UnixStream;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 
 // ============================================================================
 // Utility Functions
@@ -181,28 +192,146 @@ impl McpContext {
 // JTAG Client (Unix socket IPC)
 // ============================================================================
 
-struct JtagClient {
-    socket_path: PathBuf,
+/// Why a call failed. Only `NotSent` may be retried: once any byte of the
+/// request reached the server it may have run the command, and running a
+/// non-idempotent one twice is worse than reporting the failure.
+enum CallError {
+    /// The command reported an error (the connection is still good)
+    Command(String),
+    /// The connection broke before any of the request was written
+    NotSent(String),
+    /// The connection broke after the request was (partly) written
+    Sent(String),
 }
 
-impl JtagClient {
-    fn new(socket_path: PathBuf) -> Self {
-        Self { socket_path }
-    }
+/// Kept-alive connection to continuum-core.
+///
+/// The server answers requests on one connection in any order, but calls
+/// here are serialized by `JtagClient`'s mutex, so at most one request is
+/// in flight and the next frames always belong to it.
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
 
-    fn execute(&self, command: &str, params: Value) -> Result<Value, String> {
-        // Connect to Unix socket with timeout
-        let stream = UnixStream::connect(&self.socket_path)
+impl Connection {
+    fn open(socket_path: &Path) -> Result<Self, String> {
+        let stream = UnixStream::connect(socket_path)
             .map_err(|e| format!("Failed to connect to continuum-core: {}. Is it running?", e))?;
 
         // Set read/write timeout to 60 seconds for large responses
-        let timeout = std::time::Duration::from_secs(60);
+        let timeout = Duration::from_secs(60);
         stream.set_read_timeout(Some(timeout)).ok();
         stream.set_write_timeout(Some(timeout)).ok();
 
-        let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
-        let mut writer = BufWriter::new(stream);
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone().map_err(|e| e.to_string())?),
+            writer: stream,
+        })
+    }
+
+    /// Send one request and read frames until its final one.
+    fn call(
+        &mut self,
+        request_str: &str,
+        on_chunk: &mut impl FnMut(&Value),
+    ) -> Result<Value, CallError> {
+        // Send line-delimited JSON (server reads with BufReader::lines()),
+        // unbuffered so a failure is known to be before or after the first byte
+        let request = format!("{}\n", request_str);
+        let mut written = 0;
+        while written < request.len() {
+            match self.writer.write(&request.as_bytes()[written..]) {
+                Ok(0) => {
+                    let e = "Connection closed while sending request".to_string();
+                    return Err(if written == 0 {
+                        CallError::NotSent(e)
+                    } else {
+                        CallError::Sent(e)
+                    });
+                }
+                Ok(n) => written += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) if written == 0 => return Err(CallError::NotSent(e.to_string())),
+                Err(e) => return Err(CallError::Sent(e.to_string())),
+            }
+        }
+
+        let broken = |e: std::io::Error| CallError::Sent(e.to_string());
+        loop {
+            // Read response length
+            let mut length_bytes = [0u8; 4];
+            std::io::Read::read_exact(&mut self.reader, &mut length_bytes).map_err(broken)?;
+            let response_length = u32::from_be_bytes(length_bytes) as usize;
+
+            // Read response
+            let mut response_bytes = vec![0u8; response_length];
+            std::io::Read::read_exact(&mut self.reader, &mut response_bytes).map_err(broken)?;
+
+            // An unparseable frame leaves the stream position unknown
+            let response: Value = serde_json::from_slice(&response_bytes)
+                .map_err(|e| CallError::Sent(format!("Invalid response frame: {}", e)))?;
+
+            // Check for error
+            if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
+                return Err(CallError::Command(error.to_string()));
+            }
+
+            let result = response.get("result").cloned().unwrap_or(json!(null));
+            if result.get("done") == Some(&json!(false)) {
+                on_chunk(&result);
+                continue;
+            }
+
+            // Return result
+            return Ok(result);
+        }
+    }
+}
+
+/// Retries after a connection failure before giving up. Backoff doubles from
+/// the base delay: 0.25s, 0.5s, 1s, 2s, 4s (~8s total), enough to ride out
+/// a routine continuum-core restart.
+const RECONNECT_ATTEMPTS: u32 = 5;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(250);
+
+struct JtagClient {
+    socket_path: PathBuf,
+    /// Reused across calls; None until the first call or after a failure
+    connection: Mutex<Option<Connection>>,
+}
+
+impl JtagClient {
+    fn new(socket_path: PathBuf) -> Self {
+        Self {
+            socket_path,
+            connection: Mutex::new(None),
+        }
+    }
+
+    fn execute(&self, command: &str, params: Value) -> Result<Value, String> {
+        self.execute_streaming(command, params, |_| {})
+    }
 
+    /// Execute a command, passing each intermediate chunk of a streamed result
+    /// to `on_chunk`. Streaming commands send one frame per chunk marked
+    /// `"done": false`, then a final frame; other commands send a single frame.
+    /// The read timeout applies per frame, so a stream that keeps producing
+    /// chunks doesn't time out however long it runs.
+    ///
+    /// The connection is kept open for the next call. If it broke, it is
+    /// dropped. A request is only ever resent if none of it was written: a
+    /// kept-alive connection the server closed while idle is retried at once
+    /// on a fresh one, and if connecting fails too (the backend is
+    /// restarting) it is retried with exponential backoff. A failure after
+    /// the request went out (read timeout, broken frame) is returned as is,
+    /// since the server may already have run the command.
+    fn execute_streaming(
+        &self,
+        command: &str,
+        params: Value,
+        mut on_chunk: impl FnMut(&Value),
+    ) -> Result<Value, String> {
         // Build request - merge params at top level (not nested)
         // Protocol: {"command": "...", "field1": value, "field2": value, ...}
         let mut request = params.as_object().cloned().unwrap_or_default();
@@ -211,41 +340,88 @@ impl JtagClient {
 
         let request_str = serde_json::to_string(&request).map_err(|e| e.to_string())?;
 
-        // Send line-delimited JSON (server reads with BufReader::lines())
-        writeln!(writer, "{}", request_str).map_err(|e| e.to_string())?;
-        writer.flush().map_err(|e| e.to_string())?;
-
-        // Read response length
-        let mut length_bytes = [0u8; 4];
-        std::io::Read::read_exact(&mut reader, &mut length_bytes).map_err(|e| e.to_string())?;
-        let response_length = u32::from_be_bytes(length_bytes) as usize;
-
-        // Read response
-        let mut response_bytes = vec![0u8; response_length];
-        std::io::Read::read_exact(&mut reader, &mut response_bytes).map_err(|e| e.to_string())?;
-
-        let response_str = String::from_utf8(response_bytes).map_err(|e| e.to_string())?;
-        let response: Value = serde_json::from_str(&response_str).map_err(|e| e.to_string())?;
+        let mut connection = self
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut attempt = 0;
+        loop {
+            let reused = connection.is_some();
+            let result = match connection.as_mut() {
+                Some(conn) => conn.call(&request_str, &mut on_chunk),
+                None => match Connection::open(&self.socket_path) {
+                    Ok(conn) => connection.insert(conn).call(&request_str, &mut on_chunk),
+                    Err(e) => Err(CallError::NotSent(e)),
+                },
+            };
+
+            match result {
+                Ok(result) => return Ok(result),
+                Err(CallError::Command(e)) => return Err(e),
+                Err(CallError::Sent(e)) => {
+                    *connection = None;
+                    return Err(e);
+                }
+                Err(CallError::NotSent(e)) => {
+                    *connection = None;
+                    if reused {
+                        tracing::debug!("Kept-alive connection closed ({}), reconnecting", e);
+                        continue;
+                    }
+                    if attempt == RECONNECT_ATTEMPTS {
+                        return Err(format!("{} (gave up after {} retries)", e, attempt));
+                    }
 
-        // Check for error
-        if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
-            return Err(error.to_string());
+                    let delay = RECONNECT_BASE_DELAY * 2u32.pow(attempt);
+                    attempt += 1;
+                    eprintln!(
+                        "jtag-mcp: backend restarting, retrying… ({}/{})",
+                        attempt, RECONNECT_ATTEMPTS
+                    );
+                    tracing::debug!("Connection failed ({}), retrying in {:?}", e, delay);
+                    std::thread::sleep(delay);
+                }
+            }
         }
+    }
+}
 
-        // Return result
-        Ok(response.get("result").cloned().unwrap_or(json!(null)))
+/// MCP progress notification for one streamed chunk
+fn progress_notification(progress_token: &Value, progress: u64, chunk: &Value) -> Value {
+    let mut params = json!({
+        "progressToken": progress_token,
+        "progress": progress,
+    });
+    let message = chunk
+        .get("token")
+        .or_else(|| chunk.get("message"))
+        .and_then(|m| m.as_str());
+    if let Some(message) = message {
+        params["message"] = json!(message);
     }
+    json!({
+        "jsonrpc": "2.0",
+        "method": "notifications/progress",
+        "params": params,
+    })
 }
 
 // ============================================================================
 // MCP Server
 // ============================================================================
 
+/// Where to report progress for one tool call
+struct Progress<'a> {
+    /// `_meta.progressToken` from the client's request
+    token: &'a Value,
+    notify: &'a mut dyn FnMut(&Value),
+}
+
 struct McpServer {
     client: JtagClient,
     context: McpContext,
-    #[allow(dead_code)]
-    tools_cache: Option<Vec<Value>>,
+    /// MCP tool name → JTAG command, from the last `mcp/list-tools`
+    tool_commands: HashMap<String, String>,
 }
 
 impl McpServer {
@@ -253,11 +429,41 @@ impl McpServer {
         Self {
             client: JtagClient::new(socket_path),
             context,
-            tools_cache: None,
+            tool_commands: HashMap::new(),
         }
     }
 
-    fn handle_request(&mut self, request: JsonRpcRequest) -> JsonRpcResponse {
+    /// Fetch the tool list and refresh the name map from it
+    fn fetch_tools(&mut self) -> Result<Value, String> {
+        let result = self.client.execute("mcp/list-tools", json!({}))?;
+        if let Some(commands) = result.get("commands").and_then(|c| c.as_object()) {
+            self.tool_commands = commands
+                .iter()
+                .filter_map(|(name, command)| Some((name.clone(), command.as_str()?.to_string())))
+                .collect();
+        }
+        Ok(result.get("tools").cloned().unwrap_or(json!([])))
+    }
+
+    /// JTAG command for an MCP tool name. Unknown names trigger one refresh,
+    /// which covers calls made before `tools/list` and newly added commands.
+    fn tool_command(&mut self, tool_name: &str) -> Result<String, String> {
+        if !self.tool_commands.contains_key(tool_name) {
+            self.fetch_tools()?;
+        }
+        self.tool_commands
+            .get(tool_name)
+            .cloned()
+            .ok_or_else(|| format!("Unknown tool: {}", tool_name))
+    }
+
+    /// Handle one request. `notify` writes out-of-band messages (progress
+    /// notifications) to the client while the request is still running.
+    fn handle_request(
+        &mut self,
+        request: JsonRpcRequest,
+        notify: &mut dyn FnMut(&Value),
+    ) -> JsonRpcResponse {
         match request.method.as_str() {
             "initialize" => self.handle_initialize(request.id, request.params),
             "notifications/initialized" => {
@@ -265,7 +471,7 @@ impl McpServer {
                 JsonRpcResponse::success(request.id, json!({}))
             }
             "tools/list" => self.handle_list_tools(request.id),
-            "tools/call" => self.handle_call_tool(request.id, request.params),
+            "tools/call" => self.handle_call_tool(request.id, request.params, notify),
             _ => JsonRpcResponse::error(
                 request.id,
                 -32601,
@@ -292,21 +498,23 @@ impl McpServer {
 
     fn handle_list_tools(&mut self, id: Option<Value>) -> JsonRpcResponse {
         // Fetch tools from continuum-core
-        match self.client.execute("mcp/list-tools", json!({})) {
-            Ok(result) => {
-                let tools = result.get("tools").cloned().unwrap_or(json!([]));
-                JsonRpcResponse::success(
-                    id,
-                    json!({
-                        "tools": tools
-                    }),
-                )
-            }
+        match self.fetch_tools() {
+            Ok(tools) => JsonRpcResponse::success(
+                id,
+                json!({
+                    "tools": tools
+                }),
+            ),
             Err(e) => JsonRpcResponse::error(id, -32000, format!("Failed to list tools: {}", e)),
         }
     }
 
-    fn handle_call_tool(&self, id: Option<Value>, params: Option<Value>) -> JsonRpcResponse {
+    fn handle_call_tool(
+        &mut self,
+        id: Option<Value>,
+        params: Option<Value>,
+        notify: &mut dyn FnMut(&Value),
+    ) -> JsonRpcResponse {
         let params = match params {
             Some(p) => p,
             None => {
@@ -321,33 +529,29 @@ impl McpServer {
             }
         };
 
-        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
-
-        // Handle MCP meta-tools
-        if tool_name == "mcp_search_tools" {
-            return self.call_jtag_command(id, "mcp/search-tools", arguments);
-        }
-        if tool_name == "mcp_tool_help" {
-            return self.call_jtag_command(id, "mcp/tool-help", arguments);
-        }
-
-        // Convert MCP tool name back to JTAG command
-        // MCP names have _ instead of / and -
-        // Try the direct underscore-to-slash conversion first
-        let command_name = tool_name.replace('_', "/");
-
-        // For shell commands, TypeScript uses code/shell/execute but Rust uses code/shell-execute
-        // Handle this by also trying the hyphen variant for code/shell/* commands
-        let command_name = if command_name.starts_with("code/shell/") {
-            command_name.replacen("code/shell/", "code/shell-", 1)
-        } else {
-            command_name
+        // Look up the exact command (tool names lose '/' vs '_' vs '-')
+        let command_name = match self.tool_command(tool_name) {
+            Ok(command) => command,
+            Err(e) => return JsonRpcResponse::error(id, -32602, e),
         };
 
-        self.call_jtag_command(id, &command_name, arguments)
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        // Progress is only reported when the client asks for it
+        let progress = params
+            .get("_meta")
+            .and_then(|m| m.get("progressToken"))
+            .map(|token| Progress { token, notify });
+
+        self.call_jtag_command(id, &command_name, arguments, progress)
     }
 
-    fn call_jtag_command(&self, id: Option<Value>, command: &str, args: Value) -> JsonRpcResponse {
+    fn call_jtag_command(
+        &self,
+        id: Option<Value>,
+        command: &str,
+        args: Value,
+        mut progress: Option<Progress>,
+    ) -> JsonRpcResponse {
         // Normalize parameter names: camelCase → snake_case
         // TypeScript uses camelCase (filePath) but Rust uses snake_case (file_path)
         let args_map = args.as_object().cloned().unwrap_or_default();
@@ -360,8 +564,27 @@ impl McpServer {
         let args_with_context = self.context.inject(command, args_normalized);
         let args = Value::Object(args_with_context);
 
-        match self.client.execute(command, args) {
-            Ok(result) => {
+        // Streamed tokens aren't repeated in the final chunk — collect them
+        let mut chunks = 0u64;
+        let mut streamed_text = String::new();
+        let result = self.client.execute_streaming(command, args, |chunk| {
+            chunks += 1;
+            if let Some(token) = chunk.get("token").and_then(|t| t.as_str()) {
+                streamed_text.push_str(token);
+            }
+            if let Some(progress) = progress.as_mut() {
+                (progress.notify)(&progress_notification(progress.token, chunks, chunk));
+            }
+        });
+
+        match result {
+            Ok(mut result) => {
+                if !streamed_text.is_empty() {
+                    if let Some(obj) = result.as_object_mut() {
+                        obj.entry("text").or_insert(json!(streamed_text));
+                    }
+                }
+
                 // Format result for MCP
                 let content = vec![json!({
                     "type": "text",
@@ -397,6 +620,13 @@ impl McpServer {
 // Main
 // ============================================================================
 
+/// Write one JSON-RPC message as a line on stdout
+fn write_message(out: &mut impl Write, message: &impl Serialize) {
+    let message_str = serde_json::to_string(message).unwrap();
+    writeln!(out, "{}", message_str).ok();
+    out.flush().ok();
+}
+
 fn main() {
     // Set up tracing to stderr (stdout is for JSON-RPC)
     tracing_subscriber::fmt()
@@ -452,20 +682,18 @@ fn main() {
                 match serde_json::from_str::<JsonRpcRequest>(&line) {
                     Ok(request) => {
                         tracing::debug!("Request: {:?}", request.method);
-                        let response = server.handle_request(request);
+                        let response = server.handle_request(request, &mut |notification| {
+                            write_message(&mut stdout_lock, notification)
+                        });
 
                         // Write response
-                        let response_str = serde_json::to_string(&response).unwrap();
-                        writeln!(stdout_lock, "{}", response_str).ok();
-                        stdout_lock.flush().ok();
+                        write_message(&mut stdout_lock, &response);
                     }
                     Err(e) => {
                         tracing::error!("Failed to parse request: {}", e);
                         let response =
                             JsonRpcResponse::error(None, -32700, format!("Parse error: {}", e));
-                        let response_str = serde_json::to_string(&response).unwrap();
-                        writeln!(stdout_lock, "{}", response_str).ok();
-                        stdout_lock.flush().ok();
+                        write_message(&mut stdout_lock, &response);
                     }
                 }
             }
@@ -4,6 +4,7 @@
 /// - JTAGProtocol: Universal packet format (JSON for control)
 /// - BinaryProtocol: Zero-copy binary format (for data payloads)
 /// - LoggerClient: Structured logging to JTAG log daemon
+/// - WeightBytes: Loaded size of safetensors checkpoints
 /// - Common utilities and shared types
 ///
 /// GPU memory management lives in continuum-core/src/gpu/memory_manager.rs
@@ -12,6 +13,7 @@
 pub mod jtag_protocol;
 pub mod binary_protocol;
 pub mod logger_client;
+pub mod weight_bytes;
 
 // Re-export commonly used types for convenience
 pub use jtag_protocol::{JTAGErrorType, JTAGRequest, JTAGResponse};
@@ -0,0 +1,24 @@
+//! Weight Bytes - memory a safetensors checkpoint takes once loaded
+//!
+//! Shared by continuum-core (VRAM accounting) and inference-grpc (model
+//! status), which each include it with `#[path]`.
+
+use candle_core::safetensors::MmapedSafetensors;
+use candle_core::DType;
+use std::path::PathBuf;
+
+/// Bytes the weights in `paths` occupy once loaded at `dtype`.
+///
+/// Summed from the safetensors headers (element count × dtype size). File
+/// size alone is wrong whenever the load dtype differs from the stored one —
+/// a BF16 checkpoint loaded as F32 on CPU takes twice its file size.
+pub fn safetensors_weight_bytes(paths: &[PathBuf], dtype: DType) -> Result<u64, String> {
+    let tensors = unsafe { MmapedSafetensors::multi(paths) }
+        .map_err(|e| format!("Failed to read safetensors headers: {e}"))?;
+    Ok(tensors
+        .tensors()
+        .iter()
+        .map(|(_, view)| view.shape().iter().product::<usize>() as u64)
+        .sum::<u64>()
+        * dtype.size_in_bytes() as u64)
+}